@@ -0,0 +1,42 @@
+//! Throughput of parsing OTLP metrics lines
+//!
+//! Compare `cargo bench --bench parse` with
+//! `cargo bench --bench parse --features simd-json`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use preval::evaluator::parser::parse_metrics_line;
+use std::hint::black_box;
+
+/// Lines in the batch parsed per iteration
+const LINES: usize = 1_000;
+
+/// Metrics line shaped like the mock evaluator's, for sample `index`
+fn metrics_line(index: usize) -> String {
+    let attributes = format!(
+        r#"[{{"key": "sample.id", "value": {{"stringValue": "sample-{:05}"}}}}, {{"key": "model", "value": {{"stringValue": "gpt-4o-mini"}}}}]"#,
+        index
+    );
+    let time = 1_700_000_000_000_000_000u64 + index as u64;
+    format!(
+        r#"{{"resourceMetrics": [{{"resource": {{"attributes": [{{"key": "service.name", "value": {{"stringValue": "bench"}}}}]}}, "scopeMetrics": [{{"scope": {{"name": "bench"}}, "metrics": [{{"name": "llm.eval.accuracy", "unit": "ratio", "gauge": {{"dataPoints": [{{"timeUnixNano": "{time}", "asDouble": 0.{index:04}, "attributes": {attributes}}}]}}}}, {{"name": "llm.eval.latency", "unit": "ms", "histogram": {{"dataPoints": [{{"timeUnixNano": "{time}", "count": "1", "sum": 150.5, "min": 150.5, "max": 150.5, "bucketCounts": ["0", "0", "1", "0", "0"], "explicitBounds": [50.0, 100.0, 200.0, 500.0], "attributes": {attributes}}}]}}}}, {{"name": "llm.eval.tokens", "unit": "1", "sum": {{"dataPoints": [{{"timeUnixNano": "{time}", "asDouble": {index}, "attributes": {attributes}}}], "aggregationTemporality": 2, "isMonotonic": true}}}}]}}]}}]}}"#
+    )
+}
+
+fn parse(c: &mut Criterion) {
+    let lines: Vec<String> = (0..LINES).map(metrics_line).collect();
+    let bytes: usize = lines.iter().map(String::len).sum();
+
+    let mut group = c.benchmark_group("parse_metrics_line");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("batch", |b| {
+        b.iter(|| {
+            for line in &lines {
+                black_box(parse_metrics_line(black_box(line)).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
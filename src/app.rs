@@ -1,106 +1,735 @@
 use crate::evaluator::{
     handshake::parse_handshake,
+    parse_worker::{self, ParsedMessage},
     parser::parse_metrics_line,
-    process::{EvaluatorMessage, EvaluatorProcess},
+    process::{EvaluatorMessage, EvaluatorProcess, ExitStatus, ShutdownPolicy},
+    protocol::{Capability, ControlMessage, ValidatedHandshake},
+    resources::ResourceMonitor,
+    source::MetricSource,
+    stderr::StderrFilter,
+    supervisor::RestartPolicy,
 };
+use crate::export::dashboard;
+use crate::export::event_log::EventLog;
+use crate::export::github;
+use crate::export::hooks::{CommandHooks, HooksConfig};
+use crate::export::junit::JunitReport;
+use crate::export::otlp_forward::{ForwardEndpoint, OtlpForwarder};
+use crate::export::prometheus;
+use crate::export::report::{Report, ReportFormat, ReportTarget};
+use crate::export::tracking::{TrackedRun, TrackingConfig};
+use crate::export::webhook::{Webhook, WebhookEvent, WebhookNotice, WebhookNotifier};
+use crate::history::{
+    database::{Outcome, RunAttempt, RunDatabase, RunRecord},
+    snapshot::{RunSnapshot, SNAPSHOT_INTERVAL},
+    status::{RunStatus, STATUS_INTERVAL},
+    transcript::{FollowedRun, RunOutcome, TranscriptStream, TranscriptWriter},
+    RunDir,
+};
+use crate::signals::TerminationSignals;
 use crate::state::{
-    types::{EvaluationStatus, EvaluatorCommand, EvaluatorName, UiAction},
-    AppState,
+    aggregation::Exclusions,
+    anomaly::AnomalyDetector,
+    baseline::Baseline,
+    cost::{BudgetAction, PricingTable},
+    expression::DerivedMetric,
+    perf::{ChannelDepth, PerfRecorder, PerfStats},
+    publisher::StatePublisher,
+    rules::ThresholdRule,
+    sample_store::DuplicatePolicy,
+    script::SampleScript,
+    timeline::TimelineEventKind,
+    types::{ConfirmAction, EvaluationStatus, EvaluatorName, Tab, TextCapture, UiAction},
+    RunState,
 };
 use crate::ui::{
+    alert::AlertStyle,
+    clipboard::{self, Clipboard},
     events::EventHandler,
-    renderer::{Renderer, TerminalCleanup, Uninitialized},
+    keymap::Keymap,
+    mouse::SharedHitMap,
+    renderer::{install_panic_hook, EvaluatorPid, Renderer, TerminalCleanup, Uninitialized},
+    screen,
+    theme::Theme,
+    view::{SharedViews, ViewRegistry},
 };
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
+/// How often the evaluator's CPU, memory and open files are sampled
+const RESOURCE_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest time between state snapshots, bounding the redraw rate to about
+/// 30 frames per second however much the evaluator writes
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
 /// Main application
 pub struct App {
-    /// The evaluator command to run
-    evaluator_command: Option<String>,
+    /// Where the protocol messages of the run come from
+    source: Option<Arc<dyn MetricSource>>,
     /// Application state
-    state: AppState,
+    state: RunState,
+    /// Collector to mirror parsed metric lines to
+    forward_endpoint: Option<ForwardEndpoint>,
+    /// How to stop the evaluator when the app exits
+    shutdown_policy: ShutdownPolicy,
+    /// Stderr lines matching this filter are not shown
+    stderr_filter: StderrFilter,
+    /// Upper bound on the whole evaluation's wall-clock time
+    timeout: Option<Duration>,
+    /// Restart a crashed evaluator when set (supervisor mode)
+    restart_policy: Option<RestartPolicy>,
+    /// Directory to record the run's transcript in
+    run_dir: Option<RunDir>,
+    /// Run without a TUI, e.g. as a detached daemon
+    headless: bool,
+    /// Follow a run recorded by a detached daemon instead of spawning
+    follow: Option<RunDir>,
+    /// Spill samples that no longer fit in memory into the run directory
+    spill_samples: bool,
+    /// Database to record the finished run in
+    history: Option<PathBuf>,
+    /// Id of the run in the history database, once it is recorded there
+    history_id: Option<i64>,
+    /// When the current run of the evaluator started
+    run_started_at: DateTime<Utc>,
+    /// Each start of the evaluator during the current run
+    attempts: Vec<RunAttempt>,
+    /// What to do when the run's estimated cost exceeds its budget
+    budget_action: BudgetAction,
+    /// Which keys trigger which action in the TUI
+    keymap: Keymap,
+    /// View drawn for each tab of the TUI
+    views: SharedViews,
+    /// Colors of the TUI
+    theme: Theme,
+    /// Close the final summary after this long instead of waiting for a key
+    auto_exit: Option<Duration>,
+    /// Ask before quitting while the evaluation is running
+    confirm_quit: bool,
+    /// How to get the user's attention when the run ends or trips a rule
+    alert: AlertStyle,
+    /// System clipboard samples and summaries are copied to
+    clipboard: Clipboard,
+    /// Format of reports written on demand
+    report_format: ReportFormat,
+    /// Where reports are written, instead of the run directory
+    report_dir: Option<PathBuf>,
+    /// Reports written when the run ends
+    report_targets: Vec<ReportTarget>,
+    /// Address the run is served on for Prometheus to scrape
+    prometheus: Option<SocketAddr>,
+    /// Address a live web dashboard of the run is served on
+    dashboard: Option<SocketAddr>,
+    /// Token the dashboard's visitors must give
+    dashboard_token: Option<String>,
+    /// URLs posted to when the run starts, ends or first breaks a rule
+    webhooks: Vec<Webhook>,
+    /// Commands run when the run ends or first breaks a rule
+    hooks: HooksConfig,
+    /// Annotate the GitHub Actions step and add to its job summary
+    github_actions: bool,
+    /// Experiment trackers the finished run is logged to
+    tracking: TrackingConfig,
+    /// File the normalized events of the run are written to
+    event_log: Option<PathBuf>,
 }
 
 impl App {
     /// Create a new App instance
-    pub fn new(evaluator_command: Option<String>) -> Self {
+    pub fn new(source: Option<Box<dyn MetricSource>>) -> Self {
+        let views = ViewRegistry::default();
+        let mut state = RunState::default();
+        state.set_extra_views(views.extra_views());
         Self {
-            evaluator_command,
-            state: AppState::new(),
+            source: source.map(Arc::from),
+            state,
+            forward_endpoint: None,
+            shutdown_policy: ShutdownPolicy::default(),
+            stderr_filter: StderrFilter::default(),
+            timeout: None,
+            restart_policy: None,
+            run_dir: None,
+            headless: false,
+            follow: None,
+            spill_samples: false,
+            history: None,
+            history_id: None,
+            run_started_at: Utc::now(),
+            attempts: Vec::new(),
+            budget_action: BudgetAction::default(),
+            keymap: Keymap::default(),
+            views: SharedViews::new(views),
+            theme: Theme::default(),
+            auto_exit: None,
+            confirm_quit: true,
+            alert: AlertStyle::default(),
+            clipboard: Clipboard::default(),
+            report_format: ReportFormat::default(),
+            report_dir: None,
+            report_targets: Vec::new(),
+            prometheus: None,
+            dashboard: None,
+            dashboard_token: None,
+            webhooks: Vec::new(),
+            hooks: HooksConfig::default(),
+            github_actions: false,
+            tracking: TrackingConfig::default(),
+            event_log: None,
         }
     }
 
+    /// Set the colors of the TUI (builder pattern)
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Set which keys trigger which action in the TUI (builder pattern)
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    /// Close the final summary after `delay` instead of waiting for a key (builder pattern)
+    pub fn auto_exit(mut self, delay: Duration) -> Self {
+        self.auto_exit = Some(delay);
+        self
+    }
+
+    /// Set how to get the user's attention when the run ends or trips a
+    /// threshold rule (builder pattern)
+    pub fn alert(mut self, alert: AlertStyle) -> Self {
+        self.alert = alert;
+        self
+    }
+
+    /// Set the format of reports written on demand (builder pattern)
+    pub(crate) fn report_format(mut self, format: ReportFormat) -> Self {
+        self.report_format = format;
+        self
+    }
+
+    /// Write reports to `dir` instead of the run directory (builder pattern)
+    pub(crate) fn report_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.report_dir = dir;
+        self
+    }
+
+    /// Write these reports when the run ends (builder pattern)
+    pub(crate) fn report_targets(mut self, targets: Vec<ReportTarget>) -> Self {
+        self.report_targets = targets;
+        self
+    }
+
+    /// Serve the progress and aggregates of the run on `addr` for
+    /// Prometheus to scrape (builder pattern)
+    pub(crate) fn prometheus(mut self, addr: SocketAddr) -> Self {
+        self.prometheus = Some(addr);
+        self
+    }
+
+    /// Serve a live web dashboard of the run on `addr` (builder pattern)
+    pub(crate) fn dashboard(mut self, addr: SocketAddr) -> Self {
+        self.dashboard = Some(addr);
+        self
+    }
+
+    /// Only let visitors giving `token` see the dashboard (builder pattern)
+    pub(crate) fn dashboard_token(mut self, token: String) -> Self {
+        self.dashboard_token = Some(token);
+        self
+    }
+
+    /// Post to `webhooks` when the run starts, ends or first breaks a
+    /// threshold rule (builder pattern)
+    pub(crate) fn webhooks(mut self, webhooks: Vec<Webhook>) -> Self {
+        self.webhooks = webhooks;
+        self
+    }
+
+    /// Run the commands in `hooks` when the run ends or first breaks a
+    /// threshold rule (builder pattern)
+    pub(crate) fn hooks(mut self, hooks: HooksConfig) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Set whether the run's failed samples are annotated and its report
+    /// added to the job summary once it ends, for GitHub Actions (builder pattern)
+    pub(crate) fn github_actions(mut self, enabled: bool) -> Self {
+        self.github_actions = enabled;
+        self
+    }
+
+    /// Log the finished run to the experiment trackers in `tracking` (builder pattern)
+    pub(crate) fn tracking(mut self, tracking: TrackingConfig) -> Self {
+        self.tracking = tracking;
+        self
+    }
+
+    /// Write the normalized events of the run to `path` as JSON Lines (builder pattern)
+    pub(crate) fn event_log(mut self, path: PathBuf) -> Self {
+        self.event_log = Some(path);
+        self
+    }
+
+    /// Set whether quitting mid-run asks for confirmation first (builder pattern)
+    pub fn confirm_quit(mut self, confirm: bool) -> Self {
+        self.confirm_quit = confirm;
+        self
+    }
+
+    /// Record a transcript of the run in this directory (builder pattern)
+    pub(crate) fn record_run(mut self, run_dir: RunDir) -> Self {
+        self.run_dir = Some(run_dir);
+        self
+    }
+
+    /// Record the finished run in the history database at `path` (builder pattern)
+    pub(crate) fn record_history(mut self, path: PathBuf) -> Self {
+        self.history = Some(path);
+        self
+    }
+
+    /// Run without a TUI (builder pattern)
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.headless = headless;
+        self
+    }
+
+    /// Show a run owned by a detached daemon instead of starting one (builder pattern)
+    ///
+    /// Quitting stops following; the daemon and its evaluator keep running.
+    pub(crate) fn follow(mut self, run_dir: RunDir) -> Self {
+        self.source = Some(Arc::new(FollowedRun(run_dir.clone())));
+        self.follow = Some(run_dir);
+        self
+    }
+
+    /// Restart the evaluator if it crashes before completing (builder pattern)
+    pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = Some(policy);
+        self
+    }
+
+    /// Fail the run if it has not finished within this duration (builder pattern)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set which stderr lines are hidden from the stderr pane (builder pattern)
+    pub fn stderr_filter(mut self, filter: StderrFilter) -> Self {
+        self.stderr_filter = filter;
+        self
+    }
+
+    /// Set how many stderr lines are retained (builder pattern)
+    pub fn stderr_capacity(mut self, capacity: usize) -> Self {
+        self.state.set_stderr_capacity(capacity);
+        self
+    }
+
+    /// Set how many samples are kept in memory (builder pattern)
+    pub fn sample_capacity(mut self, capacity: usize) -> Self {
+        self.state.set_sample_capacity(capacity);
+        self
+    }
+
+    /// Set how repeated reports of a sample are combined (builder pattern)
+    pub fn duplicate_policy(mut self, policy: Option<DuplicatePolicy>) -> Self {
+        self.state.set_duplicate_policy(policy);
+        self
+    }
+
+    /// Group the statistics table by this attribute from the start (builder pattern)
+    pub fn group_by(mut self, key: Option<String>) -> Self {
+        self.state.set_group_by(key);
+        self
+    }
+
+    /// Set the metrics computed from each sample's metrics (builder pattern)
+    pub fn derived_metrics(mut self, metrics: Vec<DerivedMetric>) -> Self {
+        self.state.set_derived_metrics(metrics);
+        self
+    }
+
+    /// Run `script` on every sample (builder pattern)
+    pub fn script(mut self, script: Option<SampleScript>) -> Self {
+        self.state.set_script(script);
+        self
+    }
+
+    /// Set which values are left out of aggregate statistics (builder pattern)
+    pub fn exclusions(mut self, exclusions: Exclusions) -> Self {
+        self.state.set_exclusions(exclusions);
+        self
+    }
+
+    /// Set which attributes' text is kept with each sample (builder pattern)
+    pub fn text_capture(mut self, capture: TextCapture) -> Self {
+        self.state.set_text_capture(capture);
+        self
+    }
+
+    /// Set which metrics' recent values are drawn as sparklines (builder pattern)
+    pub fn trends(mut self, metrics: Vec<String>) -> Self {
+        self.state.set_trends(metrics);
+        self
+    }
+
+    /// Set how anomalous metric values are flagged; None disables it (builder pattern)
+    pub fn anomaly_detector(mut self, detector: Option<AnomalyDetector>) -> Self {
+        self.state.set_anomaly_detector(detector);
+        self
+    }
+
+    /// Set the composite score used as the run's headline number (builder pattern)
+    pub fn score(mut self, score: Option<DerivedMetric>) -> Self {
+        self.state.set_score(score);
+        self
+    }
+
+    /// Set the threshold rules every run must meet (builder pattern)
+    pub fn threshold_rules(mut self, rules: Vec<ThresholdRule>) -> Self {
+        self.state.set_threshold_rules(rules);
+        self
+    }
+
+    /// Set the rules that only flag values without failing (builder pattern)
+    pub fn warning_rules(mut self, rules: Vec<ThresholdRule>) -> Self {
+        self.state.set_warning_rules(rules);
+        self
+    }
+
+    /// Set how long the evaluator has to send its handshake (builder pattern)
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.state.set_handshake_timeout(timeout);
+        self
+    }
+
+    /// Set the past run to compare this one against (builder pattern)
+    pub fn baseline(mut self, baseline: Baseline) -> Self {
+        self.state.set_baseline(baseline);
+        self
+    }
+
+    /// Set the token prices used to estimate the run's cost (builder pattern)
+    pub fn pricing(mut self, pricing: PricingTable) -> Self {
+        self.state.set_pricing(pricing);
+        self
+    }
+
+    /// Limit the run's estimated cost in USD (builder pattern)
+    pub fn budget(mut self, budget_usd: f64, action: BudgetAction) -> Self {
+        self.state.set_budget(Some(budget_usd));
+        self.budget_action = action;
+        self
+    }
+
+    /// Keep samples beyond the in-memory capacity on disk (builder pattern)
+    ///
+    /// Only takes effect when the run is recorded.
+    pub fn spill_samples(mut self, spill: bool) -> Self {
+        self.spill_samples = spill;
+        self
+    }
+
+    /// Set how the evaluator is stopped on quit (builder pattern)
+    pub fn shutdown_policy(mut self, policy: ShutdownPolicy) -> Self {
+        self.shutdown_policy = policy;
+        self
+    }
+
+    /// Forward every parsed metric line to an OTLP collector (builder pattern)
+    pub fn forward_to(mut self, endpoint: ForwardEndpoint) -> Self {
+        self.forward_endpoint = Some(endpoint);
+        self
+    }
+
+    /// State of the run, as it stands once `run` returns
+    pub(crate) fn state(&self) -> &RunState {
+        &self.state
+    }
+
     /// Run the application
-    pub async fn run(&mut self) -> Result<()> {
-        if let Some(cmd) = &self.evaluator_command {
+    ///
+    /// The exit code reports failure when the run failed, including when
+    /// it broke a threshold rule.
+    pub async fn run(&mut self) -> Result<ExitCode> {
+        if let Some(source) = self.source.clone() {
+            let cmd = source.describe();
+            // Catch signals first so the evaluator never outlives PrEval
+            let mut termination_signals = TerminationSignals::install()?;
+
             // Set up TUI
             let (action_tx, mut action_rx) = mpsc::channel(100);
 
-            // Initialize terminal
-            let renderer = Renderer::<Uninitialized>::new();
-            let (renderer, mut terminal) = renderer
-                .initialize()
-                .context("Failed to initialize terminal")?;
+            let evaluator_pid = EvaluatorPid::default();
 
-            // Create cleanup guard
-            let _cleanup = TerminalCleanup;
+            // Views draw snapshots of the state at their own pace
+            let mut publisher = StatePublisher::new(&self.state, FRAME_INTERVAL);
 
-            // Start event handler in background
-            let mut event_handler = EventHandler::new(action_tx);
-            tokio::spawn(async move {
-                if let Err(e) = event_handler.run().await {
-                    tracing::error!("Event handler error: {}", e);
+            // PrEval's own frame and parse times, for the debug view
+            let perf = PerfRecorder::default();
+            if let Some(addr) = self.prometheus {
+                prometheus::serve(addr, publisher.subscribe()).await?;
+            }
+            if let Some(addr) = self.dashboard {
+                let run_id = self
+                    .run_dir
+                    .as_ref()
+                    .map(|run_dir| run_dir.id().to_string())
+                    .unwrap_or_default();
+                if !addr.ip().is_loopback() && self.dashboard_token.is_none() {
+                    let warning = format!(
+                        "The dashboard on {} can be watched by anyone who can reach it; \
+                         set --serve-token to keep them out",
+                        addr
+                    );
+                    tracing::warn!("{}", warning);
+                    self.state.warn(warning);
                 }
-            });
+                let token = self.dashboard_token.clone();
+                let addr = dashboard::serve(addr, run_id, token, publisher.subscribe()).await?;
+                tracing::info!("Serving the dashboard on http://{}", addr);
+            }
 
-            // Set evaluator name from command
-            if let Ok(name) = EvaluatorName::try_new(cmd.clone()) {
-                self.state.set_evaluator_name(name)?;
+            // Initialize terminal and start the event handler, unless headless;
+            // a headless run keeps the action sender so the channel stays open
+            let mut _headless_action_tx = None;
+            let mut ui = None;
+            if self.headless {
+                _headless_action_tx = Some(action_tx);
+            } else {
+                let hit_map = SharedHitMap::default();
+                let renderer = Renderer::<Uninitialized>::new()
+                    .keymap(self.keymap.clone())
+                    .theme(self.theme)
+                    .hit_map(hit_map.clone())
+                    .views(self.views.clone())
+                    .perf(perf.clone());
+                let (renderer, terminal) = renderer
+                    .initialize()
+                    .context("Failed to initialize terminal")?;
+                // Make sure a panic restores the terminal and takes the evaluator down
+                install_panic_hook(evaluator_pid.clone());
+
+                // Cleanup guard lives as long as the terminal
+                let render_task = renderer.spawn(terminal, publisher.subscribe());
+                ui = Some((render_task, TerminalCleanup));
+
+                let mut event_handler = EventHandler::new(action_tx, self.keymap.clone())
+                    .hit_map(hit_map)
+                    .views(self.views.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = event_handler.run().await {
+                        tracing::error!("Event handler error: {}", e);
+                    }
+                });
             }
 
-            // Update status to waiting for handshake
-            self.state
-                .update_status(EvaluationStatus::WaitingForHandshake)?;
+            // A followed run is announced by the daemon that owns it
+            let notifier = match self.follow {
+                None if !self.webhooks.is_empty() => {
+                    Some(WebhookNotifier::spawn(self.webhooks.clone())?)
+                }
+                _ => None,
+            };
+            let mut hooks = match self.follow {
+                None if !self.hooks.is_empty() => Some(CommandHooks::new(self.hooks.clone())),
+                _ => None,
+            };
+
+            let mut event_log = self
+                .event_log
+                .as_deref()
+                .map(EventLog::create)
+                .transpose()?;
+
+            self.begin_run(cmd.clone())?;
+            self.announce(
+                &notifier,
+                &mut hooks,
+                WebhookEvent::Started,
+                format!("Started {}", cmd),
+            );
 
             // Spawn evaluator process
-            let (eval_tx, mut eval_rx) = mpsc::channel(100);
-            let eval_cmd =
-                EvaluatorCommand::try_new(cmd.clone()).context("Invalid evaluator command")?;
+            let (eval_tx, eval_rx) = mpsc::channel(100);
+            let mut eval_rx = parse_worker::spawn(eval_rx, perf.clone());
+
+            // Restarts need a sender of their own; otherwise the channel closes
+            // with the evaluator, which is how a vanished evaluator is noticed
+            let mut restart_tx = self.restart_policy.map(|_| eval_tx.clone());
+            let mut restart_at = None;
+
+            // Start the transcript before the evaluator can produce anything
+            let transcript = match &self.run_dir {
+                Some(run_dir) => {
+                    let path = run_dir.transcript_path();
+                    tracing::info!("Recording run {} to {}", run_dir.id(), path.display());
+                    Some(TranscriptWriter::create(&path).await?)
+                }
+                None => None,
+            };
+            record(
+                &transcript,
+                TranscriptStream::Control,
+                format!("starting evaluator: {}", cmd),
+            );
+            self.start_sample_spill()?;
 
-            let mut evaluator = EvaluatorProcess::spawn(&eval_cmd, eval_tx)
+            let mut evaluator = source
+                .start(eval_tx, transcript.as_ref().map(TranscriptWriter::tap))
                 .await
                 .context("Failed to spawn evaluator")?;
+            self.start_attempt();
+            if let Some(pid) = evaluator.pid() {
+                evaluator_pid.store(pid, std::sync::atomic::Ordering::SeqCst);
+            }
+
+            // Start metric forwarding before any output can arrive
+            let forwarder = match self.forward_endpoint.clone() {
+                Some(endpoint) => {
+                    tracing::info!("Forwarding metrics to {}", endpoint);
+                    Some(OtlpForwarder::spawn(endpoint)?)
+                }
+                None => None,
+            };
 
             let mut handshake_received = false;
-            let handshake_timeout = Duration::from_secs(5);
-            let handshake_start = std::time::Instant::now();
+            let mut budget_exceeded = false;
+            let mut rules_alerted = false;
+            let mut last_exit_code = None;
+            let handshake_timeout = self.state.handshake_timeout();
+            let mut handshake_start = std::time::Instant::now();
+            let mut deadline = self
+                .timeout
+                .map(|timeout| tokio::time::Instant::now() + timeout);
+
+            let mut finished = false;
+            let mut resource_monitor = ResourceMonitor::new();
+            let mut resource_interval = tokio::time::interval(RESOURCE_SAMPLE_INTERVAL);
+            let mut snapshot_interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+            let mut status_interval = tokio::time::interval(STATUS_INTERVAL);
 
             // Main event loop
             loop {
-                // Render UI
-                renderer.render(&mut terminal, &self.state)?;
+                // Set by branches that leave the state as it was, so a quiet
+                // run is not redrawn for nothing
+                let mut idle = false;
 
                 // Use select! to handle multiple channels
                 tokio::select! {
                     // Handle UI actions
                     action = action_rx.recv() => {
                         match action {
-                            Some(UiAction::Quit) => {
+                            // Quitting kills the evaluator, which a followed run does not
+                            Some(UiAction::Quit) if self.confirm_quit && self.follow.is_none() => {
+                                self.state.request_confirmation(ConfirmAction::Quit);
+                            }
+                            Some(UiAction::Quit | UiAction::ForceQuit) => {
                                 tracing::info!("User requested quit");
+                                record(&transcript, TranscriptStream::Control, "user requested quit");
                                 break;
                             }
                             Some(UiAction::TogglePause) => {
-                                self.state.toggle_pause();
+                                // Only a running evaluator has samples to hold back
+                                if matches!(self.state.status(), EvaluationStatus::CollectingMetrics { .. }) {
+                                    let pausing = !self.state.is_paused();
+                                    match set_evaluator_paused(&mut evaluator, self.state.handshake(), pausing).await {
+                                        Ok(()) => {
+                                            self.state.toggle_pause();
+                                            let event = if pausing { "user paused the evaluator" } else { "user resumed the evaluator" };
+                                            record(&transcript, TranscriptStream::Control, event);
+                                        }
+                                        Err(e) => tracing::warn!("{:#}", e),
+                                    }
+                                }
+                            }
+                            Some(UiAction::KillEvaluator) => {
+                                if !self.state.is_terminal() {
+                                    self.state.request_confirmation(ConfirmAction::KillEvaluator);
+                                }
+                            }
+                            Some(UiAction::Confirm) => match self.state.take_confirmation() {
+                                Some(ConfirmAction::KillEvaluator) => {
+                                    tracing::info!("User killed the evaluator");
+                                    record(&transcript, TranscriptStream::Control, "user killed the evaluator");
+                                    if let Err(e) = evaluator.kill().await {
+                                        tracing::warn!("{:#}", e);
+                                    }
+                                    self.state.finish(EvaluationStatus::Failed(
+                                        "Evaluator killed by user".to_string()
+                                    ))?;
+                                }
+                                Some(ConfirmAction::Quit) => {
+                                    tracing::info!("User requested quit");
+                                    record(&transcript, TranscriptStream::Control, "user requested quit");
+                                    break;
+                                }
+                                None => {}
+                            },
+                            Some(UiAction::Cancel) => {
+                                // Closes the question or help if open, else the error banner
+                                if self.state.take_confirmation().is_none() && !self.state.is_help_visible() {
+                                    self.state.dismiss_error();
+                                }
+                                self.state.hide_help();
                             }
-                            Some(UiAction::Resize(size)) => {
-                                tracing::debug!("Terminal resized to {}x{}", size.width(), size.height());
-                                // Terminal will be redrawn on next iteration
+                            Some(UiAction::RestartRun) => {
+                                tracing::info!("User restarted the run");
+                                record(&transcript, TranscriptStream::Control, "user restarted the run");
+                                if let Err(e) = stop_evaluator(&mut evaluator, self.state.handshake(), self.shutdown_policy).await {
+                                    tracing::warn!("{:#}", e);
+                                }
+
+                                // A new channel, so nothing from the old evaluator leaks into the new run
+                                let (tx, rx) = mpsc::channel(100);
+                                eval_rx = parse_worker::spawn(rx, perf.clone());
+                                restart_tx = self.restart_policy.map(|_| tx.clone());
+                                restart_at = None;
+
+                                self.state = self.state.fresh_run().into();
+                                if let Some(log) = &mut event_log {
+                                    if let Err(e) = log.restart() {
+                                        self.drop_event_log(&mut event_log, e);
+                                    }
+                                }
+                                self.start_sample_spill()?;
+                                self.begin_run(cmd.clone())?;
+                                self.announce(&notifier, &mut hooks, WebhookEvent::Started, format!("Restarted {}", cmd));
+                                evaluator = source.start(tx, transcript.as_ref().map(TranscriptWriter::tap))
+                                    .await
+                                    .context("Failed to restart evaluator")?;
+                                self.start_attempt();
+                                if let Some(pid) = evaluator.pid() {
+                                    evaluator_pid.store(pid, std::sync::atomic::Ordering::SeqCst);
+                                }
+                                handshake_received = false;
+                                budget_exceeded = false;
+                                rules_alerted = false;
+                                last_exit_code = None;
+                                handshake_start = std::time::Instant::now();
+                                deadline = self
+                                    .timeout
+                                    .map(|timeout| tokio::time::Instant::now() + timeout);
                             }
-                            Some(UiAction::Refresh) => {
-                                // Just redraw on next iteration
+                            Some(action @ UiAction::Resize(_)) => {
+                                // Relayout at the new size now rather than a frame later
+                                self.browse_results(action);
+                                publisher.flush(&self.state);
+                                idle = true;
                             }
+                            Some(action) => self.browse_results(action),
                             None => {
                                 // Channel closed, exit
                                 break;
@@ -110,34 +739,42 @@ impl App {
 
                     // Handle evaluator messages
                     msg = eval_rx.recv() => {
+                        let (msg, parsed) = match msg {
+                            Some(ParsedMessage { message, metrics }) => (Some(message), metrics),
+                            None => (None, None),
+                        };
                         match msg {
                             Some(EvaluatorMessage::Output(line)) => {
+                                let mut parse_error = None;
                                 if !handshake_received {
                                     // Try to parse as handshake
                                     match parse_handshake(&line) {
+                                        Ok(validated_handshake) if self.state.handshake().is_some() => {
+                                            // A restarted evaluator: keep the progress made so far
+                                            tracing::info!("Restarted evaluator {} sent handshake, resuming", validated_handshake.evaluator.name);
+                                            if !reads_control_messages(&validated_handshake) {
+                                                evaluator.close_control_input();
+                                            }
+                                            handshake_received = true;
+                                        }
                                         Ok(validated_handshake) => {
                                             tracing::info!("Received handshake from evaluator: {}", validated_handshake.evaluator.name);
+                                            if !reads_control_messages(&validated_handshake) {
+                                                evaluator.close_control_input();
+                                            }
 
-                                            // Store handshake in state
+                                            // Store handshake in state and start collecting metrics
                                             self.state.set_handshake(validated_handshake)?;
                                             handshake_received = true;
-
-                                            // Move to collecting metrics status
-                                            let total = self.state.handshake()
-                                                .and_then(|h| h.execution_plan.as_ref())
-                                                .map(|plan| plan.total_samples.into_inner() as usize);
-
-                                            self.state.update_status(EvaluationStatus::CollectingMetrics {
-                                                received: 0,
-                                                total,
-                                            })?;
                                         }
                                         Err(e) => {
+                                            parse_error = Some(format!("Not a handshake: {}", e));
                                             // Not a handshake - check if we're past timeout
                                             if handshake_start.elapsed() > handshake_timeout {
-                                                self.state.update_status(EvaluationStatus::Failed(
-                                                    "Handshake timeout: no valid handshake received within 5 seconds".to_string()
-                                                ))?;
+                                                self.state.finish(EvaluationStatus::Failed(format!(
+                                                    "Handshake timeout: no valid handshake received within {:?}",
+                                                    handshake_timeout
+                                                )))?;
                                             } else {
                                                 tracing::debug!("Received non-handshake line while waiting: {}", e);
                                                 // Continue waiting for handshake
@@ -146,25 +783,85 @@ impl App {
                                     }
                                 } else {
                                     // Try to parse as OTLP metrics
-                                    match parse_metrics_line(&line) {
+                                    match parsed.unwrap_or_else(|| parse_metrics_line(&line)) {
                                         Ok(metrics) => {
+                                            if let Some(forwarder) = &forwarder {
+                                                forwarder.forward(&line);
+                                            }
                                             self.state.add_metrics(metrics)?;
+                                            if !budget_exceeded {
+                                                budget_exceeded = self.enforce_budget(&transcript)?;
+                                            }
+                                            if !rules_alerted && !self.state.tripped_rules().is_empty() {
+                                                rules_alerted = true;
+                                                self.raise_alert("PrEval: a sample broke a threshold rule");
+                                                let rules: Vec<&str> = self.state.tripped_rules().keys().map(String::as_str).collect();
+                                                let message = format!("A sample broke {}", rules.join(", "));
+                                                self.announce(&notifier, &mut hooks, WebhookEvent::Threshold, message);
+                                            }
                                         }
                                         Err(e) => {
+                                            parse_error = Some(e.to_string());
                                             tracing::warn!("Failed to parse metrics: {}", e);
+                                            self.state.warn(format!("Failed to parse metrics: {}", e));
+                                            self.state.record_event(
+                                                TimelineEventKind::ParseError,
+                                                format!("Failed to parse metrics: {}", e),
+                                            );
                                         }
                                     }
                                 }
+                                self.state.push_raw_line(line, parse_error);
+                            }
+                            Some(EvaluatorMessage::Stderr(line)) => {
+                                if !self.stderr_filter.is_filtered(&line) {
+                                    self.state.push_stderr_line(line);
+                                }
                             }
                             Some(EvaluatorMessage::Exited(status)) => {
-                                if !handshake_received {
-                                    self.state.update_status(EvaluationStatus::Failed(
+                                last_exit_code = status.code();
+                                self.finish_attempt(&status);
+                                self.state.record_event(
+                                    TimelineEventKind::ProcessExit,
+                                    format!("Evaluator exited with code {:?}", status.code()),
+                                );
+                                if self.state.is_terminal() {
+                                    // Outcome already decided, e.g. the user killed it
+                                    continue;
+                                }
+                                record(&transcript, TranscriptStream::Control, format!("evaluator exited with code {:?}", status.code()));
+                                // The outcome depends on every sample, including those held back by a pause
+                                self.state.apply_pending_metrics();
+                                let restarts = self.state.restarts().len() as u32;
+                                let restart_policy = self
+                                    .restart_policy
+                                    .filter(|policy| policy.allows_restart(restarts));
+
+                                if let (false, Some(policy)) = (status.success(), restart_policy) {
+                                    // Crashed before completing: try again after a backoff
+                                    let backoff = policy.backoff_for(restarts + 1);
+                                    tracing::warn!(
+                                        "Evaluator exited with code {:?}, restarting in {:?} ({}/{})",
+                                        status.code(), backoff, restarts + 1, policy.max_restarts
+                                    );
+                                    self.state.record_restart(status.code());
+                                    restart_at = Some(tokio::time::Instant::now() + backoff);
+                                } else if !handshake_received {
+                                    self.state.finish(EvaluationStatus::Failed(
                                         "Evaluator exited before sending handshake".to_string()
                                     ))?;
                                 } else if status.success() {
-                                    self.state.update_status(EvaluationStatus::Completed)?;
+                                    let violations = self.state.check_aggregate_rules();
+                                    if violations.is_empty() {
+                                        self.state.finish(EvaluationStatus::Completed)?;
+                                    } else {
+                                        self.state.finish(EvaluationStatus::Failed(format!(
+                                            "Threshold rules violated: {}",
+                                            violations.join("; ")
+                                        )))?;
+                                    }
                                 } else {
-                                    self.state.update_status(EvaluationStatus::Failed(
+                                    self.state.finish(EvaluationStatus::Failed(
                                         format!("Evaluator exited with code {:?}", status.code())
                                     ))?;
                                 }
@@ -177,7 +874,7 @@ impl App {
                                     } else {
                                         "Evaluator terminated unexpectedly"
                                     };
-                                    self.state.update_status(EvaluationStatus::Failed(
+                                    self.state.finish(EvaluationStatus::Failed(
                                         error_msg.to_string()
                                     ))?;
                                 }
@@ -185,31 +882,771 @@ impl App {
                         }
                     }
 
+                    // Shut down cleanly when PrEval itself is asked to terminate
+                    signal = termination_signals.recv() => {
+                        tracing::info!("Received {}, shutting down", signal);
+                        record(&transcript, TranscriptStream::Control, format!("received {}", signal));
+                        if !self.state.is_terminal() {
+                            self.state.finish(EvaluationStatus::Failed(
+                                format!("Interrupted by {}", signal)
+                            ))?;
+                        }
+                        break;
+                    }
+
+                    // Respawn a crashed evaluator once its backoff has elapsed
+                    _ = sleep_until_deadline(restart_at), if restart_at.is_some() && !self.state.is_terminal() => {
+                        restart_at = None;
+                        if let Some(tx) = &restart_tx {
+                            evaluator = source.start(tx.clone(), transcript.as_ref().map(TranscriptWriter::tap))
+                                .await
+                                .context("Failed to restart evaluator")?;
+                            self.start_attempt();
+                            if let Some(pid) = evaluator.pid() {
+                                evaluator_pid.store(pid, std::sync::atomic::Ordering::SeqCst);
+                            }
+                            handshake_received = false;
+                            handshake_start = std::time::Instant::now();
+                            if self.state.is_paused() {
+                                // The new evaluator starts out running
+                                self.state.toggle_pause();
+                            }
+                            record(&transcript, TranscriptStream::Control, format!(
+                                "restarted evaluator (restart {})", self.state.restarts().len()
+                            ));
+                        }
+                    }
+
+                    // Save what has completed in case PrEval or the machine dies
+                    _ = status_interval.tick(), if self.run_dir.is_some() && self.follow.is_none() => {
+                        self.save_status();
+                        idle = true;
+                    }
+                    _ = snapshot_interval.tick(), if self.run_dir.is_some() && !self.state.is_terminal() => {
+                        if let Some(run_dir) = &self.run_dir {
+                            if let Err(e) = self.save_snapshot(run_dir) {
+                                tracing::warn!("{:#}", e);
+                                self.state.warn(format!("{:#}", e));
+                            }
+                        }
+                        idle = true;
+                    }
+
+                    // Publish a change held back by the frame interval
+                    _ = sleep_until_deadline(publisher.next_publish()), if publisher.next_publish().is_some() => {}
+
+                    // Sample the resource usage of the evaluator and of PrEval
+                    // itself, which also redraws clocks such as the elapsed
+                    // time once a second
+                    _ = resource_interval.tick(), if !self.state.is_terminal() => {
+                        let pid = evaluator_pid.load(std::sync::atomic::Ordering::SeqCst);
+                        if let Some(usage) = resource_monitor.sample(pid) {
+                            self.state.record_resource_usage(usage);
+                        }
+                        if let Some(usage) = resource_monitor.sample(std::process::id()) {
+                            self.state.record_preval_memory(usage.memory_bytes);
+                            perf.record_memory(usage.memory_bytes);
+                        }
+                        self.state.set_perf_stats(PerfStats {
+                            parsed_queue: ChannelDepth {
+                                depth: eval_rx.len(),
+                                capacity: eval_rx.max_capacity(),
+                            },
+                            action_queue: ChannelDepth {
+                                depth: action_rx.len(),
+                                capacity: action_rx.max_capacity(),
+                            },
+                            ..perf.stats()
+                        });
+                    }
+
+                    // Enforce the overall evaluation timeout
+                    _ = sleep_until_deadline(deadline), if !self.state.is_terminal() => {
+                        let (completed, total, _) = self.state.progress();
+                        let completed = match total {
+                            Some(total) => format!("{}/{}", completed, total),
+                            None => completed.to_string(),
+                        };
+                        let timeout = self.timeout.unwrap_or_default();
+                        tracing::warn!("Evaluation exceeded timeout of {:?}", timeout);
+                        record(&transcript, TranscriptStream::Control, format!("timeout after {:?}", timeout));
+                        self.state.finish(EvaluationStatus::Failed(format!(
+                            "timeout after {:?} ({} samples completed)",
+                            timeout, completed
+                        )))?;
+                    }
+
                     // Check handshake timeout
                     _ = tokio::time::sleep(Duration::from_millis(100)) => {
                         if !handshake_received && handshake_start.elapsed() > handshake_timeout {
-                            self.state.update_status(EvaluationStatus::Failed(
-                                "Handshake timeout: no valid handshake received within 5 seconds".to_string()
-                            ))?;
+                            self.state.finish(EvaluationStatus::Failed(format!(
+                                "Handshake timeout: no valid handshake received within {:?}",
+                                handshake_timeout
+                            )))?;
+                        } else {
+                            // Keep the wait for the handshake visibly ticking
+                            idle = self.state.handshake_wait().is_none();
                         }
                     }
                 }
 
-                // Exit if in terminal state
+                // Publish the changed state for rendering, at most once a frame
+                if !idle {
+                    self.state
+                        .set_output_diagnostics(evaluator.output_diagnostics());
+                    self.write_events(&mut event_log);
+                    publisher.publish(&self.state);
+                }
+
+                // Stop once the run is over; the summary shows how it went
                 if self.state.is_terminal() {
-                    // Wait a moment for user to see final state
-                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                    finished = true;
                     break;
                 }
             }
 
-            // Kill evaluator if still running
-            let _ = evaluator.kill().await;
+            // Give the evaluator a chance to flush results before it is killed
+            if let Err(e) =
+                stop_evaluator(&mut evaluator, self.state.handshake(), self.shutdown_policy).await
+            {
+                tracing::warn!("{:#}", e);
+            }
+            self.state.apply_pending_metrics();
+            self.write_events(&mut event_log);
+
+            // Save the results first, so the summary can point at them
+            if let Some(run_dir) = self.run_dir.clone() {
+                self.state
+                    .add_report("Transcript", run_dir.transcript_path());
+                match run_dir.write_metric_stats(self.state.metric_stats()) {
+                    Ok(path) => self.state.add_report("Metric statistics", path),
+                    Err(e) => self.report_save_error(e),
+                }
+                match run_dir.write_timeline(self.state.timeline()) {
+                    Ok(path) => self.state.add_report("Timeline", path),
+                    Err(e) => self.report_save_error(e),
+                }
+                if let Err(e) = self.save_snapshot(&run_dir) {
+                    self.report_save_error(e);
+                }
+                if self.follow.is_none() {
+                    self.save_status();
+                    self.state.add_report("Status", run_dir.status_path());
+                }
+            }
+            // A followed run is recorded by the daemon that owns it
+            if let (Some(path), None) = (self.history.clone(), &self.follow) {
+                match self.save_to_history(&path) {
+                    Ok(()) => self.state.add_report("Run history", path),
+                    Err(e) => self.report_save_error(e),
+                }
+            }
+            if self.tracking.is_enabled() && self.follow.is_none() {
+                let run = TrackedRun::new(self.run_record(), &self.state);
+                match run.log(&self.tracking).await {
+                    Ok(logged) => {
+                        for destination in logged {
+                            self.state.notify(format!("Logged to {}", destination));
+                        }
+                    }
+                    Err(e) => self.report_save_error(e),
+                }
+            }
+            for target in self.report_targets.clone() {
+                if let Err(e) = self.write_report_target(&target) {
+                    self.report_save_error(e);
+                }
+            }
+
+            if finished {
+                let message = match self.state.status() {
+                    EvaluationStatus::Failed(reason) => format!("PrEval: run failed: {}", reason),
+                    _ => "PrEval: run completed".to_string(),
+                };
+                self.raise_alert(&message);
+                let (event, message) = match self.state.status() {
+                    EvaluationStatus::Failed(reason) => (WebhookEvent::Failed, reason.clone()),
+                    _ => (WebhookEvent::Completed, "Run completed".to_string()),
+                };
+                self.announce(&notifier, &mut hooks, event, message);
+            }
+
+            // A finished run stays on screen until the user is done with it
+            if finished && ui.is_some() {
+                self.state.show_summary();
+                let auto_exit = self
+                    .auto_exit
+                    .map(|delay| tokio::time::Instant::now() + delay);
+                loop {
+                    publisher.publish(&self.state);
+                    tokio::select! {
+                        action = action_rx.recv() => match action {
+                            Some(UiAction::Quit | UiAction::ForceQuit) | None => break,
+                            Some(UiAction::Cancel) => {
+                                if !self.state.is_help_visible() && !self.state.is_summary_visible() {
+                                    self.state.dismiss_error();
+                                }
+                                self.state.hide_help();
+                                self.state.hide_summary();
+                            }
+                            Some(action @ UiAction::Resize(_)) => {
+                                self.browse_results(action);
+                                publisher.flush(&self.state);
+                            }
+                            Some(action) => self.browse_results(action),
+                        },
+                        _ = termination_signals.recv() => break,
+                        _ = sleep_until_deadline(auto_exit), if auto_exit.is_some() => break,
+                        _ = sleep_until_deadline(publisher.next_publish()), if publisher.next_publish().is_some() => {}
+                    }
+                }
+            }
+
+            // The renderer draws the final state, then stops with the publisher
+            publisher.flush(&self.state);
+            drop(publisher);
+            if let Some((render_task, _cleanup)) = ui {
+                render_task.await.context("Renderer task panicked")??;
+            }
+            if let Some(transcript) = transcript {
+                let outcome = RunOutcome {
+                    success: matches!(self.state.status(), EvaluationStatus::Completed),
+                    exit_code: last_exit_code,
+                };
+                transcript
+                    .record_outcome(format!("run finished: {:?}", self.state.status()), outcome);
+                transcript.finish().await;
+            }
+            if let Some(notifier) = notifier {
+                notifier.finish().await;
+            }
+            if let Some(hooks) = hooks {
+                hooks.finish().await;
+            }
+            // Workflow commands go to stdout, so only once the TUI is gone
+            if self.github_actions {
+                if let Err(e) = github::publish(&self.state) {
+                    tracing::warn!("{:#}", e);
+                }
+            }
         } else {
             // No evaluator specified, just return
-            return Ok(());
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        if matches!(self.state.status(), EvaluationStatus::Failed(_)) {
+            Ok(ExitCode::FAILURE)
+        } else {
+            Ok(ExitCode::SUCCESS)
         }
+    }
 
+    /// Apply an action that only changes what is shown, which works the
+    /// same while the run is going and after it is over
+    fn browse_results(&mut self, action: UiAction) {
+        match action {
+            UiAction::Copy => {
+                self.copy_to_clipboard();
+            }
+            UiAction::Resize(size) => {
+                tracing::debug!("Terminal resized to {}x{}", size.width(), size.height());
+                // The renderer picks a layout for the new size when it redraws
+            }
+            UiAction::Refresh => {
+                // Just publish another snapshot on next iteration
+            }
+            UiAction::ToggleStderr => {
+                self.state.toggle_stderr();
+            }
+            UiAction::ToggleDiagnostics => {
+                self.state.toggle_diagnostics();
+            }
+            UiAction::ToggleDebug => {
+                self.state.toggle_debug();
+            }
+            UiAction::ToggleTimeline => {
+                self.state.toggle_timeline();
+            }
+            UiAction::ToggleComparison => {
+                self.state.toggle_comparison();
+            }
+            UiAction::CycleGroupBy => {
+                self.state.cycle_group_by();
+            }
+            UiAction::ToggleHelp => {
+                self.state.toggle_help();
+            }
+            UiAction::OpenSample(index) => {
+                self.state.select_sample(index);
+                self.state.select_tab(Tab::Samples);
+            }
+            UiAction::StartSampleSearch => {
+                self.state.start_sample_search();
+            }
+            UiAction::SearchSamples { query, done } => {
+                self.state.search_samples(&query, done);
+            }
+            UiAction::ToggleBookmark => {
+                self.state.toggle_bookmark();
+                self.save_annotation();
+            }
+            UiAction::StartSampleNote => {
+                self.state.start_sample_note();
+            }
+            UiAction::EditSampleNote { note, done } => {
+                self.state.edit_sample_note(note, done);
+                if done {
+                    self.save_annotation();
+                }
+            }
+            UiAction::CycleStatusFilter => {
+                self.state.cycle_status_filter();
+            }
+            UiAction::CycleSampleSort => {
+                self.state.cycle_sample_sort();
+            }
+            UiAction::ReverseSampleSort => {
+                self.state.reverse_sample_sort();
+            }
+            UiAction::NextFailedSample => {
+                self.state.select_failed_sample(true);
+            }
+            UiAction::PreviousFailedSample => {
+                self.state.select_failed_sample(false);
+            }
+            UiAction::SelectTab(tab) => {
+                self.state.select_tab(tab);
+            }
+            UiAction::NextTab => {
+                self.state.next_tab();
+            }
+            UiAction::Scroll(scroll) => {
+                self.state.scroll(scroll);
+            }
+            UiAction::ToggleSeries => {
+                self.state.toggle_chart_series();
+            }
+            UiAction::ToggleChartAxis => {
+                self.state.toggle_chart_axis();
+            }
+            UiAction::CycleJsonFold => {
+                self.state.cycle_json_fold();
+            }
+            UiAction::ExportReport => {
+                self.export_report();
+            }
+            UiAction::SaveScreen => {
+                self.save_screen();
+            }
+            UiAction::CycleLeaderboardMetric => {
+                self.state.cycle_leaderboard_metric();
+                self.state.select_tab(Tab::Leaderboard);
+            }
+            UiAction::ReverseLeaderboard => {
+                self.state.reverse_leaderboard();
+                self.state.select_tab(Tab::Leaderboard);
+            }
+            UiAction::CycleScatterX => {
+                self.state.cycle_scatter_axis(false);
+                self.state.select_tab(Tab::Scatter);
+            }
+            UiAction::CycleScatterY => {
+                self.state.cycle_scatter_axis(true);
+                self.state.select_tab(Tab::Scatter);
+            }
+            UiAction::OpenOutlier => {
+                self.state.open_scatter_outlier();
+            }
+            // The rest act on a running evaluator
+            _ => {}
+        }
+    }
+
+    /// Warn or fail the run if it went over budget, returning whether it did
+    fn enforce_budget(&mut self, transcript: &Option<TranscriptWriter>) -> Result<bool> {
+        let Some(cost) = self.state.cost().filter(|cost| cost.is_over_budget()) else {
+            return Ok(false);
+        };
+        let message = format!(
+            "Budget of ${:.2} exceeded (${:.2} spent)",
+            cost.budget_usd().unwrap_or_default(),
+            cost.total_usd()
+        );
+        record(transcript, TranscriptStream::Control, message.as_str());
+        match self.budget_action {
+            BudgetAction::Warn => {
+                tracing::warn!("{}", message);
+                self.state.warn(message);
+            }
+            BudgetAction::Abort => self.state.finish(EvaluationStatus::Failed(message))?,
+        }
+        Ok(true)
+    }
+
+    /// Get the user's attention with `message`, if they asked for alerts
+    /// and are watching in a terminal
+    fn raise_alert(&self, message: &str) {
+        if self.headless {
+            return;
+        }
+        if let Err(e) = self.alert.raise(message) {
+            tracing::warn!("Failed to raise alert: {}", e);
+        }
+    }
+
+    /// Copy the run summary while it is shown, else the selected sample, to
+    /// the system clipboard as Markdown
+    fn copy_to_clipboard(&mut self) {
+        let (text, what) = if self.state.is_summary_visible() {
+            (
+                Report::of(&self.state).to_markdown(),
+                "run summary".to_string(),
+            )
+        } else if let Some(sample) = self.state.read_selected_sample() {
+            (
+                clipboard::sample_markdown(&sample),
+                format!("sample {}", sample.sample_id),
+            )
+        } else {
+            self.state.notify("No sample selected to copy".to_string());
+            return;
+        };
+        match self.clipboard.copy(text) {
+            Ok(()) => self
+                .state
+                .notify(format!("Copied the {} to the clipboard", what)),
+            Err(e) => {
+                tracing::warn!("{:#}", e);
+                self.state.warn(format!("{:#}", e));
+            }
+        }
+    }
+
+    /// Write a report of the run so far, next to the run's other files when
+    /// it is recorded, and say where
+    fn export_report(&mut self) {
+        match Report::of(&self.state).write(&self.export_dir(), self.report_format) {
+            Ok(path) => self
+                .state
+                .notify(format!("Report written to {}", path.display())),
+            Err(e) => {
+                tracing::warn!("{:#}", e);
+                self.state.warn(format!("{:#}", e));
+            }
+        }
+    }
+
+    /// Save a copy of the screen as plain text and with colors, where
+    /// reports go, and say where
+    fn save_screen(&mut self) {
+        let saved = crossterm::terminal::size()
+            .context("Failed to get the terminal size")
+            .and_then(|(width, height)| {
+                Renderer::<Uninitialized>::new()
+                    .keymap(self.keymap.clone())
+                    .theme(self.theme)
+                    .views(self.views.clone())
+                    .render_offscreen(&self.state, width, height)
+            })
+            .and_then(|buffer| screen::save(&buffer, &self.export_dir()));
+        match saved {
+            Ok(path) => self
+                .state
+                .notify(format!("Screen saved to {}", path.display())),
+            Err(e) => {
+                tracing::warn!("{:#}", e);
+                self.state.warn(format!("{:#}", e));
+            }
+        }
+    }
+
+    /// Directory reports and screen copies are written to: the configured
+    /// one, else the run's directory when it is recorded, else the current
+    /// directory
+    fn export_dir(&self) -> PathBuf {
+        match (&self.report_dir, &self.run_dir, &self.follow) {
+            (Some(dir), _, _) => dir.clone(),
+            (None, Some(run_dir), _) | (None, None, Some(run_dir)) => run_dir.path().to_path_buf(),
+            (None, None, None) => PathBuf::from("."),
+        }
+    }
+
+    /// Log a result that could not be saved and show it until dismissed
+    fn report_save_error(&mut self, error: anyhow::Error) {
+        tracing::warn!("{:#}", error);
+        self.state.report_error(format!("{:#}", error));
+    }
+
+    /// Reset status for a new run of the evaluator
+    fn begin_run(&mut self, cmd: String) -> Result<()> {
+        self.run_started_at = Utc::now();
+        self.history_id = None;
+        self.attempts.clear();
+
+        // Name the evaluator after its command, cut to fit a name
+        let name = EvaluatorName::try_new(cmd.chars().take(255).collect::<String>())
+            .context("Invalid evaluator command")?;
+        self.state.set_evaluator_name(name)?;
+        Ok(())
+    }
+
+    /// Note that the evaluator was just started
+    fn start_attempt(&mut self) {
+        self.attempts.push(RunAttempt {
+            attempt: self.attempts.len() as u32 + 1,
+            started_at: Utc::now(),
+            finished_at: None,
+            exit_code: None,
+            signal: None,
+        });
+    }
+
+    /// Note how the latest start of the evaluator ended
+    fn finish_attempt(&mut self, status: &ExitStatus) {
+        if let Some(attempt) = self.attempts.last_mut() {
+            attempt.finished_at = Some(Utc::now());
+            attempt.exit_code = status.code();
+            attempt.signal = status.signal().map(str::to_string);
+        }
+    }
+
+    /// Record the finished run with every sample still available
+    fn save_to_history(&mut self, path: &Path) -> Result<()> {
+        let record = self.run_record();
+        let samples = self
+            .state
+            .sample_store()
+            .read_available()
+            .context("Failed to read samples for the run history")?;
+
+        let id =
+            RunDatabase::open(path)?.record_run(&record, self.state.metric_stats(), &samples)?;
+        tracing::info!("Recorded run {} in {}", id, path.display());
+        self.history_id = Some(id);
+        Ok(())
+    }
+
+    /// Write what happened since the last call to the event log, if any
+    fn write_events(&mut self, event_log: &mut Option<EventLog>) {
+        if let Some(log) = event_log {
+            if let Err(e) = log.catch_up(&self.state) {
+                self.drop_event_log(event_log, e);
+            }
+        }
+    }
+
+    /// Stop writing the event log after `error`, saying why
+    fn drop_event_log(&mut self, event_log: &mut Option<EventLog>, error: anyhow::Error) {
+        *event_log = None;
+        self.report_save_error(error.context("Stopped writing the event log"));
+    }
+
+    /// Tell the webhooks and hooks that want `event` about it, with how far
+    /// the run got
+    fn announce(
+        &self,
+        notifier: &Option<WebhookNotifier>,
+        hooks: &mut Option<CommandHooks>,
+        event: WebhookEvent,
+        message: String,
+    ) {
+        if notifier.is_none() && hooks.is_none() {
+            return;
+        }
+        let (samples_failed, samples_completed, _) = self.state.summary_stats();
+        let notice = WebhookNotice {
+            event,
+            evaluator: self
+                .state
+                .evaluator_name()
+                .map(|name| name.as_ref().to_string()),
+            run_id: self
+                .run_dir
+                .as_ref()
+                .map(|run_dir| run_dir.id().to_string()),
+            message,
+            samples_completed,
+            samples_failed,
+            total_samples: self.state.progress().1,
+            elapsed_secs: self.state.elapsed_time().as_secs_f64(),
+        };
+        if let Some(hooks) = hooks {
+            hooks.run(&notice);
+        }
+        if let Some(notifier) = notifier {
+            notifier.notify(notice);
+        }
+    }
+
+    /// Write a report requested with `--report` and say where
+    fn write_report_target(&mut self, target: &ReportTarget) -> Result<()> {
+        match target {
+            ReportTarget::Junit(path) => {
+                JunitReport::of(&self.state, self.run_started_at)?.write(path)?;
+                self.state.add_report("JUnit report", path.clone());
+            }
+        }
         Ok(())
     }
+
+    /// Keep the bookmark and note of the selected sample in the run's
+    /// records once the run is over; until then they are saved with it
+    fn save_annotation(&mut self) {
+        if !self.state.is_terminal() {
+            return;
+        }
+        if let Some(run_dir) = self.run_dir.clone() {
+            if let Err(e) = self.save_snapshot(&run_dir) {
+                self.report_save_error(e);
+            }
+        }
+        let Some(sample) = self.state.selected_sample_result().cloned() else {
+            return;
+        };
+        if let (Some(path), Some(id)) = (self.history.clone(), self.history_id) {
+            if let Err(e) = RunDatabase::open(&path).and_then(|db| db.update_sample(id, &sample)) {
+                self.report_save_error(e);
+            }
+        }
+    }
+
+    /// Save a snapshot of the run so far, to recover it from if PrEval dies
+    fn save_snapshot(&self, run_dir: &RunDir) -> Result<()> {
+        let store = self.state.sample_store();
+        let samples: Vec<_> = store.recent(store.capacity()).cloned().collect();
+        run_dir.write_snapshot(&RunSnapshot {
+            taken_at: Utc::now(),
+            record: self.run_record(),
+            metric_stats: self.state.metric_stats().clone(),
+            evicted_samples: store.len() - samples.len(),
+            samples,
+        })
+    }
+
+    /// Rewrite the run's live status file; a failure only warns, as the
+    /// next tick tries again
+    fn save_status(&mut self) {
+        let Some(run_dir) = &self.run_dir else {
+            return;
+        };
+        if let Err(e) = run_dir.write_status(&RunStatus::of(&self.state, run_dir.id())) {
+            tracing::warn!("{:#}", e);
+            self.state.warn(format!("{:#}", e));
+        }
+    }
+
+    /// Metadata of the run as it stands now
+    fn run_record(&self) -> RunRecord {
+        let handshake = self.state.handshake();
+        let (samples_failed, samples_completed, _) = self.state.summary_stats();
+        RunRecord {
+            run_dir: self
+                .run_dir
+                .as_ref()
+                .map(|run_dir| run_dir.id().to_string()),
+            evaluator: match (handshake, &self.source) {
+                (Some(handshake), _) => handshake.evaluator.name.to_string(),
+                (None, Some(source)) => source.describe(),
+                (None, None) => String::new(),
+            },
+            description: handshake
+                .and_then(|handshake| handshake.evaluator.description.as_ref())
+                .map(|description| description.to_string()),
+            evaluator_version: handshake.and_then(|handshake| handshake.evaluator.version.clone()),
+            total_samples: handshake
+                .and_then(|handshake| handshake.execution_plan.as_ref())
+                .map(|plan| plan.total_samples.into_inner()),
+            started_at: self.run_started_at,
+            finished_at: Utc::now(),
+            outcome: Outcome::of(self.state.status()),
+            failure: match self.state.status() {
+                EvaluationStatus::Failed(reason) => Some(reason.clone()),
+                _ => None,
+            },
+            samples_completed,
+            samples_failed,
+            score: self.state.mean_score(),
+            restarts: self.state.restarts().len() as u32,
+            samples_dropped: self.state.sample_store().available().start,
+            attempts: self.attempts.clone(),
+        }
+    }
+
+    /// Start spilling samples into the run directory, if configured, or
+    /// into a temporary file so the run history gets every sample
+    fn start_sample_spill(&mut self) -> Result<()> {
+        if let (true, Some(run_dir)) = (self.spill_samples, &self.run_dir) {
+            let path = run_dir.samples_path();
+            self.state
+                .spill_samples_to(&path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+        } else if self.history.is_some() && self.follow.is_none() {
+            self.state
+                .spill_samples_to_temporary()
+                .context("Failed to create a temporary file for samples")?;
+        }
+        Ok(())
+    }
+}
+
+/// Pause or resume the evaluator
+///
+/// Evaluators that announced the pause capability are sent a control
+/// message; any other evaluator's process group is stopped and continued,
+/// or its container frozen and thawed.
+async fn set_evaluator_paused(
+    evaluator: &mut EvaluatorProcess,
+    handshake: Option<&ValidatedHandshake>,
+    paused: bool,
+) -> Result<()> {
+    let supports_pause = evaluator.has_control_input()
+        && handshake.is_some_and(|handshake| handshake.supports(Capability::Pause));
+    match (paused, supports_pause) {
+        (true, true) => evaluator.send_control(ControlMessage::Pause).await,
+        (false, true) => evaluator.send_control(ControlMessage::Resume).await,
+        (true, false) => evaluator.suspend().await,
+        (false, false) => evaluator.resume().await,
+    }
+}
+
+/// Whether the evaluator announced a capability that needs control messages
+fn reads_control_messages(handshake: &ValidatedHandshake) -> bool {
+    handshake.supports(Capability::Pause) || handshake.supports(Capability::Cancel)
+}
+
+/// Stop the evaluator, asking it to exit with a `cancel` control message
+/// first when it announced the cancel capability
+async fn stop_evaluator(
+    evaluator: &mut EvaluatorProcess,
+    handshake: Option<&ValidatedHandshake>,
+    policy: ShutdownPolicy,
+) -> Result<()> {
+    if evaluator.has_control_input()
+        && handshake.is_some_and(|handshake| handshake.supports(Capability::Cancel))
+    {
+        // An evaluator that already exited cannot read it; shutdown copes
+        if let Err(e) = evaluator.send_control(ControlMessage::Cancel).await {
+            tracing::debug!("{:#}", e);
+        }
+    }
+    evaluator.shutdown(policy).await
+}
+
+/// Append a line to the transcript, if one is being recorded
+fn record(
+    transcript: &Option<TranscriptWriter>,
+    stream: TranscriptStream,
+    line: impl Into<String>,
+) {
+    if let Some(transcript) = transcript {
+        transcript.record(stream, line);
+    }
+}
+
+/// Sleep until the deadline, or forever if there is none
+async fn sleep_until_deadline(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
 }
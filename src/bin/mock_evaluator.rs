@@ -1,22 +1,118 @@
+use clap::{Parser, ValueEnum};
+use preval::evaluator::protocol::ControlMessage;
 use serde_json::json;
-use std::io::{self, Write};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+/// Rule in the handshake that fails samples with a low accuracy
+const ACCURACY_RULE: &str = "llm.eval.accuracy >= 0.5";
+
+/// Time the `late-handshake` scenario takes to start
+const LATE_HANDSHAKE_DELAY: Duration = Duration::from_secs(2);
+
+/// Kind of run to produce, as announced in the handshake
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// `--samples` samples, announced in the execution plan
+    TestSuite,
+    /// `--samples` samples from live traffic, each tagged with the
+    /// `session.id` it belongs to, with no execution plan
+    OnlineCollection,
+    /// Samples for as long as it runs, with no execution plan
+    Continuous,
+}
+
+impl Mode {
+    /// Name of the mode in the protocol
+    fn protocol_name(self) -> &'static str {
+        match self {
+            Mode::TestSuite => "test_suite",
+            Mode::OnlineCollection => "online_collection",
+            Mode::Continuous => "continuous",
+        }
+    }
+}
+
+/// Way of misbehaving, to exercise PrEval's error handling
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Scenario {
+    /// Follow the protocol
+    Normal,
+    /// Report metrics without ever sending a handshake
+    NoHandshake,
+    /// Print log lines and take a while before the handshake
+    LateHandshake,
+    /// Cut the middle sample's metrics line short
+    MalformedJson,
+    /// Exit with status 101 at the middle sample
+    CrashMidway,
+    /// Stop reporting at the middle sample without exiting
+    Hang,
+    /// Write progress and warnings to stderr for every sample
+    StderrNoise,
+}
+
+/// Evaluator speaking the PrEval protocol with made-up metrics, for testing
+/// PrEval itself
+#[derive(Parser, Debug)]
+#[command(about, long_about = None)]
+struct Args {
+    /// Kind of run to produce
+    #[arg(long, value_enum, default_value_t = Mode::TestSuite)]
+    mode: Mode,
+
+    /// Number of samples to report; continuous runs ignore it
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    samples: u32,
+
+    /// Time to spend on each sample, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 300)]
+    delay_ms: u64,
+
+    /// Share of samples, between 0 and 1, whose accuracy breaks the
+    /// handshake's threshold rule
+    #[arg(long, value_name = "RATE", default_value_t = 0.0, value_parser = parse_rate)]
+    fail_rate: f64,
+
+    /// Seed for the metrics and which samples fail; the same seed gives the
+    /// same run
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Number of sessions online-collection samples are spread over
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    sessions: u32,
+
+    /// Misbehave like a broken evaluator
+    #[arg(long, value_enum, default_value_t = Scenario::Normal)]
+    scenario: Scenario,
+}
+
+fn parse_rate(text: &str) -> Result<f64, String> {
+    let rate: f64 = text.parse().map_err(|e| format!("{}", e))?;
+    if (0.0..=1.0).contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(format!("{} is not between 0 and 1", rate))
+    }
+}
+
 fn main() {
+    let args = Args::parse();
+    let mut rng = SplitMix64(args.seed);
+
     // Send handshake first
-    let handshake = json!({
+    let mut handshake = json!({
         "type": "handshake",
-        "mode": "test_suite",
+        "mode": args.mode.protocol_name(),
         "version": "1.0",
         "evaluator": {
             "name": "mock-evaluator",
             "description": "Mock evaluator for testing PrEval TUI"
         },
-        "execution_plan": {
-            "total_samples": 10,
-            "runs_per_sample": 1
-        },
         "metrics_schema": [
             {
                 "name": "llm.eval.accuracy",
@@ -36,25 +132,95 @@ fn main() {
                 "unit": "1",
                 "description": "Total tokens processed"
             }
-        ]
+        ],
+        "capabilities": ["pause", "cancel"],
+        "thresholds": [ACCURACY_RULE]
     });
+    if args.mode == Mode::TestSuite {
+        handshake["execution_plan"] = json!({
+            "total_samples": args.samples,
+            "runs_per_sample": 1
+        });
+    }
 
-    // Print handshake and flush immediately
-    println!("{}", handshake);
-    io::stdout().flush().unwrap();
+    if args.scenario == Scenario::LateHandshake {
+        emit("Loading model weights...");
+        thread::sleep(LATE_HANDSHAKE_DELAY);
+    }
+    if args.scenario != Scenario::NoHandshake {
+        emit(handshake);
+    }
 
-    // Wait a moment to simulate processing
-    thread::sleep(Duration::from_millis(500));
+    let control = listen_for_control();
+
+    // Where the scenarios that break halfway through do so
+    let midway = args.samples.div_ceil(2);
 
     // Generate metrics for each sample
     let mut total_tokens = 0.0;
+    let mut total_accuracy = 0.0;
+    let mut reported = 0;
+
+    // A continuous run never gets to the end
+    let last = match args.mode {
+        Mode::Continuous => u32::MAX,
+        _ => args.samples,
+    };
+    for i in 1..=last {
+        // Simulate processing the sample
+        thread::sleep(Duration::from_millis(args.delay_ms));
+        while control.paused() && !control.cancelled() {
+            thread::sleep(Duration::from_millis(20));
+        }
+        // Abandon the sample under way and wrap up with what was reported
+        if control.cancelled() {
+            eprintln!("mock-evaluator: cancelled after {} samples", reported);
+            break;
+        }
+        if i == midway {
+            match args.scenario {
+                Scenario::CrashMidway => {
+                    eprintln!("mock-evaluator: simulated crash at sample {}", i);
+                    std::process::exit(101);
+                }
+                Scenario::Hang => {
+                    eprintln!("mock-evaluator: simulated hang at sample {}", i);
+                    loop {
+                        thread::sleep(Duration::from_secs(3600));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if args.scenario == Scenario::StderrNoise {
+            eprintln!("[{}/{}] evaluating sample-{:03}", i, args.samples, i);
+            eprintln!("WARNING: rate limited by the model API, retrying in 0s");
+        }
 
-    for i in 1..=10 {
         let sample_id = format!("sample-{:03}", i);
-        let accuracy = 0.7 + (i as f64 * 0.02); // 0.72 to 0.90
-        let latency = 100.0 + (i as f64 * 10.0); // 110ms to 200ms
-        let tokens = 500.0 + (i as f64 * 50.0); // 550 to 1000
+        let failed = rng.next_f64() < args.fail_rate;
+        let accuracy = if failed {
+            0.1 + rng.next_f64() * 0.3 // 0.1 to 0.4
+        } else {
+            0.6 + rng.next_f64() * 0.4 // 0.6 to 1.0
+        };
+        let latency = 100.0 + rng.next_f64() * 100.0; // 100ms to 200ms
+        let tokens = (500.0 + rng.next_f64() * 500.0).round(); // 500 to 1000
         total_tokens += tokens;
+        total_accuracy += accuracy;
+        reported += 1;
+
+        let mut attributes = vec![json!({
+            "key": "sample.id",
+            "value": {"stringValue": sample_id}
+        })];
+        if args.mode == Mode::OnlineCollection {
+            let session = (rng.next_f64() * f64::from(args.sessions)) as u32 + 1;
+            attributes.push(json!({
+                "key": "session.id",
+                "value": {"stringValue": format!("session-{}", session)}
+            }));
+        }
 
         // Create OTLP metrics in JSON format
         let metrics = json!({
@@ -77,10 +243,7 @@ fn main() {
                                 "dataPoints": [{
                                     "timeUnixNano": get_timestamp_nanos(),
                                     "asDouble": accuracy,
-                                    "attributes": [{
-                                        "key": "sample.id",
-                                        "value": {"stringValue": sample_id.clone()}
-                                    }]
+                                    "attributes": attributes.clone()
                                 }]
                             }
                         },
@@ -96,10 +259,7 @@ fn main() {
                                     "max": latency,
                                     "bucketCounts": ["0", "0", "1", "0", "0"],
                                     "explicitBounds": [50.0, 100.0, 200.0, 500.0],
-                                    "attributes": [{
-                                        "key": "sample.id",
-                                        "value": {"stringValue": sample_id.clone()}
-                                    }]
+                                    "attributes": attributes.clone()
                                 }]
                             }
                         },
@@ -110,10 +270,7 @@ fn main() {
                                 "dataPoints": [{
                                     "timeUnixNano": get_timestamp_nanos(),
                                     "asDouble": total_tokens,
-                                    "attributes": [{
-                                        "key": "sample.id",
-                                        "value": {"stringValue": sample_id}
-                                    }]
+                                    "attributes": attributes
                                 }],
                                 "aggregationTemporality": 2,
                                 "isMonotonic": true
@@ -124,15 +281,19 @@ fn main() {
             }]
         });
 
-        // Print metrics as JSON Lines and flush
-        println!("{}", json!(metrics));
-        io::stdout().flush().unwrap();
-
-        // Simulate processing time between samples
-        thread::sleep(Duration::from_millis(300 + (i * 50) as u64));
+        // Print metrics as JSON Lines
+        let line = metrics.to_string();
+        if args.scenario == Scenario::MalformedJson && i == midway {
+            emit(&line[..line.len() / 2]);
+        } else {
+            emit(line);
+        }
     }
 
-    // Final summary metrics
+    // Final summary metrics, which tell PrEval the run is complete
+    if reported == 0 {
+        return;
+    }
     let summary = json!({
         "resourceMetrics": [{
             "resource": {
@@ -151,7 +312,7 @@ fn main() {
                     "gauge": {
                         "dataPoints": [{
                             "timeUnixNano": get_timestamp_nanos(),
-                            "asDouble": 0.81, // Average accuracy
+                            "asDouble": total_accuracy / f64::from(reported), // Average accuracy
                             "attributes": [{
                                 "key": "summary",
                                 "value": {"boolValue": true}
@@ -163,10 +324,75 @@ fn main() {
         }]
     });
 
-    println!("{}", json!(summary));
+    emit(summary);
+}
+
+/// State of the run as set by PrEval's control messages
+#[derive(Default)]
+struct Control {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl Control {
+    /// Whether samples are held back until a `resume` message
+    fn paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Whether a `cancel` message asked the run to wrap up
+    fn cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Follow the control messages PrEval writes to stdin
+fn listen_for_control() -> Arc<Control> {
+    let control = Arc::new(Control::default());
+    let state = control.clone();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            match serde_json::from_str(&line) {
+                Ok(ControlMessage::Pause) => {
+                    eprintln!("mock-evaluator: paused");
+                    state.paused.store(true, Ordering::SeqCst);
+                }
+                Ok(ControlMessage::Resume) => {
+                    eprintln!("mock-evaluator: resumed");
+                    state.paused.store(false, Ordering::SeqCst);
+                }
+                Ok(ControlMessage::Cancel) => {
+                    state.cancelled.store(true, Ordering::SeqCst);
+                }
+                Err(e) => eprintln!("mock-evaluator: ignoring control message {}: {}", line, e),
+            }
+        }
+    });
+    control
+}
+
+/// Print a line to stdout and flush it, so PrEval sees it right away
+fn emit(line: impl std::fmt::Display) {
+    println!("{}", line);
     io::stdout().flush().unwrap();
 }
 
+/// Small seeded random number generator, so a seed always gives the same run
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Next number, evenly spread over [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
 fn get_timestamp_nanos() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -0,0 +1,731 @@
+// Command line of the `preval` binary: running an evaluator in the TUI, and
+// browsing and comparing the runs recorded before
+
+use crate::{app, config, daemon, evaluator, export, history, state, ui};
+use anyhow::{Context, Result};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use config::{Config, ThemeConfig};
+use evaluator::container::{ContainerRuntime, ContainerSpec};
+use evaluator::lint::Severity;
+use evaluator::output_queue::{OutputBuffer, OverflowPolicy};
+use evaluator::process::{ShutdownPolicy, ShutdownSignal};
+use evaluator::remote::SshSpec;
+use evaluator::source::{ChildProcess, MetricSource, SourceSpec, TailFile};
+use evaluator::stderr::StderrFilter;
+use evaluator::supervisor::RestartPolicy;
+use export::github;
+use export::otlp_forward::ForwardEndpoint;
+use export::report::ReportTarget;
+use history::{
+    database::{RunDatabase, StoredRun},
+    RunDir,
+};
+use state::cost::BudgetAction;
+use state::sample_store::DuplicatePolicy;
+use state::script::SampleScript;
+use state::significance::{compare_samples, compare_stats, DEFAULT_ALPHA};
+use state::types::EvaluatorCommand;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use ui::alert::AlertStyle;
+use ui::keymap::Keymap;
+use ui::theme::{Palette, Theme};
+
+/// PrEval - A cross-platform TUI for running and monitoring prompt evaluation tests
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run an evaluator (the default when no subcommand is given)
+    Run(Box<RunArgs>),
+    /// Re-open the TUI for a run started with --detach
+    Attach {
+        /// Run id printed when the run was detached
+        run_id: String,
+        /// Get your attention when the run ends or first breaks a threshold
+        /// rule: 'off', 'bell' or 'osc9' (desktop notification); overrides the
+        /// config
+        #[arg(long, value_name = "ALERT")]
+        alert: Option<AlertStyle>,
+
+        /// Colors to use: 'dark', 'light', 'high-contrast' or 'no-color';
+        /// overrides the config
+        #[arg(long, value_name = "THEME")]
+        theme: Option<Palette>,
+    },
+    /// List past runs from the run history
+    History {
+        /// Number of runs to list, newest first
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: usize,
+        /// Rank runs by their composite score instead, best first
+        #[arg(long)]
+        by_score: bool,
+    },
+    /// Re-open a past run in a read-only TUI
+    Show {
+        /// Run id from `preval history`, or the run directory id of a run
+        /// that never finished, to recover it from its last snapshot
+        run: String,
+        /// Colors to use: 'dark', 'light', 'high-contrast' or 'no-color';
+        /// overrides the config
+        #[arg(long, value_name = "THEME")]
+        theme: Option<Palette>,
+    },
+    /// Test which metrics differ significantly between two past runs
+    Compare {
+        /// Run id of the baseline, from `preval history`
+        baseline: i64,
+        /// Run id to compare against the baseline
+        candidate: i64,
+        /// Significance level
+        #[arg(long, default_value_t = DEFAULT_ALPHA)]
+        alpha: f64,
+    },
+    /// Check recorded evaluator output against the protocol, without
+    /// running anything
+    Lint {
+        /// File of evaluator output, one protocol message per line
+        file: PathBuf,
+    },
+}
+
+#[derive(Args, Debug)]
+struct RunArgs {
+    /// Evaluator command to run
+    evaluator: Option<String>,
+
+    /// Read the protocol from somewhere other than an evaluator PrEval runs:
+    /// 'stdin', 'file=PATH' (replay saved output), 'unix=PATH' (listen on a
+    /// Unix socket) or 'otlp=HOST:PORT' (receive OTLP/HTTP JSON)
+    #[arg(long, value_name = "SOURCE", conflicts_with_all = ["evaluator", "pty", "container", "ssh"])]
+    source: Option<SourceSpec>,
+
+    /// Listen for services pushing the protocol to this Unix socket, or to
+    /// this named pipe if it was made with mkfifo, for as long as PrEval runs
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["evaluator", "source", "follow", "pty", "container", "ssh"])]
+    listen_unix: Option<PathBuf>,
+
+    /// Follow a JSONL file another program is writing the protocol to, like
+    /// `tail -f`, through truncation and rotation
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["evaluator", "source", "pty", "container", "ssh"])]
+    follow: Option<PathBuf>,
+
+    /// Keep running in the background; reconnect with `preval attach <run-id>`
+    #[arg(long, conflicts_with = "no_transcript")]
+    detach: bool,
+
+    /// Configuration file (defaults to config.toml in the platform config directory)
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Also export every metric line to an OTLP/HTTP collector (e.g. otlp://localhost:4318)
+    #[arg(long, value_name = "URL", value_parser = ForwardEndpoint::parse)]
+    forward: Option<ForwardEndpoint>,
+
+    /// Serve the run's progress and aggregates on this address for
+    /// Prometheus to scrape at /metrics (e.g. 127.0.0.1:9184)
+    #[arg(long, value_name = "ADDR")]
+    prometheus: Option<SocketAddr>,
+
+    /// Serve a live web dashboard of the run for browsers to watch, on
+    /// this port of localhost or this address (e.g. 8080)
+    #[arg(long, value_name = "PORT|ADDR", value_parser = parse_serve_addr)]
+    serve: Option<SocketAddr>,
+
+    /// Only show the dashboard to browsers giving this token, as
+    /// `?token=...` or a bearer authorization header
+    #[arg(long, value_name = "TOKEN", requires = "serve")]
+    serve_token: Option<String>,
+
+    /// Do not open the TUI, e.g. to watch the run only through --serve
+    #[arg(long)]
+    no_tui: bool,
+
+    /// Fail the run and stop the evaluator if it takes longer than this (e.g. 30m)
+    #[arg(long, value_name = "DURATION", value_parser = config::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// How long the evaluator has to send its handshake after starting
+    #[arg(long, value_name = "DURATION", default_value = "5s", value_parser = config::parse_duration)]
+    handshake_timeout: Duration,
+
+    /// Restart the evaluator up to this many times if it crashes before finishing
+    #[arg(long, value_name = "N")]
+    max_restarts: Option<u32>,
+
+    /// Delay before the first restart; doubles with each further restart
+    #[arg(long, value_name = "DURATION", default_value = "1s", value_parser = config::parse_duration)]
+    restart_backoff: Duration,
+
+    /// Run the evaluator under a pseudo-terminal, for evaluators that need a TTY
+    ///
+    /// The evaluator's stderr is merged into its output in this mode.
+    #[arg(long)]
+    pty: bool,
+
+    /// Run the evaluator in a Docker container from this image, with the
+    /// current directory mounted as its working directory
+    #[arg(long, value_name = "IMAGE", group = "container")]
+    docker: Option<String>,
+
+    /// Like --docker, but using Podman
+    #[arg(long, value_name = "IMAGE", group = "container")]
+    podman: Option<String>,
+
+    /// Run the evaluator on this machine over SSH (host, user@host or an
+    /// alias from the SSH config), in the remote user's home directory
+    #[arg(long, value_name = "DESTINATION", conflicts_with = "container")]
+    ssh: Option<String>,
+
+    /// Pass this environment variable through to the container (repeatable)
+    #[arg(long, value_name = "NAME", requires = "container")]
+    container_env: Vec<String>,
+
+    /// Number of evaluator output lines buffered while the display catches up
+    #[arg(long, value_name = "LINES", default_value_t = 100)]
+    output_buffer: usize,
+
+    /// What to do when the output buffer is full: 'block' the evaluator or
+    /// 'drop-oldest' lines
+    #[arg(long, value_name = "POLICY", default_value = "block")]
+    overflow: OverflowPolicy,
+
+    /// Most the run may cost in USD, estimated from token usage and the
+    /// `[pricing]` config
+    #[arg(long, value_name = "USD")]
+    budget: Option<f64>,
+
+    /// What to do when the budget is exceeded: 'warn' or 'abort' the run
+    #[arg(
+        long,
+        value_name = "ACTION",
+        default_value = "abort",
+        requires = "budget"
+    )]
+    budget_action: BudgetAction,
+
+    /// How metrics reported again for a sample are combined: 'merge',
+    /// 'replace' or 'rerun' (average); overrides the config
+    #[arg(long, value_name = "POLICY")]
+    duplicates: Option<DuplicatePolicy>,
+
+    /// Close the final summary after this long (seconds, or e.g. 1m) instead
+    /// of waiting for a key
+    #[arg(long, value_name = "DURATION", value_parser = config::parse_duration)]
+    auto_exit: Option<Duration>,
+
+    /// Write a report when the run ends (repeatable); 'junit=PATH' writes
+    /// JUnit XML with a test case per sample for CI systems
+    #[arg(long, value_name = "FORMAT=PATH")]
+    report: Vec<ReportTarget>,
+
+    /// Rhai script deriving metrics, checking and noting each sample;
+    /// overrides the config
+    #[arg(long, value_name = "PATH")]
+    script: Option<PathBuf>,
+
+    /// Write every event PrEval derives from the evaluator's output
+    /// (handshake, samples, errors, threshold trips, state changes) to this
+    /// file as JSON Lines
+    #[arg(long, value_name = "PATH")]
+    event_log: Option<PathBuf>,
+
+    /// Annotate failed samples and add the report to the job summary for
+    /// GitHub Actions; on by default when GITHUB_ACTIONS is set
+    #[arg(long)]
+    gha: bool,
+
+    /// Do not record a transcript of the run under the data directory
+    #[arg(long)]
+    no_transcript: bool,
+
+    /// Do not record the finished run in the run history
+    #[arg(long)]
+    no_history: bool,
+
+    /// Run id from `preval history` to compare the run against (toggle the
+    /// comparison with 'b')
+    #[arg(long, value_name = "RUN_ID")]
+    baseline: Option<i64>,
+
+    /// Get your attention when the run ends or first breaks a threshold
+    /// rule: 'off', 'bell' or 'osc9' (desktop notification); overrides the
+    /// config
+    #[arg(long, value_name = "ALERT")]
+    alert: Option<AlertStyle>,
+
+    /// Colors to use: 'dark', 'light', 'high-contrast' or 'no-color';
+    /// overrides the config
+    #[arg(long, value_name = "THEME")]
+    theme: Option<Palette>,
+
+    /// Signal sent to ask the evaluator to stop on quit (term or int)
+    #[arg(long, value_name = "SIGNAL", default_value = "term")]
+    shutdown_signal: ShutdownSignal,
+
+    /// How long the evaluator may take to exit before it is force-killed
+    #[arg(long, value_name = "DURATION", default_value = "5s", value_parser = config::parse_duration)]
+    grace_period: Duration,
+}
+
+/// Parse the command line of the process and do what it asks, returning
+/// the exit code of the `preval` binary
+pub async fn main() -> Result<ExitCode> {
+    // Initialize tracing subscriber with env filter
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_thread_ids(false)
+                .with_thread_names(false),
+        )
+        .with(
+            EnvFilter::try_from_default_env()
+                .or_else(|_| EnvFilter::try_new("info"))
+                .unwrap(),
+        )
+        .init();
+
+    // Parse command line arguments
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Attach {
+            run_id,
+            alert,
+            theme,
+        }) => attach(&run_id, alert, theme).await,
+        Some(Command::History { limit, by_score }) => history(limit, by_score),
+        Some(Command::Show { run, theme }) => show(&run, theme),
+        Some(Command::Compare {
+            baseline,
+            candidate,
+            alpha,
+        }) => compare(baseline, candidate, alpha),
+        Some(Command::Lint { file }) => lint(&file),
+        Some(Command::Run(args)) => run(*args).await,
+        None => run(cli.run).await,
+    }
+}
+
+/// Run an evaluator in the foreground, or hand it to a detached daemon
+async fn run(mut cli: RunArgs) -> Result<ExitCode> {
+    if cli.detach && cli.source == Some(SourceSpec::Stdin) {
+        anyhow::bail!("A run reading its protocol from stdin cannot be detached");
+    }
+    let listener = match cli.follow.take() {
+        Some(path) => Some(Box::new(TailFile::new(path)) as Box<dyn MetricSource>),
+        None => listen_source(&mut cli),
+    };
+    let source: Box<dyn MetricSource> = match (listener, cli.source.take(), cli.evaluator.take()) {
+        (Some(listener), _, _) => listener,
+        (None, Some(source), _) => source.into_source(),
+        (None, None, Some(command)) => {
+            let command =
+                EvaluatorCommand::try_new(command).context("Invalid evaluator command")?;
+            let mut process = ChildProcess::new(command)
+                .pty(cli.pty)
+                .output_buffer(OutputBuffer {
+                    capacity: cli.output_buffer,
+                    overflow: cli.overflow,
+                });
+            let container = match (cli.docker.take(), cli.podman.take()) {
+                (Some(image), _) => Some(ContainerSpec::new(ContainerRuntime::Docker, image)),
+                (_, Some(image)) => Some(ContainerSpec::new(ContainerRuntime::Podman, image)),
+                (None, None) => None,
+            };
+            if let Some(container) = container {
+                let container = std::mem::take(&mut cli.container_env)
+                    .into_iter()
+                    .fold(container, ContainerSpec::forward_env);
+                process = process.container(container);
+            }
+            if let Some(destination) = cli.ssh.take() {
+                process = process.ssh(SshSpec::new(destination));
+            }
+            Box::new(process)
+        }
+        // If no evaluator specified, show help
+        (None, None, None) => {
+            Cli::command().print_help()?;
+            return Ok(ExitCode::SUCCESS);
+        }
+    };
+
+    let config = Config::load(cli.config.as_deref())?;
+    let stderr_filter =
+        StderrFilter::new(&config.stderr.filters).context("Invalid stderr filter in config")?;
+    let keymap = Keymap::with_overrides(&config.keys).context("Invalid keybindings in config")?;
+
+    // A daemon continues the run its parent created; everyone else starts one
+    let daemon_run_id = std::env::var(daemon::DAEMON_RUN_ENV).ok();
+    let run_dir = match (&daemon_run_id, RunDir::default_root()) {
+        (Some(id), Some(root)) => Some(RunDir::open(&root, id)?),
+        (None, Some(root)) if !cli.no_transcript => Some(RunDir::create(&root)?),
+        (_, None) if !cli.no_transcript => {
+            tracing::warn!("No data directory on this platform, not recording transcript");
+            None
+        }
+        _ => None,
+    };
+
+    if cli.detach {
+        let run_dir = run_dir.context("No run directory to detach into")?;
+        let pid = daemon::spawn_detached(&run_dir)?;
+        run_dir.write_daemon_pid(pid)?;
+        println!(
+            "Started run {} in the background (pid {})",
+            run_dir.id(),
+            pid
+        );
+        println!("Attach with: preval attach {}", run_dir.id());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Create and run the application
+    let mut app = app::App::new(Some(source))
+        .shutdown_policy(ShutdownPolicy {
+            signal: cli.shutdown_signal,
+            grace_period: cli.grace_period,
+        })
+        .stderr_filter(stderr_filter)
+        .stderr_capacity(config.stderr.capacity)
+        .sample_capacity(config.samples.capacity)
+        .spill_samples(config.samples.spill)
+        .duplicate_policy(cli.duplicates.or(config.samples.duplicates))
+        .text_capture(config.samples.text)
+        .trends(config.trends.metrics)
+        .keymap(keymap)
+        .theme(choose_theme(cli.theme, &config.theme))
+        .pricing(config.pricing)
+        .derived_metrics(config.derived.metrics)
+        .script(load_script(cli.script.or(config.script.path))?)
+        .score(config.score.definition)
+        .anomaly_detector(config.anomalies.detector())
+        .exclusions(config.aggregates)
+        .threshold_rules(config.thresholds.rules)
+        .warning_rules(config.thresholds.warnings)
+        .group_by(config.grouping.default)
+        .confirm_quit(config.ui.confirm_quit)
+        .alert(cli.alert.unwrap_or(config.ui.alert))
+        .report_format(config.report.format)
+        .report_dir(config.report.directory)
+        .report_targets(cli.report)
+        .webhooks(config.webhooks)
+        .hooks(config.hooks)
+        .tracking(config.tracking)
+        .github_actions(cli.gha || github::detected());
+    if let Some(run_dir) = run_dir {
+        app = app.record_run(run_dir).headless(daemon_run_id.is_some());
+    }
+    if let Some(id) = cli.baseline {
+        let path = RunDatabase::default_path().context("No data directory on this platform")?;
+        let baseline = RunDatabase::open(&path)?
+            .load_run(id)
+            .with_context(|| format!("Failed to load baseline run {}", id))?;
+        app = app.baseline(baseline.into_baseline());
+    }
+    if !cli.no_history {
+        match RunDatabase::default_path() {
+            Some(path) => app = app.record_history(path),
+            None => tracing::warn!("No data directory on this platform, not recording run history"),
+        }
+    }
+    if let Some(max_restarts) = cli.max_restarts {
+        app = app.restart_policy(RestartPolicy::new(max_restarts, cli.restart_backoff));
+    }
+    if let Some(budget) = cli.budget {
+        app = app.budget(budget, cli.budget_action);
+    }
+    if let Some(timeout) = cli.timeout {
+        app = app.timeout(timeout);
+    }
+    app = app.handshake_timeout(cli.handshake_timeout);
+    if let Some(endpoint) = cli.forward {
+        app = app.forward_to(endpoint);
+    }
+    if let Some(addr) = cli.prometheus {
+        app = app.prometheus(addr);
+    }
+    if let Some(addr) = cli.serve {
+        app = app.dashboard(addr);
+    }
+    if let Some(token) = cli.serve_token {
+        app = app.dashboard_token(token);
+    }
+    if cli.no_tui {
+        app = app.headless(true);
+    }
+    if let Some(path) = cli.event_log {
+        app = app.event_log(path);
+    }
+    if let Some(delay) = cli.auto_exit {
+        app = app.auto_exit(delay);
+    }
+
+    run_app(app).await
+}
+
+/// Open the TUI on a run owned by a detached daemon
+async fn attach(
+    run_id: &str,
+    alert: Option<AlertStyle>,
+    theme: Option<Palette>,
+) -> Result<ExitCode> {
+    let root = RunDir::default_root().context("No data directory on this platform")?;
+    let run_dir = RunDir::open(&root, run_id)?;
+
+    let config = Config::load(None)?;
+    let stderr_filter =
+        StderrFilter::new(&config.stderr.filters).context("Invalid stderr filter in config")?;
+    let keymap = Keymap::with_overrides(&config.keys).context("Invalid keybindings in config")?;
+
+    let app = app::App::new(None)
+        .follow(run_dir)
+        .stderr_filter(stderr_filter)
+        .stderr_capacity(config.stderr.capacity)
+        .sample_capacity(config.samples.capacity)
+        .duplicate_policy(config.samples.duplicates)
+        .text_capture(config.samples.text)
+        .trends(config.trends.metrics)
+        .keymap(keymap)
+        .theme(choose_theme(theme, &config.theme))
+        .pricing(config.pricing)
+        .derived_metrics(config.derived.metrics)
+        .script(load_script(config.script.path)?)
+        .score(config.score.definition)
+        .anomaly_detector(config.anomalies.detector())
+        .exclusions(config.aggregates)
+        .threshold_rules(config.thresholds.rules)
+        .warning_rules(config.thresholds.warnings)
+        .group_by(config.grouping.default)
+        .alert(alert.unwrap_or(config.ui.alert))
+        .report_format(config.report.format)
+        .report_dir(config.report.directory);
+    run_app(app).await
+}
+
+/// Source listening where `--listen-unix` asks, if it does
+#[cfg(unix)]
+fn listen_source(cli: &mut RunArgs) -> Option<Box<dyn MetricSource>> {
+    cli.listen_unix.take().map(evaluator::source::listen_unix)
+}
+
+/// Source listening where `--listen-unix` asks, which needs Unix sockets
+#[cfg(not(unix))]
+fn listen_source(_cli: &mut RunArgs) -> Option<Box<dyn MetricSource>> {
+    None
+}
+
+/// Compile the script at `path`, if one is given
+fn load_script(path: Option<PathBuf>) -> Result<Option<SampleScript>> {
+    Ok(path.as_deref().map(SampleScript::load).transpose()?)
+}
+
+/// Theme picked with `--theme`, or else in the config
+fn choose_theme(palette: Option<Palette>, config: &ThemeConfig) -> Theme {
+    let palette = palette.or(config.palette).unwrap_or_else(Palette::from_env);
+    Theme::new(palette, &config.colors)
+}
+
+/// Address given to `--serve`: a port of localhost, so the dashboard is
+/// only reachable from other hosts when asked for, or a full address
+fn parse_serve_addr(input: &str) -> Result<SocketAddr, String> {
+    match input.parse::<u16>() {
+        Ok(port) => Ok(SocketAddr::from(([127, 0, 0, 1], port))),
+        Err(_) => input.parse().map_err(|_| {
+            format!(
+                "expected a port or an address such as 127.0.0.1:8080, got '{}'",
+                input
+            )
+        }),
+    }
+}
+
+/// Print the most recent or best scoring runs from the run history
+fn history(limit: usize, by_score: bool) -> Result<ExitCode> {
+    let path = RunDatabase::default_path().context("No data directory on this platform")?;
+    let runs = RunDatabase::open(&path)?.list_runs(limit, by_score)?;
+    if runs.is_empty() {
+        println!("No runs recorded yet");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    println!(
+        "{:>5}  {:<19}  {:<9}  {:>7}  {:>6}  {:>7}  EVALUATOR",
+        "ID", "STARTED (UTC)", "OUTCOME", "SAMPLES", "FAILED", "SCORE"
+    );
+    for (id, run) in runs {
+        let score = match run.score {
+            Some(score) => format!("{:.3}", score),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:>5}  {:<19}  {:<9}  {:>7}  {:>6}  {:>7}  {}",
+            id,
+            run.started_at.format("%Y-%m-%d %H:%M:%S"),
+            run.outcome.to_string(),
+            run.samples_completed,
+            run.samples_failed,
+            score,
+            run.evaluator
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Open a past run from the run history, or recover an unfinished one from
+/// its last snapshot, in a read-only TUI
+fn show(run: &str, theme: Option<Palette>) -> Result<ExitCode> {
+    let run = match run.parse::<i64>() {
+        Ok(id) => {
+            let path = RunDatabase::default_path().context("No data directory on this platform")?;
+            RunDatabase::open(&path)?.load_run(id)?
+        }
+        Err(_) => {
+            let root = RunDir::default_root().context("No data directory on this platform")?;
+            let run_dir = RunDir::open(&root, run)?;
+            run_dir.read_snapshot()?.recover(&run_dir.samples_path())?
+        }
+    };
+    let config = Config::load(None)?;
+    ui::history::show_run(&run, choose_theme(theme, &config.theme))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Print which metrics differ significantly between two past runs
+fn compare(baseline_id: i64, candidate_id: i64, alpha: f64) -> Result<ExitCode> {
+    let path = RunDatabase::default_path().context("No data directory on this platform")?;
+    let database = RunDatabase::open(&path)?;
+    let baseline = database.load_run(baseline_id)?;
+    let candidate = database.load_run(candidate_id)?;
+
+    println!(
+        "Run {} ({}, {} samples) vs run {} ({}, {} samples)",
+        baseline_id,
+        baseline.record.evaluator,
+        baseline.record.samples_completed,
+        candidate_id,
+        candidate.record.evaluator,
+        candidate.record.samples_completed
+    );
+    // The stored statistics cover every sample, even those the run did not
+    // keep; runs recorded before statistics were stored only have samples
+    let has_stats = |run: &StoredRun| run.metric_stats.iter().next().is_some();
+    let comparisons = if has_stats(&baseline) && has_stats(&candidate) {
+        compare_stats(&baseline.metric_stats, &candidate.metric_stats)
+    } else {
+        for (id, run) in [(baseline_id, &baseline), (candidate_id, &candidate)] {
+            if run.samples.len() < run.record.samples_completed {
+                eprintln!(
+                    "Warning: run {} kept {} of its {} samples; comparing those alone",
+                    id,
+                    run.samples.len(),
+                    run.record.samples_completed
+                );
+            }
+        }
+        compare_samples(&baseline.samples, &candidate.samples)
+    };
+    if comparisons.is_empty() {
+        println!("No sample metrics to compare");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    println!(
+        "{:<30}  {:>18}  {:>18}  {:>10}  {:>8}",
+        "METRIC", "BASELINE", "CANDIDATE", "DIFF", "P-VALUE"
+    );
+    for comparison in &comparisons {
+        let p_value = match comparison.test {
+            Some(test) => format!("{:.4}", test.p_value),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<30}  {:>18}  {:>18}  {:>+10.4}  {:>8}{}",
+            comparison.metric,
+            format!(
+                "{:.4} (n={})",
+                comparison.baseline.mean, comparison.baseline.count
+            ),
+            format!(
+                "{:.4} (n={})",
+                comparison.candidate.mean, comparison.candidate.count
+            ),
+            comparison.difference(),
+            p_value,
+            if comparison.is_significant(alpha) {
+                "  *"
+            } else {
+                ""
+            }
+        );
+    }
+    println!(
+        "\n* significant at p < {} (Welch's t-test over per-sample values)",
+        alpha
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Print what is wrong with the evaluator output recorded in `path`,
+/// failing if any of it would be rejected
+fn lint(path: &std::path::Path) -> Result<ExitCode> {
+    let output = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let findings = evaluator::lint::lint(output.lines());
+    for finding in &findings {
+        match finding.line {
+            Some(line) => println!(
+                "{}:{}: {}: {}",
+                path.display(),
+                line,
+                finding.severity,
+                finding.message
+            ),
+            None => println!(
+                "{}: {}: {}",
+                path.display(),
+                finding.severity,
+                finding.message
+            ),
+        }
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    println!(
+        "{} error(s), {} warning(s)",
+        errors,
+        findings.len() - errors
+    );
+    Ok(if errors == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Run the app on its own task so a panic in the main loop is caught here,
+/// after the panic hook has restored the terminal, and then re-raised
+async fn run_app(mut app: app::App) -> Result<ExitCode> {
+    match tokio::spawn(async move { app.run().await }).await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(e.into()),
+    }
+}
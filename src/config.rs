@@ -1,8 +1,434 @@
 /// Configuration handling for PrEval
+use crate::evaluator::stderr::DEFAULT_STDERR_FILTERS;
+use crate::export::hooks::HooksConfig;
+use crate::export::report::ReportFormat;
+use crate::export::tracking::TrackingConfig;
+use crate::export::webhook::Webhook;
+use crate::state::aggregation::Exclusions;
+use crate::state::anomaly::{AnomalyDetector, DEFAULT_ANOMALY_THRESHOLD, DEFAULT_ANOMALY_WINDOW};
+use crate::state::cost::PricingTable;
+use crate::state::expression::DerivedMetric;
+use crate::state::rules::ThresholdRule;
+use crate::state::sample_store::{DuplicatePolicy, DEFAULT_SAMPLE_CAPACITY};
+use crate::state::trends::DEFAULT_TRENDS;
+use crate::state::types::TextCapture;
+use crate::ui::alert::AlertStyle;
+use crate::ui::keymap::KeyList;
+use crate::ui::theme::{Palette, ThemeColors};
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Main configuration structure
+///
+/// Loaded from `config.toml` in the platform config directory (for example
+/// `~/.config/preval/config.toml`) or from the file given with `--config`.
+/// Every section is optional and falls back to its defaults.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
-    // TODO: Add configuration fields as needed
+    /// Evaluator stderr handling
+    pub stderr: StderrConfig,
+    /// Sample history
+    pub samples: SamplesConfig,
+    /// Threshold rules every run must meet
+    pub thresholds: ThresholdsConfig,
+    /// Breakdown of statistics by attribute
+    pub grouping: GroupingConfig,
+    /// Metrics computed from the ones the evaluator reports
+    pub derived: DerivedConfig,
+    /// Script deriving metrics, checking and noting each sample
+    pub script: ScriptConfig,
+    /// Token prices for estimating what a run costs
+    pub pricing: PricingTable,
+    /// Composite score used as the headline number of a run
+    pub score: ScoreConfig,
+    /// Flagging of metric values far outside their recent values
+    pub anomalies: AnomaliesConfig,
+    /// Warm-up samples and outliers left out of aggregate statistics
+    pub aggregates: Exclusions,
+    /// Key metrics drawn as sparklines in the overview
+    pub trends: TrendsConfig,
+    /// Keys bound to UI actions, such as `quit = "q"` or
+    /// `pause = ["space", "p"]`; unlisted actions keep their default keys
+    pub keys: BTreeMap<String, KeyList>,
+    /// Colors of the TUI
+    pub theme: ThemeConfig,
+    /// Behavior of the TUI
+    pub ui: UiConfig,
+    /// Reports written from the TUI
+    pub report: ReportConfig,
+    /// URLs posted to when a run starts, ends or first breaks a threshold
+    /// rule, each written as a `[[webhooks]]` table
+    pub webhooks: Vec<Webhook>,
+    /// Shell commands run when a run completes, fails or first breaks a
+    /// threshold rule
+    pub hooks: HooksConfig,
+    /// Experiment trackers finished runs are logged to
+    pub tracking: TrackingConfig,
+}
+
+/// Settings for the stderr pane
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StderrConfig {
+    /// Regular expressions for stderr lines to hide (build-tool noise)
+    pub filters: Vec<String>,
+    /// Number of stderr lines kept for display
+    pub capacity: usize,
+}
+
+impl Default for StderrConfig {
+    fn default() -> Self {
+        Self {
+            filters: DEFAULT_STDERR_FILTERS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            capacity: 500,
+        }
+    }
+}
+
+/// Settings for the sample history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SamplesConfig {
+    /// Number of samples kept in memory
+    pub capacity: usize,
+    /// Write samples beyond `capacity` to `samples.jsonl` in the run
+    /// directory instead of dropping them
+    pub spill: bool,
+    /// How metrics reported again for a sample are combined: `merge`,
+    /// `replace` or `rerun`; by default reruns from the handshake's
+    /// `runs_per_sample` are averaged and anything else is merged
+    pub duplicates: Option<DuplicatePolicy>,
+    /// Attribute text kept with each sample for the detail view
+    pub text: TextCapture,
+}
+
+impl Default for SamplesConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_SAMPLE_CAPACITY,
+            spill: false,
+            duplicates: None,
+            text: TextCapture::default(),
+        }
+    }
+}
+
+/// Threshold rules such as `accuracy >= 0.8` or `latency.p95 < 2000ms`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdsConfig {
+    /// Rules written `<metric>[.<aggregate>] <op> <value>[unit]`
+    pub rules: Vec<ThresholdRule>,
+    /// Rules written the same way that only mark values in yellow when
+    /// broken, without failing samples or the run
+    pub warnings: Vec<ThresholdRule>,
+}
+
+/// Settings for grouping statistics by a data-point attribute
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GroupingConfig {
+    /// Attribute to group by when a run starts, e.g. `category`; `g` cycles
+    /// through the others
+    pub default: Option<String>,
+}
+
+/// Metrics computed per sample, such as `f1 = 2*p*r/(p+r)`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DerivedConfig {
+    /// Definitions written `<name> = <expression>`, evaluated in order
+    pub metrics: Vec<DerivedMetric>,
+}
+
+/// Evaluation logic too involved for the declarative config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScriptConfig {
+    /// Rhai script defining any of `derive(metrics)`, `check(sample)` and
+    /// `on_sample(sample)`
+    pub path: Option<PathBuf>,
+}
+
+/// Weighted composite of several metrics, such as
+/// `score = 0.7*accuracy + 0.3*(1 - latency/2000)`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoreConfig {
+    /// Definition written `<name> = <expression>`; overrides a score from
+    /// the evaluator's handshake
+    pub definition: Option<DerivedMetric>,
+}
+
+/// Settings for flagging samples whose metrics jump far from recent values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnomaliesConfig {
+    /// Whether anomalous samples are flagged at all
+    pub enabled: bool,
+    /// Number of recent values of a metric each value is compared against
+    pub window: usize,
+    /// Deviations from the median, scaled by the median absolute
+    /// deviation, beyond which a value is anomalous
+    pub threshold: f64,
+}
+
+impl Default for AnomaliesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window: DEFAULT_ANOMALY_WINDOW,
+            threshold: DEFAULT_ANOMALY_THRESHOLD,
+        }
+    }
+}
+
+/// Settings for the sparklines of key metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrendsConfig {
+    /// Metrics whose recent values are drawn, in order; `sample.duration_ms`
+    /// and `tokens` (input and output tokens per sample) are available too
+    pub metrics: Vec<String>,
+}
+
+impl Default for TrendsConfig {
+    fn default() -> Self {
+        Self {
+            metrics: DEFAULT_TRENDS.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// Settings for the colors of the TUI
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// Palette to start from: `dark`, `light`, `high-contrast` or
+    /// `no-color`; by default dark, or no colors if `NO_COLOR` is set
+    pub palette: Option<Palette>,
+    /// Colors replacing the palette's, e.g. `accent = "blue"` or
+    /// `warning = "#aa7700"`
+    pub colors: ThemeColors,
+}
+
+/// Settings for reports written on demand from the TUI
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReportConfig {
+    /// File format: `markdown` or `json`
+    pub format: ReportFormat,
+    /// Directory reports are written to; by default the run's directory
+    /// when it is recorded, else the current directory
+    pub directory: Option<PathBuf>,
+}
+
+/// Settings for how the TUI behaves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    /// Ask before quitting while an evaluation is running; `Q` quits
+    /// without asking either way
+    pub confirm_quit: bool,
+    /// Get your attention when a run ends or first breaks a threshold
+    /// rule: `off`, `bell` or `osc9` (a desktop notification in terminals
+    /// that support it)
+    pub alert: AlertStyle,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            confirm_quit: true,
+            alert: AlertStyle::default(),
+        }
+    }
+}
+
+impl AnomaliesConfig {
+    /// Detector for these settings, unless disabled
+    pub fn detector(&self) -> Option<AnomalyDetector> {
+        self.enabled
+            .then(|| AnomalyDetector::new(self.window, self.threshold))
+    }
+}
+
+impl Config {
+    /// Load configuration from an explicit path or the default location
+    ///
+    /// An explicit path must exist; a missing default file means defaults.
+    pub fn load(explicit_path: Option<&Path>) -> Result<Self> {
+        let path = match explicit_path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Default config file location for this platform
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("preval").join("config.toml"))
+    }
+}
+
+/// Parse a human-friendly duration such as `500ms`, `30s`, `5m`, or `2h`
+///
+/// A bare number is interpreted as seconds.
+pub(crate) fn parse_duration(input: &str) -> Result<Duration, DurationParseError> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| DurationParseError::InvalidNumber(input.to_string()))?;
+
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 60.0 * 60.0,
+        other => return Err(DurationParseError::UnknownUnit(other.to_string())),
+    };
+
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| DurationParseError::InvalidNumber(input.to_string()))
+}
+
+/// Errors for unparseable durations
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum DurationParseError {
+    #[error("invalid duration '{0}'")]
+    InvalidNumber(String),
+    #[error("unknown duration unit '{0}' (expected ms, s, m, or h)")]
+    UnknownUnit(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_config_keeps_defaults() {
+        let config: Config = toml::from_str("[stderr]\ncapacity = 50\n").unwrap();
+
+        assert_eq!(config.stderr.capacity, 50);
+        assert_eq!(config.stderr.filters.len(), DEFAULT_STDERR_FILTERS.len());
+        assert!(config.ui.confirm_quit);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(toml::from_str::<Config>("[stderr]\nfilter = []\n").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_threshold_rules() {
+        let config: Config =
+            toml::from_str("[thresholds]\nrules = [\"latency.p95 < 2000ms\"]\n").unwrap();
+        assert_eq!(
+            config.thresholds.rules[0].to_string(),
+            "latency.p95 < 2000ms"
+        );
+
+        assert!(toml::from_str::<Config>("[thresholds]\nrules = [\"latency\"]\n").is_err());
+    }
+
+    #[test]
+    fn parses_pricing_table() {
+        let config: Config = toml::from_str(
+            "[pricing]\nmodel_attribute = \"model\"\n\n\
+             [pricing.models.gpt-4o]\ninput_per_million = 2.5\noutput_per_million = 10.0\n",
+        )
+        .unwrap();
+        assert_eq!(config.pricing.model_attribute, "model");
+        assert_eq!(config.pricing.input_tokens, "gen_ai.usage.input_tokens");
+        assert_eq!(
+            config
+                .pricing
+                .price_for(Some("gpt-4o"))
+                .unwrap()
+                .output_per_million,
+            10.0
+        );
+    }
+
+    #[test]
+    fn parses_aggregate_exclusions() {
+        let config: Config =
+            toml::from_str("[aggregates]\nwarmup_samples = 5\ntrim_percent = 1.0\n").unwrap();
+        assert_eq!(config.aggregates.warmup_samples, 5);
+        assert_eq!(config.aggregates.trim_percent, 1.0);
+        assert_eq!(Config::default().aggregates, Exclusions::default());
+    }
+
+    #[test]
+    fn parses_sample_text_capture() {
+        let config: Config =
+            toml::from_str("[samples.text]\nattributes = [\"question\"]\nmax_chars = 80\n")
+                .unwrap();
+        assert_eq!(config.samples.text.attributes, vec!["question".to_string()]);
+        assert_eq!(config.samples.text.max_chars, 80);
+        assert_eq!(Config::default().samples.text, TextCapture::default());
+    }
+
+    #[test]
+    fn parses_trend_metrics() {
+        let config: Config = toml::from_str("[trends]\nmetrics = [\"f1\"]\n").unwrap();
+        assert_eq!(config.trends.metrics, vec!["f1".to_string()]);
+        assert_eq!(Config::default().trends.metrics.len(), DEFAULT_TRENDS.len());
+    }
+
+    #[test]
+    fn parses_keybindings() {
+        let config: Config =
+            toml::from_str("[keys]\nquit = \"x\"\npause = [\"space\", \"p\"]\n").unwrap();
+        assert_eq!(config.keys["quit"], KeyList::One("x".to_string()));
+        assert_eq!(
+            config.keys["pause"],
+            KeyList::Many(vec!["space".to_string(), "p".to_string()])
+        );
+    }
+
+    #[test]
+    fn parses_theme() {
+        let config: Config =
+            toml::from_str("[theme]\npalette = \"light\"\n\n[theme.colors]\naccent = \"blue\"\n")
+                .unwrap();
+        assert_eq!(config.theme.palette, Some(Palette::Light));
+        assert!(config.theme.colors.accent.is_some());
+        assert!(toml::from_str::<Config>("[theme]\npalette = \"sepia\"\n").is_err());
+    }
+
+    #[test]
+    fn parses_duration_units() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn rejects_unknown_duration_unit() {
+        assert!(matches!(
+            parse_duration("3 days"),
+            Err(DurationParseError::UnknownUnit(_))
+        ));
+    }
 }
@@ -0,0 +1,49 @@
+// Detached runs that keep going after the terminal goes away
+
+use crate::history::RunDir;
+use anyhow::{Context, Result};
+use std::process::{Command, Stdio};
+
+/// Environment variable telling a re-executed PrEval which run it is the daemon for
+pub(crate) const DAEMON_RUN_ENV: &str = "PREVAL_DAEMON_RUN";
+
+/// Start the current command line again in the background as a headless daemon
+///
+/// Returns the daemon's pid. The daemon gets its own process group and logs
+/// to the run directory, so closing the terminal or SSH session does not
+/// stop it.
+pub(crate) fn spawn_detached(run_dir: &RunDir) -> Result<u32> {
+    let exe = std::env::current_exe().context("Failed to locate the preval executable")?;
+    let args = std::env::args_os().skip(1).filter(|arg| arg != "--detach");
+    let log_path = run_dir.daemon_log_path();
+    let log = std::fs::File::create(&log_path)
+        .with_context(|| format!("Failed to create {}", log_path.display()))?;
+
+    let mut command = Command::new(exe);
+    command
+        .args(args)
+        .env(DAEMON_RUN_ENV, run_dir.id())
+        .stdin(Stdio::null())
+        .stdout(log.try_clone().context("Failed to share daemon log")?)
+        .stderr(log);
+    detach_from_terminal(&mut command);
+
+    let daemon = command.spawn().context("Failed to start daemon")?;
+    Ok(daemon.id())
+}
+
+#[cfg(unix)]
+fn detach_from_terminal(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach_from_terminal(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
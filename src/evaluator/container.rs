@@ -0,0 +1,189 @@
+//! Running evaluators inside a container
+
+use crate::state::types::EvaluatorCommand;
+use std::ffi::OsString;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Directory the working directory is mounted at inside the container
+const CONTAINER_WORKDIR: &str = "/work";
+
+/// Counts containers started by this PrEval, so restarts get fresh names
+static CONTAINER_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Container engine used to run the evaluator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+}
+
+impl ContainerRuntime {
+    /// Name of the engine's CLI
+    pub fn program(&self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::Podman => "podman",
+        }
+    }
+}
+
+/// How to run the evaluator in a container
+///
+/// The current directory is mounted at `/work` and used as the working
+/// directory, and the named environment variables are passed through from
+/// PrEval's environment. PrEval still reads the protocol from the
+/// container's stdout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerSpec {
+    pub runtime: ContainerRuntime,
+    pub image: String,
+    pub env: Vec<String>,
+}
+
+impl ContainerSpec {
+    /// Create a spec for running `image` with the given engine
+    pub fn new(runtime: ContainerRuntime, image: impl Into<String>) -> Self {
+        Self {
+            runtime,
+            image: image.into(),
+            env: Vec::new(),
+        }
+    }
+
+    /// Pass an environment variable through to the container (builder pattern)
+    pub fn forward_env(mut self, name: impl Into<String>) -> Self {
+        self.env.push(name.into());
+        self
+    }
+
+    /// Pick a name for the next container, unique within this PrEval
+    pub(crate) fn next_container_name(&self) -> String {
+        format!(
+            "preval-{}-{}",
+            std::process::id(),
+            CONTAINER_COUNT.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+
+    /// Command line that runs `command` in a container called `name`
+    pub(crate) fn argv(
+        &self,
+        command: &EvaluatorCommand,
+        name: &str,
+        tty: bool,
+        cwd: &Path,
+    ) -> Vec<OsString> {
+        let mut volume = cwd.as_os_str().to_os_string();
+        volume.push(":");
+        volume.push(CONTAINER_WORKDIR);
+
+        let mut argv: Vec<OsString> = vec![
+            self.runtime.program().into(),
+            "run".into(),
+            "--rm".into(),
+            // Forward signals to the evaluator and reap its children
+            "--init".into(),
+            "--name".into(),
+            name.into(),
+            "-v".into(),
+            volume,
+            "-w".into(),
+            CONTAINER_WORKDIR.into(),
+        ];
+        argv.push(if tty { "-it" } else { "-i" }.into());
+        for name in &self.env {
+            // Without a value, the engine copies it from its own environment
+            argv.push("-e".into());
+            argv.push(name.into());
+        }
+        argv.push(self.image.as_str().into());
+        argv.extend(command.as_ref().split_whitespace().map(OsString::from));
+        argv
+    }
+
+    /// Command line that freezes the container called `name`, or thaws it
+    /// again when `paused` is false
+    ///
+    /// Signals sent to the engine's client never reach the evaluator, so
+    /// this is how a container that does not read control messages is
+    /// paused.
+    pub(crate) fn pause_argv(&self, name: &str, paused: bool) -> Vec<OsString> {
+        vec![
+            self.runtime.program().into(),
+            if paused { "pause" } else { "unpause" }.into(),
+            name.into(),
+        ]
+    }
+
+    /// Command line that force-removes the container called `name`
+    ///
+    /// Killing the engine's client does not always stop the container,
+    /// so this runs after every evaluator exit.
+    pub(crate) fn remove_argv(&self, name: &str) -> Vec<OsString> {
+        vec![
+            self.runtime.program().into(),
+            "rm".into(),
+            "-f".into(),
+            name.into(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_command_in_container_run() {
+        let spec =
+            ContainerSpec::new(ContainerRuntime::Podman, "python:3.12").forward_env("API_KEY");
+        let command = EvaluatorCommand::try_new("python eval.py".to_string()).unwrap();
+
+        let argv = spec.argv(&command, "preval-1-0", false, Path::new("/home/me/evals"));
+
+        let argv: Vec<_> = argv.iter().map(|arg| arg.to_str().unwrap()).collect();
+        assert_eq!(
+            argv,
+            vec![
+                "podman",
+                "run",
+                "--rm",
+                "--init",
+                "--name",
+                "preval-1-0",
+                "-v",
+                "/home/me/evals:/work",
+                "-w",
+                "/work",
+                "-i",
+                "-e",
+                "API_KEY",
+                "python:3.12",
+                "python",
+                "eval.py",
+            ]
+        );
+    }
+
+    #[test]
+    fn pauses_and_unpauses_by_name() {
+        let spec = ContainerSpec::new(ContainerRuntime::Docker, "image");
+
+        assert_eq!(
+            spec.pause_argv("preval-1-0", true),
+            vec!["docker", "pause", "preval-1-0"]
+        );
+        assert_eq!(
+            spec.pause_argv("preval-1-0", false),
+            vec!["docker", "unpause", "preval-1-0"]
+        );
+    }
+
+    #[test]
+    fn container_names_are_unique() {
+        let spec = ContainerSpec::new(ContainerRuntime::Docker, "image");
+
+        assert_ne!(spec.next_container_name(), spec.next_container_name());
+    }
+}
@@ -7,15 +7,18 @@ use tokio::time::timeout;
 /// Parse a handshake JSON message from the evaluator
 pub fn parse_handshake(line: &str) -> Result<ValidatedHandshake> {
     // First validate the JSON is well-formed
-    let valid_json = ValidJson::try_new(line.to_string())
-        .context("malformed JSON in handshake")?;
-    
+    let valid_json = ValidJson::try_new(line.to_string()).context("malformed JSON in handshake")?;
+
     // Then parse it as a handshake
-    let handshake: Handshake = valid_json.parse()
+    let handshake: Handshake = valid_json
+        .parse()
         .context("failed to parse handshake JSON")?;
 
     // Validate that the message type is correct
-    if !matches!(handshake.msg_type, crate::evaluator::protocol::MessageType::Handshake) {
+    if !matches!(
+        handshake.msg_type,
+        crate::evaluator::protocol::MessageType::Handshake
+    ) {
         anyhow::bail!(
             "invalid message type: expected 'handshake', got '{:?}'",
             handshake.msg_type
@@ -73,7 +76,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::evaluator::protocol::EvaluationMode;
+    use crate::evaluator::protocol::{Capability, EvaluationMode};
 
     const VALID_HANDSHAKE_JSON: &str = r#"{
         "type": "handshake",
@@ -85,7 +88,8 @@ mod tests {
         },
         "execution_plan": {
             "total_samples": 50,
-            "batch_size": 10
+            "batch_size": 10,
+            "runs_per_sample": 3
         },
         "metrics_schema": [
             {
@@ -111,9 +115,58 @@ mod tests {
         let plan = result.execution_plan.unwrap();
         assert_eq!(plan.total_samples.into_inner(), 50);
         assert_eq!(plan.batch_size.map(|b| b.into_inner()), Some(10));
+        assert_eq!(plan.runs_per_sample.map(|r| r.into_inner()), Some(3));
 
         assert_eq!(result.metrics_schema.len(), 1);
         assert_eq!(result.metrics_schema[0].name.as_ref(), "accuracy");
+        assert!(result.capabilities.is_empty());
+    }
+
+    #[test]
+    fn reads_known_capabilities_and_ignores_the_rest() {
+        let json = VALID_HANDSHAKE_JSON.replacen(
+            r#""type": "handshake","#,
+            r#""type": "handshake", "capabilities": ["pause", "teleport", "cancel"],"#,
+            1,
+        );
+
+        let result = parse_handshake(&json).unwrap();
+
+        assert_eq!(
+            result.capabilities,
+            vec![Capability::Pause, Capability::Cancel]
+        );
+    }
+
+    #[test]
+    fn rejects_unparseable_thresholds() {
+        let json = VALID_HANDSHAKE_JSON.replacen(
+            r#""type": "handshake","#,
+            r#""type": "handshake", "thresholds": ["accuracy >= 0.8", "latency fast"],"#,
+            1,
+        );
+
+        assert!(parse_handshake(&json).is_err());
+    }
+
+    #[test]
+    fn reads_phases_of_the_execution_plan() {
+        let json = VALID_HANDSHAKE_JSON.replacen(
+            r#""total_samples": 50,"#,
+            r#""total_samples": 50, "phases": [{"name": "generate", "samples": 25}, {"name": "grade", "samples": 25}],"#,
+            1,
+        );
+
+        let plan = parse_handshake(&json).unwrap().execution_plan.unwrap();
+        let phases: Vec<(&str, u32)> = plan
+            .phases
+            .iter()
+            .map(|phase| (phase.name.as_ref(), phase.samples.into_inner()))
+            .collect();
+        assert_eq!(phases, vec![("generate", 25), ("grade", 25)]);
+
+        let json = json.replacen(r#""samples": 25}]"#, r#""samples": 0}]"#, 1);
+        assert!(parse_handshake(&json).is_err());
     }
 
     // Test removed: rejects_invalid_message_type
@@ -0,0 +1,343 @@
+//! Checking recorded evaluator output against the protocol, without
+//! running anything
+//!
+//! Each line is read the way a live run would read it: the handshake first,
+//! then one OTLP metrics message per line. Findings point at the line they
+//! are about, so an evaluator's author can fix its output before PrEval
+//! ever runs it.
+
+use super::handshake::parse_handshake;
+use super::parser::parse_metrics_line;
+use super::protocol::ValidatedHandshake;
+use crate::state::metrics::{AttributeValue, DataPoint, Metric, SampleMetric, SummaryMetric};
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Earliest plausible timestamp, 2001-09-09; earlier ones are usually
+/// seconds or milliseconds where OTLP wants nanoseconds
+const EARLIEST_TIMESTAMP_NANOS: u64 = 1_000_000_000_000_000_000;
+
+/// How far ahead of the clock a timestamp may be before it is suspect
+const CLOCK_SKEW: Duration = Duration::from_secs(60 * 60);
+
+/// How bad a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// PrEval will read the output differently than intended
+    Warning,
+    /// PrEval will reject the line or fail the run
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// Something wrong with recorded output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Line the finding is about, counting from 1, or None for the output
+    /// as a whole
+    pub line: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Check every line of recorded evaluator output, in order
+pub fn lint<I>(lines: I) -> Vec<Finding>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64);
+    let mut linter = Linter {
+        findings: Vec::new(),
+        handshake: None,
+        latest_timestamp: now + CLOCK_SKEW.as_nanos() as u64,
+        samples: HashSet::new(),
+    };
+    for (index, line) in lines.into_iter().enumerate() {
+        let line = line.as_ref().trim();
+        if !line.is_empty() {
+            linter.check_line(index + 1, line);
+        }
+    }
+    linter.finish()
+}
+
+/// What the lines read so far have established
+struct Linter {
+    findings: Vec<Finding>,
+    handshake: Option<ValidatedHandshake>,
+    /// Timestamps after this are in the future
+    latest_timestamp: u64,
+    /// Every sample id reported so far
+    samples: HashSet<String>,
+}
+
+impl Linter {
+    fn report(&mut self, line: Option<usize>, severity: Severity, message: String) {
+        self.findings.push(Finding {
+            line,
+            severity,
+            message,
+        });
+    }
+
+    fn check_line(&mut self, number: usize, line: &str) {
+        let at = Some(number);
+        if self.handshake.is_none() {
+            match parse_handshake(line) {
+                Ok(handshake) => self.handshake = Some(handshake),
+                Err(_) if parse_metrics_line(line).is_ok() => self.report(
+                    at,
+                    Severity::Error,
+                    "metrics before the handshake are ignored".to_string(),
+                ),
+                Err(e) if is_handshake(line) => {
+                    self.report(at, Severity::Error, format!("{:#}", e))
+                }
+                Err(_) => self.report(
+                    at,
+                    Severity::Warning,
+                    "not a protocol message; ignored while waiting for the handshake".to_string(),
+                ),
+            }
+            return;
+        }
+
+        if is_handshake(line) {
+            self.report(
+                at,
+                Severity::Warning,
+                "another handshake, which PrEval only expects from a restarted evaluator"
+                    .to_string(),
+            );
+            return;
+        }
+        let metrics = match parse_metrics_line(line) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                self.report(at, Severity::Error, format!("{:#}", e));
+                return;
+            }
+        };
+
+        let declared: HashSet<String> = self
+            .handshake
+            .iter()
+            .flat_map(|handshake| &handshake.metrics_schema)
+            .map(|definition| definition.name.to_string())
+            .collect();
+        for metric in &metrics.metrics {
+            let (name, points) = data_points(metric);
+            if !declared.is_empty() && !declared.contains(name) {
+                self.report(
+                    at,
+                    Severity::Warning,
+                    format!("metric '{}' is not in the handshake's metrics_schema", name),
+                );
+            }
+            let is_sample = matches!(metric, Metric::Sample(_));
+            for point in points {
+                match point.sample_id {
+                    Some(id) => {
+                        self.samples.insert(id);
+                    }
+                    None if is_sample => self.report(
+                        at,
+                        Severity::Warning,
+                        format!("metric '{}' has a data point without a sample.id", name),
+                    ),
+                    None => {}
+                }
+                self.check_timestamps(at, name, point.start, point.time);
+            }
+        }
+    }
+
+    /// Flag timestamps no clock would have produced
+    fn check_timestamps(&mut self, line: Option<usize>, name: &str, start: Option<u64>, time: u64) {
+        if time < EARLIEST_TIMESTAMP_NANOS {
+            self.report(
+                line,
+                Severity::Warning,
+                format!(
+                    "metric '{}' has timestamp {}, which looks like seconds or milliseconds rather than nanoseconds",
+                    name, time
+                ),
+            );
+        } else if time > self.latest_timestamp {
+            self.report(
+                line,
+                Severity::Warning,
+                format!("metric '{}' has timestamp {} in the future", name, time),
+            );
+        }
+        if let Some(start) = start.filter(|&start| start > time) {
+            self.report(
+                line,
+                Severity::Warning,
+                format!(
+                    "metric '{}' starts at {}, after its timestamp {}",
+                    name, start, time
+                ),
+            );
+        }
+    }
+
+    /// Findings about the output as a whole, after the ones about lines
+    fn finish(mut self) -> Vec<Finding> {
+        match &self.handshake {
+            None => self.report(None, Severity::Error, "no handshake".to_string()),
+            Some(handshake) => {
+                let planned = handshake
+                    .execution_plan
+                    .as_ref()
+                    .map(|plan| plan.total_samples.into_inner() as usize);
+                if let Some(planned) = planned.filter(|&planned| planned != self.samples.len()) {
+                    self.report(
+                        None,
+                        Severity::Warning,
+                        format!(
+                            "the execution plan promises {} samples but {} were reported",
+                            planned,
+                            self.samples.len()
+                        ),
+                    );
+                }
+            }
+        }
+        self.findings
+    }
+}
+
+/// Whether `line` is meant as a handshake, valid or not
+fn is_handshake(line: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(line)
+        .is_ok_and(|message| message["type"] == "handshake")
+}
+
+/// What the linter looks at in a data point
+struct PointInfo {
+    start: Option<u64>,
+    time: u64,
+    sample_id: Option<String>,
+}
+
+/// Name of `metric` and what the linter looks at in each of its data points
+fn data_points(metric: &Metric) -> (&str, Vec<PointInfo>) {
+    fn info<V>(points: &[DataPoint<V>]) -> Vec<PointInfo> {
+        points
+            .iter()
+            .map(|point| PointInfo {
+                start: point.start_timestamp.map(|start| start.into_inner()),
+                time: point.timestamp.into_inner(),
+                sample_id: point
+                    .attributes
+                    .iter()
+                    .find(|(key, _)| key.as_ref() == "sample.id")
+                    .and_then(|(_, value)| match value {
+                        AttributeValue::StringValue(id) => Some(id.clone()),
+                        _ => None,
+                    }),
+            })
+            .collect()
+    }
+
+    match metric {
+        Metric::Sample(SampleMetric::Gauge {
+            name, data_points, ..
+        })
+        | Metric::Summary(SummaryMetric::Gauge {
+            name, data_points, ..
+        }) => (name.as_ref(), info(data_points)),
+        Metric::Sample(SampleMetric::Counter {
+            name, data_points, ..
+        })
+        | Metric::Summary(SummaryMetric::Counter {
+            name, data_points, ..
+        }) => (name.as_ref(), info(data_points)),
+        Metric::Sample(SampleMetric::Histogram {
+            name, data_points, ..
+        })
+        | Metric::Summary(SummaryMetric::Histogram {
+            name, data_points, ..
+        }) => (name.as_ref(), info(data_points)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HANDSHAKE: &str = r#"{"type": "handshake", "mode": "test_suite", "version": "1.0", "evaluator": {"name": "eval"}, "execution_plan": {"total_samples": 2}, "metrics_schema": [{"name": "accuracy"}]}"#;
+
+    /// Metrics line with one gauge data point
+    fn gauge(name: &str, sample_id: &str, time: &str) -> String {
+        format!(
+            r#"{{"resourceMetrics": [{{"scopeMetrics": [{{"metrics": [{{"name": "{}", "gauge": {{"dataPoints": [{{"timeUnixNano": "{}", "asDouble": 0.9, "attributes": [{{"key": "sample.id", "value": {{"stringValue": "{}"}}}}]}}]}}}}]}}]}}]}}"#,
+            name, time, sample_id
+        )
+    }
+
+    #[test]
+    fn clean_output_has_no_findings() {
+        let lines = [
+            HANDSHAKE.to_string(),
+            gauge("accuracy", "q-1", "1700000000000000000"),
+            String::new(),
+            gauge("accuracy", "q-2", "1700000000000000001"),
+        ];
+
+        assert_eq!(lint(&lines), Vec::new());
+    }
+
+    #[test]
+    fn findings_point_at_their_lines() {
+        let lines = [
+            gauge("accuracy", "q-0", "1700000000000000000"),
+            HANDSHAKE.to_string(),
+            gauge("latency", "q-1", "1700000000"),
+            "{not json".to_string(),
+        ];
+
+        let findings: Vec<_> = lint(&lines)
+            .into_iter()
+            .map(|finding| (finding.line, finding.severity))
+            .collect();
+        assert_eq!(
+            findings,
+            [
+                (Some(1), Severity::Error),
+                (Some(3), Severity::Warning),
+                (Some(3), Severity::Warning),
+                (Some(4), Severity::Error),
+                (None, Severity::Warning),
+            ]
+        );
+    }
+
+    #[test]
+    fn output_without_a_handshake_is_an_error() {
+        let findings = lint(["starting up"]);
+
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(
+            findings[1],
+            Finding {
+                line: None,
+                severity: Severity::Error,
+                message: "no handshake".to_string(),
+            }
+        );
+    }
+}
@@ -1,6 +1,15 @@
 /// Evaluator module for process management and protocol handling
+pub mod container;
 pub mod handshake;
+pub mod lint;
 pub(crate) mod otlp;
+pub mod output_queue;
+pub(crate) mod parse_worker;
 pub mod parser;
 pub mod process;
-pub(crate) mod protocol;
+pub mod protocol;
+pub mod remote;
+pub mod resources;
+pub mod source;
+pub mod stderr;
+pub mod supervisor;
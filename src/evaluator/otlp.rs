@@ -55,6 +55,8 @@ pub(super) struct Resource {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct GaugeDataPoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time_unix_nano: Option<String>,
     pub time_unix_nano: String,
     pub as_double: f64,
     #[serde(default)]
@@ -72,6 +74,8 @@ pub(super) struct Gauge {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct SumDataPoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time_unix_nano: Option<String>,
     pub time_unix_nano: String,
     pub as_double: f64,
     #[serde(default)]
@@ -93,6 +97,8 @@ pub(super) struct Sum {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct HistogramDataPoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time_unix_nano: Option<String>,
     pub time_unix_nano: String,
     #[serde(default)]
     pub attributes: Vec<Attribute>,
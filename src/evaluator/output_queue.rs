@@ -0,0 +1,279 @@
+//! Bounded buffering of evaluator output between the reader and the app
+//!
+//! The reader pushes lines as fast as the evaluator writes them and a
+//! forwarder hands them to the app. When the app falls behind, the
+//! [`OverflowPolicy`] decides whether the reader waits or old lines are
+//! discarded.
+
+use crate::evaluator::process::EvaluatorMessage;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify};
+
+/// What to do with new output when the buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Stop reading until there is room, pushing back on the evaluator
+    #[default]
+    Block,
+    /// Discard the oldest buffered line to make room, counting the loss
+    DropOldest,
+}
+
+impl std::str::FromStr for OverflowPolicy {
+    type Err = OverflowPolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "block" => Ok(Self::Block),
+            "drop-oldest" | "drop_oldest" => Ok(Self::DropOldest),
+            other => Err(OverflowPolicyError(other.to_string())),
+        }
+    }
+}
+
+/// Error for unrecognized overflow policy names
+#[derive(Debug, thiserror::Error)]
+#[error("unknown overflow policy '{0}' (expected 'block' or 'drop-oldest')")]
+pub struct OverflowPolicyError(String);
+
+/// Size and overflow behavior of the evaluator output buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputBuffer {
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for OutputBuffer {
+    fn default() -> Self {
+        Self {
+            capacity: 100,
+            overflow: OverflowPolicy::default(),
+        }
+    }
+}
+
+/// Point-in-time view of the output buffer, for the diagnostics pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutputDiagnostics {
+    /// Maximum number of buffered lines
+    pub capacity: usize,
+    /// Lines currently waiting to be delivered
+    pub depth: usize,
+    /// Most lines ever waiting at once
+    pub peak_depth: usize,
+    /// Lines discarded because the buffer was full
+    pub dropped: u64,
+    /// Time the most recently delivered line spent buffered
+    pub last_latency: Duration,
+    /// Longest time any line spent buffered
+    pub max_latency: Duration,
+}
+
+/// Buffer of output lines shared by a reader and a forwarder
+#[derive(Debug)]
+pub(crate) struct OutputQueue {
+    buffer: OutputBuffer,
+    state: Mutex<QueueState>,
+    readable: Notify,
+    writable: Notify,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    lines: VecDeque<(Instant, String)>,
+    closed: bool,
+    diagnostics: OutputDiagnostics,
+}
+
+impl OutputQueue {
+    /// Create an empty queue
+    pub(crate) fn new(buffer: OutputBuffer) -> Arc<Self> {
+        let buffer = OutputBuffer {
+            capacity: buffer.capacity.max(1),
+            ..buffer
+        };
+        Arc::new(Self {
+            buffer,
+            state: Mutex::new(QueueState {
+                diagnostics: OutputDiagnostics {
+                    capacity: buffer.capacity,
+                    ..OutputDiagnostics::default()
+                },
+                ..QueueState::default()
+            }),
+            readable: Notify::new(),
+            writable: Notify::new(),
+        })
+    }
+
+    /// Buffer a line, applying the overflow policy when full
+    ///
+    /// Returns false once the queue is closed and nobody will read the line.
+    pub(crate) async fn push(&self, mut line: String) -> bool {
+        loop {
+            // Hands the line back when it has to wait for room
+            let rejected = {
+                let mut state = self.lock();
+                if state.closed {
+                    return false;
+                }
+                let full = state.lines.len() >= self.buffer.capacity;
+                match self.buffer.overflow {
+                    OverflowPolicy::Block if full => Some(line),
+                    _ => {
+                        if full {
+                            state.lines.pop_front();
+                            state.diagnostics.dropped += 1;
+                        }
+                        state.lines.push_back((Instant::now(), line));
+                        let depth = state.lines.len();
+                        state.diagnostics.peak_depth = state.diagnostics.peak_depth.max(depth);
+                        None
+                    }
+                }
+            };
+
+            match rejected {
+                None => {
+                    self.readable.notify_one();
+                    return true;
+                }
+                Some(waiting) => {
+                    line = waiting;
+                    self.writable.notified().await;
+                }
+            }
+        }
+    }
+
+    /// Take the oldest line, waiting for one; `None` once closed and empty
+    async fn pop(&self) -> Option<(Instant, String)> {
+        loop {
+            {
+                let mut state = self.lock();
+                if let Some(entry) = state.lines.pop_front() {
+                    drop(state);
+                    self.writable.notify_one();
+                    return Some(entry);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.readable.notified().await;
+        }
+    }
+
+    /// Stop accepting lines; buffered lines are still delivered
+    pub(crate) fn close(&self) {
+        self.lock().closed = true;
+        self.readable.notify_one();
+        self.writable.notify_one();
+    }
+
+    /// Deliver buffered lines to the app until the queue is closed and empty
+    pub(crate) async fn forward(&self, message_tx: mpsc::Sender<EvaluatorMessage>) {
+        while let Some((queued_at, line)) = self.pop().await {
+            if message_tx
+                .send(EvaluatorMessage::Output(line))
+                .await
+                .is_err()
+            {
+                // Nobody is listening, so stop the reader too
+                self.close();
+                break;
+            }
+
+            let latency = queued_at.elapsed();
+            let mut state = self.lock();
+            state.diagnostics.last_latency = latency;
+            state.diagnostics.max_latency = state.diagnostics.max_latency.max(latency);
+        }
+    }
+
+    /// Current buffer statistics
+    pub(crate) fn diagnostics(&self) -> OutputDiagnostics {
+        let state = self.lock();
+        OutputDiagnostics {
+            depth: state.lines.len(),
+            ..state.diagnostics
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        // The state stays consistent even if a holder panicked
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(capacity: usize, overflow: OverflowPolicy) -> OutputBuffer {
+        OutputBuffer { capacity, overflow }
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_discards_and_counts() {
+        let queue = OutputQueue::new(buffer(2, OverflowPolicy::DropOldest));
+
+        for line in ["a", "b", "c"] {
+            assert!(queue.push(line.to_string()).await);
+        }
+
+        assert_eq!(
+            queue.pop().await.map(|(_, line)| line),
+            Some("b".to_string())
+        );
+        let diagnostics = queue.diagnostics();
+        assert_eq!(diagnostics.dropped, 1);
+        assert_eq!(diagnostics.depth, 1);
+        assert_eq!(diagnostics.peak_depth, 2);
+    }
+
+    #[tokio::test]
+    async fn block_waits_for_room() {
+        let queue = OutputQueue::new(buffer(1, OverflowPolicy::Block));
+        assert!(queue.push("a".to_string()).await);
+
+        let blocked = tokio::time::timeout(Duration::from_millis(50), queue.push("b".to_string()));
+        assert!(blocked.await.is_err(), "push should wait while full");
+
+        queue.pop().await;
+        assert!(queue.push("b".to_string()).await);
+        assert_eq!(queue.diagnostics().dropped, 0);
+    }
+
+    #[tokio::test]
+    async fn forward_delivers_everything_then_stops() {
+        let queue = OutputQueue::new(OutputBuffer::default());
+        let (tx, mut rx) = mpsc::channel(10);
+        queue.push("a".to_string()).await;
+        queue.push("b".to_string()).await;
+        queue.close();
+
+        queue.forward(tx).await;
+
+        let mut lines = Vec::new();
+        while let Some(EvaluatorMessage::Output(line)) = rx.recv().await {
+            lines.push(line);
+        }
+        assert_eq!(lines, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn parses_overflow_policy() {
+        assert_eq!(
+            "block".parse::<OverflowPolicy>().unwrap(),
+            OverflowPolicy::Block
+        );
+        assert_eq!(
+            "drop-oldest".parse::<OverflowPolicy>().unwrap(),
+            OverflowPolicy::DropOldest
+        );
+        assert!("newest".parse::<OverflowPolicy>().is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! Parsing evaluator output off the main loop
+//!
+//! OTLP JSON is the most expensive thing done per line. Parsing it on a task
+//! of its own, between the source and the main loop, leaves the loop free to
+//! draw and handle keys while a fast evaluator floods it with metrics.
+
+use super::parser::parse_metrics_line;
+use super::process::EvaluatorMessage;
+use crate::state::metrics::MetricData;
+use crate::state::perf::PerfRecorder;
+use anyhow::Result;
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+/// Messages parsed ahead of the main loop, at most this many at a time
+const PARSED_BACKLOG: usize = 100;
+
+/// A message from the evaluator, with its stdout line already parsed
+#[derive(Debug)]
+pub(crate) struct ParsedMessage {
+    pub(crate) message: EvaluatorMessage,
+    /// The stdout line parsed as OTLP metrics; None for other messages
+    pub(crate) metrics: Option<Result<MetricData>>,
+}
+
+/// Parse every stdout line arriving on `raw` on a task of its own, passing
+/// messages on in the order they came and recording parse times in `perf`
+///
+/// The returned channel closes once `raw` does, so a vanished evaluator is
+/// still noticed.
+pub(crate) fn spawn(
+    mut raw: mpsc::Receiver<EvaluatorMessage>,
+    perf: PerfRecorder,
+) -> mpsc::Receiver<ParsedMessage> {
+    let (parsed_tx, parsed_rx) = mpsc::channel(PARSED_BACKLOG);
+    tokio::spawn(async move {
+        while let Some(message) = raw.recv().await {
+            let metrics = match &message {
+                EvaluatorMessage::Output(line) => {
+                    let started = Instant::now();
+                    let metrics = parse_metrics_line(line);
+                    perf.record_parse(started.elapsed());
+                    Some(metrics)
+                }
+                _ => None,
+            };
+            if parsed_tx
+                .send(ParsedMessage { message, metrics })
+                .await
+                .is_err()
+            {
+                // The run was restarted or is over
+                return;
+            }
+        }
+    });
+    parsed_rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::process::ExitStatus;
+
+    #[tokio::test]
+    async fn lines_are_parsed_in_order() {
+        let (raw_tx, raw_rx) = mpsc::channel(10);
+        let perf = PerfRecorder::default();
+        let mut parsed = spawn(raw_rx, perf.clone());
+
+        raw_tx
+            .send(EvaluatorMessage::Output("not json".to_string()))
+            .await
+            .unwrap();
+        raw_tx
+            .send(EvaluatorMessage::Output(
+                r#"{"resourceMetrics": []}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        raw_tx
+            .send(EvaluatorMessage::Exited(ExitStatus::new(true, Some(0))))
+            .await
+            .unwrap();
+        drop(raw_tx);
+
+        let first = parsed.recv().await.unwrap();
+        assert!(matches!(first.metrics, Some(Err(_))));
+        let second = parsed.recv().await.unwrap();
+        assert!(second.metrics.unwrap().unwrap().metrics.is_empty());
+        let last = parsed.recv().await.unwrap();
+        assert!(matches!(last.message, EvaluatorMessage::Exited(_)));
+        assert!(last.metrics.is_none());
+        assert!(parsed.recv().await.is_none());
+        assert_eq!(perf.stats().lines_parsed, 2);
+    }
+}
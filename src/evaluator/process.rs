@@ -1,27 +1,65 @@
+use crate::evaluator::container::ContainerSpec;
+use crate::evaluator::output_queue::{OutputBuffer, OutputDiagnostics, OutputQueue};
+use crate::evaluator::protocol::ControlMessage;
+use crate::evaluator::remote::SshSpec;
 use crate::state::types::EvaluatorCommand;
 use anyhow::{Context, Result};
+use std::ffi::OsString;
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::{Child, Command};
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+mod pty;
+
+/// How long to wait for buffered stdout after the process has exited
+const STDOUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
 
 /// Message from evaluator process
 #[derive(Debug)]
 pub enum EvaluatorMessage {
     /// Output line from stdout
     Output(String),
+    /// Diagnostic line from stderr
+    Stderr(String),
     /// Process exited
     Exited(ExitStatus),
 }
 
+/// Observer of evaluator output, told of each line as soon as it is read
+///
+/// Lines reach the tap before the output buffer, so it sees every line
+/// with the time it was written, including ones the buffer later drops.
+pub trait OutputTap: Send + Sync {
+    /// A line read from the evaluator's stdout
+    fn stdout(&self, line: &str);
+
+    /// A line read from the evaluator's stderr
+    fn stderr(&self, line: &str);
+}
+
 /// Exit status of evaluator
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExitStatus {
     success: bool,
     code: Option<i32>,
+    /// Signal that ended the process, if one did
+    signal: Option<String>,
 }
 
 impl ExitStatus {
+    /// Status of an exit PrEval learned about indirectly, e.g. from a transcript
+    pub fn new(success: bool, code: Option<i32>) -> Self {
+        Self {
+            success,
+            code,
+            signal: None,
+        }
+    }
+
     /// Whether the process exited successfully
     pub fn success(&self) -> bool {
         self.success
@@ -31,34 +69,222 @@ impl ExitStatus {
     pub fn code(&self) -> Option<i32> {
         self.code
     }
+
+    /// Name of the signal that ended the process, if one did
+    pub fn signal(&self) -> Option<&str> {
+        self.signal.as_deref()
+    }
+}
+
+impl From<portable_pty::ExitStatus> for ExitStatus {
+    fn from(status: portable_pty::ExitStatus) -> Self {
+        Self {
+            success: status.success(),
+            code: status.signal().is_none().then(|| status.exit_code() as i32),
+            signal: status.signal().map(str::to_string),
+        }
+    }
+}
+
+impl From<std::process::ExitStatus> for ExitStatus {
+    fn from(status: std::process::ExitStatus) -> Self {
+        Self {
+            success: status.success(),
+            code: status.code(),
+            signal: signal_name(&status),
+        }
+    }
+}
+
+/// Name of the signal that ended a process, e.g. `SIGKILL`
+#[cfg(unix)]
+fn signal_name(status: &std::process::ExitStatus) -> Option<String> {
+    use std::os::unix::process::ExitStatusExt;
+    status
+        .signal()
+        .map(|signal| match nix::sys::signal::Signal::try_from(signal) {
+            Ok(signal) => signal.as_str().to_string(),
+            Err(_) => format!("signal {}", signal),
+        })
+}
+
+#[cfg(not(unix))]
+fn signal_name(_status: &std::process::ExitStatus) -> Option<String> {
+    None
+}
+
+/// Signal used to ask an evaluator to stop before it is force-killed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShutdownSignal {
+    /// SIGINT, what an evaluator would see from Ctrl+C in a terminal
+    Interrupt,
+    /// SIGTERM, the conventional polite termination request
+    #[default]
+    Terminate,
+}
+
+impl std::str::FromStr for ShutdownSignal {
+    type Err = ShutdownSignalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "int" | "sigint" | "interrupt" => Ok(Self::Interrupt),
+            "term" | "sigterm" | "terminate" => Ok(Self::Terminate),
+            other => Err(ShutdownSignalError(other.to_string())),
+        }
+    }
+}
+
+/// Error for unrecognized shutdown signal names
+#[derive(Debug, thiserror::Error)]
+#[error("unknown shutdown signal '{0}' (expected 'term' or 'int')")]
+pub struct ShutdownSignalError(String);
+
+/// How to stop an evaluator that is still running
+///
+/// An evaluator that was sent a `cancel` control message is first given
+/// `grace_period` to exit on its own. Then it is sent `signal` and given
+/// `grace_period` again to flush partial results and clean up before it is
+/// force-killed. Windows has no such signal, so there an evaluator that
+/// does not act on `cancel` is killed straight away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownPolicy {
+    pub signal: ShutdownSignal,
+    pub grace_period: Duration,
+}
+
+impl Default for ShutdownPolicy {
+    fn default() -> Self {
+        Self {
+            signal: ShutdownSignal::default(),
+            grace_period: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Request sent to the task that owns the child process
+#[derive(Debug)]
+enum ProcessControl {
+    /// Kill the process and report whether that succeeded
+    Kill(oneshot::Sender<std::io::Result<()>>),
+    /// Ask the process to exit, killing it if it outlives the grace period
+    Shutdown {
+        policy: ShutdownPolicy,
+        /// Whether the process was sent a `cancel` control message
+        cancelled: bool,
+        reply_tx: oneshot::Sender<std::io::Result<()>>,
+    },
 }
 
 /// Evaluator process handle with RAII cleanup
+///
+/// The child is owned by a monitor task; dropping this handle closes the
+/// control channel, which makes the monitor kill the process.
 pub struct EvaluatorProcess {
-    child: Child,
+    control_tx: mpsc::Sender<ProcessControl>,
+    pid: Option<u32>,
+    queue: Arc<OutputQueue>,
+    /// The evaluator's stdin, for protocol control messages
+    control_input: Option<ChildStdin>,
+    /// Process group that job control signals reach, if stopping it stops
+    /// the evaluator
+    process_group: Option<u32>,
+    /// Container the evaluator runs in, frozen instead of the process group
+    container: Option<(ContainerSpec, String)>,
+    /// Whether the process group is stopped by SIGSTOP, or the container
+    /// is frozen
+    suspended: bool,
+    /// Whether the evaluator was sent a `cancel` control message
+    cancelled: bool,
 }
 
 impl EvaluatorProcess {
     /// Spawn a new evaluator process
     pub async fn spawn(
         command: &EvaluatorCommand,
+        output: OutputBuffer,
+        tap: Option<Arc<dyn OutputTap>>,
         message_tx: mpsc::Sender<EvaluatorMessage>,
     ) -> Result<Self> {
-        // Parse command into program and args
-        let parts: Vec<&str> = command.as_ref().split_whitespace().collect();
-        if parts.is_empty() {
-            anyhow::bail!("Empty evaluator command");
-        }
+        Self::spawn_command(
+            platform_command(command)?,
+            command,
+            None,
+            output,
+            tap,
+            message_tx,
+        )
+    }
+
+    /// Spawn the evaluator inside a container, under a pseudo-terminal if `pty`
+    pub async fn spawn_in_container(
+        container: &ContainerSpec,
+        command: &EvaluatorCommand,
+        pty: bool,
+        output: OutputBuffer,
+        tap: Option<Arc<dyn OutputTap>>,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+    ) -> Result<Self> {
+        let cwd = std::env::current_dir().context("Failed to get working directory")?;
+        let name = container.next_container_name();
+        let argv = container.argv(command, &name, pty, &cwd);
+        let cleanup = Some(container.remove_argv(&name));
+        let frozen = (container.clone(), name);
+
+        let mut process = if pty {
+            let mut pty_command = portable_pty::CommandBuilder::from_argv(argv);
+            pty_command.cwd(cwd);
+            Self::spawn_pty_command(pty_command, command, cleanup, output, tap, message_tx)?
+        } else {
+            let argv_command = command_from_argv(&argv)?;
+            Self::spawn_command(argv_command, command, cleanup, output, tap, message_tx)?
+        };
+        // Stopping the runtime's client would leave the container running
+        process.process_group = None;
+        process.container = Some(frozen);
+        Ok(process)
+    }
+
+    /// Spawn the evaluator on another machine over SSH, under a
+    /// pseudo-terminal there if `pty`
+    pub async fn spawn_over_ssh(
+        ssh: &SshSpec,
+        command: &EvaluatorCommand,
+        pty: bool,
+        output: OutputBuffer,
+        tap: Option<Arc<dyn OutputTap>>,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+    ) -> Result<Self> {
+        let pid_file = ssh.next_pid_file();
+        let argv = ssh.argv(command, &pid_file, pty);
+        let cleanup = Some(ssh.cleanup_argv(&pid_file));
 
-        let program = parts[0];
-        let args = &parts[1..];
+        let mut process = if pty {
+            let pty_command = portable_pty::CommandBuilder::from_argv(argv);
+            Self::spawn_pty_command(pty_command, command, cleanup, output, tap, message_tx)?
+        } else {
+            let argv_command = command_from_argv(&argv)?;
+            Self::spawn_command(argv_command, command, cleanup, output, tap, message_tx)?
+        };
+        // Stopping the local ssh client would leave the remote evaluator running
+        process.process_group = None;
+        Ok(process)
+    }
 
+    /// Spawn a prepared command, running `cleanup` after it exits
+    fn spawn_command(
+        mut evaluator: Command,
+        command: &EvaluatorCommand,
+        cleanup: Option<Vec<OsString>>,
+        output: OutputBuffer,
+        tap: Option<Arc<dyn OutputTap>>,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+    ) -> Result<Self> {
         // Spawn the process
-        let mut child = Command::new(program)
-            .args(args)
+        let mut child = evaluator
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped()) // Capture stderr to filter out cargo messages
-            .stdin(Stdio::null())
+            .stderr(Stdio::piped()) // Captured for the stderr pane
+            .stdin(Stdio::piped()) // Carries control messages
             .kill_on_drop(true) // Ensure cleanup
             .spawn()
             .with_context(|| format!("Failed to spawn evaluator: {}", command))?;
@@ -66,41 +292,41 @@ impl EvaluatorProcess {
         // Get stdout and stderr handles
         let stdout = child.stdout.take().context("Failed to capture stdout")?;
         let stderr = child.stderr.take().context("Failed to capture stderr")?;
+        let control_input = child.stdin.take();
 
-        // Spawn task to read stdout
-        let tx = message_tx.clone();
+        // Spawn task to read stdout into the bounded output queue
+        let queue = OutputQueue::new(output);
+        let reader_queue = queue.clone();
+        let stdout_tap = tap.clone();
         tokio::spawn(async move {
             let reader = BufReader::new(stdout);
             let mut lines = reader.lines();
 
             while let Ok(Some(line)) = lines.next_line().await {
-                if tx.send(EvaluatorMessage::Output(line)).await.is_err() {
+                if let Some(tap) = &stdout_tap {
+                    tap.stdout(&line);
+                }
+                if !reader_queue.push(line).await {
                     // Receiver dropped, stop reading
                     break;
                 }
             }
+            reader_queue.close();
         });
+        let stdout_task = spawn_forwarder(&queue, message_tx.clone());
 
-        // Spawn task to read stderr and filter cargo messages
+        // Spawn task to read stderr; it is kept apart from the protocol stream
         let tx_stderr = message_tx.clone();
         tokio::spawn(async move {
             let reader = BufReader::new(stderr);
             let mut lines = reader.lines();
 
             while let Ok(Some(line)) = lines.next_line().await {
-                // Filter out cargo build messages that pollute the terminal
-                if line.trim().starts_with("Compiling")
-                    || line.trim().starts_with("Finished")
-                    || line.trim().starts_with("Running")
-                    || line.trim().contains("target/debug/deps/")
-                    || line.trim().is_empty()
-                {
-                    continue; // Skip cargo build output
+                if let Some(tap) = &tap {
+                    tap.stderr(&line);
                 }
-
-                // Send actual stderr as output (for real errors)
                 if tx_stderr
-                    .send(EvaluatorMessage::Output(format!("stderr: {}", line)))
+                    .send(EvaluatorMessage::Stderr(line))
                     .await
                     .is_err()
                 {
@@ -109,62 +335,453 @@ impl EvaluatorProcess {
             }
         });
 
-        // Spawn task to monitor process exit
-        let child_id = child.id();
-        let tx_exit = message_tx;
+        // Spawn task that owns the child and reports its real exit status
+        let pid = child.id();
+        let (control_tx, control_rx) = mpsc::channel(1);
         tokio::spawn(async move {
-            // Monitor using the same child process we spawned
-            // We need to get a handle to wait on the process
-            if let Some(id) = child_id {
-                // Wait a bit for the process to potentially exit
-                loop {
-                    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-
-                    // Check if process still exists by trying to send signal 0
-                    match std::process::Command::new("kill")
-                        .args(["-0", &id.to_string()])
-                        .output()
-                    {
-                        Ok(output) if !output.status.success() => {
-                            // Process no longer exists
-                            let exit_status = ExitStatus {
-                                success: false, // We don't know the actual exit code
-                                code: None,
-                            };
-                            let _ = tx_exit.send(EvaluatorMessage::Exited(exit_status)).await;
-                            break;
-                        }
-                        _ => {
-                            // Process still running or we couldn't check
-                        }
-                    }
-                }
+            monitor_exit(child, control_rx, stdout_task, message_tx).await;
+            run_cleanup(cleanup).await;
+        });
+
+        Ok(Self {
+            control_tx,
+            pid,
+            queue,
+            control_input,
+            process_group: pid,
+            container: None,
+            suspended: false,
+            cancelled: false,
+        })
+    }
+
+    /// Follow an evaluator that PrEval does not own, e.g. one run by a
+    /// detached daemon
+    ///
+    /// `feed` delivers the evaluator's messages. Killing or shutting down
+    /// the handle only stops following; the evaluator keeps running.
+    pub fn follow<F>(feed: F) -> Self
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let feed_task = tokio::spawn(feed);
+        let (control_tx, mut control_rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            let reply_tx = match control_rx.recv().await {
+                Some(ProcessControl::Kill(reply_tx)) => Some(reply_tx),
+                Some(ProcessControl::Shutdown { reply_tx, .. }) => Some(reply_tx),
+                None => None,
+            };
+            feed_task.abort();
+            if let Some(reply_tx) = reply_tx {
+                let _ = reply_tx.send(Ok(()));
             }
         });
 
-        Ok(Self { child })
+        Self {
+            control_tx,
+            pid: None,
+            queue: OutputQueue::new(OutputBuffer::default()),
+            control_input: None,
+            process_group: None,
+            container: None,
+            suspended: false,
+            cancelled: false,
+        }
+    }
+
+    /// Operating system process id of the evaluator
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Current state of the stdout buffer
+    pub fn output_diagnostics(&self) -> OutputDiagnostics {
+        self.queue.diagnostics()
+    }
+
+    /// Whether control messages can be sent to the evaluator
+    pub fn has_control_input(&self) -> bool {
+        self.control_input.is_some()
+    }
+
+    /// Write a control message to the evaluator's stdin
+    pub async fn send_control(&mut self, message: ControlMessage) -> Result<()> {
+        let input = self
+            .control_input
+            .as_mut()
+            .context("Evaluator does not accept control messages")?;
+        let mut line =
+            serde_json::to_string(&message).context("Failed to encode control message")?;
+        line.push('\n');
+        input
+            .write_all(line.as_bytes())
+            .await
+            .context("Failed to send control message to evaluator")?;
+        input
+            .flush()
+            .await
+            .context("Failed to send control message to evaluator")?;
+        self.cancelled |= message == ControlMessage::Cancel;
+        Ok(())
+    }
+
+    /// Close the evaluator's stdin
+    ///
+    /// For evaluators that will never read control messages, so that any
+    /// that read stdin see the end of input instead of waiting forever.
+    pub fn close_control_input(&mut self) {
+        self.control_input = None;
+    }
+
+    /// Stop the evaluator and everything it started until `resume`
+    ///
+    /// A containerized evaluator has its container frozen by the engine.
+    pub async fn suspend(&mut self) -> Result<()> {
+        if !self.suspended {
+            if !self.set_suspended(true).await {
+                anyhow::bail!("Evaluator cannot be paused: it does not support pause messages and cannot be stopped by signal");
+            }
+            self.suspended = true;
+        }
+        Ok(())
+    }
+
+    /// Continue an evaluator stopped by `suspend`
+    pub async fn resume(&mut self) -> Result<()> {
+        if self.suspended {
+            if !self.set_suspended(false).await {
+                anyhow::bail!("Failed to resume evaluator");
+            }
+            self.suspended = false;
+        }
+        Ok(())
+    }
+
+    /// Stop or continue the evaluator, returning whether that worked
+    async fn set_suspended(&self, suspend: bool) -> bool {
+        match &self.container {
+            Some((container, name)) => {
+                run_engine_command(&container.pause_argv(name, suspend)).await
+            }
+            None => suspend_process_group(self.process_group, suspend),
+        }
     }
 
     /// Kill the evaluator process
     pub async fn kill(&mut self) -> Result<()> {
-        self.child
-            .kill()
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.request(ProcessControl::Kill(reply_tx), reply_rx)
             .await
-            .context("Failed to kill evaluator")?;
-        Ok(())
+            .context("Failed to kill evaluator")
+    }
+
+    /// Stop the evaluator gracefully, force-killing it after the grace period
+    ///
+    /// Resolves once the process has exited.
+    pub async fn shutdown(&mut self, policy: ShutdownPolicy) -> Result<()> {
+        // A frozen container could not act on the request
+        if self.container.is_some() {
+            self.resume().await?;
+        }
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let shutdown = ProcessControl::Shutdown {
+            policy,
+            cancelled: self.cancelled,
+            reply_tx,
+        };
+        self.request(shutdown, reply_rx)
+            .await
+            .context("Failed to shut down evaluator")
+    }
+
+    /// Send a control request to the monitor task and wait for its reply
+    async fn request(
+        &mut self,
+        control: ProcessControl,
+        reply_rx: oneshot::Receiver<std::io::Result<()>>,
+    ) -> std::io::Result<()> {
+        if self.control_tx.send(control).await.is_err() {
+            // Monitor already finished, so the process has exited
+            return Ok(());
+        }
+
+        // A dropped reply means the monitor finished before handling it
+        reply_rx.await.unwrap_or(Ok(()))
+    }
+}
+
+/// Start delivering queued stdout lines to the app
+fn spawn_forwarder(
+    queue: &Arc<OutputQueue>,
+    message_tx: mpsc::Sender<EvaluatorMessage>,
+) -> JoinHandle<()> {
+    let queue = queue.clone();
+    tokio::spawn(async move { queue.forward(message_tx).await })
+}
+
+/// Build the command used to launch an evaluator on this platform
+///
+/// On Windows the command line is handed to `cmd.exe /C` so that batch files,
+/// PATH lookup, and quoting behave as they do in a console.
+#[cfg(windows)]
+fn platform_command(command: &EvaluatorCommand) -> Result<Command> {
+    let mut cmd = Command::new("cmd.exe");
+    cmd.arg("/C").arg(command.as_ref());
+    Ok(cmd)
+}
+
+/// Build the command used to launch an evaluator on this platform
+#[cfg(not(windows))]
+fn platform_command(command: &EvaluatorCommand) -> Result<Command> {
+    // Parse command into program and args
+    let parts: Vec<OsString> = command
+        .as_ref()
+        .split_whitespace()
+        .map(OsString::from)
+        .collect();
+    command_from_argv(&parts)
+}
+
+/// Build a command from a program and its arguments
+fn command_from_argv(argv: &[OsString]) -> Result<Command> {
+    let Some((program, args)) = argv.split_first() else {
+        anyhow::bail!("Empty evaluator command");
+    };
+
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+
+    // Run in a dedicated process group so shutdown signals reach everything
+    // the evaluator starts (e.g. the binary behind `cargo run`), and a Ctrl+C
+    // in the terminal is handled by PrEval instead of hitting the evaluator
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    Ok(cmd)
+}
+
+/// Run a best-effort cleanup command once the evaluator has exited
+async fn run_cleanup(cleanup: Option<Vec<OsString>>) {
+    let Some((program, args)) = cleanup.as_deref().and_then(<[OsString]>::split_first) else {
+        return;
+    };
+
+    let status = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+    if let Err(e) = status {
+        tracing::warn!("Failed to clean up after evaluator: {}", e);
+    }
+}
+
+/// Run a container engine command, returning whether it succeeded
+async fn run_engine_command(argv: &[OsString]) -> bool {
+    let Some((program, args)) = argv.split_first() else {
+        return false;
+    };
+
+    let status = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+    match status {
+        Ok(status) => status.success(),
+        Err(e) => {
+            tracing::warn!("Failed to run {:?}: {}", program, e);
+            false
+        }
     }
 }
 
-impl Drop for EvaluatorProcess {
-    fn drop(&mut self) {
-        // Try to kill the process if it's still running
-        // This is best-effort since we're in Drop
-        if let Ok(Some(_)) = self.child.try_wait() {
-            // Process already exited
-            return;
+/// Kill the evaluator along with any processes it started
+///
+/// Evaluators run under `cmd.exe` on Windows, so killing only the direct
+/// child would orphan the real evaluator; `taskkill /T` takes the whole tree.
+#[cfg(windows)]
+async fn kill_process_tree(child: &mut Child) -> std::io::Result<()> {
+    if let Some(pid) = child.id() {
+        let status = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await;
+        if matches!(status, Ok(status) if status.success()) {
+            return Ok(());
         }
+    }
+    child.kill().await
+}
+
+/// Kill an evaluator's process tree synchronously
+///
+/// For contexts where the async runtime cannot be relied on, such as a
+/// panic hook. Errors are ignored because there is nothing left to do.
+#[cfg(unix)]
+pub fn kill_process_tree_blocking(pid: u32) {
+    use nix::sys::signal::{killpg, Signal};
+    use nix::unistd::Pid;
+
+    let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL);
+}
+
+/// Kill an evaluator's process tree synchronously
+#[cfg(windows)]
+pub fn kill_process_tree_blocking(pid: u32) {
+    let _ = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Kill the evaluator's process group
+#[cfg(unix)]
+async fn kill_process_tree(child: &mut Child) -> std::io::Result<()> {
+    use nix::sys::signal::Signal;
+
+    signal_process_group(child.id(), Signal::SIGKILL);
+    child.kill().await
+}
+
+/// Send a signal to every process in the evaluator's process group
+#[cfg(unix)]
+fn signal_process_group(pid: Option<u32>, signal: nix::sys::signal::Signal) -> bool {
+    use nix::sys::signal::killpg;
+    use nix::unistd::Pid;
+
+    match pid {
+        Some(pid) => killpg(Pid::from_raw(pid as i32), signal).is_ok(),
+        None => false,
+    }
+}
+
+/// Stop (SIGSTOP) or continue (SIGCONT) the evaluator's process group,
+/// returning whether the signal was sent
+#[cfg(unix)]
+fn suspend_process_group(pid: Option<u32>, suspend: bool) -> bool {
+    use nix::sys::signal::Signal;
+
+    let signal = if suspend {
+        Signal::SIGSTOP
+    } else {
+        Signal::SIGCONT
+    };
+    signal_process_group(pid, signal)
+}
+
+/// Stop or continue the evaluator's process group, returning whether the
+/// signal was sent
+#[cfg(not(unix))]
+fn suspend_process_group(_pid: Option<u32>, _suspend: bool) -> bool {
+    // Windows has no job control signals
+    false
+}
 
-        // Try to kill it
-        let _ = self.child.start_kill();
+/// Politely ask the evaluator to exit, returning whether the request was sent
+#[cfg(unix)]
+fn request_termination(pid: Option<u32>, signal: ShutdownSignal) -> bool {
+    use nix::sys::signal::Signal;
+
+    let signal = match signal {
+        ShutdownSignal::Interrupt => Signal::SIGINT,
+        ShutdownSignal::Terminate => Signal::SIGTERM,
+    };
+    let sent = signal_process_group(pid, signal);
+    // A paused evaluator cannot act on the request until it is continued
+    signal_process_group(pid, Signal::SIGCONT);
+    sent
+}
+
+/// Politely ask the evaluator to exit, returning whether the request was sent
+#[cfg(not(unix))]
+fn request_termination(_pid: Option<u32>, _signal: ShutdownSignal) -> bool {
+    // Console processes have no portable equivalent of SIGTERM
+    false
+}
+
+/// Ask the evaluator to exit, then kill it if the grace period runs out
+///
+/// A `cancelled` evaluator already has the request, so it gets a grace
+/// period of its own before it is signalled.
+async fn graceful_shutdown(
+    child: &mut Child,
+    policy: ShutdownPolicy,
+    cancelled: bool,
+) -> std::io::Result<()> {
+    if cancelled
+        && tokio::time::timeout(policy.grace_period, child.wait())
+            .await
+            .is_ok()
+    {
+        return Ok(());
+    }
+
+    if request_termination(child.id(), policy.signal) {
+        if tokio::time::timeout(policy.grace_period, child.wait())
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+        tracing::warn!(
+            "Evaluator did not exit within {:?} grace period, killing it",
+            policy.grace_period
+        );
     }
+
+    kill_process_tree(child).await
+}
+
+/// Wait for the child to exit, or kill it on request, then report how it ended
+async fn monitor_exit(
+    mut child: Child,
+    mut control_rx: mpsc::Receiver<ProcessControl>,
+    stdout_task: JoinHandle<()>,
+    message_tx: mpsc::Sender<EvaluatorMessage>,
+) {
+    let mut handle_open = true;
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status,
+            control = control_rx.recv(), if handle_open => match control {
+                Some(ProcessControl::Kill(reply_tx)) => {
+                    let _ = reply_tx.send(kill_process_tree(&mut child).await);
+                }
+                Some(ProcessControl::Shutdown {
+                    policy,
+                    cancelled,
+                    reply_tx,
+                }) => {
+                    let _ = reply_tx.send(graceful_shutdown(&mut child, policy, cancelled).await);
+                }
+                None => {
+                    // The handle was dropped, so nobody can stop the process later
+                    handle_open = false;
+                    let _ = kill_process_tree(&mut child).await;
+                }
+            },
+        }
+    };
+
+    // Deliver any remaining stdout before announcing the exit, so the last
+    // metrics are not lost behind the exit notification
+    let _ = tokio::time::timeout(STDOUT_DRAIN_TIMEOUT, stdout_task).await;
+
+    let exit_status = match status {
+        Ok(status) => ExitStatus::from(status),
+        Err(e) => {
+            tracing::warn!("Failed to wait for evaluator: {}", e);
+            ExitStatus::new(false, None)
+        }
+    };
+    let _ = message_tx.send(EvaluatorMessage::Exited(exit_status)).await;
 }
@@ -0,0 +1,240 @@
+//! Running evaluators under a pseudo-terminal
+
+use super::{
+    kill_process_tree_blocking, request_termination, run_cleanup, spawn_forwarder,
+    EvaluatorMessage, EvaluatorProcess, ExitStatus, OutputTap, ProcessControl, ShutdownPolicy,
+    STDOUT_DRAIN_TIMEOUT,
+};
+use crate::evaluator::output_queue::{OutputBuffer, OutputQueue};
+use crate::state::types::EvaluatorCommand;
+use anyhow::{Context, Result};
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+impl EvaluatorProcess {
+    /// Spawn an evaluator attached to a pseudo-terminal
+    ///
+    /// For evaluators that buffer output or behave differently when stdout
+    /// is not a TTY. A terminal has a single output stream, so stderr is
+    /// merged into it and every line arrives as `Output`.
+    pub async fn spawn_pty(
+        command: &EvaluatorCommand,
+        output: OutputBuffer,
+        tap: Option<Arc<dyn OutputTap>>,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+    ) -> Result<Self> {
+        Self::spawn_pty_command(
+            pty_command(command)?,
+            command,
+            None,
+            output,
+            tap,
+            message_tx,
+        )
+    }
+
+    /// Spawn a prepared command under a terminal, running `cleanup` after it exits
+    pub(super) fn spawn_pty_command(
+        pty_command: CommandBuilder,
+        command: &EvaluatorCommand,
+        cleanup: Option<Vec<OsString>>,
+        output: OutputBuffer,
+        tap: Option<Arc<dyn OutputTap>>,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+    ) -> Result<Self> {
+        let pair = native_pty_system()
+            .openpty(PtySize::default())
+            .context("Failed to open pseudo-terminal")?;
+        let child = pair
+            .slave
+            .spawn_command(pty_command)
+            .with_context(|| format!("Failed to spawn evaluator: {}", command))?;
+        // Only the child may hold the slave end, or reading never hits EOF
+        drop(pair.slave);
+
+        let reader = pair
+            .master
+            .try_clone_reader()
+            .context("Failed to read from pseudo-terminal")?;
+        let queue = OutputQueue::new(output);
+        let reader_queue = queue.clone();
+        let runtime = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || {
+            let mut reader = BufReader::new(reader);
+            let mut buf = Vec::new();
+
+            // Reads fail (EIO on Linux) once the child side is closed
+            while matches!(reader.read_until(b'\n', &mut buf), Ok(n) if n > 0) {
+                // The terminal turns every "\n" into "\r\n"
+                let line = String::from_utf8_lossy(&buf);
+                let line = line.trim_end_matches(['\r', '\n']).to_string();
+                buf.clear();
+                if let Some(tap) = &tap {
+                    tap.stdout(&line);
+                }
+                if !runtime.block_on(reader_queue.push(line)) {
+                    break;
+                }
+            }
+            reader_queue.close();
+        });
+        let output_task = spawn_forwarder(&queue, message_tx.clone());
+
+        let pid = child.process_id();
+        let (control_tx, control_rx) = mpsc::channel(1);
+        let child = PtyChild::new(child, pair.master);
+        tokio::spawn(async move {
+            monitor_pty_exit(child, control_rx, output_task, message_tx).await;
+            run_cleanup(cleanup).await;
+        });
+
+        // Writing to the terminal would echo control messages back as
+        // output, so pausing falls back to signals
+        Ok(Self {
+            control_tx,
+            pid,
+            queue,
+            control_input: None,
+            process_group: pid,
+            container: None,
+            suspended: false,
+            cancelled: false,
+        })
+    }
+}
+
+/// Build the command used to launch an evaluator under a terminal
+#[cfg(windows)]
+fn pty_command(command: &EvaluatorCommand) -> Result<CommandBuilder> {
+    let mut cmd = CommandBuilder::new("cmd.exe");
+    cmd.args(["/C", command.as_ref()]);
+    cmd.cwd(std::env::current_dir().context("Failed to get working directory")?);
+    Ok(cmd)
+}
+
+/// Build the command used to launch an evaluator under a terminal
+#[cfg(not(windows))]
+fn pty_command(command: &EvaluatorCommand) -> Result<CommandBuilder> {
+    let parts: Vec<&str> = command.as_ref().split_whitespace().collect();
+    if parts.is_empty() {
+        anyhow::bail!("Empty evaluator command");
+    }
+
+    let mut cmd = CommandBuilder::new(parts[0]);
+    cmd.args(&parts[1..]);
+    // CommandBuilder starts in the home directory unless told otherwise
+    cmd.cwd(std::env::current_dir().context("Failed to get working directory")?);
+    Ok(cmd)
+}
+
+/// A child running under a pseudo-terminal
+///
+/// The child is its own session leader, so its pid doubles as the process
+/// group id for signalling.
+struct PtyChild {
+    pid: Option<u32>,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+    status_rx: watch::Receiver<Option<ExitStatus>>,
+    /// Dropping the master hangs up the terminal, so it lives with the child
+    _master: Box<dyn MasterPty + Send>,
+}
+
+impl PtyChild {
+    fn new(
+        mut child: Box<dyn portable_pty::Child + Send + Sync>,
+        master: Box<dyn MasterPty + Send>,
+    ) -> Self {
+        let pid = child.process_id();
+        let killer = child.clone_killer();
+
+        // portable-pty only offers a blocking wait
+        let (status_tx, status_rx) = watch::channel(None);
+        tokio::task::spawn_blocking(move || {
+            let status = match child.wait() {
+                Ok(status) => ExitStatus::from(status),
+                Err(e) => {
+                    tracing::warn!("Failed to wait for evaluator: {}", e);
+                    ExitStatus::new(false, None)
+                }
+            };
+            let _ = status_tx.send(Some(status));
+        });
+
+        Self {
+            pid,
+            killer,
+            status_rx,
+            _master: master,
+        }
+    }
+
+    /// Wait for the child to exit; safe to call again after it has
+    async fn wait(&mut self) -> ExitStatus {
+        match self.status_rx.wait_for(Option::is_some).await {
+            Ok(status) => status.clone().unwrap_or(ExitStatus::new(false, None)),
+            Err(_) => ExitStatus::new(false, None),
+        }
+    }
+
+    /// Kill the child along with any processes it started
+    fn kill(&mut self) -> std::io::Result<()> {
+        if let Some(pid) = self.pid {
+            kill_process_tree_blocking(pid);
+        }
+        self.killer.kill()
+    }
+
+    /// Ask the child to exit, then kill it if the grace period runs out
+    async fn graceful_shutdown(&mut self, policy: ShutdownPolicy) -> std::io::Result<()> {
+        if request_termination(self.pid, policy.signal) {
+            if tokio::time::timeout(policy.grace_period, self.wait())
+                .await
+                .is_ok()
+            {
+                return Ok(());
+            }
+            tracing::warn!(
+                "Evaluator did not exit within {:?} grace period, killing it",
+                policy.grace_period
+            );
+        }
+
+        self.kill()
+    }
+}
+
+/// Wait for the child to exit, or kill it on request, then report how it ended
+async fn monitor_pty_exit(
+    mut child: PtyChild,
+    mut control_rx: mpsc::Receiver<ProcessControl>,
+    output_task: JoinHandle<()>,
+    message_tx: mpsc::Sender<EvaluatorMessage>,
+) {
+    let mut handle_open = true;
+    let exit_status = loop {
+        tokio::select! {
+            status = child.wait() => break status,
+            control = control_rx.recv(), if handle_open => match control {
+                Some(ProcessControl::Kill(reply_tx)) => {
+                    let _ = reply_tx.send(child.kill());
+                }
+                // Without control input, the evaluator cannot have been cancelled
+                Some(ProcessControl::Shutdown { policy, reply_tx, .. }) => {
+                    let _ = reply_tx.send(child.graceful_shutdown(policy).await);
+                }
+                None => {
+                    handle_open = false;
+                    let _ = child.kill();
+                }
+            },
+        }
+    };
+
+    // Deliver any remaining output before announcing the exit
+    let _ = tokio::time::timeout(STDOUT_DRAIN_TIMEOUT, output_task).await;
+    let _ = message_tx.send(EvaluatorMessage::Exited(exit_status)).await;
+}
@@ -1,3 +1,5 @@
+use crate::state::expression::DerivedMetric;
+use crate::state::rules::ThresholdRule;
 use nutype::nutype;
 use serde::{Deserialize, Serialize};
 
@@ -79,6 +81,32 @@ pub struct TotalSamples(u32);
 )]
 pub struct BatchSize(u32);
 
+/// Number of times each sample is run, at least once
+#[nutype(
+    validate(greater = 0),
+    derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        PartialOrd,
+        Ord,
+        Into,
+        Serialize,
+        Deserialize
+    )
+)]
+pub struct RunsPerSample(u32);
+
+/// Name of a phase of the run that must be non-empty
+#[nutype(
+    sanitize(trim),
+    validate(not_empty, len_char_max = 64),
+    derive(Debug, Clone, PartialEq, Eq, AsRef, Display, Serialize, Deserialize)
+)]
+pub struct PhaseName(String);
+
 /// Evaluation mode for the evaluator
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -119,6 +147,18 @@ where
 pub struct ExecutionPlan {
     pub total_samples: u32,      // Will be converted to TotalSamples after parsing
     pub batch_size: Option<u32>, // Will be converted to BatchSize after parsing
+    #[serde(default)]
+    pub runs_per_sample: Option<u32>, // Will be converted to RunsPerSample after parsing
+    /// Stages the samples run through in order, e.g. generation then grading
+    #[serde(default)]
+    pub phases: Vec<PhaseDefinition>,
+}
+
+/// Phase of the run in the handshake
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseDefinition {
+    pub name: String, // Will be converted to PhaseName after parsing
+    pub samples: u32, // Will be converted to TotalSamples after parsing
 }
 
 /// Metric definition in the handshake
@@ -150,18 +190,64 @@ pub struct Handshake {
     pub evaluator: EvaluatorInfo,
     pub execution_plan: Option<ExecutionPlan>,
     pub metrics_schema: Vec<MetricDefinition>,
+    /// Optional protocol features the evaluator implements
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Threshold rules the run must meet, e.g. `accuracy >= 0.8`
+    #[serde(default)]
+    pub thresholds: Vec<String>,
+    /// Composite score of each sample, e.g. `score = 0.7*accuracy + 0.3*speed`
+    #[serde(default)]
+    pub score: Option<String>,
+}
+
+/// Optional protocol feature an evaluator can announce in its handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Understands `pause` and `resume` control messages on stdin
+    Pause,
+    /// Exits on its own after a `cancel` control message on stdin
+    Cancel,
+}
+
+impl Capability {
+    /// Look up a capability by its protocol name
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pause" => Some(Self::Pause),
+            "cancel" => Some(Self::Cancel),
+            _ => None,
+        }
+    }
+}
+
+/// Message PrEval writes to the evaluator's stdin as a JSON line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlMessage {
+    /// Stop starting new samples until resumed
+    Pause,
+    /// Carry on after a pause
+    Resume,
+    /// Wrap up and exit, as the run is being stopped
+    Cancel,
 }
 
 /// Validated handshake with strong types
 #[derive(Debug, Clone)]
 pub struct ValidatedHandshake {
-    #[allow(dead_code)] // Used in future stories
     pub mode: EvaluationMode,
     pub version: ProtocolVersion,
     pub evaluator: ValidatedEvaluatorInfo,
     pub execution_plan: Option<ValidatedExecutionPlan>,
     #[allow(dead_code)] // Used in future stories
     pub metrics_schema: Vec<ValidatedMetricDefinition>,
+    /// Capabilities PrEval understands; unknown names are ignored
+    pub capabilities: Vec<Capability>,
+    /// Threshold rules the evaluator asks PrEval to enforce
+    pub thresholds: Vec<ThresholdRule>,
+    /// Composite score the evaluator suggests as the run's headline number
+    pub score: Option<DerivedMetric>,
 }
 
 /// Validated evaluator information
@@ -177,8 +263,17 @@ pub struct ValidatedEvaluatorInfo {
 #[derive(Debug, Clone)]
 pub struct ValidatedExecutionPlan {
     pub total_samples: TotalSamples,
-    #[allow(dead_code)] // Used in future stories
     pub batch_size: Option<BatchSize>,
+    pub runs_per_sample: Option<RunsPerSample>,
+    pub phases: Vec<ValidatedPhase>,
+}
+
+/// Validated phase of the run
+#[derive(Debug, Clone)]
+pub struct ValidatedPhase {
+    pub name: PhaseName,
+    /// Samples completed within the phase before the next one starts
+    pub samples: TotalSamples,
 }
 
 /// Validated metric definition
@@ -197,7 +292,7 @@ impl ValidatedHandshake {
     pub fn parse(handshake: Handshake) -> Result<Self, ValidationError> {
         // Validate protocol version
         let version = ProtocolVersion::try_new(handshake.version)
-            .map_err(|e| ValidationError::InvalidVersion(e.to_string()))?;
+            .map_err(|e| ValidationError::Version(e.to_string()))?;
 
         // Validate evaluator info
         let evaluator = ValidatedEvaluatorInfo::parse(handshake.evaluator)?;
@@ -215,14 +310,42 @@ impl ValidatedHandshake {
             .map(ValidatedMetricDefinition::parse)
             .collect::<Result<Vec<_>, _>>()?;
 
+        // Unknown capabilities belong to newer protocol versions
+        let capabilities = handshake
+            .capabilities
+            .iter()
+            .filter_map(|name| Capability::from_name(name))
+            .collect();
+
+        let thresholds = handshake
+            .thresholds
+            .iter()
+            .map(|rule| rule.parse::<ThresholdRule>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ValidationError::Threshold(e.to_string()))?;
+
+        let score = handshake
+            .score
+            .map(|score| score.parse::<DerivedMetric>())
+            .transpose()
+            .map_err(|e| ValidationError::Score(e.to_string()))?;
+
         Ok(Self {
             mode: handshake.mode,
             version,
             evaluator,
             execution_plan,
             metrics_schema,
+            capabilities,
+            thresholds,
+            score,
         })
     }
+
+    /// Whether the evaluator announced `capability`
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
 }
 
 impl ValidatedEvaluatorInfo {
@@ -232,7 +355,7 @@ impl ValidatedEvaluatorInfo {
             .description
             .map(EvaluatorDescription::try_new)
             .transpose()
-            .map_err(|e| ValidationError::InvalidDescription(e.to_string()))?;
+            .map_err(|e| ValidationError::Description(e.to_string()))?;
 
         Ok(Self {
             name: info.name,
@@ -245,31 +368,55 @@ impl ValidatedEvaluatorInfo {
 impl ValidatedExecutionPlan {
     fn parse(plan: ExecutionPlan) -> Result<Self, ValidationError> {
         let total_samples = TotalSamples::try_new(plan.total_samples)
-            .map_err(|e| ValidationError::InvalidTotalSamples(e.to_string()))?;
+            .map_err(|e| ValidationError::TotalSamples(e.to_string()))?;
 
         let batch_size = plan
             .batch_size
             .map(BatchSize::try_new)
             .transpose()
-            .map_err(|e| ValidationError::InvalidBatchSize(e.to_string()))?;
+            .map_err(|e| ValidationError::BatchSize(e.to_string()))?;
+
+        let runs_per_sample = plan
+            .runs_per_sample
+            .map(RunsPerSample::try_new)
+            .transpose()
+            .map_err(|e| ValidationError::RunsPerSample(e.to_string()))?;
+
+        let phases = plan
+            .phases
+            .into_iter()
+            .map(ValidatedPhase::parse)
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Self {
             total_samples,
             batch_size,
+            runs_per_sample,
+            phases,
         })
     }
 }
 
+impl ValidatedPhase {
+    fn parse(phase: PhaseDefinition) -> Result<Self, ValidationError> {
+        let name =
+            PhaseName::try_new(phase.name).map_err(|e| ValidationError::Phase(e.to_string()))?;
+        let samples = TotalSamples::try_new(phase.samples)
+            .map_err(|e| ValidationError::Phase(format!("{}: {}", name, e)))?;
+        Ok(Self { name, samples })
+    }
+}
+
 impl ValidatedMetricDefinition {
     fn parse(def: MetricDefinition) -> Result<Self, ValidationError> {
         let name = MetricDefinitionName::try_new(def.name)
-            .map_err(|e| ValidationError::InvalidMetricName(e.to_string()))?;
+            .map_err(|e| ValidationError::MetricName(e.to_string()))?;
 
         let unit = def
             .unit
             .map(MetricUnit::try_new)
             .transpose()
-            .map_err(|e| ValidationError::InvalidMetricUnit(e.to_string()))?;
+            .map_err(|e| ValidationError::MetricUnit(e.to_string()))?;
 
         Ok(Self {
             name,
@@ -283,24 +430,35 @@ impl ValidatedMetricDefinition {
 #[derive(Debug, thiserror::Error)]
 pub enum ValidationError {
     #[error("protocol version is invalid: {0}")]
-    InvalidVersion(String),
+    Version(String),
 
-    // Error removed: EmptyEvaluatorName  
+    // Error removed: EmptyEvaluatorName
     // The EvaluatorNameProtocol type with custom deserializer now enforces
     // non-empty names at the JSON parsing level, making this error impossible.
-
     #[error("evaluator description is invalid: {0}")]
-    InvalidDescription(String),
+    Description(String),
 
     #[error("total samples count is invalid: {0}")]
-    InvalidTotalSamples(String),
+    TotalSamples(String),
 
     #[error("batch size is invalid: {0}")]
-    InvalidBatchSize(String),
+    BatchSize(String),
+
+    #[error("runs per sample is invalid: {0}")]
+    RunsPerSample(String),
+
+    #[error("phase is invalid: {0}")]
+    Phase(String),
 
     #[error("metric name is invalid: {0}")]
-    InvalidMetricName(String),
+    MetricName(String),
 
     #[error("metric unit is invalid: {0}")]
-    InvalidMetricUnit(String),
+    MetricUnit(String),
+
+    #[error("threshold is invalid: {0}")]
+    Threshold(String),
+
+    #[error("score is invalid: {0}")]
+    Score(String),
 }
@@ -0,0 +1,244 @@
+//! Running evaluators on another machine over SSH
+
+use crate::state::types::EvaluatorCommand;
+use std::collections::hash_map::RandomState;
+use std::ffi::OsString;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Counts remote evaluators started by this PrEval, so restarts get fresh
+/// pid files
+static REMOTE_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Seconds a remote evaluator has to exit after SIGTERM before it is
+/// killed
+const REMOTE_GRACE_SECS: u32 = 5;
+
+/// How to run the evaluator on another machine
+///
+/// The command runs through `ssh` in the remote user's home directory, so
+/// it usually starts with a `cd`. PrEval still reads the protocol from the
+/// evaluator's stdout, which `ssh` streams back. Authentication must not
+/// prompt: use keys or an agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshSpec {
+    /// Where to run, as given to `ssh`: `host`, `user@host` or a host alias
+    /// from the SSH config
+    pub destination: String,
+}
+
+impl SshSpec {
+    /// Create a spec for running on `destination`
+    pub fn new(destination: impl Into<String>) -> Self {
+        Self {
+            destination: destination.into(),
+        }
+    }
+
+    /// Pick a remote file for the next evaluator's pid, unique within this
+    /// PrEval and with a random part other users of the host cannot guess
+    pub(crate) fn next_pid_file(&self) -> String {
+        let count = REMOTE_COUNT.fetch_add(1, Ordering::Relaxed);
+        // Hashing with randomly seeded keys, to avoid a dependency for this
+        let random = RandomState::new().hash_one((std::process::id(), count));
+        format!(
+            "/tmp/preval-{}-{}-{:016x}.pid",
+            std::process::id(),
+            count,
+            random
+        )
+    }
+
+    /// Command line that runs `command` remotely in a session of its own,
+    /// writing the session's process group to `pid_file` first
+    ///
+    /// `setsid -w` starts the session, forking if it has to, and waits for
+    /// the evaluator so its exit status still reaches `ssh`. The pid file is
+    /// written with `set -C`, so the evaluator does not start if something,
+    /// such as a symlink planted in `/tmp`, is already there.
+    pub(crate) fn argv(
+        &self,
+        command: &EvaluatorCommand,
+        pid_file: &str,
+        tty: bool,
+    ) -> Vec<OsString> {
+        let script = format!(
+            "set -C; echo $$ > {} || exit 1; exec sh -c {}",
+            pid_file,
+            shell_quote(command.as_ref())
+        );
+        self.ssh(
+            tty,
+            format!("exec setsid -w sh -c {}", shell_quote(&script)),
+        )
+    }
+
+    /// Command line that stops the evaluator whose process group is in
+    /// `pid_file`, with every process it started
+    ///
+    /// The group gets SIGTERM, then SIGKILL if any of it is still running
+    /// `REMOTE_GRACE_SECS` later. Without a terminal, ending the local `ssh`
+    /// does not stop the remote command, so this runs after every evaluator
+    /// exit.
+    pub(crate) fn cleanup_argv(&self, pid_file: &str) -> Vec<OsString> {
+        let script = format!(
+            "pgid=$(cat {file} 2>/dev/null) && {{ kill -TERM -\"$pgid\"; n=0; \
+             while kill -0 -\"$pgid\" && [ $n -lt {grace} ]; do sleep 1; n=$((n + 1)); done; \
+             kill -KILL -\"$pgid\"; }} 2>/dev/null; rm -f {file}",
+            file = pid_file,
+            grace = REMOTE_GRACE_SECS
+        );
+        self.ssh(false, format!("sh -c {}", shell_quote(&script)))
+    }
+
+    /// `ssh` running `remote_command` on the destination
+    fn ssh(&self, tty: bool, remote_command: String) -> Vec<OsString> {
+        vec![
+            "ssh".into(),
+            // Fail instead of prompting, which would draw over the TUI
+            "-o".into(),
+            "BatchMode=yes".into(),
+            if tty { "-tt" } else { "-T" }.into(),
+            "--".into(),
+            self.destination.as_str().into(),
+            remote_command.into(),
+        ]
+    }
+}
+
+/// `text` quoted as a single word for a POSIX shell
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_command_in_ssh() {
+        let spec = SshSpec::new("me@gpu-box");
+        let command = EvaluatorCommand::try_new("python eval.py".to_string()).unwrap();
+
+        let argv = spec.argv(&command, "/tmp/preval-1-0.pid", false);
+
+        let argv: Vec<_> = argv.iter().map(|arg| arg.to_str().unwrap()).collect();
+        assert_eq!(
+            argv,
+            vec![
+                "ssh",
+                "-o",
+                "BatchMode=yes",
+                "-T",
+                "--",
+                "me@gpu-box",
+                r"exec setsid -w sh -c 'set -C; echo $$ > /tmp/preval-1-0.pid || exit 1; exec sh -c '\''python eval.py'\'''",
+            ]
+        );
+        assert_eq!(spec.argv(&command, "/tmp/preval-1-0.pid", true)[3], "-tt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quoted_words_reach_the_shell_unchanged() {
+        let text = r#"cd "my evals" && python eval.py --name 'it''s' $HOME"#;
+
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {}", shell_quote(text)))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), text);
+    }
+
+    /// What `argv` would run on the remote host, run here instead
+    #[cfg(target_os = "linux")]
+    fn run_locally(argv: Vec<OsString>) -> std::process::Command {
+        let mut command = std::process::Command::new("sh");
+        command.arg("-c").arg(argv.last().unwrap());
+        command
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cleanup_stops_the_whole_session_even_when_it_ignores_sigterm() {
+        let spec = SshSpec::new("localhost");
+        let pid_file = std::env::temp_dir()
+            .join(format!("preval-remote-{}.pid", std::process::id()))
+            .display()
+            .to_string();
+        // A grandchild that shrugs off SIGTERM
+        let command = EvaluatorCommand::try_new(
+            "sh -c 'trap \"\" TERM; while true; do sleep 1; done' & wait".to_string(),
+        )
+        .unwrap();
+        let mut evaluator = run_locally(spec.argv(&command, &pid_file, false))
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let pgid = loop {
+            match std::fs::read_to_string(&pid_file) {
+                Ok(pgid) if !pgid.trim().is_empty() => break pgid.trim().to_string(),
+                _ => std::thread::sleep(std::time::Duration::from_millis(20)),
+            }
+        };
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let status = run_locally(spec.cleanup_argv(&pid_file)).status().unwrap();
+        assert!(status.success());
+        // Reaped, as its zombie would still count as part of the group
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while evaluator.try_wait().unwrap().is_none() {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "the evaluator kept running"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        // Killed processes linger until init reaps them
+        let alive = || {
+            std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("kill -0 -{}", pgid))
+                .stderr(std::process::Stdio::null())
+                .status()
+                .unwrap()
+                .success()
+        };
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while alive() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert!(!alive(), "processes of the evaluator's session survived");
+        assert!(!std::path::Path::new(&pid_file).exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn a_planted_pid_file_stops_the_evaluator_from_starting() {
+        let spec = SshSpec::new("localhost");
+        let dir = std::env::temp_dir().join(format!("preval-planted-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("victim");
+        std::fs::write(&target, "keep me").unwrap();
+        let pid_file = dir.join("evaluator.pid");
+        std::os::unix::fs::symlink(&target, &pid_file).unwrap();
+        let command = EvaluatorCommand::try_new("echo started".to_string()).unwrap();
+
+        let output = run_locally(spec.argv(&command, &pid_file.display().to_string(), false))
+            .output()
+            .unwrap();
+
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "keep me");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pid_files_are_unique() {
+        let spec = SshSpec::new("gpu-box");
+
+        assert_ne!(spec.next_pid_file(), spec.next_pid_file());
+    }
+}
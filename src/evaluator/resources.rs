@@ -0,0 +1,118 @@
+//! Resource usage sampling for the evaluator process
+
+use crate::state::types::ResourceUsage;
+use std::collections::HashMap;
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// Samples CPU, memory and open files of a process and everything it started
+///
+/// Evaluators often do their work in children, e.g. the binary behind
+/// `cargo run` or the workers of a Python pool, so usage is summed over the
+/// whole process tree.
+///
+/// CPU usage is measured between consecutive samples, so the first sample
+/// of a process always reports 0%.
+pub struct ResourceMonitor {
+    system: System,
+}
+
+impl ResourceMonitor {
+    /// Create a new monitor
+    pub fn new() -> Self {
+        Self {
+            system: System::new(),
+        }
+    }
+
+    /// Sample the process with the given pid and its descendants, or `None`
+    /// if it is gone
+    pub fn sample(&mut self, pid: u32) -> Option<ResourceUsage> {
+        // Descendants can only be found by looking at every process
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+
+        let mut usage = ResourceUsage {
+            cpu_percent: 0.0,
+            memory_bytes: 0,
+            open_files: Some(0),
+        };
+        for process in process_tree(&self.system, Pid::from_u32(pid))? {
+            usage.cpu_percent += process.cpu_usage();
+            usage.memory_bytes += process.memory();
+            usage.open_files = usage
+                .open_files
+                .zip(process.open_files())
+                .map(|(a, b)| a + b);
+        }
+        Some(usage)
+    }
+}
+
+impl Default for ResourceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The process `root` followed by every process descending from it, or
+/// `None` if it is gone
+///
+/// Threads, which some platforms list as processes, are left out because
+/// they share their process's memory.
+fn process_tree(system: &System, root: Pid) -> Option<Vec<&Process>> {
+    let mut children: HashMap<Pid, Vec<&Process>> = HashMap::new();
+    for process in system.processes().values() {
+        if let (Some(parent), None) = (process.parent(), process.thread_kind()) {
+            children.entry(parent).or_default().push(process);
+        }
+    }
+
+    let mut tree = vec![system.process(root)?];
+    let mut next = 0;
+    while let Some(process) = tree.get(next) {
+        if let Some(descendants) = children.get(&process.pid()) {
+            tree.extend(descendants);
+        }
+        next += 1;
+    }
+    Some(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_current_process() {
+        let mut monitor = ResourceMonitor::new();
+
+        let usage = monitor.sample(std::process::id()).unwrap();
+
+        assert!(usage.memory_bytes > 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_tree_includes_children() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+        let mut system = System::new();
+        system.refresh_processes(ProcessesToUpdate::All, true);
+
+        let tree: Vec<_> = process_tree(&system, Pid::from_u32(std::process::id()))
+            .unwrap()
+            .iter()
+            .map(|process| process.pid())
+            .collect();
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert_eq!(tree[0], Pid::from_u32(std::process::id()));
+        assert!(tree.contains(&Pid::from_u32(child.id())));
+    }
+}
@@ -0,0 +1,720 @@
+//! Transports the protocol messages of a run arrive over
+//!
+//! The app is written against [`MetricSource`]: whether the messages come
+//! from an evaluator PrEval runs, from its own stdin, from a file, a Unix
+//! socket, a named pipe or OTLP/HTTP, they arrive as [`EvaluatorMessage`]s and the run is
+//! controlled through the [`EvaluatorProcess`] handle the source returns.
+
+mod otlp_http;
+
+use super::container::ContainerSpec;
+use super::output_queue::OutputBuffer;
+use super::process::{EvaluatorMessage, EvaluatorProcess, ExitStatus, OutputTap};
+use super::remote::SshSpec;
+use crate::state::types::EvaluatorCommand;
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc;
+
+pub use otlp_http::OtlpHttp;
+
+/// Future starting a source, resolving to the handle of the running source
+pub type SourceFuture<'a> = Pin<Box<dyn Future<Output = Result<EvaluatorProcess>> + Send + 'a>>;
+
+/// Where the protocol messages of a run come from
+pub trait MetricSource: Send + Sync {
+    /// What the source reads from, e.g. the evaluator command, used to name
+    /// the run until the handshake names the evaluator
+    fn describe(&self) -> String;
+
+    /// Start delivering messages to `message_tx`, ending with
+    /// [`EvaluatorMessage::Exited`] once the source has nothing more to say
+    ///
+    /// Each line is shown to `tap` as soon as it is read, before anything
+    /// can buffer or drop it. Called again when the run is restarted.
+    /// Killing the handle of a source PrEval does not run only stops
+    /// reading from it.
+    fn start(
+        &self,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+        tap: Option<Arc<dyn OutputTap>>,
+    ) -> SourceFuture<'_>;
+}
+
+/// An evaluator PrEval runs, reporting on its stdout
+#[derive(Debug, Clone)]
+pub struct ChildProcess {
+    command: EvaluatorCommand,
+    container: Option<ContainerSpec>,
+    ssh: Option<SshSpec>,
+    pty: bool,
+    output: OutputBuffer,
+}
+
+impl ChildProcess {
+    /// Run `command` directly, with its output piped
+    pub fn new(command: EvaluatorCommand) -> Self {
+        Self {
+            command,
+            container: None,
+            ssh: None,
+            pty: false,
+            output: OutputBuffer::default(),
+        }
+    }
+
+    /// Run the evaluator inside a container (builder pattern)
+    pub fn container(mut self, container: ContainerSpec) -> Self {
+        self.container = Some(container);
+        self
+    }
+
+    /// Run the evaluator on another machine over SSH (builder pattern)
+    pub fn ssh(mut self, ssh: SshSpec) -> Self {
+        self.ssh = Some(ssh);
+        self
+    }
+
+    /// Run the evaluator under a pseudo-terminal (builder pattern)
+    pub fn pty(mut self, pty: bool) -> Self {
+        self.pty = pty;
+        self
+    }
+
+    /// Set how evaluator output is buffered when the app falls behind (builder pattern)
+    pub fn output_buffer(mut self, output: OutputBuffer) -> Self {
+        self.output = output;
+        self
+    }
+}
+
+impl MetricSource for ChildProcess {
+    fn describe(&self) -> String {
+        self.command.to_string()
+    }
+
+    fn start(
+        &self,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+        tap: Option<Arc<dyn OutputTap>>,
+    ) -> SourceFuture<'_> {
+        Box::pin(async move {
+            if let Some(container) = &self.container {
+                EvaluatorProcess::spawn_in_container(
+                    container,
+                    &self.command,
+                    self.pty,
+                    self.output,
+                    tap,
+                    message_tx,
+                )
+                .await
+            } else if let Some(ssh) = &self.ssh {
+                EvaluatorProcess::spawn_over_ssh(
+                    ssh,
+                    &self.command,
+                    self.pty,
+                    self.output,
+                    tap,
+                    message_tx,
+                )
+                .await
+            } else if self.pty {
+                EvaluatorProcess::spawn_pty(&self.command, self.output, tap, message_tx).await
+            } else {
+                EvaluatorProcess::spawn(&self.command, self.output, tap, message_tx).await
+            }
+        })
+    }
+}
+
+/// Protocol messages piped into PrEval's own stdin, e.g. by
+/// `my-eval | preval --source stdin`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stdin;
+
+impl MetricSource for Stdin {
+    fn describe(&self) -> String {
+        "stdin".to_string()
+    }
+
+    fn start(
+        &self,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+        tap: Option<Arc<dyn OutputTap>>,
+    ) -> SourceFuture<'_> {
+        Box::pin(async move {
+            Ok(EvaluatorProcess::follow(read_lines(
+                tokio::io::stdin(),
+                message_tx,
+                tap,
+            )))
+        })
+    }
+}
+
+/// A file of protocol messages saved from an earlier run, replayed as fast
+/// as it can be read
+#[derive(Debug, Clone)]
+pub struct FileReplay {
+    path: PathBuf,
+}
+
+impl FileReplay {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl MetricSource for FileReplay {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn start(
+        &self,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+        tap: Option<Arc<dyn OutputTap>>,
+    ) -> SourceFuture<'_> {
+        Box::pin(async move {
+            let file = tokio::fs::File::open(&self.path)
+                .await
+                .with_context(|| format!("Failed to open {}", self.path.display()))?;
+            Ok(EvaluatorProcess::follow(read_lines(file, message_tx, tap)))
+        })
+    }
+}
+
+/// A Unix socket PrEval listens on, reading the protocol messages of the
+/// first evaluator to connect until it hangs up
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct UnixSocket {
+    path: PathBuf,
+    keep_listening: bool,
+}
+
+#[cfg(unix)]
+impl UnixSocket {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            keep_listening: false,
+        }
+    }
+
+    /// Keep accepting connections for as long as PrEval runs, reading
+    /// every one of them, instead of ending with the first (builder pattern)
+    ///
+    /// For long-lived services that push metrics to a running PrEval.
+    pub fn keep_listening(mut self, keep_listening: bool) -> Self {
+        self.keep_listening = keep_listening;
+        self
+    }
+}
+
+#[cfg(unix)]
+impl MetricSource for UnixSocket {
+    fn describe(&self) -> String {
+        format!("unix:{}", self.path.display())
+    }
+
+    fn start(
+        &self,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+        tap: Option<Arc<dyn OutputTap>>,
+    ) -> SourceFuture<'_> {
+        use std::os::unix::fs::FileTypeExt;
+
+        Box::pin(async move {
+            // A socket left behind by an earlier run would fail the bind
+            let stale = std::fs::symlink_metadata(&self.path)
+                .is_ok_and(|metadata| metadata.file_type().is_socket());
+            if stale {
+                std::fs::remove_file(&self.path).with_context(|| {
+                    format!("Failed to remove stale socket {}", self.path.display())
+                })?;
+            }
+            let listener = tokio::net::UnixListener::bind(&self.path)
+                .with_context(|| format!("Failed to listen on {}", self.path.display()))?;
+            tracing::info!("Waiting for an evaluator on {}", self.path.display());
+
+            if self.keep_listening {
+                return Ok(EvaluatorProcess::follow(async move {
+                    loop {
+                        match listener.accept().await {
+                            Ok((stream, _)) => {
+                                let message_tx = message_tx.clone();
+                                let tap = tap.clone();
+                                tokio::spawn(async move {
+                                    if let Err(e) =
+                                        forward_lines(stream, &message_tx, tap.as_deref()).await
+                                    {
+                                        tracing::warn!("Failed to read protocol messages: {}", e);
+                                    }
+                                });
+                            }
+                            Err(e) => {
+                                tracing::warn!("Failed to accept evaluator connection: {}", e)
+                            }
+                        }
+                    }
+                }));
+            }
+            Ok(EvaluatorProcess::follow(async move {
+                match listener.accept().await {
+                    Ok((stream, _)) => read_lines(stream, message_tx, tap).await,
+                    Err(e) => {
+                        tracing::warn!("Failed to accept evaluator connection: {}", e);
+                        let _ = message_tx
+                            .send(EvaluatorMessage::Exited(ExitStatus::new(false, None)))
+                            .await;
+                    }
+                }
+            }))
+        })
+    }
+}
+
+/// A named pipe made with `mkfifo`, read for as long as PrEval runs: each
+/// writer's messages in turn, reopening the pipe whenever the last writer
+/// closes it
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct NamedPipe {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl NamedPipe {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[cfg(unix)]
+impl MetricSource for NamedPipe {
+    fn describe(&self) -> String {
+        format!("pipe:{}", self.path.display())
+    }
+
+    fn start(
+        &self,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+        tap: Option<Arc<dyn OutputTap>>,
+    ) -> SourceFuture<'_> {
+        let path = self.path.clone();
+        Box::pin(async move {
+            tracing::info!("Waiting for an evaluator to write to {}", path.display());
+            Ok(EvaluatorProcess::follow(async move {
+                loop {
+                    // Opening blocks until a writer opens the other end
+                    let forwarded = match tokio::fs::File::open(&path).await {
+                        Ok(pipe) => forward_lines(pipe, &message_tx, tap.as_deref()).await,
+                        Err(e) => Err(e),
+                    };
+                    match forwarded {
+                        Ok(true) => {}
+                        Ok(false) => return,
+                        Err(e) => {
+                            tracing::warn!("Failed to read {}: {}", path.display(), e);
+                            let _ = message_tx
+                                .send(EvaluatorMessage::Exited(ExitStatus::new(false, None)))
+                                .await;
+                            return;
+                        }
+                    }
+                }
+            }))
+        })
+    }
+}
+
+/// Source for `--listen-unix`: the named pipe at `path` if there is one,
+/// or else a Unix socket there that keeps accepting connections
+#[cfg(unix)]
+pub fn listen_unix(path: PathBuf) -> Box<dyn MetricSource> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let fifo = std::fs::metadata(&path).is_ok_and(|metadata| metadata.file_type().is_fifo());
+    if fifo {
+        Box::new(NamedPipe::new(path))
+    } else {
+        Box::new(UnixSocket::new(path).keep_listening(true))
+    }
+}
+
+/// A file another program is still appending protocol messages to, read
+/// like `tail -f` for as long as PrEval runs
+///
+/// Reading starts at the beginning, so the handshake is not missed. A file
+/// that shrinks was truncated and is read again from the start; one
+/// replaced by a new file at the same path was rotated, and the new file
+/// is read once the old one is finished. A file that does not exist yet is
+/// waited for.
+#[derive(Debug, Clone)]
+pub struct TailFile {
+    path: PathBuf,
+}
+
+impl TailFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl MetricSource for TailFile {
+    fn describe(&self) -> String {
+        format!("tail:{}", self.path.display())
+    }
+
+    fn start(
+        &self,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+        tap: Option<Arc<dyn OutputTap>>,
+    ) -> SourceFuture<'_> {
+        let path = self.path.clone();
+        Box::pin(async move {
+            tracing::info!("Following {}", path.display());
+            Ok(EvaluatorProcess::follow(async move {
+                let mut tail = Tail::default();
+                loop {
+                    match tail.read_lines(&path) {
+                        Ok(lines) => {
+                            for line in lines {
+                                if let Some(tap) = &tap {
+                                    tap.stdout(&line);
+                                }
+                                if message_tx
+                                    .send(EvaluatorMessage::Output(line))
+                                    .await
+                                    .is_err()
+                                {
+                                    return;
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to read {}: {}", path.display(), e);
+                            let _ = message_tx
+                                .send(EvaluatorMessage::Exited(ExitStatus::new(false, None)))
+                                .await;
+                            return;
+                        }
+                    }
+                    tokio::time::sleep(TAIL_INTERVAL).await;
+                }
+            }))
+        })
+    }
+}
+
+/// Time between two looks at a followed file
+const TAIL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Where reading a followed file got to
+#[derive(Debug, Default)]
+struct Tail {
+    /// The file being read, once it exists
+    file: Option<std::fs::File>,
+    /// Identity of that file, to tell when another one replaces it
+    id: Option<FileId>,
+    /// Bytes read so far
+    position: u64,
+    /// The start of a line still being written
+    partial: Vec<u8>,
+}
+
+impl Tail {
+    /// Every complete line added to the file at `path` since the last call
+    fn read_lines(&mut self, path: &std::path::Path) -> std::io::Result<Vec<String>> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        if self.file.is_none() {
+            match std::fs::File::open(path) {
+                Ok(file) => {
+                    self.id = file_id(&file.metadata()?);
+                    self.file = Some(file);
+                    self.position = 0;
+                    self.partial.clear();
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(e) => return Err(e),
+            }
+        }
+        let Some(file) = self.file.as_mut() else {
+            return Ok(Vec::new());
+        };
+
+        if file.metadata()?.len() < self.position {
+            tracing::info!(
+                "{} was truncated, reading it from the start",
+                path.display()
+            );
+            file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            self.partial.clear();
+        }
+        let mut added = Vec::new();
+        self.position += file.read_to_end(&mut added)? as u64;
+        self.partial.extend_from_slice(&added);
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.partial.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            lines.push(line.trim_end_matches(['\n', '\r']).to_string());
+        }
+
+        // The old file is finished, so a new one at the path is read next
+        let rotated = std::fs::metadata(path)
+            .ok()
+            .is_some_and(|metadata| file_id(&metadata) != self.id);
+        if rotated {
+            tracing::info!("{} was rotated, reading the new file", path.display());
+            self.file = None;
+        }
+        Ok(lines)
+    }
+}
+
+/// What tells two files apart, where the platform has it
+#[cfg(unix)]
+type FileId = (u64, u64);
+
+#[cfg(not(unix))]
+type FileId = ();
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Rotation cannot be told apart from appending here, only truncation
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<FileId> {
+    None
+}
+
+/// Transport chosen with `--source`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceSpec {
+    /// `stdin`
+    Stdin,
+    /// `file=PATH`
+    File(PathBuf),
+    /// `unix=PATH`
+    #[cfg(unix)]
+    Unix(PathBuf),
+    /// `otlp=ADDR`
+    Otlp(SocketAddr),
+}
+
+impl SourceSpec {
+    /// The source this spec describes
+    pub fn into_source(self) -> Box<dyn MetricSource> {
+        match self {
+            SourceSpec::Stdin => Box::new(Stdin),
+            SourceSpec::File(path) => Box::new(FileReplay::new(path)),
+            #[cfg(unix)]
+            SourceSpec::Unix(path) => Box::new(UnixSocket::new(path)),
+            SourceSpec::Otlp(addr) => Box::new(OtlpHttp::new(addr)),
+        }
+    }
+}
+
+impl std::str::FromStr for SourceSpec {
+    type Err = SourceSpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("stdin") {
+            return Ok(Self::Stdin);
+        }
+        let (kind, value) = s
+            .split_once('=')
+            .filter(|(_, value)| !value.is_empty())
+            .ok_or_else(|| SourceSpecError(s.to_string()))?;
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "file" => Ok(Self::File(PathBuf::from(value))),
+            #[cfg(unix)]
+            "unix" => Ok(Self::Unix(PathBuf::from(value))),
+            "otlp" => value
+                .trim()
+                .parse()
+                .map(Self::Otlp)
+                .map_err(|_| SourceSpecError(s.to_string())),
+            _ => Err(SourceSpecError(s.to_string())),
+        }
+    }
+}
+
+/// Error for unrecognized sources of protocol messages
+#[derive(Debug, thiserror::Error)]
+#[error("unknown source '{0}' (expected 'stdin', 'file=PATH', 'unix=PATH' or 'otlp=HOST:PORT')")]
+pub struct SourceSpecError(String);
+
+/// Deliver each line of `reader` as output, then report a clean exit at the
+/// end of input, or a failed one if it cannot be read
+async fn read_lines<R>(
+    reader: R,
+    message_tx: mpsc::Sender<EvaluatorMessage>,
+    tap: Option<Arc<dyn OutputTap>>,
+) where
+    R: AsyncRead + Unpin,
+{
+    let success = match forward_lines(reader, &message_tx, tap.as_deref()).await {
+        Ok(true) => true,
+        Ok(false) => return,
+        Err(e) => {
+            tracing::warn!("Failed to read protocol messages: {}", e);
+            false
+        }
+    };
+    let _ = message_tx
+        .send(EvaluatorMessage::Exited(ExitStatus::new(success, None)))
+        .await;
+}
+
+/// Deliver each line of `reader` as output until the end of input
+///
+/// Returns false if nobody is receiving the messages any more.
+async fn forward_lines<R>(
+    reader: R,
+    message_tx: &mpsc::Sender<EvaluatorMessage>,
+    tap: Option<&dyn OutputTap>,
+) -> std::io::Result<bool>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if let Some(tap) = tap {
+            tap.stdout(&line);
+        }
+        if message_tx
+            .send(EvaluatorMessage::Output(line))
+            .await
+            .is_err()
+        {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_parse_from_the_command_line() {
+        assert_eq!("stdin".parse::<SourceSpec>().unwrap(), SourceSpec::Stdin);
+        assert_eq!(
+            "file=runs/eval.jsonl".parse::<SourceSpec>().unwrap(),
+            SourceSpec::File(PathBuf::from("runs/eval.jsonl"))
+        );
+        assert_eq!(
+            "otlp=127.0.0.1:4318".parse::<SourceSpec>().unwrap(),
+            SourceSpec::Otlp("127.0.0.1:4318".parse().unwrap())
+        );
+        assert!("otlp=localhost".parse::<SourceSpec>().is_err());
+        assert!("file=".parse::<SourceSpec>().is_err());
+        assert!("tcp=:4000".parse::<SourceSpec>().is_err());
+    }
+
+    #[tokio::test]
+    async fn file_replay_delivers_lines_then_exits() {
+        let path = std::env::temp_dir().join(format!("preval-replay-{}.jsonl", std::process::id()));
+        std::fs::write(
+            &path,
+            "{\"type\": \"handshake\"}\n{\"resourceMetrics\": []}\n",
+        )
+        .unwrap();
+        let (tx, mut rx) = mpsc::channel(10);
+
+        let _replay = FileReplay::new(path.clone()).start(tx, None).await.unwrap();
+        let mut lines = Vec::new();
+        let status = loop {
+            match rx.recv().await {
+                Some(EvaluatorMessage::Output(line)) => lines.push(line),
+                Some(EvaluatorMessage::Exited(status)) => break status,
+                other => panic!("unexpected message {:?}", other),
+            }
+        };
+        assert_eq!(
+            lines,
+            vec!["{\"type\": \"handshake\"}", "{\"resourceMetrics\": []}"]
+        );
+        assert!(status.success());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn a_listening_socket_reads_every_connection() {
+        use tokio::io::AsyncWriteExt;
+
+        let path = std::env::temp_dir().join(format!("preval-listen-{}.sock", std::process::id()));
+        let (tx, mut rx) = mpsc::channel(10);
+        let _listener = UnixSocket::new(path.clone())
+            .keep_listening(true)
+            .start(tx, None)
+            .await
+            .unwrap();
+
+        for line in ["{\"type\": \"handshake\"}\n", "{\"resourceMetrics\": []}\n"] {
+            let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+            stream.write_all(line.as_bytes()).await.unwrap();
+            stream.shutdown().await.unwrap();
+            match rx.recv().await {
+                Some(EvaluatorMessage::Output(received)) => assert_eq!(received, line.trim_end()),
+                other => panic!("unexpected message {:?}", other),
+            }
+        }
+        // Hanging up does not end the run
+        assert!(rx.try_recv().is_err());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_followed_file_survives_truncation_and_rotation() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("preval-tail-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("metrics.jsonl");
+        let mut tail = Tail::default();
+        assert!(tail.read_lines(&path).unwrap().is_empty());
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(b"first\nsec").unwrap();
+        assert_eq!(tail.read_lines(&path).unwrap(), ["first"]);
+        file.write_all(b"ond\n").unwrap();
+        assert_eq!(tail.read_lines(&path).unwrap(), ["second"]);
+
+        // Truncated and rewritten
+        std::fs::write(&path, "again\n").unwrap();
+        assert_eq!(tail.read_lines(&path).unwrap(), ["again"]);
+
+        // Rotated: the rest of the old file comes before the new one
+        file.write_all(b"last of old\n").unwrap();
+        std::fs::rename(&path, dir.join("metrics.jsonl.1")).unwrap();
+        std::fs::write(&path, "new\n").unwrap();
+        assert_eq!(tail.read_lines(&path).unwrap(), ["last of old"]);
+        assert_eq!(tail.read_lines(&path).unwrap(), ["new"]);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
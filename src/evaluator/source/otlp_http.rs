@@ -0,0 +1,297 @@
+//! OTLP/HTTP receiver, for evaluators that export their metrics with an
+//! OpenTelemetry SDK instead of writing them to stdout
+
+use super::{MetricSource, SourceFuture};
+use crate::evaluator::process::{EvaluatorMessage, EvaluatorProcess, ExitStatus, OutputTap};
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Notify};
+
+/// Path OTLP/HTTP exporters post metrics to
+const METRICS_PATH: &str = "/v1/metrics";
+
+/// Path the evaluator posts its handshake to before any metrics
+const HANDSHAKE_PATH: &str = "/v1/handshake";
+
+/// Path the evaluator posts to once it is done, ending the run
+const EXIT_PATH: &str = "/v1/exit";
+
+/// Most bytes of request line and headers read
+const MAX_HEAD_SIZE: usize = 8192;
+
+/// Largest request body accepted
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// An OTLP/HTTP endpoint PrEval listens on
+///
+/// Only the JSON encoding is understood: exporters must be set to
+/// `http/json`. The handshake is posted to `/v1/handshake` first, metrics to
+/// `/v1/metrics`, and a post to `/v1/exit` ends the run.
+#[derive(Debug, Clone)]
+pub struct OtlpHttp {
+    addr: SocketAddr,
+}
+
+impl OtlpHttp {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr }
+    }
+}
+
+impl MetricSource for OtlpHttp {
+    fn describe(&self) -> String {
+        format!("otlp http://{}", self.addr)
+    }
+
+    fn start(
+        &self,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+        tap: Option<Arc<dyn OutputTap>>,
+    ) -> SourceFuture<'_> {
+        Box::pin(async move {
+            let listener = TcpListener::bind(self.addr)
+                .await
+                .with_context(|| format!("Failed to listen for OTLP/HTTP on {}", self.addr))?;
+            tracing::info!("Receiving OTLP/HTTP on http://{}", listener.local_addr()?);
+            Ok(EvaluatorProcess::follow(receive(listener, message_tx, tap)))
+        })
+    }
+}
+
+/// Answer requests on `listener` until the evaluator posts to the exit path
+async fn receive(
+    listener: TcpListener,
+    message_tx: mpsc::Sender<EvaluatorMessage>,
+    tap: Option<Arc<dyn OutputTap>>,
+) {
+    let exited = Arc::new(Notify::new());
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((socket, _)) => {
+                    let message_tx = message_tx.clone();
+                    let tap = tap.clone();
+                    let exited = exited.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = answer(socket, &message_tx, tap.as_deref(), &exited).await {
+                            tracing::debug!("OTLP/HTTP request failed: {:#}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept OTLP/HTTP connection: {}", e),
+            },
+            _ = exited.notified() => return,
+        }
+    }
+}
+
+/// Read one request from `socket`, deliver what it carries and answer it
+async fn answer(
+    mut socket: TcpStream,
+    message_tx: &mpsc::Sender<EvaluatorMessage>,
+    tap: Option<&dyn OutputTap>,
+    exited: &Notify,
+) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 8192];
+    let head_end = loop {
+        if let Some(at) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break at + 4;
+        }
+        if request.len() >= MAX_HEAD_SIZE {
+            return respond(&mut socket, "431 Request Header Fields Too Large", "").await;
+        }
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&request[..head_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let header = |name: &str| {
+        head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    };
+    let content_length = header("content-length")
+        .and_then(|length| length.parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_BODY_SIZE {
+        return respond(&mut socket, "413 Payload Too Large", "").await;
+    }
+    if header("expect").is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue")) {
+        socket.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+    }
+
+    let mut body = request.split_off(head_end);
+    while body.len() < content_length {
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+    body.truncate(content_length);
+
+    match route(&method, &path, header("content-type").as_deref(), &body) {
+        Ok(message) => {
+            let exit = matches!(message, EvaluatorMessage::Exited(_));
+            if let (Some(tap), EvaluatorMessage::Output(line)) = (tap, &message) {
+                tap.stdout(line);
+            }
+            if message_tx.send(message).await.is_err() {
+                return respond(&mut socket, "503 Service Unavailable", "").await;
+            }
+            if exit {
+                exited.notify_one();
+            }
+            respond(&mut socket, "200 OK", "{}").await
+        }
+        Err((status, reason)) => respond(&mut socket, status, &reason).await,
+    }
+}
+
+/// Message a request delivers to the run, or the status and reason it is
+/// rejected with
+fn route(
+    method: &str,
+    path: &str,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<EvaluatorMessage, (&'static str, String)> {
+    let path = path.split('?').next().unwrap_or_default();
+    if ![METRICS_PATH, HANDSHAKE_PATH, EXIT_PATH].contains(&path) {
+        return Err((
+            "404 Not Found",
+            format!(
+                "Post the handshake to {}, metrics to {} and {} when done",
+                HANDSHAKE_PATH, METRICS_PATH, EXIT_PATH
+            ),
+        ));
+    }
+    if method != "POST" {
+        return Err(("405 Method Not Allowed", format!("{} takes POST", path)));
+    }
+    if path == EXIT_PATH {
+        return Ok(EvaluatorMessage::Exited(ExitStatus::new(true, None)));
+    }
+    if content_type.is_some_and(|content_type| content_type.contains("protobuf")) {
+        return Err((
+            "415 Unsupported Media Type",
+            "Only OTLP/JSON is supported; set the exporter protocol to http/json".to_string(),
+        ));
+    }
+    // Each message is one line of the protocol
+    let message: serde_json::Value = serde_json::from_slice(body)
+        .map_err(|e| ("400 Bad Request", format!("Invalid JSON: {}", e)))?;
+    Ok(EvaluatorMessage::Output(message.to_string()))
+}
+
+/// Write a response with `body` and close the connection
+async fn respond(socket: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    let content_type = if body.starts_with('{') {
+        "application/json"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    socket.write_all(response.as_bytes()).await?;
+    socket.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posted_messages_become_protocol_lines() {
+        let body = b"{\n  \"resourceMetrics\": []\n}";
+        match route("POST", METRICS_PATH, Some("application/json"), body) {
+            Ok(EvaluatorMessage::Output(line)) => assert_eq!(line, r#"{"resourceMetrics":[]}"#),
+            other => panic!("unexpected route {:?}", other),
+        }
+        assert!(matches!(
+            route("POST", EXIT_PATH, None, b""),
+            Ok(EvaluatorMessage::Exited(status)) if status.success()
+        ));
+    }
+
+    #[test]
+    fn other_requests_are_rejected() {
+        let status = |result: Result<EvaluatorMessage, (&'static str, String)>| match result {
+            Err((status, _)) => status,
+            Ok(message) => panic!("unexpected message {:?}", message),
+        };
+        assert_eq!(
+            status(route("POST", "/v1/traces", None, b"{}")),
+            "404 Not Found"
+        );
+        assert_eq!(
+            status(route("GET", METRICS_PATH, None, b"")),
+            "405 Method Not Allowed"
+        );
+        assert_eq!(
+            status(route(
+                "POST",
+                METRICS_PATH,
+                Some("application/x-protobuf"),
+                b"\x0a"
+            )),
+            "415 Unsupported Media Type"
+        );
+        assert_eq!(
+            status(route("POST", HANDSHAKE_PATH, None, b"{not json")),
+            "400 Bad Request"
+        );
+    }
+
+    #[tokio::test]
+    async fn the_endpoint_delivers_posts_until_exit() {
+        let source = OtlpHttp::new("127.0.0.1:0".parse().unwrap());
+        let listener = TcpListener::bind(source.addr).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, mut rx) = mpsc::channel(10);
+        let server = tokio::spawn(receive(listener, tx, None));
+
+        for (path, body) in [
+            (HANDSHAKE_PATH, "{\"type\": \"handshake\"}"),
+            (EXIT_PATH, ""),
+        ] {
+            let mut socket = TcpStream::connect(addr).await.unwrap();
+            let request = format!(
+                "POST {} HTTP/1.1\r\nhost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+                path,
+                body.len(),
+                body
+            );
+            socket.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            socket.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        }
+
+        assert!(
+            matches!(rx.recv().await, Some(EvaluatorMessage::Output(line)) if line == r#"{"type":"handshake"}"#)
+        );
+        assert!(matches!(rx.recv().await, Some(EvaluatorMessage::Exited(_))));
+        server.await.unwrap();
+    }
+}
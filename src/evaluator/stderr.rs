@@ -0,0 +1,58 @@
+use regex::RegexSet;
+
+/// Patterns for build-tool chatter that is hidden from the stderr pane by default
+///
+/// These cover `cargo run` wrappers, which print compilation progress to
+/// stderr before the evaluator itself starts.
+pub const DEFAULT_STDERR_FILTERS: &[&str] = &[
+    r"^\s*Compiling ",
+    r"^\s*Finished ",
+    r"^\s*Running ",
+    r"target[/\\]debug[/\\]deps[/\\]",
+    r"^\s*$",
+];
+
+/// Compiled set of regular expressions matching stderr lines to hide
+#[derive(Debug, Clone)]
+pub struct StderrFilter {
+    patterns: RegexSet,
+}
+
+impl StderrFilter {
+    /// Compile a filter from a list of regular expressions
+    pub fn new<I, S>(patterns: I) -> Result<Self, regex::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Ok(Self {
+            patterns: RegexSet::new(patterns)?,
+        })
+    }
+
+    /// Whether a stderr line should be hidden
+    pub fn is_filtered(&self, line: &str) -> bool {
+        self.patterns.is_match(line)
+    }
+}
+
+impl Default for StderrFilter {
+    fn default() -> Self {
+        Self::new(DEFAULT_STDERR_FILTERS).expect("default stderr filters are valid regexes")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_filter_hides_cargo_output() {
+        let filter = StderrFilter::default();
+
+        assert!(filter.is_filtered("   Compiling preval v0.1.0 (/root/crate)"));
+        assert!(filter.is_filtered("     Running `target/debug/mock_evaluator`"));
+        assert!(filter.is_filtered(r"     Running `target\debug\deps\mock.exe`"));
+        assert!(!filter.is_filtered("Traceback (most recent call last):"));
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+/// Policy for restarting an evaluator that crashes before finishing
+///
+/// Restarts back off exponentially: the first waits `initial_backoff`, and
+/// each one after doubles the wait, up to `max_backoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    pub max_restarts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RestartPolicy {
+    /// Create a policy allowing up to `max_restarts` restarts
+    pub fn new(max_restarts: u32, initial_backoff: Duration) -> Self {
+        Self {
+            max_restarts,
+            initial_backoff,
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+
+    /// Whether another restart is allowed after `restarts_so_far`
+    pub fn allows_restart(&self, restarts_so_far: u32) -> bool {
+        restarts_so_far < self.max_restarts
+    }
+
+    /// Delay before the given restart (1-based)
+    pub fn backoff_for(&self, restart: u32) -> Duration {
+        let doublings = restart.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RestartPolicy {
+            max_restarts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+
+        assert_eq!(policy.backoff_for(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff_for(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff_for(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff_for(4), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn restarts_are_limited() {
+        let policy = RestartPolicy::new(2, Duration::from_secs(1));
+
+        assert!(policy.allows_restart(0));
+        assert!(policy.allows_restart(1));
+        assert!(!policy.allows_restart(2));
+    }
+}
@@ -0,0 +1,370 @@
+// Live web dashboard of a run, for watching it from a browser
+
+use super::report::Report;
+use crate::history::status::RunStatus;
+use crate::state::app::AppData;
+use crate::state::publisher::StateSnapshot;
+use crate::state::types::SampleStatus;
+use anyhow::{Context, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+/// Path of the dashboard page
+const PAGE_PATH: &str = "/";
+
+/// Path of the latest state as JSON
+const STATE_PATH: &str = "/state.json";
+
+/// Path of the server-sent events stream pushing every new state
+const EVENTS_PATH: &str = "/events";
+
+/// Path of the WebSocket pushing every new state, for clients that prefer
+/// it to server-sent events
+const WEBSOCKET_PATH: &str = "/ws";
+
+/// The dashboard page, rendering the states pushed on the events stream
+const PAGE: &str = include_str!("dashboard.html");
+
+/// Least time between two states pushed to one browser
+const PUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Number of most recent samples listed on the dashboard
+const RECENT_SAMPLES: usize = 20;
+
+/// What every request to the dashboard is answered from
+struct Dashboard {
+    run_id: String,
+    /// Token every request must carry, when one is set
+    token: Option<String>,
+    snapshots: watch::Receiver<StateSnapshot>,
+}
+
+impl Dashboard {
+    /// The latest state as JSON, marking it seen
+    fn latest_json(&self, snapshots: &mut watch::Receiver<StateSnapshot>) -> String {
+        let state = snapshots.borrow_and_update().clone();
+        serde_json::to_string(&DashboardState::of(&state, &self.run_id))
+            .unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// Serve a live dashboard of the run `run_id` on `addr`, pushing every
+/// snapshot published to `snapshots`, until the process exits
+///
+/// With a `token`, requests without it in a `token` query parameter or a
+/// bearer authorization header are refused. Binding happens before
+/// returning, so a port already in use fails the run instead of going
+/// unnoticed.
+pub async fn serve(
+    addr: SocketAddr,
+    run_id: String,
+    token: Option<String>,
+    snapshots: watch::Receiver<StateSnapshot>,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to serve the dashboard on {}", addr))?;
+    let local_addr = listener.local_addr()?;
+    let dashboard = Arc::new(Dashboard {
+        run_id,
+        token,
+        snapshots,
+    });
+    let router = Router::new()
+        .route(PAGE_PATH, get(|| async { Html(PAGE) }))
+        .route(STATE_PATH, get(state_json))
+        .route(EVENTS_PATH, get(events))
+        .route(WEBSOCKET_PATH, get(websocket))
+        .fallback(not_found)
+        .layer(middleware::from_fn_with_state(
+            dashboard.clone(),
+            require_token,
+        ))
+        .with_state(dashboard);
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            tracing::warn!("Dashboard stopped: {}", e);
+        }
+    });
+    Ok(local_addr)
+}
+
+/// Query parameters the dashboard reads itself
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// Refuse requests without the dashboard's token, when it has one
+async fn require_token(
+    State(dashboard): State<Arc<Dashboard>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = &dashboard.token else {
+        return next.run(request).await;
+    };
+    // Decoded, as browsers percent-encode what the token shares with URLs
+    let from_query = Query::<TokenQuery>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(query)| query.token);
+    let from_header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if from_query
+        .as_deref()
+        .into_iter()
+        .chain(from_header)
+        .any(|given| same_token(given, token))
+    {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::UNAUTHORIZED,
+            "Missing or wrong dashboard token\n",
+        )
+            .into_response()
+    }
+}
+
+/// Whether `given` is `token`, taking as long whichever byte differs
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |differ, (a, b)| differ | (a ^ b))
+            == 0
+}
+
+/// The latest state
+async fn state_json(State(dashboard): State<Arc<Dashboard>>) -> Response {
+    let mut snapshots = dashboard.snapshots.clone();
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        dashboard.latest_json(&mut snapshots),
+    )
+        .into_response()
+}
+
+/// Every new state as a server-sent event, ending with an `end` event
+/// once the run is over
+async fn events(
+    State(dashboard): State<Arc<Dashboard>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let pushed = states(dashboard).map(|json| {
+        Ok(match json {
+            Some(json) => Event::default().data(json),
+            None => Event::default().event("end").data("{}"),
+        })
+    });
+    Sse::new(pushed).keep_alive(KeepAlive::default())
+}
+
+/// Every new state as a text message, closing once the run is over
+async fn websocket(State(dashboard): State<Arc<Dashboard>>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(|mut socket: WebSocket| async move {
+        let mut pushed = std::pin::pin!(states(dashboard));
+        while let Some(json) = pushed.next().await {
+            let message = match json {
+                Some(json) => Message::Text(json.into()),
+                None => Message::Close(None),
+            };
+            if socket.send(message).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// The current state, then each new one at most every `PUSH_INTERVAL`,
+/// then None once the run is over and its final state was pushed
+fn states(dashboard: Arc<Dashboard>) -> impl Stream<Item = Option<String>> {
+    let snapshots = dashboard.snapshots.clone();
+    stream::unfold(Some((snapshots, true)), move |next| {
+        let dashboard = dashboard.clone();
+        async move {
+            let (mut snapshots, first) = next?;
+            if !first {
+                tokio::time::sleep(PUSH_INTERVAL).await;
+                if snapshots.changed().await.is_err() {
+                    return Some((None, None));
+                }
+            }
+            let json = dashboard.latest_json(&mut snapshots);
+            Some((Some(json), Some((snapshots, false))))
+        }
+    })
+}
+
+/// Where the dashboard is, for any other path
+async fn not_found() -> Response {
+    let body = format!(
+        "The dashboard is served on {}, its state on {}, {} and {}\n",
+        PAGE_PATH, STATE_PATH, EVENTS_PATH, WEBSOCKET_PATH
+    );
+    (StatusCode::NOT_FOUND, body).into_response()
+}
+
+/// Everything the dashboard shows of a run
+#[derive(Debug, Serialize)]
+struct DashboardState {
+    /// Progress, rates and the latest problem, as in `status.json`
+    status: RunStatus,
+    /// Statistics of every metric, failed samples and threshold rules
+    report: Report,
+    /// The most recent samples, newest first
+    recent_samples: Vec<RecentSample>,
+}
+
+/// A sample listed on the dashboard
+#[derive(Debug, Serialize)]
+struct RecentSample {
+    id: String,
+    /// "processing", "completed" or "failed"
+    status: &'static str,
+    error: Option<String>,
+    metrics: Vec<(String, f64)>,
+}
+
+impl DashboardState {
+    /// What the dashboard shows of the run `run_id` in `state`
+    fn of(state: &AppData, run_id: &str) -> Self {
+        Self {
+            status: RunStatus::of(state, run_id),
+            report: Report::of(state),
+            recent_samples: state
+                .sample_store()
+                .recent(RECENT_SAMPLES)
+                .rev()
+                .map(|sample| {
+                    let (status, error) = match &sample.status {
+                        SampleStatus::Processing => ("processing", None),
+                        SampleStatus::Completed => ("completed", None),
+                        SampleStatus::Failed(error) => ("failed", Some(error.clone())),
+                    };
+                    RecentSample {
+                        id: sample.sample_id.clone(),
+                        status,
+                        error,
+                        metrics: sample.metrics.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RunState;
+
+    #[tokio::test]
+    async fn the_dashboard_serves_and_streams_the_latest_snapshot() {
+        let (sender, snapshots) = watch::channel(Arc::new(RunState::default()));
+        let addr = serve(
+            "127.0.0.1:0".parse().unwrap(),
+            "run-1".to_string(),
+            None,
+            snapshots,
+        )
+        .await
+        .unwrap();
+        let client = reqwest::Client::new();
+        let get = |path: &str| client.get(format!("http://{}{}", addr, path)).send();
+
+        let page = get("/").await.unwrap();
+        assert!(page.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html"));
+        assert!(page.text().await.unwrap().contains(EVENTS_PATH));
+
+        let state: serde_json::Value = get("/state.json").await.unwrap().json().await.unwrap();
+        assert_eq!(state["status"]["run_id"], "run-1");
+        assert_eq!(state["status"]["status"], "starting");
+        assert_eq!(state["recent_samples"], serde_json::json!([]));
+
+        // The stream pushes the current state, then ends with the run
+        let events = get("/events").await.unwrap();
+        assert_eq!(events.headers()[header::CONTENT_TYPE], "text/event-stream");
+        drop(sender);
+        let events = events.text().await.unwrap();
+        assert!(events.starts_with("data: {\"status\":{"));
+        assert!(events.ends_with("event: end\ndata: {}\n\n"));
+
+        assert_eq!(get("/metrics").await.unwrap().status(), 404);
+    }
+
+    #[tokio::test]
+    async fn a_token_keeps_out_requests_without_it() {
+        let (_sender, snapshots) = watch::channel(Arc::new(RunState::default()));
+        let addr = serve(
+            "127.0.0.1:0".parse().unwrap(),
+            "run-1".to_string(),
+            Some("s3cret".to_string()),
+            snapshots,
+        )
+        .await
+        .unwrap();
+        let client = reqwest::Client::new();
+        let url = |path: &str| format!("http://{}{}", addr, path);
+        let status = |request: reqwest::RequestBuilder| async move {
+            request.send().await.unwrap().status().as_u16()
+        };
+
+        assert_eq!(status(client.get(url("/state.json"))).await, 401);
+        assert_eq!(status(client.get(url("/?token=wrong"))).await, 401);
+        assert_eq!(status(client.get(url("/?token=s3cret"))).await, 200);
+        assert_eq!(
+            status(client.get(url("/state.json")).bearer_auth("s3cret")).await,
+            200
+        );
+    }
+
+    #[tokio::test]
+    async fn a_token_is_compared_once_decoded_from_the_query() {
+        let token = "a+b/c=d&e%f g";
+        let (_sender, snapshots) = watch::channel(Arc::new(RunState::default()));
+        let addr = serve(
+            "127.0.0.1:0".parse().unwrap(),
+            "run-1".to_string(),
+            Some(token.to_string()),
+            snapshots,
+        )
+        .await
+        .unwrap();
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/state.json", addr);
+        let status = |request: reqwest::RequestBuilder| async move {
+            request.send().await.unwrap().status().as_u16()
+        };
+
+        assert_eq!(
+            status(client.get(&url).query(&[("token", token)])).await,
+            200
+        );
+        assert_eq!(
+            status(client.get(format!("{}?token={}", url, token.replace(' ', "%20")))).await,
+            401
+        );
+        assert_eq!(status(client.get(&url).bearer_auth(token)).await, 200);
+    }
+}
@@ -0,0 +1,232 @@
+// Log of the normalized events PrEval derives from an evaluator's output,
+// as a stable stream for other tools that does not depend on raw OTLP
+
+use crate::state::app::AppData;
+use crate::state::timeline::{TimelineEvent, TimelineEventKind};
+use crate::state::types::{SampleResult, SampleStatus};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// One line of the event log
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoggedEvent {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: RunEvent,
+}
+
+/// What happened, tagged with its `type`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunEvent {
+    /// The evaluator introduced itself
+    Handshake {
+        evaluator: String,
+        version: Option<String>,
+        protocol_version: String,
+        total_samples: Option<u32>,
+    },
+    /// The run moved to another phase, e.g. "Collecting metrics" or "Completed"
+    StateTransition { status: String },
+    /// A sample was recorded, failed when it broke a threshold rule
+    Sample {
+        sample_id: String,
+        status: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        metrics: BTreeMap<String, f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        duration_ms: Option<u64>,
+    },
+    /// A sample or the run broke a threshold rule
+    ThresholdTrip { message: String },
+    /// A line from the evaluator could not be parsed
+    Error { message: String },
+    /// Anything else on the run's timeline, such as a pause or restart
+    Other {
+        kind: TimelineEventKind,
+        message: String,
+    },
+    /// The user restarted the run; counts start over after this
+    RunRestarted,
+}
+
+impl RunEvent {
+    /// Event for `event` from the timeline of the run in `state`
+    fn from_timeline(event: &TimelineEvent, state: &AppData) -> Self {
+        match event.kind {
+            TimelineEventKind::Handshake => match state.handshake() {
+                Some(handshake) => RunEvent::Handshake {
+                    evaluator: handshake.evaluator.name.to_string(),
+                    version: handshake.evaluator.version.clone(),
+                    protocol_version: handshake.version.to_string(),
+                    total_samples: handshake
+                        .execution_plan
+                        .as_ref()
+                        .map(|plan| plan.total_samples.into_inner()),
+                },
+                None => RunEvent::Other {
+                    kind: event.kind,
+                    message: event.message.clone(),
+                },
+            },
+            TimelineEventKind::Phase => RunEvent::StateTransition {
+                status: event.message.clone(),
+            },
+            TimelineEventKind::ThresholdViolation => RunEvent::ThresholdTrip {
+                message: event.message.clone(),
+            },
+            TimelineEventKind::ParseError => RunEvent::Error {
+                message: event.message.clone(),
+            },
+            kind => RunEvent::Other {
+                kind,
+                message: event.message.clone(),
+            },
+        }
+    }
+
+    /// Event for recording `sample`
+    fn from_sample(sample: &SampleResult) -> Self {
+        let (status, error) = match &sample.status {
+            SampleStatus::Processing => ("processing", None),
+            SampleStatus::Completed => ("completed", None),
+            SampleStatus::Failed(error) => ("failed", Some(error.clone())),
+        };
+        RunEvent::Sample {
+            sample_id: sample.sample_id.clone(),
+            status,
+            error,
+            metrics: sample.metrics.iter().cloned().collect(),
+            duration_ms: sample.duration.map(|d| d.as_millis() as u64),
+        }
+    }
+}
+
+/// JSON Lines file the events of a run are appended to as they happen
+pub struct EventLog {
+    file: BufWriter<File>,
+    /// Timeline events of the run already written
+    timeline_written: usize,
+    /// Samples of the run already written
+    samples_written: usize,
+}
+
+impl EventLog {
+    /// Start a new event log at `path`, replacing any file there
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create event log {}", path.display()))?;
+        Ok(Self {
+            file: BufWriter::new(file),
+            timeline_written: 0,
+            samples_written: 0,
+        })
+    }
+
+    /// Write what happened in `state` since the last call: the samples
+    /// recorded, then the events on its timeline
+    pub fn catch_up(&mut self, state: &AppData) -> Result<()> {
+        let store = state.sample_store();
+        if store.len() > self.samples_written {
+            let samples = store
+                .page(self.samples_written, store.len() - self.samples_written)
+                .context("Failed to read samples for the event log")?;
+            for sample in &samples {
+                let at = sample.completed_at.unwrap_or_else(Utc::now);
+                self.write(at, RunEvent::from_sample(sample))?;
+            }
+            self.samples_written = store.len();
+        }
+
+        let timeline = state.timeline();
+        let new = timeline.recorded() - self.timeline_written;
+        if new > 0 {
+            // Events dropped from the timeline before they were written are lost
+            for event in timeline.events().skip(timeline.len().saturating_sub(new)) {
+                self.write(event.at, RunEvent::from_timeline(event, state))?;
+            }
+            self.timeline_written = timeline.recorded();
+        }
+        self.file.flush().context("Failed to write event log")
+    }
+
+    /// Note that the run started over, so the counts of the fresh run are
+    /// followed from the start
+    pub fn restart(&mut self) -> Result<()> {
+        self.write(Utc::now(), RunEvent::RunRestarted)?;
+        self.timeline_written = 0;
+        self.samples_written = 0;
+        self.file.flush().context("Failed to write event log")
+    }
+
+    fn write(&mut self, at: DateTime<Utc>, event: RunEvent) -> Result<()> {
+        let line =
+            serde_json::to_string(&LoggedEvent { at, event }).context("Failed to encode event")?;
+        writeln!(self.file, "{}", line).context("Failed to write event log")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RunState;
+
+    fn lines(path: &Path) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn events_are_written_once_as_they_happen() {
+        let path = std::env::temp_dir().join(format!("preval-events-{}.jsonl", std::process::id()));
+        let mut log = EventLog::create(&path).unwrap();
+        let mut state = RunState::default();
+
+        state.record_event(
+            TimelineEventKind::ParseError,
+            "Failed to parse metrics: eof",
+        );
+        log.catch_up(&state).unwrap();
+        log.catch_up(&state).unwrap();
+        state.record_event(TimelineEventKind::Pause, "Evaluator paused");
+        log.catch_up(&state).unwrap();
+        log.restart().unwrap();
+
+        let events = lines(&path);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["type"], "error");
+        assert_eq!(events[0]["message"], "Failed to parse metrics: eof");
+        assert_eq!(events[1]["type"], "other");
+        assert_eq!(events[1]["kind"], "pause");
+        assert_eq!(events[2]["type"], "run_restarted");
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn samples_carry_their_outcome_and_metrics() {
+        let mut sample = SampleResult::new_processing("q-1".to_string());
+        sample.mark_completed(vec![("accuracy".to_string(), 0.5)]);
+        sample.mark_failed("accuracy = 0.5 violates accuracy >= 0.8".to_string());
+
+        let json = serde_json::to_value(LoggedEvent {
+            at: Utc::now(),
+            event: RunEvent::from_sample(&sample),
+        })
+        .unwrap();
+        assert_eq!(json["type"], "sample");
+        assert_eq!(json["sample_id"], "q-1");
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["error"], "accuracy = 0.5 violates accuracy >= 0.8");
+        assert_eq!(json["metrics"]["accuracy"], 0.5);
+        assert!(json.get("duration_ms").is_none());
+    }
+}
@@ -0,0 +1,183 @@
+// GitHub Actions annotations and job summary of a finished run
+
+use crate::export::report::Report;
+use crate::state::app::AppData;
+use crate::state::rules::ThresholdRule;
+use crate::state::types::{EvaluationStatus, SampleResult, SampleStatus};
+use anyhow::{Context, Result};
+use std::io::Write as _;
+use std::path::Path;
+
+/// Environment variable GitHub Actions sets to `true` in every step
+pub(crate) const GITHUB_ACTIONS_ENV: &str = "GITHUB_ACTIONS";
+
+/// Environment variable naming the file a step appends its summary to
+const STEP_SUMMARY_ENV: &str = "GITHUB_STEP_SUMMARY";
+
+/// Most samples annotated, so a run with many failures does not bury the log
+const MAX_ANNOTATIONS: usize = 50;
+
+/// Whether PrEval runs in a GitHub Actions step
+pub(crate) fn detected() -> bool {
+    std::env::var(GITHUB_ACTIONS_ENV).is_ok_and(|value| value == "true")
+}
+
+/// Print annotations for the run in `state` as workflow commands on stdout,
+/// and add its report to the job summary when the step has one
+pub(crate) fn publish(state: &AppData) -> Result<()> {
+    let samples = state
+        .sample_store()
+        .read_available()
+        .context("Failed to read samples for GitHub Actions annotations")?;
+    let failure = match state.status() {
+        EvaluationStatus::Failed(reason) => Some(reason.as_str()),
+        _ => None,
+    };
+    for command in annotations(failure, &samples, state.warning_rules()) {
+        println!("{}", command);
+    }
+
+    if let Some(path) = std::env::var_os(STEP_SUMMARY_ENV) {
+        append_summary(Path::new(&path), &Report::of(state).to_markdown())?;
+    }
+    Ok(())
+}
+
+/// Workflow commands annotating the run: an error for the run failing and
+/// for each failed sample, and a warning for each sample breaking a
+/// warning rule
+pub(crate) fn annotations(
+    failure: Option<&str>,
+    samples: &[SampleResult],
+    warning_rules: &[ThresholdRule],
+) -> Vec<String> {
+    let mut commands = Vec::new();
+    if let Some(reason) = failure {
+        commands.push(command("error", "PrEval run failed", reason));
+    }
+
+    let mut annotated = 0;
+    let mut left_out = 0;
+    for sample in samples {
+        let (level, message) = match &sample.status {
+            SampleStatus::Failed(error) => ("error", error.clone()),
+            _ => {
+                let warnings: Vec<String> = warning_rules
+                    .iter()
+                    .filter_map(|rule| rule.check_sample(sample))
+                    .collect();
+                if warnings.is_empty() {
+                    continue;
+                }
+                ("warning", warnings.join("; "))
+            }
+        };
+        if annotated == MAX_ANNOTATIONS {
+            left_out += 1;
+            continue;
+        }
+        annotated += 1;
+        let title = format!("Sample {}", sample.sample_id);
+        commands.push(command(level, &title, &message));
+    }
+    if left_out > 0 {
+        commands.push(format!(
+            "::notice title=PrEval::{} more samples failed or broke a warning rule; see the job summary",
+            left_out
+        ));
+    }
+    commands
+}
+
+/// Append `markdown` to the job summary file at `path`
+pub(crate) fn append_summary(path: &Path, markdown: &str) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open job summary {}", path.display()))?;
+    writeln!(file, "{}", markdown)
+        .with_context(|| format!("Failed to write job summary {}", path.display()))
+}
+
+/// Workflow command annotating the step at `level` with `title` and `message`
+fn command(level: &str, title: &str, message: &str) -> String {
+    format!(
+        "::{} title={}::{}",
+        level,
+        escape_property(title),
+        escape_data(message)
+    )
+}
+
+/// `text` escaped for the message of a workflow command
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// `text` escaped for a property of a workflow command
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str, accuracy: f64) -> SampleResult {
+        let mut sample = SampleResult::new_processing(id.to_string());
+        sample.mark_completed(vec![("accuracy".to_string(), accuracy)]);
+        sample
+    }
+
+    #[test]
+    fn failed_samples_and_warnings_become_annotations() {
+        let mut failed = sample("q:1", 0.2);
+        failed.mark_failed("accuracy = 0.2 violates accuracy >= 0.5\n100% off".to_string());
+        let samples = vec![failed, sample("q2", 0.6), sample("q3", 0.9)];
+        let warnings = vec!["accuracy >= 0.8".parse().unwrap()];
+
+        assert_eq!(
+            annotations(Some("accuracy.mean >= 0.9 was 0.567"), &samples, &warnings),
+            vec![
+                "::error title=PrEval run failed::accuracy.mean >= 0.9 was 0.567".to_string(),
+                "::error title=Sample q%3A1::accuracy = 0.2 violates accuracy >= 0.5%0A100%25 off"
+                    .to_string(),
+                "::warning title=Sample q2::accuracy = 0.6 violates accuracy >= 0.8".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn annotations_are_capped() {
+        let samples: Vec<SampleResult> = (0..MAX_ANNOTATIONS + 3)
+            .map(|i| {
+                let mut sample = sample(&format!("q{}", i), 0.0);
+                sample.mark_failed("broken".to_string());
+                sample
+            })
+            .collect();
+
+        let commands = annotations(None, &samples, &[]);
+        assert_eq!(commands.len(), MAX_ANNOTATIONS + 1);
+        assert!(commands
+            .last()
+            .unwrap()
+            .starts_with("::notice title=PrEval::3 more samples"));
+    }
+
+    #[test]
+    fn summaries_are_appended_to_the_step_summary() {
+        let path = std::env::temp_dir().join(format!("preval-gha-summary-{}", std::process::id()));
+        std::fs::write(&path, "## Earlier step\n").unwrap();
+
+        append_summary(&path, "## PrEval run").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "## Earlier step\n## PrEval run\n"
+        );
+        std::fs::remove_file(&path).ok();
+    }
+}
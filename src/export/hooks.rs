@@ -0,0 +1,376 @@
+// Shell commands run when a run ends or first breaks a threshold rule, for
+// environments where a webhook cannot reach anything
+
+use crate::export::webhook::{WebhookEvent, WebhookNotice};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+/// Longest PrEval waits for a hook to exit before leaving it running
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Commands run on the events of a run
+///
+/// `{status}`, `{evaluator}`, `{run_id}`, `{message}`, `{passed}`,
+/// `{failed}`, `{completed}`, `{total}` and `{elapsed}` are replaced by
+/// references to `PREVAL_STATUS`, `PREVAL_EVALUATOR` and so on, which hold
+/// the values; the evaluator picks its own name and messages, so they must
+/// never be parsed by the shell.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Run when the run ends without failing
+    pub on_complete: Option<String>,
+    /// Run when the run fails
+    pub on_failure: Option<String>,
+    /// Run when a sample first breaks a threshold rule
+    pub on_threshold: Option<String>,
+}
+
+impl HooksConfig {
+    /// Whether no hook is set
+    pub(crate) fn is_empty(&self) -> bool {
+        self.on_complete.is_none() && self.on_failure.is_none() && self.on_threshold.is_none()
+    }
+
+    /// Command run for `notice`, with its placeholders replaced by
+    /// references to the variables holding their values
+    pub(crate) fn command(&self, notice: &WebhookNotice) -> Option<String> {
+        let template = match notice.event {
+            WebhookEvent::Started => None,
+            WebhookEvent::Completed => self.on_complete.as_ref(),
+            WebhookEvent::Failed => self.on_failure.as_ref(),
+            WebhookEvent::Threshold => self.on_threshold.as_ref(),
+        }?;
+        let names = variables(notice).map(|(name, _)| name);
+        Some(fill_in(template, &names))
+    }
+}
+
+/// Values of a notice a hook can use, by name
+fn variables(notice: &WebhookNotice) -> [(&'static str, String); 9] {
+    [
+        ("status", notice.event.name().to_string()),
+        ("evaluator", notice.evaluator.clone().unwrap_or_default()),
+        ("run_id", notice.run_id.clone().unwrap_or_default()),
+        ("message", notice.message.clone()),
+        (
+            "passed",
+            notice
+                .samples_completed
+                .saturating_sub(notice.samples_failed)
+                .to_string(),
+        ),
+        ("failed", notice.samples_failed.to_string()),
+        ("completed", notice.samples_completed.to_string()),
+        (
+            "total",
+            notice
+                .total_samples
+                .unwrap_or(notice.samples_completed)
+                .to_string(),
+        ),
+        ("elapsed", format!("{:.0}", notice.elapsed_secs)),
+    ]
+}
+
+/// Quotes a placeholder of a POSIX shell command sits in
+#[cfg(not(windows))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Quoting {
+    None,
+    Single,
+    Double,
+}
+
+/// `template` with each `{name}` replaced by a reference to `PREVAL_NAME`
+///
+/// The shell expands a reference inside double quotes without parsing the
+/// value, so each one is double quoted, closing and reopening the single
+/// quotes of the template around it where it sits in them.
+#[cfg(not(windows))]
+fn fill_in(template: &str, names: &[&str]) -> String {
+    let mut command = String::new();
+    let mut quoting = Quoting::None;
+    let mut rest = template;
+    while let Some(c) = rest.chars().next() {
+        let placeholder = names.iter().find(|name| {
+            rest.strip_prefix('{')
+                .and_then(|r| r.strip_prefix(**name))
+                .is_some_and(|r| r.starts_with('}'))
+        });
+        if let Some(name) = placeholder {
+            let variable = format!("${{PREVAL_{}}}", name.to_uppercase());
+            match quoting {
+                Quoting::None => command.push_str(&format!("\"{}\"", variable)),
+                Quoting::Single => command.push_str(&format!("'\"{}\"'", variable)),
+                Quoting::Double => command.push_str(&variable),
+            }
+            rest = &rest[name.len() + 2..];
+            continue;
+        }
+
+        command.push(c);
+        rest = &rest[c.len_utf8()..];
+        match (quoting, c) {
+            // An escaped character is taken as is
+            (Quoting::None | Quoting::Double, '\\') => {
+                if let Some(escaped) = rest.chars().next() {
+                    command.push(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                }
+            }
+            (Quoting::None, '\'') => quoting = Quoting::Single,
+            (Quoting::None, '"') => quoting = Quoting::Double,
+            (Quoting::Single, '\'') | (Quoting::Double, '"') => quoting = Quoting::None,
+            _ => {}
+        }
+    }
+    command
+}
+
+/// `template` with each `{name}` replaced by a reference to `PREVAL_NAME`
+///
+/// cmd.exe has no quoting that holds every character, so the command
+/// reads each value from its environment variable, expanded only after
+/// the command is parsed.
+#[cfg(windows)]
+fn fill_in(template: &str, names: &[&str]) -> String {
+    names.iter().fold(template.to_string(), |command, name| {
+        command.replace(
+            &format!("{{{}}}", name),
+            &format!("\"!PREVAL_{}!\"", name.to_uppercase()),
+        )
+    })
+}
+
+/// Hooks of a run and the ones still running
+///
+/// Hooks never hold up the run; one that fails only gets a warning in the
+/// log.
+pub(crate) struct CommandHooks {
+    config: HooksConfig,
+    running: Vec<JoinHandle<()>>,
+}
+
+impl CommandHooks {
+    pub(crate) fn new(config: HooksConfig) -> Self {
+        Self {
+            config,
+            running: Vec::new(),
+        }
+    }
+
+    /// Start the hook for `notice`'s event, if one is set
+    pub(crate) fn run(&mut self, notice: &WebhookNotice) {
+        let Some(command) = self.config.command(notice) else {
+            return;
+        };
+        let mut shell = shell_command(&command);
+        for (name, value) in variables(notice) {
+            shell.env(format!("PREVAL_{}", name.to_uppercase()), value);
+        }
+        // Output would draw over the TUI
+        let spawned = shell
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                tracing::warn!("Failed to run hook `{}`: {}", command, e);
+                return;
+            }
+        };
+
+        self.running.retain(|task| !task.is_finished());
+        self.running.push(tokio::spawn(async move {
+            match tokio::time::timeout(HOOK_TIMEOUT, child.wait()).await {
+                Ok(Ok(status)) if !status.success() => {
+                    tracing::warn!("Hook `{}` exited with {}", command, status);
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => tracing::warn!("Failed to wait for hook `{}`: {}", command, e),
+                Err(_) => tracing::warn!(
+                    "Hook `{}` still running after {}s; leaving it be",
+                    command,
+                    HOOK_TIMEOUT.as_secs()
+                ),
+            }
+        }));
+    }
+
+    /// Wait a while for the hooks still running, e.g. before PrEval exits
+    pub(crate) async fn finish(self) {
+        for task in self.running {
+            if task.await.is_err() {
+                tracing::warn!("Hook task panicked");
+            }
+        }
+    }
+}
+
+/// Command running `command` through the platform's shell
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd.exe");
+    // Delayed expansion, for the values filled in by `quote`
+    cmd.arg("/V:ON").arg("/C").arg(command);
+    cmd
+}
+
+/// Command running `command` through the platform's shell
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notice(event: WebhookEvent) -> WebhookNotice {
+        WebhookNotice {
+            event,
+            evaluator: Some("python eval.py".to_string()),
+            run_id: Some("20260102T030405Z-1".to_string()),
+            message: "Run completed".to_string(),
+            samples_completed: 10,
+            samples_failed: 2,
+            total_samples: Some(12),
+            elapsed_secs: 75.4,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_are_filled_in_for_their_event() {
+        let hooks: HooksConfig = toml::from_str(
+            r#"
+            on_complete = "notify-send PrEval {evaluator}"
+            on_threshold = "echo {run_id} {failed} {elapsed}s"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            hooks.command(&notice(WebhookEvent::Completed)).as_deref(),
+            Some(r#"notify-send PrEval "${PREVAL_EVALUATOR}""#)
+        );
+        assert_eq!(
+            hooks.command(&notice(WebhookEvent::Threshold)).as_deref(),
+            Some(r#"echo "${PREVAL_RUN_ID}" "${PREVAL_FAILED}" "${PREVAL_ELAPSED}"s"#)
+        );
+        assert_eq!(hooks.command(&notice(WebhookEvent::Failed)), None);
+        assert_eq!(hooks.command(&notice(WebhookEvent::Started)), None);
+        assert!(toml::from_str::<HooksConfig>("on_start = \"true\"").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn hooks_see_the_run_in_their_environment() {
+        let path = std::env::temp_dir().join(format!("preval-hook-{}", std::process::id()));
+        let mut hooks = CommandHooks::new(HooksConfig {
+            on_failure: Some(format!(
+                "printf '%s %s' \"$PREVAL_STATUS\" \"$PREVAL_EVALUATOR\" > {}",
+                path.display()
+            )),
+            ..HooksConfig::default()
+        });
+
+        hooks.run(&notice(WebhookEvent::Failed));
+        hooks.finish().await;
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "failed python eval.py"
+        );
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn filled_in_values_cannot_run_commands() {
+        let dir = std::env::temp_dir().join(format!("preval-hook-quote-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut hooks = CommandHooks::new(HooksConfig {
+            on_complete: Some(format!("cd {} && echo {{evaluator}} > out", dir.display())),
+            ..HooksConfig::default()
+        });
+
+        hooks.run(&WebhookNotice {
+            evaluator: Some("x; touch pwned".to_string()),
+            ..notice(WebhookEvent::Completed)
+        });
+        hooks.finish().await;
+        let written = std::fs::read_to_string(dir.join("out")).unwrap();
+        let pwned = dir.join("pwned").exists();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(written, "x; touch pwned\n");
+        assert!(!pwned, "the evaluator's name ran as a command");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn placeholders_inside_quotes_cannot_run_commands() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("preval-hook-quoted-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Stands in for notify-send, recording its arguments
+        let notify_send = dir.join("notify-send");
+        std::fs::write(&notify_send, "#!/bin/sh\nprintf '%s\\n' \"$@\" > out\n").unwrap();
+        std::fs::set_permissions(&notify_send, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let template = "notify-send 'PrEval' '{status}: {passed}/{total}'";
+        let mut hooks = CommandHooks::new(HooksConfig {
+            on_failure: Some(format!(
+                "cd {} && PATH=\"$PWD:$PATH\" {} '{{message}}' \"{{message}}\"",
+                dir.display(),
+                template
+            )),
+            ..HooksConfig::default()
+        });
+
+        let message = "a < b $(touch pwned) ' ; touch pwned ; '";
+        hooks.run(&WebhookNotice {
+            message: message.to_string(),
+            ..notice(WebhookEvent::Failed)
+        });
+        hooks.finish().await;
+        let written = std::fs::read_to_string(dir.join("out")).unwrap();
+        let pwned = dir.join("pwned").exists();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(
+            written,
+            format!("PrEval\nfailed: 8/12\n{}\n{}\n", message, message)
+        );
+        assert!(!pwned, "the message ran as a command");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn placeholders_are_filled_in_whatever_quotes_they_are_in() {
+        let hooks = HooksConfig {
+            on_complete: Some("notify-send 'PrEval' '{status}: {passed}/{total}'".to_string()),
+            on_failure: Some(r#"echo "{message} \" {run_id}" '\{elapsed}' \'{failed}"#.to_string()),
+            ..HooksConfig::default()
+        };
+
+        assert_eq!(
+            hooks.command(&notice(WebhookEvent::Completed)).as_deref(),
+            Some(
+                r#"notify-send 'PrEval' ''"${PREVAL_STATUS}"': '"${PREVAL_PASSED}"'/'"${PREVAL_TOTAL}"''"#
+            )
+        );
+        assert_eq!(
+            hooks.command(&notice(WebhookEvent::Failed)).as_deref(),
+            Some(
+                r#"echo "${PREVAL_MESSAGE} \" ${PREVAL_RUN_ID}" '\'"${PREVAL_ELAPSED}"'' \'"${PREVAL_FAILED}""#
+            )
+        );
+    }
+}
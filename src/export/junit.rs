@@ -0,0 +1,161 @@
+// JUnit XML reports of a run, so CI systems can show its samples as tests
+
+use crate::state::app::AppData;
+use crate::state::types::{SampleResult, SampleStatus};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::Duration;
+
+/// Test suite of the samples of a run, one test case per sample
+#[derive(Debug, Clone)]
+pub struct JunitReport {
+    /// Name of the suite, and the class name of its test cases
+    pub suite: String,
+    pub timestamp: DateTime<Utc>,
+    pub elapsed: Duration,
+    pub samples: Vec<SampleResult>,
+}
+
+impl JunitReport {
+    /// Report of every sample of the run in `state`, started at
+    /// `started_at`, that can still be read, including those spilled to disk
+    pub fn of(state: &AppData, started_at: DateTime<Utc>) -> Result<Self> {
+        let samples = state
+            .sample_store()
+            .read_available()
+            .context("Failed to read samples for the JUnit report")?;
+        Ok(Self {
+            suite: state
+                .evaluator_name()
+                .map_or_else(|| "preval".to_string(), |name| name.as_ref().to_string()),
+            timestamp: started_at,
+            elapsed: state.elapsed_time(),
+            samples,
+        })
+    }
+
+    /// The report as a JUnit XML document
+    ///
+    /// Failed samples fail their test case with why, from the threshold
+    /// rules they broke or the evaluator's error; samples still processing
+    /// when the run ended are skipped.
+    pub fn to_xml(&self) -> String {
+        let count = |status: fn(&SampleStatus) -> bool| {
+            self.samples
+                .iter()
+                .filter(|sample| status(&sample.status))
+                .count()
+        };
+        let failures = count(|status| matches!(status, SampleStatus::Failed(_)));
+        let skipped = count(|status| matches!(status, SampleStatus::Processing));
+        let suite = escape(&self.suite);
+        let totals = format!(
+            "tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{:.3}\"",
+            self.samples.len(),
+            failures,
+            skipped,
+            self.elapsed.as_secs_f64()
+        );
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(xml, "<testsuites name=\"preval\" {}>", totals).ok();
+        writeln!(
+            xml,
+            "  <testsuite name=\"{}\" {} timestamp=\"{}\">",
+            suite,
+            totals,
+            self.timestamp.format("%Y-%m-%dT%H:%M:%S")
+        )
+        .ok();
+        for sample in &self.samples {
+            write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape(&sample.sample_id),
+                suite,
+                sample.duration.unwrap_or_default().as_secs_f64()
+            )
+            .ok();
+            match &sample.status {
+                SampleStatus::Completed => xml.push_str("/>\n"),
+                SampleStatus::Processing => {
+                    xml.push_str(">\n      <skipped message=\"not finished when the run ended\"/>\n    </testcase>\n");
+                }
+                SampleStatus::Failed(error) => {
+                    let error = escape(error);
+                    write!(
+                        xml,
+                        ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                        error, error
+                    )
+                    .ok();
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    /// Write the report to `path`
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_xml())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// `text` with the characters XML gives a meaning escaped, and those it
+/// does not allow left out
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_become_test_cases() {
+        let mut passed = SampleResult::new_processing("q-1".to_string());
+        passed.mark_completed(vec![("accuracy".to_string(), 1.0)]);
+        passed.duration = Some(Duration::from_millis(1500));
+        let mut failed = SampleResult::new_processing("q-2".to_string());
+        failed.mark_failed("accuracy = 0.5 violates accuracy >= 0.8 & \"more\"".to_string());
+        let pending = SampleResult::new_processing("q<3>".to_string());
+
+        let report = JunitReport {
+            suite: "my-eval".to_string(),
+            timestamp: DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            elapsed: Duration::from_secs(75),
+            samples: vec![passed, failed, pending],
+        };
+        let xml = report.to_xml();
+
+        assert!(xml.contains(
+            "<testsuite name=\"my-eval\" tests=\"3\" failures=\"1\" errors=\"0\" skipped=\"1\" time=\"75.000\" timestamp=\"2026-01-02T03:04:05\">"
+        ));
+        assert!(xml.contains("<testcase name=\"q-1\" classname=\"my-eval\" time=\"1.500\"/>\n"));
+        assert!(xml.contains(
+            "<failure message=\"accuracy = 0.5 violates accuracy &gt;= 0.8 &amp; &quot;more&quot;\">"
+        ));
+        assert!(xml.contains("<testcase name=\"q&lt;3&gt;\""));
+        assert!(xml.contains("<skipped message=\"not finished when the run ended\"/>"));
+        assert!(xml.ends_with("</testsuite>\n</testsuites>\n"));
+    }
+}
@@ -0,0 +1,12 @@
+// Exporters that ship evaluator data to systems outside PrEval
+
+pub mod dashboard;
+pub mod event_log;
+pub(crate) mod github;
+pub mod hooks;
+pub mod junit;
+pub(crate) mod otlp_forward;
+pub mod prometheus;
+pub mod report;
+pub mod tracking;
+pub mod webhook;
@@ -0,0 +1,195 @@
+use anyhow::{Context, Result};
+use tokio::sync::mpsc;
+
+/// Path OTLP/HTTP collectors accept metrics on
+const OTLP_METRICS_PATH: &str = "/v1/metrics";
+
+/// Port collectors receive OTLP over gRPC on, which cannot take OTLP/HTTP
+const OTLP_GRPC_PORT: u16 = 4317;
+
+/// Number of lines that may be queued for export before new ones are dropped
+const FORWARD_QUEUE_SIZE: usize = 1024;
+
+/// Validated OTLP/HTTP endpoint that metric lines are forwarded to
+///
+/// Accepts `otlp://host:port` as shorthand for `http://host:port/v1/metrics`.
+/// Lines are exported using OTLP/HTTP with JSON encoding, so the port should
+/// be the collector's HTTP receiver (4318 by default), not its gRPC one;
+/// the default gRPC port 4317 is rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ForwardEndpoint(reqwest::Url);
+
+impl ForwardEndpoint {
+    /// Parse an endpoint from command-line input
+    pub(crate) fn parse(input: &str) -> Result<Self, ForwardEndpointError> {
+        let input = input.trim();
+        let normalized = match input.strip_prefix("otlp://") {
+            Some(rest) => format!("http://{}", rest),
+            None => input.to_string(),
+        };
+
+        let mut url = reqwest::Url::parse(&normalized)
+            .map_err(|e| ForwardEndpointError::InvalidUrl(e.to_string()))?;
+
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(ForwardEndpointError::UnsupportedScheme(
+                url.scheme().to_string(),
+            ));
+        }
+
+        if url.host_str().is_none() {
+            return Err(ForwardEndpointError::MissingHost);
+        }
+
+        if url.port() == Some(OTLP_GRPC_PORT) {
+            return Err(ForwardEndpointError::GrpcPort);
+        }
+
+        // A bare collector address means the standard metrics path
+        if url.path().is_empty() || url.path() == "/" {
+            url.set_path(OTLP_METRICS_PATH);
+        }
+
+        Ok(Self(url))
+    }
+
+    /// Get the full URL metrics are posted to
+    pub(crate) fn url(&self) -> &reqwest::Url {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ForwardEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Errors for invalid forward endpoints
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ForwardEndpointError {
+    #[error("invalid forward URL: {0}")]
+    InvalidUrl(String),
+    #[error("unsupported forward scheme '{0}' (expected otlp, http, or https)")]
+    UnsupportedScheme(String),
+    #[error("forward URL must include a host")]
+    MissingHost,
+    #[error("port 4317 is the collector's gRPC receiver; forward to its OTLP/HTTP one (4318 by default)")]
+    GrpcPort,
+}
+
+/// Handle to a background task exporting metric lines to an OTLP collector
+///
+/// Forwarding never blocks the evaluator pipeline: if the collector falls
+/// behind and the queue fills up, lines are dropped with a warning.
+pub(crate) struct OtlpForwarder {
+    line_tx: mpsc::Sender<String>,
+}
+
+impl OtlpForwarder {
+    /// Start forwarding to the given endpoint
+    pub(crate) fn spawn(endpoint: ForwardEndpoint) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .build()
+            .context("Failed to create OTLP forward client")?;
+        let (line_tx, mut line_rx) = mpsc::channel::<String>(FORWARD_QUEUE_SIZE);
+
+        tokio::spawn(async move {
+            while let Some(line) = line_rx.recv().await {
+                let response = client
+                    .post(endpoint.url().clone())
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(line)
+                    .send()
+                    .await;
+
+                match response {
+                    Ok(response) if !response.status().is_success() => {
+                        tracing::warn!(
+                            "OTLP collector at {} rejected metrics: {}",
+                            endpoint,
+                            response.status()
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!("Failed to forward metrics to {}: {}", endpoint, e);
+                    }
+                }
+            }
+        });
+
+        Ok(Self { line_tx })
+    }
+
+    /// Queue a raw OTLP/JSON metrics line for export
+    pub(crate) fn forward(&self, line: &str) {
+        if let Err(mpsc::error::TrySendError::Full(_)) = self.line_tx.try_send(line.to_string()) {
+            tracing::warn!("OTLP forward queue full, dropping metrics line");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn otlp_scheme_maps_to_http_metrics_path() {
+        let endpoint = ForwardEndpoint::parse("otlp://collector:4318").unwrap();
+        assert_eq!(endpoint.url().as_str(), "http://collector:4318/v1/metrics");
+    }
+
+    #[test]
+    fn explicit_path_is_preserved() {
+        let endpoint = ForwardEndpoint::parse("https://otel.example.com/custom/metrics").unwrap();
+        assert_eq!(
+            endpoint.url().as_str(),
+            "https://otel.example.com/custom/metrics"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_scheme() {
+        let result = ForwardEndpoint::parse("ftp://collector:21");
+        assert!(matches!(
+            result,
+            Err(ForwardEndpointError::UnsupportedScheme(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_grpc_port() {
+        let result = ForwardEndpoint::parse("otlp://collector:4317");
+        assert!(matches!(result, Err(ForwardEndpointError::GrpcPort)));
+    }
+
+    #[tokio::test]
+    async fn forwards_lines_to_collector() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let endpoint = ForwardEndpoint::parse(&format!("otlp://{}", addr)).unwrap();
+
+        let forwarder = OtlpForwarder::spawn(endpoint).unwrap();
+        forwarder.forward(r#"{"resourceMetrics":[]}"#);
+
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !String::from_utf8_lossy(&request).contains(r#"{"resourceMetrics":[]}"#) {
+            let n = socket.read(&mut buf).await.unwrap();
+            assert!(n > 0, "connection closed before body was received");
+            request.extend_from_slice(&buf[..n]);
+        }
+        socket
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .await
+            .unwrap();
+
+        let request = String::from_utf8_lossy(&request);
+        assert!(request.starts_with("POST /v1/metrics HTTP/1.1"));
+        assert!(request.contains("content-type: application/json"));
+    }
+}
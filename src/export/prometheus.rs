@@ -0,0 +1,306 @@
+// Prometheus scrape endpoint serving the aggregates and progress of a run
+
+use crate::state::aggregation::{MetricAggregator, MetricStats};
+use crate::state::app::AppData;
+use crate::state::publisher::StateSnapshot;
+use crate::state::types::EvaluationStatus;
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+/// Path scrapes are served on
+const METRICS_PATH: &str = "/metrics";
+
+/// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Most bytes of a scrape request read before answering it
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// Serve the latest state snapshot as Prometheus metrics on `addr` until
+/// the process exits
+///
+/// Binding happens before returning, so a port already in use fails the
+/// run instead of going unnoticed.
+pub async fn serve(
+    addr: SocketAddr,
+    snapshots: watch::Receiver<StateSnapshot>,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to listen for Prometheus scrapes on {}", addr))?;
+    let local_addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((socket, _)) => {
+                    let snapshots = snapshots.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = answer(socket, &snapshots).await {
+                            tracing::debug!("Prometheus scrape failed: {:#}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept Prometheus scrape: {}", e),
+            }
+        }
+    });
+    Ok(local_addr)
+}
+
+/// Answer one HTTP request on `socket` with the metrics of the latest
+/// snapshot, or 404 for any other path
+async fn answer(mut socket: TcpStream, snapshots: &watch::Receiver<StateSnapshot>) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_SIZE {
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+    let (status, body) = if path.split('?').next() == Some(METRICS_PATH) {
+        let snapshot = snapshots.borrow().clone();
+        ("200 OK", exposition(&snapshot))
+    } else {
+        (
+            "404 Not Found",
+            format!("Metrics are served on {}\n", METRICS_PATH),
+        )
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        CONTENT_TYPE,
+        body.len(),
+        body
+    );
+    socket.write_all(response.as_bytes()).await?;
+    socket.shutdown().await?;
+    Ok(())
+}
+
+/// Progress and aggregates of the run in `state`, in the Prometheus text
+/// exposition format
+pub fn exposition(state: &AppData) -> String {
+    let mut out = String::new();
+    let (failed, recorded, _) = state.summary_stats();
+    let (received, total, _) = state.progress();
+    let phase = match state.status() {
+        EvaluationStatus::Starting | EvaluationStatus::WaitingForHandshake => "starting",
+        EvaluationStatus::CollectingMetrics { .. } => "running",
+        EvaluationStatus::Completed => "completed",
+        EvaluationStatus::Failed(_) => "failed",
+    };
+
+    family(
+        &mut out,
+        "preval_run_status",
+        "Phase of the run, as a label set to 1",
+    );
+    for name in ["starting", "running", "completed", "failed"] {
+        sample(
+            &mut out,
+            "preval_run_status",
+            &[("status", name)],
+            f64::from(u8::from(name == phase)),
+        );
+    }
+    family(
+        &mut out,
+        "preval_elapsed_seconds",
+        "Time since the run started",
+    );
+    sample(
+        &mut out,
+        "preval_elapsed_seconds",
+        &[],
+        state.elapsed_time().as_secs_f64(),
+    );
+    family(
+        &mut out,
+        "preval_samples_received",
+        "Samples reported so far, counting toward progress",
+    );
+    sample(&mut out, "preval_samples_received", &[], received as f64);
+    if let Some(total) = total {
+        family(
+            &mut out,
+            "preval_samples_planned",
+            "Samples the evaluator plans to run",
+        );
+        sample(&mut out, "preval_samples_planned", &[], total as f64);
+    }
+    family(
+        &mut out,
+        "preval_samples_completed",
+        "Samples recorded without failing",
+    );
+    sample(
+        &mut out,
+        "preval_samples_completed",
+        &[],
+        recorded.saturating_sub(failed) as f64,
+    );
+    family(
+        &mut out,
+        "preval_samples_failed",
+        "Samples that failed or broke a threshold rule",
+    );
+    sample(&mut out, "preval_samples_failed", &[], failed as f64);
+    if let Some(throughput) = state.throughput() {
+        family(
+            &mut out,
+            "preval_samples_per_second",
+            "Recent rate samples complete at",
+        );
+        sample(
+            &mut out,
+            "preval_samples_per_second",
+            &[],
+            throughput.samples_per_sec,
+        );
+    }
+    if let Some((score, mean)) = state.score().zip(state.mean_score()) {
+        family(&mut out, "preval_score", "Mean of the composite score");
+        sample(&mut out, "preval_score", &[("score", score.name())], mean);
+    }
+    if !state.tripped_rules().is_empty() {
+        family(
+            &mut out,
+            "preval_threshold_violations",
+            "Times each threshold rule was broken",
+        );
+        for (rule, violations) in state.tripped_rules() {
+            sample(
+                &mut out,
+                "preval_threshold_violations",
+                &[("rule", rule)],
+                *violations as f64,
+            );
+        }
+    }
+    write_metric_stats(&mut out, state.metric_stats());
+    out
+}
+
+/// Statistics of every metric, one family per statistic labelled by metric
+fn write_metric_stats(out: &mut String, stats: &MetricAggregator) {
+    if stats.iter().next().is_none() {
+        return;
+    }
+    type Statistic = fn(&MetricStats) -> Option<f64>;
+    let statistics: [(&str, &str, Statistic); 6] = [
+        ("count", "Values recorded of each metric", |s| {
+            Some(s.count() as f64)
+        }),
+        ("mean", "Mean of each metric", |s| s.mean()),
+        ("min", "Lowest value of each metric", |s| s.min()),
+        ("max", "Highest value of each metric", |s| s.max()),
+        ("stddev", "Standard deviation of each metric", |s| {
+            s.stddev()
+        }),
+        ("p95", "95th percentile of each metric", |s| {
+            s.percentile(95.0)
+        }),
+    ];
+    for (statistic, help, value) in statistics {
+        let name = format!("preval_metric_{}", statistic);
+        family(out, &name, help);
+        for (metric, metric_stats) in stats.iter() {
+            if let Some(value) = value(metric_stats) {
+                sample(out, &name, &[("metric", metric)], value);
+            }
+        }
+    }
+}
+
+/// Write the `# HELP` and `# TYPE` lines of a metric family; every value
+/// served is a gauge, as each is recomputed from the run on every scrape
+fn family(out: &mut String, name: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).ok();
+    writeln!(out, "# TYPE {} gauge", name).ok();
+}
+
+/// Write one sample of a metric family with its labels
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+            .collect();
+        write!(out, "{{{}}}", labels.join(",")).ok();
+    }
+    let value = match value {
+        v if v.is_nan() => "NaN".to_string(),
+        v if v == f64::INFINITY => "+Inf".to_string(),
+        v if v == f64::NEG_INFINITY => "-Inf".to_string(),
+        v => v.to_string(),
+    };
+    writeln!(out, " {}", value).ok();
+}
+
+/// `value` with the characters label values may not contain escaped
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RunState;
+    use std::sync::Arc;
+
+    #[test]
+    fn metric_stats_are_labelled_by_metric() {
+        let mut stats = MetricAggregator::new();
+        stats.record("accuracy", 0.5);
+        stats.record("accuracy", 1.0);
+        stats.record("say \"hi\"", 2.0);
+
+        let mut out = String::new();
+        write_metric_stats(&mut out, &stats);
+        assert!(out.contains(
+            "# HELP preval_metric_mean Mean of each metric\n# TYPE preval_metric_mean gauge\n"
+        ));
+        assert!(out.contains("preval_metric_count{metric=\"accuracy\"} 2\n"));
+        assert!(out.contains("preval_metric_mean{metric=\"accuracy\"} 0.75\n"));
+        assert!(out.contains("preval_metric_max{metric=\"say \\\"hi\\\"\"} 2\n"));
+    }
+
+    #[tokio::test]
+    async fn scrapes_are_served_from_the_latest_snapshot() {
+        let (_sender, snapshots) = watch::channel(Arc::new(RunState::default()));
+        let addr = serve("127.0.0.1:0".parse().unwrap(), snapshots)
+            .await
+            .unwrap();
+
+        let scrape = |path: &'static str| async move {
+            let mut socket = TcpStream::connect(addr).await.unwrap();
+            socket
+                .write_all(format!("GET {} HTTP/1.1\r\nhost: preval\r\n\r\n", path).as_bytes())
+                .await
+                .unwrap();
+            let mut response = String::new();
+            socket.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = scrape("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("preval_run_status{status=\"starting\"} 1\n"));
+        assert!(response.contains("preval_samples_failed 0\n"));
+        assert!(scrape("/").await.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
@@ -0,0 +1,421 @@
+// Reports summing up a run, written on demand from the TUI
+
+use crate::state::app::AppData;
+use crate::state::sample_store::{SampleFilter, StatusFilter};
+use crate::state::types::{EvaluationStatus, SampleStatus};
+use crate::ui::widgets::progress::ProgressView;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Most failed samples listed in a Markdown report; JSON lists them all
+const MAX_MARKDOWN_FAILURES: usize = 20;
+
+/// File format of a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    /// Headings, lists and tables to read or paste into an issue
+    #[default]
+    Markdown,
+    /// The same content for other tools to read
+    Json,
+}
+
+impl ReportFormat {
+    /// Extension of report files in this format
+    fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+/// Statistics of one metric over the run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricSummary {
+    pub name: String,
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub stddev: Option<f64>,
+    pub p95: Option<f64>,
+}
+
+/// A sample that failed, with why
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FailedSample {
+    pub id: String,
+    pub error: String,
+}
+
+/// A sample the user bookmarked or left a note on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnotatedSample {
+    pub id: String,
+    pub bookmarked: bool,
+    pub note: Option<String>,
+}
+
+/// A threshold rule and how often samples broke it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleResult {
+    pub rule: String,
+    pub violations: usize,
+}
+
+/// How a run went, as far as it has got
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub generated_at: DateTime<Utc>,
+    pub evaluator: Option<String>,
+    /// Phase of the run: e.g. "running", "completed" or "failed"
+    pub status: &'static str,
+    pub failure: Option<String>,
+    pub elapsed_secs: f64,
+    pub total_samples: Option<usize>,
+    pub samples_completed: usize,
+    pub samples_failed: usize,
+    /// Name and mean of the composite score, when there is one
+    pub score: Option<(String, f64)>,
+    pub metrics: Vec<MetricSummary>,
+    pub failed_samples: Vec<FailedSample>,
+    pub annotated_samples: Vec<AnnotatedSample>,
+    pub thresholds: Vec<RuleResult>,
+}
+
+impl Report {
+    /// Report of the run in `state` so far
+    pub fn of(state: &AppData) -> Self {
+        let (status, failure) = match state.status() {
+            EvaluationStatus::Starting => ("starting", None),
+            EvaluationStatus::WaitingForHandshake => ("waiting for handshake", None),
+            EvaluationStatus::CollectingMetrics { .. } => ("running", None),
+            EvaluationStatus::Completed => ("completed", None),
+            EvaluationStatus::Failed(reason) => ("failed", Some(reason.clone())),
+        };
+        let (samples_failed, samples_completed, _) = state.summary_stats();
+        let failed_filter = SampleFilter::default().with_status(StatusFilter::Failed);
+        let tripped = state.tripped_rules();
+
+        Self {
+            generated_at: Utc::now(),
+            evaluator: state.evaluator_name().map(|name| name.as_ref().to_string()),
+            status,
+            failure,
+            elapsed_secs: state.elapsed_time().as_secs_f64(),
+            total_samples: state.progress().1,
+            samples_completed,
+            samples_failed,
+            score: state
+                .score()
+                .zip(state.mean_score())
+                .map(|(score, mean)| (score.name().to_string(), mean)),
+            metrics: state
+                .metric_stats()
+                .iter()
+                .map(|(name, stats)| MetricSummary {
+                    name: name.to_string(),
+                    count: stats.count(),
+                    mean: stats.mean(),
+                    min: stats.min(),
+                    max: stats.max(),
+                    stddev: stats.stddev(),
+                    p95: stats.percentile(95.0),
+                })
+                .collect(),
+            failed_samples: state
+                .sample_store()
+                .matching(&failed_filter)
+                .map(|(_, sample)| FailedSample {
+                    id: sample.sample_id.clone(),
+                    error: match &sample.status {
+                        SampleStatus::Failed(error) => error.clone(),
+                        _ => String::new(),
+                    },
+                })
+                .collect(),
+            annotated_samples: state
+                .sample_store()
+                .matching(&SampleFilter::default())
+                .filter(|(_, sample)| !sample.annotation.is_empty())
+                .map(|(_, sample)| AnnotatedSample {
+                    id: sample.sample_id.clone(),
+                    bookmarked: sample.annotation.bookmarked,
+                    note: sample.annotation.note.clone(),
+                })
+                .collect(),
+            thresholds: state
+                .threshold_rules()
+                .map(|rule| {
+                    let rule = rule.to_string();
+                    let violations = tripped.get(&rule).copied().unwrap_or(0);
+                    RuleResult { rule, violations }
+                })
+                .collect(),
+        }
+    }
+
+    /// Success rate of the completed samples, in percent
+    fn success_rate(&self) -> Option<f64> {
+        (self.samples_completed > 0).then(|| {
+            let succeeded = self.samples_completed - self.samples_failed;
+            succeeded as f64 / self.samples_completed as f64 * 100.0
+        })
+    }
+
+    /// The report as Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = match &self.evaluator {
+            Some(evaluator) => format!("## PrEval run: {}\n\n", evaluator),
+            None => "## PrEval run\n\n".to_string(),
+        };
+        let status = match (&self.failure, self.status) {
+            (Some(reason), _) => format!("❌ Failed: {}", reason),
+            (None, "completed") => "✅ Completed".to_string(),
+            (None, status) => status.to_string(),
+        };
+        writeln!(markdown, "- **Status:** {}", status).ok();
+        writeln!(
+            markdown,
+            "- **Elapsed:** {}",
+            ProgressView::format_duration(std::time::Duration::from_secs_f64(self.elapsed_secs))
+        )
+        .ok();
+        write!(
+            markdown,
+            "- **Samples:** {} completed, {} failed",
+            self.samples_completed, self.samples_failed
+        )
+        .ok();
+        if let Some(total) = self.total_samples {
+            write!(markdown, " of {}", total).ok();
+        }
+        if let Some(success_rate) = self.success_rate() {
+            write!(markdown, " ({:.1}% success rate)", success_rate).ok();
+        }
+        markdown.push('\n');
+        if let Some((name, mean)) = &self.score {
+            writeln!(markdown, "- **{}:** {:.3}", name, mean).ok();
+        }
+
+        if !self.metrics.is_empty() {
+            let cell = |value: Option<f64>| {
+                value.map_or_else(|| "–".to_string(), |value| format!("{:.4}", value))
+            };
+            markdown.push_str("\n| Metric | Count | Mean | Min | Max | Std dev | p95 |\n");
+            markdown.push_str("| --- | ---: | ---: | ---: | ---: | ---: | ---: |\n");
+            for metric in &self.metrics {
+                writeln!(
+                    markdown,
+                    "| {} | {} | {} | {} | {} | {} | {} |",
+                    metric.name,
+                    metric.count,
+                    cell(metric.mean),
+                    cell(metric.min),
+                    cell(metric.max),
+                    cell(metric.stddev),
+                    cell(metric.p95)
+                )
+                .ok();
+            }
+        }
+
+        if !self.failed_samples.is_empty() {
+            markdown.push_str("\n### Failed samples\n\n");
+            for sample in self.failed_samples.iter().take(MAX_MARKDOWN_FAILURES) {
+                writeln!(markdown, "- `{}`: {}", sample.id, sample.error).ok();
+            }
+            if self.failed_samples.len() > MAX_MARKDOWN_FAILURES {
+                writeln!(
+                    markdown,
+                    "- … and {} more",
+                    self.failed_samples.len() - MAX_MARKDOWN_FAILURES
+                )
+                .ok();
+            }
+        }
+
+        if !self.annotated_samples.is_empty() {
+            markdown.push_str("\n### Bookmarks and notes\n\n");
+            for sample in &self.annotated_samples {
+                let mark = if sample.bookmarked { "★ " } else { "" };
+                match &sample.note {
+                    Some(note) => writeln!(markdown, "- {}`{}`: {}", mark, sample.id, note).ok(),
+                    None => writeln!(markdown, "- {}`{}`", mark, sample.id).ok(),
+                };
+            }
+        }
+
+        if !self.thresholds.is_empty() {
+            markdown.push_str("\n### Thresholds\n\n");
+            for result in &self.thresholds {
+                match result.violations {
+                    0 => writeln!(markdown, "- ✅ `{}`", result.rule).ok(),
+                    n => writeln!(markdown, "- ❌ `{}` (tripped x{})", result.rule, n).ok(),
+                };
+            }
+        }
+        markdown
+    }
+
+    /// Write the report in `format` to a new file in `dir`, named after
+    /// when it was generated; returns the file written
+    pub fn write(&self, dir: &Path, format: ReportFormat) -> Result<PathBuf> {
+        let contents = match format {
+            ReportFormat::Markdown => self.to_markdown(),
+            ReportFormat::Json => {
+                serde_json::to_string_pretty(self).context("Failed to encode report")?
+            }
+        };
+        let path = dir.join(format!(
+            "preval-report-{}.{}",
+            self.generated_at.format("%Y%m%dT%H%M%SZ"),
+            format.extension()
+        ));
+        std::fs::write(&path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = ReportFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            other => Err(ReportFormatError(other.to_string())),
+        }
+    }
+}
+
+/// Error for unrecognized report formats
+#[derive(Debug, thiserror::Error)]
+#[error("unknown report format '{0}' (expected 'markdown' or 'json')")]
+pub struct ReportFormatError(String);
+
+/// A report written when the run ends, from `--report FORMAT=PATH`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportTarget {
+    /// JUnit XML with a test case per sample, for CI systems to show
+    Junit(PathBuf),
+}
+
+impl std::str::FromStr for ReportTarget {
+    type Err = ReportTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = s
+            .split_once('=')
+            .filter(|(_, path)| !path.is_empty())
+            .ok_or_else(|| ReportTargetError(s.to_string()))?;
+        match format.trim().to_ascii_lowercase().as_str() {
+            "junit" => Ok(Self::Junit(PathBuf::from(path))),
+            _ => Err(ReportTargetError(s.to_string())),
+        }
+    }
+}
+
+/// Error for unrecognized reports to write at the end of a run
+#[derive(Debug, thiserror::Error)]
+#[error("unknown report '{0}' (expected 'junit=PATH')")]
+pub struct ReportTargetError(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report {
+            generated_at: DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            evaluator: Some("my-eval".to_string()),
+            status: "running",
+            failure: None,
+            elapsed_secs: 75.0,
+            total_samples: Some(10),
+            samples_completed: 4,
+            samples_failed: 1,
+            score: None,
+            metrics: vec![MetricSummary {
+                name: "accuracy".to_string(),
+                count: 4,
+                mean: Some(0.75),
+                min: Some(0.5),
+                max: Some(1.0),
+                stddev: None,
+                p95: Some(1.0),
+            }],
+            failed_samples: vec![FailedSample {
+                id: "q-3".to_string(),
+                error: "accuracy < 0.6".to_string(),
+            }],
+            annotated_samples: vec![
+                AnnotatedSample {
+                    id: "q-1".to_string(),
+                    bookmarked: true,
+                    note: Some("odd phrasing".to_string()),
+                },
+                AnnotatedSample {
+                    id: "q-2".to_string(),
+                    bookmarked: true,
+                    note: None,
+                },
+            ],
+            thresholds: vec![RuleResult {
+                rule: "accuracy >= 0.6".to_string(),
+                violations: 1,
+            }],
+        }
+    }
+
+    #[test]
+    fn report_targets_parse_from_format_and_path() {
+        assert_eq!(
+            "junit=out/results.xml".parse::<ReportTarget>().unwrap(),
+            ReportTarget::Junit(PathBuf::from("out/results.xml"))
+        );
+        assert!("junit=".parse::<ReportTarget>().is_err());
+        assert!("results.xml".parse::<ReportTarget>().is_err());
+        assert!("tap=results.tap".parse::<ReportTarget>().is_err());
+    }
+
+    #[test]
+    fn reports_render_as_markdown() {
+        let markdown = report().to_markdown();
+        assert!(markdown.starts_with("## PrEval run: my-eval\n\n- **Status:** running\n"));
+        assert!(markdown.contains("- **Elapsed:** 1:15\n"));
+        assert!(markdown.contains("4 completed, 1 failed of 10 (75.0% success rate)"));
+        assert!(markdown.contains("| accuracy | 4 | 0.7500 | 0.5000 | 1.0000 | – | 1.0000 |\n"));
+        assert!(markdown.contains("- `q-3`: accuracy < 0.6\n"));
+        assert!(markdown.contains("- ★ `q-1`: odd phrasing\n- ★ `q-2`\n"));
+        assert!(markdown.contains("- ❌ `accuracy >= 0.6` (tripped x1)\n"));
+    }
+
+    #[test]
+    fn reports_are_written_in_the_chosen_format() {
+        let dir = std::env::temp_dir().join(format!("preval-report-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = report().write(&dir, ReportFormat::Json).unwrap();
+        assert_eq!(
+            path.file_name().unwrap(),
+            "preval-report-20260102T030405Z.json"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["samples_failed"], 1);
+        assert_eq!(json["metrics"][0]["name"], "accuracy");
+
+        let path = report().write(&dir, ReportFormat::Markdown).unwrap();
+        assert_eq!(path.extension().unwrap(), "md");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,490 @@
+// Experiment trackers (MLflow, Weights & Biases) a finished run is logged
+// to, so evaluation runs sit next to the training runs they evaluate
+
+use crate::history::database::{Outcome, RunRecord};
+use crate::state::app::AppData;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Longest a tracker may take to answer one request
+const TRACKING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most metrics MLflow accepts in one batch
+const MLFLOW_MAX_BATCH_METRICS: usize = 1000;
+
+/// Most params MLflow accepts in one batch
+const MLFLOW_MAX_BATCH_PARAMS: usize = 100;
+
+/// Longest param value MLflow accepts
+const MLFLOW_MAX_PARAM_LENGTH: usize = 500;
+
+/// Experiment trackers finished runs are logged to; each config file
+/// (picked with `--config`) can log to its own
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrackingConfig {
+    pub mlflow: Option<MlflowConfig>,
+    pub wandb: Option<WandbConfig>,
+}
+
+impl TrackingConfig {
+    /// Whether any tracker is configured
+    pub fn is_enabled(&self) -> bool {
+        self.mlflow.is_some() || self.wandb.is_some()
+    }
+}
+
+/// Where and how runs are logged to an MLflow tracking server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MlflowConfig {
+    /// Tracking server, e.g. `http://localhost:5000`
+    pub url: String,
+    /// Experiment runs are logged to, created when missing
+    #[serde(default = "MlflowConfig::default_experiment")]
+    pub experiment: String,
+    /// Environment variable holding a bearer token, for servers that need one
+    #[serde(default = "MlflowConfig::default_token_env")]
+    pub token_env: String,
+}
+
+impl MlflowConfig {
+    fn default_experiment() -> String {
+        "preval".to_string()
+    }
+
+    fn default_token_env() -> String {
+        "MLFLOW_TRACKING_TOKEN".to_string()
+    }
+}
+
+/// Where and how runs are logged to Weights & Biases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WandbConfig {
+    pub project: String,
+    /// Team or user the project belongs to; the API key's default when left out
+    #[serde(default)]
+    pub entity: Option<String>,
+    /// API server, for self-hosted W&B
+    #[serde(default = "WandbConfig::default_url")]
+    pub url: String,
+    /// Environment variable holding the API key
+    #[serde(default = "WandbConfig::default_api_key_env")]
+    pub api_key_env: String,
+}
+
+impl WandbConfig {
+    fn default_url() -> String {
+        "https://api.wandb.ai".to_string()
+    }
+
+    fn default_api_key_env() -> String {
+        "WANDB_API_KEY".to_string()
+    }
+}
+
+/// What is logged of a finished run: its parameters from the handshake and
+/// its final aggregates
+#[derive(Debug, Clone)]
+pub(crate) struct TrackedRun {
+    /// Id unique to the run, e.g. its run directory
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) record: RunRecord,
+    pub(crate) params: BTreeMap<String, String>,
+    pub(crate) metrics: BTreeMap<String, f64>,
+}
+
+impl TrackedRun {
+    /// The run described by `record`, with the handshake and statistics of
+    /// `state`
+    pub(crate) fn new(record: RunRecord, state: &AppData) -> Self {
+        let mut params = BTreeMap::new();
+        let mut param = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                params.insert(key.to_string(), value);
+            }
+        };
+        param("evaluator", Some(record.evaluator.clone()));
+        param("evaluator.description", record.description.clone());
+        param("evaluator.version", record.evaluator_version.clone());
+        param("total_samples", record.total_samples.map(|n| n.to_string()));
+        if let Some(handshake) = state.handshake() {
+            param("protocol_version", Some(handshake.version.to_string()));
+            param(
+                "mode",
+                serde_json::to_value(&handshake.mode)
+                    .ok()
+                    .and_then(|mode| mode.as_str().map(str::to_string)),
+            );
+            let plan = handshake.execution_plan.as_ref();
+            param(
+                "batch_size",
+                plan.and_then(|plan| plan.batch_size.as_ref())
+                    .map(|size| size.into_inner().to_string()),
+            );
+            param(
+                "runs_per_sample",
+                plan.and_then(|plan| plan.runs_per_sample.as_ref())
+                    .map(|runs| runs.into_inner().to_string()),
+            );
+        }
+
+        let mut metrics = BTreeMap::new();
+        for (name, stats) in state.metric_stats().iter() {
+            let statistics = [
+                ("mean", stats.mean()),
+                ("min", stats.min()),
+                ("max", stats.max()),
+                ("stddev", stats.stddev()),
+                ("p95", stats.percentile(95.0)),
+                ("count", Some(stats.count() as f64)),
+            ];
+            for (statistic, value) in statistics {
+                if let Some(value) = value.filter(|value| value.is_finite()) {
+                    metrics.insert(format!("{}.{}", name, statistic), value);
+                }
+            }
+        }
+        metrics.insert(
+            "samples_completed".to_string(),
+            record.samples_completed as f64,
+        );
+        metrics.insert("samples_failed".to_string(), record.samples_failed as f64);
+        metrics.insert(
+            "elapsed_secs".to_string(),
+            (record.finished_at - record.started_at).num_milliseconds() as f64 / 1000.0,
+        );
+        if let Some(score) = record.score {
+            metrics.insert("score".to_string(), score);
+        }
+
+        Self {
+            id: record
+                .run_dir
+                .clone()
+                .unwrap_or_else(|| format!("preval-{}", record.started_at.format("%Y%m%dT%H%M%S"))),
+            name: record.evaluator.chars().take(64).collect(),
+            record,
+            params,
+            metrics,
+        }
+    }
+
+    /// Log the run to every tracker in `config`; returns where it went
+    pub(crate) async fn log(&self, config: &TrackingConfig) -> Result<Vec<String>> {
+        let client = reqwest::Client::builder()
+            .timeout(TRACKING_TIMEOUT)
+            .build()
+            .context("Failed to create experiment tracking client")?;
+        let mut logged = Vec::new();
+        if let Some(mlflow) = &config.mlflow {
+            let run_id = self
+                .log_to_mlflow(&client, mlflow)
+                .await
+                .context("Failed to log the run to MLflow")?;
+            logged.push(format!("MLflow run {}", run_id));
+        }
+        if let Some(wandb) = &config.wandb {
+            self.log_to_wandb(&client, wandb)
+                .await
+                .context("Failed to log the run to Weights & Biases")?;
+            logged.push(format!("W&B run {}/{}", wandb.project, self.id));
+        }
+        Ok(logged)
+    }
+
+    /// Create an MLflow run holding this run's params and metrics; returns
+    /// its id
+    async fn log_to_mlflow(
+        &self,
+        client: &reqwest::Client,
+        config: &MlflowConfig,
+    ) -> Result<String> {
+        let api = |path: &str| {
+            format!(
+                "{}/api/2.0/mlflow/{}",
+                config.url.trim_end_matches('/'),
+                path
+            )
+        };
+        let token = std::env::var(&config.token_env).ok();
+        let request = |builder: reqwest::RequestBuilder| match &token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        };
+
+        let found = request(client.get(api("experiments/get-by-name")))
+            .query(&[("experiment_name", &config.experiment)])
+            .send()
+            .await?;
+        let experiment_id = if found.status() == reqwest::StatusCode::NOT_FOUND {
+            let created = answer(
+                request(client.post(api("experiments/create")))
+                    .json(&json!({ "name": config.experiment }))
+                    .send()
+                    .await?,
+            )
+            .await?;
+            created["experiment_id"].clone()
+        } else {
+            answer(found).await?["experiment"]["experiment_id"].clone()
+        };
+        let Some(experiment_id) = experiment_id.as_str().map(str::to_string) else {
+            bail!("no id for experiment '{}'", config.experiment);
+        };
+
+        let created = answer(
+            request(client.post(api("runs/create")))
+                .json(&json!({
+                    "experiment_id": experiment_id,
+                    "run_name": self.name,
+                    "start_time": self.record.started_at.timestamp_millis(),
+                    "tags": [{ "key": "preval.run_id", "value": self.id }],
+                }))
+                .send()
+                .await?,
+        )
+        .await?;
+        let Some(run_id) = created["run"]["info"]["run_id"]
+            .as_str()
+            .map(str::to_string)
+        else {
+            bail!("no id for the created run");
+        };
+
+        for batch in self.mlflow_batches(&run_id) {
+            answer(
+                request(client.post(api("runs/log-batch")))
+                    .json(&batch)
+                    .send()
+                    .await?,
+            )
+            .await?;
+        }
+        answer(
+            request(client.post(api("runs/update")))
+                .json(&json!({
+                    "run_id": run_id,
+                    "status": self.mlflow_status(),
+                    "end_time": self.record.finished_at.timestamp_millis(),
+                }))
+                .send()
+                .await?,
+        )
+        .await?;
+        Ok(run_id)
+    }
+
+    /// Bodies of the `runs/log-batch` requests logging this run to MLflow
+    /// run `run_id`, split to fit its limits
+    fn mlflow_batches(&self, run_id: &str) -> Vec<Value> {
+        let timestamp = self.record.finished_at.timestamp_millis();
+        let metrics: Vec<Value> = self
+            .metrics
+            .iter()
+            .map(|(key, value)| {
+                json!({ "key": mlflow_key(key), "value": value, "timestamp": timestamp, "step": 0 })
+            })
+            .collect();
+        let params: Vec<Value> = self
+            .params
+            .iter()
+            .map(|(key, value)| {
+                let value: String = value.chars().take(MLFLOW_MAX_PARAM_LENGTH).collect();
+                json!({ "key": mlflow_key(key), "value": value })
+            })
+            .collect();
+
+        let batches = metrics
+            .len()
+            .div_ceil(MLFLOW_MAX_BATCH_METRICS)
+            .max(params.len().div_ceil(MLFLOW_MAX_BATCH_PARAMS));
+        let mut metrics = metrics.chunks(MLFLOW_MAX_BATCH_METRICS);
+        let mut params = params.chunks(MLFLOW_MAX_BATCH_PARAMS);
+        (0..batches)
+            .map(|_| {
+                json!({
+                    "run_id": run_id,
+                    "metrics": metrics.next().unwrap_or_default(),
+                    "params": params.next().unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// MLflow status of the finished run
+    fn mlflow_status(&self) -> &'static str {
+        match self.record.outcome {
+            Outcome::Completed => "FINISHED",
+            Outcome::Failed => "FAILED",
+            Outcome::Stopped => "KILLED",
+        }
+    }
+
+    /// Create or update a W&B run with this run's config and summary
+    async fn log_to_wandb(&self, client: &reqwest::Client, config: &WandbConfig) -> Result<()> {
+        let Ok(api_key) = std::env::var(&config.api_key_env) else {
+            bail!("no API key in {}", config.api_key_env);
+        };
+        let response = client
+            .post(format!("{}/graphql", config.url.trim_end_matches('/')))
+            .basic_auth("api", Some(api_key))
+            .json(&self.wandb_request(config))
+            .send()
+            .await?;
+        let response = answer(response).await?;
+        if let Some(error) = response["errors"].get(0) {
+            bail!("{}", error["message"].as_str().unwrap_or("unknown error"));
+        }
+        Ok(())
+    }
+
+    /// GraphQL request upserting this run into the W&B project of `config`
+    fn wandb_request(&self, config: &WandbConfig) -> Value {
+        let run_config: serde_json::Map<String, Value> = self
+            .params
+            .iter()
+            .map(|(key, value)| (key.clone(), json!({ "value": value })))
+            .collect();
+        json!({
+            "query": WANDB_UPSERT_RUN,
+            "variables": {
+                "name": self.id,
+                "project": config.project,
+                "entity": config.entity,
+                "displayName": self.name,
+                "state": match self.record.outcome {
+                    Outcome::Completed => "finished",
+                    Outcome::Failed => "failed",
+                    Outcome::Stopped => "crashed",
+                },
+                "config": Value::Object(run_config).to_string(),
+                "summaryMetrics": json!(self.metrics).to_string(),
+            },
+        })
+    }
+}
+
+/// GraphQL mutation creating or updating a W&B run
+const WANDB_UPSERT_RUN: &str = "mutation UpsertRun($name: String, $project: String, \
+$entity: String, $displayName: String, $state: String, $config: JSONString, \
+$summaryMetrics: JSONString) { upsertBucket(input: { name: $name, modelName: $project, \
+entityName: $entity, displayName: $displayName, state: $state, config: $config, \
+summaryMetrics: $summaryMetrics }) { bucket { id name } } }";
+
+/// JSON body of a successful `response`, or why it failed
+async fn answer(response: reqwest::Response) -> Result<Value> {
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        bail!("{}: {}", status, body.trim());
+    }
+    Ok(serde_json::from_str(&body).unwrap_or(Value::Null))
+}
+
+/// `key` with the characters MLflow does not allow in names replaced
+fn mlflow_key(key: &str) -> String {
+    key.chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c,
+            '_' | '-' | '.' | ' ' | '/' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn tracked_run() -> TrackedRun {
+        let at = |time: &str| {
+            DateTime::parse_from_rfc3339(time)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let record = RunRecord {
+            run_dir: Some("20260102-030405".to_string()),
+            evaluator: "my-eval".to_string(),
+            description: None,
+            evaluator_version: Some("1.2".to_string()),
+            total_samples: Some(10),
+            started_at: at("2026-01-02T03:04:05Z"),
+            finished_at: at("2026-01-02T03:05:20Z"),
+            outcome: Outcome::Failed,
+            failure: Some("accuracy.mean >= 0.9 was 0.750".to_string()),
+            samples_completed: 10,
+            samples_failed: 2,
+            score: None,
+            restarts: 0,
+            samples_dropped: 0,
+            attempts: Vec::new(),
+        };
+        TrackedRun::new(record, &crate::state::RunState::default())
+    }
+
+    #[test]
+    fn runs_carry_their_params_and_aggregates() {
+        let run = tracked_run();
+        assert_eq!(run.id, "20260102-030405");
+        assert_eq!(run.params["evaluator.version"], "1.2");
+        assert_eq!(run.params["total_samples"], "10");
+        assert_eq!(run.metrics["samples_failed"], 2.0);
+        assert_eq!(run.metrics["elapsed_secs"], 75.0);
+    }
+
+    #[test]
+    fn mlflow_batches_fit_its_limits() {
+        let mut run = tracked_run();
+        for i in 0..MLFLOW_MAX_BATCH_METRICS {
+            run.metrics.insert(format!("score@{}", i), i as f64);
+        }
+        run.params.insert("notes".to_string(), "x".repeat(600));
+
+        let batches = run.mlflow_batches("abc");
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0]["run_id"], "abc");
+        assert_eq!(
+            batches[0]["metrics"].as_array().unwrap().len(),
+            MLFLOW_MAX_BATCH_METRICS
+        );
+        assert!(batches[0]["metrics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|metric| metric["key"] == "score_0"));
+        let notes = batches[0]["params"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|param| param["key"] == "notes")
+            .unwrap();
+        assert_eq!(notes["value"].as_str().unwrap().len(), 500);
+        assert!(batches[1]["params"].as_array().unwrap().is_empty());
+        assert_eq!(run.mlflow_status(), "FAILED");
+    }
+
+    #[test]
+    fn wandb_runs_are_upserted_with_config_and_summary() {
+        let config: WandbConfig = toml::from_str(r#"project = "evals""#).unwrap();
+        assert_eq!(config.url, "https://api.wandb.ai");
+
+        let request = tracked_run().wandb_request(&config);
+        let variables = &request["variables"];
+        assert_eq!(variables["name"], "20260102-030405");
+        assert_eq!(variables["project"], "evals");
+        assert_eq!(variables["state"], "failed");
+        let run_config: Value =
+            serde_json::from_str(variables["config"].as_str().unwrap()).unwrap();
+        assert_eq!(run_config["evaluator"]["value"], "my-eval");
+        let summary: Value =
+            serde_json::from_str(variables["summaryMetrics"].as_str().unwrap()).unwrap();
+        assert_eq!(summary["samples_completed"], 10.0);
+    }
+}
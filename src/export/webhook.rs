@@ -0,0 +1,271 @@
+// Webhooks told when a run starts, ends or first breaks a threshold rule
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Number of notices that may be queued before new ones are dropped
+const WEBHOOK_QUEUE_SIZE: usize = 64;
+
+/// Longest a webhook may take to answer before it is given up on
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What happened to a run that webhooks can be told about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEvent {
+    /// The evaluator was started, including when the run is restarted
+    Started,
+    /// The run ended without failing
+    Completed,
+    /// The run failed, e.g. by breaking an aggregate threshold rule
+    Failed,
+    /// A sample first broke a threshold rule
+    Threshold,
+}
+
+impl WebhookEvent {
+    /// Every event, which webhooks are told about unless they pick some
+    const ALL: [WebhookEvent; 4] = [
+        WebhookEvent::Started,
+        WebhookEvent::Completed,
+        WebhookEvent::Failed,
+        WebhookEvent::Threshold,
+    ];
+
+    /// Name of the event, as written in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            WebhookEvent::Started => "started",
+            WebhookEvent::Completed => "completed",
+            WebhookEvent::Failed => "failed",
+            WebhookEvent::Threshold => "threshold",
+        }
+    }
+}
+
+/// HTTP(S) URL a webhook posts to, checked when the config is loaded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct WebhookUrl(reqwest::Url);
+
+impl TryFrom<String> for WebhookUrl {
+    type Error = WebhookUrlError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let url = reqwest::Url::parse(input.trim())
+            .map_err(|e| WebhookUrlError(format!("{}: {}", input, e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(WebhookUrlError(format!(
+                "{}: expected an http or https URL",
+                input
+            )));
+        }
+        Ok(Self(url))
+    }
+}
+
+impl From<WebhookUrl> for String {
+    fn from(url: WebhookUrl) -> Self {
+        url.0.into()
+    }
+}
+
+/// Error for webhook URLs that cannot be posted to
+#[derive(Debug, thiserror::Error)]
+#[error("invalid webhook URL {0}")]
+pub struct WebhookUrlError(String);
+
+/// A URL posted to when chosen events happen to a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Webhook {
+    pub url: WebhookUrl,
+    /// Events posted; every event when left out
+    #[serde(default = "Webhook::all_events")]
+    pub events: Vec<WebhookEvent>,
+    /// JSON body posted, with `{{event}}`, `{{evaluator}}`, `{{run_id}}`,
+    /// `{{message}}`, `{{completed}}`, `{{failed}}`, `{{total}}` and
+    /// `{{elapsed}}` filled in; a JSON object of all of them when left out
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+impl Webhook {
+    fn all_events() -> Vec<WebhookEvent> {
+        WebhookEvent::ALL.to_vec()
+    }
+
+    /// Body posted for `notice`
+    ///
+    /// Values are escaped as the inside of a JSON string, so placeholders
+    /// belong between quotes in the template.
+    pub fn body(&self, notice: &WebhookNotice) -> String {
+        let Some(template) = &self.template else {
+            return serde_json::to_string(notice).unwrap_or_default();
+        };
+        let escaped = |value: String| {
+            let quoted = serde_json::to_string(&value).unwrap_or_default();
+            quoted[1..quoted.len() - 1].to_string()
+        };
+        let fields = [
+            ("event", notice.event.name().to_string()),
+            ("evaluator", notice.evaluator.clone().unwrap_or_default()),
+            ("run_id", notice.run_id.clone().unwrap_or_default()),
+            ("message", notice.message.clone()),
+            ("completed", notice.samples_completed.to_string()),
+            ("failed", notice.samples_failed.to_string()),
+            (
+                "total",
+                notice
+                    .total_samples
+                    .map(|n| n.to_string())
+                    .unwrap_or_default(),
+            ),
+            ("elapsed", format!("{:.0}", notice.elapsed_secs)),
+        ];
+        fields
+            .into_iter()
+            .fold(template.clone(), |body, (name, value)| {
+                body.replace(&format!("{{{{{}}}}}", name), &escaped(value))
+            })
+    }
+}
+
+/// What a webhook is told about a run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookNotice {
+    pub event: WebhookEvent,
+    pub evaluator: Option<String>,
+    /// Id of the run's directory, when it is recorded
+    pub run_id: Option<String>,
+    /// What happened, in words
+    pub message: String,
+    pub samples_completed: usize,
+    pub samples_failed: usize,
+    pub total_samples: Option<usize>,
+    pub elapsed_secs: f64,
+}
+
+/// Handle to a background task posting notices to webhooks
+///
+/// Posting never holds up the run: notices queue up and a webhook that
+/// fails or is slow to answer only gets a warning in the log.
+pub(crate) struct WebhookNotifier {
+    notice_tx: mpsc::Sender<WebhookNotice>,
+    task: JoinHandle<()>,
+}
+
+impl WebhookNotifier {
+    /// Start posting to `webhooks`
+    pub(crate) fn spawn(webhooks: Vec<Webhook>) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .context("Failed to create webhook client")?;
+        let (notice_tx, mut notice_rx) = mpsc::channel::<WebhookNotice>(WEBHOOK_QUEUE_SIZE);
+
+        let task = tokio::spawn(async move {
+            while let Some(notice) = notice_rx.recv().await {
+                for webhook in webhooks.iter().filter(|w| w.events.contains(&notice.event)) {
+                    let response = client
+                        .post(webhook.url.0.clone())
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .body(webhook.body(&notice))
+                        .send()
+                        .await;
+                    match response {
+                        Ok(response) if !response.status().is_success() => {
+                            tracing::warn!(
+                                "Webhook {} rejected the {} notice: {}",
+                                webhook.url.0,
+                                notice.event.name(),
+                                response.status()
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            tracing::warn!("Failed to post to webhook {}: {}", webhook.url.0, e);
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Self { notice_tx, task })
+    }
+
+    /// Queue `notice` for the webhooks that want its event
+    pub(crate) fn notify(&self, notice: WebhookNotice) {
+        if let Err(mpsc::error::TrySendError::Full(_)) = self.notice_tx.try_send(notice) {
+            tracing::warn!("Webhook queue full, dropping notice");
+        }
+    }
+
+    /// Wait until the queued notices are posted, e.g. before PrEval exits
+    pub(crate) async fn finish(self) {
+        drop(self.notice_tx);
+        if self.task.await.is_err() {
+            tracing::warn!("Webhook task panicked");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notice() -> WebhookNotice {
+        WebhookNotice {
+            event: WebhookEvent::Failed,
+            evaluator: Some("python eval.py".to_string()),
+            run_id: None,
+            message: "accuracy.mean >= 0.9 was \"0.750\"".to_string(),
+            samples_completed: 8,
+            samples_failed: 2,
+            total_samples: Some(10),
+            elapsed_secs: 75.4,
+        }
+    }
+
+    #[test]
+    fn webhooks_parse_from_config() {
+        let webhook: Webhook = toml::from_str(
+            r#"
+            url = "https://hooks.example.com/preval"
+            events = ["failed", "threshold"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            webhook.events,
+            vec![WebhookEvent::Failed, WebhookEvent::Threshold]
+        );
+
+        let webhook: Webhook = toml::from_str(r#"url = "http://localhost:8080/""#).unwrap();
+        assert_eq!(webhook.events, WebhookEvent::ALL.to_vec());
+
+        assert!(toml::from_str::<Webhook>(r#"url = "ftp://example.com""#).is_err());
+        assert!(toml::from_str::<Webhook>(r#"url = "not a url""#).is_err());
+    }
+
+    #[test]
+    fn templates_are_filled_in_with_escaped_values() {
+        let mut webhook: Webhook = toml::from_str(r#"url = "http://localhost/""#).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&webhook.body(&notice())).unwrap();
+        assert_eq!(json["event"], "failed");
+        assert_eq!(json["samples_failed"], 2);
+
+        webhook.template = Some(
+            r#"{"text": "{{evaluator}} {{event}} after {{elapsed}}s ({{completed}}/{{total}}): {{message}}"}"#
+                .to_string(),
+        );
+        let json: serde_json::Value = serde_json::from_str(&webhook.body(&notice())).unwrap();
+        assert_eq!(
+            json["text"],
+            "python eval.py failed after 75s (8/10): accuracy.mean >= 0.9 was \"0.750\""
+        );
+    }
+}
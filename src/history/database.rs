@@ -0,0 +1,507 @@
+// SQLite database of finished runs, for `preval history` and `preval show`
+
+use crate::state::aggregation::MetricAggregator;
+use crate::state::baseline::Baseline;
+use crate::state::types::{EvaluationStatus, SampleResult, SampleStatus};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_dir TEXT,
+    evaluator TEXT NOT NULL,
+    description TEXT,
+    evaluator_version TEXT,
+    total_samples INTEGER,
+    started_at TEXT NOT NULL,
+    finished_at TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    failure TEXT,
+    samples_completed INTEGER NOT NULL,
+    samples_failed INTEGER NOT NULL,
+    metric_stats TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS samples (
+    run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    sample_id TEXT NOT NULL,
+    failed INTEGER NOT NULL,
+    result TEXT NOT NULL,
+    PRIMARY KEY (run, position)
+);
+";
+
+/// Changes to the schema since it was created, applied in order; the
+/// database's `user_version` counts those already applied
+const MIGRATIONS: [&str; 4] = [
+    "ALTER TABLE runs ADD COLUMN score REAL;",
+    "ALTER TABLE runs ADD COLUMN restarts INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE runs ADD COLUMN samples_dropped INTEGER NOT NULL DEFAULT 0;",
+    "CREATE TABLE attempts (
+        run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+        attempt INTEGER NOT NULL,
+        started_at TEXT NOT NULL,
+        finished_at TEXT,
+        exit_code INTEGER,
+        signal TEXT,
+        PRIMARY KEY (run, attempt)
+    );",
+];
+
+/// How a recorded run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Outcome {
+    Completed,
+    Failed,
+    /// Quit before the evaluator finished
+    Stopped,
+}
+
+impl Outcome {
+    /// Outcome of a run that ended in `status`
+    pub(crate) fn of(status: &EvaluationStatus) -> Self {
+        match status {
+            EvaluationStatus::Completed => Self::Completed,
+            EvaluationStatus::Failed(_) => Self::Failed,
+            _ => Self::Stopped,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Stopped => "stopped",
+        }
+    }
+
+    fn from_str(outcome: &str) -> Self {
+        match outcome {
+            "completed" => Self::Completed,
+            "failed" => Self::Failed,
+            _ => Self::Stopped,
+        }
+    }
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One start of the evaluator within a run; a run has several when a
+/// crashed evaluator was restarted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RunAttempt {
+    /// Which attempt this was (1-based)
+    pub(crate) attempt: u32,
+    pub(crate) started_at: DateTime<Utc>,
+    /// None while the evaluator is still running, or if the run ended
+    /// without its exit being observed
+    pub(crate) finished_at: Option<DateTime<Utc>>,
+    pub(crate) exit_code: Option<i32>,
+    /// Signal that ended the evaluator, e.g. `SIGKILL`
+    pub(crate) signal: Option<String>,
+}
+
+/// Metadata of one recorded run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RunRecord {
+    /// Run directory id, when the run was recorded there too
+    pub(crate) run_dir: Option<String>,
+    pub(crate) evaluator: String,
+    pub(crate) description: Option<String>,
+    pub(crate) evaluator_version: Option<String>,
+    pub(crate) total_samples: Option<u32>,
+    pub(crate) started_at: DateTime<Utc>,
+    pub(crate) finished_at: DateTime<Utc>,
+    pub(crate) outcome: Outcome,
+    pub(crate) failure: Option<String>,
+    pub(crate) samples_completed: usize,
+    pub(crate) samples_failed: usize,
+    /// Mean composite score, when the run had one
+    pub(crate) score: Option<f64>,
+    /// Times a crashed evaluator was restarted during the run
+    #[serde(default)]
+    pub(crate) restarts: u32,
+    /// Samples that could no longer be read when the run was recorded, so
+    /// are missing from it
+    #[serde(default)]
+    pub(crate) samples_dropped: usize,
+    /// Each start of the evaluator, oldest first; only loaded with the
+    /// whole run, not when listing runs
+    #[serde(default)]
+    pub(crate) attempts: Vec<RunAttempt>,
+}
+
+/// A recorded run with everything needed to show it again
+#[derive(Debug)]
+pub(crate) struct StoredRun {
+    /// Id in the run history; None for a run recovered from its snapshot
+    pub(crate) id: Option<i64>,
+    pub(crate) record: RunRecord,
+    pub(crate) metric_stats: MetricAggregator,
+    pub(crate) samples: Vec<SampleResult>,
+}
+
+impl StoredRun {
+    /// The run as a baseline for another to be compared against
+    pub(crate) fn into_baseline(self) -> Baseline {
+        let record = self.record;
+        Baseline {
+            label: match (self.id, record.run_dir) {
+                (Some(id), _) => format!("run {}", id),
+                (None, Some(run_dir)) => format!("run {}", run_dir),
+                (None, None) => "recovered run".to_string(),
+            },
+            evaluator: record.evaluator,
+            total_samples: record.total_samples,
+            samples_completed: record.samples_completed,
+            samples_failed: record.samples_failed,
+            score: record.score,
+            metric_stats: self.metric_stats,
+        }
+    }
+}
+
+/// Database of every run PrEval has finished on this machine
+pub(crate) struct RunDatabase {
+    connection: Connection,
+}
+
+impl RunDatabase {
+    /// Default location of the database for this platform
+    /// (e.g. `~/.local/share/preval/history.db` on Linux)
+    pub(crate) fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("preval").join("history.db"))
+    }
+
+    /// Open the database at `path`, creating it if needed
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open run history {}", path.display()))?;
+        connection
+            .execute_batch(SCHEMA)
+            .context("Failed to create run history tables")?;
+
+        let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (applied, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            connection
+                .execute_batch(&format!(
+                    "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+                    migration,
+                    applied + 1
+                ))
+                .context("Failed to upgrade the run history database")?;
+        }
+        Ok(Self { connection })
+    }
+
+    /// Record a finished run with its statistics and samples, returning its id
+    pub(crate) fn record_run(
+        &mut self,
+        record: &RunRecord,
+        metric_stats: &MetricAggregator,
+        samples: &[SampleResult],
+    ) -> Result<i64> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO runs (run_dir, evaluator, description, evaluator_version, total_samples,
+                 started_at, finished_at, outcome, failure, samples_completed, samples_failed,
+                 score, restarts, samples_dropped, metric_stats)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                record.run_dir,
+                record.evaluator,
+                record.description,
+                record.evaluator_version,
+                record.total_samples,
+                record.started_at,
+                record.finished_at,
+                record.outcome.as_str(),
+                record.failure,
+                record.samples_completed,
+                record.samples_failed,
+                record.score,
+                record.restarts,
+                record.samples_dropped,
+                serde_json::to_string(metric_stats)?,
+            ],
+        )?;
+        let id = transaction.last_insert_rowid();
+
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO samples (run, position, sample_id, failed, result)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (position, sample) in samples.iter().enumerate() {
+                insert.execute(params![
+                    id,
+                    position,
+                    sample.sample_id,
+                    matches!(sample.status, SampleStatus::Failed(_)),
+                    serde_json::to_string(sample)?,
+                ])?;
+            }
+        }
+
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO attempts (run, attempt, started_at, finished_at, exit_code, signal)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for attempt in &record.attempts {
+                insert.execute(params![
+                    id,
+                    attempt.attempt,
+                    attempt.started_at,
+                    attempt.finished_at,
+                    attempt.exit_code,
+                    attempt.signal,
+                ])?;
+            }
+        }
+
+        transaction.commit()?;
+        Ok(id)
+    }
+
+    /// Replace the recorded result of a sample of run `id`, e.g. once it
+    /// was bookmarked after the run
+    pub(crate) fn update_sample(&self, id: i64, sample: &SampleResult) -> Result<()> {
+        self.connection.execute(
+            "UPDATE samples SET result = ?3 WHERE run = ?1 AND sample_id = ?2",
+            params![id, sample.sample_id, serde_json::to_string(sample)?],
+        )?;
+        Ok(())
+    }
+
+    /// The `limit` most recent runs, newest first, or the best scoring ones
+    /// first when `by_score`
+    pub(crate) fn list_runs(&self, limit: usize, by_score: bool) -> Result<Vec<(i64, RunRecord)>> {
+        let order = if by_score {
+            "score IS NULL, score DESC, id DESC"
+        } else {
+            "id DESC"
+        };
+        let mut query = self.connection.prepare(&format!(
+            "SELECT id, run_dir, evaluator, description, evaluator_version, total_samples,
+                 started_at, finished_at, outcome, failure, samples_completed, samples_failed,
+                 score, restarts, samples_dropped
+             FROM runs ORDER BY {} LIMIT ?1",
+            order
+        ))?;
+        let runs = query
+            .query_map(params![limit], |row| Ok((row.get(0)?, Self::record(row)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(runs)
+    }
+
+    /// Load run `id` with its statistics and samples
+    pub(crate) fn load_run(&self, id: i64) -> Result<StoredRun> {
+        let (mut record, metric_stats) = self
+            .connection
+            .query_row(
+                "SELECT id, run_dir, evaluator, description, evaluator_version, total_samples,
+                     started_at, finished_at, outcome, failure, samples_completed,
+                     samples_failed, score, restarts, samples_dropped, metric_stats
+                 FROM runs WHERE id = ?1",
+                params![id],
+                |row| Ok((Self::record(row)?, row.get::<_, String>(15)?)),
+            )
+            .optional()?
+            .with_context(|| format!("No run {} in the run history", id))?;
+
+        let mut query = self
+            .connection
+            .prepare("SELECT result FROM samples WHERE run = ?1 ORDER BY position")?;
+        let samples = query
+            .query_map(params![id], |row| row.get::<_, String>(0))?
+            .map(|result| Ok(serde_json::from_str(&result?)?))
+            .collect::<Result<_>>()?;
+
+        let mut query = self.connection.prepare(
+            "SELECT attempt, started_at, finished_at, exit_code, signal
+             FROM attempts WHERE run = ?1 ORDER BY attempt",
+        )?;
+        record.attempts = query
+            .query_map(params![id], |row| {
+                Ok(RunAttempt {
+                    attempt: row.get(0)?,
+                    started_at: row.get(1)?,
+                    finished_at: row.get(2)?,
+                    exit_code: row.get(3)?,
+                    signal: row.get(4)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(StoredRun {
+            id: Some(id),
+            record,
+            metric_stats: serde_json::from_str(&metric_stats)
+                .context("Corrupt metric statistics in run history")?,
+            samples,
+        })
+    }
+
+    /// Read a run's metadata from a row starting with its id
+    fn record(row: &rusqlite::Row<'_>) -> rusqlite::Result<RunRecord> {
+        Ok(RunRecord {
+            run_dir: row.get(1)?,
+            evaluator: row.get(2)?,
+            description: row.get(3)?,
+            evaluator_version: row.get(4)?,
+            total_samples: row.get(5)?,
+            started_at: row.get(6)?,
+            finished_at: row.get(7)?,
+            outcome: Outcome::from_str(&row.get::<_, String>(8)?),
+            failure: row.get(9)?,
+            samples_completed: row.get(10)?,
+            samples_failed: row.get(11)?,
+            score: row.get(12)?,
+            restarts: row.get(13)?,
+            samples_dropped: row.get(14)?,
+            attempts: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(evaluator: &str) -> RunRecord {
+        RunRecord {
+            run_dir: None,
+            evaluator: evaluator.to_string(),
+            description: None,
+            evaluator_version: Some("1.0".to_string()),
+            total_samples: Some(2),
+            started_at: Utc::now(),
+            finished_at: Utc::now(),
+            outcome: Outcome::Failed,
+            failure: Some("Threshold rules violated".to_string()),
+            samples_completed: 2,
+            samples_failed: 1,
+            score: None,
+            restarts: 0,
+            samples_dropped: 0,
+            attempts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn recorded_runs_can_be_listed_and_loaded() {
+        let dir = std::env::temp_dir().join(format!("preval-history-{}", std::process::id()));
+        let mut database = RunDatabase::open(&dir.join("history.db")).unwrap();
+
+        let mut stats = MetricAggregator::new();
+        stats.record("accuracy", 0.5);
+        let mut passed = SampleResult::new_processing("a".to_string());
+        passed.mark_completed(vec![("accuracy".to_string(), 0.5)]);
+        let mut failed = SampleResult::new_processing("b".to_string());
+        failed.mark_failed("boom".to_string());
+
+        let started_at = Utc::now();
+        let crashed = RunAttempt {
+            attempt: 1,
+            started_at,
+            finished_at: Some(started_at + chrono::Duration::seconds(5)),
+            exit_code: None,
+            signal: Some("SIGKILL".to_string()),
+        };
+        let retried = RunAttempt {
+            attempt: 2,
+            started_at: started_at + chrono::Duration::seconds(6),
+            finished_at: Some(started_at + chrono::Duration::seconds(9)),
+            exit_code: Some(0),
+            signal: None,
+        };
+        let restarted = RunRecord {
+            restarts: 1,
+            samples_dropped: 3,
+            attempts: vec![crashed.clone(), retried.clone()],
+            ..record("first")
+        };
+        let first = database
+            .record_run(&restarted, &stats, &[passed, failed])
+            .unwrap();
+        let second = database
+            .record_run(&record("second"), &MetricAggregator::new(), &[])
+            .unwrap();
+
+        let runs = database.list_runs(10, false).unwrap();
+        let ids: Vec<_> = runs.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![second, first]);
+
+        let run = database.load_run(first).unwrap();
+        assert_eq!(run.record.evaluator, "first");
+        assert_eq!(run.record.outcome, Outcome::Failed);
+        assert_eq!(run.record.restarts, 1);
+        assert_eq!(run.record.attempts, vec![crashed, retried]);
+        // Listing runs leaves out their attempts
+        assert!(runs[1].1.attempts.is_empty());
+        assert_eq!(run.record.samples_dropped, 3);
+        assert_eq!(run.metric_stats.get("accuracy").map(|s| s.count()), Some(1));
+        let sample_ids: Vec<_> = run.samples.iter().map(|s| s.sample_id.as_str()).collect();
+        assert_eq!(sample_ids, vec!["a", "b"]);
+
+        // Samples bookmarked after the run are updated in place
+        let mut bookmarked = run.samples[1].clone();
+        bookmarked.annotation.bookmarked = true;
+        database.update_sample(first, &bookmarked).unwrap();
+        let run = database.load_run(first).unwrap();
+        assert!(run.samples[1].annotation.bookmarked);
+        assert!(!run.samples[0].annotation.bookmarked);
+
+        assert!(database.load_run(second + 1).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn runs_can_be_ranked_by_score() {
+        let dir = std::env::temp_dir().join(format!("preval-ranking-{}", std::process::id()));
+        let path = dir.join("history.db");
+        let mut database = RunDatabase::open(&path).unwrap();
+
+        let mut ids = Vec::new();
+        for score in [Some(0.5), None, Some(0.9)] {
+            let record = RunRecord {
+                score,
+                ..record("scored")
+            };
+            ids.push(
+                database
+                    .record_run(&record, &MetricAggregator::new(), &[])
+                    .unwrap(),
+            );
+        }
+
+        // Reopening must not apply the migrations again
+        drop(database);
+        let database = RunDatabase::open(&path).unwrap();
+        let ranked: Vec<_> = database
+            .list_runs(10, true)
+            .unwrap()
+            .into_iter()
+            .map(|(id, run)| (id, run.score))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![(ids[2], Some(0.9)), (ids[0], Some(0.5)), (ids[1], None)]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
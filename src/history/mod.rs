@@ -0,0 +1,160 @@
+// Records of past runs, kept on disk after the TUI closes
+
+pub(crate) mod database;
+pub(crate) mod snapshot;
+pub(crate) mod status;
+pub(crate) mod transcript;
+
+use crate::state::aggregation::MetricAggregator;
+use crate::state::timeline::Timeline;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use snapshot::RunSnapshot;
+use status::RunStatus;
+use std::path::{Path, PathBuf};
+
+/// Directory holding everything recorded about one run
+#[derive(Debug, Clone)]
+pub(crate) struct RunDir {
+    id: String,
+    path: PathBuf,
+}
+
+impl RunDir {
+    /// Create a fresh run directory under `root`
+    ///
+    /// Ids sort chronologically: the UTC start time, plus PrEval's pid so
+    /// concurrent runs never collide.
+    pub(crate) fn create(root: &Path) -> Result<Self> {
+        let id = format!(
+            "{}-{}",
+            Utc::now().format("%Y%m%dT%H%M%SZ"),
+            std::process::id()
+        );
+        let path = root.join(&id);
+        std::fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create run directory {}", path.display()))?;
+
+        Ok(Self { id, path })
+    }
+
+    /// Open the existing directory of run `id` under `root`
+    pub(crate) fn open(root: &Path, id: &str) -> Result<Self> {
+        let path = root.join(id);
+        if !path.is_dir() {
+            anyhow::bail!("No run '{}' in {}", id, root.display());
+        }
+
+        Ok(Self {
+            id: id.to_string(),
+            path,
+        })
+    }
+
+    /// Default location of run directories for this platform
+    /// (e.g. `~/.local/share/preval/runs` on Linux)
+    pub(crate) fn default_root() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("preval").join("runs"))
+    }
+
+    /// Identifier of the run
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Directory holding the run's files
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of the transcript file
+    pub(crate) fn transcript_path(&self) -> PathBuf {
+        self.path.join("transcript.jsonl")
+    }
+
+    /// Path of the file holding samples that no longer fit in memory
+    pub(crate) fn samples_path(&self) -> PathBuf {
+        self.path.join("samples.jsonl")
+    }
+
+    /// Save per-metric statistics, including the percentile bins, so
+    /// runs can be compared or merged later; returns the file written
+    pub(crate) fn write_metric_stats(&self, stats: &MetricAggregator) -> Result<PathBuf> {
+        let path = self.path.join("metric-stats.json");
+        let json = serde_json::to_string_pretty(stats).context("Failed to encode statistics")?;
+        std::fs::write(&path, json)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Save the run's notable events as JSON Lines, one event per line;
+    /// returns the file written
+    pub(crate) fn write_timeline(&self, timeline: &Timeline) -> Result<PathBuf> {
+        let path = self.path.join("timeline.jsonl");
+        let mut lines = String::new();
+        for event in timeline.events() {
+            lines.push_str(&serde_json::to_string(event).context("Failed to encode event")?);
+            lines.push('\n');
+        }
+        std::fs::write(&path, lines)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Replace the run's snapshot, so a crash mid-write leaves the previous
+    /// snapshot intact
+    pub(crate) fn write_snapshot(&self, snapshot: &RunSnapshot) -> Result<()> {
+        let json = serde_json::to_vec(snapshot).context("Failed to encode snapshot")?;
+        self.replace_file("snapshot.json", &json)
+    }
+
+    /// Path of the live status of the run
+    pub(crate) fn status_path(&self) -> PathBuf {
+        self.path.join("status.json")
+    }
+
+    /// Replace the run's live status, so watchers never read half of one
+    pub(crate) fn write_status(&self, status: &RunStatus) -> Result<()> {
+        let json = serde_json::to_vec_pretty(status).context("Failed to encode status")?;
+        self.replace_file("status.json", &json)
+    }
+
+    /// Replace the file `name` with `contents`, writing them to a temporary
+    /// file first so a crash mid-write leaves the previous file intact
+    fn replace_file(&self, name: &str, contents: &[u8]) -> Result<()> {
+        let path = self.path.join(name);
+        let partial = self.path.join(format!("{}.tmp", name));
+        std::fs::write(&partial, contents)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        std::fs::rename(&partial, &path)
+            .with_context(|| format!("Failed to replace {}", path.display()))
+    }
+
+    /// Latest snapshot of the run
+    pub(crate) fn read_snapshot(&self) -> Result<RunSnapshot> {
+        let path = self.path.join("snapshot.json");
+        let json = std::fs::read(&path)
+            .with_context(|| format!("No snapshot of run '{}' to recover", self.id))?;
+        serde_json::from_slice(&json)
+            .with_context(|| format!("Corrupt snapshot {}", path.display()))
+    }
+
+    /// Path of the log written by a detached daemon
+    pub(crate) fn daemon_log_path(&self) -> PathBuf {
+        self.path.join("daemon.log")
+    }
+
+    /// Remember the pid of the daemon running this run
+    pub(crate) fn write_daemon_pid(&self, pid: u32) -> Result<()> {
+        let path = self.path.join("daemon.pid");
+        std::fs::write(&path, pid.to_string())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Pid of the daemon running this run, if it was started detached
+    pub(crate) fn daemon_pid(&self) -> Option<u32> {
+        std::fs::read_to_string(self.path.join("daemon.pid"))
+            .ok()
+            .and_then(|pid| pid.trim().parse().ok())
+    }
+}
@@ -0,0 +1,124 @@
+// Periodic snapshots of a run in progress, so a crash of PrEval or the
+// machine does not lose what had completed
+
+use super::database::{Outcome, RunRecord, StoredRun};
+use crate::state::aggregation::MetricAggregator;
+use crate::state::types::SampleResult;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
+
+/// How often a recorded run saves a snapshot
+pub(crate) const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Compact state of a run at one moment
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RunSnapshot {
+    pub(crate) taken_at: DateTime<Utc>,
+    /// Progress so far; `finished_at` is when the snapshot was taken
+    pub(crate) record: RunRecord,
+    pub(crate) metric_stats: MetricAggregator,
+    /// Samples that had been moved out of memory; those spilled are in the
+    /// run's `samples.jsonl`
+    pub(crate) evicted_samples: usize,
+    /// Samples held in memory, oldest first
+    pub(crate) samples: Vec<SampleResult>,
+}
+
+impl RunSnapshot {
+    /// Run as it was when the snapshot was taken, with the samples spilled
+    /// to `spill_path` before then, if there are any
+    pub(crate) fn recover(self, spill_path: &Path) -> Result<StoredRun> {
+        let mut samples = match std::fs::File::open(spill_path) {
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .take(self.evicted_samples)
+                .map(|line| {
+                    let line = line?;
+                    serde_json::from_str(&line).context("Corrupt sample in spill file")
+                })
+                .collect::<Result<Vec<SampleResult>>>()
+                .with_context(|| format!("Failed to read {}", spill_path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to open {}", spill_path.display()))
+            }
+        };
+        samples.extend(self.samples);
+
+        let mut record = self.record;
+        if record.failure.is_none() && record.outcome != Outcome::Completed {
+            record.failure = Some(format!(
+                "Recovered from a snapshot taken at {}",
+                self.taken_at.format("%Y-%m-%d %H:%M:%S UTC")
+            ));
+        }
+        Ok(StoredRun {
+            id: None,
+            record,
+            metric_stats: self.metric_stats,
+            samples,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str) -> SampleResult {
+        let mut sample = SampleResult::new_processing(id.to_string());
+        sample.mark_completed(vec![("accuracy".to_string(), 1.0)]);
+        sample
+    }
+
+    #[test]
+    fn recovers_spilled_and_in_memory_samples() {
+        let dir = std::env::temp_dir().join(format!("preval-snapshot-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let spill_path = dir.join("samples.jsonl");
+        // The third spilled sample was written after the snapshot was taken
+        let spilled: String = ["a", "b", "c"]
+            .into_iter()
+            .map(|id| serde_json::to_string(&sample(id)).unwrap() + "\n")
+            .collect();
+        std::fs::write(&spill_path, spilled).unwrap();
+
+        let now = Utc::now();
+        let snapshot = RunSnapshot {
+            taken_at: now,
+            record: RunRecord {
+                run_dir: Some("run".to_string()),
+                evaluator: "eval".to_string(),
+                description: None,
+                evaluator_version: None,
+                total_samples: Some(10),
+                started_at: now,
+                finished_at: now,
+                outcome: Outcome::Stopped,
+                failure: None,
+                samples_completed: 4,
+                samples_failed: 0,
+                score: None,
+                restarts: 0,
+                samples_dropped: 0,
+                attempts: Vec::new(),
+            },
+            metric_stats: MetricAggregator::new(),
+            evicted_samples: 2,
+            samples: vec![sample("c"), sample("d")],
+        };
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: RunSnapshot = serde_json::from_str(&json).unwrap();
+
+        let run = snapshot.recover(&spill_path).unwrap();
+        let ids: Vec<_> = run.samples.iter().map(|s| s.sample_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c", "d"]);
+        assert!(run.record.failure.unwrap().starts_with("Recovered"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+// Small live status of a run in its directory, for dashboards, editor
+// plugins and scripts to poll without attaching to the TUI
+
+use crate::state::app::AppData;
+use crate::state::timeline::TimelineEventKind;
+use crate::state::types::{EvaluationStatus, StageProgress};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often a recorded run rewrites its status file
+pub(crate) const STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Where a run is at, as written to `status.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RunStatus {
+    pub(crate) updated_at: DateTime<Utc>,
+    pub(crate) run_id: String,
+    pub(crate) evaluator: Option<String>,
+    /// Phase of the run: "starting", "running", "completed" or "failed"
+    pub(crate) status: String,
+    pub(crate) paused: bool,
+    pub(crate) elapsed_secs: f64,
+    pub(crate) samples_received: usize,
+    pub(crate) total_samples: Option<usize>,
+    /// Percentage of the planned samples received, when there is a plan
+    pub(crate) percent: Option<f64>,
+    pub(crate) samples_failed: usize,
+    pub(crate) samples_per_sec: Option<f64>,
+    pub(crate) tokens_per_sec: Option<f64>,
+    /// Expected time left, when it can be estimated
+    pub(crate) eta_secs: Option<f64>,
+    /// Phase of the execution plan the run is in, when it has phases
+    pub(crate) phase: Option<StageStatus>,
+    /// Batch of the execution plan the run is in, when it is batched
+    pub(crate) batch: Option<StageStatus>,
+    /// Why the run failed, or else the latest problem it carried on past
+    pub(crate) last_error: Option<String>,
+}
+
+/// Progress through a phase or batch of the execution plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct StageStatus {
+    pub(crate) name: Option<String>,
+    pub(crate) number: usize,
+    pub(crate) of: usize,
+    pub(crate) completed: usize,
+    pub(crate) samples: usize,
+}
+
+impl From<StageProgress> for StageStatus {
+    fn from(stage: StageProgress) -> Self {
+        Self {
+            name: stage.name,
+            number: stage.number,
+            of: stage.stages,
+            completed: stage.completed,
+            samples: stage.samples,
+        }
+    }
+}
+
+impl RunStatus {
+    /// Status of the run `run_id` in `state` now
+    pub(crate) fn of(state: &AppData, run_id: &str) -> Self {
+        let (received, total, percent) = state.progress();
+        let (samples_failed, _, _) = state.summary_stats();
+        let throughput = state.throughput();
+        let status = match state.status() {
+            EvaluationStatus::Starting | EvaluationStatus::WaitingForHandshake => "starting",
+            EvaluationStatus::CollectingMetrics { .. } => "running",
+            EvaluationStatus::Completed => "completed",
+            EvaluationStatus::Failed(_) => "failed",
+        };
+        let last_error = match state.status() {
+            EvaluationStatus::Failed(reason) => Some(reason.clone()),
+            _ => state.error_banner().map(str::to_string).or_else(|| {
+                state
+                    .timeline()
+                    .events()
+                    .rev()
+                    .find(|event| event.kind == TimelineEventKind::ParseError)
+                    .map(|event| event.message.clone())
+            }),
+        };
+
+        Self {
+            updated_at: Utc::now(),
+            run_id: run_id.to_string(),
+            evaluator: state.evaluator_name().map(|name| name.as_ref().to_string()),
+            status: status.to_string(),
+            paused: state.is_paused(),
+            elapsed_secs: state.elapsed_time().as_secs_f64(),
+            samples_received: received,
+            total_samples: total,
+            percent: total.map(|_| percent),
+            samples_failed,
+            samples_per_sec: throughput.map(|t| t.samples_per_sec),
+            tokens_per_sec: throughput.and_then(|t| t.tokens_per_sec),
+            eta_secs: (!state.is_terminal())
+                .then(|| state.calculate_eta())
+                .flatten()
+                .map(|eta| eta.expected.as_secs_f64()),
+            phase: state.phase_progress().map(StageStatus::from),
+            batch: state.batch_progress().map(StageStatus::from),
+            last_error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RunState;
+
+    #[test]
+    fn status_reports_progress_and_the_latest_problem() {
+        let mut state = RunState::default();
+        state.record_event(
+            TimelineEventKind::ParseError,
+            "Failed to parse metrics: eof",
+        );
+
+        let status = RunStatus::of(&state, "20260102T030405Z-1");
+        assert_eq!(status.run_id, "20260102T030405Z-1");
+        assert_eq!(status.status, "starting");
+        assert_eq!(status.samples_received, 0);
+        assert_eq!(status.percent, None);
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some("Failed to parse metrics: eof")
+        );
+
+        state.report_error("Failed to write snapshot".to_string());
+        let json = serde_json::to_value(RunStatus::of(&state, "r")).unwrap();
+        assert_eq!(json["last_error"], "Failed to write snapshot");
+        assert_eq!(json["phase"], serde_json::Value::Null);
+    }
+}
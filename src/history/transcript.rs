@@ -0,0 +1,358 @@
+use super::RunDir;
+use crate::evaluator::process::{EvaluatorMessage, EvaluatorProcess, ExitStatus, OutputTap};
+use crate::evaluator::resources::ResourceMonitor;
+use crate::evaluator::source::{MetricSource, SourceFuture};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// How often a followed transcript is checked for new entries
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Where a transcript line came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TranscriptStream {
+    /// Evaluator stdout (the protocol stream)
+    Stdout,
+    /// Evaluator stderr
+    Stderr,
+    /// Events PrEval itself observed, such as exits, restarts and signals
+    Control,
+}
+
+/// One line of the transcript, stamped with when PrEval received it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TranscriptEntry {
+    pub(crate) received_at: DateTime<Utc>,
+    pub(crate) stream: TranscriptStream,
+    pub(crate) line: String,
+    /// Set only on the control entry that ends the run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) outcome: Option<RunOutcome>,
+}
+
+/// How a run ended, as recorded in its final transcript entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RunOutcome {
+    pub(crate) success: bool,
+    pub(crate) exit_code: Option<i32>,
+}
+
+/// Handle to a background task appending entries to a JSON Lines transcript
+///
+/// Unlike metric forwarding, nothing is ever dropped: the transcript is
+/// the record used to debug and replay a run.
+pub(crate) struct TranscriptWriter {
+    entry_tx: mpsc::UnboundedSender<TranscriptEntry>,
+    task: JoinHandle<()>,
+}
+
+impl TranscriptWriter {
+    /// Create the transcript file and start writing to it
+    pub(crate) async fn create(path: &Path) -> Result<Self> {
+        let file = tokio::fs::File::create(path)
+            .await
+            .with_context(|| format!("Failed to create transcript {}", path.display()))?;
+        let (entry_tx, mut entry_rx) = mpsc::unbounded_channel::<TranscriptEntry>();
+
+        let path = path.to_path_buf();
+        let task = tokio::spawn(async move {
+            let mut writer = BufWriter::new(file);
+            while let Some(entry) = entry_rx.recv().await {
+                let mut written = write_entry(&mut writer, &entry).await;
+                // Flush once the backlog is written, so a crash loses little
+                while let Ok(entry) = entry_rx.try_recv() {
+                    written = written.and(write_entry(&mut writer, &entry).await);
+                }
+                if let Err(e) = written.and(writer.flush().await) {
+                    tracing::warn!("Failed to write transcript {}: {}", path.display(), e);
+                }
+            }
+        });
+
+        Ok(Self { entry_tx, task })
+    }
+
+    /// Record a line received now
+    pub(crate) fn record(&self, stream: TranscriptStream, line: impl Into<String>) {
+        let _ = self.entry_tx.send(TranscriptEntry {
+            received_at: Utc::now(),
+            stream,
+            line: line.into(),
+            outcome: None,
+        });
+    }
+
+    /// Tap recording evaluator output as the source reads it
+    pub(crate) fn tap(&self) -> Arc<dyn OutputTap> {
+        Arc::new(TranscriptTap(self.entry_tx.downgrade()))
+    }
+
+    /// Record that the run is over; followers stop at this entry
+    pub(crate) fn record_outcome(&self, line: impl Into<String>, outcome: RunOutcome) {
+        let _ = self.entry_tx.send(TranscriptEntry {
+            received_at: Utc::now(),
+            stream: TranscriptStream::Control,
+            line: line.into(),
+            outcome: Some(outcome),
+        });
+    }
+
+    /// Write out everything recorded so far and close the file
+    pub(crate) async fn finish(self) {
+        drop(self.entry_tx);
+        let _ = self.task.await;
+    }
+}
+
+/// Records evaluator output in a transcript, stamped with when it was read
+///
+/// Holds the writer weakly, so a reader that outlives the run cannot keep
+/// [`TranscriptWriter::finish`] waiting.
+struct TranscriptTap(mpsc::WeakUnboundedSender<TranscriptEntry>);
+
+impl TranscriptTap {
+    fn send(&self, stream: TranscriptStream, line: &str) {
+        let Some(entry_tx) = self.0.upgrade() else {
+            return;
+        };
+        let _ = entry_tx.send(TranscriptEntry {
+            received_at: Utc::now(),
+            stream,
+            line: line.to_string(),
+            outcome: None,
+        });
+    }
+}
+
+impl OutputTap for TranscriptTap {
+    fn stdout(&self, line: &str) {
+        self.send(TranscriptStream::Stdout, line);
+    }
+
+    fn stderr(&self, line: &str) {
+        self.send(TranscriptStream::Stderr, line);
+    }
+}
+
+/// Replay a transcript as evaluator messages, following it as it grows
+///
+/// Ends after the run's outcome entry, or once `daemon_pid` is gone and
+/// the transcript has nothing more to read.
+pub(crate) async fn follow(
+    path: PathBuf,
+    daemon_pid: Option<u32>,
+    message_tx: mpsc::Sender<EvaluatorMessage>,
+) {
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(e) => {
+            tracing::warn!("Failed to open transcript {}: {}", path.display(), e);
+            let _ = message_tx
+                .send(EvaluatorMessage::Exited(ExitStatus::new(false, None)))
+                .await;
+            return;
+        }
+    };
+    let mut reader = BufReader::new(file);
+    let mut monitor = ResourceMonitor::new();
+    let mut line = String::new();
+
+    loop {
+        match reader.read_line(&mut line).await {
+            // A line without its newline is still being written
+            Ok(_) if line.ends_with('\n') => {
+                let entry = serde_json::from_str::<TranscriptEntry>(line.trim_end());
+                line.clear();
+                let message = match entry {
+                    Ok(TranscriptEntry {
+                        outcome: Some(outcome),
+                        ..
+                    }) => {
+                        let status = ExitStatus::new(outcome.success, outcome.exit_code);
+                        let _ = message_tx.send(EvaluatorMessage::Exited(status)).await;
+                        return;
+                    }
+                    Ok(entry) => match entry.stream {
+                        TranscriptStream::Stdout => EvaluatorMessage::Output(entry.line),
+                        TranscriptStream::Stderr => EvaluatorMessage::Stderr(entry.line),
+                        TranscriptStream::Control => continue,
+                    },
+                    Err(e) => {
+                        tracing::warn!("Skipping unreadable transcript entry: {}", e);
+                        continue;
+                    }
+                };
+                if message_tx.send(message).await.is_err() {
+                    return;
+                }
+            }
+            Ok(_) => {
+                let daemon_gone = daemon_pid.is_some_and(|pid| monitor.sample(pid).is_none());
+                if daemon_gone {
+                    tracing::warn!("Daemon exited without finishing the transcript");
+                    let _ = message_tx
+                        .send(EvaluatorMessage::Exited(ExitStatus::new(false, None)))
+                        .await;
+                    return;
+                }
+                tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read transcript {}: {}", path.display(), e);
+                return;
+            }
+        }
+    }
+}
+
+/// The run a detached daemon owns, followed through its transcript
+pub(crate) struct FollowedRun(pub(crate) RunDir);
+
+impl MetricSource for FollowedRun {
+    fn describe(&self) -> String {
+        self.0.id().to_string()
+    }
+
+    fn start(
+        &self,
+        message_tx: mpsc::Sender<EvaluatorMessage>,
+        _tap: Option<Arc<dyn OutputTap>>,
+    ) -> SourceFuture<'_> {
+        // The followed daemon records its own transcript
+        let feed = follow(self.0.transcript_path(), self.0.daemon_pid(), message_tx);
+        Box::pin(async move { Ok(EvaluatorProcess::follow(feed)) })
+    }
+}
+
+async fn write_entry(
+    writer: &mut BufWriter<tokio::fs::File>,
+    entry: &TranscriptEntry,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn writes_entries_in_order() {
+        let dir = std::env::temp_dir().join(format!("preval-transcript-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("transcript.jsonl");
+
+        let writer = TranscriptWriter::create(&path).await.unwrap();
+        writer.record(TranscriptStream::Stdout, r#"{"type":"handshake"}"#);
+        writer.record(TranscriptStream::Stderr, "warming up");
+        writer.record(TranscriptStream::Control, "evaluator exited with code 0");
+        writer.finish().await;
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let entries: Vec<TranscriptEntry> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let streams: Vec<_> = entries.iter().map(|e| e.stream).collect();
+        assert_eq!(
+            streams,
+            vec![
+                TranscriptStream::Stdout,
+                TranscriptStream::Stderr,
+                TranscriptStream::Control
+            ]
+        );
+        assert_eq!(entries[0].line, r#"{"type":"handshake"}"#);
+        assert!(entries[0].received_at <= entries[2].received_at);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn records_output_the_buffer_drops() {
+        use crate::evaluator::output_queue::{OutputBuffer, OverflowPolicy};
+
+        let dir = std::env::temp_dir().join(format!("preval-tap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("transcript.jsonl");
+        let writer = TranscriptWriter::create(&path).await.unwrap();
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let command =
+            crate::state::types::EvaluatorCommand::try_new("seq 1 200".to_string()).unwrap();
+        let output = OutputBuffer {
+            capacity: 2,
+            overflow: OverflowPolicy::DropOldest,
+        };
+        let _evaluator = EvaluatorProcess::spawn(&command, output, Some(writer.tap()), tx)
+            .await
+            .unwrap();
+        // Let the evaluator finish writing before anything is read
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let mut delivered = 0;
+        while let Some(message) = rx.recv().await {
+            match message {
+                EvaluatorMessage::Output(_) => delivered += 1,
+                EvaluatorMessage::Exited(_) => break,
+                EvaluatorMessage::Stderr(_) => {}
+            }
+        }
+        writer.finish().await;
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let recorded: Vec<TranscriptEntry> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(delivered < 200, "the buffer should have dropped lines");
+        assert_eq!(recorded.len(), 200);
+        assert_eq!(recorded[199].line, "200");
+    }
+
+    #[tokio::test]
+    async fn follow_replays_until_outcome() {
+        let dir = std::env::temp_dir().join(format!("preval-follow-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("transcript.jsonl");
+
+        let writer = TranscriptWriter::create(&path).await.unwrap();
+        writer.record(TranscriptStream::Control, "starting evaluator: eval");
+        writer.record(TranscriptStream::Stdout, "line one");
+        writer.record(TranscriptStream::Stderr, "warning");
+        writer.record_outcome(
+            "run finished",
+            RunOutcome {
+                success: true,
+                exit_code: Some(0),
+            },
+        );
+        writer.record(TranscriptStream::Stdout, "after the end");
+        writer.finish().await;
+
+        let (tx, mut rx) = mpsc::channel(10);
+        follow(path, None, tx).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(
+            matches!(rx.recv().await, Some(EvaluatorMessage::Output(line)) if line == "line one")
+        );
+        assert!(
+            matches!(rx.recv().await, Some(EvaluatorMessage::Stderr(line)) if line == "warning")
+        );
+        assert!(
+            matches!(rx.recv().await, Some(EvaluatorMessage::Exited(status)) if status.success())
+        );
+        assert!(rx.recv().await.is_none());
+    }
+}
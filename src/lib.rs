@@ -1,2 +1,50 @@
+//! PrEval runs prompt evaluation suites and follows their progress live.
+//!
+//! Besides the `preval` binary, the crate can be embedded by other tools
+//! that speak the evaluator protocol:
+//!
+//! - [`evaluator`] parses the handshake and the OpenTelemetry metrics lines
+//!   an evaluator writes, and spawns and supervises evaluator processes
+//! - [`state`] is the state machine of a run: samples, aggregates, threshold
+//!   rules, the timeline and snapshots of it for other tasks
+//! - [`export`] turns a run into reports, JUnit XML, Prometheus metrics and
+//!   event logs, and holds the config of webhooks, hooks and trackers
+//! - [`run_evaluation`] runs a whole evaluation without a terminal and
+//!   returns its results, for integration tests and orchestration scripts
+//!
+//! ```
+//! use preval::evaluator::handshake::parse_handshake;
+//! use preval::evaluator::parser::parse_metrics_line;
+//! use preval::state::types::EvaluatorName;
+//! use preval::state::RunState;
+//!
+//! let mut state = RunState::default();
+//! state.set_evaluator_name(EvaluatorName::try_new("my-eval").unwrap())?;
+//! state.set_handshake(parse_handshake(
+//!     r#"{"type": "handshake", "mode": "test_suite", "version": "1.0",
+//!         "evaluator": {"name": "my-eval"}, "metrics_schema": []}"#,
+//! )?)?;
+//!
+//! state.add_metrics(parse_metrics_line(
+//!     r#"{"resourceMetrics": [{"resource": {"attributes": []},
+//!         "scopeMetrics": [{"metrics": [{"name": "accuracy", "gauge": {"dataPoints": [{
+//!             "timeUnixNano": "1234567890000000000", "asDouble": 0.9, "attributes": [
+//!                 {"key": "sample.id", "value": {"stringValue": "q-1"}}]}]}}]}]}]}"#,
+//! )?)?;
+//! assert_eq!(state.summary_stats().1, 1);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+mod app;
+pub mod cli;
+mod config;
+mod daemon;
 pub mod evaluator;
+pub mod export;
+mod history;
+mod runner;
+mod signals;
 pub mod state;
+mod ui;
+
+pub use runner::{run_evaluation, RunConfig, RunResult};
@@ -0,0 +1,117 @@
+//! Running an evaluation from other Rust code, without a terminal
+//!
+//! [`run_evaluation`] drives the same pipeline as the `preval` binary:
+//! it starts the evaluator, applies threshold rules and derived metrics to
+//! every sample, and returns how the run went instead of drawing it.
+//! Nothing is recorded under the data directory.
+
+use crate::app::App;
+use crate::evaluator::source::{ChildProcess, MetricSource};
+use crate::export::report::Report;
+use crate::state::expression::DerivedMetric;
+use crate::state::rules::ThresholdRule;
+use crate::state::types::{EvaluationStatus, EvaluatorCommand, SampleResult};
+use anyhow::{Context, Result};
+use std::time::Duration;
+
+/// What to evaluate and how to judge it
+pub struct RunConfig {
+    source: Box<dyn MetricSource>,
+    timeout: Option<Duration>,
+    handshake_timeout: Option<Duration>,
+    threshold_rules: Vec<ThresholdRule>,
+    warning_rules: Vec<ThresholdRule>,
+    derived_metrics: Vec<DerivedMetric>,
+}
+
+impl RunConfig {
+    /// Run `command` as the evaluator
+    pub fn new(command: EvaluatorCommand) -> Self {
+        Self::from_source(Box::new(ChildProcess::new(command)))
+    }
+
+    /// Read the protocol from `source` instead of running an evaluator
+    pub fn from_source(source: Box<dyn MetricSource>) -> Self {
+        Self {
+            source,
+            timeout: None,
+            handshake_timeout: None,
+            threshold_rules: Vec::new(),
+            warning_rules: Vec::new(),
+            derived_metrics: Vec::new(),
+        }
+    }
+
+    /// Fail the run once it has taken longer than `timeout` (builder pattern)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fail the run if no handshake arrives within `timeout` (builder pattern)
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the rules that fail samples and the run (builder pattern)
+    pub fn threshold_rules(mut self, rules: Vec<ThresholdRule>) -> Self {
+        self.threshold_rules = rules;
+        self
+    }
+
+    /// Set the rules that only warn when broken (builder pattern)
+    pub fn warning_rules(mut self, rules: Vec<ThresholdRule>) -> Self {
+        self.warning_rules = rules;
+        self
+    }
+
+    /// Set the metrics computed per sample from the reported ones (builder pattern)
+    pub fn derived_metrics(mut self, metrics: Vec<DerivedMetric>) -> Self {
+        self.derived_metrics = metrics;
+        self
+    }
+}
+
+/// How a run went
+#[derive(Debug, Clone)]
+pub struct RunResult {
+    /// Whether the run completed without failing, the same as the exit
+    /// code of the `preval` binary
+    pub success: bool,
+    /// Outcome, statistics of every metric, failed samples and threshold
+    /// rules of the run
+    pub report: Report,
+    /// Every sample still held when the run ended, oldest first
+    pub samples: Vec<SampleResult>,
+}
+
+/// Run the evaluation `config` describes to the end, without a terminal
+///
+/// A run that fails, e.g. by breaking a threshold rule, is still a result;
+/// errors are for runs that could not be carried out, such as an evaluator
+/// that cannot be started.
+pub async fn run_evaluation(config: RunConfig) -> Result<RunResult> {
+    let mut app = App::new(Some(config.source))
+        .headless(true)
+        .threshold_rules(config.threshold_rules)
+        .warning_rules(config.warning_rules)
+        .derived_metrics(config.derived_metrics);
+    if let Some(timeout) = config.timeout {
+        app = app.timeout(timeout);
+    }
+    if let Some(timeout) = config.handshake_timeout {
+        app = app.handshake_timeout(timeout);
+    }
+    app.run().await?;
+
+    let state = app.state();
+    Ok(RunResult {
+        success: !matches!(state.status(), EvaluationStatus::Failed(_)),
+        report: Report::of(state),
+        samples: state
+            .sample_store()
+            .read_available()
+            .context("Failed to read the samples of the run")?,
+    })
+}
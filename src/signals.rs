@@ -0,0 +1,70 @@
+/// Process signal handling so PrEval can shut down cleanly when asked to
+use anyhow::{Context, Result};
+
+/// Listener for signals that ask PrEval itself to terminate
+///
+/// In raw mode a Ctrl+C keypress reaches the TUI as a key event, but signals
+/// sent from outside (`kill`, a closing terminal, a CI runner cancelling the
+/// job) bypass the key handler entirely and must be caught here.
+#[cfg(unix)]
+pub(crate) struct TerminationSignals {
+    interrupt: tokio::signal::unix::Signal,
+    terminate: tokio::signal::unix::Signal,
+    hangup: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl TerminationSignals {
+    /// Install the signal handlers
+    pub(crate) fn install() -> Result<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        Ok(Self {
+            interrupt: signal(SignalKind::interrupt())
+                .context("Failed to install SIGINT handler")?,
+            terminate: signal(SignalKind::terminate())
+                .context("Failed to install SIGTERM handler")?,
+            hangup: signal(SignalKind::hangup()).context("Failed to install SIGHUP handler")?,
+        })
+    }
+
+    /// Wait for the next termination signal, returning its name
+    pub(crate) async fn recv(&mut self) -> &'static str {
+        tokio::select! {
+            _ = self.interrupt.recv() => "SIGINT",
+            _ = self.terminate.recv() => "SIGTERM",
+            _ = self.hangup.recv() => "SIGHUP",
+        }
+    }
+}
+
+/// Listener for console control events that ask PrEval to terminate
+#[cfg(windows)]
+pub(crate) struct TerminationSignals {
+    ctrl_c: tokio::signal::windows::CtrlC,
+    ctrl_break: tokio::signal::windows::CtrlBreak,
+    ctrl_close: tokio::signal::windows::CtrlClose,
+}
+
+#[cfg(windows)]
+impl TerminationSignals {
+    /// Install the console control handlers
+    pub(crate) fn install() -> Result<Self> {
+        use tokio::signal::windows::{ctrl_break, ctrl_c, ctrl_close};
+
+        Ok(Self {
+            ctrl_c: ctrl_c().context("Failed to install Ctrl+C handler")?,
+            ctrl_break: ctrl_break().context("Failed to install Ctrl+Break handler")?,
+            ctrl_close: ctrl_close().context("Failed to install console close handler")?,
+        })
+    }
+
+    /// Wait for the next termination event, returning its name
+    pub(crate) async fn recv(&mut self) -> &'static str {
+        tokio::select! {
+            _ = self.ctrl_c.recv() => "Ctrl+C",
+            _ = self.ctrl_break.recv() => "Ctrl+Break",
+            _ = self.ctrl_close.recv() => "console close",
+        }
+    }
+}
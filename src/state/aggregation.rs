@@ -0,0 +1,901 @@
+//! Per-metric statistics folded from every sample of a run
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Weight of the newest value in a metric's recent average
+const RECENT_WEIGHT: f64 = 0.1;
+
+/// Values needed before a metric's recent average tells a trend
+const MIN_TREND_VALUES: usize = 10;
+
+/// Standard deviations the recent average has to move away from the mean
+/// of the run to count as a trend rather than noise
+const TREND_THRESHOLD: f64 = 0.5;
+
+/// Relative accuracy of percentiles
+const BIN_ACCURACY: f64 = 0.01;
+
+/// Magnitude below which values are counted as zero
+const MIN_BINNED_VALUE: f64 = 1e-9;
+
+/// Which way a metric's recent values moved relative to the whole run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+/// Running statistics for one metric across all samples seen so far
+///
+/// Percentiles come from logarithmic bins, which answer within 1% of the
+/// true value in little memory however many samples a run has. A value can
+/// be taken back out of them, and they can be merged, so statistics
+/// exported from separate runs can be combined.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct MetricStats {
+    count: usize,
+    mean: f64,
+    /// Sum of squared differences from the mean (Welford's algorithm)
+    m2: f64,
+    min: f64,
+    max: f64,
+    /// Values left out because their sample was part of the warm-up
+    #[serde(default)]
+    warmup_excluded: usize,
+    /// Values left out as outliers in the trimmed tails
+    #[serde(default)]
+    trimmed: usize,
+    /// Every value offered, trimmed or not, to find where the tails start
+    #[serde(skip)]
+    offered: Option<Bins>,
+    /// Exact values left out as outliers, by their bits, so taking one back
+    /// cannot take out a recorded value that shares its bin
+    #[serde(skip)]
+    trimmed_values: BTreeMap<u64, usize>,
+    /// Exponentially weighted average, favouring the latest values
+    #[serde(default)]
+    recent: Option<f64>,
+    /// Every value recorded, for percentiles
+    #[serde(default)]
+    bins: Bins,
+}
+
+impl MetricStats {
+    /// Fold one sample value into the statistics
+    ///
+    /// Non-finite values are ignored, as they would poison every statistic.
+    pub fn record(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.recent = Some(match self.recent {
+            Some(recent) => recent + RECENT_WEIGHT * (value - recent),
+            None => value,
+        });
+
+        self.bins.add(value);
+    }
+
+    /// Take back a value recorded earlier, e.g. the result of a sample
+    /// reported again
+    ///
+    /// Returns false if the value was never recorded. A value trimmed as
+    /// an outlier is no longer counted as trimmed, but leaves the recorded
+    /// values alone. The recent average keeps the value, and a min or max
+    /// it was is only known to within 1% afterwards.
+    pub fn retract(&mut self, value: f64) -> bool {
+        if !value.is_finite() {
+            return false;
+        }
+        if let Some(count) = self.trimmed_values.get_mut(&value.to_bits()) {
+            *count -= 1;
+            if *count == 0 {
+                self.trimmed_values.remove(&value.to_bits());
+            }
+            self.trimmed -= 1;
+            if let Some(offered) = &mut self.offered {
+                offered.remove(value);
+            }
+            return false;
+        }
+        if !self.has_bins() || !self.bins.remove(value) {
+            return false;
+        }
+        if let Some(offered) = &mut self.offered {
+            offered.remove(value);
+        }
+        if self.count == 1 {
+            *self = Self {
+                warmup_excluded: self.warmup_excluded,
+                trimmed: self.trimmed,
+                offered: self.offered.take(),
+                trimmed_values: std::mem::take(&mut self.trimmed_values),
+                recent: self.recent,
+                ..Self::default()
+            };
+            return true;
+        }
+
+        let count = self.count - 1;
+        let mean = self.mean - (value - self.mean) / count as f64;
+        self.m2 = (self.m2 - (value - mean) * (value - self.mean)).max(0.0);
+        self.mean = mean;
+        self.count = count;
+        if value <= self.min {
+            self.min = self.bins.quantile(0.0).unwrap_or(self.min);
+        }
+        if value >= self.max {
+            self.max = self.bins.quantile(1.0).unwrap_or(self.max);
+        }
+        true
+    }
+
+    /// Whether the bins hold every value, which they do unless the
+    /// statistics were saved before there were bins
+    fn has_bins(&self) -> bool {
+        self.bins.count() == self.count
+    }
+
+    /// Fold a value into the statistics unless it lies in the lowest or
+    /// highest `trim_percent` of the values offered so far
+    ///
+    /// Nothing is trimmed until enough values were offered for each tail
+    /// to hold one.
+    pub fn record_trimmed(&mut self, value: f64, trim_percent: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        let tail = trim_percent.clamp(0.0, 50.0) / 100.0;
+        let offered = self.offered.get_or_insert_with(Bins::default);
+        let outlier = tail > 0.0 && offered.count() as f64 * tail >= 1.0 && {
+            let low = offered.quantile(tail);
+            let high = offered.quantile(1.0 - tail);
+            low.is_some_and(|low| value < low) || high.is_some_and(|high| value > high)
+        };
+        offered.add(value);
+
+        if outlier {
+            self.trimmed += 1;
+            *self.trimmed_values.entry(value.to_bits()).or_default() += 1;
+        } else {
+            self.record(value);
+        }
+    }
+
+    /// Note a value left out because its sample was part of the warm-up
+    pub fn exclude_warmup(&mut self) {
+        self.warmup_excluded += 1;
+    }
+
+    /// Combine with statistics gathered separately, e.g. by another run
+    pub fn merge(&mut self, other: &MetricStats) {
+        self.warmup_excluded += other.warmup_excluded;
+        self.trimmed += other.trimmed;
+        self.recent = self.recent.or(other.recent);
+        if other.count == 0 {
+            return;
+        }
+        self.bins.merge(&other.bins);
+
+        if self.count == 0 {
+            self.min = other.min;
+            self.max = other.max;
+        } else {
+            self.min = self.min.min(other.min);
+            self.max = self.max.max(other.max);
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.m2 += other.m2 + delta * delta * (self.count * other.count) as f64 / count as f64;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.count = count;
+    }
+
+    /// Number of values recorded
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Number of values left out as warm-up
+    pub fn warmup_excluded(&self) -> usize {
+        self.warmup_excluded
+    }
+
+    /// Number of values left out as outliers
+    pub fn trimmed(&self) -> usize {
+        self.trimmed
+    }
+
+    /// Arithmetic mean
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Smallest value
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    /// Largest value
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Population standard deviation over all samples of the run
+    pub fn stddev(&self) -> Option<f64> {
+        (self.count > 0).then(|| (self.m2 / self.count as f64).sqrt())
+    }
+
+    /// Whether recent values are above or below the mean of the run, once
+    /// there are enough to tell
+    pub fn trend(&self) -> Option<Trend> {
+        let recent = self.recent.filter(|_| self.count >= MIN_TREND_VALUES)?;
+        let shift = recent - self.mean;
+        let threshold = TREND_THRESHOLD * self.stddev()?;
+        Some(if shift > threshold {
+            Trend::Rising
+        } else if shift < -threshold {
+            Trend::Falling
+        } else {
+            Trend::Steady
+        })
+    }
+
+    /// Median (50th percentile)
+    pub fn median(&self) -> Option<f64> {
+        self.percentile(50.0)
+    }
+
+    /// 90th percentile
+    pub fn p90(&self) -> Option<f64> {
+        self.percentile(90.0)
+    }
+
+    /// 95th percentile
+    pub fn p95(&self) -> Option<f64> {
+        self.percentile(95.0)
+    }
+
+    /// 99th percentile
+    pub fn p99(&self) -> Option<f64> {
+        self.percentile(99.0)
+    }
+
+    /// Value below which `percent` of the samples fall
+    ///
+    /// Statistics saved before there were bins, or merged with such, have
+    /// no percentiles.
+    pub fn percentile(&self, percent: f64) -> Option<f64> {
+        let q = percent.clamp(0.0, 100.0) / 100.0;
+        if !self.has_bins() {
+            return None;
+        }
+        // The extremes are known exactly
+        match q {
+            q if q <= 0.0 => self.min(),
+            q if q >= 1.0 => self.max(),
+            q => self.bins.quantile(q),
+        }
+    }
+}
+
+/// Counts of values in bins whose width grows with their distance from
+/// zero, keeping every value to within `BIN_ACCURACY`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Bins {
+    #[serde(default)]
+    positive: BTreeMap<i32, usize>,
+    #[serde(default)]
+    negative: BTreeMap<i32, usize>,
+    #[serde(default)]
+    zero: usize,
+}
+
+impl Bins {
+    /// Ratio between the bounds of a bin
+    fn gamma() -> f64 {
+        (1.0 + BIN_ACCURACY) / (1.0 - BIN_ACCURACY)
+    }
+
+    /// Bin of a value's magnitude
+    fn key(magnitude: f64) -> i32 {
+        (magnitude.ln() / Self::gamma().ln()).ceil() as i32
+    }
+
+    /// Magnitude standing for every value in bin `key`
+    fn magnitude(key: i32) -> f64 {
+        2.0 * Self::gamma().powi(key) / (Self::gamma() + 1.0)
+    }
+
+    /// Bins on the side of zero `value` is on, and its key there, or None
+    /// for values counted as zero
+    fn side(&mut self, value: f64) -> Option<(&mut BTreeMap<i32, usize>, i32)> {
+        if value.abs() < MIN_BINNED_VALUE {
+            None
+        } else if value > 0.0 {
+            Some((&mut self.positive, Self::key(value)))
+        } else {
+            Some((&mut self.negative, Self::key(-value)))
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        match self.side(value) {
+            Some((bins, key)) => *bins.entry(key).or_default() += 1,
+            None => self.zero += 1,
+        }
+    }
+
+    /// Take `value` out of its bin, returning false if the bin is empty
+    fn remove(&mut self, value: f64) -> bool {
+        match self.side(value) {
+            Some((bins, key)) => match bins.get_mut(&key) {
+                Some(1) => {
+                    bins.remove(&key);
+                    true
+                }
+                Some(count) => {
+                    *count -= 1;
+                    true
+                }
+                None => false,
+            },
+            None => {
+                let removed = self.zero > 0;
+                self.zero = self.zero.saturating_sub(1);
+                removed
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &Bins) {
+        for (key, count) in &other.positive {
+            *self.positive.entry(*key).or_default() += count;
+        }
+        for (key, count) in &other.negative {
+            *self.negative.entry(*key).or_default() += count;
+        }
+        self.zero += other.zero;
+    }
+
+    fn count(&self) -> usize {
+        self.positive.values().sum::<usize>() + self.negative.values().sum::<usize>() + self.zero
+    }
+
+    /// Value at quantile `q`, rounded to its bin
+    fn quantile(&self, q: f64) -> Option<f64> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = (q * (count - 1) as f64) as usize;
+        // Most negative values first, then zero, then positive ones
+        let bins = self
+            .negative
+            .iter()
+            .rev()
+            .map(|(key, count)| (-Self::magnitude(*key), *count))
+            .chain(std::iter::once((0.0, self.zero)))
+            .chain(
+                self.positive
+                    .iter()
+                    .map(|(key, count)| (Self::magnitude(*key), *count)),
+            );
+        let mut seen = 0;
+        for (value, count) in bins {
+            seen += count;
+            if seen > rank {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+impl std::fmt::Debug for MetricStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The bins are too noisy to be useful in debug output
+        f.debug_struct("MetricStats")
+            .field("count", &self.count)
+            .field("mean", &self.mean)
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .field("warmup_excluded", &self.warmup_excluded)
+            .field("trimmed", &self.trimmed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Which values are left out of aggregate statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Exclusions {
+    /// Samples at the start of a run left out while the evaluator warms up,
+    /// e.g. a cold LLM endpoint answering slowly
+    pub warmup_samples: usize,
+    /// Percentage of values trimmed from each tail of every metric
+    pub trim_percent: f64,
+}
+
+/// Statistics for every metric the evaluator has reported per sample
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MetricAggregator {
+    metrics: BTreeMap<String, MetricStats>,
+    /// Percentage of values trimmed from each tail of every metric
+    #[serde(skip)]
+    trim_percent: f64,
+}
+
+impl MetricAggregator {
+    /// Create an empty aggregator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trim `trim_percent` of the values from each tail of every metric
+    pub fn set_trim_percent(&mut self, trim_percent: f64) {
+        self.trim_percent = trim_percent;
+    }
+
+    /// Fold a sample's value for `name` into that metric's statistics
+    pub fn record(&mut self, name: &str, value: f64) {
+        match self.metrics.get_mut(name) {
+            Some(stats) => stats.record_trimmed(value, self.trim_percent),
+            None => {
+                let mut stats = MetricStats::default();
+                stats.record_trimmed(value, self.trim_percent);
+                self.metrics.insert(name.to_string(), stats);
+            }
+        }
+    }
+
+    /// Take back a value recorded earlier for `name`, if it was recorded
+    pub fn retract(&mut self, name: &str, value: f64) {
+        if let Some(stats) = self.metrics.get_mut(name) {
+            stats.retract(value);
+        }
+    }
+
+    /// Note a value for `name` left out because its sample was part of the
+    /// warm-up
+    pub fn exclude_warmup(&mut self, name: &str) {
+        match self.metrics.get_mut(name) {
+            Some(stats) => stats.exclude_warmup(),
+            None => {
+                let mut stats = MetricStats::default();
+                stats.exclude_warmup();
+                self.metrics.insert(name.to_string(), stats);
+            }
+        }
+    }
+
+    /// Most values of any metric left out as warm-up, and the values
+    /// trimmed as outliers across all metrics
+    pub fn exclusions(&self) -> (usize, usize) {
+        self.metrics
+            .values()
+            .fold((0, 0), |(warmup, trimmed), stats| {
+                (warmup.max(stats.warmup_excluded), trimmed + stats.trimmed)
+            })
+    }
+
+    /// Combine with statistics gathered separately, metric by metric
+    pub fn merge(&mut self, other: &MetricAggregator) {
+        for (name, stats) in &other.metrics {
+            self.metrics.entry(name.clone()).or_default().merge(stats);
+        }
+    }
+
+    /// Statistics for one metric
+    pub fn get(&self, name: &str) -> Option<&MetricStats> {
+        self.metrics.get(name)
+    }
+
+    /// Statistics for every metric, ordered by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MetricStats)> {
+        self.metrics
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+    }
+
+    /// Number of metrics with statistics
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    /// Whether no metric has been recorded yet
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+}
+
+/// Distinct values an attribute may take before it is no longer grouped by
+///
+/// Attributes unique to each sample, such as ids, would otherwise keep a
+/// full set of statistics per sample.
+pub const MAX_GROUPS_PER_ATTRIBUTE: usize = 32;
+
+/// Per-metric statistics broken down by the value of each data-point
+/// attribute, e.g. accuracy by `category`
+#[derive(Debug, Clone, Default)]
+pub struct GroupedAggregator {
+    attributes: BTreeMap<String, AttributeGroups>,
+    /// Percentage of values trimmed from each tail of every group's metrics
+    trim_percent: f64,
+}
+
+/// Statistics for each value of one attribute
+#[derive(Debug, Clone, Default)]
+struct AttributeGroups {
+    groups: BTreeMap<String, MetricAggregator>,
+    /// Took more than `MAX_GROUPS_PER_ATTRIBUTE` values; no longer grouped
+    overflowed: bool,
+}
+
+impl GroupedAggregator {
+    /// Create an empty grouped aggregator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trim `trim_percent` of the values from each tail of every group's
+    /// metrics
+    pub fn set_trim_percent(&mut self, trim_percent: f64) {
+        self.trim_percent = trim_percent;
+    }
+
+    /// Fold a value for `name` into the group of each of its attributes
+    pub fn record<'a>(
+        &mut self,
+        attributes: impl IntoIterator<Item = (&'a str, String)>,
+        name: &str,
+        value: f64,
+    ) {
+        for (key, group) in attributes {
+            let groups = match self.attributes.get_mut(key) {
+                Some(groups) => groups,
+                None => self.attributes.entry(key.to_string()).or_default(),
+            };
+            if groups.overflowed {
+                continue;
+            }
+            if !groups.groups.contains_key(&group)
+                && groups.groups.len() >= MAX_GROUPS_PER_ATTRIBUTE
+            {
+                groups.overflowed = true;
+                groups.groups.clear();
+                continue;
+            }
+            groups
+                .groups
+                .entry(group)
+                .or_insert_with(|| {
+                    let mut stats = MetricAggregator::new();
+                    stats.set_trim_percent(self.trim_percent);
+                    stats
+                })
+                .record(name, value);
+        }
+    }
+
+    /// Take back a value for `name` recorded earlier in the group of each
+    /// of its attributes
+    pub fn retract<'a>(
+        &mut self,
+        attributes: impl IntoIterator<Item = (&'a str, String)>,
+        name: &str,
+        value: f64,
+    ) {
+        for (key, group) in attributes {
+            if let Some(stats) = self
+                .attributes
+                .get_mut(key)
+                .and_then(|groups| groups.groups.get_mut(&group))
+            {
+                stats.retract(name, value);
+            }
+        }
+    }
+
+    /// Attributes that can be grouped by, ordered by name
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.attributes
+            .iter()
+            .filter(|(_, groups)| !groups.overflowed)
+            .map(|(key, _)| key.as_str())
+    }
+
+    /// Statistics for each value of attribute `key`, ordered by value
+    pub fn groups(&self, key: &str) -> impl Iterator<Item = (&str, &MetricAggregator)> {
+        self.attributes
+            .get(key)
+            .into_iter()
+            .flat_map(|groups| groups.groups.iter())
+            .map(|(group, stats)| (group.as_str(), stats))
+    }
+
+    /// Statistics of `metric` for each value of attribute `key`, ranked by
+    /// their mean, highest first unless `ascending`
+    ///
+    /// Groups without any value of `metric` are left out.
+    pub fn ranking(&self, key: &str, metric: &str, ascending: bool) -> Vec<(&str, &MetricStats)> {
+        let mut ranking: Vec<_> = self
+            .groups(key)
+            .filter_map(|(group, stats)| Some((group, stats.get(metric)?)))
+            .filter(|(_, stats)| stats.mean().is_some())
+            .collect();
+        ranking.sort_by(|(_, a), (_, b)| {
+            let order = a.mean().partial_cmp(&b.mean()).unwrap_or(Ordering::Equal);
+            if ascending {
+                order
+            } else {
+                order.reverse()
+            }
+        });
+        ranking
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_of(values: &[f64]) -> MetricStats {
+        let mut stats = MetricStats::default();
+        for value in values {
+            stats.record(*value);
+        }
+        stats
+    }
+
+    #[test]
+    fn empty_stats_have_no_values() {
+        let stats = MetricStats::default();
+
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.median(), None);
+        assert_eq!(stats.stddev(), None);
+    }
+
+    #[test]
+    fn folds_values_into_summary_statistics() {
+        let stats = stats_of(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+
+        assert_eq!(stats.count(), 8);
+        assert_eq!(stats.mean(), Some(5.0));
+        assert_eq!(stats.stddev(), Some(2.0));
+        assert_eq!(stats.min(), Some(2.0));
+        assert_eq!(stats.max(), Some(9.0));
+        assert_close(stats.median(), 4.0);
+    }
+
+    /// Percentiles are approximate: within 1% of the true value
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("percentile should have a value");
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 0.01,
+            "expected about {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn percentiles_stay_accurate_over_many_samples() {
+        let values: Vec<f64> = (1..=100_000).map(f64::from).collect();
+        let stats = stats_of(&values);
+
+        assert_close(stats.median(), 50_000.0);
+        assert_close(stats.p90(), 90_000.0);
+        assert_close(stats.p99(), 99_000.0);
+        assert_eq!(stats.percentile(0.0), Some(1.0));
+        assert_eq!(stats.percentile(100.0), Some(100_000.0));
+    }
+
+    #[test]
+    fn retracted_values_leave_the_statistics() {
+        let mut stats = stats_of(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0, 100.0]);
+
+        assert!(stats.retract(100.0));
+        assert!(!stats.retract(50.0));
+        assert_eq!(stats.count(), 8);
+        assert_close(stats.mean(), 5.0);
+        assert_close(stats.stddev(), 2.0);
+        assert_close(stats.max(), 9.0);
+        assert_close(stats.median(), 4.0);
+        // The 100 no longer drags up the tail
+        assert_close(stats.p99(), 7.0);
+
+        let mut single = stats_of(&[3.0]);
+        assert!(single.retract(3.0));
+        assert_eq!(single.count(), 0);
+        assert_eq!(single.mean(), None);
+    }
+
+    #[test]
+    fn retracted_outliers_leave_the_recorded_values_alone() {
+        let mut stats = MetricStats::default();
+        for value in [
+            5000.0, 100.0, 110.0, 120.0, 130.0, 140.0, 150.0, 160.0, 170.0, 180.0,
+        ] {
+            stats.record_trimmed(value, 10.0);
+        }
+        // Trimmed, though it shares a bin with the recorded 5000
+        stats.record_trimmed(5001.0, 10.0);
+        assert_eq!(stats.trimmed(), 1);
+        let mean = stats.mean();
+
+        assert!(!stats.retract(5001.0));
+        assert_eq!(stats.trimmed(), 0);
+        assert_eq!(stats.count(), 10);
+        assert_eq!(stats.mean(), mean);
+        assert_eq!(stats.max(), Some(5000.0));
+
+        assert!(stats.retract(5000.0));
+        assert_eq!(stats.count(), 9);
+        assert_close(stats.max(), 180.0);
+    }
+
+    #[test]
+    fn merged_stats_match_stats_of_all_values() {
+        let mut merged = stats_of(&[2.0, 4.0, 4.0, 4.0]);
+        merged.merge(&stats_of(&[5.0, 5.0, 7.0, 9.0]));
+
+        assert_eq!(merged.count(), 8);
+        assert_eq!(merged.mean(), Some(5.0));
+        assert_eq!(merged.stddev(), Some(2.0));
+        assert_eq!(merged.min(), Some(2.0));
+        assert_eq!(merged.max(), Some(9.0));
+        assert_close(merged.median(), 4.0);
+    }
+
+    #[test]
+    fn exported_stats_can_be_merged_later() {
+        let mut first = MetricAggregator::new();
+        first.record("latency", 100.0);
+        let mut second = MetricAggregator::new();
+        second.record("latency", 300.0);
+        second.record("accuracy", 0.9);
+
+        let json = serde_json::to_string(&second).unwrap();
+        let restored: MetricAggregator = serde_json::from_str(&json).unwrap();
+        first.merge(&restored);
+
+        assert_eq!(
+            first.get("latency").and_then(MetricStats::mean),
+            Some(200.0)
+        );
+        assert_eq!(first.get("accuracy").map(MetricStats::count), Some(1));
+    }
+
+    #[test]
+    fn trend_follows_recent_values() {
+        let mut stats = stats_of(&[1.0, 2.0, 1.0]);
+        assert_eq!(stats.trend(), None);
+
+        for i in 0..40 {
+            stats.record(1.0 + (i % 2) as f64);
+        }
+        assert_eq!(stats.trend(), Some(Trend::Steady));
+        for _ in 0..10 {
+            stats.record(3.0);
+        }
+        assert_eq!(stats.trend(), Some(Trend::Rising));
+        for _ in 0..30 {
+            stats.record(0.0);
+        }
+        assert_eq!(stats.trend(), Some(Trend::Falling));
+    }
+
+    #[test]
+    fn ignores_non_finite_values() {
+        let stats = stats_of(&[1.0, f64::NAN, f64::INFINITY, 3.0]);
+
+        assert_eq!(stats.count(), 2);
+        assert_eq!(stats.mean(), Some(2.0));
+    }
+
+    #[test]
+    fn trims_outliers_once_tails_hold_a_value() {
+        let mut aggregator = MetricAggregator::new();
+        aggregator.set_trim_percent(10.0);
+        for latency in [
+            5000.0, 100.0, 110.0, 120.0, 130.0, 140.0, 150.0, 160.0, 170.0, 180.0,
+        ] {
+            aggregator.record("latency", latency);
+        }
+        aggregator.record("latency", 9000.0);
+        aggregator.record("latency", 1.0);
+        aggregator.record("latency", 125.0);
+
+        let latency = aggregator.get("latency").unwrap();
+        // Only the first ten values are seen before trimming starts
+        assert_eq!(latency.count(), 11);
+        assert_eq!(latency.trimmed(), 2);
+        assert_eq!(latency.max(), Some(5000.0));
+        assert_eq!(latency.min(), Some(100.0));
+        assert_eq!(aggregator.exclusions(), (0, 2));
+    }
+
+    #[test]
+    fn groups_statistics_by_attribute_value() {
+        let mut grouped = GroupedAggregator::new();
+        grouped.record([("category", "math".to_string())], "accuracy", 0.5);
+        grouped.record([("category", "prose".to_string())], "accuracy", 1.0);
+        grouped.record([("category", "math".to_string())], "accuracy", 0.7);
+
+        let groups: Vec<_> = grouped
+            .groups("category")
+            .map(|(group, stats)| (group, stats.get("accuracy").unwrap().count()))
+            .collect();
+        assert_eq!(groups, vec![("math", 2), ("prose", 1)]);
+        assert_eq!(grouped.groups("model").count(), 0);
+    }
+
+    #[test]
+    fn ranks_groups_by_mean() {
+        let mut grouped = GroupedAggregator::new();
+        grouped.record([("model", "a".to_string())], "accuracy", 0.5);
+        grouped.record([("model", "b".to_string())], "accuracy", 0.9);
+        grouped.record([("model", "c".to_string())], "accuracy", 0.7);
+        grouped.record([("model", "d".to_string())], "latency", 100.0);
+
+        let ranking = |ascending| -> Vec<&str> {
+            grouped
+                .ranking("model", "accuracy", ascending)
+                .into_iter()
+                .map(|(group, _)| group)
+                .collect()
+        };
+        assert_eq!(ranking(false), vec!["b", "c", "a"]);
+        assert_eq!(ranking(true), vec!["a", "c", "b"]);
+    }
+
+    #[test]
+    fn stops_grouping_by_high_cardinality_attributes() {
+        let mut grouped = GroupedAggregator::new();
+        for id in 0..=MAX_GROUPS_PER_ATTRIBUTE {
+            grouped.record(
+                [("sample.id", id.to_string()), ("model", "a".to_string())],
+                "accuracy",
+                1.0,
+            );
+        }
+
+        assert_eq!(grouped.keys().collect::<Vec<_>>(), vec!["model"]);
+        assert_eq!(grouped.groups("sample.id").count(), 0);
+    }
+
+    #[test]
+    fn aggregator_keeps_metrics_apart() {
+        let mut aggregator = MetricAggregator::new();
+        aggregator.record("accuracy", 0.5);
+        aggregator.record("latency", 100.0);
+        aggregator.record("accuracy", 1.0);
+
+        let names: Vec<_> = aggregator.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["accuracy", "latency"]);
+        assert_eq!(
+            aggregator.get("accuracy").and_then(MetricStats::mean),
+            Some(0.75)
+        );
+        assert_eq!(aggregator.get("latency").map(MetricStats::count), Some(1));
+    }
+}
@@ -0,0 +1,135 @@
+//! Flags metric values far outside the recent ones, such as a prompt
+//! suddenly producing garbage in the middle of a run
+
+use std::collections::{HashMap, VecDeque};
+
+/// Recent values per metric each value is compared against
+pub const DEFAULT_ANOMALY_WINDOW: usize = 50;
+
+/// Robust z-score beyond which a value is anomalous
+pub const DEFAULT_ANOMALY_THRESHOLD: f64 = 3.5;
+
+/// Values a metric needs before anything is flagged
+const MIN_HISTORY: usize = 10;
+
+/// Scales the median absolute deviation to a standard deviation for
+/// normally distributed values
+const MAD_SCALE: f64 = 1.4826;
+
+/// Rolling-window anomaly detector over every metric
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    window: usize,
+    threshold: f64,
+    history: HashMap<String, VecDeque<f64>>,
+}
+
+impl AnomalyDetector {
+    /// Compare values against the last `window` of their metric, flagging
+    /// those more than `threshold` deviations from the median
+    pub fn new(window: usize, threshold: f64) -> Self {
+        Self {
+            window: window.max(MIN_HISTORY),
+            threshold,
+            history: HashMap::new(),
+        }
+    }
+
+    /// Same settings with no values seen yet, for starting a run over
+    pub fn without_history(&self) -> Self {
+        Self::new(self.window, self.threshold)
+    }
+
+    /// Check `value` against the recent values of `metric`, then remember it
+    ///
+    /// Returns the value's z-score when it is anomalous.
+    pub fn observe(&mut self, metric: &str, value: f64) -> Option<f64> {
+        let history = self.history.entry(metric.to_string()).or_default();
+        let score = (history.len() >= MIN_HISTORY)
+            .then(|| robust_z_score(history, value))
+            .filter(|score| score.abs() > self.threshold);
+
+        history.push_back(value);
+        if history.len() > self.window {
+            history.pop_front();
+        }
+        score
+    }
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_ANOMALY_WINDOW, DEFAULT_ANOMALY_THRESHOLD)
+    }
+}
+
+/// How many deviations `value` lies from the median of `history`
+///
+/// Uses the median absolute deviation, so earlier outliers do not hide
+/// later ones. When most values are identical the MAD is zero and the
+/// standard deviation is used instead; a value differing from a window of
+/// identical values is infinitely far off.
+fn robust_z_score(history: &VecDeque<f64>, value: f64) -> f64 {
+    let mut sorted: Vec<f64> = history.iter().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    let median = median_of_sorted(&sorted);
+
+    let mut deviations: Vec<f64> = sorted.iter().map(|v| (v - median).abs()).collect();
+    deviations.sort_by(f64::total_cmp);
+    let mad = median_of_sorted(&deviations) * MAD_SCALE;
+    if mad > 0.0 {
+        return (value - median) / mad;
+    }
+
+    let n = sorted.len() as f64;
+    let mean = sorted.iter().sum::<f64>() / n;
+    let std_dev = (sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    match (value - mean) / std_dev {
+        score if score.is_nan() => 0.0,
+        score => score,
+    }
+}
+
+fn median_of_sorted(sorted: &[f64]) -> f64 {
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_values_far_from_recent_ones() {
+        let mut detector = AnomalyDetector::default();
+        for i in 0..20 {
+            let latency = 1000.0 + (i % 5) as f64 * 20.0;
+            assert_eq!(detector.observe("latency", latency), None);
+        }
+
+        assert_eq!(detector.observe("latency", 1100.0), None);
+        assert!(detector.observe("latency", 9000.0).unwrap() > 3.5);
+        // Another metric has no history yet
+        assert_eq!(detector.observe("accuracy", 9000.0), None);
+    }
+
+    #[test]
+    fn mostly_identical_values_fall_back_to_standard_deviation() {
+        let mut detector = AnomalyDetector::default();
+        // Pass/fail metric: 80% passes must not flag every failure
+        for i in 0..20 {
+            detector.observe("passed", if i % 5 == 0 { 0.0 } else { 1.0 });
+        }
+        assert_eq!(detector.observe("passed", 0.0), None);
+
+        for _ in 0..20 {
+            detector.observe("constant", 1.0);
+        }
+        assert_eq!(detector.observe("constant", 1.0), None);
+        assert_eq!(detector.observe("constant", 0.0), Some(f64::NEG_INFINITY));
+    }
+}
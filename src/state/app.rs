@@ -1,16 +1,64 @@
-use super::metrics::{MetricData, Metric, SampleMetric};
+use super::aggregation::{Exclusions, GroupedAggregator, MetricAggregator, MetricStats};
+use super::anomaly::AnomalyDetector;
+use super::baseline::Baseline;
+use super::cost::{CostTracker, PricingTable, COST_METRIC};
+use super::expression::DerivedMetric;
+use super::histogram::{HistogramHistory, MergedHistogram};
+use super::metrics::{
+    AttributeKey, AttributeValue, Metric, MetricData, SampleMetric, TimeUnixNano,
+};
+use super::perf::PerfStats;
+use super::rules::{RuleSet, ThresholdRule};
+use super::sample_store::{DuplicatePolicy, SampleFilter, SampleSort, SampleStore};
+use super::scatter::{scatter_points, ScatterPoint};
+use super::script::SampleScript;
+use super::throughput::{Throughput, ThroughputTracker};
+use super::timeline::{Timeline, TimelineEventKind};
+use super::trends::{Trends, TOKENS_TREND};
 use super::types::{
-    EvaluationStatus, EvaluatorName, SampleResult, EtaCalculator, SampleStatus,
-    EvaluatorNotSet, EvaluatorSet, HandshakeNotSet, HandshakeSet,
-    Starting, WaitingForHandshake, CollectingMetrics, CompletedOrFailed,
+    ChartAxis, CollectingMetrics, CompletedOrFailed, ConfirmAction, EtaCalculator, EtaRange,
+    EvaluationStatus, EvaluatorName, EvaluatorNotSet, EvaluatorRestart, EvaluatorSet,
+    HandshakeNotSet, HandshakeSet, Notification, NotificationLevel, RawLine, ResourceUsage,
+    SampleResult, SampleStatus, Scroll, StageProgress, Starting, Tab, TextCapture,
+    WaitingForHandshake,
 };
-use crate::evaluator::protocol::ValidatedHandshake;
-use std::collections::HashMap;
+use crate::evaluator::output_queue::OutputDiagnostics;
+use crate::evaluator::protocol::{ValidatedExecutionPlan, ValidatedHandshake};
+use chrono::Utc;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::sync::Arc;
 
-/// Central application state with full typestate pattern
-#[derive(Debug)]
-pub struct AppState<E = EvaluatorNotSet, H = HandshakeNotSet, S = Starting> {
+/// How long the evaluator has to send its handshake unless told otherwise
+pub const DEFAULT_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Most notifications shown at once, the oldest giving way to new ones
+const MAX_NOTIFICATIONS: usize = 3;
+
+/// Most metric lines held back while paused; past this the oldest are
+/// applied, so a long pause cannot grow memory without bound
+const MAX_PENDING_METRICS: usize = 10_000;
+
+/// Most stdout lines kept for the raw view
+const MAX_RAW_LINES: usize = 500;
+
+/// Deepest level the raw view starts folding JSON at
+const MAX_JSON_FOLD_DEPTH: usize = 3;
+
+/// Attributes the leaderboard ranks by unless the statistics are grouped,
+/// in order of preference
+const LEADERBOARD_ATTRIBUTES: [&str; 4] = ["model", "variant", "gen_ai.request.model", "llm.model"];
+
+/// Statistic of how long samples took, in milliseconds
+pub const SAMPLE_DURATION_METRIC: &str = "sample.duration_ms";
+
+/// Everything the app tracks about a run, whatever phase it is in
+///
+/// Reached through [`AppState`], whose type records the phase.
+#[derive(Debug, Clone)]
+pub struct AppData {
     /// Name of the running evaluator (only available when E = EvaluatorSet)
     evaluator_name: Option<EvaluatorName>,
 
@@ -20,8 +68,9 @@ pub struct AppState<E = EvaluatorNotSet, H = HandshakeNotSet, S = Starting> {
     /// Current evaluation status (encoded in S type parameter)
     status: EvaluationStatus,
 
-    /// Collected metrics
-    metrics: Vec<MetricData>,
+    /// Summary metrics reported by the evaluator; sample metrics live in
+    /// `samples`
+    summary_metrics: Vec<MetricData>,
 
     /// Whether evaluation is paused
     paused: bool,
@@ -29,14 +78,24 @@ pub struct AppState<E = EvaluatorNotSet, H = HandshakeNotSet, S = Starting> {
     /// Track number of metrics received
     metrics_received: usize,
 
-    /// Sample tracking for progress display
-    samples: HashMap<String, SampleResult>,
+    /// Every sample of the run, within bounded memory
+    samples: SampleStore,
+
+    /// Index of the sample selected in the sample list, or `None` to
+    /// follow the newest
+    sample_cursor: Option<usize>,
+
+    /// Which samples the sample list shows
+    sample_filter: SampleFilter,
 
-    /// Recent completed samples (bounded for UI display)
-    recent_samples: Vec<SampleResult>,
-    
-    /// Maximum number of recent samples to keep
-    max_recent_samples: usize,
+    /// Whether the sample list's filter is being typed
+    sample_search_active: bool,
+
+    /// Note for the selected sample typed so far, while it is typed
+    sample_note_draft: Option<String>,
+
+    /// Metric the sample list is sorted by; unsorted lists are oldest first
+    sample_sort: Option<SampleSort>,
 
     /// ETA calculator for progress estimation
     eta_calculator: EtaCalculator,
@@ -44,34 +103,299 @@ pub struct AppState<E = EvaluatorNotSet, H = HandshakeNotSet, S = Starting> {
     /// Current sample being processed
     current_sample: Option<String>,
 
+    /// Recent evaluator stderr lines (bounded)
+    stderr_lines: VecDeque<String>,
+
+    /// Maximum number of stderr lines to keep
+    max_stderr_lines: usize,
+
+    /// Whether the stderr pane is shown
+    stderr_visible: bool,
+
+    /// Evaluator crashes that were followed by a restart
+    restarts: Vec<EvaluatorRestart>,
+
+    /// Latest resource usage sample of the evaluator
+    resource_usage: Option<ResourceUsage>,
+
+    /// Highest evaluator memory usage seen so far
+    peak_memory_bytes: u64,
+
+    /// Latest statistics of the evaluator output buffer
+    output_diagnostics: OutputDiagnostics,
+
+    /// When the evaluator last wrote a line to stdout or stderr
+    last_output_at: Option<std::time::Instant>,
+
+    /// When the evaluator was started and PrEval began waiting for its handshake
+    handshake_wait_started: Option<std::time::Instant>,
+
+    /// How long the evaluator has to send its handshake
+    handshake_timeout: std::time::Duration,
+
+    /// Latest memory usage of PrEval itself
+    preval_memory_bytes: Option<u64>,
+
+    /// Whether the diagnostics pane is shown
+    diagnostics_visible: bool,
+
+    /// Latest performance statistics of PrEval itself
+    perf_stats: PerfStats,
+
+    /// Whether the debug view of PrEval's own performance is shown
+    debug_visible: bool,
+
+    /// Destructive action waiting for the user to confirm it
+    pending_confirmation: Option<ConfirmAction>,
+
+    /// Per-metric statistics over every sample
+    metric_stats: MetricAggregator,
+
+    /// Configured threshold rules; the handshake may add more
+    threshold_rules: Vec<ThresholdRule>,
+
+    /// Rules that only flag values as worth a look, without failing
+    warning_rules: Vec<ThresholdRule>,
+
+    /// Violations so far of each threshold rule that tripped
+    tripped_rules: BTreeMap<String, usize>,
+
+    /// Per-metric statistics by data-point attribute value
+    grouped_stats: GroupedAggregator,
+
+    /// Attribute the statistics table is grouped by, if any
+    group_by: Option<String>,
+
+    /// Metrics that arrived while paused, applied on resume
+    pending_metrics: VecDeque<MetricData>,
+
+    /// Metrics computed per sample from the reported ones
+    derived_metrics: Vec<DerivedMetric>,
+
+    /// User script deriving metrics, checking and noting samples
+    script: Option<SampleScript>,
+
+    /// Token prices for estimating the run's cost
+    pricing: PricingTable,
+
+    /// Estimated cost of the run so far against its budget
+    cost: CostTracker,
+
+    /// How repeated reports of a sample are combined; None picks from the handshake
+    duplicate_policy: Option<DuplicatePolicy>,
+
+    /// Configured composite score; overrides the handshake's
+    score: Option<DerivedMetric>,
+
+    /// Flags metric values far outside their recent values; None disables it
+    anomaly_detector: Option<AnomalyDetector>,
+
+    /// Latest messages drawing attention to something during the run,
+    /// oldest first
+    notifications: VecDeque<Notification>,
+
+    /// Fatal problem shown until the user dismisses it
+    error_banner: Option<String>,
+
+    /// Warm-up samples and outlier tails left out of aggregate statistics
+    exclusions: Exclusions,
+
+    /// Timestamped log of notable events of the run
+    timeline: Timeline,
+
+    /// Whether the timeline pane is visible
+    timeline_visible: bool,
+
+    /// Past run to compare this one against
+    baseline: Option<Arc<Baseline>>,
+
+    /// Whether the overview compares the run to the baseline
+    comparison_visible: bool,
+
+    /// Whether the keybindings help is shown over the view
+    help_visible: bool,
+
+    /// Whether the final summary is shown over the view
+    summary_visible: bool,
+
+    /// Files the run's results were saved to, with what each holds
+    reports: Vec<(String, PathBuf)>,
+
+    /// How long the run took, once it is over
+    finished_after: Option<std::time::Duration>,
+
+    /// Samples and tokens completed per second
+    throughput: ThroughputTracker,
+
+    /// Which attributes' text is kept with each sample
+    text_capture: TextCapture,
+
+    /// View shown below the header
+    active_tab: Tab,
+
+    /// Number of views registered after the built-in tabs
+    extra_views: usize,
+
+    /// Scroll position within each built-in tab, indexed by [`Tab::index`]
+    scroll_positions: [usize; Tab::ALL.len()],
+
+    /// Most recent stdout lines of the evaluator, oldest first
+    raw_lines: VecDeque<RawLine>,
+
+    /// Nesting depth from which the raw view folds JSON objects and arrays
+    json_fold_depth: Option<usize>,
+
+    /// Recent values of the key metrics
+    trends: Trends,
+
+    /// Metrics toggled on in the chart
+    chart_series: BTreeSet<String>,
+
+    /// What the chart plots metrics against
+    chart_axis: ChartAxis,
+
+    /// Metric the leaderboard ranks groups by, instead of the score
+    leaderboard_metric: Option<String>,
+
+    /// Rank the lowest mean first, for metrics where less is better
+    leaderboard_ascending: bool,
+
+    /// Metrics plotted across and up the scatter plot, instead of the
+    /// token count and the sample duration
+    scatter_axes: (Option<String>, Option<String>),
+
+    /// Buckets of each histogram metric summed over the samples
+    histograms: BTreeMap<String, MergedHistogram>,
+
+    /// Bucket counts of each histogram metric per window of the run
+    histogram_history: BTreeMap<String, HistogramHistory>,
+}
+
+/// Central application state with full typestate pattern
+///
+/// Transitions consume the state and return it in its next phase; the data
+/// shared by every phase is reached through `Deref` to [`AppData`]. That
+/// data is boxed, so a transition only moves a pointer however much the run
+/// has stored.
+#[derive(Debug, Clone)]
+pub struct AppState<E = EvaluatorNotSet, H = HandshakeNotSet, S = Starting> {
+    data: Box<AppData>,
+
     /// Phantom data for typestate tracking
     _evaluator_state: PhantomData<E>,
     _handshake_state: PhantomData<H>,
     _status_state: PhantomData<S>,
 }
 
+impl<E, H, S> AppState<E, H, S> {
+    /// The same data in another phase
+    fn into_phase<E2, H2, S2>(self) -> AppState<E2, H2, S2> {
+        AppState {
+            data: self.data,
+            _evaluator_state: PhantomData,
+            _handshake_state: PhantomData,
+            _status_state: PhantomData,
+        }
+    }
+}
+
+impl<E, H, S> Deref for AppState<E, H, S> {
+    type Target = AppData;
+
+    fn deref(&self) -> &AppData {
+        &self.data
+    }
+}
+
+impl<E, H, S> DerefMut for AppState<E, H, S> {
+    fn deref_mut(&mut self) -> &mut AppData {
+        &mut self.data
+    }
+}
+
 /// Type aliases for common state combinations
 pub type InitialAppState = AppState<EvaluatorNotSet, HandshakeNotSet, Starting>;
 pub type AppStateWithEvaluator = AppState<EvaluatorSet, HandshakeNotSet, Starting>;
 pub type AppStateReady = AppState<EvaluatorSet, HandshakeSet, WaitingForHandshake>;
 pub type AppStateCollecting = AppState<EvaluatorSet, HandshakeSet, CollectingMetrics>;
 pub type AppStateFinished = AppState<EvaluatorSet, HandshakeSet, CompletedOrFailed>;
+pub type AppStateAborted = AppState<EvaluatorSet, HandshakeNotSet, CompletedOrFailed>;
 
 impl InitialAppState {
     /// Create new app state in initial starting state
     pub fn new() -> Self {
         Self {
-            evaluator_name: None,
-            handshake: None,
-            status: EvaluationStatus::Starting,
-            metrics: Vec::new(),
-            paused: false,
-            metrics_received: 0,
-            samples: HashMap::new(),
-            recent_samples: Vec::new(),
-            max_recent_samples: 10,
-            eta_calculator: EtaCalculator::new(),
-            current_sample: None,
+            data: Box::new(AppData {
+                evaluator_name: None,
+                handshake: None,
+                status: EvaluationStatus::Starting,
+                summary_metrics: Vec::new(),
+                paused: false,
+                metrics_received: 0,
+                samples: SampleStore::default(),
+                sample_cursor: None,
+                sample_filter: SampleFilter::default(),
+                sample_search_active: false,
+                sample_note_draft: None,
+                sample_sort: None,
+                eta_calculator: EtaCalculator::new(),
+                current_sample: None,
+                stderr_lines: VecDeque::new(),
+                max_stderr_lines: 500,
+                stderr_visible: false,
+                restarts: Vec::new(),
+                resource_usage: None,
+                peak_memory_bytes: 0,
+                output_diagnostics: OutputDiagnostics::default(),
+                last_output_at: None,
+                handshake_wait_started: None,
+                handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+                preval_memory_bytes: None,
+                diagnostics_visible: false,
+                perf_stats: PerfStats::default(),
+                debug_visible: false,
+                pending_confirmation: None,
+                metric_stats: MetricAggregator::new(),
+                threshold_rules: Vec::new(),
+                warning_rules: Vec::new(),
+                tripped_rules: BTreeMap::new(),
+                grouped_stats: GroupedAggregator::new(),
+                group_by: None,
+                pending_metrics: VecDeque::new(),
+                derived_metrics: Vec::new(),
+                script: None,
+                pricing: PricingTable::default(),
+                cost: CostTracker::default(),
+                duplicate_policy: None,
+                score: None,
+                anomaly_detector: Some(AnomalyDetector::default()),
+                notifications: VecDeque::new(),
+                error_banner: None,
+                exclusions: Exclusions::default(),
+                timeline: Timeline::default(),
+                timeline_visible: false,
+                baseline: None,
+                comparison_visible: false,
+                help_visible: false,
+                summary_visible: false,
+                reports: Vec::new(),
+                finished_after: None,
+                throughput: ThroughputTracker::default(),
+                text_capture: TextCapture::default(),
+                active_tab: Tab::default(),
+                extra_views: 0,
+                scroll_positions: [0; Tab::ALL.len()],
+                raw_lines: VecDeque::new(),
+                json_fold_depth: None,
+                trends: Trends::default(),
+                chart_series: BTreeSet::new(),
+                chart_axis: ChartAxis::default(),
+                leaderboard_metric: None,
+                leaderboard_ascending: false,
+                scatter_axes: (None, None),
+                histograms: BTreeMap::new(),
+                histogram_history: BTreeMap::new(),
+            }),
             _evaluator_state: PhantomData,
             _handshake_state: PhantomData,
             _status_state: PhantomData,
@@ -79,138 +403,225 @@ impl InitialAppState {
     }
 
     /// Set evaluator name - transitions to EvaluatorSet state
+    ///
+    /// The evaluator is started under this name, so its handshake is next.
     pub fn set_evaluator_name(mut self, name: EvaluatorName) -> AppStateWithEvaluator {
         self.evaluator_name = Some(name);
-        AppStateWithEvaluator {
-            evaluator_name: self.evaluator_name,
-            handshake: self.handshake,
-            status: self.status,
-            metrics: self.metrics,
-            paused: self.paused,
-            metrics_received: self.metrics_received,
-            samples: self.samples,
-            recent_samples: self.recent_samples,
-            max_recent_samples: self.max_recent_samples,
-            eta_calculator: self.eta_calculator,
-            current_sample: self.current_sample,
-            _evaluator_state: PhantomData,
-            _handshake_state: PhantomData,
-            _status_state: PhantomData,
-        }
+        self.enter_phase(EvaluationStatus::WaitingForHandshake);
+        self.handshake_wait_started = Some(std::time::Instant::now());
+        self.into_phase()
     }
 }
 
 impl AppStateWithEvaluator {
     /// Set handshake and transition to WaitingForHandshake state
     pub fn set_handshake(mut self, handshake: ValidatedHandshake) -> AppStateReady {
+        self.timeline.record(
+            TimelineEventKind::Handshake,
+            format!("Handshake from {}", handshake.evaluator.name),
+        );
         self.handshake = Some(handshake);
         self.status = EvaluationStatus::WaitingForHandshake;
-        AppStateReady {
-            evaluator_name: self.evaluator_name,
-            handshake: self.handshake,
-            status: self.status,
-            metrics: self.metrics,
-            paused: self.paused,
-            metrics_received: self.metrics_received,
-            samples: self.samples,
-            recent_samples: self.recent_samples,
-            max_recent_samples: self.max_recent_samples,
-            eta_calculator: self.eta_calculator,
-            current_sample: self.current_sample,
-            _evaluator_state: PhantomData,
-            _handshake_state: PhantomData,
-            _status_state: PhantomData,
-        }
+        self.into_phase()
+    }
+
+    /// Give up before the evaluator sent a handshake
+    pub fn fail(mut self, reason: String) -> AppStateAborted {
+        self.enter_phase(EvaluationStatus::Failed(reason));
+        self.into_phase()
     }
 }
 
 impl AppStateReady {
     /// Start collecting metrics - transition to CollectingMetrics state
     pub fn start_collecting(mut self) -> AppStateCollecting {
-        self.status = EvaluationStatus::CollectingMetrics {
-            received: 0,
-            total: self.get_total_samples_from_handshake(),
-        };
-        AppStateCollecting {
-            evaluator_name: self.evaluator_name,
-            handshake: self.handshake,
-            status: self.status,
-            metrics: self.metrics,
-            paused: self.paused,
-            metrics_received: self.metrics_received,
-            samples: self.samples,
-            recent_samples: self.recent_samples,
-            max_recent_samples: self.max_recent_samples,
-            eta_calculator: self.eta_calculator,
-            current_sample: self.current_sample,
-            _evaluator_state: PhantomData,
-            _handshake_state: PhantomData,
-            _status_state: PhantomData,
-        }
+        let total = self.get_total_samples_from_handshake();
+        self.enter_phase(EvaluationStatus::CollectingMetrics { received: 0, total });
+        self.into_phase()
     }
 }
 
 impl AppStateCollecting {
     /// Add metrics - only available in CollectingMetrics state
+    ///
+    /// While paused the metrics are only queued, so the display stays
+    /// frozen; they are applied on resume.
     pub fn add_metrics(mut self, metrics: MetricData) -> AppStateCollecting {
-        // Check if this is a summary metric (should not count toward sample progress)
-        let is_summary = self.is_summary_metrics(&metrics);
+        self.receive_metrics(metrics);
+        self
+    }
 
-        // Extract sample ID if present and not a summary
-        if !is_summary {
-            if let Some(sample_id) = self.extract_sample_id(&metrics) {
-                self.process_sample_metrics(sample_id.clone(), &metrics);
-                self.current_sample = Some(sample_id);
+    /// Add metrics in place, for owners that cannot give the state up
+    pub(crate) fn receive_metrics(&mut self, metrics: MetricData) {
+        if self.paused {
+            if self.pending_metrics.len() >= MAX_PENDING_METRICS {
+                if let Some(oldest) = self.pending_metrics.pop_front() {
+                    self.apply_metrics(oldest);
+                }
             }
+            self.pending_metrics.push_back(metrics);
+        } else {
+            self.apply_metrics(metrics);
         }
+    }
 
-        self.metrics.push(metrics);
+    /// Transition to finished state
+    pub fn finish(mut self, final_status: EvaluationStatus) -> AppStateFinished {
+        self.enter_phase(final_status);
+        self.into_phase()
+    }
+}
 
-        // Only increment counter for non-summary metrics (actual samples)
-        if !is_summary {
-            self.metrics_received += 1;
+// Shared implementation for all states
+impl AppData {
+    /// Fresh state for starting the run over, keeping display settings
+    pub fn fresh_run(&self) -> InitialAppState {
+        let mut state = InitialAppState::new();
+        state.max_stderr_lines = self.max_stderr_lines;
+        state.samples = SampleStore::new(self.samples.capacity());
+        state.threshold_rules = self.threshold_rules.clone();
+        state.warning_rules = self.warning_rules.clone();
+        state.group_by = self.group_by.clone();
+        state.derived_metrics = self.derived_metrics.clone();
+        state.script = self.script.clone();
+        state.score = self.score.clone();
+        state.pricing = self.pricing.clone();
+        state.duplicate_policy = self.duplicate_policy;
+        state.set_exclusions(self.exclusions);
+        state.text_capture = self.text_capture.clone();
+        state.trends = self.trends.without_history();
+        state.chart_series = self.chart_series.clone();
+        state.chart_axis = self.chart_axis;
+        state.leaderboard_metric = self.leaderboard_metric.clone();
+        state.leaderboard_ascending = self.leaderboard_ascending;
+        state.scatter_axes = self.scatter_axes.clone();
+        state.handshake_timeout = self.handshake_timeout;
+        state.json_fold_depth = self.json_fold_depth;
+        state.anomaly_detector = self
+            .anomaly_detector
+            .as_ref()
+            .map(AnomalyDetector::without_history);
+        state.cost.set_budget(self.cost.budget_usd());
+        state.stderr_visible = self.stderr_visible;
+        state.diagnostics_visible = self.diagnostics_visible;
+        state.perf_stats = self.perf_stats.clone();
+        state.debug_visible = self.debug_visible;
+        state.timeline_visible = self.timeline_visible;
+        state.baseline = self.baseline.clone();
+        state.comparison_visible = self.comparison_visible;
+        state.help_visible = self.help_visible;
+        state.active_tab = self.active_tab;
+        state.extra_views = self.extra_views;
+        state.sample_filter = self.sample_filter.clone();
+        state.sample_sort = self.sample_sort.clone();
+        state
+    }
+
+    /// Move to the phase of `status`, noting it on the timeline and
+    /// reporting why the run failed if it did
+    fn enter_phase(&mut self, status: EvaluationStatus) {
+        self.timeline
+            .record(TimelineEventKind::Phase, status.phase());
+        if let EvaluationStatus::Failed(reason) = &status {
+            self.report_error(format!("Run failed: {}", reason));
+        }
+        self.status = status;
+        if self.is_terminal() {
+            self.finished_after = Some(self.eta_calculator.elapsed());
         }
+    }
 
-        // Update ETA calculator with progress
-        self.eta_calculator.record_progress(self.metrics_received);
+    /// Ask the user to confirm an action before it happens
+    pub fn request_confirmation(&mut self, action: ConfirmAction) {
+        self.pending_confirmation = Some(action);
+    }
 
-        // Update status with new count using handshake data if available
-        let total = self.get_total_samples_from_handshake();
-        self.status = EvaluationStatus::CollectingMetrics {
-            received: self.metrics_received,
-            total,
-        };
+    /// Get the action waiting for confirmation, if any
+    pub fn pending_confirmation(&self) -> Option<ConfirmAction> {
+        self.pending_confirmation
+    }
 
-        self
+    /// Show a message to the user for a few seconds
+    pub fn notify(&mut self, message: String) {
+        self.raise_notification(message, NotificationLevel::Info);
     }
 
-    /// Transition to finished state
-    pub fn finish(mut self, final_status: EvaluationStatus) -> AppStateFinished {
-        self.status = final_status;
-        AppStateFinished {
-            evaluator_name: self.evaluator_name,
-            handshake: self.handshake,
-            status: self.status,
-            metrics: self.metrics,
-            paused: self.paused,
-            metrics_received: self.metrics_received,
-            samples: self.samples,
-            recent_samples: self.recent_samples,
-            max_recent_samples: self.max_recent_samples,
-            eta_calculator: self.eta_calculator,
-            current_sample: self.current_sample,
-            _evaluator_state: PhantomData,
-            _handshake_state: PhantomData,
-            _status_state: PhantomData,
+    /// Show a problem the run carries on past to the user for a few seconds
+    pub fn warn(&mut self, message: String) {
+        self.raise_notification(message, NotificationLevel::Warning);
+    }
+
+    /// Add a notification, counting repeats of the newest instead of
+    /// stacking them
+    fn raise_notification(&mut self, message: String, level: NotificationLevel) {
+        if let Some(newest) = self.notifications.back_mut().filter(|newest| {
+            !newest.is_expired() && newest.message == message && newest.level == level
+        }) {
+            newest.count += 1;
+            newest.raised_at = std::time::Instant::now();
+            return;
+        }
+        self.notifications
+            .retain(|notification| !notification.is_expired());
+        while self.notifications.len() >= MAX_NOTIFICATIONS {
+            self.notifications.pop_front();
         }
+        self.notifications
+            .push_back(Notification::new(message, level));
     }
-}
 
-// Shared implementation for all states
-impl<E, H, S> AppState<E, H, S> {
-    /// Toggle pause state
+    /// Notifications to show the user until they expire, oldest first
+    pub fn notifications(&self) -> impl Iterator<Item = &Notification> {
+        self.notifications
+            .iter()
+            .filter(|notification| !notification.is_expired())
+    }
+
+    /// Show a fatal problem to the user until they dismiss it
+    pub fn report_error(&mut self, message: String) {
+        self.error_banner = Some(message);
+    }
+
+    /// Fatal problem to show the user, until dismissed
+    pub fn error_banner(&self) -> Option<&str> {
+        self.error_banner.as_deref()
+    }
+
+    /// Hide the fatal problem shown to the user
+    pub fn dismiss_error(&mut self) {
+        self.error_banner = None;
+    }
+
+    /// Resolve the pending confirmation, returning the action it was for
+    pub fn take_confirmation(&mut self) -> Option<ConfirmAction> {
+        self.pending_confirmation.take()
+    }
+
+    /// Toggle pause state, freezing the ETA clock while paused
     pub fn toggle_pause(&mut self) {
         self.paused = !self.paused;
+        if self.paused {
+            self.eta_calculator.pause();
+            self.timeline
+                .record(TimelineEventKind::Pause, "Evaluator paused");
+        } else {
+            self.timeline
+                .record(TimelineEventKind::Resume, "Evaluator resumed");
+            self.eta_calculator.resume();
+            self.apply_pending_metrics();
+        }
+    }
+
+    /// Number of metric lines waiting for the pause to end
+    pub fn pending_metrics(&self) -> usize {
+        self.pending_metrics.len()
+    }
+
+    /// Apply metrics queued while paused, e.g. once the evaluator has exited
+    pub fn apply_pending_metrics(&mut self) {
+        while let Some(metrics) = self.pending_metrics.pop_front() {
+            self.apply_metrics(metrics);
+        }
     }
 
     /// Check if we're in a terminal state
@@ -232,10 +643,10 @@ impl<E, H, S> AppState<E, H, S> {
         &self.status
     }
 
-    /// Get metrics
+    /// Get the summary metrics reported by the evaluator
     #[allow(dead_code)] // Used in future stories
-    pub fn metrics(&self) -> &[MetricData] {
-        &self.metrics
+    pub fn summary_metrics(&self) -> &[MetricData] {
+        &self.summary_metrics
     }
 
     /// Check if paused
@@ -243,149 +654,1239 @@ impl<E, H, S> AppState<E, H, S> {
         self.paused
     }
 
-    /// Get handshake
-    pub fn handshake(&self) -> Option<&ValidatedHandshake> {
-        self.handshake.as_ref()
+    /// Record a line the evaluator wrote to stderr, dropping the oldest when full
+    pub fn push_stderr_line(&mut self, line: String) {
+        self.last_output_at = Some(std::time::Instant::now());
+        if self.max_stderr_lines == 0 {
+            return;
+        }
+        while self.stderr_lines.len() >= self.max_stderr_lines {
+            self.stderr_lines.pop_front();
+        }
+        self.stderr_lines.push_back(line);
+        self.hold_scroll_position(Tab::Logs);
     }
 
-    /// Get recent completed samples
-    pub fn recent_samples(&self) -> &[SampleResult] {
-        &self.recent_samples
+    /// Get buffered stderr lines, oldest first
+    pub fn stderr_lines(&self) -> &VecDeque<String> {
+        &self.stderr_lines
     }
 
-    /// Get current sample being processed
-    pub fn current_sample(&self) -> Option<&str> {
-        self.current_sample.as_deref()
+    /// Set how many stderr lines are retained
+    pub fn set_stderr_capacity(&mut self, capacity: usize) {
+        self.max_stderr_lines = capacity;
+        while self.stderr_lines.len() > capacity {
+            self.stderr_lines.pop_front();
+        }
     }
 
-    /// Calculate ETA for completion
-    pub fn calculate_eta(&self) -> Option<std::time::Duration> {
-        let total = self.get_total_samples_from_handshake()?;
-        self.eta_calculator.calculate_eta(self.metrics_received, total)
+    /// Record that the evaluator crashed and is being restarted
+    pub fn record_restart(&mut self, exit_code: Option<i32>) {
+        let attempt = self.restarts.len() as u32 + 1;
+        self.restarts.push(EvaluatorRestart {
+            attempt,
+            exit_code,
+            at: std::time::Instant::now(),
+        });
+        self.timeline.record(
+            TimelineEventKind::Restart,
+            format!("Restarting the evaluator (restart {})", attempt),
+        );
     }
 
-    /// Get elapsed time since evaluation started
-    pub fn elapsed_time(&self) -> std::time::Duration {
-        self.eta_calculator.elapsed()
+    /// Record something notable that happened during the run
+    pub fn record_event(&mut self, kind: TimelineEventKind, message: impl Into<String>) {
+        self.timeline.record(kind, message);
     }
 
-    /// Get completion progress as (completed, total, percentage)
-    pub fn progress(&self) -> (usize, Option<usize>, f64) {
-        let completed = self.metrics_received;
-        let total = self.get_total_samples_from_handshake();
-        let percentage = match total {
-            Some(t) if t > 0 => (completed as f64 / t as f64) * 100.0,
-            _ => 0.0,
-        };
-        (completed, total, percentage)
+    /// Throughput over the last minute, once there is a rate to show
+    pub fn throughput(&self) -> Option<Throughput> {
+        self.throughput.current()
     }
 
-    /// Get summary statistics
-    pub fn summary_stats(&self) -> (usize, usize, f64) {
-        let total_completed = self.recent_samples.len();
-        let failed_count = self.recent_samples.iter()
-            .filter(|sample| matches!(sample.status, SampleStatus::Failed(_)))
-            .count();
-        let success_rate = if total_completed > 0 {
-            ((total_completed - failed_count) as f64 / total_completed as f64) * 100.0
-        } else {
-            0.0
-        };
-        (failed_count, total_completed, success_rate)
+    /// Throughput over the whole run so far
+    pub fn average_throughput(&self) -> Option<Throughput> {
+        self.throughput.average()
     }
 
-    /// Check if metrics data represents a summary (not a sample)
-    /// With the new type system, this is now encoded at the type level!
-    fn is_summary_metrics(&self, metrics: &MetricData) -> bool {
-        // Check if any metric is a summary metric - the type system now makes this trivial!
-        metrics.metrics.iter().any(|metric| {
-            matches!(metric, Metric::Summary(_))
-        })
+    /// Get the notable events of the run, oldest first
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
     }
 
-    /// Get total samples from handshake execution plan
-    fn get_total_samples_from_handshake(&self) -> Option<usize> {
-        self.handshake
-            .as_ref()?
-            .execution_plan
-            .as_ref()
-            .map(|plan| plan.total_samples.into_inner() as usize)
+    /// Toggle timeline pane visibility
+    pub fn toggle_timeline(&mut self) {
+        self.timeline_visible = !self.timeline_visible;
     }
 
-    /// Extract sample ID from metrics data
-    fn extract_sample_id(&self, metrics: &MetricData) -> Option<String> {
-        use crate::state::metrics::AttributeValue;
+    /// Check if the timeline pane is visible
+    pub fn is_timeline_visible(&self) -> bool {
+        self.timeline_visible
+    }
 
-        // Try to find sample.id attribute in sample metrics only
-        for metric in &metrics.metrics {
-            match metric {
-                Metric::Sample(sample_metric) => {
-                    match sample_metric {
-                        SampleMetric::Gauge { data_points, .. } => {
-                            for point in data_points {
-                                for (key, value) in &point.attributes {
-                                    if key.as_ref() == "sample.id" {
-                                        if let AttributeValue::StringValue(s) = value {
-                                            return Some(s.clone());
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        SampleMetric::Counter { data_points, .. } => {
-                            for point in data_points {
-                                for (key, value) in &point.attributes {
-                                    if key.as_ref() == "sample.id" {
-                                        if let AttributeValue::StringValue(s) = value {
-                                            return Some(s.clone());
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        SampleMetric::Histogram { data_points, .. } => {
-                            for point in data_points {
-                                for (key, value) in &point.attributes {
-                                    if key.as_ref() == "sample.id" {
-                                        if let AttributeValue::StringValue(s) = value {
-                                            return Some(s.clone());
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                Metric::Summary(_) => {
-                    // Summary metrics don't have sample IDs by definition
-                    continue;
-                }
-            }
+    /// Set the past run to compare this one against
+    pub fn set_baseline(&mut self, baseline: Baseline) {
+        self.baseline = Some(Arc::new(baseline));
+    }
+
+    /// Get the past run this one is compared against, if any
+    pub fn baseline(&self) -> Option<&Baseline> {
+        self.baseline.as_deref()
+    }
+
+    /// Toggle comparing the run to the baseline, if there is one
+    pub fn toggle_comparison(&mut self) {
+        if self.baseline.is_none() {
+            self.notify(
+                "No baseline to compare against (start with --baseline <RUN_ID>)".to_string(),
+            );
+            return;
         }
-        None
+        self.comparison_visible = !self.comparison_visible;
     }
 
-    /// Process metrics for a specific sample
-    fn process_sample_metrics(&mut self, sample_id: String, metrics: &MetricData) {
-        // Extract key metrics from the data - only from sample metrics
-        let mut extracted_metrics = Vec::new();
-        
-        for metric in &metrics.metrics {
-            match metric {
-                Metric::Sample(sample_metric) => {
-                    match sample_metric {
-                        SampleMetric::Gauge { name, data_points, .. } => {
-                            for point in data_points {
-                                extracted_metrics.push((name.as_ref().to_string(), point.value.value()));
-                            }
-                        }
-                        SampleMetric::Counter { name, data_points, .. } => {
-                            for point in data_points {
-                                extracted_metrics.push((name.as_ref().to_string(), point.value.value()));
+    /// Check if the overview compares the run to the baseline
+    pub fn is_comparison_visible(&self) -> bool {
+        self.comparison_visible && self.baseline.is_some()
+    }
+
+    /// Toggle the keybindings help
+    pub fn toggle_help(&mut self) {
+        self.help_visible = !self.help_visible;
+    }
+
+    /// Close the keybindings help
+    pub fn hide_help(&mut self) {
+        self.help_visible = false;
+    }
+
+    /// Check if the keybindings help is shown
+    pub fn is_help_visible(&self) -> bool {
+        self.help_visible
+    }
+
+    /// Show the summary of the finished run over the view
+    pub fn show_summary(&mut self) {
+        self.summary_visible = true;
+    }
+
+    /// Close the summary to browse the results behind it
+    pub fn hide_summary(&mut self) {
+        self.summary_visible = false;
+    }
+
+    /// Check if the final summary is shown
+    pub fn is_summary_visible(&self) -> bool {
+        self.summary_visible
+    }
+
+    /// Note that the run's `description` was saved to `path`
+    pub fn add_report(&mut self, description: impl Into<String>, path: PathBuf) {
+        self.reports.push((description.into(), path));
+    }
+
+    /// Files the run's results were saved to, with what each holds
+    pub fn reports(&self) -> &[(String, PathBuf)] {
+        &self.reports
+    }
+
+    /// Get the crashes that led to restarts, oldest first
+    pub fn restarts(&self) -> &[EvaluatorRestart] {
+        &self.restarts
+    }
+
+    /// Record a resource usage sample of the evaluator
+    pub fn record_resource_usage(&mut self, usage: ResourceUsage) {
+        self.peak_memory_bytes = self.peak_memory_bytes.max(usage.memory_bytes);
+        self.resource_usage = Some(usage);
+    }
+
+    /// Get the latest resource usage sample, if any
+    pub fn resource_usage(&self) -> Option<&ResourceUsage> {
+        self.resource_usage.as_ref()
+    }
+
+    /// Get the highest evaluator memory usage seen, in bytes
+    pub fn peak_memory_bytes(&self) -> u64 {
+        self.peak_memory_bytes
+    }
+
+    /// Record the latest statistics of the evaluator output buffer
+    pub fn set_output_diagnostics(&mut self, diagnostics: OutputDiagnostics) {
+        self.output_diagnostics = diagnostics;
+    }
+
+    /// Get the latest statistics of the evaluator output buffer
+    pub fn output_diagnostics(&self) -> &OutputDiagnostics {
+        &self.output_diagnostics
+    }
+
+    /// How long ago the evaluator last wrote a line, if it has
+    pub fn last_output_age(&self) -> Option<std::time::Duration> {
+        self.last_output_at.map(|at| at.elapsed())
+    }
+
+    /// Set how long the evaluator has to send its handshake
+    pub fn set_handshake_timeout(&mut self, timeout: std::time::Duration) {
+        self.handshake_timeout = timeout;
+    }
+
+    /// Get how long the evaluator has to send its handshake
+    pub fn handshake_timeout(&self) -> std::time::Duration {
+        self.handshake_timeout
+    }
+
+    /// Time spent waiting for the handshake, while still waiting for it
+    pub fn handshake_wait(&self) -> Option<std::time::Duration> {
+        if self.handshake.is_some() || self.status != EvaluationStatus::WaitingForHandshake {
+            return None;
+        }
+        self.handshake_wait_started.map(|at| at.elapsed())
+    }
+
+    /// Record the latest memory usage of PrEval itself
+    pub fn record_preval_memory(&mut self, bytes: u64) {
+        self.preval_memory_bytes = Some(bytes);
+    }
+
+    /// Get the latest memory usage of PrEval itself, if sampled
+    pub fn preval_memory_bytes(&self) -> Option<u64> {
+        self.preval_memory_bytes
+    }
+
+    /// Show or hide the diagnostics pane
+    pub fn toggle_diagnostics(&mut self) {
+        self.diagnostics_visible = !self.diagnostics_visible;
+    }
+
+    /// Whether the diagnostics pane is shown
+    pub fn is_diagnostics_visible(&self) -> bool {
+        self.diagnostics_visible
+    }
+
+    /// Record the latest performance statistics of PrEval itself
+    pub fn set_perf_stats(&mut self, stats: PerfStats) {
+        self.perf_stats = stats;
+    }
+
+    /// Get the latest performance statistics of PrEval itself
+    pub fn perf_stats(&self) -> &PerfStats {
+        &self.perf_stats
+    }
+
+    /// Show or hide the debug view
+    pub fn toggle_debug(&mut self) {
+        self.debug_visible = !self.debug_visible;
+    }
+
+    /// Whether the debug view is shown
+    pub fn is_debug_visible(&self) -> bool {
+        self.debug_visible
+    }
+
+    /// Show or hide the stderr pane
+    pub fn toggle_stderr(&mut self) {
+        self.stderr_visible = !self.stderr_visible;
+    }
+
+    /// Check if the stderr pane is shown
+    pub fn is_stderr_visible(&self) -> bool {
+        self.stderr_visible
+    }
+
+    /// Switch to `tab`
+    pub fn select_tab(&mut self, tab: Tab) {
+        self.active_tab = tab;
+    }
+
+    /// Switch to the tab after the active one
+    pub fn next_tab(&mut self) {
+        self.active_tab = self.active_tab.next(self.extra_views);
+    }
+
+    /// Set how many views the UI shows after the built-in tabs
+    pub fn set_extra_views(&mut self, extra_views: usize) {
+        self.extra_views = extra_views;
+    }
+
+    /// Tab currently shown
+    pub fn active_tab(&self) -> Tab {
+        self.active_tab
+    }
+
+    /// Move through the list of the active tab
+    ///
+    /// The overview and the sample browser share the sample list.
+    pub fn scroll(&mut self, scroll: Scroll) {
+        let tab = self.active_tab;
+        if matches!(tab, Tab::Overview | Tab::Samples) {
+            self.scroll_samples(scroll);
+            return;
+        }
+        let scroll = if tab.follows_tail() {
+            scroll.reversed()
+        } else {
+            scroll
+        };
+        let len = self.tab_len(tab);
+        // Extra views scroll themselves, if at all
+        if let Some(position) = self.scroll_positions.get_mut(tab.index()) {
+            *position = scroll.apply(*position, len);
+        }
+    }
+
+    /// Keep the entries in view of `tab`, which follows its tail, where
+    /// they are as a new entry arrives, unless it is following new entries
+    fn hold_scroll_position(&mut self, tab: Tab) {
+        let position = &mut self.scroll_positions[tab.index()];
+        if *position > 0 {
+            *position += 1;
+        }
+    }
+
+    /// Scroll position within `tab`, kept inside its list
+    ///
+    /// Tabs that follow their tail count it back from the newest entry.
+    pub fn scroll_position(&self, tab: Tab) -> usize {
+        self.scroll_positions
+            .get(tab.index())
+            .map_or(0, |&position| {
+                position.min(self.tab_len(tab).saturating_sub(1))
+            })
+    }
+
+    /// Number of entries `tab` scrolls through
+    fn tab_len(&self, tab: Tab) -> usize {
+        match tab {
+            Tab::Overview | Tab::Samples | Tab::Extra(_) => 0,
+            Tab::Metrics | Tab::Chart => self.metric_stats.len(),
+            Tab::Logs => self.stderr_lines.len(),
+            Tab::Raw => self.raw_lines.len(),
+            Tab::Leaderboard => self.leaderboard().len(),
+            Tab::Scatter => self.scatter_outliers().len(),
+        }
+    }
+
+    /// Metric under the cursor of the chart's series list
+    pub fn chart_cursor(&self) -> Option<&str> {
+        self.metric_stats
+            .iter()
+            .nth(self.scroll_position(Tab::Chart))
+            .map(|(name, _)| name)
+    }
+
+    /// Plot or stop plotting the metric under the chart's cursor
+    pub fn toggle_chart_series(&mut self) {
+        let Some(name) = self.chart_cursor().map(str::to_string) else {
+            return;
+        };
+        if !self.chart_series.remove(&name) {
+            self.chart_series.insert(name);
+        }
+    }
+
+    /// Metrics toggled on in the chart
+    pub fn toggled_chart_series(&self) -> &BTreeSet<String> {
+        &self.chart_series
+    }
+
+    /// Metrics the chart plots: those toggled on, or else the one under
+    /// the cursor
+    pub fn chart_series(&self) -> Vec<&str> {
+        if self.chart_series.is_empty() {
+            self.chart_cursor().into_iter().collect()
+        } else {
+            self.chart_series.iter().map(String::as_str).collect()
+        }
+    }
+
+    /// Plot against the other of sample index and wall-clock time
+    pub fn toggle_chart_axis(&mut self) {
+        self.chart_axis = self.chart_axis.toggled();
+    }
+
+    /// What the chart plots metrics against
+    pub fn chart_axis(&self) -> ChartAxis {
+        self.chart_axis
+    }
+
+    /// Record a line the evaluator wrote to stdout, with why it was not
+    /// accepted if it was not
+    pub fn push_raw_line(&mut self, text: String, error: Option<String>) {
+        self.last_output_at = Some(std::time::Instant::now());
+        while self.raw_lines.len() >= MAX_RAW_LINES {
+            self.raw_lines.pop_front();
+        }
+        self.raw_lines.push_back(RawLine {
+            received_at: Utc::now(),
+            text,
+            error,
+        });
+        self.hold_scroll_position(Tab::Raw);
+    }
+
+    /// Get buffered stdout lines, oldest first
+    pub fn raw_lines(&self) -> &VecDeque<RawLine> {
+        &self.raw_lines
+    }
+
+    /// Fold the JSON of the raw view one level further out, or unfold it
+    /// again after the outermost level
+    pub fn cycle_json_fold(&mut self) {
+        self.json_fold_depth = match self.json_fold_depth {
+            None => Some(MAX_JSON_FOLD_DEPTH),
+            Some(1) => None,
+            Some(depth) => Some(depth - 1),
+        };
+    }
+
+    /// Nesting depth from which the raw view folds JSON, if it does
+    pub fn json_fold_depth(&self) -> Option<usize> {
+        self.json_fold_depth
+    }
+
+    /// Get handshake
+    pub fn handshake(&self) -> Option<&ValidatedHandshake> {
+        self.handshake.as_ref()
+    }
+
+    /// Get the samples kept in memory, oldest first
+    pub fn samples_in_memory(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &SampleResult> + ExactSizeIterator {
+        self.samples.recent(self.samples.capacity())
+    }
+
+    /// Samples in memory that pass the sample list's filter with their
+    /// indices, in its order
+    pub fn listed_samples(&self) -> Vec<(usize, &SampleResult)> {
+        self.samples
+            .query(&self.sample_filter, self.sample_sort.as_ref())
+    }
+
+    /// Whether the sample list shows every sample that can still be read,
+    /// paging the ones evicted from memory back from the spill file
+    ///
+    /// Filtering and sorting only cover the samples in memory.
+    pub fn lists_sample_history(&self) -> bool {
+        !self.sample_filter.is_active() && self.sample_sort.is_none()
+    }
+
+    /// Indices of the samples the sample list shows, in its order
+    fn listed_indices(&self) -> Vec<usize> {
+        if self.lists_sample_history() {
+            return self.samples.available().collect();
+        }
+        self.listed_samples()
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Number of samples the sample list shows
+    pub fn listed_len(&self) -> usize {
+        if self.lists_sample_history() {
+            return self.samples.available().len();
+        }
+        self.listed_samples().len()
+    }
+
+    /// Up to `count` of the samples the sample list shows from position
+    /// `first` on, with their indices
+    pub fn listed_page(&self, first: usize, count: usize) -> Vec<(usize, SampleResult)> {
+        if !self.lists_sample_history() {
+            return self
+                .listed_samples()
+                .into_iter()
+                .skip(first)
+                .take(count)
+                .map(|(index, sample)| (index, sample.clone()))
+                .collect();
+        }
+        let start = self.samples.available().start + first;
+        match self.samples.page(start, count) {
+            Ok(page) => (start..).zip(page).collect(),
+            Err(e) => {
+                tracing::warn!("Failed to read spilled samples: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Index of the sample selected in the sample list, if it shows any
+    ///
+    /// A selected sample the list no longer shows gives way to the next
+    /// newer one it does.
+    pub fn selected_sample(&self) -> Option<usize> {
+        let shown = self.listed_indices();
+        let last = *shown.last()?;
+        Some(match self.sample_cursor {
+            Some(cursor) if shown.contains(&cursor) => cursor,
+            Some(cursor) => shown
+                .into_iter()
+                .find(|&index| index >= cursor)
+                .unwrap_or(last),
+            None => last,
+        })
+    }
+
+    /// The sample selected in the sample list, while it is in memory
+    pub fn selected_sample_result(&self) -> Option<&SampleResult> {
+        let in_memory = self.samples.in_memory();
+        self.selected_sample()
+            .and_then(|index| self.samples_in_memory().nth(index - in_memory.start))
+    }
+
+    /// The sample selected in the sample list, read back from the spill
+    /// file if it is no longer in memory
+    pub fn read_selected_sample(&self) -> Option<SampleResult> {
+        let index = self.selected_sample()?;
+        match self.samples.page(index, 1) {
+            Ok(mut page) => page.pop(),
+            Err(e) => {
+                tracing::warn!("Failed to read spilled samples: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Whether the sample list follows the newest sample
+    pub fn is_following_samples(&self) -> bool {
+        self.sample_cursor.is_none()
+    }
+
+    /// Select the sample at `index` of the sample store, if the sample
+    /// list shows it
+    pub fn select_sample(&mut self, index: usize) {
+        let shown = self.listed_indices();
+        if shown.contains(&index) {
+            self.sample_cursor = (shown.last() != Some(&index)).then_some(index);
+        }
+    }
+
+    /// Move the selection of the sample list, which follows the newest
+    /// sample again once moved onto it
+    fn scroll_samples(&mut self, scroll: Scroll) {
+        let Some(selected) = self.selected_sample() else {
+            return;
+        };
+        let shown = self.listed_indices();
+        let Some(position) = shown.iter().position(|&index| index == selected) else {
+            return;
+        };
+        let position = scroll.apply(position, shown.len());
+        self.sample_cursor = (position + 1 != shown.len()).then_some(shown[position]);
+    }
+
+    /// Select the failed sample after the selected one in the sample list,
+    /// or before it unless `forward`, wrapping around at either end
+    pub fn select_failed_sample(&mut self, forward: bool) {
+        let listed = self.listed_samples();
+        let selected = self.selected_sample();
+        let start = listed
+            .iter()
+            .position(|(index, _)| Some(*index) == selected)
+            .unwrap_or(0);
+        let len = listed.len();
+        let failed = (1..=len)
+            .map(|step| {
+                let position = if forward {
+                    start + step
+                } else {
+                    start + len - step
+                };
+                listed[position % len]
+            })
+            .find(|(_, sample)| matches!(sample.status, SampleStatus::Failed(_)))
+            .map(|(index, _)| index);
+
+        match failed {
+            Some(index) => {
+                self.select_sample(index);
+                self.show_sample_list();
+            }
+            None => self.notify("No failed samples in the sample list".to_string()),
+        }
+    }
+
+    /// Which samples the sample list shows
+    pub fn sample_filter(&self) -> &SampleFilter {
+        &self.sample_filter
+    }
+
+    /// Start typing a filter for the sample list, showing the list if the
+    /// active tab has none
+    pub fn start_sample_search(&mut self) {
+        self.sample_search_active = true;
+        self.show_sample_list();
+    }
+
+    /// Show only samples whose id matches `query`, still being typed
+    /// unless `done`
+    pub fn search_samples(&mut self, query: &str, done: bool) {
+        self.sample_filter = self.sample_filter.clone().with_query(query);
+        self.sample_search_active = !done;
+        self.reset_sample_cursor();
+    }
+
+    /// Whether the sample list's filter is being typed
+    pub fn is_searching_samples(&self) -> bool {
+        self.sample_search_active
+    }
+
+    /// Bookmark the selected sample, or remove its bookmark
+    pub fn toggle_bookmark(&mut self) {
+        let Some((index, sample_id)) = self
+            .selected_sample()
+            .zip(self.selected_sample_result())
+            .map(|(index, sample)| (index, sample.sample_id.clone()))
+        else {
+            return;
+        };
+        let Some(annotation) = self.samples.annotation_mut(index) else {
+            return;
+        };
+        annotation.bookmarked = !annotation.bookmarked;
+        let message = if annotation.bookmarked {
+            format!("Bookmarked {}", sample_id)
+        } else {
+            format!("Removed the bookmark of {}", sample_id)
+        };
+        self.notify(message);
+    }
+
+    /// Start typing a note for the selected sample, showing the sample list
+    /// if the active tab has none
+    pub fn start_sample_note(&mut self) {
+        self.sample_note_draft = Some(String::new());
+        self.show_sample_list();
+    }
+
+    /// Note the selected sample with `note`, still being typed unless
+    /// `done`; an empty note removes it, and no note cancels typing it
+    pub fn edit_sample_note(&mut self, note: Option<String>, done: bool) {
+        if !done {
+            self.sample_note_draft = note;
+            return;
+        }
+        self.sample_note_draft = None;
+        let Some(note) = note else {
+            return;
+        };
+        let Some(annotation) = self
+            .selected_sample()
+            .and_then(|index| self.samples.annotation_mut(index))
+        else {
+            self.warn("No sample selected to note".to_string());
+            return;
+        };
+        let note = note.trim();
+        annotation.note = (!note.is_empty()).then(|| note.to_string());
+    }
+
+    /// Note for the selected sample typed so far, while it is typed
+    pub fn sample_note_draft(&self) -> Option<&str> {
+        self.sample_note_draft.as_deref()
+    }
+
+    /// Show samples of the next status only, or all of them again
+    pub fn cycle_status_filter(&mut self) {
+        let status = self.sample_filter.status().next();
+        self.sample_filter = self.sample_filter.clone().with_status(status);
+        self.reset_sample_cursor();
+        self.show_sample_list();
+    }
+
+    /// Metric the sample list is sorted by, if any
+    pub fn sample_sort(&self) -> Option<&SampleSort> {
+        self.sample_sort.as_ref()
+    }
+
+    /// Sort the sample list by the next metric the samples report, or in
+    /// the order they came in after the last one
+    pub fn cycle_sample_sort(&mut self) {
+        let names = self.samples.metric_names();
+        let next = match &self.sample_sort {
+            None => names.first(),
+            Some(sort) => names.iter().skip_while(|name| **name != sort.metric).nth(1),
+        };
+        self.sample_sort = next.map(|name| SampleSort::ascending(*name));
+        self.reset_sample_cursor();
+        self.show_sample_list();
+    }
+
+    /// Sort the sample list the other way round
+    pub fn reverse_sample_sort(&mut self) {
+        if let Some(sort) = &mut self.sample_sort {
+            sort.descending = !sort.descending;
+            self.reset_sample_cursor();
+            self.show_sample_list();
+        }
+    }
+
+    /// Select the top of a sorted list, where the samples sorted first
+    /// are, or else follow new samples
+    fn reset_sample_cursor(&mut self) {
+        self.sample_cursor = None;
+        if self.sample_sort.is_some() {
+            self.scroll_samples(Scroll::Top);
+        }
+    }
+
+    /// Switch to the sample browser unless the active tab shows samples
+    fn show_sample_list(&mut self) {
+        if !matches!(self.active_tab, Tab::Overview | Tab::Samples) {
+            self.active_tab = Tab::Samples;
+        }
+    }
+
+    /// Get every sample of the run recorded so far
+    pub fn sample_store(&self) -> &SampleStore {
+        &self.samples
+    }
+
+    /// Set how many samples are kept in memory
+    pub fn set_sample_capacity(&mut self, capacity: usize) {
+        self.samples = SampleStore::new(capacity);
+    }
+
+    /// Spill samples that no longer fit in memory to a JSON Lines file
+    pub fn spill_samples_to(&mut self, path: &std::path::Path) -> std::io::Result<()> {
+        self.samples.spill_to(path)
+    }
+
+    /// Write samples evicted from memory to a temporary file from now on
+    pub fn spill_samples_to_temporary(&mut self) -> std::io::Result<()> {
+        self.samples.spill_to_temporary()
+    }
+
+    /// Get current sample being processed
+    pub fn current_sample(&self) -> Option<&str> {
+        self.current_sample.as_deref()
+    }
+
+    /// Calculate ETA for completion, with its confidence band
+    pub fn calculate_eta(&self) -> Option<EtaRange> {
+        let total = self.get_total_samples_from_handshake()?;
+        self.eta_calculator
+            .calculate_eta(self.metrics_received, total)
+    }
+
+    /// Get elapsed time since evaluation started, up to its end once over
+    pub fn elapsed_time(&self) -> std::time::Duration {
+        self.finished_after
+            .unwrap_or_else(|| self.eta_calculator.elapsed())
+    }
+
+    /// Get completion progress as (completed, total, percentage)
+    pub fn progress(&self) -> (usize, Option<usize>, f64) {
+        let completed = self.metrics_received;
+        let total = self.get_total_samples_from_handshake();
+        let percentage = match total {
+            Some(t) if t > 0 => (completed as f64 / t as f64) * 100.0,
+            _ => 0.0,
+        };
+        (completed, total, percentage)
+    }
+
+    /// Batch the run is in, when the handshake gives a batch size
+    pub fn batch_progress(&self) -> Option<StageProgress> {
+        let plan = self.execution_plan()?;
+        let size = plan.batch_size?.into_inner() as usize;
+        let total = plan.total_samples.into_inner() as usize;
+        let batches: Vec<(Option<&str>, usize)> = (0..total)
+            .step_by(size)
+            .map(|start| (None, size.min(total - start)))
+            .collect();
+        StageProgress::locate(&batches, self.metrics_received)
+    }
+
+    /// Phase the run is in, of those the handshake declares
+    pub fn phase_progress(&self) -> Option<StageProgress> {
+        let phases: Vec<(Option<&str>, usize)> = self
+            .execution_plan()?
+            .phases
+            .iter()
+            .map(|phase| {
+                (
+                    Some(phase.name.as_ref()),
+                    phase.samples.into_inner() as usize,
+                )
+            })
+            .collect();
+        StageProgress::locate(&phases, self.metrics_received)
+    }
+
+    /// Set the threshold rules from configuration
+    pub fn set_threshold_rules(&mut self, rules: Vec<ThresholdRule>) {
+        self.threshold_rules = rules;
+    }
+
+    /// Set the rules that only warn, from configuration
+    pub fn set_warning_rules(&mut self, rules: Vec<ThresholdRule>) {
+        self.warning_rules = rules;
+    }
+
+    /// Rules that only mark values as worth a look when broken
+    pub fn warning_rules(&self) -> &[ThresholdRule] {
+        &self.warning_rules
+    }
+
+    /// Set the metrics computed per sample, in evaluation order
+    pub fn set_derived_metrics(&mut self, metrics: Vec<DerivedMetric>) {
+        self.derived_metrics = metrics;
+    }
+
+    /// Set the script run on every sample
+    pub fn set_script(&mut self, script: Option<SampleScript>) {
+        self.script = script;
+    }
+
+    /// Set the token prices used to estimate the run's cost
+    pub fn set_pricing(&mut self, pricing: PricingTable) {
+        self.pricing = pricing;
+    }
+
+    /// Set the most the run may cost, in USD
+    pub fn set_budget(&mut self, budget_usd: Option<f64>) {
+        self.cost.set_budget(budget_usd);
+    }
+
+    /// Estimated cost of the run, when any model has a price
+    pub fn cost(&self) -> Option<&CostTracker> {
+        (!self.pricing.is_empty()).then_some(&self.cost)
+    }
+
+    /// Set which values are left out of aggregate statistics
+    pub fn set_exclusions(&mut self, exclusions: Exclusions) {
+        self.exclusions = exclusions;
+        self.metric_stats.set_trim_percent(exclusions.trim_percent);
+        self.grouped_stats.set_trim_percent(exclusions.trim_percent);
+    }
+
+    /// Set which metrics' recent values are followed
+    pub fn set_trends(&mut self, metrics: Vec<String>) {
+        self.trends = Trends::new(metrics);
+    }
+
+    /// Recent values of the key metrics
+    pub fn trends(&self) -> &Trends {
+        &self.trends
+    }
+
+    /// Set which attributes' text is kept with each sample
+    pub fn set_text_capture(&mut self, capture: TextCapture) {
+        self.text_capture = capture;
+    }
+
+    /// Set how anomalous metric values are detected; None disables it
+    pub fn set_anomaly_detector(&mut self, detector: Option<AnomalyDetector>) {
+        self.anomaly_detector = detector;
+    }
+
+    /// Set the composite score of each sample, overriding the handshake's
+    pub fn set_score(&mut self, score: Option<DerivedMetric>) {
+        self.score = score;
+    }
+
+    /// Composite score of each sample, from configuration or the handshake
+    pub fn score(&self) -> Option<&DerivedMetric> {
+        self.score
+            .as_ref()
+            .or_else(|| self.handshake.as_ref()?.score.as_ref())
+    }
+
+    /// Mean composite score of the samples so far, the run's headline number
+    pub fn mean_score(&self) -> Option<f64> {
+        self.metric_stats.get(self.score()?.name())?.mean()
+    }
+
+    /// Set how repeated reports of a sample are combined
+    ///
+    /// Without one, samples the handshake says are run several times are
+    /// averaged over their runs, and other repeated reports are merged.
+    pub fn set_duplicate_policy(&mut self, policy: Option<DuplicatePolicy>) {
+        self.duplicate_policy = policy;
+    }
+
+    /// How repeated reports of a sample are combined
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        let reruns = self
+            .handshake
+            .as_ref()
+            .and_then(|handshake| handshake.execution_plan.as_ref())
+            .and_then(|plan| plan.runs_per_sample)
+            .is_some_and(|runs| runs.into_inner() > 1);
+        match self.duplicate_policy {
+            Some(policy) => policy,
+            None if reruns => DuplicatePolicy::Rerun,
+            None => DuplicatePolicy::Merge,
+        }
+    }
+
+    /// Configured threshold rules followed by those from the handshake
+    pub fn threshold_rules(&self) -> impl Iterator<Item = &ThresholdRule> {
+        self.threshold_rules.iter().chain(
+            self.handshake
+                .iter()
+                .flat_map(|handshake| handshake.thresholds.iter()),
+        )
+    }
+
+    /// Every threshold rule, for judging the values shown
+    pub fn rule_set(&self) -> RuleSet<'_> {
+        RuleSet::new(self.threshold_rules(), &self.warning_rules)
+    }
+
+    /// Threshold rules that tripped and how often, ordered by rule
+    pub fn tripped_rules(&self) -> &BTreeMap<String, usize> {
+        &self.tripped_rules
+    }
+
+    /// Check the aggregate threshold rules against the run's statistics
+    ///
+    /// Returns a description of every violation; an empty list means the
+    /// run passed.
+    pub fn check_aggregate_rules(&mut self) -> Vec<String> {
+        let violations: Vec<(String, String)> = self
+            .threshold_rules()
+            .filter_map(|rule| {
+                rule.check_stats(&self.metric_stats)
+                    .map(|violation| (rule.to_string(), violation))
+            })
+            .collect();
+        violations
+            .into_iter()
+            .map(|(rule, violation)| {
+                *self.tripped_rules.entry(rule).or_default() += 1;
+                self.timeline
+                    .record(TimelineEventKind::ThresholdViolation, violation.as_str());
+                violation
+            })
+            .collect()
+    }
+
+    /// Get per-metric statistics by data-point attribute value
+    pub fn grouped_stats(&self) -> &GroupedAggregator {
+        &self.grouped_stats
+    }
+
+    /// Attribute the statistics table is grouped by, if any
+    pub fn group_by(&self) -> Option<&str> {
+        self.group_by.as_deref()
+    }
+
+    /// Group the statistics table by `key`, or not at all
+    pub fn set_group_by(&mut self, key: Option<String>) {
+        self.group_by = key;
+    }
+
+    /// Group by the next attribute seen so far, after the last going back
+    /// to ungrouped statistics
+    pub fn cycle_group_by(&mut self) {
+        let next = match &self.group_by {
+            None => self.grouped_stats.keys().next(),
+            Some(current) => self
+                .grouped_stats
+                .keys()
+                .find(|key| key > &current.as_str()),
+        };
+        self.group_by = next.map(str::to_string);
+    }
+
+    /// Attribute the leaderboard ranks the groups of: the one the
+    /// statistics are grouped by, else a model or variant attribute, else
+    /// the first seen
+    pub fn leaderboard_attribute(&self) -> Option<&str> {
+        self.group_by().or_else(|| {
+            LEADERBOARD_ATTRIBUTES
+                .iter()
+                .find_map(|preferred| self.grouped_stats.keys().find(|key| key == preferred))
+                .or_else(|| self.grouped_stats.keys().next())
+        })
+    }
+
+    /// Metric the leaderboard ranks by: the one picked, else the composite
+    /// score, else the first metric
+    pub fn leaderboard_metric(&self) -> Option<&str> {
+        self.leaderboard_metric
+            .as_deref()
+            .or_else(|| self.score().map(DerivedMetric::name))
+            .or_else(|| self.metric_stats.iter().next().map(|(name, _)| name))
+    }
+
+    /// Rank the leaderboard by the next metric, wrapping around
+    pub fn cycle_leaderboard_metric(&mut self) {
+        let names: Vec<&str> = self.metric_stats.iter().map(|(name, _)| name).collect();
+        let next = self
+            .leaderboard_metric()
+            .and_then(|current| names.iter().skip_while(|name| **name != current).nth(1))
+            .or(names.first());
+        self.leaderboard_metric = next.map(|name| name.to_string());
+    }
+
+    /// Rank the leaderboard the other way round
+    pub fn reverse_leaderboard(&mut self) {
+        self.leaderboard_ascending = !self.leaderboard_ascending;
+    }
+
+    /// Whether the leaderboard ranks the lowest mean first
+    pub fn is_leaderboard_ascending(&self) -> bool {
+        self.leaderboard_ascending
+    }
+
+    /// Groups of the leaderboard attribute with their statistics of the
+    /// leaderboard metric, best first
+    pub fn leaderboard(&self) -> Vec<(&str, &MetricStats)> {
+        match (self.leaderboard_attribute(), self.leaderboard_metric()) {
+            (Some(key), Some(metric)) => {
+                self.grouped_stats
+                    .ranking(key, metric, self.leaderboard_ascending)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Metrics plotted across and up the scatter plot: the ones picked,
+    /// else the output token count (or else the first metric) against
+    /// how long samples took (or else the next metric)
+    pub fn scatter_axes(&self) -> (Option<&str>, Option<&str>) {
+        let names: Vec<&str> = self.metric_stats.iter().map(|(name, _)| name).collect();
+        let has = |name: &str| names.contains(&name);
+        let x = self.scatter_axes.0.as_deref().or_else(|| {
+            [&self.pricing.output_tokens, &self.pricing.input_tokens]
+                .into_iter()
+                .map(String::as_str)
+                .find(|name| has(name))
+                .or(names.first().copied())
+        });
+        let y = self.scatter_axes.1.as_deref().or_else(|| {
+            Some(SAMPLE_DURATION_METRIC)
+                .filter(|name| has(name) && x != Some(*name))
+                .or_else(|| names.iter().copied().find(|name| x != Some(*name)))
+        });
+        (x, y)
+    }
+
+    /// Plot the next metric along the scatter plot's x axis, or its y axis
+    /// when `vertical`
+    pub fn cycle_scatter_axis(&mut self, vertical: bool) {
+        let (x, y) = self.scatter_axes();
+        let current = if vertical { y } else { x };
+        let names: Vec<&str> = self.metric_stats.iter().map(|(name, _)| name).collect();
+        let next = current
+            .and_then(|current| names.iter().skip_while(|name| **name != current).nth(1))
+            .or(names.first())
+            .map(|name| name.to_string());
+        if vertical {
+            self.scatter_axes.1 = next;
+        } else {
+            self.scatter_axes.0 = next;
+        }
+    }
+
+    /// Samples in memory plotted in the scatter plot, oldest first
+    pub fn scatter_points(&self) -> Vec<ScatterPoint> {
+        let (Some(x), Some(y)) = self.scatter_axes() else {
+            return Vec::new();
+        };
+        let first = self.samples.in_memory().start;
+        scatter_points((first..).zip(self.samples_in_memory()), x, y)
+    }
+
+    /// Samples far off the scatter plot's trend, oldest first
+    pub fn scatter_outliers(&self) -> Vec<ScatterPoint> {
+        self.scatter_points()
+            .into_iter()
+            .filter(|point| point.outlier)
+            .collect()
+    }
+
+    /// Open the detail of the outlier selected in the scatter plot
+    pub fn open_scatter_outlier(&mut self) {
+        let position = self.scroll_position(Tab::Scatter);
+        if let Some(outlier) = self.scatter_outliers().get(position) {
+            self.select_sample(outlier.index);
+            self.select_tab(Tab::Samples);
+        }
+    }
+
+    /// Buckets of histogram metric `name` summed over the samples
+    pub fn histogram(&self, name: &str) -> Option<&MergedHistogram> {
+        self.histograms.get(name)
+    }
+
+    /// Buckets of histogram metric `name` per window of the run
+    pub fn histogram_history(&self, name: &str) -> Option<&HistogramHistory> {
+        self.histogram_history.get(name)
+    }
+
+    /// Get per-metric statistics over every completed sample
+    pub fn metric_stats(&self) -> &MetricAggregator {
+        &self.metric_stats
+    }
+
+    /// Get summary statistics
+    pub fn summary_stats(&self) -> (usize, usize, f64) {
+        let total_completed = self.samples.len();
+        let failed_count = self.samples.failed();
+        let success_rate = if total_completed > 0 {
+            ((total_completed - failed_count) as f64 / total_completed as f64) * 100.0
+        } else {
+            0.0
+        };
+        (failed_count, total_completed, success_rate)
+    }
+
+    /// Check if metrics data represents a summary (not a sample)
+    /// With the new type system, this is now encoded at the type level!
+    fn is_summary_metrics(&self, metrics: &MetricData) -> bool {
+        // Check if any metric is a summary metric - the type system now makes this trivial!
+        metrics
+            .metrics
+            .iter()
+            .any(|metric| !metric.counts_toward_progress())
+    }
+
+    /// Get total samples from handshake execution plan
+    fn get_total_samples_from_handshake(&self) -> Option<usize> {
+        self.execution_plan()
+            .map(|plan| plan.total_samples.into_inner() as usize)
+    }
+
+    /// Execution plan from the handshake, if it gave one
+    fn execution_plan(&self) -> Option<&ValidatedExecutionPlan> {
+        self.handshake.as_ref()?.execution_plan.as_ref()
+    }
+
+    /// Extract sample ID from metrics data
+    fn extract_sample_id(&self, metrics: &MetricData) -> Option<String> {
+        use crate::state::metrics::AttributeValue;
+
+        // Try to find sample.id attribute in sample metrics only
+        for metric in &metrics.metrics {
+            match metric {
+                Metric::Sample(sample_metric) => match sample_metric {
+                    SampleMetric::Gauge { data_points, .. } => {
+                        for point in data_points {
+                            for (key, value) in &point.attributes {
+                                if key.as_ref() == "sample.id" {
+                                    if let AttributeValue::StringValue(s) = value {
+                                        return Some(s.clone());
+                                    }
+                                }
                             }
                         }
-                        SampleMetric::Histogram { name, data_points, .. } => {
+                    }
+                    SampleMetric::Counter { data_points, .. } => {
+                        for point in data_points {
+                            for (key, value) in &point.attributes {
+                                if key.as_ref() == "sample.id" {
+                                    if let AttributeValue::StringValue(s) = value {
+                                        return Some(s.clone());
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    SampleMetric::Histogram { data_points, .. } => {
+                        for point in data_points {
+                            for (key, value) in &point.attributes {
+                                if key.as_ref() == "sample.id" {
+                                    if let AttributeValue::StringValue(s) = value {
+                                        return Some(s.clone());
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                Metric::Summary(_) => {
+                    // Summary metrics don't have sample IDs by definition
+                    continue;
+                }
+            }
+        }
+        None
+    }
+
+    /// Fold metrics into the samples, statistics and progress
+    fn apply_metrics(&mut self, metrics: MetricData) {
+        // Check if this is a summary metric (should not count toward sample progress)
+        let is_summary = self.is_summary_metrics(&metrics);
+
+        // Extract sample ID if present and not a summary
+        if !is_summary {
+            if let Some(sample_id) = self.extract_sample_id(&metrics) {
+                self.process_sample_metrics(sample_id.clone(), &metrics);
+                self.current_sample = Some(sample_id);
+            }
+        }
+
+        // Only increment counter for non-summary metrics (actual samples)
+        if is_summary {
+            self.summary_metrics.push(metrics);
+        } else {
+            self.metrics_received += 1;
+        }
+
+        // Update ETA calculator with progress
+        self.eta_calculator.record_progress(self.metrics_received);
+
+        // Update status with new count using handshake data if available
+        let total = self.get_total_samples_from_handshake();
+        self.status = EvaluationStatus::CollectingMetrics {
+            received: self.metrics_received,
+            total,
+        };
+    }
+
+    /// Process metrics for a specific sample
+    fn process_sample_metrics(&mut self, sample_id: String, metrics: &MetricData) {
+        // Extract key metrics from the data - only from sample metrics
+        let mut extracted_metrics = Vec::new();
+        // Samples while the evaluator warms up stay out of the statistics; a
+        // sample reported again keeps the place of its first report
+        let position = self
+            .samples
+            .index_of(&sample_id)
+            .unwrap_or(self.samples.len());
+        let warming_up = position < self.exclusions.warmup_samples;
+
+        for metric in &metrics.metrics {
+            match metric {
+                Metric::Sample(sample_metric) => {
+                    match sample_metric {
+                        SampleMetric::Gauge {
+                            name, data_points, ..
+                        } => {
                             for point in data_points {
+                                let value = point.value.value();
+                                extracted_metrics.push((name.as_ref().to_string(), value));
+                            }
+                        }
+                        SampleMetric::Counter {
+                            name, data_points, ..
+                        } => {
+                            for point in data_points {
+                                let value = point.value.value();
+                                extracted_metrics.push((name.as_ref().to_string(), value));
+                            }
+                        }
+                        SampleMetric::Histogram {
+                            name, data_points, ..
+                        } => {
+                            for point in data_points {
+                                if !warming_up {
+                                    self.histograms
+                                        .entry(name.as_ref().to_string())
+                                        .and_modify(|merged| merged.merge(&point.value))
+                                        .or_insert_with(|| MergedHistogram::new(&point.value));
+                                    self.histogram_history
+                                        .entry(name.as_ref().to_string())
+                                        .or_insert_with(|| HistogramHistory::new(&point.value))
+                                        .record(&point.value);
+                                }
                                 // Use average value for histograms
                                 let avg = if point.value.count > 0 {
                                     point.value.sum.unwrap_or(0.0) / point.value.count as f64
@@ -404,22 +1905,330 @@ impl<E, H, S> AppState<E, H, S> {
             }
         }
 
-        // Update or create sample result
-        let sample_result = self.samples.entry(sample_id.clone()).or_insert_with(|| {
-            SampleResult::new_processing(sample_id.clone())
-        });
+        if !self.pricing.is_empty() {
+            self.record_cost(metrics, &mut extracted_metrics);
+        }
+
+        // Each derived metric can use those defined before it
+        for derived in &self.derived_metrics {
+            if let Some(value) = derived.evaluate(&extracted_metrics) {
+                extracted_metrics.push((derived.name().to_string(), value));
+            }
+        }
+        let script = self.script.clone();
+        if let Some(script) = &script {
+            match script.derive(&extracted_metrics) {
+                Ok(derived) => extracted_metrics.extend(derived),
+                Err(e) => self.report_script_error(script, e),
+            }
+        }
+        // The score can weigh reported and derived metrics alike
+        if let Some(score) = self.score() {
+            if let Some(value) = score.evaluate(&extracted_metrics) {
+                extracted_metrics.push((score.name().to_string(), value));
+            }
+        }
+        let anomalies = self.detect_anomalies(&sample_id, &extracted_metrics);
+
+        // Record the sample as completed with its metrics
+        let mut sample_result = SampleResult::new_processing(sample_id);
+        sample_result.mark_completed(extracted_metrics);
+        sample_result.anomalies = anomalies;
+        sample_result.duration = sample_duration(metrics);
+        sample_result.texts = sample_texts(metrics, &self.text_capture);
+        sample_result.groups = sample_group_labels(metrics);
+
+        // A sample reported again is combined with its earlier result, and
+        // only rules it did not break before count as tripped again
+        let previous = self.samples.get(&sample_result.sample_id).cloned();
+        // Metrics merged into a sample reported before are not another sample
+        let new_samples = match (&previous, self.duplicate_policy()) {
+            (Some(_), DuplicatePolicy::Merge) => 0,
+            _ => 1,
+        };
+        let tokens = self
+            .pricing
+            .tokens(&sample_result.metrics)
+            .map(|(input, output)| input + output);
+        self.throughput.record(new_samples, tokens);
+        for (name, value) in &sample_result.metrics {
+            self.trends.record(name, *value);
+        }
+        if let Some(duration) = sample_result.duration {
+            self.trends
+                .record(SAMPLE_DURATION_METRIC, duration.as_secs_f64() * 1000.0);
+        }
+        if let Some(tokens) = tokens {
+            self.trends.record(TOKENS_TREND, tokens);
+        }
+        let mut already_tripped = Vec::new();
+        if let Some(previous) = &previous {
+            sample_result = self.duplicate_policy().combine(previous, sample_result);
+            already_tripped = self
+                .threshold_rules()
+                .filter(|rule| rule.check_sample(previous).is_some())
+                .map(ToString::to_string)
+                .collect();
+        }
+        if warming_up {
+            if previous.is_none() {
+                for (name, _) in sample_stat_values(&sample_result) {
+                    self.metric_stats.exclude_warmup(&name);
+                }
+            }
+        } else {
+            self.record_sample_stats(previous.as_ref(), &sample_result);
+        }
+
+        let mut violations: Vec<(String, String)> = self
+            .threshold_rules()
+            .filter_map(|rule| {
+                rule.check_sample(&sample_result)
+                    .map(|violation| (rule.to_string(), violation))
+            })
+            .collect();
+        if let Some(script) = &script {
+            match script.check(&sample_result) {
+                Ok(Some(reason)) => {
+                    let failed_before = previous
+                        .as_ref()
+                        .is_some_and(|previous| matches!(script.check(previous), Ok(Some(_))));
+                    if failed_before {
+                        already_tripped.push(script.name().to_string());
+                    }
+                    violations.push((
+                        script.name().to_string(),
+                        format!("{}: {}", script.name(), reason),
+                    ));
+                }
+                Ok(None) => {}
+                Err(e) => self.report_script_error(script, e),
+            }
+        }
+        if !violations.is_empty() {
+            let mut messages = Vec::with_capacity(violations.len());
+            for (rule, violation) in violations {
+                if !already_tripped.contains(&rule) {
+                    *self.tripped_rules.entry(rule).or_default() += 1;
+                    self.timeline.record(
+                        TimelineEventKind::ThresholdViolation,
+                        format!("Sample {}: {}", sample_result.sample_id, violation),
+                    );
+                }
+                messages.push(violation);
+            }
+            sample_result.mark_failed(messages.join("; "));
+        }
+        if let Some(script) = &script {
+            match script.on_sample(&sample_result) {
+                Ok(Some(note)) => self.timeline.record(
+                    TimelineEventKind::Script,
+                    format!("Sample {}: {}", sample_result.sample_id, note),
+                ),
+                Ok(None) => {}
+                Err(e) => self.report_script_error(script, e),
+            }
+        }
+        self.samples.record(sample_result);
+    }
+
+    /// Fold the values of `sample` into the statistics, in place of those
+    /// of its `previous` result
+    ///
+    /// Statistics count each sample once, with the values the duplicate
+    /// policy settled on, so a sample reported again takes back what its
+    /// earlier report added.
+    fn record_sample_stats(&mut self, previous: Option<&SampleResult>, sample: &SampleResult) {
+        if let Some(previous) = previous {
+            for (name, value) in sample_stat_values(previous) {
+                self.metric_stats.retract(&name, value);
+            }
+            let labels = &previous.groups;
+            for (name, value) in &previous.metrics {
+                self.grouped_stats.retract(
+                    labels
+                        .iter()
+                        .map(|(key, label)| (key.as_str(), label.clone())),
+                    name,
+                    *value,
+                );
+            }
+        }
+        for (name, value) in sample_stat_values(sample) {
+            self.metric_stats.record(&name, value);
+        }
+        let labels = &sample.groups;
+        for (name, value) in &sample.metrics {
+            self.grouped_stats.record(
+                labels
+                    .iter()
+                    .map(|(key, label)| (key.as_str(), label.clone())),
+                name,
+                *value,
+            );
+        }
+    }
+
+    /// Tell the user a call into `script` failed; the sample is recorded
+    /// as if the script had not been called
+    fn report_script_error(&mut self, script: &SampleScript, error: String) {
+        tracing::warn!("Script {}: {}", script.name(), error);
+        self.warn(format!("Script {}: {}", script.name(), error));
+    }
+
+    /// Metrics of a sample far outside their recent values, notifying the
+    /// user of the first
+    fn detect_anomalies(&mut self, sample_id: &str, metrics: &[(String, f64)]) -> Vec<String> {
+        let Some(detector) = &mut self.anomaly_detector else {
+            return Vec::new();
+        };
+        let mut anomalies: Vec<(String, f64)> = Vec::new();
+        for (name, value) in metrics {
+            if detector.observe(name, *value).is_some()
+                && !anomalies.iter().any(|(anomaly, _)| anomaly == name)
+            {
+                anomalies.push((name.clone(), *value));
+            }
+        }
+
+        if let Some((name, value)) = anomalies.first() {
+            let others = match anomalies.len() {
+                1 => String::new(),
+                n => format!(" and {} more", n - 1),
+            };
+            let message = format!(
+                "Anomalous {} = {:.3} in sample {}{}",
+                name, value, sample_id, others
+            );
+            self.timeline
+                .record(TimelineEventKind::Anomaly, message.as_str());
+            self.notify(message);
+        }
+        anomalies.into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Price a sample's tokens, adding its cost to the run and its metrics
+    fn record_cost(&mut self, metrics: &MetricData, extracted_metrics: &mut Vec<(String, f64)>) {
+        let Some((input, output)) = self.pricing.tokens(extracted_metrics) else {
+            return;
+        };
+        let model = attribute_label(metrics, &self.pricing.model_attribute);
+        match self.pricing.price_for(model.as_deref()) {
+            Some(price) => {
+                let cost = price.cost(input, output);
+                self.cost.record(cost);
+                extracted_metrics.push((COST_METRIC.to_string(), cost));
+            }
+            None => self
+                .cost
+                .record_unpriced(model.unwrap_or_else(|| "(no model)".to_string())),
+        }
+    }
+}
+
+/// Time from the earliest start to the latest timestamp of a sample's data
+/// points, if they span any time at all
+fn sample_duration(metrics: &MetricData) -> Option<std::time::Duration> {
+    let times = metrics.metrics.iter().flat_map(|metric| match metric {
+        Metric::Sample(SampleMetric::Gauge { data_points, .. }) => data_points
+            .iter()
+            .map(|p| (p.start_timestamp, p.timestamp))
+            .collect(),
+        Metric::Sample(SampleMetric::Counter { data_points, .. }) => data_points
+            .iter()
+            .map(|p| (p.start_timestamp, p.timestamp))
+            .collect(),
+        Metric::Sample(SampleMetric::Histogram { data_points, .. }) => data_points
+            .iter()
+            .map(|p| (p.start_timestamp, p.timestamp))
+            .collect(),
+        Metric::Summary(_) => Vec::new(),
+    });
+    let (start, end) = times.fold(
+        None,
+        |span: Option<(TimeUnixNano, TimeUnixNano)>, (start, end)| {
+            let start = start.unwrap_or(end);
+            Some(match span {
+                Some((first, last)) => (first.min(start), last.max(end)),
+                None => (start, end),
+            })
+        },
+    )?;
+    let nanos = end.into_inner().checked_sub(start.into_inner())?;
+    (nanos > 0).then(|| std::time::Duration::from_nanos(nanos))
+}
+
+/// Attributes of every sample data point in `metrics`
+fn point_attributes(
+    metrics: &MetricData,
+) -> impl Iterator<Item = &HashMap<AttributeKey, AttributeValue>> {
+    metrics.metrics.iter().flat_map(|metric| match metric {
+        Metric::Sample(SampleMetric::Gauge { data_points, .. }) => {
+            data_points.iter().map(|p| &p.attributes).collect()
+        }
+        Metric::Sample(SampleMetric::Counter { data_points, .. }) => {
+            data_points.iter().map(|p| &p.attributes).collect()
+        }
+        Metric::Sample(SampleMetric::Histogram { data_points, .. }) => {
+            data_points.iter().map(|p| &p.attributes).collect()
+        }
+        Metric::Summary(_) => Vec::new(),
+    })
+}
+
+/// Label of attribute `key` on a sample data point, or else on the resource
+fn attribute_label(metrics: &MetricData, key: &str) -> Option<String> {
+    point_attributes(metrics)
+        .chain(std::iter::once(&metrics.resource_attributes))
+        .find_map(|attributes| {
+            attributes
+                .iter()
+                .find(|(name, _)| name.as_ref() == key)
+                .and_then(|(_, value)| value.group_label())
+        })
+}
 
-        // Mark as completed with metrics
-        sample_result.mark_completed(extracted_metrics);
+/// Text of the captured attributes a sample reported, cut down to size
+fn sample_texts(metrics: &MetricData, capture: &TextCapture) -> BTreeMap<String, String> {
+    capture
+        .attributes
+        .iter()
+        .filter_map(|key| {
+            attribute_label(metrics, key).map(|text| (key.clone(), capture.truncate(text)))
+        })
+        .collect()
+}
 
-        // Add to recent samples (keep only the most recent)
-        self.recent_samples.push(sample_result.clone());
-        
-        // Keep only the most recent samples
-        if self.recent_samples.len() > self.max_recent_samples {
-            self.recent_samples.remove(0);
-        }
+/// Attributes of a data point that samples can be grouped by, with their labels
+fn group_labels(
+    attributes: &HashMap<AttributeKey, AttributeValue>,
+) -> impl Iterator<Item = (&str, String)> {
+    attributes
+        .iter()
+        .filter_map(|(key, value)| Some((key.as_ref(), value.group_label()?)))
+}
+
+/// Values `sample` adds to the statistics: its metrics, then its duration
+/// in milliseconds
+fn sample_stat_values(sample: &SampleResult) -> Vec<(String, f64)> {
+    let mut values = sample.metrics.clone();
+    if let Some(duration) = sample.duration {
+        values.push((
+            SAMPLE_DURATION_METRIC.to_string(),
+            duration.as_secs_f64() * 1000.0,
+        ));
+    }
+    values
+}
+
+/// Labels of the attributes on any of a sample's data points, the first
+/// label of each
+fn sample_group_labels(metrics: &MetricData) -> BTreeMap<String, String> {
+    let mut labels = BTreeMap::new();
+    for (key, label) in point_attributes(metrics).flat_map(group_labels) {
+        labels.entry(key.to_string()).or_insert(label);
     }
+    labels
 }
 
 impl Default for InitialAppState {
@@ -428,27 +2237,34 @@ impl Default for InitialAppState {
     }
 }
 
-/// State-related errors (most eliminated by typestate pattern)
+/// State-related errors
+///
+/// Transitions of [`AppState`] itself are checked at compile time; these
+/// come from [`RunState`](super::RunState), which picks the transition for
+/// whatever phase the run is in.
 #[derive(Debug, thiserror::Error)]
 pub enum StateError {
-    // These errors are eliminated by the typestate pattern:
-    // - EvaluatorAlreadySet: transitions ensure evaluator can only be set once
-    // - HandshakeAlreadySet: transitions ensure handshake can only be set once
-    // - InvalidTransition: state machine enforced by types
-    // - NotCollectingMetrics: add_metrics only available on AppStateCollecting
-    
     #[error("cannot transition from terminal state")]
-    TerminalState, // Could be eliminated with more complex phantom types
+    TerminalState,
+    #[error("cannot go from '{from}' to '{to}'")]
+    InvalidTransition { from: String, to: String },
+    #[error("metrics can only be added while collecting metrics")]
+    NotCollectingMetrics,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::evaluator::protocol::{ValidatedHandshake, Handshake, EvaluationMode, EvaluatorInfo, MessageType, ExecutionPlan};
+    use crate::evaluator::protocol::{
+        BatchSize, EvaluationMode, EvaluatorInfo, ExecutionPlan, Handshake, MessageType, PhaseName,
+        TotalSamples, ValidatedHandshake, ValidatedPhase,
+    };
+    use crate::state::sample_store::StatusFilter;
+    use crate::state::types::SampleStatus;
 
     // Tests removed by typestate pattern:
     //
-    // - test_evaluator_name_can_only_be_set_once: 
+    // - test_evaluator_name_can_only_be_set_once:
     //   The typestate pattern makes it impossible to set an evaluator name twice.
     //   Once set_evaluator_name() is called, it returns AppStateWithEvaluator,
     //   which doesn't have a set_evaluator_name() method.
@@ -485,6 +2301,1042 @@ mod tests {
         let _state = state.add_metrics(metrics);
     }
 
+    #[test]
+    fn handshake_wait_is_timed_until_the_handshake() {
+        let mut state = InitialAppState::new();
+        state.set_handshake_timeout(std::time::Duration::from_secs(30));
+        assert!(state.handshake_wait().is_none());
+
+        let state = state.set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap());
+        assert!(state.handshake_wait().is_some());
+
+        let state = state.set_handshake(create_test_handshake());
+        assert!(state.handshake_wait().is_none());
+        assert_eq!(
+            state.fresh_run().handshake_timeout(),
+            std::time::Duration::from_secs(30)
+        );
+    }
+
+    fn accuracy_sample(id: &str, accuracy: f64) -> MetricData {
+        use crate::state::metrics::*;
+
+        let mut attributes = std::collections::HashMap::new();
+        attributes.insert(
+            AttributeKey::try_new("sample.id".to_string()).unwrap(),
+            AttributeValue::StringValue(id.to_string()),
+        );
+        MetricData {
+            resource_attributes: Default::default(),
+            metrics: vec![Metric::Sample(SampleMetric::Gauge {
+                name: MetricName::try_new("accuracy".to_string()).unwrap(),
+                unit: None,
+                data_points: vec![DataPoint {
+                    start_timestamp: None,
+                    timestamp: TimeUnixNano::try_new(1234567890).unwrap(),
+                    value: GaugeValue::new(accuracy),
+                    attributes,
+                }],
+            })],
+        }
+    }
+
+    #[test]
+    fn sample_metrics_feed_metric_stats() {
+        let state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(accuracy_sample("a", 0.5))
+            .add_metrics(accuracy_sample("b", 1.0));
+
+        let accuracy = state.metric_stats().get("accuracy").unwrap();
+        assert_eq!(accuracy.count(), 2);
+        assert_eq!(accuracy.mean(), Some(0.75));
+    }
+
+    #[test]
+    fn timeline_records_transitions_and_pauses() {
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        state.toggle_pause();
+        state.toggle_pause();
+        state.record_event(TimelineEventKind::ParseError, "bad line");
+        let state = state.finish(EvaluationStatus::Completed);
+
+        let events: Vec<_> = state
+            .timeline()
+            .events()
+            .map(|event| (event.kind, event.message.as_str()))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (TimelineEventKind::Phase, "Waiting for handshake"),
+                (
+                    TimelineEventKind::Handshake,
+                    "Handshake from test-evaluator"
+                ),
+                (TimelineEventKind::Phase, "Collecting metrics"),
+                (TimelineEventKind::Pause, "Evaluator paused"),
+                (TimelineEventKind::Resume, "Evaluator resumed"),
+                (TimelineEventKind::ParseError, "bad line"),
+                (TimelineEventKind::Phase, "Completed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn warmup_samples_stay_out_of_metric_stats() {
+        let mut state = InitialAppState::new();
+        state.set_exclusions(Exclusions {
+            warmup_samples: 2,
+            trim_percent: 0.0,
+        });
+        let state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(accuracy_sample("cold", 0.0))
+            .add_metrics(accuracy_sample("warming", 0.25))
+            .add_metrics(accuracy_sample("a", 0.5))
+            .add_metrics(accuracy_sample("b", 1.0));
+
+        let accuracy = state.metric_stats().get("accuracy").unwrap();
+        assert_eq!(accuracy.count(), 2);
+        assert_eq!(accuracy.mean(), Some(0.75));
+        assert_eq!(accuracy.warmup_excluded(), 2);
+        assert_eq!(state.sample_store().len(), 4);
+    }
+
+    #[test]
+    fn groups_statistics_by_selected_attribute() {
+        let categorized = |id: &str, category: &str, accuracy: f64| {
+            let mut data = accuracy_sample(id, accuracy);
+            if let Metric::Sample(SampleMetric::Gauge { data_points, .. }) = &mut data.metrics[0] {
+                data_points[0].attributes.insert(
+                    AttributeKey::try_new("category".to_string()).unwrap(),
+                    AttributeValue::StringValue(category.to_string()),
+                );
+            }
+            data
+        };
+
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(categorized("a", "math", 0.5))
+            .add_metrics(categorized("b", "prose", 1.0))
+            .add_metrics(categorized("c", "math", 0.7));
+
+        assert_eq!(state.group_by(), None);
+        state.cycle_group_by();
+        assert_eq!(state.group_by(), Some("category"));
+        let math = state.grouped_stats().groups("category").next().unwrap();
+        assert_eq!(math.0, "math");
+        assert_eq!(math.1.get("accuracy").unwrap().count(), 2);
+
+        state.cycle_group_by();
+        assert_eq!(state.group_by(), Some("sample.id"));
+        state.cycle_group_by();
+        assert_eq!(state.group_by(), None);
+    }
+
+    #[test]
+    fn leaderboard_ranks_models_by_score() {
+        let by_model = |id: &str, model: &str, accuracy: f64| {
+            let mut data = accuracy_sample(id, accuracy);
+            if let Metric::Sample(SampleMetric::Gauge { data_points, .. }) = &mut data.metrics[0] {
+                data_points[0].attributes.insert(
+                    AttributeKey::try_new("model".to_string()).unwrap(),
+                    AttributeValue::StringValue(model.to_string()),
+                );
+            }
+            data
+        };
+        let mut state = InitialAppState::new();
+        state.set_score(Some("quality = 2 * accuracy".parse().unwrap()));
+        let mut state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(by_model("a", "small", 0.5))
+            .add_metrics(by_model("b", "large", 0.9))
+            .add_metrics(by_model("c", "small", 0.7));
+
+        assert_eq!(state.leaderboard_attribute(), Some("model"));
+        assert_eq!(state.leaderboard_metric(), Some("quality"));
+        let ranking: Vec<_> = state
+            .leaderboard()
+            .into_iter()
+            .map(|(group, stats)| (group, stats.mean().unwrap()))
+            .collect();
+        assert_eq!(ranking, vec![("large", 1.8), ("small", 1.2)]);
+
+        state.cycle_leaderboard_metric();
+        assert_eq!(state.leaderboard_metric(), Some("accuracy"));
+        state.reverse_leaderboard();
+        assert_eq!(state.leaderboard()[0].0, "small");
+    }
+
+    #[test]
+    fn scatter_plots_tokens_and_opens_outliers() {
+        use crate::state::metrics::*;
+
+        let with_tokens = |i: usize, tokens: f64| {
+            let mut data = accuracy_sample(&format!("s{}", i), 0.01 * tokens);
+            let mut metric = data.metrics[0].clone();
+            if let Metric::Sample(SampleMetric::Gauge {
+                name, data_points, ..
+            }) = &mut metric
+            {
+                *name = MetricName::try_new("gen_ai.usage.output_tokens".to_string()).unwrap();
+                data_points[0].value = GaugeValue::new(tokens);
+            }
+            data.metrics.push(metric);
+            data
+        };
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        for i in 0..12 {
+            state = state.add_metrics(with_tokens(i, i as f64 * 10.0));
+        }
+        let mut outlier = with_tokens(12, 50.0);
+        if let Metric::Sample(SampleMetric::Gauge { data_points, .. }) = &mut outlier.metrics[0] {
+            data_points[0].value = GaugeValue::new(9.0);
+        }
+        let mut state = state.add_metrics(outlier);
+
+        assert_eq!(
+            state.scatter_axes(),
+            (Some("gen_ai.usage.output_tokens"), Some("accuracy"))
+        );
+        let outliers = state.scatter_outliers();
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].sample_id, "s12");
+
+        state.select_tab(Tab::Scatter);
+        state.open_scatter_outlier();
+        assert_eq!(state.active_tab(), Tab::Samples);
+        assert_eq!(state.selected_sample(), Some(outliers[0].index));
+
+        state.cycle_scatter_axis(false);
+        assert_eq!(state.scatter_axes().0, Some("accuracy"));
+    }
+
+    #[test]
+    fn batches_and_phases_follow_completed_samples() {
+        let mut handshake = create_test_handshake();
+        let plan = handshake.execution_plan.as_mut().unwrap();
+        plan.batch_size = Some(BatchSize::try_new(4).unwrap());
+        plan.phases = ["generate", "grade"]
+            .into_iter()
+            .map(|name| ValidatedPhase {
+                name: PhaseName::try_new(name.to_string()).unwrap(),
+                samples: TotalSamples::try_new(5).unwrap(),
+            })
+            .collect();
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(handshake)
+            .start_collecting();
+        for i in 0..6 {
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), 0.5));
+        }
+
+        let batch = state.batch_progress().unwrap();
+        assert_eq!(
+            (batch.number, batch.stages, batch.completed, batch.samples),
+            (2, 3, 2, 4)
+        );
+        let phase = state.phase_progress().unwrap();
+        assert_eq!(phase.name.as_deref(), Some("grade"));
+        assert_eq!((phase.number, phase.completed, phase.samples), (2, 1, 5));
+
+        // The last batch holds what is left of the samples
+        for i in 6..12 {
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), 0.5));
+        }
+        let batch = state.batch_progress().unwrap();
+        assert_eq!((batch.number, batch.completed, batch.samples), (3, 2, 2));
+        assert_eq!(state.phase_progress().unwrap().completed, 5);
+    }
+
+    #[test]
+    fn metrics_arriving_while_paused_wait_for_resume() {
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(accuracy_sample("a", 0.5));
+        state.toggle_pause();
+
+        let mut state = state
+            .add_metrics(accuracy_sample("b", 1.0))
+            .add_metrics(accuracy_sample("c", 0.7));
+        assert_eq!(state.pending_metrics(), 2);
+        assert_eq!(state.progress().0, 1);
+        assert_eq!(state.sample_store().len(), 1);
+
+        state.toggle_pause();
+        assert_eq!(state.pending_metrics(), 0);
+        assert_eq!(state.progress().0, 3);
+        let ids: Vec<_> = state
+            .samples_in_memory()
+            .map(|s| s.sample_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn metrics_held_back_while_paused_are_bounded() {
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        state.toggle_pause();
+
+        for i in 0..MAX_PENDING_METRICS + 2 {
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), 0.5));
+        }
+        assert_eq!(state.pending_metrics(), MAX_PENDING_METRICS);
+        assert_eq!(state.progress().0, 2);
+    }
+
+    #[test]
+    fn reruns_of_a_sample_are_averaged() {
+        let mut handshake = create_test_handshake();
+        handshake.execution_plan.as_mut().unwrap().runs_per_sample =
+            Some(crate::evaluator::protocol::RunsPerSample::try_new(2).unwrap());
+        let state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(handshake);
+        assert_eq!(state.duplicate_policy(), DuplicatePolicy::Rerun);
+
+        let state = state
+            .start_collecting()
+            .add_metrics(accuracy_sample("a", 0.5))
+            .add_metrics(accuracy_sample("b", 0.5))
+            .add_metrics(accuracy_sample("a", 1.0));
+
+        let samples: Vec<_> = state
+            .samples_in_memory()
+            .map(|s| (s.sample_id.as_str(), s.runs, s.metrics[0].1))
+            .collect();
+        assert_eq!(samples, vec![("a", 2, 0.75), ("b", 1, 0.5)]);
+        let accuracy = state.metric_stats().get("accuracy").unwrap();
+        assert_eq!(accuracy.count(), 2);
+        assert_eq!(accuracy.mean(), Some(0.625));
+    }
+
+    #[test]
+    fn replaced_samples_leave_the_metric_stats() {
+        let mut state = InitialAppState::new();
+        state.set_duplicate_policy(Some(DuplicatePolicy::Replace));
+        let state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(accuracy_sample("a", 0.0))
+            .add_metrics(accuracy_sample("b", 0.5))
+            .add_metrics(accuracy_sample("a", 1.0));
+
+        let accuracy = state.metric_stats().get("accuracy").unwrap();
+        assert_eq!(accuracy.count(), 2);
+        assert_eq!(accuracy.mean(), Some(0.75));
+        // A retracted minimum is replaced by the next value to within 1%
+        assert!((accuracy.min().unwrap() - 0.5).abs() <= 0.005);
+        assert_eq!(accuracy.max(), Some(1.0));
+    }
+
+    #[test]
+    fn composite_score_is_tracked_as_a_metric() {
+        let mut handshake = create_test_handshake();
+        handshake.score = Some("score = accuracy".parse().unwrap());
+        let mut state = InitialAppState::new();
+        state.set_derived_metrics(vec!["error = 1 - accuracy".parse().unwrap()]);
+        state.set_score(Some(
+            "quality = 0.5 * accuracy + 0.5 * (1 - error)"
+                .parse()
+                .unwrap(),
+        ));
+        let state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(handshake)
+            .start_collecting()
+            .add_metrics(accuracy_sample("a", 0.5))
+            .add_metrics(accuracy_sample("b", 1.0));
+
+        assert_eq!(state.score().map(|score| score.name()), Some("quality"));
+        assert_eq!(state.mean_score(), Some(0.75));
+        assert_eq!(
+            state.samples_in_memory().next().unwrap().metrics.last(),
+            Some(&("quality".to_string(), 0.5))
+        );
+    }
+
+    #[test]
+    fn anomalous_samples_are_flagged_and_notified() {
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        for i in 0..12 {
+            let accuracy = 0.8 + (i % 3) as f64 * 0.05;
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), accuracy));
+        }
+        assert_eq!(latest_notification(&state), None);
+
+        let state = state.add_metrics(accuracy_sample("garbage", 0.0));
+
+        let sample = state.samples_in_memory().last().unwrap();
+        assert_eq!(sample.anomalies, vec!["accuracy".to_string()]);
+        assert_eq!(
+            latest_notification(&state),
+            Some("Anomalous accuracy = 0.000 in sample garbage")
+        );
+    }
+
+    /// Newest message shown to the user
+    fn latest_notification(state: &AppData) -> Option<&str> {
+        state
+            .notifications()
+            .last()
+            .map(|notification| notification.message.as_str())
+    }
+
+    #[test]
+    fn repeated_notifications_are_counted_and_the_oldest_give_way() {
+        let mut state = InitialAppState::new();
+        state.warn("Failed to parse metrics".to_string());
+        state.warn("Failed to parse metrics".to_string());
+        let counts: Vec<_> = state.notifications().map(|n| n.count).collect();
+        assert_eq!(counts, [2]);
+
+        for i in 0..MAX_NOTIFICATIONS {
+            state.notify(format!("message {}", i));
+        }
+        let messages: Vec<_> = state.notifications().map(|n| n.message.as_str()).collect();
+        assert_eq!(messages, ["message 0", "message 1", "message 2"]);
+        assert_eq!(latest_notification(&state), Some("message 2"));
+    }
+
+    #[test]
+    fn transitions_move_only_a_pointer() {
+        assert_eq!(
+            std::mem::size_of::<AppStateCollecting>(),
+            std::mem::size_of::<Box<AppData>>()
+        );
+    }
+
+    #[test]
+    fn failing_the_run_raises_a_dismissible_banner() {
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .fail("Evaluator exited before sending handshake".to_string());
+        assert_eq!(
+            state.error_banner(),
+            Some("Run failed: Evaluator exited before sending handshake")
+        );
+
+        state.dismiss_error();
+        assert_eq!(state.error_banner(), None);
+    }
+
+    #[test]
+    fn sample_text_is_captured_and_truncated() {
+        use crate::state::metrics::{AttributeKey, AttributeValue};
+
+        let mut state = InitialAppState::new();
+        state.set_text_capture(TextCapture {
+            attributes: vec!["prompt".to_string(), "response".to_string()],
+            max_chars: 5,
+        });
+        let mut sample = accuracy_sample("a", 1.0);
+        if let Metric::Sample(SampleMetric::Gauge { data_points, .. }) = &mut sample.metrics[0] {
+            for (key, text) in [("prompt", "2+2?"), ("response", "four, of course")] {
+                data_points[0].attributes.insert(
+                    AttributeKey::try_new(key.to_string()).unwrap(),
+                    AttributeValue::StringValue(text.to_string()),
+                );
+            }
+        }
+
+        let state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(sample);
+
+        let texts = &state.samples_in_memory().last().unwrap().texts;
+        assert_eq!(texts.get("prompt").map(String::as_str), Some("2+2?"));
+        assert_eq!(texts.get("response").map(String::as_str), Some("four,…"));
+        assert!(!texts.contains_key("expected"));
+    }
+
+    #[test]
+    fn each_tab_keeps_its_own_scroll_position() {
+        let mut state = InitialAppState::new();
+        for i in 0..15 {
+            state.push_stderr_line(format!("error {}", i));
+            state.push_raw_line(format!("line {}", i), None);
+        }
+
+        // Both views count back from the newest line, so up goes back
+        state.select_tab(Tab::Logs);
+        state.scroll(Scroll::PageUp);
+        state.scroll(Scroll::PageUp);
+        assert_eq!(state.scroll_position(Tab::Logs), 14);
+
+        state.next_tab();
+        assert_eq!(state.active_tab(), Tab::Raw);
+        state.scroll(Scroll::Up);
+        assert_eq!(state.scroll_position(Tab::Raw), 1);
+        state.scroll(Scroll::Bottom);
+        assert_eq!(state.scroll_position(Tab::Raw), 0);
+        assert_eq!(state.scroll_position(Tab::Logs), 14);
+
+        state.next_tab();
+        state.next_tab();
+        state.next_tab();
+        state.next_tab();
+        assert_eq!(state.active_tab(), Tab::Overview);
+    }
+
+    #[test]
+    fn extra_views_follow_the_built_in_tabs() {
+        let mut state = InitialAppState::new();
+        state.set_extra_views(2);
+        state.select_tab(Tab::Scatter);
+
+        state.next_tab();
+        assert_eq!(state.active_tab(), Tab::Extra(0));
+        // They keep no scroll position of their own
+        state.scroll(Scroll::Down);
+        assert_eq!(state.scroll_position(Tab::Extra(0)), 0);
+        state.next_tab();
+        assert_eq!(state.active_tab(), Tab::Extra(1));
+        state.next_tab();
+        assert_eq!(state.active_tab(), Tab::Overview);
+    }
+
+    #[test]
+    fn scrolled_back_logs_stay_put_until_following_again() {
+        let mut state = InitialAppState::new();
+        state.set_stderr_capacity(10);
+        for i in 0..10 {
+            state.push_stderr_line(format!("error {}", i));
+        }
+        state.select_tab(Tab::Logs);
+
+        // Following the tail, new lines scroll into view
+        state.push_stderr_line("error 10".to_string());
+        assert_eq!(state.scroll_position(Tab::Logs), 0);
+
+        // Scrolled back, the same line stays selected as lines arrive and
+        // the oldest are dropped
+        state.scroll(Scroll::PageUp);
+        assert_eq!(state.scroll_position(Tab::Logs), 9);
+        state.scroll(Scroll::Down);
+        state.scroll(Scroll::Down);
+        assert_eq!(state.stderr_lines()[10 - 1 - 7], "error 3");
+        state.push_stderr_line("error 11".to_string());
+        state.push_stderr_line("error 12".to_string());
+        assert_eq!(state.scroll_position(Tab::Logs), 9);
+        assert_eq!(state.stderr_lines()[10 - 1 - 9], "error 3");
+
+        state.scroll(Scroll::Bottom);
+        state.push_stderr_line("error 13".to_string());
+        assert_eq!(state.scroll_position(Tab::Logs), 0);
+    }
+
+    #[test]
+    fn sample_list_follows_new_samples_until_scrolled_up() {
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        assert_eq!(state.selected_sample(), None);
+        for i in 0..15 {
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), 1.0));
+        }
+        assert_eq!(state.selected_sample(), Some(14));
+
+        state.scroll(Scroll::PageUp);
+        assert_eq!(state.selected_sample(), Some(4));
+        let mut state = state.add_metrics(accuracy_sample("s15", 1.0));
+        assert_eq!(state.selected_sample(), Some(4));
+        assert!(!state.is_following_samples());
+
+        // Moving back onto the newest sample follows new ones again
+        state.select_tab(Tab::Samples);
+        state.scroll(Scroll::Bottom);
+        let state = state.add_metrics(accuracy_sample("s16", 1.0));
+        assert_eq!(state.selected_sample(), Some(16));
+        assert!(state.is_following_samples());
+    }
+
+    #[test]
+    fn sample_list_pages_back_into_spilled_samples() {
+        let dir = std::env::temp_dir().join(format!("preval-browse-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        state.set_sample_capacity(3);
+        state.spill_samples_to(&dir.join("samples.jsonl")).unwrap();
+        for i in 0..8 {
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), 1.0));
+        }
+
+        assert_eq!(state.listed_len(), 8);
+        state.select_tab(Tab::Samples);
+        state.scroll(Scroll::Top);
+        assert_eq!(state.selected_sample(), Some(0));
+        assert_eq!(state.read_selected_sample().unwrap().sample_id, "s0");
+        let ids: Vec<_> = state
+            .listed_page(1, 3)
+            .into_iter()
+            .map(|(index, sample)| (index, sample.sample_id))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                (1, "s1".to_string()),
+                (2, "s2".to_string()),
+                (3, "s3".to_string())
+            ]
+        );
+
+        // A filter only searches the samples in memory
+        state.search_samples("s", true);
+        assert_eq!(state.listed_len(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn selected_samples_can_be_bookmarked_and_noted() {
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        for i in 0..3 {
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), 1.0));
+        }
+        state.select_sample(1);
+        state.toggle_bookmark();
+
+        state.select_tab(Tab::Metrics);
+        state.start_sample_note();
+        assert_eq!(state.active_tab(), Tab::Samples);
+        state.edit_sample_note(Some("odd".to_string()), false);
+        assert_eq!(state.sample_note_draft(), Some("odd"));
+        state.edit_sample_note(Some(" odd answer ".to_string()), true);
+        assert_eq!(state.sample_note_draft(), None);
+
+        // Reported again, the sample keeps what the user marked it with
+        let state = state.add_metrics(accuracy_sample("s1", 0.5));
+        let annotation = &state.sample_store().get("s1").unwrap().annotation;
+        assert!(annotation.bookmarked);
+        assert_eq!(annotation.note.as_deref(), Some("odd answer"));
+
+        // Cancelling keeps the note, and an empty one removes it
+        let mut state = state;
+        state.select_sample(1);
+        state.start_sample_note();
+        state.edit_sample_note(None, true);
+        assert!(state
+            .selected_sample_result()
+            .unwrap()
+            .annotation
+            .note
+            .is_some());
+        state.start_sample_note();
+        state.edit_sample_note(Some(String::new()), true);
+        state.toggle_bookmark();
+        assert!(state
+            .selected_sample_result()
+            .unwrap()
+            .annotation
+            .is_empty());
+    }
+
+    #[test]
+    fn sample_list_shows_only_samples_passing_the_filter() {
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        for i in 0..12 {
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), 1.0));
+        }
+        state.select_tab(Tab::Metrics);
+
+        state.start_sample_search();
+        assert!(state.is_searching_samples());
+        assert_eq!(state.active_tab(), Tab::Samples);
+        state.search_samples("s1", false);
+        state.search_samples("s1", true);
+        assert!(!state.is_searching_samples());
+        let shown: Vec<_> = state
+            .listed_samples()
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(shown, vec![1, 10, 11]);
+        assert_eq!(state.selected_sample(), Some(11));
+
+        // Scrolling skips the samples the filter hides
+        state.scroll(Scroll::Up);
+        assert_eq!(state.selected_sample(), Some(10));
+        state.scroll(Scroll::Up);
+        assert_eq!(state.selected_sample(), Some(1));
+        state.select_sample(5);
+        assert_eq!(state.selected_sample(), Some(1));
+
+        state.cycle_status_filter();
+        assert_eq!(state.sample_filter().status(), StatusFilter::Completed);
+        state.cycle_status_filter();
+        assert_eq!(state.selected_sample(), None);
+        state.search_samples("", true);
+        state.cycle_status_filter();
+        state.cycle_status_filter();
+        assert!(!state.sample_filter().is_active());
+        assert_eq!(state.selected_sample(), Some(11));
+    }
+
+    #[test]
+    fn output_age_counts_from_the_latest_line() {
+        let mut state = InitialAppState::new();
+        assert_eq!(state.last_output_age(), None);
+
+        state.push_stderr_line("loading model".to_string());
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let since_stderr = state.last_output_age().unwrap();
+        state.push_raw_line("{}".to_string(), None);
+        assert!(state.last_output_age().unwrap() < since_stderr);
+    }
+
+    #[test]
+    fn finished_runs_keep_their_elapsed_time() {
+        let state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(accuracy_sample("s0", 1.0));
+        let mut state = state.finish(EvaluationStatus::Completed);
+        let elapsed = state.elapsed_time();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(state.elapsed_time(), elapsed);
+
+        state.show_summary();
+        state.add_report("Timeline", PathBuf::from("runs/1/timeline.jsonl"));
+        assert!(state.is_summary_visible());
+        assert_eq!(state.reports()[0].0, "Timeline");
+        state.hide_summary();
+        assert!(!state.is_summary_visible());
+    }
+
+    #[test]
+    fn comparison_needs_a_baseline() {
+        let mut state = InitialAppState::new();
+        state.toggle_comparison();
+        assert!(!state.is_comparison_visible());
+        assert!(latest_notification(&state).is_some());
+
+        state.set_baseline(Baseline {
+            label: "run 1".to_string(),
+            evaluator: "test-evaluator".to_string(),
+            total_samples: None,
+            samples_completed: 0,
+            samples_failed: 0,
+            score: None,
+            metric_stats: MetricAggregator::new(),
+        });
+        state.toggle_comparison();
+        assert!(state.is_comparison_visible());
+
+        // A restarted run is compared to the same baseline
+        let restarted = state.fresh_run();
+        assert!(restarted.is_comparison_visible());
+        assert_eq!(restarted.baseline().unwrap().label, "run 1");
+    }
+
+    #[test]
+    fn sample_list_sorts_by_each_metric_in_turn() {
+        let mut state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        for (i, accuracy) in [0.5, 0.2, 0.9].into_iter().enumerate() {
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), accuracy));
+        }
+        let listed = |state: &AppData| -> Vec<usize> {
+            state
+                .listed_samples()
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect()
+        };
+
+        state.cycle_sample_sort();
+        assert_eq!(
+            state.sample_sort(),
+            Some(&SampleSort::ascending("accuracy"))
+        );
+        assert_eq!(listed(&state), vec![1, 0, 2]);
+        // The lowest accuracy is selected, and stays so as samples arrive
+        assert_eq!(state.selected_sample(), Some(1));
+        let mut state = state.add_metrics(accuracy_sample("s3", 0.1));
+        assert_eq!(state.selected_sample(), Some(1));
+
+        state.reverse_sample_sort();
+        assert_eq!(listed(&state), vec![2, 0, 1, 3]);
+        assert_eq!(state.selected_sample(), Some(2));
+
+        // After the last metric the list is unsorted and follows again
+        while state.sample_sort().is_some() {
+            state.cycle_sample_sort();
+        }
+        assert_eq!(listed(&state), vec![0, 1, 2, 3]);
+        assert!(state.is_following_samples());
+    }
+
+    #[test]
+    fn failed_samples_are_selected_in_turn() {
+        let mut state = InitialAppState::new();
+        state.set_threshold_rules(vec!["accuracy >= 0.8".parse().unwrap()]);
+        let mut state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting();
+        for i in 0..6 {
+            state = state.add_metrics(accuracy_sample(&format!("s{}", i), 1.0));
+        }
+        state.select_failed_sample(true);
+        assert!(latest_notification(&state).is_some());
+        assert!(state.is_following_samples());
+
+        // Reported again below the threshold, so they fail
+        for id in ["s1", "s4"] {
+            state = state.add_metrics(accuracy_sample(id, 0.5));
+        }
+        state.select_failed_sample(true);
+        assert_eq!(state.selected_sample(), Some(1));
+        state.select_failed_sample(true);
+        assert_eq!(state.selected_sample(), Some(4));
+        state.select_failed_sample(true);
+        assert_eq!(state.selected_sample(), Some(1));
+        state.select_failed_sample(false);
+        assert_eq!(state.selected_sample(), Some(4));
+    }
+
+    #[test]
+    fn chart_plots_toggled_series_or_the_selected_one() {
+        let mut state = InitialAppState::new();
+        state.set_derived_metrics(vec!["error = 1 - accuracy".parse().unwrap()]);
+        let mut state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(accuracy_sample("a", 0.75));
+        state.select_tab(Tab::Chart);
+        assert_eq!(state.chart_series(), vec!["accuracy"]);
+
+        state.scroll(Scroll::Down);
+        assert_eq!(state.chart_series(), vec!["error"]);
+        state.toggle_chart_series();
+        state.scroll(Scroll::Up);
+        state.toggle_chart_series();
+        assert_eq!(state.chart_series(), vec!["accuracy", "error"]);
+
+        state.toggle_chart_axis();
+        assert_eq!(state.chart_axis(), ChartAxis::WallClock);
+    }
+
+    #[test]
+    fn json_folds_one_level_further_out_at_a_time() {
+        let mut state = InitialAppState::new();
+        assert_eq!(state.json_fold_depth(), None);
+        let depths: Vec<_> = (0..4)
+            .map(|_| {
+                state.cycle_json_fold();
+                state.json_fold_depth()
+            })
+            .collect();
+        assert_eq!(depths, [Some(3), Some(2), Some(1), None]);
+    }
+
+    #[test]
+    fn derived_metrics_are_computed_per_sample() {
+        let mut state = InitialAppState::new();
+        state.set_derived_metrics(vec![
+            "error = 1 - accuracy".parse().unwrap(),
+            "error_pct = error * 100".parse().unwrap(),
+        ]);
+        let state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(accuracy_sample("a", 0.75));
+
+        let sample = state.samples_in_memory().next().unwrap();
+        assert_eq!(
+            sample.metrics,
+            vec![
+                ("accuracy".to_string(), 0.75),
+                ("error".to_string(), 0.25),
+                ("error_pct".to_string(), 25.0)
+            ]
+        );
+        assert_eq!(
+            state.metric_stats().get("error_pct").unwrap().mean(),
+            Some(25.0)
+        );
+    }
+
+    #[test]
+    fn sample_durations_come_from_metric_timestamps() {
+        let timed = |id: &str, start: u64, end: u64| {
+            let mut data = accuracy_sample(id, 1.0);
+            if let Metric::Sample(SampleMetric::Gauge { data_points, .. }) = &mut data.metrics[0] {
+                data_points[0].start_timestamp = Some(TimeUnixNano::try_new(start).unwrap());
+                data_points[0].timestamp = TimeUnixNano::try_new(end).unwrap();
+            }
+            data
+        };
+
+        let state = InitialAppState::new()
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(timed("a", 1_000_000_000, 1_250_000_000))
+            .add_metrics(timed("b", 1_000_000_000, 1_750_000_000))
+            .add_metrics(accuracy_sample("c", 1.0));
+
+        let durations: Vec<_> = state.samples_in_memory().map(|s| s.duration).collect();
+        assert_eq!(
+            durations,
+            vec![
+                Some(std::time::Duration::from_millis(250)),
+                Some(std::time::Duration::from_millis(750)),
+                None
+            ]
+        );
+        let stats = state.metric_stats().get(SAMPLE_DURATION_METRIC).unwrap();
+        assert_eq!(stats.count(), 2);
+        assert_eq!(stats.mean(), Some(500.0));
+    }
+
+    #[test]
+    fn token_usage_is_priced_per_sample_and_run() {
+        use crate::state::cost::ModelPrice;
+        use crate::state::metrics::*;
+
+        let priced = |id: &str, model: &str| {
+            let mut data = accuracy_sample(id, 1.0);
+            let Metric::Sample(SampleMetric::Gauge { data_points, .. }) = &data.metrics[0] else {
+                unreachable!()
+            };
+            let attributes = data_points[0].attributes.clone();
+            for (name, tokens) in [
+                ("gen_ai.usage.input_tokens", 1000.0),
+                ("gen_ai.usage.output_tokens", 500.0),
+            ] {
+                data.metrics.push(Metric::Sample(SampleMetric::Counter {
+                    name: MetricName::try_new(name.to_string()).unwrap(),
+                    unit: None,
+                    data_points: vec![DataPoint {
+                        start_timestamp: None,
+                        timestamp: TimeUnixNano::try_new(1234567890).unwrap(),
+                        value: CounterValue::new(NonNegativeF64::try_new(tokens).unwrap()),
+                        attributes: attributes.clone(),
+                    }],
+                }));
+            }
+            data.resource_attributes.insert(
+                AttributeKey::try_new("gen_ai.request.model".to_string()).unwrap(),
+                AttributeValue::StringValue(model.to_string()),
+            );
+            data
+        };
+
+        let mut pricing = PricingTable::default();
+        pricing.models.insert(
+            "small".to_string(),
+            ModelPrice {
+                input_per_million: 1.0,
+                output_per_million: 2.0,
+            },
+        );
+        let mut state = InitialAppState::new();
+        assert!(state.cost().is_none());
+        state.set_pricing(pricing);
+        state.set_budget(Some(0.003));
+        let state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(priced("a", "small"))
+            .add_metrics(priced("b", "small"))
+            .add_metrics(priced("c", "large"));
+
+        let cost = state.cost().unwrap();
+        assert!((cost.total_usd() - 0.004).abs() < 1e-12);
+        assert!(cost.is_over_budget());
+        assert!(cost.unpriced_models().contains("large"));
+        assert_eq!(state.metric_stats().get(COST_METRIC).unwrap().count(), 2);
+
+        // Tokens per sample are followed as a key metric
+        let (_, tokens) = state
+            .trends()
+            .iter()
+            .find(|(name, _)| *name == TOKENS_TREND)
+            .unwrap();
+        assert_eq!(tokens.iter().copied().collect::<Vec<_>>(), vec![1500.0; 3]);
+    }
+
+    #[test]
+    fn threshold_rules_fail_samples_and_runs() {
+        let mut state = InitialAppState::new();
+        state.set_threshold_rules(vec![
+            "accuracy >= 0.8".parse().unwrap(),
+            "accuracy.mean >= 0.9".parse().unwrap(),
+        ]);
+        let mut state = state
+            .set_evaluator_name(EvaluatorName::try_new("test-evaluator").unwrap())
+            .set_handshake(create_test_handshake())
+            .start_collecting()
+            .add_metrics(accuracy_sample("a", 0.5))
+            .add_metrics(accuracy_sample("b", 1.0));
+
+        assert_eq!(state.summary_stats().0, 1);
+        let failed = state.samples_in_memory().next().unwrap();
+        assert_eq!(
+            failed.status,
+            SampleStatus::Failed("accuracy = 0.5 violates accuracy >= 0.8".to_string())
+        );
+
+        assert_eq!(
+            state.check_aggregate_rules(),
+            vec!["accuracy.mean >= 0.9 was 0.750".to_string()]
+        );
+        let tripped: Vec<_> = state.tripped_rules().iter().collect();
+        assert_eq!(
+            tripped,
+            vec![
+                (&"accuracy >= 0.8".to_string(), &1),
+                (&"accuracy.mean >= 0.9".to_string(), &1)
+            ]
+        );
+    }
+
     // Test ELIMINATED by mutually exclusive metric types:
     //
     // The test_summary_metrics_do_not_count_toward_progress test has been
@@ -511,6 +3363,7 @@ mod tests {
             name: MetricName::try_new("accuracy".to_string()).unwrap(),
             unit: None,
             data_points: vec![DataPoint {
+                start_timestamp: None,
                 timestamp: TimeUnixNano::try_new(1234567890).unwrap(),
                 value: GaugeValue::new(0.85),
                 attributes: HashMap::new(),
@@ -521,6 +3374,7 @@ mod tests {
             name: MetricName::try_new("accuracy".to_string()).unwrap(),
             unit: None,
             data_points: vec![DataPoint {
+                start_timestamp: None,
                 timestamp: TimeUnixNano::try_new(1234567890).unwrap(),
                 value: GaugeValue::new(0.81),
                 attributes: HashMap::new(),
@@ -541,22 +3395,89 @@ mod tests {
             mode: EvaluationMode::TestSuite,
             version: "1.0".to_string(),
             evaluator: EvaluatorInfo {
-                name: crate::evaluator::protocol::EvaluatorNameProtocol::try_new("test-evaluator".to_string()).unwrap(),
+                name: crate::evaluator::protocol::EvaluatorNameProtocol::try_new(
+                    "test-evaluator".to_string(),
+                )
+                .unwrap(),
                 description: None,
                 version: None,
             },
             execution_plan: Some(ExecutionPlan {
                 total_samples: 10,
                 batch_size: None,
+                runs_per_sample: None,
+                phases: vec![],
             }),
             metrics_schema: vec![],
+            capabilities: vec![],
+            thresholds: vec![],
+            score: None,
         };
         ValidatedHandshake::parse(handshake).unwrap()
     }
 
+    #[test]
+    fn stderr_buffer_is_bounded() {
+        let mut state = InitialAppState::new();
+        state.set_stderr_capacity(2);
+
+        state.push_stderr_line("first".to_string());
+        state.push_stderr_line("second".to_string());
+        state.push_stderr_line("third".to_string());
+
+        let lines: Vec<_> = state.stderr_lines().iter().map(String::as_str).collect();
+        assert_eq!(lines, vec!["second", "third"]);
+    }
+
+    #[test]
+    fn fresh_run_keeps_display_settings() {
+        let mut state = InitialAppState::new();
+        state.set_stderr_capacity(3);
+        state.toggle_stderr();
+        state.push_stderr_line("old run".to_string());
+        state.request_confirmation(ConfirmAction::KillEvaluator);
+
+        let fresh = state.fresh_run();
+
+        assert!(fresh.is_stderr_visible());
+        assert!(fresh.stderr_lines().is_empty());
+        assert_eq!(fresh.pending_confirmation(), None);
+        assert_eq!(fresh.max_stderr_lines, 3);
+    }
+
+    #[test]
+    fn peak_memory_survives_lower_samples() {
+        let mut state = InitialAppState::new();
+        let usage = |memory_bytes| ResourceUsage {
+            cpu_percent: 0.0,
+            memory_bytes,
+            open_files: None,
+        };
+
+        state.record_resource_usage(usage(300));
+        state.record_resource_usage(usage(100));
+
+        assert_eq!(state.resource_usage().map(|u| u.memory_bytes), Some(100));
+        assert_eq!(state.peak_memory_bytes(), 300);
+    }
+
+    #[test]
+    fn pausing_freezes_the_clock() {
+        let mut state = InitialAppState::new();
+        state.toggle_pause();
+        let frozen = state.elapsed_time();
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(state.elapsed_time(), frozen);
+
+        state.toggle_pause();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(state.elapsed_time() > frozen);
+    }
+
     // Note: Typestate pattern eliminates need for many tests:
     // - Cannot set evaluator name twice (method not available after first set)
-    // - Cannot set handshake twice (method not available after first set)  
+    // - Cannot set handshake twice (method not available after first set)
     // - Cannot add metrics unless in collecting state (method only on AppStateCollecting)
     // - Cannot transition to invalid states (only valid transitions available)
     //
@@ -0,0 +1,26 @@
+//! A finished run loaded from the run history for the current one to be
+//! compared against
+
+use super::aggregation::MetricAggregator;
+use super::significance::{compare_stats, MetricComparison};
+
+/// Outcome and metric statistics of a past run
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    /// How the run is referred to, e.g. "run 12"
+    pub label: String,
+    pub evaluator: String,
+    pub total_samples: Option<u32>,
+    pub samples_completed: usize,
+    pub samples_failed: usize,
+    /// Mean composite score, when the run had one
+    pub score: Option<f64>,
+    pub metric_stats: MetricAggregator,
+}
+
+impl Baseline {
+    /// How every metric of a run with `metric_stats` compares to this one
+    pub fn compare(&self, metric_stats: &MetricAggregator) -> Vec<MetricComparison> {
+        compare_stats(&self.metric_stats, metric_stats)
+    }
+}
@@ -0,0 +1,209 @@
+//! Estimated spend of a run from token counters and per-model prices
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Metric every priced sample gets, holding its estimated cost in USD
+pub const COST_METRIC: &str = "cost_usd";
+
+/// Model key whose price applies to models without their own
+pub const FALLBACK_MODEL: &str = "*";
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPrice {
+    /// Cost of `input` and `output` tokens in USD
+    pub fn cost(&self, input: f64, output: f64) -> f64 {
+        (input * self.input_per_million + output * self.output_per_million) / 1_000_000.0
+    }
+}
+
+/// Prices by model, and where samples report their model and token counts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PricingTable {
+    /// Attribute naming the model, on the data points or the resource
+    pub model_attribute: String,
+    /// Metric counting the prompt tokens of a sample
+    pub input_tokens: String,
+    /// Metric counting the completion tokens of a sample
+    pub output_tokens: String,
+    /// Price of each model; `"*"` prices every other model
+    pub models: BTreeMap<String, ModelPrice>,
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        Self {
+            model_attribute: "gen_ai.request.model".to_string(),
+            input_tokens: "gen_ai.usage.input_tokens".to_string(),
+            output_tokens: "gen_ai.usage.output_tokens".to_string(),
+            models: BTreeMap::new(),
+        }
+    }
+}
+
+impl PricingTable {
+    /// Whether no model has a price, so nothing is tracked
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Price of `model`, falling back to the `"*"` entry
+    pub fn price_for(&self, model: Option<&str>) -> Option<&ModelPrice> {
+        model
+            .and_then(|model| self.models.get(model))
+            .or_else(|| self.models.get(FALLBACK_MODEL))
+    }
+
+    /// Token counts of a sample's metrics, if it reports any
+    pub fn tokens(&self, metrics: &[(String, f64)]) -> Option<(f64, f64)> {
+        let total = |name: &str| {
+            metrics
+                .iter()
+                .filter(|(metric, _)| metric == name)
+                .map(|(_, value)| *value)
+                .reduce(|a, b| a + b)
+        };
+        match (total(&self.input_tokens), total(&self.output_tokens)) {
+            (None, None) => None,
+            (input, output) => Some((input.unwrap_or(0.0), output.unwrap_or(0.0))),
+        }
+    }
+}
+
+/// What to do when a run goes over its budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BudgetAction {
+    /// Log a warning and keep going
+    Warn,
+    /// Fail the run and stop the evaluator
+    #[default]
+    Abort,
+}
+
+impl std::str::FromStr for BudgetAction {
+    type Err = BudgetActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "warn" => Ok(Self::Warn),
+            "abort" => Ok(Self::Abort),
+            other => Err(BudgetActionError(other.to_string())),
+        }
+    }
+}
+
+/// Error for unrecognized budget action names
+#[derive(Debug, thiserror::Error)]
+#[error("unknown budget action '{0}' (expected 'warn' or 'abort')")]
+pub struct BudgetActionError(String);
+
+/// Running total of a run's estimated cost against an optional budget
+#[derive(Debug, Clone, Default)]
+pub struct CostTracker {
+    total_usd: f64,
+    budget_usd: Option<f64>,
+    unpriced_models: BTreeSet<String>,
+}
+
+impl CostTracker {
+    /// Set the most the run may cost, in USD
+    pub fn set_budget(&mut self, budget_usd: Option<f64>) {
+        self.budget_usd = budget_usd;
+    }
+
+    /// Add the cost of one sample
+    pub fn record(&mut self, cost_usd: f64) {
+        self.total_usd += cost_usd;
+    }
+
+    /// Remember a model that reported tokens but has no price
+    pub fn record_unpriced(&mut self, model: String) {
+        self.unpriced_models.insert(model);
+    }
+
+    /// Estimated cost of the run so far, in USD
+    pub fn total_usd(&self) -> f64 {
+        self.total_usd
+    }
+
+    /// Most the run may cost, in USD
+    pub fn budget_usd(&self) -> Option<f64> {
+        self.budget_usd
+    }
+
+    /// Whether the run has cost more than its budget
+    pub fn is_over_budget(&self) -> bool {
+        self.budget_usd
+            .is_some_and(|budget| self.total_usd > budget)
+    }
+
+    /// Models that reported tokens without a price, so are not counted
+    pub fn unpriced_models(&self) -> &BTreeSet<String> {
+        &self.unpriced_models
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> PricingTable {
+        let mut table = PricingTable::default();
+        table.models.insert(
+            "gpt-4o".to_string(),
+            ModelPrice {
+                input_per_million: 2.5,
+                output_per_million: 10.0,
+            },
+        );
+        table
+    }
+
+    #[test]
+    fn prices_tokens_by_model_with_fallback() {
+        let mut table = table();
+        let price = table.price_for(Some("gpt-4o")).unwrap();
+        assert_eq!(price.cost(1_000_000.0, 100_000.0), 3.5);
+        assert!(table.price_for(Some("claude")).is_none());
+        assert!(table.price_for(None).is_none());
+
+        table
+            .models
+            .insert(FALLBACK_MODEL.to_string(), ModelPrice::default());
+        assert_eq!(
+            table.price_for(Some("claude")),
+            Some(&ModelPrice::default())
+        );
+    }
+
+    #[test]
+    fn token_counts_come_from_configured_metrics() {
+        let table = table();
+        let metrics = vec![
+            ("gen_ai.usage.input_tokens".to_string(), 100.0),
+            ("gen_ai.usage.input_tokens".to_string(), 20.0),
+            ("accuracy".to_string(), 1.0),
+        ];
+        assert_eq!(table.tokens(&metrics), Some((120.0, 0.0)));
+        assert_eq!(table.tokens(&metrics[2..]), None);
+    }
+
+    #[test]
+    fn tracks_total_against_budget() {
+        let mut tracker = CostTracker::default();
+        tracker.record(0.75);
+        assert!(!tracker.is_over_budget());
+        tracker.set_budget(Some(1.0));
+        tracker.record(0.5);
+        assert_eq!(tracker.total_usd(), 1.25);
+        assert!(tracker.is_over_budget());
+    }
+}
@@ -0,0 +1,442 @@
+//! Derived metrics computed per sample from arithmetic expressions,
+//! e.g. `f1 = 2*p*r/(p+r)`
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Arithmetic over a sample's metrics
+///
+/// Supports numbers, metric names (which may contain dots), `+ - * / ^`,
+/// parentheses and the functions `abs`, `sqrt`, `min` and `max`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    Metric(String),
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Call(Function, Vec<Expression>),
+}
+
+/// Binary arithmetic operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+/// Built-in function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Abs,
+    Sqrt,
+    Min,
+    Max,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "abs" => Some(Self::Abs),
+            "sqrt" => Some(Self::Sqrt),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            _ => None,
+        }
+    }
+
+    /// Number of arguments the function takes
+    fn arity(self) -> usize {
+        match self {
+            Self::Abs | Self::Sqrt => 1,
+            Self::Min | Self::Max => 2,
+        }
+    }
+}
+
+impl Expression {
+    /// Evaluate with metric values from `lookup`
+    ///
+    /// Returns `None` when a metric is missing or the result is not a
+    /// finite number, e.g. after dividing by zero.
+    pub fn evaluate(&self, lookup: &impl Fn(&str) -> Option<f64>) -> Option<f64> {
+        let value = match self {
+            Expression::Number(value) => *value,
+            Expression::Metric(name) => lookup(name)?,
+            Expression::Negate(operand) => -operand.evaluate(lookup)?,
+            Expression::Binary(operator, left, right) => {
+                let (left, right) = (left.evaluate(lookup)?, right.evaluate(lookup)?);
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide => left / right,
+                    Operator::Power => left.powf(right),
+                }
+            }
+            Expression::Call(function, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.evaluate(lookup))
+                    .collect::<Option<Vec<_>>>()?;
+                match function {
+                    Function::Abs => arguments[0].abs(),
+                    Function::Sqrt => arguments[0].sqrt(),
+                    Function::Min => arguments[0].min(arguments[1]),
+                    Function::Max => arguments[0].max(arguments[1]),
+                }
+            }
+        };
+        value.is_finite().then_some(value)
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+        };
+        let expression = parser.sum()?;
+        match parser.next() {
+            None => Ok(expression),
+            Some(token) => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{}", value),
+            Token::Name(name) => f.write_str(name),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ExpressionError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                // Allow exponents such as 2e-6
+                let exponent_sign =
+                    matches!(c, '+' | '-') && matches!(input[..i].chars().last(), Some('e' | 'E'));
+                if !(c.is_ascii_alphanumeric() || c == '.' || exponent_sign) {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let number = &input[start..end];
+            tokens.push(Token::Number(
+                number
+                    .parse()
+                    .map_err(|_| ExpressionError::InvalidNumber(number.to_string()))?,
+            ));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || matches!(c, '_' | '.')) {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Name(input[start..end].to_string()));
+        } else if "+-*/^(),".contains(c) {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else {
+            return Err(ExpressionError::UnexpectedCharacter(c));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser, one method per precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.tokens.get(self.position) == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), ExpressionError> {
+        match self.next() {
+            Some(Token::Symbol(c)) if c == symbol => Ok(()),
+            Some(token) => Err(ExpressionError::UnexpectedToken(token.to_string())),
+            None => Err(ExpressionError::UnexpectedEnd),
+        }
+    }
+
+    /// `product (('+' | '-') product)*`
+    fn sum(&mut self) -> Result<Expression, ExpressionError> {
+        let mut expression = self.product()?;
+        loop {
+            let operator = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Subtract
+            } else {
+                return Ok(expression);
+            };
+            expression =
+                Expression::Binary(operator, Box::new(expression), Box::new(self.product()?));
+        }
+    }
+
+    /// `unary (('*' | '/') unary)*`
+    fn product(&mut self) -> Result<Expression, ExpressionError> {
+        let mut expression = self.unary()?;
+        loop {
+            let operator = if self.eat('*') {
+                Operator::Multiply
+            } else if self.eat('/') {
+                Operator::Divide
+            } else {
+                return Ok(expression);
+            };
+            expression =
+                Expression::Binary(operator, Box::new(expression), Box::new(self.unary()?));
+        }
+    }
+
+    /// `'-' unary | power`
+    fn unary(&mut self) -> Result<Expression, ExpressionError> {
+        if self.eat('-') {
+            Ok(Expression::Negate(Box::new(self.unary()?)))
+        } else {
+            self.power()
+        }
+    }
+
+    /// `atom ('^' unary)?`, right associative
+    fn power(&mut self) -> Result<Expression, ExpressionError> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            Ok(Expression::Binary(
+                Operator::Power,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    /// A number, metric, function call or parenthesised expression
+    fn atom(&mut self) -> Result<Expression, ExpressionError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expression::Number(value)),
+            Some(Token::Name(name)) if self.eat('(') => {
+                let function = Function::from_name(&name)
+                    .ok_or_else(|| ExpressionError::UnknownFunction(name.clone()))?;
+                let mut arguments = vec![self.sum()?];
+                while self.eat(',') {
+                    arguments.push(self.sum()?);
+                }
+                self.expect(')')?;
+                if arguments.len() != function.arity() {
+                    return Err(ExpressionError::WrongArgumentCount {
+                        function: name,
+                        expected: function.arity(),
+                    });
+                }
+                Ok(Expression::Call(function, arguments))
+            }
+            Some(Token::Name(name)) => Ok(Expression::Metric(name)),
+            Some(Token::Symbol('(')) => {
+                let expression = self.sum()?;
+                self.expect(')')?;
+                Ok(expression)
+            }
+            Some(token) => Err(ExpressionError::UnexpectedToken(token.to_string())),
+            None => Err(ExpressionError::UnexpectedEnd),
+        }
+    }
+}
+
+/// A metric computed from other metrics of the same sample, written
+/// `<name> = <expression>`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DerivedMetric {
+    text: String,
+    name: String,
+    expression: Expression,
+}
+
+impl DerivedMetric {
+    /// Name the computed metric is reported under
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Compute the metric from a sample's `(name, value)` pairs
+    pub fn evaluate(&self, metrics: &[(String, f64)]) -> Option<f64> {
+        self.expression.evaluate(&|name| {
+            metrics
+                .iter()
+                .find(|(metric, _)| metric == name)
+                .map(|(_, value)| *value)
+        })
+    }
+}
+
+impl FromStr for DerivedMetric {
+    type Err = ExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let (name, expression) = text
+            .split_once('=')
+            .ok_or_else(|| ExpressionError::MissingName(text.to_string()))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ExpressionError::MissingName(text.to_string()));
+        }
+
+        Ok(Self {
+            text: text.to_string(),
+            name: name.to_string(),
+            expression: expression.parse()?,
+        })
+    }
+}
+
+impl TryFrom<String> for DerivedMetric {
+    type Error = ExpressionError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<DerivedMetric> for String {
+    fn from(metric: DerivedMetric) -> Self {
+        metric.text
+    }
+}
+
+/// Errors for unparseable expressions
+#[derive(Debug, thiserror::Error)]
+pub enum ExpressionError {
+    #[error("derived metric '{0}' should be written '<name> = <expression>'")]
+    MissingName(String),
+    #[error("unexpected character '{0}' in expression")]
+    UnexpectedCharacter(char),
+    #[error("invalid number '{0}' in expression")]
+    InvalidNumber(String),
+    #[error("unexpected '{0}' in expression")]
+    UnexpectedToken(String),
+    #[error("expression ends unexpectedly")]
+    UnexpectedEnd,
+    #[error("unknown function '{0}' (expected abs, sqrt, min or max)")]
+    UnknownFunction(String),
+    #[error("function '{function}' takes {expected} argument(s)")]
+    WrongArgumentCount { function: String, expected: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(pairs: &[(&str, f64)]) -> Vec<(String, f64)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    #[test]
+    fn computes_derived_metrics_from_sample_metrics() {
+        let f1: DerivedMetric = "f1 = 2*p*r/(p+r)".parse().unwrap();
+        assert_eq!(f1.name(), "f1");
+        assert_eq!(
+            f1.evaluate(&metrics(&[("p", 0.5), ("r", 1.0)])),
+            Some(2.0 / 3.0)
+        );
+
+        let cost: DerivedMetric = "cost_usd = llm.eval.tokens * 2e-6".parse().unwrap();
+        assert_eq!(
+            cost.evaluate(&metrics(&[("llm.eval.tokens", 1000.0)])),
+            Some(0.002)
+        );
+    }
+
+    #[test]
+    fn follows_operator_precedence() {
+        let evaluate = |text: &str| text.parse::<Expression>().unwrap().evaluate(&|_| None);
+
+        assert_eq!(evaluate("1 + 2 * 3"), Some(7.0));
+        assert_eq!(evaluate("(1 + 2) * 3"), Some(9.0));
+        assert_eq!(evaluate("2 ^ 3 ^ 2"), Some(512.0));
+        assert_eq!(evaluate("-2 ^ 2"), Some(-4.0));
+        assert_eq!(evaluate("10 - 4 - 3"), Some(3.0));
+        assert_eq!(evaluate("max(1, sqrt(16)) + abs(-1)"), Some(5.0));
+    }
+
+    #[test]
+    fn missing_metrics_and_non_finite_results_have_no_value() {
+        let ratio: DerivedMetric = "ratio = a / b".parse().unwrap();
+
+        assert_eq!(ratio.evaluate(&metrics(&[("a", 1.0)])), None);
+        assert_eq!(ratio.evaluate(&metrics(&[("a", 1.0), ("b", 0.0)])), None);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(matches!(
+            "f1 2*p".parse::<DerivedMetric>(),
+            Err(ExpressionError::MissingName(_))
+        ));
+        assert!(matches!(
+            "x = (a + b".parse::<DerivedMetric>(),
+            Err(ExpressionError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            "x = a $ b".parse::<DerivedMetric>(),
+            Err(ExpressionError::UnexpectedCharacter('$'))
+        ));
+        assert!(matches!(
+            "x = log(a)".parse::<DerivedMetric>(),
+            Err(ExpressionError::UnknownFunction(_))
+        ));
+        assert!(matches!(
+            "x = min(a)".parse::<DerivedMetric>(),
+            Err(ExpressionError::WrongArgumentCount { .. })
+        ));
+    }
+}
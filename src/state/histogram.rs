@@ -0,0 +1,291 @@
+//! Buckets of histogram metrics summed over every sample, so their shape
+//! is not lost by collapsing each data point to its average
+
+use super::metrics::{HistogramBucket, HistogramValue};
+use std::time::{Duration, Instant};
+
+/// Span of each window of a histogram's history to begin with
+pub const HISTORY_WINDOW: Duration = Duration::from_secs(5);
+
+/// Most windows a histogram's history keeps; past this, neighbouring
+/// windows are merged so the whole run stays in view
+pub const MAX_HISTORY_WINDOWS: usize = 120;
+
+/// A histogram metric's buckets summed over its data points
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedHistogram {
+    buckets: Vec<HistogramBucket>,
+    count: u64,
+    min: Option<f64>,
+    max: Option<f64>,
+    /// Data points left out because their bucket bounds differ
+    mismatched: usize,
+}
+
+impl MergedHistogram {
+    /// Start from one data point's value
+    pub fn new(value: &HistogramValue) -> Self {
+        Self {
+            buckets: value.buckets.clone(),
+            count: value.count,
+            min: value.min,
+            max: value.max,
+            mismatched: 0,
+        }
+    }
+
+    /// Add a data point's value, unless its bucket bounds differ
+    pub fn merge(&mut self, value: &HistogramValue) {
+        let same_bounds = self.buckets.len() == value.buckets.len()
+            && self
+                .buckets
+                .iter()
+                .zip(&value.buckets)
+                .all(|(bucket, other)| bucket.upper_bound == other.upper_bound);
+        if !same_bounds {
+            self.mismatched += 1;
+            return;
+        }
+
+        for (bucket, other) in self.buckets.iter_mut().zip(&value.buckets) {
+            bucket.count += other.count;
+        }
+        self.count += value.count;
+        self.min = combine(self.min, value.min, f64::min);
+        self.max = combine(self.max, value.max, f64::max);
+    }
+
+    /// Buckets in order of their upper bound
+    pub fn buckets(&self) -> &[HistogramBucket] {
+        &self.buckets
+    }
+
+    /// Number of values across the data points
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Smallest value, if the evaluator reported it
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    /// Largest value, if the evaluator reported it
+    pub fn max(&self) -> Option<f64> {
+        self.max
+    }
+
+    /// Data points left out because their bucket bounds differ from the first
+    pub fn mismatched(&self) -> usize {
+        self.mismatched
+    }
+
+    /// Index of the bucket holding the value at `quantile` (0 to 1)
+    pub fn bucket_of_quantile(&self, quantile: f64) -> Option<usize> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = (quantile * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        self.buckets.iter().position(|bucket| {
+            seen += bucket.count;
+            seen >= rank
+        })
+    }
+
+    /// Value at `quantile` (0 to 1), interpolated within its bucket
+    pub fn quantile(&self, quantile: f64) -> Option<f64> {
+        let index = self.bucket_of_quantile(quantile)?;
+        let bucket = &self.buckets[index];
+        let before: u64 = self.buckets[..index].iter().map(|b| b.count).sum();
+        let lower = match index {
+            0 => self.min?,
+            _ => self.buckets[index - 1].upper_bound,
+        };
+        let upper = match bucket.upper_bound {
+            bound if bound.is_finite() => bound,
+            _ => self.max?,
+        };
+        let rank = quantile * self.count as f64 - before as f64;
+        let fraction = (rank / bucket.count as f64).clamp(0.0, 1.0);
+        let value = lower + (upper - lower) * fraction;
+        Some(value.clamp(self.min.unwrap_or(value), self.max.unwrap_or(value)))
+    }
+}
+
+/// A histogram metric's bucket counts per window of time, to show how its
+/// distribution shifts during a run
+#[derive(Debug, Clone)]
+pub struct HistogramHistory {
+    started_at: Instant,
+    window: Duration,
+    upper_bounds: Vec<f64>,
+    /// Bucket counts of each window, oldest first
+    windows: Vec<Vec<u64>>,
+}
+
+impl HistogramHistory {
+    /// Start the history now, with the bucket bounds of `value`
+    pub fn new(value: &HistogramValue) -> Self {
+        Self::started_at(Instant::now(), value)
+    }
+
+    fn started_at(started_at: Instant, value: &HistogramValue) -> Self {
+        Self {
+            started_at,
+            window: HISTORY_WINDOW,
+            upper_bounds: value.buckets.iter().map(|b| b.upper_bound).collect(),
+            windows: Vec::new(),
+        }
+    }
+
+    /// Add a data point's value reported just now, unless its bucket
+    /// bounds differ
+    pub fn record(&mut self, value: &HistogramValue) {
+        self.record_at(Instant::now(), value);
+    }
+
+    fn record_at(&mut self, at: Instant, value: &HistogramValue) {
+        let same_bounds = self.upper_bounds.len() == value.buckets.len()
+            && self
+                .upper_bounds
+                .iter()
+                .zip(&value.buckets)
+                .all(|(bound, other)| *bound == other.upper_bound);
+        if !same_bounds {
+            return;
+        }
+
+        let elapsed = at.saturating_duration_since(self.started_at);
+        let mut index = (elapsed.as_secs_f64() / self.window.as_secs_f64()) as usize;
+        while index >= MAX_HISTORY_WINDOWS {
+            self.coarsen();
+            index /= 2;
+        }
+        if self.windows.len() <= index {
+            self.windows
+                .resize(index + 1, vec![0; self.upper_bounds.len()]);
+        }
+        for (count, bucket) in self.windows[index].iter_mut().zip(&value.buckets) {
+            *count += bucket.count;
+        }
+    }
+
+    /// Double the span of each window by merging neighbouring ones
+    fn coarsen(&mut self) {
+        self.window *= 2;
+        self.windows = self
+            .windows
+            .chunks(2)
+            .map(|pair| {
+                let mut merged = pair[0].clone();
+                if let Some(next) = pair.get(1) {
+                    for (count, other) in merged.iter_mut().zip(next) {
+                        *count += other;
+                    }
+                }
+                merged
+            })
+            .collect();
+    }
+
+    /// Span of time each window covers
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Upper bounds of the buckets, in order
+    pub fn upper_bounds(&self) -> &[f64] {
+        &self.upper_bounds
+    }
+
+    /// Bucket counts of each window since the history started, oldest first
+    pub fn windows(&self) -> &[Vec<u64>] {
+        &self.windows
+    }
+
+    /// Largest count of any bucket in any window
+    pub fn max_count(&self) -> u64 {
+        self.windows.iter().flatten().copied().max().unwrap_or(0)
+    }
+}
+
+/// Combine two optional values with `pick`, keeping whichever is known
+fn combine(a: Option<f64>, b: Option<f64>, pick: fn(f64, f64) -> f64) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(pick(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(counts: [u64; 3], min: f64, max: f64) -> HistogramValue {
+        HistogramValue {
+            count: counts.iter().sum(),
+            sum: None,
+            buckets: [100.0, 200.0, f64::INFINITY]
+                .into_iter()
+                .zip(counts)
+                .map(|(upper_bound, count)| HistogramBucket { upper_bound, count })
+                .collect(),
+            min: Some(min),
+            max: Some(max),
+        }
+    }
+
+    #[test]
+    fn sums_buckets_and_finds_the_median() {
+        let mut merged = MergedHistogram::new(&histogram([2, 6, 0], 50.0, 180.0));
+        merged.merge(&histogram([0, 0, 2], 250.0, 300.0));
+        let counts: Vec<u64> = merged.buckets().iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![2, 6, 2]);
+        assert_eq!((merged.min(), merged.max()), (Some(50.0), Some(300.0)));
+
+        assert_eq!(merged.bucket_of_quantile(0.5), Some(1));
+        assert_eq!(merged.quantile(0.5), Some(150.0));
+        assert_eq!(merged.quantile(1.0), Some(300.0));
+    }
+
+    #[test]
+    fn leaves_out_data_points_with_other_bounds() {
+        let mut merged = MergedHistogram::new(&histogram([1, 1, 1], 0.0, 500.0));
+        let mut other = histogram([1, 1, 1], 0.0, 500.0);
+        other.buckets[0].upper_bound = 50.0;
+        merged.merge(&other);
+        assert_eq!(merged.count(), 3);
+        assert_eq!(merged.mismatched(), 1);
+    }
+
+    #[test]
+    fn history_counts_buckets_per_window() {
+        let start = Instant::now();
+        let mut history = HistogramHistory::started_at(start, &histogram([0, 0, 0], 0.0, 0.0));
+        history.record_at(start, &histogram([2, 1, 0], 50.0, 150.0));
+        history.record_at(
+            start + HISTORY_WINDOW * 2,
+            &histogram([0, 1, 3], 150.0, 400.0),
+        );
+        let mut other = histogram([1, 1, 1], 0.0, 500.0);
+        other.buckets[0].upper_bound = 50.0;
+        history.record_at(start, &other);
+
+        assert_eq!(
+            history.windows(),
+            &[vec![2, 1, 0], vec![0, 0, 0], vec![0, 1, 3]]
+        );
+        assert_eq!(history.max_count(), 3);
+
+        // Windows past the last are merged pairwise to make room
+        history.record_at(
+            start + HISTORY_WINDOW * MAX_HISTORY_WINDOWS as u32,
+            &histogram([1, 0, 0], 10.0, 10.0),
+        );
+        assert_eq!(history.window(), HISTORY_WINDOW * 2);
+        assert_eq!(history.windows().len(), MAX_HISTORY_WINDOWS / 2 + 1);
+        assert_eq!(history.windows()[0], vec![2, 1, 0]);
+        assert_eq!(history.windows()[1], vec![0, 1, 3]);
+    }
+}
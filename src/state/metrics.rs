@@ -88,6 +88,20 @@ pub enum AttributeValue {
     KvlistValue(HashMap<String, AttributeValue>),
 }
 
+impl AttributeValue {
+    /// Label for grouping samples by this value
+    ///
+    /// Only discrete values group; floats, arrays and maps have no label.
+    pub fn group_label(&self) -> Option<String> {
+        match self {
+            AttributeValue::StringValue(value) => Some(value.clone()),
+            AttributeValue::BoolValue(value) => Some(value.to_string()),
+            AttributeValue::IntValue(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+}
+
 /// Type-safe gauge value (can be negative)
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct GaugeValue(f64);
@@ -124,14 +138,13 @@ impl CounterValue {
     /// Legacy method for JSON parsing - validates input
     #[allow(dead_code)] // Keep for backward compatibility during transition
     pub fn try_new(value: f64) -> Result<Self, CounterValueError> {
-        let non_neg_value = NonNegativeF64::try_new(value)
-            .map_err(|_| {
-                if value < 0.0 {
-                    CounterValueError::MustBeNonNegative
-                } else {
-                    CounterValueError::NotFinite
-                }
-            })?;
+        let non_neg_value = NonNegativeF64::try_new(value).map_err(|_| {
+            if value < 0.0 {
+                CounterValueError::MustBeNonNegative
+            } else {
+                CounterValueError::NotFinite
+            }
+        })?;
 
         Ok(CounterValue(non_neg_value))
     }
@@ -177,6 +190,9 @@ pub struct HistogramValue {
 /// A single data point with timestamp and attributes
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DataPoint<V> {
+    /// Start of the interval the value covers, when the evaluator reports one
+    #[serde(default)]
+    pub start_timestamp: Option<TimeUnixNano>,
     pub timestamp: TimeUnixNano,
     pub value: V,
     pub attributes: HashMap<AttributeKey, AttributeValue>,
@@ -241,22 +257,6 @@ pub enum Metric {
 }
 
 impl Metric {
-    /// Get the metric name regardless of type
-    pub fn name(&self) -> &MetricName {
-        match self {
-            Metric::Sample(sample_metric) => match sample_metric {
-                SampleMetric::Gauge { name, .. } => name,
-                SampleMetric::Counter { name, .. } => name,
-                SampleMetric::Histogram { name, .. } => name,
-            },
-            Metric::Summary(summary_metric) => match summary_metric {
-                SummaryMetric::Gauge { name, .. } => name,
-                SummaryMetric::Counter { name, .. } => name,
-                SummaryMetric::Histogram { name, .. } => name,
-            },
-        }
-    }
-
     /// Check if this metric counts toward progress (is a sample metric)
     pub fn counts_toward_progress(&self) -> bool {
         matches!(self, Metric::Sample(_))
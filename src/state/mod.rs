@@ -1,7 +1,24 @@
 // State management module for application state
 
+pub mod aggregation;
+pub mod anomaly;
 pub mod app;
+pub mod baseline;
+pub mod cost;
+pub mod expression;
+pub mod histogram;
 pub mod metrics;
+pub mod perf;
+pub mod publisher;
+pub mod rules;
+pub mod run;
+pub mod sample_store;
+pub mod scatter;
+pub mod script;
+pub mod significance;
+pub mod throughput;
+pub mod timeline;
+pub mod trends;
 pub mod types;
 
-pub use app::AppState;
+pub use run::RunState;
@@ -0,0 +1,253 @@
+//! How fast PrEval itself draws and parses, for the hidden debug view
+//!
+//! The renderer and the parse worker run on tasks of their own, so they
+//! record into a shared [`PerfRecorder`]; the main loop copies a
+//! [`PerfStats`] out of it into the state once a second. Allocations are
+//! only counted when the `alloc-stats` feature installs
+//! [`CountingAllocator`] as the global allocator, as counting slows every
+//! allocation down.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Parse times kept for the percentiles
+const PARSE_WINDOW: usize = 1000;
+
+/// How full a channel between PrEval's tasks is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelDepth {
+    pub depth: usize,
+    pub capacity: usize,
+}
+
+/// Point-in-time view of PrEval's own performance
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PerfStats {
+    /// Frames drawn so far
+    pub frames: u64,
+    pub last_frame_time: Duration,
+    pub max_frame_time: Duration,
+    /// Metrics lines parsed so far
+    pub lines_parsed: u64,
+    /// Median, 90th and 99th percentile of recent parse times, once
+    /// anything was parsed
+    pub parse_percentiles: Option<[Duration; 3]>,
+    /// Parsed messages waiting for the main loop
+    pub parsed_queue: ChannelDepth,
+    /// Key presses and other UI actions waiting for the main loop
+    pub action_queue: ChannelDepth,
+    /// Resident memory of PrEval, once sampled
+    pub memory_bytes: Option<u64>,
+    pub peak_memory_bytes: u64,
+    /// Heap allocations, when they are counted
+    pub allocations: Option<AllocationStats>,
+}
+
+/// Heap allocations made by PrEval so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationStats {
+    pub allocations: u64,
+    pub deallocations: u64,
+    /// Bytes allocated and not yet freed
+    pub live_bytes: u64,
+}
+
+impl AllocationStats {
+    /// Counts of the global allocator, if it is a [`CountingAllocator`]
+    pub fn current() -> Option<Self> {
+        cfg!(feature = "alloc-stats").then(Self::counted)
+    }
+
+    /// Counts of every [`CountingAllocator`]
+    fn counted() -> Self {
+        Self {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+            live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Allocations not yet freed
+    pub fn live(&self) -> u64 {
+        self.allocations.saturating_sub(self.deallocations)
+    }
+}
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static DEALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static LIVE_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// System allocator that counts allocations for [`AllocationStats`]
+///
+/// The `preval` binary installs it as the global allocator when built with
+/// the `alloc-stats` feature.
+pub struct CountingAllocator;
+
+// SAFETY: every call is passed on to the system allocator unchanged
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            LIVE_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            LIVE_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            // A block grown or shrunk in place is still the same allocation;
+            // a moved one was freed and allocated anew
+            if new != ptr {
+                ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+                DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            }
+            LIVE_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+            LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        }
+        new
+    }
+}
+
+/// Performance counters shared by PrEval's tasks
+#[derive(Debug, Clone, Default)]
+pub struct PerfRecorder {
+    inner: Arc<Mutex<Counters>>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    stats: PerfStats,
+    /// Most recent parse times, oldest first
+    parse_times: VecDeque<Duration>,
+}
+
+impl PerfRecorder {
+    /// Record a frame that took `time` to draw
+    pub fn record_frame(&self, time: Duration) {
+        if let Ok(mut counters) = self.inner.lock() {
+            let stats = &mut counters.stats;
+            stats.frames += 1;
+            stats.last_frame_time = time;
+            stats.max_frame_time = stats.max_frame_time.max(time);
+        }
+    }
+
+    /// Record a metrics line that took `time` to parse
+    pub fn record_parse(&self, time: Duration) {
+        if let Ok(mut counters) = self.inner.lock() {
+            counters.stats.lines_parsed += 1;
+            if counters.parse_times.len() == PARSE_WINDOW {
+                counters.parse_times.pop_front();
+            }
+            counters.parse_times.push_back(time);
+        }
+    }
+
+    /// Record the resident memory of PrEval
+    pub fn record_memory(&self, bytes: u64) {
+        if let Ok(mut counters) = self.inner.lock() {
+            counters.stats.memory_bytes = Some(bytes);
+            counters.stats.peak_memory_bytes = counters.stats.peak_memory_bytes.max(bytes);
+        }
+    }
+
+    /// Everything recorded so far; channel depths are left for the caller,
+    /// who owns the channels
+    pub fn stats(&self) -> PerfStats {
+        let Ok(counters) = self.inner.lock() else {
+            return PerfStats::default();
+        };
+        let mut sorted: Vec<Duration> = counters.parse_times.iter().copied().collect();
+        sorted.sort_unstable();
+        let percentile = |p: usize| sorted[(sorted.len() - 1) * p / 100];
+        PerfStats {
+            parse_percentiles: (!sorted.is_empty())
+                .then(|| [percentile(50), percentile(90), percentile(99)]),
+            allocations: AllocationStats::current(),
+            ..counters.stats.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_cover_recent_parses() {
+        let recorder = PerfRecorder::default();
+        assert_eq!(recorder.stats().parse_percentiles, None);
+
+        for micros in 1..=PARSE_WINDOW as u64 + 100 {
+            recorder.record_parse(Duration::from_micros(micros));
+        }
+
+        let stats = recorder.stats();
+        assert_eq!(stats.lines_parsed, PARSE_WINDOW as u64 + 100);
+        assert_eq!(
+            stats.parse_percentiles,
+            Some([
+                Duration::from_micros(600),
+                Duration::from_micros(1000),
+                Duration::from_micros(1090),
+            ])
+        );
+    }
+
+    #[test]
+    fn frames_and_memory_keep_their_peaks() {
+        let recorder = PerfRecorder::default();
+        recorder.record_frame(Duration::from_millis(8));
+        recorder.record_frame(Duration::from_millis(2));
+        recorder.record_memory(300);
+        recorder.record_memory(200);
+
+        let stats = recorder.stats();
+        assert_eq!(stats.frames, 2);
+        assert_eq!(stats.last_frame_time, Duration::from_millis(2));
+        assert_eq!(stats.max_frame_time, Duration::from_millis(8));
+        assert_eq!(stats.memory_bytes, Some(200));
+        assert_eq!(stats.peak_memory_bytes, 300);
+    }
+
+    #[test]
+    fn the_allocator_counts_what_it_hands_out() {
+        // The test binary keeps the system allocator, so only these calls
+        // reach the counters
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let before = AllocationStats::counted();
+        let moved = unsafe {
+            let ptr = CountingAllocator.alloc(layout);
+            let grown = CountingAllocator.realloc(ptr, layout, 128);
+            CountingAllocator.dealloc(grown, Layout::from_size_align(128, 8).unwrap());
+            grown != ptr
+        };
+
+        // Growing in place is not another allocation
+        let after = AllocationStats::counted();
+        let expected = 1 + u64::from(moved);
+        assert_eq!(after.allocations - before.allocations, expected);
+        assert_eq!(after.deallocations - before.deallocations, expected);
+        assert_eq!(after.live_bytes, before.live_bytes);
+    }
+}
@@ -0,0 +1,89 @@
+//! Snapshots of the app state published over a watch channel, so the
+//! renderer and other views draw at their own pace instead of after every
+//! evaluator message
+
+use super::RunState;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Immutable copy of the state at one point in time
+pub type StateSnapshot = Arc<RunState>;
+
+/// Publishes state snapshots to any number of subscribers
+///
+/// Publishing clones the state, so snapshots are published at most once per
+/// `min_interval`; a change within the interval is held back until the next
+/// call after it has passed.
+#[derive(Debug)]
+pub struct StatePublisher {
+    sender: watch::Sender<StateSnapshot>,
+    min_interval: Duration,
+    last_published: Instant,
+    /// Whether the state changed since the last snapshot
+    pending: bool,
+}
+
+impl StatePublisher {
+    /// Start publishing with `state` as the first snapshot
+    pub fn new(state: &RunState, min_interval: Duration) -> Self {
+        let (sender, _) = watch::channel(Arc::new(state.clone()));
+        Self {
+            sender,
+            min_interval,
+            last_published: Instant::now(),
+            pending: false,
+        }
+    }
+
+    /// Receive the latest snapshot and be woken when a newer one is published
+    pub fn subscribe(&self) -> watch::Receiver<StateSnapshot> {
+        self.sender.subscribe()
+    }
+
+    /// Publish `state` unless a snapshot went out too recently
+    pub fn publish(&mut self, state: &RunState) {
+        self.pending = true;
+        if self.last_published.elapsed() >= self.min_interval {
+            self.flush(state);
+        }
+    }
+
+    /// Publish `state` now, e.g. before the publisher goes away
+    pub fn flush(&mut self, state: &RunState) {
+        self.sender.send_replace(Arc::new(state.clone()));
+        self.last_published = Instant::now();
+        self.pending = false;
+    }
+
+    /// When a held-back change can be published, if there is one
+    pub fn next_publish(&self) -> Option<Instant> {
+        self.pending
+            .then(|| self.last_published + self.min_interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_back_changes_within_the_interval() {
+        let mut state = RunState::default();
+        let mut publisher = StatePublisher::new(&state, Duration::from_secs(3600));
+        let mut snapshots = publisher.subscribe();
+        assert!(!snapshots.borrow_and_update().is_stderr_visible());
+        assert_eq!(publisher.next_publish(), None);
+
+        state.toggle_stderr();
+        publisher.publish(&state);
+        assert!(!snapshots.has_changed().unwrap());
+        assert!(publisher.next_publish().is_some());
+
+        publisher.flush(&state);
+        assert!(snapshots.has_changed().unwrap());
+        assert!(snapshots.borrow_and_update().is_stderr_visible());
+        assert_eq!(publisher.next_publish(), None);
+    }
+}
@@ -0,0 +1,412 @@
+//! Threshold rules that fail samples and runs, e.g. `llm.eval.accuracy >= 0.8`
+
+use super::aggregation::{MetricAggregator, MetricStats};
+use super::types::SampleResult;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How a value is compared against a rule's threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    /// Operators in the order they are searched for, longest first
+    const OPERATORS: [(&'static str, Comparison); 6] = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
+
+    /// Whether `value` passes against `threshold`
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Equal => value == threshold,
+            Comparison::NotEqual => value != threshold,
+        }
+    }
+}
+
+/// Statistic an aggregate rule checks instead of individual samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Mean,
+    Median,
+    P90,
+    P95,
+    P99,
+    Min,
+    Max,
+    StdDev,
+}
+
+impl Aggregate {
+    /// Look up an aggregate by the suffix used in rules, e.g. `p95`
+    fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix {
+            "mean" | "avg" => Some(Self::Mean),
+            "median" | "p50" => Some(Self::Median),
+            "p90" => Some(Self::P90),
+            "p95" => Some(Self::P95),
+            "p99" => Some(Self::P99),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            "stddev" => Some(Self::StdDev),
+            _ => None,
+        }
+    }
+
+    /// This statistic of `stats`
+    fn of(self, stats: &MetricStats) -> Option<f64> {
+        match self {
+            Aggregate::Mean => stats.mean(),
+            Aggregate::Median => stats.median(),
+            Aggregate::P90 => stats.p90(),
+            Aggregate::P95 => stats.p95(),
+            Aggregate::P99 => stats.p99(),
+            Aggregate::Min => stats.min(),
+            Aggregate::Max => stats.max(),
+            Aggregate::StdDev => stats.stddev(),
+        }
+    }
+}
+
+/// A threshold a metric must meet, written `<metric>[.<aggregate>] <op> <value>[unit]`
+///
+/// Without an aggregate suffix every sample is checked and failing samples
+/// are marked failed. With one (`mean`, `median`/`p50`, `p90`, `p95`,
+/// `p99`, `min`, `max`, `stddev`) the statistic over all samples is checked
+/// when the run completes. A `%` unit divides the value by 100. The time
+/// units `ms`, `s` and `m` give the value in milliseconds, the unit PrEval
+/// reports durations in, so `2s` is compared as 2000. Any other unit is an
+/// error.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThresholdRule {
+    text: String,
+    metric: String,
+    aggregate: Option<Aggregate>,
+    comparison: Comparison,
+    threshold: f64,
+}
+
+impl ThresholdRule {
+    /// Whether the rule checks a statistic over all samples
+    pub fn is_aggregate(&self) -> bool {
+        self.aggregate.is_some()
+    }
+
+    /// Describe how `sample` breaks the rule, if it does
+    pub fn check_sample(&self, sample: &SampleResult) -> Option<String> {
+        if self.is_aggregate() {
+            return None;
+        }
+        sample
+            .metrics
+            .iter()
+            .filter(|(name, _)| *name == self.metric)
+            .find(|(_, value)| !self.comparison.holds(*value, self.threshold))
+            .map(|(_, value)| format!("{} = {} violates {}", self.metric, value, self))
+    }
+
+    /// Whether `value` passes the rule, if the rule covers it: a sample's
+    /// value of the metric for a per-sample rule, or the statistic of the
+    /// metric it names for an aggregate rule
+    fn judge(&self, metric: &str, aggregate: Option<Aggregate>, value: f64) -> Option<bool> {
+        (self.metric == metric && self.aggregate == aggregate)
+            .then(|| self.comparison.holds(value, self.threshold))
+    }
+
+    /// Describe how the run's statistics break the rule, if they do
+    ///
+    /// A metric no sample reported is not checked.
+    pub fn check_stats(&self, stats: &MetricAggregator) -> Option<String> {
+        let value = self.aggregate?.of(stats.get(&self.metric)?)?;
+        (!self.comparison.holds(value, self.threshold))
+            .then(|| format!("{} was {:.3}", self, value))
+    }
+}
+
+impl FromStr for ThresholdRule {
+    type Err = ThresholdRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let (index, operator, comparison) = Comparison::OPERATORS
+            .iter()
+            .filter_map(|(operator, comparison)| {
+                text.find(operator)
+                    .map(|index| (index, *operator, *comparison))
+            })
+            .min_by_key(|(index, operator, _)| (*index, usize::MAX - operator.len()))
+            .ok_or_else(|| ThresholdRuleError::MissingComparison(text.to_string()))?;
+
+        let target = text[..index].trim();
+        if target.is_empty() {
+            return Err(ThresholdRuleError::MissingMetric(text.to_string()));
+        }
+        let (metric, aggregate) = match target.rsplit_once('.') {
+            Some((metric, suffix)) => match Aggregate::from_suffix(suffix) {
+                Some(aggregate) => (metric, Some(aggregate)),
+                None => (target, None),
+            },
+            None => (target, None),
+        };
+
+        let value = text[index + operator.len()..].trim();
+        let unit_start = value
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')))
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(unit_start);
+        let mut threshold: f64 = number
+            .parse()
+            .map_err(|_| ThresholdRuleError::InvalidThreshold(text.to_string()))?;
+        threshold *= match unit.trim() {
+            "" | "ms" => 1.0,
+            "%" => 0.01,
+            "s" => 1000.0,
+            "m" => 60_000.0,
+            unit => {
+                return Err(ThresholdRuleError::UnknownUnit(
+                    text.to_string(),
+                    unit.to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            text: text.to_string(),
+            metric: metric.to_string(),
+            aggregate,
+            comparison,
+            threshold,
+        })
+    }
+}
+
+impl TryFrom<String> for ThresholdRule {
+    type Error = ThresholdRuleError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<ThresholdRule> for String {
+    fn from(rule: ThresholdRule) -> Self {
+        rule.text
+    }
+}
+
+impl fmt::Display for ThresholdRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// How a metric value fares against the threshold rules covering it,
+/// from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Rules that fail samples and runs together with softer ones that only
+/// warn, for judging the values shown
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet<'a> {
+    failing: Vec<&'a ThresholdRule>,
+    warning: Vec<&'a ThresholdRule>,
+}
+
+impl<'a> RuleSet<'a> {
+    /// Judge by the `failing` rules, and by the `warning` ones short of failing
+    pub fn new(
+        failing: impl IntoIterator<Item = &'a ThresholdRule>,
+        warning: impl IntoIterator<Item = &'a ThresholdRule>,
+    ) -> Self {
+        Self {
+            failing: failing.into_iter().collect(),
+            warning: warning.into_iter().collect(),
+        }
+    }
+
+    /// Verdict on `value` of `metric` in one sample; None if no rule covers it
+    pub fn judge_sample(&self, metric: &str, value: f64) -> Option<Verdict> {
+        self.judge(metric, None, value)
+    }
+
+    /// Verdict on the `aggregate` statistic of `metric`
+    ///
+    /// The minimum and maximum are extreme samples, so per-sample rules
+    /// judge them too.
+    pub fn judge_stat(&self, metric: &str, aggregate: Aggregate, value: f64) -> Option<Verdict> {
+        let verdict = self.judge(metric, Some(aggregate), value);
+        match aggregate {
+            Aggregate::Min | Aggregate::Max => verdict.max(self.judge_sample(metric, value)),
+            _ => verdict,
+        }
+    }
+
+    /// Worst verdict of the rules covering `value`
+    fn judge(&self, metric: &str, aggregate: Option<Aggregate>, value: f64) -> Option<Verdict> {
+        let worst = |rules: &[&ThresholdRule], broken| {
+            rules
+                .iter()
+                .filter_map(|rule| rule.judge(metric, aggregate, value))
+                .map(|holds| if holds { Verdict::Pass } else { broken })
+                .max()
+        };
+        worst(&self.failing, Verdict::Fail).max(worst(&self.warning, Verdict::Warn))
+    }
+}
+
+/// Errors for unparseable threshold rules
+#[derive(Debug, thiserror::Error)]
+pub enum ThresholdRuleError {
+    #[error("threshold rule '{0}' has no comparison (expected <, <=, >, >=, == or !=)")]
+    MissingComparison(String),
+    #[error("threshold rule '{0}' names no metric")]
+    MissingMetric(String),
+    #[error("threshold rule '{0}' has an invalid threshold value")]
+    InvalidThreshold(String),
+    #[error("threshold rule '{0}' has unknown unit '{1}' (expected %, ms, s or m)")]
+    UnknownUnit(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_with(name: &str, value: f64) -> SampleResult {
+        let mut sample = SampleResult::new_processing("sample-1".to_string());
+        sample.mark_completed(vec![(name.to_string(), value)]);
+        sample
+    }
+
+    #[test]
+    fn parses_sample_and_aggregate_rules() {
+        let rule: ThresholdRule = "llm.eval.accuracy >= 0.8".parse().unwrap();
+        assert_eq!(rule.metric, "llm.eval.accuracy");
+        assert_eq!(rule.aggregate, None);
+        assert_eq!(rule.comparison, Comparison::GreaterOrEqual);
+        assert_eq!(rule.threshold, 0.8);
+
+        let rule: ThresholdRule = "llm.eval.latency.p95 < 2000ms".parse().unwrap();
+        assert_eq!(rule.metric, "llm.eval.latency");
+        assert_eq!(rule.aggregate, Some(Aggregate::P95));
+        assert_eq!(rule.comparison, Comparison::Less);
+        assert_eq!(rule.threshold, 2000.0);
+
+        let rule: ThresholdRule = "accuracy.mean>=80%".parse().unwrap();
+        assert_eq!(rule.threshold, 0.8);
+    }
+
+    #[test]
+    fn time_units_convert_to_milliseconds() {
+        let threshold = |text: &str| text.parse::<ThresholdRule>().unwrap().threshold;
+
+        assert_eq!(threshold("latency.p95 < 250ms"), 250.0);
+        assert_eq!(threshold("latency.p95 < 2s"), 2000.0);
+        assert_eq!(threshold("latency.p95 < 1.5 m"), 90_000.0);
+        assert!(matches!(
+            "latency < 2h".parse::<ThresholdRule>(),
+            Err(ThresholdRuleError::UnknownUnit(_, unit)) if unit == "h"
+        ));
+        assert!(matches!(
+            "tokens < 500tokens".parse::<ThresholdRule>(),
+            Err(ThresholdRuleError::UnknownUnit(_, _))
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        assert!(matches!(
+            "accuracy 0.8".parse::<ThresholdRule>(),
+            Err(ThresholdRuleError::MissingComparison(_))
+        ));
+        assert!(matches!(
+            ">= 0.8".parse::<ThresholdRule>(),
+            Err(ThresholdRuleError::MissingMetric(_))
+        ));
+        assert!(matches!(
+            "accuracy >= high".parse::<ThresholdRule>(),
+            Err(ThresholdRuleError::InvalidThreshold(_))
+        ));
+    }
+
+    #[test]
+    fn sample_rules_report_failing_values() {
+        let rule: ThresholdRule = "accuracy >= 0.8".parse().unwrap();
+
+        assert_eq!(rule.check_sample(&sample_with("accuracy", 0.9)), None);
+        assert_eq!(rule.check_sample(&sample_with("latency", 0.1)), None);
+        assert_eq!(
+            rule.check_sample(&sample_with("accuracy", 0.5)),
+            Some("accuracy = 0.5 violates accuracy >= 0.8".to_string())
+        );
+    }
+
+    #[test]
+    fn rule_sets_judge_values_by_the_worst_rule() {
+        let failing: Vec<ThresholdRule> = vec![
+            "accuracy >= 0.5".parse().unwrap(),
+            "accuracy.mean >= 0.7".parse().unwrap(),
+        ];
+        let warning: Vec<ThresholdRule> = vec!["accuracy >= 0.8".parse().unwrap()];
+        let rules = RuleSet::new(&failing, &warning);
+
+        assert_eq!(rules.judge_sample("accuracy", 0.9), Some(Verdict::Pass));
+        assert_eq!(rules.judge_sample("accuracy", 0.6), Some(Verdict::Warn));
+        assert_eq!(rules.judge_sample("accuracy", 0.4), Some(Verdict::Fail));
+        assert_eq!(rules.judge_sample("latency", 0.4), None);
+
+        assert_eq!(
+            rules.judge_stat("accuracy", Aggregate::Mean, 0.75),
+            Some(Verdict::Pass)
+        );
+        assert_eq!(
+            rules.judge_stat("accuracy", Aggregate::Mean, 0.6),
+            Some(Verdict::Fail)
+        );
+        // The lowest sample broke the per-sample rule
+        assert_eq!(
+            rules.judge_stat("accuracy", Aggregate::Min, 0.4),
+            Some(Verdict::Fail)
+        );
+        assert_eq!(rules.judge_stat("accuracy", Aggregate::P95, 0.4), None);
+    }
+
+    #[test]
+    fn aggregate_rules_check_run_statistics() {
+        let rule: ThresholdRule = "latency.max < 200".parse().unwrap();
+        let mut stats = MetricAggregator::new();
+        assert_eq!(rule.check_stats(&stats), None);
+
+        stats.record("latency", 100.0);
+        assert_eq!(rule.check_stats(&stats), None);
+        assert_eq!(rule.check_sample(&sample_with("latency", 500.0)), None);
+
+        stats.record("latency", 250.0);
+        assert_eq!(
+            rule.check_stats(&stats),
+            Some("latency.max < 200 was 250.000".to_string())
+        );
+    }
+}
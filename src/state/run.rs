@@ -0,0 +1,212 @@
+//! Owned state machine over the phases of [`AppState`](super::app::AppState),
+//! for code such as the app loop that only learns at runtime which transition
+//! comes next
+
+use super::app::{
+    AppData, AppStateAborted, AppStateCollecting, AppStateFinished, AppStateWithEvaluator,
+    InitialAppState, StateError,
+};
+use super::metrics::MetricData;
+use super::types::{EvaluationStatus, EvaluatorName};
+use crate::evaluator::protocol::ValidatedHandshake;
+use std::ops::{Deref, DerefMut};
+
+/// State handed back by a transition it is not valid for, with the reason
+type Rejected = Box<(RunState, StateError)>;
+
+/// The app state in whichever phase the run is in
+///
+/// Each transition is the typed one of [`AppState`](super::app::AppState) for the
+/// current phase, and fails with a [`StateError`] in any other phase.
+#[derive(Debug, Clone)]
+pub enum RunState {
+    /// The evaluator has not been started
+    Starting(InitialAppState),
+    /// The evaluator was started and its handshake is awaited
+    WaitingForHandshake(AppStateWithEvaluator),
+    /// The evaluator is reporting metrics
+    Collecting(AppStateCollecting),
+    /// The run completed or failed after the handshake
+    Finished(AppStateFinished),
+    /// The run failed before the evaluator sent a handshake
+    Aborted(AppStateAborted),
+}
+
+impl RunState {
+    /// Start the evaluator under `name`
+    pub fn set_evaluator_name(&mut self, name: EvaluatorName) -> Result<(), StateError> {
+        self.transition(|state| match state {
+            RunState::Starting(state) => Ok(RunState::WaitingForHandshake(
+                state.set_evaluator_name(name),
+            )),
+            other => Err(other.invalid_transition(EvaluationStatus::WaitingForHandshake)),
+        })
+    }
+
+    /// Accept the evaluator's handshake and start collecting its metrics
+    pub fn set_handshake(&mut self, handshake: ValidatedHandshake) -> Result<(), StateError> {
+        self.transition(|state| match state {
+            RunState::WaitingForHandshake(state) => Ok(RunState::Collecting(
+                state.set_handshake(handshake).start_collecting(),
+            )),
+            other => Err(
+                other.invalid_transition(EvaluationStatus::CollectingMetrics {
+                    received: 0,
+                    total: None,
+                }),
+            ),
+        })
+    }
+
+    /// Add metrics reported by the evaluator
+    ///
+    /// The phase stays the same, so this happens in place.
+    pub fn add_metrics(&mut self, metrics: MetricData) -> Result<(), StateError> {
+        match self {
+            RunState::Collecting(state) => {
+                state.receive_metrics(metrics);
+                Ok(())
+            }
+            _ => Err(StateError::NotCollectingMetrics),
+        }
+    }
+
+    /// End the run with `status`
+    ///
+    /// Before the handshake a run can only fail.
+    pub fn finish(&mut self, status: EvaluationStatus) -> Result<(), StateError> {
+        self.transition(|state| match (state, status) {
+            (RunState::Collecting(state), status) => Ok(RunState::Finished(state.finish(status))),
+            (RunState::WaitingForHandshake(state), EvaluationStatus::Failed(reason)) => {
+                Ok(RunState::Aborted(state.fail(reason)))
+            }
+            (state @ (RunState::Finished(_) | RunState::Aborted(_)), _) => {
+                Err(Box::new((state, StateError::TerminalState)))
+            }
+            (other, status) => Err(other.invalid_transition(status)),
+        })
+    }
+
+    /// Apply `transition` to the owned state, keeping it unchanged on error
+    fn transition(
+        &mut self,
+        transition: impl FnOnce(Self) -> Result<Self, Rejected>,
+    ) -> Result<(), StateError> {
+        let state = std::mem::take(self);
+        match transition(state) {
+            Ok(next) => {
+                *self = next;
+                Ok(())
+            }
+            Err(rejected) => {
+                let (state, error) = *rejected;
+                *self = state;
+                Err(error)
+            }
+        }
+    }
+
+    /// Error for moving to `status` from the current phase, with the state
+    fn invalid_transition(self, status: EvaluationStatus) -> Rejected {
+        let error = StateError::InvalidTransition {
+            from: self.status().phase(),
+            to: status.phase(),
+        };
+        Box::new((self, error))
+    }
+}
+
+impl Default for RunState {
+    fn default() -> Self {
+        RunState::Starting(InitialAppState::new())
+    }
+}
+
+impl From<InitialAppState> for RunState {
+    fn from(state: InitialAppState) -> Self {
+        RunState::Starting(state)
+    }
+}
+
+impl Deref for RunState {
+    type Target = AppData;
+
+    fn deref(&self) -> &AppData {
+        match self {
+            RunState::Starting(state) => state,
+            RunState::WaitingForHandshake(state) => state,
+            RunState::Collecting(state) => state,
+            RunState::Finished(state) => state,
+            RunState::Aborted(state) => state,
+        }
+    }
+}
+
+impl DerefMut for RunState {
+    fn deref_mut(&mut self) -> &mut AppData {
+        match self {
+            RunState::Starting(state) => state,
+            RunState::WaitingForHandshake(state) => state,
+            RunState::Collecting(state) => state,
+            RunState::Finished(state) => state,
+            RunState::Aborted(state) => state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::handshake::parse_handshake;
+
+    fn handshake() -> ValidatedHandshake {
+        parse_handshake(
+            r#"{"type": "handshake", "mode": "test_suite", "version": "1.0",
+                "evaluator": {"name": "test-evaluator"}, "metrics_schema": []}"#,
+        )
+        .unwrap()
+    }
+
+    fn evaluator_name() -> EvaluatorName {
+        EvaluatorName::try_new("test-evaluator").unwrap()
+    }
+
+    #[test]
+    fn follows_a_run_from_start_to_finish() {
+        let mut state = RunState::default();
+        state.set_evaluator_name(evaluator_name()).unwrap();
+        assert_eq!(state.status(), &EvaluationStatus::WaitingForHandshake);
+
+        state.set_handshake(handshake()).unwrap();
+        assert!(matches!(state, RunState::Collecting(_)));
+
+        state.finish(EvaluationStatus::Completed).unwrap();
+        assert!(matches!(state, RunState::Finished(_)));
+        assert!(matches!(
+            state.finish(EvaluationStatus::Completed),
+            Err(StateError::TerminalState)
+        ));
+    }
+
+    #[test]
+    fn rejected_transitions_keep_the_state() {
+        let mut state = RunState::default();
+        state.toggle_pause();
+
+        assert!(matches!(
+            state.set_handshake(handshake()),
+            Err(StateError::InvalidTransition { .. })
+        ));
+        assert!(matches!(state, RunState::Starting(_)));
+        assert!(state.is_paused());
+
+        // Before the handshake a run can fail but not complete
+        state.set_evaluator_name(evaluator_name()).unwrap();
+        assert!(state.finish(EvaluationStatus::Completed).is_err());
+        state
+            .finish(EvaluationStatus::Failed("no handshake".to_string()))
+            .unwrap();
+        assert!(matches!(state, RunState::Aborted(_)));
+        assert!(state.is_terminal());
+    }
+}
@@ -0,0 +1,746 @@
+//! Every sample of a run, in bounded memory
+
+use super::app::SAMPLE_DURATION_METRIC;
+use super::types::{SampleAnnotation, SampleResult, SampleStatus};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering as AtomicOrdering};
+
+/// Samples kept in memory unless configured otherwise
+pub const DEFAULT_SAMPLE_CAPACITY: usize = 1000;
+
+/// Counts temporary spill files made by this PrEval, so each gets its own
+static TEMPORARY_SPILLS: AtomicU32 = AtomicU32::new(0);
+
+/// How metrics reported again for a sample id are combined with its result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicatePolicy {
+    /// Add the new metrics to the sample, for evaluators that report a
+    /// sample over several lines; a metric reported again takes its new
+    /// value
+    Merge,
+    /// Discard the earlier result
+    Replace,
+    /// Treat the report as another run of the sample and average its metrics
+    #[serde(alias = "count-as-rerun")]
+    Rerun,
+}
+
+impl DuplicatePolicy {
+    /// Result of a sample reported again, given its `previous` result
+    ///
+    /// The combined result keeps the status of `latest`, and the
+    /// annotation of `previous`.
+    pub fn combine(self, previous: &SampleResult, latest: SampleResult) -> SampleResult {
+        let mut combined = match self {
+            DuplicatePolicy::Replace => latest,
+            DuplicatePolicy::Merge => {
+                let mut merged = latest;
+                let mut metrics = previous.metrics.clone();
+                for (name, value) in merged.metrics.drain(..) {
+                    match metrics.iter_mut().find(|(metric, _)| *metric == name) {
+                        Some((_, previous)) => *previous = value,
+                        None => metrics.push((name, value)),
+                    }
+                }
+                merged.metrics = metrics;
+                merged.duration = merged.duration.max(previous.duration);
+                merged.runs = previous.runs;
+                merged.anomalies = union(&previous.anomalies, merged.anomalies);
+                merged.texts = keep_texts(previous, merged.texts);
+                merged
+            }
+            DuplicatePolicy::Rerun => {
+                let runs = previous.runs + 1;
+                let mut averaged = latest;
+                let mut metrics = previous.metrics.clone();
+                for (name, value) in averaged.metrics.drain(..) {
+                    match metrics.iter_mut().find(|(metric, _)| *metric == name) {
+                        Some((_, mean)) => *mean += (value - *mean) / runs as f64,
+                        None => metrics.push((name, value)),
+                    }
+                }
+                averaged.metrics = metrics;
+                averaged.duration = match (previous.duration, averaged.duration) {
+                    (Some(mean), Some(duration)) => Some((mean * previous.runs + duration) / runs),
+                    (mean, duration) => duration.or(mean),
+                };
+                averaged.runs = runs;
+                averaged.anomalies = union(&previous.anomalies, averaged.anomalies);
+                averaged.texts = keep_texts(previous, averaged.texts);
+                averaged
+            }
+        };
+        combined.annotation = previous.annotation.clone();
+        let mut groups = previous.groups.clone();
+        groups.append(&mut combined.groups);
+        combined.groups = groups;
+        combined
+    }
+}
+
+/// Names in `previous` followed by those of `latest` not already there
+fn union(previous: &[String], latest: Vec<String>) -> Vec<String> {
+    let mut names = previous.to_vec();
+    for name in latest {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Texts of `previous`, updated with those reported again
+fn keep_texts(
+    previous: &SampleResult,
+    latest: BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut texts = previous.texts.clone();
+    texts.extend(latest);
+    texts
+}
+
+impl std::str::FromStr for DuplicatePolicy {
+    type Err = DuplicatePolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "merge" => Ok(Self::Merge),
+            "replace" => Ok(Self::Replace),
+            "rerun" | "count-as-rerun" => Ok(Self::Rerun),
+            other => Err(DuplicatePolicyError(other.to_string())),
+        }
+    }
+}
+
+/// Error for unrecognized duplicate policy names
+#[derive(Debug, thiserror::Error)]
+#[error("unknown duplicate policy '{0}' (expected 'merge', 'replace' or 'rerun')")]
+pub struct DuplicatePolicyError(String);
+
+/// Statuses a sample query keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatusFilter {
+    #[default]
+    All,
+    Completed,
+    Failed,
+    Processing,
+}
+
+impl StatusFilter {
+    /// The filter after this one, cycling back to all samples
+    pub fn next(self) -> Self {
+        match self {
+            StatusFilter::All => StatusFilter::Completed,
+            StatusFilter::Completed => StatusFilter::Failed,
+            StatusFilter::Failed => StatusFilter::Processing,
+            StatusFilter::Processing => StatusFilter::All,
+        }
+    }
+
+    /// Name of the filter for display
+    pub fn label(self) -> &'static str {
+        match self {
+            StatusFilter::All => "all",
+            StatusFilter::Completed => "completed",
+            StatusFilter::Failed => "failed",
+            StatusFilter::Processing => "processing",
+        }
+    }
+
+    fn matches(self, status: &SampleStatus) -> bool {
+        match self {
+            StatusFilter::All => true,
+            StatusFilter::Completed => matches!(status, SampleStatus::Completed),
+            StatusFilter::Failed => matches!(status, SampleStatus::Failed(_)),
+            StatusFilter::Processing => matches!(status, SampleStatus::Processing),
+        }
+    }
+}
+
+/// Which samples a query of the store returns
+#[derive(Debug, Clone, Default)]
+pub struct SampleFilter {
+    query: String,
+    /// Compiled `query`; None matches every id
+    id_pattern: Option<Regex>,
+    status: StatusFilter,
+}
+
+impl SampleFilter {
+    /// Keep only samples whose id matches `query` as a regex, or contains
+    /// it if it is not a valid one
+    pub fn with_query(mut self, query: &str) -> Self {
+        self.id_pattern = (!query.is_empty()).then(|| {
+            Regex::new(query)
+                .or_else(|_| Regex::new(&regex::escape(query)))
+                .expect("escaped text is a valid regex")
+        });
+        self.query = query.to_string();
+        self
+    }
+
+    /// Keep only samples with a status that `status` allows
+    pub fn with_status(mut self, status: StatusFilter) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Text the sample ids are matched against
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Statuses kept
+    pub fn status(&self) -> StatusFilter {
+        self.status
+    }
+
+    /// Whether the filter leaves out any sample
+    pub fn is_active(&self) -> bool {
+        self.id_pattern.is_some() || self.status != StatusFilter::All
+    }
+
+    /// Whether `sample` passes the filter
+    pub fn matches(&self, sample: &SampleResult) -> bool {
+        self.status.matches(&sample.status)
+            && self
+                .id_pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(&sample.sample_id))
+    }
+}
+
+/// Order of the samples a query of the store returns, by a metric's value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleSort {
+    pub metric: String,
+    pub descending: bool,
+}
+
+impl SampleSort {
+    /// Sort by `metric`, lowest value first
+    pub fn ascending(metric: impl Into<String>) -> Self {
+        Self {
+            metric: metric.into(),
+            descending: false,
+        }
+    }
+
+    /// Order of two samples; those without the metric come last either way
+    fn compare(&self, a: &SampleResult, b: &SampleResult) -> Ordering {
+        match (a.value(&self.metric), b.value(&self.metric)) {
+            (Some(a), Some(b)) if self.descending => b.total_cmp(&a),
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }
+    }
+}
+
+/// Sample history of a run
+///
+/// The most recent `capacity` samples are kept in memory. Older samples are
+/// appended to a JSON Lines spill file when one is attached, so the whole
+/// run can still be paged through; without one they are dropped and only
+/// counted.
+#[derive(Debug)]
+pub struct SampleStore {
+    capacity: usize,
+    in_memory: VecDeque<SampleResult>,
+    /// Samples that no longer fit in memory, oldest first
+    evicted: usize,
+    spill: Option<SampleSpill>,
+    failed: usize,
+}
+
+/// A clone holds the samples in memory only; the spill file stays with the
+/// original, so only one store ever appends to it
+impl Clone for SampleStore {
+    fn clone(&self) -> Self {
+        Self {
+            capacity: self.capacity,
+            in_memory: self.in_memory.clone(),
+            evicted: self.evicted,
+            spill: None,
+            failed: self.failed,
+        }
+    }
+}
+
+impl SampleStore {
+    /// Create a store keeping up to `capacity` samples in memory
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            in_memory: VecDeque::new(),
+            evicted: 0,
+            spill: None,
+            failed: 0,
+        }
+    }
+
+    /// Samples kept in memory
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Write samples evicted from memory to `path` from now on
+    pub fn spill_to(&mut self, path: &Path) -> std::io::Result<()> {
+        self.spill = Some(SampleSpill::create(path)?);
+        Ok(())
+    }
+
+    /// Write samples evicted from memory to a temporary file from now on,
+    /// removed along with the store
+    pub fn spill_to_temporary(&mut self) -> std::io::Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "preval-samples-{}-{}.jsonl",
+            std::process::id(),
+            TEMPORARY_SPILLS.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        let mut spill = SampleSpill::create(&path)?;
+        spill.temporary = true;
+        self.spill = Some(spill);
+        Ok(())
+    }
+
+    /// Record a sample's result
+    ///
+    /// A result for a sample still in memory replaces it in place; combine
+    /// the two first with a [`DuplicatePolicy`]. A sample already evicted
+    /// from memory is recorded again.
+    pub fn record(&mut self, sample: SampleResult) {
+        if let Some(existing) = self
+            .in_memory
+            .iter_mut()
+            .rev()
+            .find(|existing| existing.sample_id == sample.sample_id)
+        {
+            self.failed -= is_failed(existing) as usize;
+            self.failed += is_failed(&sample) as usize;
+            *existing = sample;
+            return;
+        }
+
+        self.failed += is_failed(&sample) as usize;
+        self.in_memory.push_back(sample);
+        if self.in_memory.len() > self.capacity {
+            if let Some(oldest) = self.in_memory.pop_front() {
+                self.evict(oldest);
+            }
+        }
+    }
+
+    /// Move a sample out of memory, into the spill file if there is one
+    fn evict(&mut self, sample: SampleResult) {
+        self.evicted += 1;
+        if let Some(spill) = &mut self.spill {
+            if let Err(e) = spill.append(&sample) {
+                // Keep the run going; only history beyond memory is lost
+                tracing::warn!(
+                    "Failed to spill samples to {}, keeping only recent samples: {}",
+                    spill.path.display(),
+                    e
+                );
+                self.spill = None;
+            }
+        }
+    }
+
+    /// Index of the sample `sample_id`, if it is still in memory
+    pub fn index_of(&self, sample_id: &str) -> Option<usize> {
+        self.in_memory
+            .iter()
+            .rposition(|sample| sample.sample_id == sample_id)
+            .map(|position| self.evicted + position)
+    }
+
+    /// Latest result of the sample `sample_id`, if it is still in memory
+    pub fn get(&self, sample_id: &str) -> Option<&SampleResult> {
+        self.in_memory
+            .iter()
+            .rev()
+            .find(|sample| sample.sample_id == sample_id)
+    }
+
+    /// Annotation of the sample at `index`, while it is in memory
+    pub fn annotation_mut(&mut self, index: usize) -> Option<&mut SampleAnnotation> {
+        let position = index.checked_sub(self.evicted)?;
+        self.in_memory
+            .get_mut(position)
+            .map(|sample| &mut sample.annotation)
+    }
+
+    /// Number of samples recorded
+    pub fn len(&self) -> usize {
+        self.evicted + self.in_memory.len()
+    }
+
+    /// Whether no sample has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of recorded samples that failed
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Indices of the samples kept in memory
+    pub fn in_memory(&self) -> Range<usize> {
+        self.evicted..self.len()
+    }
+
+    /// Indices of the samples that can still be read
+    pub fn available(&self) -> Range<usize> {
+        let start = match &self.spill {
+            Some(spill) => self.evicted - spill.offsets.len(),
+            None => self.evicted,
+        };
+        start..self.len()
+    }
+
+    /// The `count` most recent samples, oldest first
+    pub fn recent(
+        &self,
+        count: usize,
+    ) -> impl DoubleEndedIterator<Item = &SampleResult> + ExactSizeIterator {
+        self.in_memory
+            .iter()
+            .skip(self.in_memory.len().saturating_sub(count))
+    }
+
+    /// Samples in memory that pass `filter` with their indices, oldest first
+    pub fn matching<'a>(
+        &'a self,
+        filter: &'a SampleFilter,
+    ) -> impl DoubleEndedIterator<Item = (usize, &'a SampleResult)> {
+        self.in_memory
+            .iter()
+            .enumerate()
+            .map(move |(position, sample)| (self.evicted + position, sample))
+            .filter(move |(_, sample)| filter.matches(sample))
+    }
+
+    /// Samples in memory that pass `filter` with their indices, in the
+    /// order of `sort` or else oldest first
+    pub fn query<'a>(
+        &'a self,
+        filter: &'a SampleFilter,
+        sort: Option<&SampleSort>,
+    ) -> Vec<(usize, &'a SampleResult)> {
+        let mut samples: Vec<_> = self.matching(filter).collect();
+        if let Some(sort) = sort {
+            samples.sort_by(|(_, a), (_, b)| sort.compare(a, b));
+        }
+        samples
+    }
+
+    /// Metrics reported by the samples in memory, in the order first seen,
+    /// followed by their duration if any has one
+    pub fn metric_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in self.in_memory.iter().flat_map(|sample| &sample.metrics) {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+        if self
+            .in_memory
+            .iter()
+            .any(|sample| sample.duration.is_some())
+        {
+            names.push(SAMPLE_DURATION_METRIC);
+        }
+        names
+    }
+
+    /// Read every sample that can still be read, oldest first
+    pub fn read_available(&self) -> std::io::Result<Vec<SampleResult>> {
+        let available = self.available();
+        self.page(available.start, available.len())
+    }
+
+    /// Read up to `count` samples starting at index `start`, oldest first
+    ///
+    /// Samples outside `available()` are skipped.
+    pub fn page(&self, start: usize, count: usize) -> std::io::Result<Vec<SampleResult>> {
+        let available = self.available();
+        let start = start.max(available.start);
+        let end = start.saturating_add(count).min(available.end);
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let mut page = Vec::with_capacity(end - start);
+        if start < self.evicted {
+            let spill = self
+                .spill
+                .as_ref()
+                .expect("evicted samples are only available from a spill");
+            let first_spilled = self.evicted - spill.offsets.len();
+            let spilled_end = end.min(self.evicted);
+            page.extend(spill.read(start - first_spilled..spilled_end - first_spilled)?);
+        }
+        let memory_start = start.saturating_sub(self.evicted);
+        let memory_end = end.saturating_sub(self.evicted);
+        if memory_start < memory_end {
+            page.extend(self.in_memory.range(memory_start..memory_end).cloned());
+        }
+        Ok(page)
+    }
+}
+
+impl Default for SampleStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_CAPACITY)
+    }
+}
+
+fn is_failed(sample: &SampleResult) -> bool {
+    matches!(sample.status, SampleStatus::Failed(_))
+}
+
+/// JSON Lines file of samples evicted from memory
+#[derive(Debug)]
+struct SampleSpill {
+    path: PathBuf,
+    file: File,
+    /// Byte offset of every line, so pages can be read without scanning
+    offsets: Vec<u64>,
+    len: u64,
+    /// Whether the file is removed once the spill is dropped
+    temporary: bool,
+}
+
+impl SampleSpill {
+    fn create(path: &Path) -> std::io::Result<Self> {
+        let file = File::options()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            offsets: Vec::new(),
+            len: 0,
+            temporary: false,
+        })
+    }
+
+    fn append(&mut self, sample: &SampleResult) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(sample)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.offsets.push(self.len);
+        self.len += line.len() as u64;
+        Ok(())
+    }
+
+    /// Read the spilled samples at positions `range`
+    fn read(&self, range: Range<usize>) -> std::io::Result<Vec<SampleResult>> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(self.offsets[range.start]))?;
+
+        let mut samples = Vec::with_capacity(range.len());
+        let mut line = String::new();
+        for _ in range {
+            line.clear();
+            reader.read_line(&mut line)?;
+            samples.push(serde_json::from_str(&line)?);
+        }
+        Ok(samples)
+    }
+}
+
+impl Drop for SampleSpill {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: usize) -> SampleResult {
+        let mut sample = SampleResult::new_processing(format!("sample-{}", id));
+        sample.mark_completed(vec![("accuracy".to_string(), id as f64)]);
+        sample
+    }
+
+    fn ids(samples: &[SampleResult]) -> Vec<String> {
+        samples.iter().map(|s| s.sample_id.clone()).collect()
+    }
+
+    #[test]
+    fn keeps_only_capacity_in_memory_without_spill() {
+        let mut store = SampleStore::new(3);
+        for id in 0..5 {
+            store.record(sample(id));
+        }
+
+        assert_eq!(store.len(), 5);
+        assert_eq!(store.available(), 2..5);
+        assert_eq!(
+            ids(&store.page(0, 10).unwrap()),
+            vec!["sample-2", "sample-3", "sample-4"]
+        );
+    }
+
+    #[test]
+    fn repeated_sample_replaces_latest_result() {
+        let mut store = SampleStore::new(3);
+        store.record(sample(1));
+        let mut failed = sample(1);
+        failed.mark_failed("boom".to_string());
+        store.record(failed);
+
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.failed(), 1);
+        let recent: Vec<_> = store.recent(10).collect();
+        assert!(matches!(recent[0].status, SampleStatus::Failed(_)));
+    }
+
+    #[test]
+    fn out_of_order_sample_is_updated_in_place() {
+        let mut store = SampleStore::new(3);
+        store.record(sample(1));
+        store.record(sample(2));
+        let mut again = sample(1);
+        again.metrics[0].1 = 5.0;
+        store.record(again);
+
+        assert_eq!(store.len(), 2);
+        let recent: Vec<_> = store.recent(10).map(|s| s.metrics[0].1).collect();
+        assert_eq!(recent, vec![5.0, 2.0]);
+        assert_eq!(store.get("sample-1").unwrap().metrics[0].1, 5.0);
+    }
+
+    #[test]
+    fn queries_filter_by_id_and_status() {
+        let mut store = SampleStore::new(3);
+        for id in [1, 12, 13, 20] {
+            store.record(sample(id));
+        }
+        let mut failed = sample(13);
+        failed.mark_failed("boom".to_string());
+        store.record(failed);
+        let matching = |filter: &SampleFilter| -> Vec<usize> {
+            store.matching(filter).map(|(index, _)| index).collect()
+        };
+
+        assert_eq!(matching(&SampleFilter::default()), vec![1, 2, 3]);
+        assert_eq!(
+            matching(&SampleFilter::default().with_query("-1")),
+            vec![1, 2]
+        );
+        assert_eq!(matching(&SampleFilter::default().with_query("0$")), vec![3]);
+        // Not a valid regex, so matched as text
+        assert!(matching(&SampleFilter::default().with_query("(")).is_empty());
+
+        let failed = SampleFilter::default().with_status(StatusFilter::Failed);
+        assert!(failed.is_active());
+        assert_eq!(matching(&failed), vec![2]);
+        assert!(matching(&failed.with_query("20")).is_empty());
+    }
+
+    #[test]
+    fn queries_sort_by_a_metric_with_missing_values_last() {
+        let mut store = SampleStore::new(5);
+        for (id, accuracy) in [(0, 0.5), (1, 0.9), (2, 0.1)] {
+            let mut sample = sample(id);
+            sample.metrics = vec![("accuracy".to_string(), accuracy)];
+            store.record(sample);
+        }
+        store.record(SampleResult::new_processing("sample-3".to_string()));
+        let sorted = |sort: &SampleSort| -> Vec<usize> {
+            store
+                .query(&SampleFilter::default(), Some(sort))
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect()
+        };
+
+        let mut sort = SampleSort::ascending("accuracy");
+        assert_eq!(sorted(&sort), vec![2, 0, 1, 3]);
+        sort.descending = true;
+        assert_eq!(sorted(&sort), vec![1, 0, 2, 3]);
+        assert_eq!(store.metric_names(), vec!["accuracy"]);
+    }
+
+    #[test]
+    fn duplicate_policies_combine_repeated_reports() {
+        let mut first = SampleResult::new_processing("a".to_string());
+        first.mark_completed(vec![("accuracy".to_string(), 1.0)]);
+        let mut second = SampleResult::new_processing("a".to_string());
+        second.mark_completed(vec![
+            ("accuracy".to_string(), 0.25),
+            ("latency".to_string(), 20.0),
+        ]);
+
+        let replaced = DuplicatePolicy::Replace.combine(&first, second.clone());
+        assert_eq!(replaced.metrics, second.metrics);
+
+        let merged = DuplicatePolicy::Merge.combine(&first, second.clone());
+        assert_eq!(merged.metrics, second.metrics);
+        assert_eq!(merged.runs, 1);
+
+        let rerun = DuplicatePolicy::Rerun.combine(&first, second.clone());
+        let rerun = DuplicatePolicy::Rerun.combine(&rerun, second);
+        assert_eq!(rerun.runs, 3);
+        assert_eq!(
+            rerun.metrics,
+            vec![("accuracy".to_string(), 0.5), ("latency".to_string(), 20.0)]
+        );
+    }
+
+    #[test]
+    fn pages_through_spilled_and_in_memory_samples() {
+        let dir = std::env::temp_dir().join(format!("preval-samples-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("samples.jsonl");
+
+        let mut store = SampleStore::new(2);
+        store.spill_to(&path).unwrap();
+        for id in 0..6 {
+            store.record(sample(id));
+        }
+
+        assert_eq!(store.available(), 0..6);
+        assert_eq!(
+            ids(&store.page(2, 3).unwrap()),
+            vec!["sample-2", "sample-3", "sample-4"]
+        );
+        assert_eq!(store.page(0, 6).unwrap().len(), 6);
+        assert_eq!(store.page(0, 1).unwrap()[0].metrics[0].1, 0.0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn temporary_spill_is_removed_with_the_store() {
+        let mut store = SampleStore::new(2);
+        store.spill_to_temporary().unwrap();
+        for id in 0..5 {
+            store.record(sample(id));
+        }
+        let path = store.spill.as_ref().unwrap().path.clone();
+
+        assert_eq!(ids(&store.read_available().unwrap()).len(), 5);
+        assert!(path.exists());
+        drop(store);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,105 @@
+//! Two metrics of each sample plotted against each other, with the samples
+//! far off the trend between them flagged as outliers
+
+use super::types::SampleResult;
+
+/// Residuals, in standard deviations, beyond which a sample is an outlier
+pub const OUTLIER_THRESHOLD: f64 = 3.0;
+
+/// Fewest samples a trend is fitted through before any is an outlier
+const MIN_FIT_SAMPLES: usize = 10;
+
+/// A sample plotted in the scatter plot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScatterPoint {
+    /// Index of the sample in the sample store
+    pub index: usize,
+    pub sample_id: String,
+    pub x: f64,
+    pub y: f64,
+    /// Whether the sample is far off the trend of the others
+    pub outlier: bool,
+}
+
+/// Points of the `samples` (with their store indices) reporting metrics `x`
+/// and `y`, in the order given
+///
+/// A sample is an outlier when it is more than `OUTLIER_THRESHOLD`
+/// standard deviations off the least-squares line through all of them.
+pub fn scatter_points<'a>(
+    samples: impl Iterator<Item = (usize, &'a SampleResult)>,
+    x: &str,
+    y: &str,
+) -> Vec<ScatterPoint> {
+    let mut points: Vec<ScatterPoint> = samples
+        .filter_map(|(index, sample)| {
+            Some(ScatterPoint {
+                index,
+                sample_id: sample.sample_id.clone(),
+                x: sample.value(x)?,
+                y: sample.value(y)?,
+                outlier: false,
+            })
+        })
+        .collect();
+    if points.len() < MIN_FIT_SAMPLES {
+        return points;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.y).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|p| (p.x - mean_x) * (p.y - mean_y)).sum();
+    let variance_x: f64 = points.iter().map(|p| (p.x - mean_x).powi(2)).sum();
+    // Without any spread in x the best line is flat
+    let slope = if variance_x > 0.0 {
+        covariance / variance_x
+    } else {
+        0.0
+    };
+    let residual = |p: &ScatterPoint| p.y - (mean_y + slope * (p.x - mean_x));
+    let spread = (points.iter().map(|p| residual(p).powi(2)).sum::<f64>() / n).sqrt();
+    if spread > 0.0 {
+        for point in &mut points {
+            point.outlier = residual(point).abs() > OUTLIER_THRESHOLD * spread;
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: usize, tokens: f64, latency: f64) -> SampleResult {
+        let mut sample = SampleResult::new_processing(format!("s{}", id));
+        sample.mark_completed(vec![
+            ("tokens".to_string(), tokens),
+            ("latency".to_string(), latency),
+        ]);
+        sample
+    }
+
+    #[test]
+    fn flags_samples_far_off_the_trend() {
+        // Latency grows with tokens, except for one slow sample
+        let mut samples: Vec<SampleResult> = (0..20)
+            .map(|i| sample(i, i as f64 * 100.0, i as f64 * 10.0 + (i % 3) as f64))
+            .collect();
+        samples[7] = sample(7, 700.0, 400.0);
+        samples.push(SampleResult::new_processing("pending".to_string()));
+
+        let points = scatter_points(samples.iter().enumerate(), "tokens", "latency");
+        assert_eq!(points.len(), 20);
+        let outliers: Vec<_> = points.iter().filter(|p| p.outlier).collect();
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(
+            (outliers[0].index, outliers[0].sample_id.as_str()),
+            (7, "s7")
+        );
+
+        // Too few samples to tell what is off the trend
+        let points = scatter_points(samples[..5].iter().enumerate(), "tokens", "latency");
+        assert!(points.iter().all(|p| !p.outlier));
+    }
+}
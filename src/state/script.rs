@@ -0,0 +1,304 @@
+//! User scripts for evaluation logic that does not fit the declarative
+//! config: derived metrics, pass/fail checks and callbacks on each sample
+//!
+//! A script is written in [Rhai](https://rhai.rs) and may define any of:
+//!
+//! ```rhai
+//! // Metrics to add to the sample, computed from its metrics
+//! fn derive(metrics) { #{ f1: 2.0 * metrics.precision * metrics.recall / (metrics.precision + metrics.recall) } }
+//!
+//! // true (or nothing) to pass the sample, false or a reason to fail it
+//! fn check(sample) { if sample.metrics.f1 < 0.5 { "f1 below 0.5" } else { true } }
+//!
+//! // Called with every recorded sample; a returned string is noted on the timeline
+//! fn on_sample(sample) { if sample.failed { `${sample.id} failed: ${sample.error}` } }
+//! ```
+//!
+//! A sample is passed as a map of its `id`, `metrics` (a map of name to
+//! value), whether it `failed`, its `error` and its `duration_ms`.
+
+use super::types::{SampleResult, SampleStatus};
+use rhai::{CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Most operations one call into a script may take, so a runaway loop
+/// fails that call instead of freezing the run
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Function computing metrics of a sample
+pub const DERIVE_FN: &str = "derive";
+
+/// Function passing or failing a sample
+pub const CHECK_FN: &str = "check";
+
+/// Function called with every recorded sample
+pub const ON_SAMPLE_FN: &str = "on_sample";
+
+/// A compiled user script
+///
+/// Cloning shares the compiled script.
+#[derive(Clone)]
+pub struct SampleScript {
+    name: String,
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+}
+
+impl fmt::Debug for SampleScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SampleScript")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SampleScript {
+    /// Read and compile the script at `path`
+    pub fn load(path: &Path) -> Result<Self, ScriptError> {
+        let source = std::fs::read_to_string(path).map_err(|e| ScriptError::Read {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        Self::compile(name, &source)
+    }
+
+    /// Compile `source`, naming the script `name` in messages
+    pub fn compile(name: impl Into<String>, source: &str) -> Result<Self, ScriptError> {
+        let name = name.into();
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // Printing to stdout would draw over the TUI
+        let print_name = name.clone();
+        engine.on_print(move |text| tracing::info!("{}: {}", print_name, text));
+        let debug_name = name.clone();
+        engine.on_debug(move |text, _, _| tracing::debug!("{}: {}", debug_name, text));
+
+        let ast = engine.compile(source).map_err(|e| ScriptError::Compile {
+            path: PathBuf::from(&name),
+            message: e.to_string(),
+        })?;
+        Ok(Self {
+            name,
+            engine: Arc::new(engine),
+            ast: Arc::new(ast),
+        })
+    }
+
+    /// Name of the script, e.g. its file name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the script defines `function` taking one argument
+    pub fn defines(&self, function: &str) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|f| f.name == function && f.params.len() == 1)
+    }
+
+    /// Metrics `derive` computes from a sample's `metrics`; none when the
+    /// script does not define it
+    pub fn derive(&self, metrics: &[(String, f64)]) -> Result<Vec<(String, f64)>, String> {
+        if !self.defines(DERIVE_FN) {
+            return Ok(Vec::new());
+        }
+        let result = self.call(DERIVE_FN, Dynamic::from_map(metric_map(metrics)))?;
+        if result.is_unit() {
+            return Ok(Vec::new());
+        }
+        let derived = result
+            .try_cast::<Map>()
+            .ok_or_else(|| format!("{}() should return a map of metrics", DERIVE_FN))?;
+        derived
+            .into_iter()
+            .map(|(name, value)| {
+                number(&value)
+                    .map(|value| (name.to_string(), value))
+                    .ok_or_else(|| format!("{}() returned a non-number for '{}'", DERIVE_FN, name))
+            })
+            .collect()
+    }
+
+    /// Why `check` fails `sample`; None when it passes or the script does
+    /// not define it
+    pub fn check(&self, sample: &SampleResult) -> Result<Option<String>, String> {
+        if !self.defines(CHECK_FN) {
+            return Ok(None);
+        }
+        let result = self.call(CHECK_FN, sample_map(sample))?;
+        if result.is_unit() {
+            Ok(None)
+        } else if let Some(passed) = result.clone().try_cast::<bool>() {
+            Ok((!passed).then(|| "failed the script's check".to_string()))
+        } else if result.is_string() {
+            Ok(Some(result.to_string()))
+        } else {
+            Err(format!(
+                "{}() should return true, false or a reason",
+                CHECK_FN
+            ))
+        }
+    }
+
+    /// What `on_sample` noted about `sample`, if anything
+    pub fn on_sample(&self, sample: &SampleResult) -> Result<Option<String>, String> {
+        if !self.defines(ON_SAMPLE_FN) {
+            return Ok(None);
+        }
+        let result = self.call(ON_SAMPLE_FN, sample_map(sample))?;
+        Ok(result.is_string().then(|| result.to_string()))
+    }
+
+    /// Call `function` with `argument`, without rerunning the script's
+    /// top-level statements
+    fn call(&self, function: &str, argument: Dynamic) -> Result<Dynamic, String> {
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options::<Dynamic>(
+                options,
+                &mut Scope::new(),
+                &self.ast,
+                function,
+                (argument,),
+            )
+            .map_err(|e| format!("{}() failed: {}", function, e))
+    }
+}
+
+/// `metrics` as a script map
+fn metric_map(metrics: &[(String, f64)]) -> Map {
+    metrics
+        .iter()
+        .map(|(name, value)| (name.as_str().into(), Dynamic::from_float(*value)))
+        .collect()
+}
+
+/// `sample` as a script map
+fn sample_map(sample: &SampleResult) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("id".into(), sample.sample_id.clone().into());
+    map.insert(
+        "metrics".into(),
+        Dynamic::from_map(metric_map(&sample.metrics)),
+    );
+    let error = match &sample.status {
+        SampleStatus::Failed(error) => Some(error.clone()),
+        _ => None,
+    };
+    map.insert("failed".into(), error.is_some().into());
+    map.insert("error".into(), error.map_or(Dynamic::UNIT, Dynamic::from));
+    map.insert(
+        "duration_ms".into(),
+        sample.duration.map_or(Dynamic::UNIT, |duration| {
+            Dynamic::from_float(duration.as_secs_f64() * 1000.0)
+        }),
+    );
+    Dynamic::from_map(map)
+}
+
+/// `value` as a metric value, if it is a number
+fn number(value: &Dynamic) -> Option<f64> {
+    value
+        .as_float()
+        .ok()
+        .or_else(|| value.as_int().ok().map(|value| value as f64))
+}
+
+/// Errors for scripts that cannot be loaded
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptError {
+    #[error("failed to read script {}: {message}", path.display())]
+    Read { path: PathBuf, message: String },
+    #[error("failed to compile script {}: {message}", path.display())]
+    Compile { path: PathBuf, message: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str, metrics: &[(&str, f64)]) -> SampleResult {
+        let mut sample = SampleResult::new_processing(id.to_string());
+        sample.mark_completed(
+            metrics
+                .iter()
+                .map(|(name, value)| (name.to_string(), *value))
+                .collect(),
+        );
+        sample
+    }
+
+    #[test]
+    fn scripts_derive_metrics_and_check_samples() {
+        let script = SampleScript::compile(
+            "checks.rhai",
+            r#"
+            fn derive(metrics) {
+                #{ f1: 2.0 * metrics.precision * metrics.recall / (metrics.precision + metrics.recall), tries: 3 }
+            }
+            fn check(sample) {
+                if sample.metrics["llm.accuracy"] < 0.5 { "accuracy below 0.5" } else { true }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let mut derived = script
+            .derive(&[("precision".to_string(), 0.5), ("recall".to_string(), 1.0)])
+            .unwrap();
+        derived.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(derived[0].0, "f1");
+        assert!((derived[0].1 - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(derived[1], ("tries".to_string(), 3.0));
+
+        assert_eq!(
+            script
+                .check(&sample("q1", &[("llm.accuracy", 0.2)]))
+                .unwrap()
+                .as_deref(),
+            Some("accuracy below 0.5")
+        );
+        assert_eq!(
+            script
+                .check(&sample("q2", &[("llm.accuracy", 0.9)]))
+                .unwrap(),
+            None
+        );
+        assert_eq!(script.on_sample(&sample("q2", &[])).unwrap(), None);
+    }
+
+    #[test]
+    fn script_failures_are_reported_not_raised() {
+        let script = SampleScript::compile(
+            "loop.rhai",
+            r#"
+            fn check(sample) { loop { } }
+            fn on_sample(sample) { if sample.failed { `${sample.id}: ${sample.error}` } }
+            fn derive(metrics) { 42 }
+            "#,
+        )
+        .unwrap();
+        let mut failed = sample("q1", &[]);
+        failed.mark_failed("broken".to_string());
+
+        assert!(script
+            .check(&failed)
+            .unwrap_err()
+            .starts_with("check() failed"));
+        assert_eq!(
+            script.on_sample(&failed).unwrap().as_deref(),
+            Some("q1: broken")
+        );
+        assert!(script.derive(&[]).is_err());
+        assert!(matches!(
+            SampleScript::compile("bad.rhai", "fn check(sample) {"),
+            Err(ScriptError::Compile { .. })
+        ));
+    }
+}
@@ -0,0 +1,330 @@
+//! Whether metrics differ between two runs by more than chance, using
+//! Welch's t-test over the per-sample values
+
+use super::aggregation::{MetricAggregator, MetricStats};
+use super::types::SampleResult;
+use std::collections::BTreeMap;
+
+/// Significance level used unless another is asked for
+pub const DEFAULT_ALPHA: f64 = 0.05;
+
+/// Count, mean and sample variance of a metric's values in one run
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl Summary {
+    /// Summarize `values`
+    pub fn of(values: &[f64]) -> Self {
+        let count = values.len();
+        if count == 0 {
+            return Self::default();
+        }
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let variance = if count > 1 {
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+        } else {
+            0.0
+        };
+        Self {
+            count,
+            mean,
+            variance,
+        }
+    }
+
+    /// Summarize the values `stats` has seen
+    pub fn of_stats(stats: &MetricStats) -> Self {
+        let count = stats.count();
+        let mean = stats.mean().unwrap_or_default();
+        // The aggregate keeps the population deviation; the test wants the sample one
+        let variance = match stats.stddev() {
+            Some(stddev) if count > 1 => stddev.powi(2) * count as f64 / (count - 1) as f64,
+            _ => 0.0,
+        };
+        Self {
+            count,
+            mean,
+            variance,
+        }
+    }
+}
+
+/// Result of Welch's t-test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WelchTest {
+    pub t: f64,
+    pub degrees_of_freedom: f64,
+    /// Two-sided probability of a difference at least this large by chance
+    pub p_value: f64,
+}
+
+impl WelchTest {
+    /// Test whether the means of `a` and `b` differ; needs two values of each
+    pub fn new(a: &Summary, b: &Summary) -> Option<Self> {
+        if a.count < 2 || b.count < 2 {
+            return None;
+        }
+        let (na, nb) = (a.count as f64, b.count as f64);
+        let (va, vb) = (a.variance / na, b.variance / nb);
+        let difference = b.mean - a.mean;
+
+        // Without any spread, a difference is certain and no difference is none
+        if va + vb == 0.0 {
+            return Some(Self {
+                t: if difference == 0.0 {
+                    0.0
+                } else {
+                    difference.signum() * f64::INFINITY
+                },
+                degrees_of_freedom: na + nb - 2.0,
+                p_value: if difference == 0.0 { 1.0 } else { 0.0 },
+            });
+        }
+
+        let t = difference / (va + vb).sqrt();
+        let degrees_of_freedom =
+            (va + vb).powi(2) / (va.powi(2) / (na - 1.0) + vb.powi(2) / (nb - 1.0));
+        Some(Self {
+            t,
+            degrees_of_freedom,
+            p_value: students_t_two_sided(t, degrees_of_freedom),
+        })
+    }
+}
+
+/// How one metric compares between a baseline and a candidate run
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricComparison {
+    pub metric: String,
+    pub baseline: Summary,
+    pub candidate: Summary,
+    /// Missing when either run has fewer than two values
+    pub test: Option<WelchTest>,
+}
+
+impl MetricComparison {
+    /// Change of the mean from baseline to candidate
+    pub fn difference(&self) -> f64 {
+        self.candidate.mean - self.baseline.mean
+    }
+
+    /// Whether the difference is significant at level `alpha`
+    pub fn is_significant(&self, alpha: f64) -> bool {
+        self.test.is_some_and(|test| test.p_value < alpha)
+    }
+}
+
+/// Compare every metric reported by either run's samples, ordered by name
+pub fn compare_samples(
+    baseline: &[SampleResult],
+    candidate: &[SampleResult],
+) -> Vec<MetricComparison> {
+    let mut values: BTreeMap<&str, (Vec<f64>, Vec<f64>)> = BTreeMap::new();
+    for sample in baseline {
+        for (name, value) in &sample.metrics {
+            values.entry(name).or_default().0.push(*value);
+        }
+    }
+    for sample in candidate {
+        for (name, value) in &sample.metrics {
+            values.entry(name).or_default().1.push(*value);
+        }
+    }
+
+    values
+        .into_iter()
+        .map(|(metric, (baseline, candidate))| {
+            let baseline = Summary::of(&baseline);
+            let candidate = Summary::of(&candidate);
+            MetricComparison {
+                metric: metric.to_string(),
+                baseline,
+                candidate,
+                test: WelchTest::new(&baseline, &candidate),
+            }
+        })
+        .collect()
+}
+
+/// Compare every metric aggregated in either run, ordered by name
+///
+/// Cheaper than [`compare_samples`] for runs still in progress, whose
+/// samples may not all be in memory.
+pub fn compare_stats(
+    baseline: &MetricAggregator,
+    candidate: &MetricAggregator,
+) -> Vec<MetricComparison> {
+    let mut summaries: BTreeMap<&str, (Summary, Summary)> = BTreeMap::new();
+    for (name, stats) in baseline.iter() {
+        summaries.entry(name).or_default().0 = Summary::of_stats(stats);
+    }
+    for (name, stats) in candidate.iter() {
+        summaries.entry(name).or_default().1 = Summary::of_stats(stats);
+    }
+
+    summaries
+        .into_iter()
+        .map(|(metric, (baseline, candidate))| MetricComparison {
+            metric: metric.to_string(),
+            baseline,
+            candidate,
+            test: WelchTest::new(&baseline, &candidate),
+        })
+        .collect()
+}
+
+/// Two-sided p-value of `t` under Student's t distribution
+fn students_t_two_sided(t: f64, degrees_of_freedom: f64) -> f64 {
+    let x = degrees_of_freedom / (degrees_of_freedom + t * t);
+    regularized_incomplete_beta(x, degrees_of_freedom / 2.0, 0.5).clamp(0.0, 1.0)
+}
+
+/// Regularized incomplete beta function I_x(a, b)
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly only on this side
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+/// Continued fraction for the incomplete beta function (modified Lentz)
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut result = d;
+
+    for m in 1..=200 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        for step in [even, odd] {
+            d = 1.0 + step * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + step / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            result *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    result
+}
+
+/// Natural logarithm of the gamma function (Lanczos approximation)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000_000_000_190_015, |sum, (i, c)| {
+            sum + c / (x + 1.0 + i as f64)
+        });
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(accuracy: f64) -> SampleResult {
+        let mut sample = SampleResult::new_processing("s".to_string());
+        sample.mark_completed(vec![("accuracy".to_string(), accuracy)]);
+        sample
+    }
+
+    #[test]
+    fn t_distribution_p_values_match_tables() {
+        assert!((students_t_two_sided(2.228, 10.0) - 0.05).abs() < 1e-3);
+        assert!((students_t_two_sided(1.0, 1.0) - 0.5).abs() < 1e-9);
+        assert!((students_t_two_sided(0.0, 5.0) - 1.0).abs() < 1e-9);
+        assert!((students_t_two_sided(-2.576, 1e6) - 0.01).abs() < 1e-3);
+    }
+
+    #[test]
+    fn separates_real_differences_from_noise() {
+        let baseline: Vec<_> = [0.70, 0.72, 0.68, 0.71, 0.69, 0.70]
+            .into_iter()
+            .map(sample)
+            .collect();
+        let better: Vec<_> = [0.80, 0.82, 0.79, 0.81, 0.78, 0.80]
+            .into_iter()
+            .map(sample)
+            .collect();
+        let same: Vec<_> = [0.71, 0.69, 0.70, 0.72, 0.68, 0.70]
+            .into_iter()
+            .map(sample)
+            .collect();
+
+        let improved = &compare_samples(&baseline, &better)[0];
+        assert_eq!(improved.metric, "accuracy");
+        assert!((improved.difference() - 0.1).abs() < 1e-9);
+        assert!(improved.is_significant(DEFAULT_ALPHA));
+
+        let unchanged = &compare_samples(&baseline, &same)[0];
+        assert!(!unchanged.is_significant(DEFAULT_ALPHA));
+
+        let too_few = &compare_samples(&baseline, &better[..1])[0];
+        assert!(too_few.test.is_none());
+    }
+
+    #[test]
+    fn aggregates_compare_like_their_samples() {
+        let aggregate = |values: &[f64]| {
+            let mut stats = MetricAggregator::new();
+            for value in values {
+                stats.record("accuracy", *value);
+            }
+            stats
+        };
+        let baseline = [0.70, 0.72, 0.68, 0.71, 0.69, 0.70];
+        let better = [0.80, 0.82, 0.79, 0.81, 0.78, 0.80];
+
+        let from_stats = &compare_stats(&aggregate(&baseline), &aggregate(&better))[0];
+        let from_samples = &compare_samples(&baseline.map(sample), &better.map(sample))[0];
+        assert!((from_stats.difference() - from_samples.difference()).abs() < 1e-9);
+        assert!((from_stats.baseline.variance - from_samples.baseline.variance).abs() < 1e-9);
+        assert!(from_stats.is_significant(DEFAULT_ALPHA));
+
+        // Metrics missing from a run compare against nothing
+        let mut candidate = aggregate(&better);
+        candidate.record("latency_ms", 120.0);
+        let comparisons = compare_stats(&aggregate(&baseline), &candidate);
+        assert_eq!(comparisons[1].metric, "latency_ms");
+        assert_eq!(comparisons[1].baseline.count, 0);
+        assert!(comparisons[1].test.is_none());
+    }
+}
@@ -0,0 +1,155 @@
+//! Sliding-window throughput of a run, so operators of long benchmarks can
+//! spot rate-limit throttling
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How far back the current throughput looks
+pub const THROUGHPUT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Shortest span a rate is given over; anything shorter is noise
+const MIN_SPAN: Duration = Duration::from_secs(1);
+
+/// Samples and tokens processed per second
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    pub samples_per_sec: f64,
+    /// Missing until a sample reports token counts
+    pub tokens_per_sec: Option<f64>,
+}
+
+/// Completed work of a run over time
+#[derive(Debug, Clone)]
+pub struct ThroughputTracker {
+    window: Duration,
+    started_at: Instant,
+    /// Samples and tokens reported within the window, oldest first
+    recent: VecDeque<(Instant, usize, f64)>,
+    total_samples: usize,
+    total_tokens: f64,
+    saw_tokens: bool,
+}
+
+impl ThroughputTracker {
+    /// Start tracking now, with rates over the last `window`
+    pub fn new(window: Duration) -> Self {
+        Self::started_at(Instant::now(), window)
+    }
+
+    fn started_at(started_at: Instant, window: Duration) -> Self {
+        Self {
+            window,
+            started_at,
+            recent: VecDeque::new(),
+            total_samples: 0,
+            total_tokens: 0.0,
+            saw_tokens: false,
+        }
+    }
+
+    /// Record `samples` completed just now, using `tokens` if reported
+    pub fn record(&mut self, samples: usize, tokens: Option<f64>) {
+        self.record_at(Instant::now(), samples, tokens);
+    }
+
+    fn record_at(&mut self, at: Instant, samples: usize, tokens: Option<f64>) {
+        self.saw_tokens |= tokens.is_some();
+        let tokens = tokens.unwrap_or(0.0);
+        self.total_samples += samples;
+        self.total_tokens += tokens;
+
+        self.recent.push_back((at, samples, tokens));
+        while let Some((oldest, _, _)) = self.recent.front() {
+            if at.duration_since(*oldest) <= self.window {
+                break;
+            }
+            self.recent.pop_front();
+        }
+    }
+
+    /// Throughput over the last window, or since tracking started if that
+    /// is more recent
+    pub fn current(&self) -> Option<Throughput> {
+        self.current_at(Instant::now())
+    }
+
+    fn current_at(&self, now: Instant) -> Option<Throughput> {
+        let since = now
+            .checked_sub(self.window)
+            .map_or(self.started_at, |start| start.max(self.started_at));
+        let (samples, tokens) = self
+            .recent
+            .iter()
+            .filter(|(at, _, _)| *at >= since)
+            .fold((0, 0.0), |(samples, tokens), (_, s, t)| {
+                (samples + s, tokens + t)
+            });
+        self.rate(samples, tokens, now.saturating_duration_since(since))
+    }
+
+    /// Throughput over the whole run so far
+    pub fn average(&self) -> Option<Throughput> {
+        self.rate(
+            self.total_samples,
+            self.total_tokens,
+            self.started_at.elapsed(),
+        )
+    }
+
+    fn rate(&self, samples: usize, tokens: f64, span: Duration) -> Option<Throughput> {
+        if span < MIN_SPAN {
+            return None;
+        }
+        let seconds = span.as_secs_f64();
+        Some(Throughput {
+            samples_per_sec: samples as f64 / seconds,
+            tokens_per_sec: self.saw_tokens.then(|| tokens / seconds),
+        })
+    }
+}
+
+impl Default for ThroughputTracker {
+    fn default() -> Self {
+        Self::new(THROUGHPUT_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_cover_only_the_recent_window() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let mut tracker = ThroughputTracker::started_at(start, Duration::from_secs(10));
+        assert_eq!(tracker.current_at(start), None);
+
+        // Fast at first: 2 samples/s with 100 tokens each
+        for second in 0..10 {
+            tracker.record_at(at(second), 2, Some(200.0));
+        }
+        let fast = tracker.current_at(at(10)).unwrap();
+        assert_eq!(fast.samples_per_sec, 2.0);
+        assert_eq!(fast.tokens_per_sec, Some(200.0));
+
+        // Then throttled to a sample every other second
+        for second in (10..20).step_by(2) {
+            tracker.record_at(at(second), 1, Some(100.0));
+        }
+        let throttled = tracker.current_at(at(20)).unwrap();
+        assert_eq!(throttled.samples_per_sec, 0.5);
+        assert_eq!(throttled.tokens_per_sec, Some(50.0));
+    }
+
+    #[test]
+    fn tokens_are_unknown_until_reported() {
+        let start = Instant::now();
+        let mut tracker = ThroughputTracker::started_at(start, THROUGHPUT_WINDOW);
+        tracker.record_at(start, 1, None);
+
+        let rate = tracker.current_at(start + Duration::from_secs(2)).unwrap();
+        assert_eq!(rate.samples_per_sec, 0.5);
+        assert_eq!(rate.tokens_per_sec, None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Timestamped log of what happened during a run, so post-mortems do not
+//! depend on scraping tracing logs
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Events kept before the oldest are dropped
+pub const DEFAULT_TIMELINE_CAPACITY: usize = 1000;
+
+/// What kind of thing happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimelineEventKind {
+    /// The evaluator introduced itself
+    Handshake,
+    /// The run moved to another phase, e.g. collecting or completed
+    Phase,
+    /// The user paused the evaluator
+    Pause,
+    /// The user resumed the evaluator
+    Resume,
+    /// A line from the evaluator could not be parsed
+    ParseError,
+    /// A sample or the run broke a threshold rule
+    ThresholdViolation,
+    /// A sample's metric was far outside its recent values
+    Anomaly,
+    /// The evaluator process exited
+    ProcessExit,
+    /// A crashed evaluator is being restarted
+    Restart,
+    /// The user's script noted something about a sample
+    Script,
+}
+
+impl TimelineEventKind {
+    /// Short name shown next to each event
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimelineEventKind::Handshake => "handshake",
+            TimelineEventKind::Phase => "phase",
+            TimelineEventKind::Pause => "pause",
+            TimelineEventKind::Resume => "resume",
+            TimelineEventKind::ParseError => "parse error",
+            TimelineEventKind::ThresholdViolation => "threshold",
+            TimelineEventKind::Anomaly => "anomaly",
+            TimelineEventKind::ProcessExit => "exit",
+            TimelineEventKind::Restart => "restart",
+            TimelineEventKind::Script => "script",
+        }
+    }
+}
+
+/// One thing that happened during a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    pub at: DateTime<Utc>,
+    pub kind: TimelineEventKind,
+    pub message: String,
+}
+
+/// Events of a run, oldest first, within bounded memory
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    events: VecDeque<TimelineEvent>,
+    capacity: usize,
+    /// Events dropped to stay within `capacity`
+    dropped: usize,
+}
+
+impl Timeline {
+    /// Create a timeline keeping up to `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+        }
+    }
+
+    /// Record that something happened just now
+    pub fn record(&mut self, kind: TimelineEventKind, message: impl Into<String>) {
+        self.events.push_back(TimelineEvent {
+            at: Utc::now(),
+            kind,
+            message: message.into(),
+        });
+        if self.events.len() > self.capacity {
+            self.events.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    /// Events kept, oldest first
+    pub fn events(&self) -> impl DoubleEndedIterator<Item = &TimelineEvent> + ExactSizeIterator {
+        self.events.iter()
+    }
+
+    /// Number of events kept
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether nothing has happened yet
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Number of old events dropped to stay within capacity
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Number of events recorded since the run started, including dropped ones
+    pub fn recorded(&self) -> usize {
+        self.dropped + self.events.len()
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIMELINE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_events_beyond_capacity() {
+        let mut timeline = Timeline::new(2);
+        timeline.record(TimelineEventKind::Handshake, "first");
+        timeline.record(TimelineEventKind::ParseError, "second");
+        timeline.record(TimelineEventKind::ProcessExit, "third");
+
+        let messages: Vec<_> = timeline.events().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["second", "third"]);
+        assert_eq!(timeline.dropped(), 1);
+
+        let json = serde_json::to_string(timeline.events().next().unwrap()).unwrap();
+        assert!(json.contains("\"kind\":\"parse-error\""));
+    }
+}
@@ -0,0 +1,82 @@
+//! Recent values of a few key metrics, so drift during a run is visible at
+//! a glance instead of only the latest value
+
+use super::app::SAMPLE_DURATION_METRIC;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Most recent values kept per metric
+pub const TREND_LENGTH: usize = 120;
+
+/// Trend of the tokens each sample used, input and output together
+pub const TOKENS_TREND: &str = "tokens";
+
+/// Metrics followed unless configured otherwise
+pub const DEFAULT_TRENDS: [&str; 3] = ["accuracy", SAMPLE_DURATION_METRIC, TOKENS_TREND];
+
+/// Recent values of the followed metrics, oldest first
+#[derive(Debug, Clone)]
+pub struct Trends {
+    followed: Vec<String>,
+    values: BTreeMap<String, VecDeque<f64>>,
+}
+
+impl Trends {
+    /// Follow the metrics named `followed`, in that order
+    pub fn new(followed: Vec<String>) -> Self {
+        Self {
+            followed,
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Same metrics with no values seen yet, for starting a run over
+    pub fn without_history(&self) -> Self {
+        Self::new(self.followed.clone())
+    }
+
+    /// Remember `value` of `metric` if it is followed
+    pub fn record(&mut self, metric: &str, value: f64) {
+        if !self.followed.iter().any(|name| name == metric) {
+            return;
+        }
+        let values = self.values.entry(metric.to_string()).or_default();
+        if values.len() == TREND_LENGTH {
+            values.pop_front();
+        }
+        values.push_back(value);
+    }
+
+    /// Followed metrics with at least one value, in the configured order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &VecDeque<f64>)> {
+        self.followed
+            .iter()
+            .filter_map(|name| Some((name.as_str(), self.values.get(name)?)))
+    }
+}
+
+impl Default for Trends {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRENDS.iter().map(ToString::to_string).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_recent_values_of_followed_metrics_only() {
+        let mut trends = Trends::new(vec!["latency".to_string(), "accuracy".to_string()]);
+        for i in 0..TREND_LENGTH + 5 {
+            trends.record("accuracy", i as f64);
+        }
+        trends.record("f1", 0.5);
+
+        let followed: Vec<_> = trends.iter().collect();
+        assert_eq!(followed.len(), 1);
+        let (name, values) = followed[0];
+        assert_eq!(name, "accuracy");
+        assert_eq!(values.len(), TREND_LENGTH);
+        assert_eq!(values.front(), Some(&5.0));
+    }
+}
@@ -1,5 +1,7 @@
+use chrono::{DateTime, Utc};
 use nutype::nutype;
-use std::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::{Duration, Instant};
 
 /// Non-empty evaluator name
@@ -85,35 +87,49 @@ pub enum EvaluationStatus {
     Failed(String),
 }
 
+impl EvaluationStatus {
+    /// Name of the phase of the run, without progress counts
+    pub fn phase(&self) -> String {
+        match self {
+            EvaluationStatus::Starting => "Starting".to_string(),
+            EvaluationStatus::WaitingForHandshake => "Waiting for handshake".to_string(),
+            EvaluationStatus::CollectingMetrics { .. } => "Collecting metrics".to_string(),
+            EvaluationStatus::Completed => "Completed".to_string(),
+            EvaluationStatus::Failed(reason) => format!("Failed: {}", reason),
+        }
+    }
+}
+
 /// Phantom types for application state - Evaluator setting
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct EvaluatorNotSet;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct EvaluatorSet;
 
 /// Phantom types for application state - Handshake setting
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct HandshakeNotSet;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct HandshakeSet;
 
 /// Phantom types for application state - Status tracking
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Starting;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct WaitingForHandshake;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct CollectingMetrics;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct CompletedOrFailed;
 
 /// Sample status during evaluation
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SampleStatus {
     /// Currently being processed
     Processing,
@@ -125,7 +141,7 @@ pub enum SampleStatus {
 }
 
 /// A sample result with its metrics and status
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SampleResult {
     /// Unique identifier for the sample
     pub sample_id: String,
@@ -134,7 +150,42 @@ pub struct SampleResult {
     /// Key metrics extracted from the sample
     pub metrics: Vec<(String, f64)>, // (metric_name, value) pairs
     /// When the sample was completed or failed
-    pub completed_at: Option<Instant>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// How long the evaluator took on the sample, from its metric timestamps
+    #[serde(default)]
+    pub duration: Option<Duration>,
+    /// Number of runs of the sample its metrics are averaged over
+    #[serde(default = "SampleResult::single_run")]
+    pub runs: u32,
+    /// Metrics whose value was far outside their recent values
+    #[serde(default)]
+    pub anomalies: Vec<String>,
+    /// Text the sample was evaluated on, such as its prompt and response,
+    /// by attribute name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub texts: BTreeMap<String, String>,
+    /// Bookmark and note the user left on the sample
+    #[serde(default, skip_serializing_if = "SampleAnnotation::is_empty")]
+    pub annotation: SampleAnnotation,
+    /// Value of each attribute the sample's statistics are grouped by
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, String>,
+}
+
+/// What the user marked a sample with, to revisit it after the run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampleAnnotation {
+    #[serde(default)]
+    pub bookmarked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl SampleAnnotation {
+    /// Whether the sample is neither bookmarked nor noted
+    pub fn is_empty(&self) -> bool {
+        !self.bookmarked && self.note.is_none()
+    }
 }
 
 impl SampleResult {
@@ -145,33 +196,178 @@ impl SampleResult {
             status: SampleStatus::Processing,
             metrics: Vec::new(),
             completed_at: None,
+            duration: None,
+            runs: 1,
+            anomalies: Vec::new(),
+            texts: BTreeMap::new(),
+            annotation: SampleAnnotation::default(),
+            groups: BTreeMap::new(),
         }
     }
 
+    fn single_run() -> u32 {
+        1
+    }
+
+    /// Value of `metric` in this sample, with its duration available as
+    /// [`SAMPLE_DURATION_METRIC`](super::app::SAMPLE_DURATION_METRIC)
+    pub fn value(&self, metric: &str) -> Option<f64> {
+        if metric == super::app::SAMPLE_DURATION_METRIC {
+            return self
+                .duration
+                .map(|duration| duration.as_secs_f64() * 1000.0);
+        }
+        self.metrics
+            .iter()
+            .find(|(name, _)| name == metric)
+            .map(|(_, value)| *value)
+    }
+
     /// Mark sample as completed with metrics
     pub fn mark_completed(&mut self, metrics: Vec<(String, f64)>) {
         self.status = SampleStatus::Completed;
         self.metrics = metrics;
-        self.completed_at = Some(Instant::now());
+        self.completed_at = Some(Utc::now());
     }
 
     /// Mark sample as failed
     #[allow(dead_code)] // Used when sample processing fails
     pub fn mark_failed(&mut self, error: String) {
         self.status = SampleStatus::Failed(error);
-        self.completed_at = Some(Instant::now());
+        self.completed_at = Some(Utc::now());
+    }
+}
+
+/// Which data-point attributes hold text worth keeping with each sample
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TextCapture {
+    /// Attributes whose values are kept, e.g. `prompt` or `response`
+    pub attributes: Vec<String>,
+    /// Most characters kept of each value; longer text is cut off
+    pub max_chars: usize,
+}
+
+impl Default for TextCapture {
+    fn default() -> Self {
+        Self {
+            attributes: ["prompt", "response", "expected"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            max_chars: 2000,
+        }
+    }
+}
+
+impl TextCapture {
+    /// `text` cut down to `max_chars`, marking where it was cut
+    pub fn truncate(&self, text: String) -> String {
+        match text.char_indices().nth(self.max_chars) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text,
+        }
+    }
+}
+
+/// Record of an evaluator crash that triggered an automatic restart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvaluatorRestart {
+    /// Which restart this was (1-based)
+    pub attempt: u32,
+    /// Exit code of the crashed process, if it had one
+    pub exit_code: Option<i32>,
+    /// When the crash was observed
+    pub at: Instant,
+}
+
+/// Resource usage of the evaluator process at one point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceUsage {
+    /// CPU usage since the previous sample, where 100.0 is one full core
+    pub cpu_percent: f32,
+    /// Resident set size in bytes
+    pub memory_bytes: u64,
+    /// Number of open file descriptors, where the platform reports it
+    pub open_files: Option<usize>,
+}
+
+/// Time after which progress counts half as much towards the completion rate
+const ETA_HALF_LIFE: Duration = Duration::from_secs(30);
+
+/// Weight of each newly completed sample in the spread of time per sample
+const ETA_SPREAD_SMOOTHING: f64 = 0.1;
+
+/// Standard deviations either side of the estimate covered by the ETA range
+/// (about 90% confidence)
+const ETA_CONFIDENCE_Z: f64 = 1.645;
+
+/// Estimated time to completion with its confidence band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EtaRange {
+    /// Most likely time remaining
+    pub expected: Duration,
+    /// Optimistic end of the band
+    pub low: Duration,
+    /// Pessimistic end of the band
+    pub high: Duration,
+}
+
+/// Where a run is within one of the stages it passes through in order,
+/// such as its batches or the phases its handshake declares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageProgress {
+    /// Name of the stage, for phases
+    pub name: Option<String>,
+    /// Which stage the run is in, counting from 1
+    pub number: usize,
+    /// Number of stages
+    pub stages: usize,
+    /// Samples completed within the stage
+    pub completed: usize,
+    /// Samples in the stage
+    pub samples: usize,
+}
+
+impl StageProgress {
+    /// Stage of a run that has `completed` samples, out of stages of the
+    /// given names and sizes; past the last, the run stays in the last
+    pub fn locate(stages: &[(Option<&str>, usize)], completed: usize) -> Option<Self> {
+        let mut start = 0;
+        for (index, (name, samples)) in stages.iter().enumerate() {
+            if completed < start + samples || index + 1 == stages.len() {
+                return Some(Self {
+                    name: name.map(str::to_string),
+                    number: index + 1,
+                    stages: stages.len(),
+                    completed: completed.saturating_sub(start).min(*samples),
+                    samples: *samples,
+                });
+            }
+            start += samples;
+        }
+        None
     }
 }
 
-/// ETA calculator with rolling average
+/// ETA calculator using an exponentially weighted completion rate, so bursty
+/// evaluators do not make it jump around
 #[derive(Debug, Clone)]
 pub struct EtaCalculator {
     /// When evaluation started
     start_time: Instant,
-    /// Recent completion times for rolling average
-    completion_history: Vec<(Instant, usize)>, // (time, samples_completed)
-    /// Maximum history size
-    max_history: usize,
+    /// When the current pause began, if paused
+    paused_at: Option<Instant>,
+    /// Time spent in pauses that have ended
+    paused_total: Duration,
+    /// Active time and sample count at the last progress update
+    last_progress: (Duration, usize),
+    /// Completed samples, decayed by age
+    weighted_samples: f64,
+    /// Active seconds, decayed by age
+    weighted_seconds: f64,
+    /// Weighted variance of the seconds each sample took
+    variance: f64,
 }
 
 impl EtaCalculator {
@@ -179,63 +375,83 @@ impl EtaCalculator {
     pub fn new() -> Self {
         Self {
             start_time: Instant::now(),
-            completion_history: Vec::new(),
-            max_history: 10, // Keep last 10 data points for rolling average
+            paused_at: None,
+            paused_total: Duration::ZERO,
+            last_progress: (Duration::ZERO, 0),
+            weighted_samples: 0.0,
+            weighted_seconds: 0.0,
+            variance: 0.0,
         }
     }
 
-    /// Record progress update
-    pub fn record_progress(&mut self, completed: usize) {
-        let now = Instant::now();
-        self.completion_history.push((now, completed));
-
-        // Keep only recent history
-        if self.completion_history.len() > self.max_history {
-            self.completion_history.remove(0);
-        }
+    /// Stop the clock; time spent paused does not count towards the rate
+    pub fn pause(&mut self) {
+        self.paused_at.get_or_insert_with(Instant::now);
     }
 
-    /// Calculate ETA based on current progress
-    pub fn calculate_eta(&self, completed: usize, total: usize) -> Option<Duration> {
-        if completed == 0 || completed >= total {
-            return None;
+    /// Restart the clock after a pause
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_total += paused_at.elapsed();
         }
+    }
 
-        let rate = self.calculate_completion_rate(completed)?;
-        let remaining = total - completed;
-        let eta_seconds = remaining as f64 / rate;
-
-        Some(Duration::from_secs_f64(eta_seconds))
+    /// Record progress update
+    pub fn record_progress(&mut self, completed: usize) {
+        self.record_progress_at(self.elapsed(), completed);
     }
 
-    /// Calculate completion rate (samples per second)
-    fn calculate_completion_rate(&self, current_completed: usize) -> Option<f64> {
-        if self.completion_history.len() < 2 {
-            // Fall back to overall rate if not enough history
-            let elapsed = self.start_time.elapsed().as_secs_f64();
-            if elapsed > 0.0 && current_completed > 0 {
-                return Some(current_completed as f64 / elapsed);
-            }
-            return None;
+    /// Record that `completed` samples were done after `elapsed` active time
+    fn record_progress_at(&mut self, elapsed: Duration, completed: usize) {
+        let (last_elapsed, last_completed) = self.last_progress;
+        let Some(new_samples) = completed.checked_sub(last_completed).filter(|&n| n > 0) else {
+            return;
+        };
+        self.last_progress = (elapsed, completed);
+        let interval = elapsed.saturating_sub(last_elapsed).as_secs_f64();
+
+        // The samples since the last update share its interval evenly
+        let seconds = interval / new_samples as f64;
+        if let Some(mean) = self.mean_seconds() {
+            let weight = 1.0 - (1.0 - ETA_SPREAD_SMOOTHING).powi(new_samples as i32);
+            let difference = seconds - mean;
+            self.variance = (1.0 - weight) * (self.variance + weight * difference * difference);
         }
 
-        // Use recent history for rolling average
-        let recent_start = self.completion_history[0];
-        let recent_end = self.completion_history[self.completion_history.len() - 1];
+        let decay = 0.5f64.powf(interval / ETA_HALF_LIFE.as_secs_f64());
+        self.weighted_samples = self.weighted_samples * decay + new_samples as f64;
+        self.weighted_seconds = self.weighted_seconds * decay + interval;
+    }
 
-        let time_diff = recent_end.0.duration_since(recent_start.0).as_secs_f64();
-        let samples_diff = recent_end.1.saturating_sub(recent_start.1) as f64;
+    /// Recent average seconds per sample
+    fn mean_seconds(&self) -> Option<f64> {
+        (self.weighted_samples > 0.0 && self.weighted_seconds > 0.0)
+            .then(|| self.weighted_seconds / self.weighted_samples)
+    }
 
-        if time_diff > 0.0 && samples_diff > 0.0 {
-            Some(samples_diff / time_diff)
-        } else {
-            None
+    /// Calculate ETA and its confidence band based on current progress
+    pub fn calculate_eta(&self, completed: usize, total: usize) -> Option<EtaRange> {
+        if completed == 0 || completed >= total {
+            return None;
         }
+        let mean = self.mean_seconds()?;
+        let remaining = (total - completed) as f64;
+
+        // Per-sample deviations partly cancel out over the remaining samples
+        let expected = remaining * mean;
+        let spread = ETA_CONFIDENCE_Z * self.variance.sqrt() * remaining.sqrt();
+        Some(EtaRange {
+            expected: Duration::from_secs_f64(expected),
+            low: Duration::from_secs_f64((expected - spread).max(0.0)),
+            high: Duration::from_secs_f64(expected + spread),
+        })
     }
 
-    /// Get elapsed time since start
+    /// Get time spent running since start, excluding pauses
     pub fn elapsed(&self) -> Duration {
-        self.start_time.elapsed()
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        now.duration_since(self.start_time)
+            .saturating_sub(self.paused_total)
     }
 }
 
@@ -255,7 +471,7 @@ impl ValidJson {
         // Parse the JSON to validate it's well-formed
         serde_json::from_str::<serde_json::Value>(&json_str)
             .map_err(|e| JsonValidationError::MalformedJson(e.to_string()))?;
-        
+
         Ok(ValidJson(json_str))
     }
 
@@ -287,31 +503,224 @@ pub enum JsonValidationError {
     MalformedJson(String),
 }
 
-/// Phantom types for terminal state
-#[derive(Debug)]
-pub struct Uninitialized;
-
+/// Phantom type for a terminal set up for drawing
 #[derive(Debug)]
 pub struct Initialized;
 
-/// Type-safe terminal state that tracks initialization
-#[derive(Debug)]
-pub struct TerminalState<S> {
-    _phantom: PhantomData<S>,
+/// Destructive action that only happens once the user confirms it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmAction {
+    /// Kill the running evaluator
+    KillEvaluator,
+    /// Quit while the evaluation is still running, killing the evaluator
+    Quit,
 }
 
-impl TerminalState<Uninitialized> {
-    /// Create new uninitialized terminal state
-    pub fn new() -> Self {
-        Self {
-            _phantom: PhantomData,
+impl ConfirmAction {
+    /// Question shown to the user
+    pub fn prompt(&self) -> &'static str {
+        match self {
+            ConfirmAction::KillEvaluator => "Kill the evaluator?",
+            ConfirmAction::Quit => "Evaluation running — quit and kill the evaluator?",
         }
     }
 }
 
-impl Default for TerminalState<Uninitialized> {
-    fn default() -> Self {
-        Self::new()
+/// Views of the run the UI switches between
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tab {
+    /// Progress, recent samples and statistics at a glance
+    #[default]
+    Overview,
+    /// The sample list beside the detail of the selected sample
+    Samples,
+    /// Statistics of each metric, with the detail of the selected one
+    Metrics,
+    /// Evaluator stderr and the timeline of the run
+    Logs,
+    /// Lines the evaluator wrote to stdout, as received
+    Raw,
+    /// Chosen metrics plotted over the run
+    Chart,
+    /// Groups of an attribute such as the model, ranked by a metric
+    Leaderboard,
+    /// Two metrics of each sample plotted against each other
+    Scatter,
+    /// A view registered after the built-in ones, by its position among
+    /// the extra views
+    Extra(usize),
+}
+
+impl Tab {
+    /// Every built-in tab, in the order of the tab bar
+    pub const ALL: [Tab; 8] = [
+        Tab::Overview,
+        Tab::Samples,
+        Tab::Metrics,
+        Tab::Logs,
+        Tab::Raw,
+        Tab::Chart,
+        Tab::Leaderboard,
+        Tab::Scatter,
+    ];
+
+    /// Position of the tab in the tab bar
+    pub fn index(self) -> usize {
+        match self {
+            Tab::Overview => 0,
+            Tab::Samples => 1,
+            Tab::Metrics => 2,
+            Tab::Logs => 3,
+            Tab::Raw => 4,
+            Tab::Chart => 5,
+            Tab::Leaderboard => 6,
+            Tab::Scatter => 7,
+            Tab::Extra(extra) => Tab::ALL.len() + extra,
+        }
+    }
+
+    /// Tab at `index` in the tab bar
+    pub fn from_index(index: usize) -> Tab {
+        Tab::ALL
+            .get(index)
+            .copied()
+            .unwrap_or_else(|| Tab::Extra(index - Tab::ALL.len()))
+    }
+
+    /// Tab after this one, wrapping around after `extra_views` extra views
+    pub fn next(self, extra_views: usize) -> Tab {
+        Tab::from_index((self.index() + 1) % (Tab::ALL.len() + extra_views))
+    }
+
+    /// Name of a built-in tab; extra views name themselves
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Overview => "Overview",
+            Tab::Samples => "Samples",
+            Tab::Metrics => "Metrics",
+            Tab::Logs => "Logs",
+            Tab::Raw => "Raw",
+            Tab::Chart => "Chart",
+            Tab::Leaderboard => "Leaderboard",
+            Tab::Scatter => "Scatter",
+            Tab::Extra(_) => "View",
+        }
+    }
+
+    /// Whether the tab lists the newest entries last and follows them,
+    /// counting its scroll position back from the newest
+    pub fn follows_tail(self) -> bool {
+        matches!(self, Tab::Logs | Tab::Raw)
+    }
+}
+
+/// What the chart plots metrics against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartAxis {
+    /// Position of the sample in the run
+    #[default]
+    SampleIndex,
+    /// When the sample completed
+    WallClock,
+}
+
+impl ChartAxis {
+    /// The other axis
+    pub fn toggled(self) -> ChartAxis {
+        match self {
+            ChartAxis::SampleIndex => ChartAxis::WallClock,
+            ChartAxis::WallClock => ChartAxis::SampleIndex,
+        }
+    }
+}
+
+/// Movement through the list of the active tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scroll {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+}
+
+impl Scroll {
+    /// Entries moved by a page
+    const PAGE: usize = 10;
+
+    /// Position after moving from `position` in a list of `len` entries
+    pub fn apply(self, position: usize, len: usize) -> usize {
+        let last = len.saturating_sub(1);
+        let position = match self {
+            Scroll::Up => position.saturating_sub(1),
+            Scroll::Down => position.saturating_add(1),
+            Scroll::PageUp => position.saturating_sub(Self::PAGE),
+            Scroll::PageDown => position.saturating_add(Self::PAGE),
+            Scroll::Top => 0,
+            Scroll::Bottom => last,
+        };
+        position.min(last)
+    }
+
+    /// The same movement in a list whose positions count from the bottom
+    pub fn reversed(self) -> Scroll {
+        match self {
+            Scroll::Up => Scroll::Down,
+            Scroll::Down => Scroll::Up,
+            Scroll::PageUp => Scroll::PageDown,
+            Scroll::PageDown => Scroll::PageUp,
+            Scroll::Top => Scroll::Bottom,
+            Scroll::Bottom => Scroll::Top,
+        }
+    }
+}
+
+/// Line the evaluator wrote to stdout, with whether it was understood
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawLine {
+    pub received_at: DateTime<Utc>,
+    pub text: String,
+    /// Why the line was not accepted as protocol output
+    pub error: Option<String>,
+}
+
+/// How long a notification stays on screen
+pub const NOTIFICATION_DURATION: Duration = Duration::from_secs(8);
+
+/// How much attention a notification asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationLevel {
+    /// Something worth knowing, e.g. an anomalous sample
+    Info,
+    /// A problem the run carries on past, e.g. an unparseable line
+    Warning,
+}
+
+/// Short-lived message drawing attention to something during a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub message: String,
+    pub level: NotificationLevel,
+    /// Times the message was raised in a row
+    pub count: usize,
+    pub raised_at: Instant,
+}
+
+impl Notification {
+    /// Raise a notification now
+    pub fn new(message: String, level: NotificationLevel) -> Self {
+        Self {
+            message,
+            level,
+            count: 1,
+            raised_at: Instant::now(),
+        }
+    }
+
+    /// Whether the notification has been shown long enough
+    pub fn is_expired(&self) -> bool {
+        self.raised_at.elapsed() >= NOTIFICATION_DURATION
     }
 }
 
@@ -330,14 +739,91 @@ pub trait Action: private::Sealed {
 /// Concrete UI actions
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UiAction {
-    /// User requested quit
+    /// User requested quit (asks for confirmation while the run is going)
     Quit,
+    /// Quit right away, even while the run is going
+    ForceQuit,
     /// Terminal was resized
     Resize(TerminalSize),
     /// Pause/resume evaluation
     TogglePause,
     /// Refresh display
     Refresh,
+    /// Show/hide the evaluator stderr pane
+    ToggleStderr,
+    /// Show/hide the output buffer diagnostics pane
+    ToggleDiagnostics,
+    /// Show/hide the debug view of PrEval's own performance
+    ToggleDebug,
+    /// Show/hide the timeline of notable events
+    ToggleTimeline,
+    /// Show/hide the comparison with the baseline run
+    ToggleComparison,
+    /// Group the statistics table by the next attribute
+    CycleGroupBy,
+    /// Kill the evaluator (asks for confirmation first)
+    KillEvaluator,
+    /// Throw away the current run and start the evaluator again
+    RestartRun,
+    /// Answer yes to a pending confirmation
+    Confirm,
+    /// Answer no to a pending confirmation
+    Cancel,
+    /// Switch to a tab
+    SelectTab(Tab),
+    /// Switch to the next tab
+    NextTab,
+    /// Move through the list of the active tab
+    Scroll(Scroll),
+    /// Plot or stop plotting the metric selected in the chart
+    ToggleSeries,
+    /// Plot against sample index or wall-clock time
+    ToggleChartAxis,
+    /// Fold the raw view's JSON at the next nesting depth
+    CycleJsonFold,
+    /// Write a report of the run so far to disk
+    ExportReport,
+    /// Save a copy of the screen to disk
+    SaveScreen,
+    /// Copy the selected sample, or the run summary while it is shown, to
+    /// the clipboard
+    Copy,
+    /// Rank the leaderboard by the next metric
+    CycleLeaderboardMetric,
+    /// Rank the leaderboard the other way round
+    ReverseLeaderboard,
+    /// Plot the next metric across the scatter plot
+    CycleScatterX,
+    /// Plot the next metric up the scatter plot
+    CycleScatterY,
+    /// Open the outlier selected in the scatter plot in the sample list
+    OpenOutlier,
+    /// Show/hide the keybindings help
+    ToggleHelp,
+    /// Open the detail of the sample at an index of the sample store
+    OpenSample(usize),
+    /// Bookmark the selected sample, or remove its bookmark
+    ToggleBookmark,
+    /// Start typing a note for the selected sample
+    StartSampleNote,
+    /// Note typed so far for the selected sample, saved once `done`, or
+    /// none when typing it was cancelled
+    EditSampleNote { note: Option<String>, done: bool },
+    /// Start typing a filter for the sample list
+    StartSampleSearch,
+    /// Filter the sample list by the sample ids matching `query`, typed
+    /// until `done`
+    SearchSamples { query: String, done: bool },
+    /// Show the sample list's next status filter
+    CycleStatusFilter,
+    /// Sort the sample list by the next metric
+    CycleSampleSort,
+    /// Sort the sample list the other way round
+    ReverseSampleSort,
+    /// Select the next failed sample in the sample list
+    NextFailedSample,
+    /// Select the previous failed sample in the sample list
+    PreviousFailedSample,
 }
 
 impl private::Sealed for UiAction {}
@@ -346,14 +832,51 @@ impl Action for UiAction {
     fn description(&self) -> &str {
         match self {
             UiAction::Quit => "quit",
+            UiAction::ForceQuit => "force quit",
             UiAction::Resize(_) => "resize",
             UiAction::TogglePause => "toggle pause",
             UiAction::Refresh => "refresh",
+            UiAction::ToggleStderr => "toggle stderr",
+            UiAction::ToggleDiagnostics => "toggle diagnostics",
+            UiAction::ToggleDebug => "toggle debug view",
+            UiAction::ToggleTimeline => "toggle timeline",
+            UiAction::ToggleComparison => "toggle baseline comparison",
+            UiAction::CycleGroupBy => "cycle group by",
+            UiAction::KillEvaluator => "kill evaluator",
+            UiAction::RestartRun => "restart run",
+            UiAction::Confirm => "confirm",
+            UiAction::Cancel => "cancel",
+            UiAction::SelectTab(_) => "select tab",
+            UiAction::NextTab => "next tab",
+            UiAction::Scroll(_) => "scroll",
+            UiAction::ToggleSeries => "toggle series",
+            UiAction::ToggleChartAxis => "toggle chart axis",
+            UiAction::CycleJsonFold => "cycle json fold",
+            UiAction::ExportReport => "export report",
+            UiAction::SaveScreen => "save screen",
+            UiAction::Copy => "copy",
+            UiAction::CycleLeaderboardMetric => "cycle leaderboard metric",
+            UiAction::ReverseLeaderboard => "reverse leaderboard",
+            UiAction::CycleScatterX => "cycle scatter x",
+            UiAction::CycleScatterY => "cycle scatter y",
+            UiAction::OpenOutlier => "open outlier",
+            UiAction::ToggleHelp => "toggle help",
+            UiAction::OpenSample(_) => "open sample",
+            UiAction::StartSampleSearch => "start sample search",
+            UiAction::ToggleBookmark => "toggle bookmark",
+            UiAction::StartSampleNote => "start sample note",
+            UiAction::EditSampleNote { .. } => "edit sample note",
+            UiAction::SearchSamples { .. } => "search samples",
+            UiAction::CycleStatusFilter => "cycle status filter",
+            UiAction::CycleSampleSort => "cycle sample sort",
+            UiAction::ReverseSampleSort => "reverse sample sort",
+            UiAction::NextFailedSample => "next failed sample",
+            UiAction::PreviousFailedSample => "previous failed sample",
         }
     }
 }
 
-// Note: All tests in this module have been eliminated through type constraints:
+// Note: Most tests in this module have been eliminated through type constraints:
 // - EvaluatorName and EvaluatorCommand cannot be empty (enforced by nutype)
 // - Terminal dimensions must be positive (enforced by nutype)
 // - Action trait is sealed, preventing external implementations
@@ -361,3 +884,42 @@ impl Action for UiAction {
 //
 // The type system now makes these test scenarios impossible to express,
 // providing compile-time guarantees instead of runtime checks.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steady_progress_gives_a_narrow_eta() {
+        let mut eta = EtaCalculator::new();
+        for completed in 1..=20 {
+            eta.record_progress_at(Duration::from_secs(completed as u64), completed);
+        }
+
+        let range = eta.calculate_eta(20, 80).unwrap();
+        assert_eq!(range.expected.as_secs(), 60);
+        assert_eq!(range.low, range.high);
+        assert!(eta.calculate_eta(80, 80).is_none());
+    }
+
+    #[test]
+    fn bursts_widen_the_band_without_moving_the_estimate_much() {
+        let mut eta = EtaCalculator::new();
+        // Five samples arriving at once every five seconds: one second each
+        let mut completed = 0;
+        for batch in 1..=20 {
+            for _ in 0..5 {
+                completed += 1;
+                eta.record_progress_at(Duration::from_secs(batch * 5), completed);
+
+                if batch > 10 {
+                    let range = eta.calculate_eta(completed, 200).unwrap();
+                    let steady = (200 - completed) as f64;
+                    let expected = range.expected.as_secs_f64();
+                    assert!((expected - steady).abs() < steady * 0.15, "{:?}", range);
+                    assert!(range.low < range.expected && range.expected < range.high);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// Getting the user's attention when a run needs it, for users who tabbed away
+
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+/// How the terminal is asked to get the user's attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlertStyle {
+    /// No alerts
+    #[default]
+    Off,
+    /// Ring the terminal bell (BEL)
+    Bell,
+    /// Desktop notification through the terminal (OSC 9), as supported by
+    /// e.g. iTerm2, kitty, WezTerm and Windows Terminal
+    Osc9,
+}
+
+impl AlertStyle {
+    /// Escape sequence raising an alert with `message`, if any
+    fn sequence(self, message: &str) -> Option<String> {
+        match self {
+            AlertStyle::Off => None,
+            AlertStyle::Bell => Some("\x07".to_string()),
+            // Control characters would end the sequence early
+            AlertStyle::Osc9 => Some(format!(
+                "\x1b]9;{}\x07",
+                message.replace(|c: char| c.is_control(), " ")
+            )),
+        }
+    }
+
+    /// Raise an alert with `message` on the terminal
+    pub(crate) fn raise(self, message: &str) -> io::Result<()> {
+        let Some(sequence) = self.sequence(message) else {
+            return Ok(());
+        };
+        let mut stdout = io::stdout();
+        stdout.write_all(sequence.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+impl std::str::FromStr for AlertStyle {
+    type Err = AlertStyleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" => Ok(Self::Off),
+            "bell" | "bel" => Ok(Self::Bell),
+            "osc9" | "desktop" => Ok(Self::Osc9),
+            other => Err(AlertStyleError(other.to_string())),
+        }
+    }
+}
+
+/// Error for unrecognized alert styles
+#[derive(Debug, thiserror::Error)]
+#[error("unknown alert '{0}' (expected 'off', 'bell' or 'osc9')")]
+pub struct AlertStyleError(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alerts_are_escape_sequences() {
+        assert_eq!(AlertStyle::Off.sequence("done"), None);
+        assert_eq!(AlertStyle::Bell.sequence("done").unwrap(), "\x07");
+        assert_eq!(
+            AlertStyle::Osc9.sequence("Run failed:\nexit 1").unwrap(),
+            "\x1b]9;Run failed: exit 1\x07"
+        );
+        assert_eq!("desktop".parse::<AlertStyle>().unwrap(), AlertStyle::Osc9);
+        assert!("siren".parse::<AlertStyle>().is_err());
+    }
+}
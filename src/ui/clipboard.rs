@@ -0,0 +1,85 @@
+// Markdown copies of samples and run summaries for the system clipboard,
+// for pasting into issues and chats
+
+use crate::state::types::{SampleResult, SampleStatus};
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+
+/// The system clipboard, opened on first use
+///
+/// On some platforms copied text is only available while the clipboard
+/// is open, so it is kept open for the rest of the session.
+#[derive(Default)]
+pub(crate) struct Clipboard {
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    /// Replace the contents of the system clipboard with `text`
+    pub(crate) fn copy(&mut self, text: String) -> Result<()> {
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard,
+            None => self
+                .clipboard
+                .insert(arboard::Clipboard::new().context("No system clipboard available")?),
+        };
+        clipboard
+            .set_text(text)
+            .context("Failed to copy to the clipboard")
+    }
+}
+
+/// Everything known about `sample`, as Markdown
+pub(crate) fn sample_markdown(sample: &SampleResult) -> String {
+    let mut markdown = format!("### Sample `{}`\n\n", sample.sample_id);
+    let status = match &sample.status {
+        SampleStatus::Processing => "processing".to_string(),
+        SampleStatus::Completed => "completed".to_string(),
+        SampleStatus::Failed(error) => format!("failed: {}", error),
+    };
+    writeln!(markdown, "- **Status:** {}", status).ok();
+    if sample.runs > 1 {
+        writeln!(markdown, "- **Runs:** {} (metrics averaged)", sample.runs).ok();
+    }
+    if let Some(duration) = sample.duration {
+        writeln!(markdown, "- **Duration:** {:.3}s", duration.as_secs_f64()).ok();
+    }
+
+    if !sample.metrics.is_empty() {
+        markdown.push_str("\n| Metric | Value |\n| --- | ---: |\n");
+        for (name, value) in &sample.metrics {
+            let flag = if sample.anomalies.contains(name) {
+                " ⚠ anomalous"
+            } else {
+                ""
+            };
+            writeln!(markdown, "| {} | {:.4}{} |", name, value, flag).ok();
+        }
+    }
+
+    for (attribute, text) in &sample.texts {
+        write!(markdown, "\n**{}**\n\n```\n{}\n```\n", attribute, text).ok();
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn samples_copy_as_markdown() {
+        let mut sample = SampleResult::new_processing("q-17".to_string());
+        sample.mark_failed("accuracy < 0.5".to_string());
+        sample.metrics = vec![("accuracy".to_string(), 0.25)];
+        sample.anomalies = vec!["accuracy".to_string()];
+        sample.texts = BTreeMap::from([("prompt".to_string(), "What is 2+2?".to_string())]);
+
+        let markdown = sample_markdown(&sample);
+        assert!(markdown.starts_with("### Sample `q-17`\n"));
+        assert!(markdown.contains("- **Status:** failed: accuracy < 0.5\n"));
+        assert!(markdown.contains("| accuracy | 0.2500 ⚠ anomalous |\n"));
+        assert!(markdown.contains("**prompt**\n\n```\nWhat is 2+2?\n```\n"));
+    }
+}
@@ -1,4 +1,7 @@
 use crate::state::types::{TerminalSize, UiAction};
+use crate::ui::keymap::Keymap;
+use crate::ui::mouse::SharedHitMap;
+use crate::ui::view::{SharedViews, ViewRegistry};
 use anyhow::{Context, Result};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use std::time::Duration;
@@ -8,12 +11,47 @@ use tokio::sync::mpsc;
 pub(crate) struct EventHandler {
     /// Channel to send actions to the main app
     action_tx: mpsc::Sender<UiAction>,
+    /// Which keys trigger which action
+    keymap: Keymap,
+    /// What the renderer drew where, for turning clicks into actions
+    hit_map: SharedHitMap,
+    /// Views offered the keys pressed while they are shown
+    views: SharedViews,
+    /// Text typed so far, while keys type it instead of acting
+    input: Option<TextInput>,
+}
+
+/// Text the keys are typing instead of acting
+#[derive(Clone)]
+enum TextInput {
+    /// Filter for the sample list
+    Search(String),
+    /// Note for the selected sample
+    Note(String),
 }
 
 impl EventHandler {
-    /// Create a new event handler
-    pub(crate) fn new(action_tx: mpsc::Sender<UiAction>) -> Self {
-        Self { action_tx }
+    /// Create a new event handler looking keys up in `keymap`
+    pub(crate) fn new(action_tx: mpsc::Sender<UiAction>, keymap: Keymap) -> Self {
+        Self {
+            action_tx,
+            keymap,
+            hit_map: SharedHitMap::default(),
+            views: SharedViews::new(ViewRegistry::default()),
+            input: None,
+        }
+    }
+
+    /// Set where the renderer records what clicks can select (builder pattern)
+    pub(crate) fn hit_map(mut self, hit_map: SharedHitMap) -> Self {
+        self.hit_map = hit_map;
+        self
+    }
+
+    /// Set the views the renderer draws for each tab (builder pattern)
+    pub(crate) fn views(mut self, views: SharedViews) -> Self {
+        self.views = views;
+        self
     }
 
     /// Start listening for events
@@ -42,9 +80,14 @@ impl EventHandler {
     }
 
     /// Handle a terminal event and convert to an action
-    fn handle_event(&self, event: Event) -> Result<Option<UiAction>> {
+    fn handle_event(&mut self, event: Event) -> Result<Option<UiAction>> {
         match event {
             Event::Key(key_event) => Ok(self.handle_key_event(key_event)),
+            Event::Mouse(mouse_event) => Ok(self
+                .hit_map
+                .lock()
+                .ok()
+                .and_then(|hit_map| hit_map.action(&mouse_event))),
             Event::Resize(width, height) => {
                 // Try to create valid terminal size
                 match TerminalSize::try_new(width, height) {
@@ -60,31 +103,84 @@ impl EventHandler {
     }
 
     /// Handle keyboard events
-    fn handle_key_event(&self, key: KeyEvent) -> Option<UiAction> {
-        match (key.code, key.modifiers) {
-            // Quit on 'q' or Ctrl+C
-            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(UiAction::Quit),
-            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(UiAction::Quit),
-
-            // Pause/resume on space
-            (KeyCode::Char(' '), KeyModifiers::NONE) => Some(UiAction::TogglePause),
-
-            // Force refresh on Ctrl+L
-            (KeyCode::Char('l'), KeyModifiers::CONTROL) => Some(UiAction::Refresh),
-
-            _ => None, // Ignore other keys
+    fn handle_key_event(&mut self, key: KeyEvent) -> Option<UiAction> {
+        if self.input.is_some() {
+            return self.handle_input_key(key);
+        }
+        let shown = self.hit_map.lock().ok().and_then(|hits| hits.view());
+        if let Some(action) = shown
+            .and_then(|tab| self.views.get(tab))
+            .and_then(|view| view.handle_key(&key))
+        {
+            return Some(action);
+        }
+        let action = self.keymap.action(&key);
+        match action {
+            Some(UiAction::StartSampleSearch) => {
+                self.input = Some(TextInput::Search(String::new()));
+            }
+            Some(UiAction::StartSampleNote) => {
+                self.input = Some(TextInput::Note(String::new()));
+            }
+            _ => {}
         }
+        action
+    }
+
+    /// Type the sample filter or note: Enter keeps it, and Esc clears the
+    /// filter or cancels the note
+    fn handle_input_key(&mut self, key: KeyEvent) -> Option<UiAction> {
+        let text = match self.input.as_mut()? {
+            TextInput::Search(text) | TextInput::Note(text) => text,
+        };
+        let (done, cancelled) = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Some(UiAction::Quit);
+            }
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                text.push(c);
+                (false, false)
+            }
+            KeyCode::Backspace => {
+                text.pop();
+                (false, false)
+            }
+            KeyCode::Enter => (true, false),
+            KeyCode::Esc => {
+                text.clear();
+                (true, true)
+            }
+            _ => return None,
+        };
+        let input = if done {
+            self.input.take()?
+        } else {
+            self.input.clone()?
+        };
+        Some(match input {
+            TextInput::Search(query) => UiAction::SearchSamples { query, done },
+            TextInput::Note(note) => UiAction::EditSampleNote {
+                note: (!cancelled).then_some(note),
+                done,
+            },
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::types::{Scroll, Tab};
+    use crossterm::event::{KeyCode, KeyModifiers};
 
     #[tokio::test]
     async fn test_quit_on_q_key() {
         let (tx, _rx) = mpsc::channel(1);
-        let handler = EventHandler::new(tx);
+        let mut handler = EventHandler::new(tx, Keymap::default());
 
         let key_event = KeyEvent {
             code: KeyCode::Char('q'),
@@ -100,7 +196,7 @@ mod tests {
     #[tokio::test]
     async fn test_quit_on_ctrl_c() {
         let (tx, _rx) = mpsc::channel(1);
-        let handler = EventHandler::new(tx);
+        let mut handler = EventHandler::new(tx, Keymap::default());
 
         let key_event = KeyEvent {
             code: KeyCode::Char('c'),
@@ -113,10 +209,21 @@ mod tests {
         assert_eq!(action, Some(UiAction::Quit));
     }
 
+    #[tokio::test]
+    async fn test_force_quit_on_shift_q() {
+        let (tx, _rx) = mpsc::channel(1);
+        let mut handler = EventHandler::new(tx, Keymap::default());
+
+        let key_event = KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::SHIFT);
+
+        let action = handler.handle_key_event(key_event);
+        assert_eq!(action, Some(UiAction::ForceQuit));
+    }
+
     #[tokio::test]
     async fn test_toggle_pause_on_space() {
         let (tx, _rx) = mpsc::channel(1);
-        let handler = EventHandler::new(tx);
+        let mut handler = EventHandler::new(tx, Keymap::default());
 
         let key_event = KeyEvent {
             code: KeyCode::Char(' '),
@@ -129,6 +236,145 @@ mod tests {
         assert_eq!(action, Some(UiAction::TogglePause));
     }
 
+    #[tokio::test]
+    async fn test_kill_and_confirmation_keys() {
+        let (tx, _rx) = mpsc::channel(1);
+        let mut handler = EventHandler::new(tx, Keymap::default());
+        let key = |code| KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+            kind: event::KeyEventKind::Press,
+            state: event::KeyEventState::NONE,
+        };
+
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('k'))),
+            Some(UiAction::KillEvaluator)
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('y'))),
+            Some(UiAction::Confirm)
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('Y'))),
+            Some(UiAction::Copy)
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Esc)),
+            Some(UiAction::Cancel)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tab_and_scroll_keys() {
+        let (tx, _rx) = mpsc::channel(1);
+        let mut handler = EventHandler::new(tx, Keymap::default());
+        let key = |code| KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+            kind: event::KeyEventKind::Press,
+            state: event::KeyEventState::NONE,
+        };
+
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('2'))),
+            Some(UiAction::SelectTab(Tab::Samples))
+        );
+        assert_eq!(handler.handle_key_event(key(KeyCode::Char('9'))), None);
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Tab)),
+            Some(UiAction::NextTab)
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::PageDown)),
+            Some(UiAction::Scroll(Scroll::PageDown))
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('x'))),
+            Some(UiAction::ToggleChartAxis)
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('o'))),
+            Some(UiAction::OpenOutlier)
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('B'))),
+            Some(UiAction::ToggleBookmark)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_typing_a_sample_note() {
+        let (tx, _rx) = mpsc::channel(1);
+        let mut handler = EventHandler::new(tx, Keymap::default());
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let note = |note: Option<&str>, done| UiAction::EditSampleNote {
+            note: note.map(str::to_string),
+            done,
+        };
+
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('a'))),
+            Some(UiAction::StartSampleNote)
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('q'))),
+            Some(note(Some("q"), false))
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Enter)),
+            Some(note(Some("q"), true))
+        );
+
+        handler.handle_key_event(key(KeyCode::Char('a')));
+        handler.handle_key_event(key(KeyCode::Char('x')));
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Esc)),
+            Some(note(None, true))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_typing_a_sample_search() {
+        let (tx, _rx) = mpsc::channel(1);
+        let mut handler = EventHandler::new(tx, Keymap::default());
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let search = |query: &str, done| UiAction::SearchSamples {
+            query: query.to_string(),
+            done,
+        };
+
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('/'))),
+            Some(UiAction::StartSampleSearch)
+        );
+        // Bound keys type into the filter instead of acting
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('q'))),
+            Some(search("q", false))
+        );
+        handler.handle_key_event(key(KeyCode::Char('x')));
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Backspace)),
+            Some(search("q", false))
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Enter)),
+            Some(search("q", true))
+        );
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Char('q'))),
+            Some(UiAction::Quit)
+        );
+
+        handler.handle_key_event(key(KeyCode::Char('/')));
+        handler.handle_key_event(key(KeyCode::Char('a')));
+        assert_eq!(
+            handler.handle_key_event(key(KeyCode::Esc)),
+            Some(search("", true))
+        );
+    }
+
     // Note: Tests for invalid terminal sizes are unnecessary because
     // the type system prevents creating TerminalSize with invalid dimensions
 }
@@ -0,0 +1,62 @@
+// Read-only TUI for runs from the history database
+
+use crate::history::database::StoredRun;
+use crate::ui::renderer::{install_panic_hook, EvaluatorPid, Renderer, TerminalCleanup};
+use crate::ui::theme::Theme;
+use crate::ui::widgets::stored_run::StoredRunView;
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+
+/// Samples moved by PgUp/PgDn
+const PAGE: usize = 10;
+
+/// Show `run` in the colors of `theme` until the user quits
+pub(crate) fn show_run(run: &StoredRun, theme: Theme) -> Result<()> {
+    let (_renderer, mut terminal) = Renderer::new()
+        .initialize()
+        .context("Failed to initialize terminal")?;
+    install_panic_hook(EvaluatorPid::default());
+    let _cleanup = TerminalCleanup;
+
+    let last = run.samples.len().saturating_sub(1);
+    let mut scroll = 0;
+    let mut detail = false;
+    loop {
+        terminal
+            .draw(|frame| {
+                frame.render_widget(
+                    StoredRunView::new(run)
+                        .scroll(scroll)
+                        .detail(detail)
+                        .theme(&theme),
+                    frame.area(),
+                )
+            })
+            .context("Failed to draw frame")?;
+
+        let Event::Key(key) = event::read().context("Failed to read terminal event")? else {
+            continue;
+        };
+        scroll = match (key.code, key.modifiers) {
+            (KeyCode::Enter, _) => {
+                detail = !run.samples.is_empty();
+                scroll
+            }
+            (KeyCode::Esc, _) if detail => {
+                detail = false;
+                scroll
+            }
+            (KeyCode::Char('q'), _) | (KeyCode::Esc, _) => break,
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => break,
+            (KeyCode::Down, _) | (KeyCode::Char('j'), _) => (scroll + 1).min(last),
+            (KeyCode::Up, _) | (KeyCode::Char('k'), _) => scroll.saturating_sub(1),
+            (KeyCode::PageDown, _) => (scroll + PAGE).min(last),
+            (KeyCode::PageUp, _) => scroll.saturating_sub(PAGE),
+            (KeyCode::Home, _) => 0,
+            (KeyCode::End, _) => last,
+            _ => scroll,
+        };
+    }
+
+    Ok(())
+}
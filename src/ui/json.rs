@@ -0,0 +1,138 @@
+// Pretty-printed, syntax-colored JSON for the raw view's inspector
+
+use crate::ui::theme::Theme;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use serde_json::Value;
+
+/// Lines of `value` pretty-printed with colors from `theme`
+///
+/// Objects and arrays nested `fold_depth` levels deep or more are folded
+/// into a single summary such as `{…3 keys}`; `None` shows everything.
+pub(crate) fn highlight(
+    value: &Value,
+    fold_depth: Option<usize>,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let mut printer = Printer {
+        theme,
+        fold_depth,
+        lines: Vec::new(),
+        current: Vec::new(),
+    };
+    printer.value(value, 0);
+    printer.newline(0);
+    printer.lines
+}
+
+/// Builds the lines of a value one span at a time
+struct Printer<'a> {
+    theme: &'a Theme,
+    fold_depth: Option<usize>,
+    lines: Vec<Line<'static>>,
+    current: Vec<Span<'static>>,
+}
+
+impl Printer<'_> {
+    fn push(&mut self, text: impl Into<String>, style: Style) {
+        self.current.push(Span::styled(text.into(), style));
+    }
+
+    /// End the current line, indenting the next one by `depth` levels
+    fn newline(&mut self, depth: usize) {
+        self.lines
+            .push(Line::from(std::mem::take(&mut self.current)));
+        if depth > 0 {
+            self.current.push(Span::raw("  ".repeat(depth)));
+        }
+    }
+
+    fn is_folded(&self, depth: usize) -> bool {
+        self.fold_depth
+            .is_some_and(|fold_depth| depth >= fold_depth)
+    }
+
+    /// Write `value`, nested `depth` levels deep, from the current position
+    fn value(&mut self, value: &Value, depth: usize) {
+        let punctuation = Style::default();
+        match value {
+            Value::Object(map) if map.is_empty() => self.push("{}", punctuation),
+            Value::Object(map) if self.is_folded(depth) => {
+                let keys = if map.len() == 1 { "key" } else { "keys" };
+                self.push(format!("{{…{} {}}}", map.len(), keys), self.theme.muted);
+            }
+            Value::Object(map) => {
+                self.push("{", punctuation);
+                for (i, (key, value)) in map.iter().enumerate() {
+                    self.newline(depth + 1);
+                    self.push(Value::from(key.as_str()).to_string(), self.theme.accent);
+                    self.push(": ", punctuation);
+                    self.value(value, depth + 1);
+                    if i + 1 < map.len() {
+                        self.push(",", punctuation);
+                    }
+                }
+                self.newline(depth);
+                self.push("}", punctuation);
+            }
+            Value::Array(items) if items.is_empty() => self.push("[]", punctuation),
+            Value::Array(items) if self.is_folded(depth) => {
+                let noun = if items.len() == 1 { "item" } else { "items" };
+                self.push(format!("[…{} {}]", items.len(), noun), self.theme.muted);
+            }
+            Value::Array(items) => {
+                self.push("[", punctuation);
+                for (i, item) in items.iter().enumerate() {
+                    self.newline(depth + 1);
+                    self.value(item, depth + 1);
+                    if i + 1 < items.len() {
+                        self.push(",", punctuation);
+                    }
+                }
+                self.newline(depth);
+                self.push("]", punctuation);
+            }
+            Value::String(_) => self.push(value.to_string(), self.theme.success),
+            Value::Number(_) => self.push(value.to_string(), self.theme.warning),
+            Value::Bool(_) | Value::Null => self.push(value.to_string(), self.theme.notice),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn prints_like_serde_json_when_unfolded() {
+        let value = json!({
+            "type": "result",
+            "sample": {"id": "a\"1", "tags": ["x", 2, null], "empty": {}},
+            "ok": true
+        });
+        let lines = highlight(&value, None, &Theme::DARK);
+        let pretty = serde_json::to_string_pretty(&value).unwrap();
+        assert_eq!(text(&lines), pretty.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn folds_containers_at_the_fold_depth() {
+        let value = json!({"sample": {"id": "1", "tags": ["x"]}, "ok": true});
+        let lines = highlight(&value, Some(1), &Theme::DARK);
+        assert_eq!(
+            text(&lines),
+            ["{", "  \"ok\": true,", "  \"sample\": {…2 keys}", "}"]
+        );
+
+        let lines = highlight(&value, Some(2), &Theme::DARK);
+        assert!(text(&lines).contains(&"    \"tags\": […1 item]".to_string()));
+
+        let lines = highlight(&value, Some(0), &Theme::DARK);
+        assert_eq!(text(&lines), ["{…2 keys}"]);
+    }
+}
@@ -0,0 +1,599 @@
+use crate::state::types::{Scroll, Tab, UiAction};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A key, with the modifiers held down while pressing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// The key `code` pressed on its own
+    pub(crate) fn plain(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    /// The key `c` pressed with Ctrl held down
+    pub(crate) fn ctrl(c: char) -> Self {
+        Self {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::CONTROL,
+        }
+    }
+
+    /// Whether `key` is a press of this key
+    ///
+    /// Shift is part of the character for character keys, e.g. `?`, so it
+    /// is not compared for them.
+    fn matches(&self, key: &KeyEvent) -> bool {
+        let modifiers = match key.code {
+            KeyCode::Char(_) => key.modifiers - KeyModifiers::SHIFT,
+            _ => key.modifiers,
+        };
+        key.code == self.code && modifiers == self.modifiers
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = KeymapError;
+
+    /// Parse a key such as `q`, `space`, `pgdn` or `ctrl+l`
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || KeymapError::InvalidKey(input.to_string());
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = input;
+        // Split at a '+' only when something follows it, so "+" is a key
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                _ => return Err(invalid()),
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            // Terminals report Ctrl with the lowercase letter
+            (Some(c), None) if modifiers.contains(KeyModifiers::CONTROL) => {
+                KeyCode::Char(c.to_ascii_lowercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                name => name
+                    .strip_prefix('f')
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| (1..=24).contains(n))
+                    .map(KeyCode::F)
+                    .ok_or_else(invalid)?,
+            },
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Keys bound to one action in the config file, e.g. `"q"` or `["q", "ctrl+c"]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    /// Every key in the list
+    fn keys(&self) -> &[String] {
+        match self {
+            KeyList::One(key) => std::slice::from_ref(key),
+            KeyList::Many(keys) => keys,
+        }
+    }
+}
+
+/// An action with the keys bound to it
+#[derive(Debug, Clone)]
+pub(crate) struct Binding {
+    /// Name of the action in the `[keys]` config section
+    pub(crate) name: String,
+    pub(crate) keys: Vec<KeyBinding>,
+    pub(crate) action: UiAction,
+    pub(crate) description: String,
+    /// Left out of the help, for actions only PrEval's developers need
+    pub(crate) hidden: bool,
+}
+
+/// Which keys trigger which action
+///
+/// The event handler looks key presses up here and the footer and help
+/// overlay show the same bindings, so they never disagree.
+#[derive(Debug, Clone)]
+pub(crate) struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    /// Default keymap with the actions named in `overrides` bound to the
+    /// given keys instead
+    ///
+    /// Fails for unknown actions, unparseable keys and keys bound to more
+    /// than one action.
+    pub(crate) fn with_overrides(
+        overrides: &BTreeMap<String, KeyList>,
+    ) -> Result<Self, KeymapError> {
+        let mut keymap = Self::default();
+        for (name, keys) in overrides {
+            let binding = keymap
+                .bindings
+                .iter_mut()
+                .find(|binding| &binding.name == name)
+                .ok_or_else(|| KeymapError::UnknownAction(name.clone()))?;
+            binding.keys = keys
+                .keys()
+                .iter()
+                .map(|key| key.parse())
+                .collect::<Result<_, _>>()?;
+        }
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+
+    /// Fail if a key is bound to more than one action
+    fn check_conflicts(&self) -> Result<(), KeymapError> {
+        for (i, first) in self.bindings.iter().enumerate() {
+            for second in &self.bindings[i + 1..] {
+                if let Some(key) = first.keys.iter().find(|key| second.keys.contains(key)) {
+                    return Err(KeymapError::Conflict {
+                        key: key.to_string(),
+                        first: first.name.clone(),
+                        second: second.name.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Action bound to the key pressed in `key`, if any
+    pub(crate) fn action(&self, key: &KeyEvent) -> Option<UiAction> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.keys.iter().any(|bound| bound.matches(key)))
+            .map(|binding| binding.action.clone())
+    }
+
+    /// First key bound to `action`, for hints such as the footer's
+    pub(crate) fn key(&self, action: &UiAction) -> Option<KeyBinding> {
+        self.bindings
+            .iter()
+            .find(|binding| &binding.action == action)
+            .and_then(|binding| binding.keys.first().copied())
+    }
+
+    /// Every binding, in the order the help lists them
+    pub(crate) fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bind = |name: &str, keys: Vec<KeyBinding>, action, description: &str| Binding {
+            name: name.to_string(),
+            keys,
+            action,
+            description: description.to_string(),
+            hidden: false,
+        };
+        let key = |c| KeyBinding::plain(KeyCode::Char(c));
+
+        let mut bindings = vec![
+            bind(
+                "quit",
+                vec![key('q'), KeyBinding::ctrl('c')],
+                UiAction::Quit,
+                "Quit",
+            ),
+            bind(
+                "force_quit",
+                vec![key('Q')],
+                UiAction::ForceQuit,
+                "Quit without confirming",
+            ),
+            bind(
+                "help",
+                vec![key('?')],
+                UiAction::ToggleHelp,
+                "Show or hide this help",
+            ),
+            bind(
+                "pause",
+                vec![key(' ')],
+                UiAction::TogglePause,
+                "Pause or resume",
+            ),
+            bind(
+                "kill",
+                vec![key('k')],
+                UiAction::KillEvaluator,
+                "Kill the evaluator",
+            ),
+            bind(
+                "restart",
+                vec![key('r')],
+                UiAction::RestartRun,
+                "Restart the run",
+            ),
+            bind("confirm", vec![key('y')], UiAction::Confirm, "Confirm"),
+            bind(
+                "cancel",
+                vec![key('n'), KeyBinding::plain(KeyCode::Esc)],
+                UiAction::Cancel,
+                "Cancel or close",
+            ),
+            bind(
+                "stderr",
+                vec![key('l')],
+                UiAction::ToggleStderr,
+                "Show or hide stderr",
+            ),
+            bind(
+                "diagnostics",
+                vec![key('d')],
+                UiAction::ToggleDiagnostics,
+                "Show or hide output diagnostics",
+            ),
+            bind(
+                "timeline",
+                vec![key('t')],
+                UiAction::ToggleTimeline,
+                "Show or hide the timeline",
+            ),
+            bind(
+                "comparison",
+                vec![key('b')],
+                UiAction::ToggleComparison,
+                "Compare the run with the baseline",
+            ),
+            bind(
+                "group",
+                vec![key('g')],
+                UiAction::CycleGroupBy,
+                "Group statistics by the next attribute",
+            ),
+            bind(
+                "refresh",
+                vec![KeyBinding::ctrl('l')],
+                UiAction::Refresh,
+                "Redraw",
+            ),
+        ];
+
+        for (number, tab) in ('1'..).zip(Tab::ALL) {
+            bindings.push(bind(
+                &tab.title().to_lowercase(),
+                vec![key(number)],
+                UiAction::SelectTab(tab),
+                &format!("Show the {} tab", tab.title()),
+            ));
+        }
+        bindings.push(bind(
+            "next_tab",
+            vec![KeyBinding::plain(KeyCode::Tab)],
+            UiAction::NextTab,
+            "Show the next tab",
+        ));
+
+        for (name, keys, scroll, description) in [
+            ("up", vec![KeyCode::Up], Scroll::Up, "Move up"),
+            ("down", vec![KeyCode::Down], Scroll::Down, "Move down"),
+            (
+                "page_up",
+                vec![KeyCode::PageUp],
+                Scroll::PageUp,
+                "Move up a page",
+            ),
+            (
+                "page_down",
+                vec![KeyCode::PageDown],
+                Scroll::PageDown,
+                "Move down a page",
+            ),
+            ("top", vec![KeyCode::Home], Scroll::Top, "Move to the top"),
+            (
+                "bottom",
+                vec![KeyCode::Char('G'), KeyCode::End],
+                Scroll::Bottom,
+                "Move to the bottom, following new entries again",
+            ),
+        ] {
+            bindings.push(bind(
+                name,
+                keys.into_iter().map(KeyBinding::plain).collect(),
+                UiAction::Scroll(scroll),
+                description,
+            ));
+        }
+
+        bindings.push(bind(
+            "search",
+            vec![key('/')],
+            UiAction::StartSampleSearch,
+            "Filter samples by id (substring or regex)",
+        ));
+        bindings.push(bind(
+            "bookmark",
+            vec![key('B')],
+            UiAction::ToggleBookmark,
+            "Bookmark the selected sample, or remove its bookmark",
+        ));
+        bindings.push(bind(
+            "note",
+            vec![key('a')],
+            UiAction::StartSampleNote,
+            "Note the selected sample",
+        ));
+        bindings.push(bind(
+            "filter",
+            vec![key('f')],
+            UiAction::CycleStatusFilter,
+            "Show only completed, failed or processing samples",
+        ));
+        bindings.push(bind(
+            "sort",
+            vec![key('s')],
+            UiAction::CycleSampleSort,
+            "Sort samples by the next metric",
+        ));
+        bindings.push(bind(
+            "sort_order",
+            vec![key('S')],
+            UiAction::ReverseSampleSort,
+            "Reverse the order samples are sorted in",
+        ));
+        bindings.push(bind(
+            "next_failed",
+            vec![key('e')],
+            UiAction::NextFailedSample,
+            "Select the next failed sample",
+        ));
+        bindings.push(bind(
+            "previous_failed",
+            vec![key('E')],
+            UiAction::PreviousFailedSample,
+            "Select the previous failed sample",
+        ));
+        bindings.push(bind(
+            "plot",
+            vec![KeyBinding::plain(KeyCode::Enter)],
+            UiAction::ToggleSeries,
+            "Plot the selected metric in the chart",
+        ));
+        bindings.push(bind(
+            "chart_axis",
+            vec![key('x')],
+            UiAction::ToggleChartAxis,
+            "Plot the chart against sample or time",
+        ));
+        bindings.push(bind(
+            "fold",
+            vec![key('z')],
+            UiAction::CycleJsonFold,
+            "Fold the inspected JSON one level further out",
+        ));
+        // Under Ctrl, as e already jumps between failed samples
+        bindings.push(bind(
+            "export",
+            vec![KeyBinding::ctrl('e')],
+            UiAction::ExportReport,
+            "Write a report of the run so far to disk",
+        ));
+        bindings.push(bind(
+            "screen",
+            vec![KeyBinding::ctrl('p')],
+            UiAction::SaveScreen,
+            "Save a text copy of the screen to disk",
+        ));
+        bindings.push(bind(
+            "copy",
+            vec![key('Y')],
+            UiAction::Copy,
+            "Copy the selected sample, or the run summary while it is shown, as Markdown",
+        ));
+        bindings.push(bind(
+            "rank",
+            vec![key('m')],
+            UiAction::CycleLeaderboardMetric,
+            "Rank the leaderboard by the next metric",
+        ));
+        bindings.push(bind(
+            "rank_order",
+            vec![key('M')],
+            UiAction::ReverseLeaderboard,
+            "Rank the leaderboard the other way round",
+        ));
+        bindings.push(bind(
+            "scatter_x",
+            vec![key('h')],
+            UiAction::CycleScatterX,
+            "Plot the next metric across the scatter plot",
+        ));
+        bindings.push(bind(
+            "scatter_y",
+            vec![key('v')],
+            UiAction::CycleScatterY,
+            "Plot the next metric up the scatter plot",
+        ));
+        bindings.push(bind(
+            "open_outlier",
+            vec![key('o')],
+            UiAction::OpenOutlier,
+            "Open the selected scatter outlier in the Samples view",
+        ));
+        bindings.push(Binding {
+            hidden: true,
+            ..bind(
+                "debug",
+                vec![KeyBinding::plain(KeyCode::F(12))],
+                UiAction::ToggleDebug,
+                "Show or hide PrEval's own frame times, parse times and memory",
+            )
+        });
+
+        Self { bindings }
+    }
+}
+
+/// Errors in the keybindings of the config file
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum KeymapError {
+    #[error("unknown action '{0}'")]
+    UnknownAction(String),
+    #[error("invalid key '{0}' (expected e.g. 'q', 'space', 'pgdn' or 'ctrl+l')")]
+    InvalidKey(String),
+    #[error("key {key} is bound to both '{first}' and '{second}'")]
+    Conflict {
+        key: String,
+        first: String,
+        second: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(toml: &str) -> BTreeMap<String, KeyList> {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn shift_is_part_of_character_keys() {
+        let keymap = Keymap::default();
+        let question_mark = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&question_mark), Some(UiAction::ToggleHelp));
+
+        let shift_tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&shift_tab), None);
+    }
+
+    #[test]
+    fn the_debug_view_is_bound_but_left_out_of_the_help() {
+        let keymap = Keymap::default();
+        let f12 = KeyEvent::new(KeyCode::F(12), KeyModifiers::NONE);
+        assert_eq!(keymap.action(&f12), Some(UiAction::ToggleDebug));
+
+        let hidden: Vec<_> = keymap
+            .bindings()
+            .iter()
+            .filter(|binding| binding.hidden)
+            .map(|binding| binding.name.as_str())
+            .collect();
+        assert_eq!(hidden, ["debug"]);
+    }
+
+    #[test]
+    fn keys_are_named_for_the_help() {
+        assert_eq!(KeyBinding::ctrl('l').to_string(), "Ctrl+L");
+        assert_eq!(KeyBinding::plain(KeyCode::Char(' ')).to_string(), "Space");
+        assert_eq!(KeyBinding::plain(KeyCode::PageDown).to_string(), "PgDn");
+    }
+
+    #[test]
+    fn parses_keys_from_config() {
+        assert_eq!(
+            "space".parse::<KeyBinding>().unwrap(),
+            KeyBinding::plain(KeyCode::Char(' '))
+        );
+        assert_eq!(
+            "Ctrl+L".parse::<KeyBinding>().unwrap(),
+            KeyBinding::ctrl('l')
+        );
+        assert_eq!(
+            "+".parse::<KeyBinding>().unwrap(),
+            KeyBinding::plain(KeyCode::Char('+'))
+        );
+        assert_eq!(
+            "f5".parse::<KeyBinding>().unwrap(),
+            KeyBinding::plain(KeyCode::F(5))
+        );
+        assert!("hyper+q".parse::<KeyBinding>().is_err());
+        assert!("qq".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn overrides_replace_the_keys_of_an_action() {
+        let keymap = Keymap::with_overrides(&overrides(
+            "pause = \"p\"\nquit = [\"x\", \"ctrl+q\"]\nchart_axis = \"c\"\n",
+        ))
+        .unwrap();
+        let press = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+
+        assert_eq!(keymap.action(&press('p')), Some(UiAction::TogglePause));
+        assert_eq!(keymap.action(&press(' ')), None);
+        assert_eq!(keymap.action(&press('x')), Some(UiAction::Quit));
+        assert_eq!(
+            keymap.key(&UiAction::Quit),
+            Some(KeyBinding::plain(KeyCode::Char('x')))
+        );
+    }
+
+    #[test]
+    fn rejects_conflicting_and_unknown_bindings() {
+        assert!(Keymap::default().check_conflicts().is_ok());
+        assert!(matches!(
+            Keymap::with_overrides(&overrides("quit = \"l\"\n")),
+            Err(KeymapError::Conflict { .. })
+        ));
+        assert!(matches!(
+            Keymap::with_overrides(&overrides("explode = \"e\"\n")),
+            Err(KeymapError::UnknownAction(_))
+        ));
+    }
+}
@@ -8,35 +8,52 @@ use ratatui::prelude::*;
 )]
 struct LayoutHeight(u16);
 
+/// Rows below which the compact layout is used
+const COMPACT_HEIGHT: u16 = 20;
+
+/// Columns below which the compact layout is used
+const COMPACT_WIDTH: u16 = 60;
+
 /// Layout areas for the UI
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct UiLayout {
     pub(crate) header: Rect,
     pub(crate) content: Rect,
+    /// Empty in the compact layout
+    pub(crate) status: Rect,
     pub(crate) footer: Rect,
+    /// Whether the terminal is small enough for the compact layout
+    pub(crate) compact: bool,
 }
 
 impl UiLayout {
     /// Calculate layout from terminal area
+    ///
+    /// Small terminals get a compact layout: a single-line header and no
+    /// status bar, leaving the rest to the content.
     pub(crate) fn new(area: Rect) -> Result<Self, LayoutError> {
-        // Ensure we have minimum space
-        const MIN_HEIGHT: u16 = 5; // header (3) + content (1) + footer (1)
+        let compact = area.height < COMPACT_HEIGHT || area.width < COMPACT_WIDTH;
+
+        // Header is 3 lines with its border, 1 without
+        let header_height = LayoutHeight::try_new(if compact { 1 } else { 3 }).unwrap(); // Safe: both > 0
 
-        if area.height < MIN_HEIGHT {
+        // Footer is always 1 line
+        let footer_height = LayoutHeight::try_new(1).unwrap(); // Safe: 1 > 0
+
+        // Status bar is 1 line unless compact
+        let status_height = if compact { 0 } else { 1 };
+
+        // Ensure we have minimum space, including one line of content
+        let fixed_height = header_height.into_inner() + status_height + footer_height.into_inner();
+        if area.height <= fixed_height {
             return Err(LayoutError::TooSmall {
-                required: MIN_HEIGHT,
+                required: fixed_height + 1,
                 actual: area.height,
             });
         }
 
-        // Header is always 3 lines
-        let header_height = LayoutHeight::try_new(3).unwrap(); // Safe: 3 > 0
-
-        // Footer is always 1 line
-        let footer_height = LayoutHeight::try_new(1).unwrap(); // Safe: 1 > 0
-
         // Content gets remaining space
-        let content_height = area.height - header_height.into_inner() - footer_height.into_inner();
+        let content_height = area.height - fixed_height;
 
         // Create layout constraints
         let chunks = Layout::default()
@@ -44,6 +61,7 @@ impl UiLayout {
             .constraints([
                 Constraint::Length(header_height.into_inner()),
                 Constraint::Min(content_height),
+                Constraint::Length(status_height),
                 Constraint::Length(footer_height.into_inner()),
             ])
             .split(area);
@@ -51,7 +69,9 @@ impl UiLayout {
         Ok(Self {
             header: chunks[0],
             content: chunks[1],
-            footer: chunks[2],
+            status: chunks[2],
+            footer: chunks[3],
+            compact,
         })
     }
 }
@@ -67,18 +87,35 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_layout_minimum_size() {
-        let area = Rect::new(0, 0, 80, 5);
+    fn test_layout_full_size() {
+        let area = Rect::new(0, 0, 80, 24);
         let layout = UiLayout::new(area).unwrap();
 
+        assert!(!layout.compact);
         assert_eq!(layout.header.height, 3);
+        assert_eq!(layout.content.height, 19);
+        assert_eq!(layout.status.height, 1);
+        assert_eq!(layout.footer.height, 1);
+    }
+
+    #[test]
+    fn test_layout_compact_size() {
+        let area = Rect::new(0, 0, 80, 3);
+        let layout = UiLayout::new(area).unwrap();
+
+        assert!(layout.compact);
+        assert_eq!(layout.header.height, 1);
         assert_eq!(layout.content.height, 1);
+        assert_eq!(layout.status.height, 0);
         assert_eq!(layout.footer.height, 1);
+
+        // Narrow terminals are compact too
+        assert!(UiLayout::new(Rect::new(0, 0, 40, 24)).unwrap().compact);
     }
 
     #[test]
     fn test_layout_too_small() {
-        let area = Rect::new(0, 0, 80, 4);
+        let area = Rect::new(0, 0, 80, 2);
         let result = UiLayout::new(area);
 
         assert!(result.is_err());
@@ -1,6 +1,15 @@
 // UI module for terminal user interface components
 
+pub(crate) mod alert;
+pub(crate) mod clipboard;
 pub(crate) mod events;
+pub(crate) mod history;
+pub(crate) mod json;
+pub(crate) mod keymap;
 pub(crate) mod layout;
+pub(crate) mod mouse;
 pub(crate) mod renderer;
+pub(crate) mod screen;
+pub(crate) mod theme;
+pub(crate) mod view;
 pub(crate) mod widgets;
@@ -0,0 +1,108 @@
+use crate::state::types::{Scroll, Tab, UiAction};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+use std::sync::{Arc, Mutex};
+
+/// What clicking on part of the screen selects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Target {
+    /// A tab in the tab bar
+    Tab(Tab),
+    /// Rows of the sample list, showing the samples at these indices from
+    /// the top row down
+    Samples(Vec<usize>),
+}
+
+/// Where the last frame drew the things a click can select
+///
+/// The renderer records it while drawing, and the event handler reads it
+/// to turn clicks into actions, so the two agree on the layout. It also
+/// records the view the frame showed, which is offered keys first.
+#[derive(Debug, Default)]
+pub(crate) struct HitMap {
+    targets: Vec<(Rect, Target)>,
+    view: Option<Tab>,
+}
+
+/// Hit map shared between the renderer and the event handler
+pub(crate) type SharedHitMap = Arc<Mutex<HitMap>>;
+
+impl HitMap {
+    /// Make clicks in `area` select `target`
+    pub(crate) fn add(&mut self, area: Rect, target: Target) {
+        self.targets.push((area, target));
+    }
+
+    /// Record that the frame shows the view of `tab`, or none under an overlay
+    pub(crate) fn set_view(&mut self, view: Option<Tab>) {
+        self.view = view;
+    }
+
+    /// Tab of the view the frame shows, if keys can reach it
+    pub(crate) fn view(&self) -> Option<Tab> {
+        self.view
+    }
+
+    /// Action for a mouse event, if it selects or scrolls something
+    ///
+    /// The wheel scrolls the active tab wherever the pointer is.
+    pub(crate) fn action(&self, mouse: &MouseEvent) -> Option<UiAction> {
+        match mouse.kind {
+            MouseEventKind::ScrollUp => Some(UiAction::Scroll(Scroll::Up)),
+            MouseEventKind::ScrollDown => Some(UiAction::Scroll(Scroll::Down)),
+            MouseEventKind::Down(MouseButton::Left) => self.click(mouse.column, mouse.row),
+            _ => None,
+        }
+    }
+
+    /// Action for a click at `column`, `row`
+    fn click(&self, column: u16, row: u16) -> Option<UiAction> {
+        let position = Position::new(column, row);
+        let (area, target) = self
+            .targets
+            .iter()
+            .find(|(area, _)| area.contains(position))?;
+        match target {
+            Target::Tab(tab) => Some(UiAction::SelectTab(*tab)),
+            Target::Samples(samples) => samples
+                .get(usize::from(row - area.y))
+                .map(|&index| UiAction::OpenSample(index)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn clicks_select_what_was_drawn_under_them() {
+        let mut hits = HitMap::default();
+        hits.add(Rect::new(0, 0, 10, 1), Target::Tab(Tab::Samples));
+        hits.add(Rect::new(0, 5, 30, 10), Target::Samples(vec![40, 41, 42]));
+        let click = |column, row| press(MouseEventKind::Down(MouseButton::Left), column, row);
+
+        assert_eq!(
+            hits.action(&click(4, 0)),
+            Some(UiAction::SelectTab(Tab::Samples))
+        );
+        assert_eq!(hits.action(&click(4, 7)), Some(UiAction::OpenSample(42)));
+        // Below the last sample and outside every target
+        assert_eq!(hits.action(&click(4, 8)), None);
+        assert_eq!(hits.action(&click(40, 0)), None);
+        assert_eq!(
+            hits.action(&press(MouseEventKind::ScrollDown, 40, 0)),
+            Some(UiAction::Scroll(Scroll::Down))
+        );
+    }
+}
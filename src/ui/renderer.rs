@@ -1,18 +1,55 @@
-use crate::state::types::Initialized;
+use crate::state::app::AppData;
+use crate::state::perf::PerfRecorder;
+use crate::state::publisher::StateSnapshot;
+use crate::state::types::{Initialized, Tab, UiAction};
+use crate::ui::keymap::Keymap;
 use crate::ui::layout::UiLayout;
-use crate::ui::widgets::{footer::Footer, header::Header, progress::ProgressView};
+use crate::ui::mouse::{HitMap, SharedHitMap, Target};
+use crate::ui::theme::Theme;
+use crate::ui::view::{SharedViews, ViewContext, ViewRegistry};
+use crate::ui::widgets::{
+    debug::DebugOverlay,
+    footer::Footer,
+    header::Header,
+    help::HelpOverlay,
+    notifications::{ErrorBanner, Toasts},
+    pause::PauseOverlay,
+    progress::ProgressView,
+    status_bar::StatusBar,
+    summary::RunSummary,
+};
 use anyhow::{Context, Result};
 use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
+use ratatui::{
+    backend::{CrosstermBackend, TestBackend},
+    prelude::*,
+    widgets::Tabs,
+};
 use std::io::{self, Stdout};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, Once, PoisonError, TryLockError};
+use std::time::Instant;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
 
 /// Terminal renderer with typestate pattern to ensure proper initialization
 pub(crate) struct Renderer<S> {
     _state: PhantomData<S>,
+    /// Keybindings listed in the help overlay
+    keymap: Keymap,
+    /// Colors of every widget
+    theme: Theme,
+    /// Where the last frame drew what clicks can select
+    hit_map: SharedHitMap,
+    /// View drawn for each tab
+    views: SharedViews,
+    /// Where to record how long each frame took
+    perf: PerfRecorder,
 }
 
 /// Uninitialized state marker
@@ -24,9 +61,67 @@ impl Renderer<Uninitialized> {
     pub(crate) fn new() -> Self {
         Self {
             _state: PhantomData,
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            hit_map: SharedHitMap::default(),
+            views: SharedViews::new(ViewRegistry::default()),
+            perf: PerfRecorder::default(),
         }
     }
 
+    /// Set where to record how long each frame took (builder pattern)
+    pub(crate) fn perf(mut self, perf: PerfRecorder) -> Self {
+        self.perf = perf;
+        self
+    }
+
+    /// Set the view drawn for each tab (builder pattern)
+    pub(crate) fn views(mut self, views: SharedViews) -> Self {
+        self.views = views;
+        self
+    }
+
+    /// Set where to record what clicks can select (builder pattern)
+    pub(crate) fn hit_map(mut self, hit_map: SharedHitMap) -> Self {
+        self.hit_map = hit_map;
+        self
+    }
+
+    /// Set the colors of every widget (builder pattern)
+    pub(crate) fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Set the keybindings listed in the help overlay (builder pattern)
+    pub(crate) fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    /// Draw `state` into a buffer of `width` by `height` cells, the way a
+    /// terminal of that size would show it, leaving the terminal alone
+    pub(crate) fn render_offscreen(
+        self,
+        state: &AppData,
+        width: u16,
+        height: u16,
+    ) -> Result<Buffer> {
+        let renderer = Renderer {
+            _state: PhantomData::<Initialized>,
+            keymap: self.keymap,
+            theme: self.theme,
+            // What the copy draws is not clickable
+            hit_map: SharedHitMap::default(),
+            views: self.views,
+            perf: self.perf,
+        };
+        let mut terminal = Terminal::new(TestBackend::new(width, height))
+            .context("Failed to create off-screen terminal")?;
+        renderer.render(&mut terminal, state)?;
+        Ok(terminal.backend().buffer().clone())
+    }
+
     /// Initialize the terminal and transition to initialized state
     pub(crate) fn initialize(
         self,
@@ -37,6 +132,7 @@ impl Renderer<Uninitialized> {
         // Enter alternate screen
         let mut stdout = io::stdout();
         execute!(stdout, EnterAlternateScreen).context("Failed to enter alternate screen")?;
+        execute!(stdout, EnableMouseCapture).context("Failed to enable mouse capture")?;
 
         // Create terminal
         let backend = CrosstermBackend::new(stdout);
@@ -44,6 +140,11 @@ impl Renderer<Uninitialized> {
 
         let renderer = Renderer {
             _state: PhantomData,
+            keymap: self.keymap,
+            theme: self.theme,
+            hit_map: self.hit_map,
+            views: self.views,
+            perf: self.perf,
         };
 
         Ok((renderer, terminal))
@@ -52,12 +153,35 @@ impl Renderer<Uninitialized> {
 
 /// Initialized renderer - can only be created through initialize()
 impl Renderer<Initialized> {
+    /// Draw every snapshot published to `snapshots` on a task of its own
+    ///
+    /// The task draws the latest snapshot whenever a newer one arrives and
+    /// finishes once the publisher is gone, after drawing its final state.
+    pub(crate) fn spawn(
+        self,
+        mut terminal: Terminal<CrosstermBackend<Stdout>>,
+        mut snapshots: watch::Receiver<StateSnapshot>,
+    ) -> JoinHandle<Result<()>> {
+        tokio::spawn(async move {
+            loop {
+                let snapshot = snapshots.borrow_and_update().clone();
+                let started = Instant::now();
+                self.render(&mut terminal, &snapshot)?;
+                self.perf.record_frame(started.elapsed());
+                if snapshots.changed().await.is_err() {
+                    return Ok(());
+                }
+            }
+        })
+    }
+
     /// Render the UI
     pub(crate) fn render<B: ratatui::backend::Backend>(
         &self,
         terminal: &mut Terminal<B>,
-        state: &crate::state::AppState,
+        state: &AppData,
     ) -> Result<()> {
+        let mut hits = HitMap::default();
         terminal
             .draw(|frame| {
                 let area = frame.area();
@@ -66,6 +190,7 @@ impl Renderer<Initialized> {
                 match UiLayout::new(area) {
                     Ok(layout) => {
                         // Render header - prefer handshake data over evaluator name
+                        let theme = &self.theme;
                         let header = match state.handshake() {
                             Some(handshake) => Header::new().handshake(handshake),
                             None => {
@@ -76,23 +201,100 @@ impl Renderer<Initialized> {
                                 }
                             }
                         };
-                        frame.render_widget(header, layout.header);
+                        frame.render_widget(
+                            header.compact(layout.compact).theme(theme),
+                            layout.header,
+                        );
+
+                        // A fatal problem stays above the view until dismissed
+                        let mut views_area = layout.content;
+                        if let Some(error) = state.error_banner() {
+                            let [banner_area, rest] =
+                                Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+                                    .areas(views_area);
+                            frame.render_widget(
+                                ErrorBanner::new(error)
+                                    .dismiss_key(self.keymap.key(&UiAction::Cancel))
+                                    .theme(theme),
+                                banner_area,
+                            );
+                            views_area = rest;
+                        }
 
-                        // Render content (progress)
-                        let progress_view = ProgressView::new(state);
-                        frame.render_widget(progress_view, layout.content);
+                        // Render the tab bar above the view of the active tab
+                        let [tabs_area, content] =
+                            Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+                                .areas(views_area);
+                        let titles = tab_titles(&self.views, &self.keymap);
+                        let mut x = tabs_area.x;
+                        for ((tab, _), title) in self.views.iter().zip(&titles) {
+                            // Each title is padded by a space on either side, then divided
+                            // from the next by one column
+                            let width = Line::from(title.as_str()).width() as u16 + 2;
+                            let tab_area = Rect::new(x, tabs_area.y, width, 1);
+                            hits.add(tab_area.intersection(tabs_area), Target::Tab(tab));
+                            x = x.saturating_add(width + 1);
+                        }
+                        frame.render_widget(tab_bar(titles, state.active_tab(), theme), tabs_area);
+                        if let Some(view) = self.views.get(state.active_tab()) {
+                            hits.set_view(Some(state.active_tab()));
+                            let mut context = ViewContext {
+                                state,
+                                theme,
+                                keymap: &self.keymap,
+                                compact: layout.compact,
+                                hits: &mut hits,
+                            };
+                            view.render(frame, content, &mut context);
+                        }
+
+                        if state.is_paused() && !state.is_terminal() {
+                            frame.render_widget(
+                                PauseOverlay::new(state.pending_metrics())
+                                    .resume_key(self.keymap.key(&UiAction::TogglePause))
+                                    .theme(theme),
+                                content,
+                            );
+                        }
+                        frame.render_widget(
+                            Toasts::new(state.notifications()).theme(theme),
+                            content,
+                        );
+                        if state.is_debug_visible() {
+                            frame.render_widget(
+                                DebugOverlay::new(state.perf_stats(), state.output_diagnostics())
+                                    .theme(theme),
+                                content,
+                            );
+                        }
 
                         // Render footer
-                        let footer = Footer::new().paused(state.is_paused());
+                        let footer = Footer::new(&self.keymap)
+                            .theme(theme)
+                            .paused(state.is_paused())
+                            .confirmation(state.pending_confirmation())
+                            .resources(state.resource_usage())
+                            .cost(state.cost());
                         frame.render_widget(footer, layout.footer);
+                        if !layout.compact {
+                            frame.render_widget(StatusBar::new(state).theme(theme), layout.status);
+                        }
+
+                        if state.is_summary_visible() {
+                            // Nothing under the summary can be clicked
+                            hits = HitMap::default();
+                            frame.render_widget(RunSummary::new(state).theme(theme), area);
+                        }
+                        if state.is_help_visible() {
+                            // Nothing under the help can be clicked
+                            hits = HitMap::default();
+                            frame.render_widget(HelpOverlay::new(&self.keymap).theme(theme), area);
+                        }
                     }
                     Err(_) => {
-                        // Terminal too small, show error
-                        let msg = "Terminal too small!";
+                        // No room for any chrome, just the progress of the run
                         frame.render_widget(
-                            ratatui::widgets::Paragraph::new(msg).style(
-                                ratatui::style::Style::default().fg(ratatui::style::Color::Red),
-                            ),
+                            ProgressView::new(state).compact(true).theme(&self.theme),
                             area,
                         );
                     }
@@ -100,21 +302,106 @@ impl Renderer<Initialized> {
             })
             .context("Failed to draw frame")?;
 
+        if let Ok(mut hit_map) = self.hit_map.lock() {
+            *hit_map = hits;
+        }
         Ok(())
     }
 }
 
+/// Title of every tab, naming the key that selects it
+fn tab_titles(views: &ViewRegistry, keymap: &Keymap) -> Vec<String> {
+    views
+        .iter()
+        .map(|(tab, view)| match keymap.key(&UiAction::SelectTab(tab)) {
+            Some(key) => format!("{} {}", key, view.title()),
+            None => view.title().to_string(),
+        })
+        .collect()
+}
+
+/// Bar of the tabs with `titles`, highlighting the `active` one
+fn tab_bar(titles: Vec<String>, active: Tab, theme: &Theme) -> Tabs<'static> {
+    Tabs::new(titles)
+        .select(active.index())
+        .highlight_style(theme.accent.add_modifier(Modifier::BOLD))
+}
+
 /// Cleanup helper for terminal restoration
 pub(crate) struct TerminalCleanup;
 
 impl Drop for TerminalCleanup {
     fn drop(&mut self) {
-        // Best effort cleanup - ignore errors since we're in Drop
-        let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        restore_terminal();
+        // The terminal is back to normal, so later panics are left alone
+        *PANIC_EVALUATOR_PID
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
     }
 }
 
+/// Leave raw mode and the alternate screen
+fn restore_terminal() {
+    // Best effort cleanup - ignore errors since there is no one to report them to
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen);
+}
+
+/// Process id of the running evaluator, shared with the panic hook (0 = none)
+pub(crate) type EvaluatorPid = Arc<AtomicU32>;
+
+/// Evaluator the panic hook kills: that of the TUI currently on screen, or
+/// None while there is none and the hook stands aside
+static PANIC_EVALUATOR_PID: Mutex<Option<EvaluatorPid>> = Mutex::new(None);
+
+/// Install a panic hook that restores the terminal before reporting the panic,
+/// for as long as the interactive TUI is on screen
+///
+/// Without this a panic inside the render loop leaves the terminal in raw
+/// mode on the alternate screen, where the message is unreadable. The hook
+/// also kills the evaluator (if any) and defers to the previous hook so the
+/// panic message and backtrace are printed as usual. It then aborts: a panic
+/// in any task leaves the TUI without its terminal, so it cannot carry on.
+///
+/// Once the [`TerminalCleanup`] guard is dropped the hook only defers to
+/// the previous one. It is installed once per process; later calls only
+/// point it at their run's evaluator, so runs in one process do not stack
+/// hooks.
+pub(crate) fn install_panic_hook(evaluator_pid: EvaluatorPid) {
+    static INSTALLED: Once = Once::new();
+
+    *PANIC_EVALUATOR_PID
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(evaluator_pid);
+    INSTALLED.call_once(|| {
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // Never wait for the lock: the panic may have happened while it was held
+            let pid = match PANIC_EVALUATOR_PID.try_lock() {
+                Ok(pid) => pid.as_ref().map(|pid| pid.load(Ordering::SeqCst)),
+                Err(TryLockError::Poisoned(pid)) => pid
+                    .into_inner()
+                    .as_ref()
+                    .map(|pid| pid.load(Ordering::SeqCst)),
+                // Only held for a moment as a TUI starts or stops; err towards
+                // restoring the terminal
+                Err(TryLockError::WouldBlock) => Some(0),
+            };
+            let Some(pid) = pid else {
+                previous_hook(info);
+                return;
+            };
+
+            restore_terminal();
+            if pid != 0 {
+                crate::evaluator::process::kill_process_tree_blocking(pid);
+            }
+            previous_hook(info);
+            std::process::abort();
+        }));
+    });
+}
+
 // Tests removed: test_renderer_typestate
 // The phantom type parameter already ensures at compile time that:
 // - Renderer<Initialized> can only be created through initialize()
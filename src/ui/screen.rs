@@ -0,0 +1,170 @@
+// Copies of what the TUI shows, for attaching to bug reports
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// SGR parameter of each modifier, in the order they are written
+const MODIFIER_CODES: [(Modifier, u8); 9] = [
+    (Modifier::BOLD, 1),
+    (Modifier::DIM, 2),
+    (Modifier::ITALIC, 3),
+    (Modifier::UNDERLINED, 4),
+    (Modifier::SLOW_BLINK, 5),
+    (Modifier::RAPID_BLINK, 6),
+    (Modifier::REVERSED, 7),
+    (Modifier::HIDDEN, 8),
+    (Modifier::CROSSED_OUT, 9),
+];
+
+/// Rows of `buffer` as plain text, without trailing spaces
+pub(crate) fn plain_text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for row in rows(buffer) {
+        let line: String = row.iter().map(|(symbol, _)| *symbol).collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Rows of `buffer` with their colors and modifiers as ANSI escape
+/// sequences, for viewing with e.g. `cat` or `less -R`
+pub(crate) fn ansi_text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for row in rows(buffer) {
+        let mut current = Style::default();
+        for (symbol, style) in row {
+            if style != current {
+                text.push_str(&sgr(style));
+                current = style;
+            }
+            text.push_str(symbol);
+        }
+        if current != Style::default() {
+            text.push_str("\x1b[0m");
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Write the screen in `buffer` to `dir`, as plain text and with colors,
+/// named after when it was taken; returns the plain text file
+pub(crate) fn save(buffer: &Buffer, dir: &Path) -> Result<PathBuf> {
+    let stem = format!("preval-screen-{}", Utc::now().format("%Y%m%dT%H%M%SZ"));
+    let path = dir.join(format!("{}.txt", stem));
+    std::fs::write(&path, plain_text(buffer))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    let ansi_path = dir.join(format!("{}.ans", stem));
+    std::fs::write(&ansi_path, ansi_text(buffer))
+        .with_context(|| format!("Failed to write {}", ansi_path.display()))?;
+    Ok(path)
+}
+
+/// Symbol and style of each cell of each row, leaving out the cells
+/// covered by wide characters before them
+fn rows(buffer: &Buffer) -> impl Iterator<Item = Vec<(&str, Style)>> {
+    let width = buffer.area.width as usize;
+    buffer.content.chunks(width.max(1)).map(|cells| {
+        let mut row = Vec::with_capacity(cells.len());
+        let mut covered = 0;
+        for cell in cells {
+            if covered > 0 {
+                covered -= 1;
+                continue;
+            }
+            let symbol = cell.symbol();
+            covered = Span::raw(symbol).width().saturating_sub(1);
+            row.push((symbol, plain_style(cell.style())));
+        }
+        row
+    })
+}
+
+/// `style` with only what is drawn, so styles that look the same are equal
+fn plain_style(style: Style) -> Style {
+    Style {
+        fg: style.fg.filter(|color| *color != Color::Reset),
+        bg: style.bg.filter(|color| *color != Color::Reset),
+        add_modifier: style.add_modifier - style.sub_modifier,
+        ..Style::default()
+    }
+}
+
+/// Escape sequence switching to `style` from any other
+fn sgr(style: Style) -> String {
+    let mut codes = vec!["0".to_string()];
+    for (flag, code) in MODIFIER_CODES {
+        if style.add_modifier.contains(flag) {
+            codes.push(code.to_string());
+        }
+    }
+    if let Some(fg) = style.fg.and_then(|color| color_code(color, false)) {
+        codes.push(fg);
+    }
+    if let Some(bg) = style.bg.and_then(|color| color_code(color, true)) {
+        codes.push(bg);
+    }
+    let mut sequence = String::from("\x1b[");
+    write!(sequence, "{}m", codes.join(";")).ok();
+    sequence
+}
+
+/// SGR parameters for `color` in the foreground, or the `background`
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let basic = |code: u8| Some((code + offset).to_string());
+    match color {
+        Color::Reset => None,
+        Color::Black => basic(30),
+        Color::Red => basic(31),
+        Color::Green => basic(32),
+        Color::Yellow => basic(33),
+        Color::Blue => basic(34),
+        Color::Magenta => basic(35),
+        Color::Cyan => basic(36),
+        Color::Gray => basic(37),
+        Color::DarkGray => basic(90),
+        Color::LightRed => basic(91),
+        Color::LightGreen => basic(92),
+        Color::LightYellow => basic(93),
+        Color::LightBlue => basic(94),
+        Color::LightMagenta => basic(95),
+        Color::LightCyan => basic(96),
+        Color::White => basic(97),
+        Color::Indexed(index) => Some(format!("{};5;{}", 38 + offset, index)),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", 38 + offset, r, g, b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    #[test]
+    fn screens_export_as_plain_and_ansi_text() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 8, 2));
+        buffer.set_string(0, 0, "ok", Style::default().fg(Color::Green));
+        buffer.set_string(3, 0, "表", Style::default());
+        buffer.set_string(
+            0,
+            1,
+            "x",
+            Style::default()
+                .bg(Color::Rgb(1, 2, 3))
+                .add_modifier(Modifier::BOLD),
+        );
+
+        assert_eq!(plain_text(&buffer), "ok 表\nx\n");
+        assert_eq!(
+            ansi_text(&buffer),
+            "\x1b[0;32mok\x1b[0m 表   \n\x1b[0;1;48;2;1;2;3mx\x1b[0m       \n"
+        );
+    }
+}
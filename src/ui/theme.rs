@@ -0,0 +1,279 @@
+use crate::state::rules::Verdict;
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+/// Built-in set of colors the TUI starts from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// Bright colors for dark terminal backgrounds
+    Dark,
+    /// Dark colors for light terminal backgrounds
+    Light,
+    /// Bold, bright colors with strong contrast
+    HighContrast,
+    /// No colors at all, only bold, dim and reversed text
+    NoColor,
+}
+
+impl Palette {
+    /// Palette used unless one is chosen: no colors when the `NO_COLOR`
+    /// environment variable is set, dark otherwise
+    pub fn from_env() -> Self {
+        match std::env::var_os("NO_COLOR") {
+            Some(value) if !value.is_empty() => Palette::NoColor,
+            _ => Palette::Dark,
+        }
+    }
+}
+
+impl std::str::FromStr for Palette {
+    type Err = PaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dark" => Ok(Self::Dark),
+            "light" => Ok(Self::Light),
+            "high-contrast" => Ok(Self::HighContrast),
+            "no-color" | "none" => Ok(Self::NoColor),
+            other => Err(PaletteError(other.to_string())),
+        }
+    }
+}
+
+/// Error for unrecognized palette names
+#[derive(Debug, thiserror::Error)]
+#[error("unknown theme '{0}' (expected 'dark', 'light', 'high-contrast' or 'no-color')")]
+pub struct PaletteError(String);
+
+/// A color from the config file, e.g. `"blue"`, `"#aa7700"` or `"208"`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThemeColor(Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = ThemeColorError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .parse()
+            .map(ThemeColor)
+            .map_err(|_| ThemeColorError(value))
+    }
+}
+
+impl From<ThemeColor> for String {
+    fn from(color: ThemeColor) -> Self {
+        color.0.to_string()
+    }
+}
+
+/// Error for unparseable colors
+#[derive(Debug, thiserror::Error)]
+#[error("invalid color '{0}' (expected a name such as 'blue', '#rrggbb' or a number up to 255)")]
+pub struct ThemeColorError(String);
+
+/// Colors replacing those of the palette, by the role they play
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeColors {
+    /// Hints, placeholders and other secondary text
+    pub muted: Option<ThemeColor>,
+    /// Borders of the panes
+    pub border: Option<ThemeColor>,
+    /// Progress bar, sparklines, the active tab and key names
+    pub accent: Option<ThemeColor>,
+    /// Title in the header
+    pub title: Option<ThemeColor>,
+    /// Completed samples and runs
+    pub success: Option<ThemeColor>,
+    /// Samples in progress, pending prompts and soft problems
+    pub warning: Option<ThemeColor>,
+    /// Failures
+    pub error: Option<ThemeColor>,
+    /// Anomalies and notifications
+    pub notice: Option<ThemeColor>,
+}
+
+/// Style of every part of the TUI, by the role it plays
+///
+/// Widgets take their styles from here instead of naming colors, so a
+/// palette can be swapped for terminals the defaults are unreadable on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Theme {
+    pub(crate) muted: Style,
+    pub(crate) border: Style,
+    pub(crate) accent: Style,
+    pub(crate) title: Style,
+    pub(crate) subtitle: Style,
+    pub(crate) heading: Style,
+    pub(crate) highlight: Style,
+    pub(crate) success: Style,
+    pub(crate) warning: Style,
+    pub(crate) error: Style,
+    pub(crate) notice: Style,
+    /// Colors of the chart's series, reused in turn
+    pub(crate) series: [Color; 6],
+}
+
+impl Theme {
+    /// Bright colors for dark terminal backgrounds
+    pub(crate) const DARK: Theme = Theme {
+        muted: Style::new().fg(Color::DarkGray),
+        border: Style::new().fg(Color::Gray),
+        accent: Style::new().fg(Color::Cyan),
+        title: Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+        subtitle: Style::new().fg(Color::Gray),
+        heading: Style::new().add_modifier(Modifier::BOLD),
+        highlight: Style::new().add_modifier(Modifier::REVERSED),
+        success: Style::new().fg(Color::Green),
+        warning: Style::new().fg(Color::Yellow),
+        error: Style::new().fg(Color::Red),
+        notice: Style::new().fg(Color::Magenta),
+        series: [
+            Color::Cyan,
+            Color::Yellow,
+            Color::Green,
+            Color::Magenta,
+            Color::Blue,
+            Color::Red,
+        ],
+    };
+
+    /// Dark colors for light terminal backgrounds
+    const LIGHT: Theme = Theme {
+        muted: Style::new().fg(Color::DarkGray),
+        border: Style::new().fg(Color::DarkGray),
+        accent: Style::new().fg(Color::Blue),
+        title: Style::new().fg(Color::Black).add_modifier(Modifier::BOLD),
+        subtitle: Style::new().fg(Color::DarkGray),
+        heading: Style::new().add_modifier(Modifier::BOLD),
+        highlight: Style::new().add_modifier(Modifier::REVERSED),
+        success: Style::new().fg(Color::Indexed(28)),
+        warning: Style::new().fg(Color::Indexed(130)),
+        error: Style::new().fg(Color::Indexed(160)),
+        notice: Style::new().fg(Color::Indexed(90)),
+        series: [
+            Color::Blue,
+            Color::Indexed(130),
+            Color::Indexed(28),
+            Color::Indexed(90),
+            Color::Indexed(30),
+            Color::Indexed(160),
+        ],
+    };
+
+    /// Bold, bright colors with strong contrast
+    const HIGH_CONTRAST: Theme = Theme {
+        muted: Style::new().fg(Color::White),
+        border: Style::new().fg(Color::White),
+        accent: Style::new()
+            .fg(Color::LightCyan)
+            .add_modifier(Modifier::BOLD),
+        title: Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+        subtitle: Style::new().fg(Color::White),
+        heading: Style::new().add_modifier(Modifier::BOLD.union(Modifier::UNDERLINED)),
+        highlight: Style::new().add_modifier(Modifier::REVERSED.union(Modifier::BOLD)),
+        success: Style::new()
+            .fg(Color::LightGreen)
+            .add_modifier(Modifier::BOLD),
+        warning: Style::new()
+            .fg(Color::LightYellow)
+            .add_modifier(Modifier::BOLD),
+        error: Style::new()
+            .fg(Color::LightRed)
+            .add_modifier(Modifier::BOLD),
+        notice: Style::new()
+            .fg(Color::LightMagenta)
+            .add_modifier(Modifier::BOLD),
+        series: [
+            Color::LightCyan,
+            Color::LightYellow,
+            Color::LightGreen,
+            Color::LightMagenta,
+            Color::LightBlue,
+            Color::LightRed,
+        ],
+    };
+
+    /// No colors at all, only bold, dim and reversed text
+    const NO_COLOR: Theme = Theme {
+        muted: Style::new().add_modifier(Modifier::DIM),
+        border: Style::new(),
+        accent: Style::new(),
+        title: Style::new().add_modifier(Modifier::BOLD),
+        subtitle: Style::new(),
+        heading: Style::new().add_modifier(Modifier::BOLD),
+        highlight: Style::new().add_modifier(Modifier::REVERSED),
+        success: Style::new(),
+        warning: Style::new(),
+        error: Style::new().add_modifier(Modifier::BOLD),
+        notice: Style::new().add_modifier(Modifier::BOLD),
+        series: [Color::Reset; 6],
+    };
+
+    /// Theme of `palette` with any of `colors` replacing its own
+    pub(crate) fn new(palette: Palette, colors: &ThemeColors) -> Self {
+        let mut theme = match palette {
+            Palette::Dark => Self::DARK,
+            Palette::Light => Self::LIGHT,
+            Palette::HighContrast => Self::HIGH_CONTRAST,
+            Palette::NoColor => Self::NO_COLOR,
+        };
+        for (style, color) in [
+            (&mut theme.muted, colors.muted),
+            (&mut theme.border, colors.border),
+            (&mut theme.accent, colors.accent),
+            (&mut theme.title, colors.title),
+            (&mut theme.success, colors.success),
+            (&mut theme.warning, colors.warning),
+            (&mut theme.error, colors.error),
+            (&mut theme.notice, colors.notice),
+        ] {
+            if let Some(ThemeColor(color)) = color {
+                *style = style.fg(color);
+            }
+        }
+        theme
+    }
+
+    /// Style of a metric value judged by the threshold rules
+    pub(crate) fn verdict(&self, verdict: Verdict) -> Style {
+        match verdict {
+            Verdict::Pass => self.success,
+            Verdict::Warn => self.warning,
+            Verdict::Fail => self.error,
+        }
+    }
+
+    /// Color of the `index`th series of a chart
+    pub(crate) fn series_color(&self, index: usize) -> Color {
+        self.series[index % self.series.len()]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_colors_replace_the_palettes() {
+        let colors: ThemeColors =
+            toml::from_str("accent = \"#aa7700\"\nerror = \"magenta\"\n").unwrap();
+        let theme = Theme::new(Palette::HighContrast, &colors);
+
+        assert_eq!(theme.accent.fg, Some(Color::Rgb(0xaa, 0x77, 0x00)));
+        assert_eq!(theme.error.fg, Some(Color::Magenta));
+        // Only the color changes, the palette's emphasis stays
+        assert!(theme.error.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(theme.warning, Theme::HIGH_CONTRAST.warning);
+
+        assert!(toml::from_str::<ThemeColors>("accent = \"blurple\"\n").is_err());
+    }
+}
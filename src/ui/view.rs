@@ -0,0 +1,181 @@
+//! Views the UI switches between with the tab bar
+//!
+//! Every tab draws a [`View`] from the [`ViewRegistry`]. The built-in views
+//! are registered first, in the order of [`Tab::ALL`]; views compiled into
+//! PrEval behind a feature or in a fork are added to
+//! `ViewRegistry::default` after them and reached as [`Tab::Extra`],
+//! without touching the renderer. The registry is internal to the crate, so
+//! library users cannot add views of their own.
+
+mod builtin;
+
+use crate::state::app::AppData;
+use crate::state::types::{Tab, UiAction};
+use crate::ui::keymap::Keymap;
+use crate::ui::mouse::HitMap;
+use crate::ui::theme::Theme;
+use crossterm::event::KeyEvent;
+use ratatui::{layout::Rect, Frame};
+use std::sync::Arc;
+
+/// What a view draws from, and where it records what clicks can select
+pub(crate) struct ViewContext<'a> {
+    /// State of the run being shown
+    pub(crate) state: &'a AppData,
+    /// Colors of every widget
+    pub(crate) theme: &'a Theme,
+    /// Keybindings, for naming keys in hints
+    pub(crate) keymap: &'a Keymap,
+    /// Whether the terminal is too small for the full layout
+    pub(crate) compact: bool,
+    /// Where the frame draws what clicks can select
+    pub(crate) hits: &'a mut HitMap,
+}
+
+/// A view of the run, drawn below the tab bar while its tab is active
+pub(crate) trait View: Send + Sync {
+    /// Name shown in the tab bar
+    fn title(&self) -> &str;
+
+    /// Draw the view into `area`
+    fn render(&self, frame: &mut Frame, area: Rect, context: &mut ViewContext<'_>);
+
+    /// Action for `key` while the view is shown, taking precedence over the
+    /// keymap; None leaves the key to the keymap
+    fn handle_key(&self, _key: &KeyEvent) -> Option<UiAction> {
+        None
+    }
+}
+
+/// Every view, in the order of the tab bar
+pub(crate) struct ViewRegistry {
+    views: Vec<Box<dyn View>>,
+}
+
+/// Registry shared between the renderer and the event handler
+pub(crate) type SharedViews = Arc<ViewRegistry>;
+
+impl Default for ViewRegistry {
+    fn default() -> Self {
+        let mut registry = Self { views: Vec::new() };
+        registry.register(builtin::Overview);
+        registry.register(builtin::Samples);
+        registry.register(builtin::Metrics);
+        registry.register(builtin::Logs);
+        registry.register(builtin::Raw);
+        registry.register(builtin::Chart);
+        registry.register(builtin::Leaderboard);
+        registry.register(builtin::Scatter);
+        // Views behind features or in forks are registered here, after the
+        // built-in ones
+        registry
+    }
+}
+
+impl ViewRegistry {
+    /// Add `view` after the views registered so far, returning its tab
+    pub(crate) fn register(&mut self, view: impl View + 'static) -> Tab {
+        self.views.push(Box::new(view));
+        Tab::from_index(self.views.len() - 1)
+    }
+
+    /// View drawn for `tab`, if one is registered
+    pub(crate) fn get(&self, tab: Tab) -> Option<&dyn View> {
+        self.views.get(tab.index()).map(|view| view.as_ref())
+    }
+
+    /// Every tab with its view, in the order of the tab bar
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Tab, &dyn View)> {
+        self.views
+            .iter()
+            .enumerate()
+            .map(|(index, view)| (Tab::from_index(index), view.as_ref()))
+    }
+
+    /// Number of views registered after the built-in ones
+    pub(crate) fn extra_views(&self) -> usize {
+        self.views.len().saturating_sub(Tab::ALL.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use ratatui::{backend::TestBackend, widgets::Paragraph, Terminal};
+
+    struct Dashboard;
+
+    impl View for Dashboard {
+        fn title(&self) -> &str {
+            "Dashboard"
+        }
+
+        fn render(&self, frame: &mut Frame, area: Rect, context: &mut ViewContext<'_>) {
+            let line = format!("{} samples", context.state.sample_store().len());
+            frame.render_widget(Paragraph::new(line), area);
+        }
+
+        fn handle_key(&self, key: &KeyEvent) -> Option<UiAction> {
+            (key.code == KeyCode::Char('r')).then_some(UiAction::Refresh)
+        }
+    }
+
+    #[test]
+    fn extra_views_are_registered_after_the_built_in_ones() {
+        let mut registry = ViewRegistry::default();
+        assert_eq!(registry.extra_views(), 0);
+        let tabs: Vec<Tab> = registry.iter().map(|(tab, _)| tab).collect();
+        assert_eq!(tabs, Tab::ALL);
+
+        assert_eq!(registry.register(Dashboard), Tab::Extra(0));
+        assert_eq!(registry.extra_views(), 1);
+        let view = registry.get(Tab::Extra(0)).unwrap();
+        assert_eq!(view.title(), "Dashboard");
+        assert_eq!(
+            view.handle_key(&KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE)),
+            Some(UiAction::Refresh)
+        );
+        assert!(registry.get(Tab::Extra(1)).is_none());
+        // Built-in views leave other keys to the keymap
+        let overview = registry.get(Tab::Overview).unwrap();
+        assert_eq!(
+            overview.handle_key(&KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE)),
+            None
+        );
+        let scatter = registry.get(Tab::Scatter).unwrap();
+        assert_eq!(
+            scatter.handle_key(&KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(UiAction::OpenOutlier)
+        );
+        let samples = registry.get(Tab::Samples).unwrap();
+        assert_eq!(
+            samples.handle_key(&KeyEvent::new(KeyCode::Char('b'), KeyModifiers::NONE)),
+            Some(UiAction::ToggleBookmark)
+        );
+        assert_eq!(
+            samples.handle_key(&KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL)),
+            None
+        );
+
+        let state = crate::state::app::InitialAppState::new();
+        let mut terminal = Terminal::new(TestBackend::new(20, 1)).unwrap();
+        let mut hits = HitMap::default();
+        terminal
+            .draw(|frame| {
+                let mut context = ViewContext {
+                    state: &state,
+                    theme: &Theme::default(),
+                    keymap: &Keymap::default(),
+                    compact: false,
+                    hits: &mut hits,
+                };
+                view.render(frame, frame.area(), &mut context);
+            })
+            .unwrap();
+        let drawn: String = (0..9)
+            .map(|x| terminal.backend().buffer()[(x, 0)].symbol().to_string())
+            .collect();
+        assert_eq!(drawn, "0 samples");
+    }
+}
@@ -0,0 +1,285 @@
+//! Views of the built-in tabs
+
+use super::{View, ViewContext};
+use crate::state::types::{Scroll, Tab, UiAction};
+use crate::ui::widgets::{
+    chart::MetricChart,
+    comparison::ComparisonView,
+    diagnostics::DiagnosticsPane,
+    leaderboard::Leaderboard as LeaderboardTable,
+    metric_detail::MetricDetail,
+    metrics::MetricsView,
+    progress::ProgressView,
+    raw_lines::RawLinesPane,
+    sample_browser::{SampleBrowser, SampleList},
+    scatter::ScatterPlot,
+    stderr::StderrPane,
+    timeline::TimelinePane,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+/// Whether `key` is `code` pressed without Ctrl or Alt
+fn is_plain(key: &KeyEvent, code: KeyCode) -> bool {
+    key.code == code
+        && !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+}
+
+/// Rows given to the stderr pane when it is visible
+const STDERR_PANE_HEIGHT: u16 = 10;
+
+/// Rows given to the timeline pane when it is visible
+const TIMELINE_PANE_HEIGHT: u16 = 10;
+
+/// Rows given to the diagnostics pane when it is visible
+const DIAGNOSTICS_PANE_HEIGHT: u16 = 5;
+
+/// Progress of the run with the optional panes below it, or the comparison
+/// with the baseline run
+pub(super) struct Overview;
+
+impl View for Overview {
+    fn title(&self) -> &str {
+        Tab::Overview.title()
+    }
+
+    fn render(&self, frame: &mut Frame, mut content: Rect, context: &mut ViewContext<'_>) {
+        let ViewContext {
+            state,
+            theme,
+            compact,
+            ..
+        } = *context;
+        if state.is_comparison_visible() {
+            if let Some(baseline) = state.baseline() {
+                frame.render_widget(ComparisonView::new(state, baseline).theme(theme), content);
+            }
+            return;
+        }
+
+        if state.is_diagnostics_visible() {
+            let [rest, diagnostics_area] = Layout::vertical([
+                Constraint::Min(0),
+                Constraint::Length(DIAGNOSTICS_PANE_HEIGHT),
+            ])
+            .areas(content);
+            frame.render_widget(
+                DiagnosticsPane::new(state.output_diagnostics()).theme(theme),
+                diagnostics_area,
+            );
+            content = rest;
+        }
+        if state.is_timeline_visible() {
+            let [rest, timeline_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(TIMELINE_PANE_HEIGHT)])
+                    .areas(content);
+            frame.render_widget(
+                TimelinePane::new(state.timeline()).theme(theme),
+                timeline_area,
+            );
+            content = rest;
+        }
+        if state.is_stderr_visible() {
+            let [rest, stderr_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(STDERR_PANE_HEIGHT)])
+                    .areas(content);
+            frame.render_widget(
+                StderrPane::new(state.stderr_lines()).theme(theme),
+                stderr_area,
+            );
+            content = rest;
+        }
+        let progress = ProgressView::new(state).compact(compact);
+        let list_area = progress.sample_list_area(content);
+        frame.render_widget(progress.theme(theme), content);
+        if let Some((rows, target)) = SampleList::new(state).target(list_area) {
+            context.hits.add(rows, target);
+        }
+    }
+}
+
+/// The sample list beside the detail of the selected sample
+pub(super) struct Samples;
+
+impl View for Samples {
+    fn title(&self) -> &str {
+        Tab::Samples.title()
+    }
+
+    fn render(&self, frame: &mut Frame, content: Rect, context: &mut ViewContext<'_>) {
+        let list_area = SampleBrowser::list_area(content);
+        if let Some((rows, target)) = SampleList::new(context.state).target(list_area) {
+            context.hits.add(rows, target);
+        }
+        frame.render_widget(
+            SampleBrowser::new(context.state).theme(context.theme),
+            content,
+        )
+    }
+
+    /// `b` bookmarks the selected sample, as there is no comparison to
+    /// toggle from the sample list
+    fn handle_key(&self, key: &KeyEvent) -> Option<UiAction> {
+        is_plain(key, KeyCode::Char('b')).then_some(UiAction::ToggleBookmark)
+    }
+}
+
+/// Summary of every metric above the detail of the selected one
+pub(super) struct Metrics;
+
+impl View for Metrics {
+    fn title(&self) -> &str {
+        Tab::Metrics.title()
+    }
+
+    fn render(&self, frame: &mut Frame, content: Rect, context: &mut ViewContext<'_>) {
+        let ViewContext { state, theme, .. } = *context;
+        let selected = state.scroll_position(Tab::Metrics);
+        let stats = state.metric_stats();
+        let Some((name, metric)) = stats.iter().nth(selected) else {
+            frame.render_widget(
+                Paragraph::new("No metrics reported yet...")
+                    .style(theme.muted)
+                    .block(Block::default().borders(Borders::ALL).title("Metrics")),
+                content,
+            );
+            return;
+        };
+
+        let [table_area, detail_area] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(content);
+        frame.render_widget(
+            MetricsView::new(stats)
+                .rules(state.rule_set())
+                .selected(selected)
+                .theme(theme),
+            table_area,
+        );
+        frame.render_widget(
+            MetricDetail::new(name, metric, state.sample_store())
+                .histogram(state.histogram(name))
+                .history(state.histogram_history(name))
+                .theme(theme),
+            detail_area,
+        );
+    }
+}
+
+/// Evaluator stderr beside the timeline of the run
+pub(super) struct Logs;
+
+impl View for Logs {
+    fn title(&self) -> &str {
+        Tab::Logs.title()
+    }
+
+    fn render(&self, frame: &mut Frame, content: Rect, context: &mut ViewContext<'_>) {
+        let ViewContext {
+            state,
+            theme,
+            keymap,
+            ..
+        } = *context;
+        let [stderr_area, timeline_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(content);
+        frame.render_widget(
+            StderrPane::new(state.stderr_lines())
+                .scrolled_back(state.scroll_position(Tab::Logs))
+                .follow_key(keymap.key(&UiAction::Scroll(Scroll::Bottom)))
+                .theme(theme),
+            stderr_area,
+        );
+        frame.render_widget(
+            TimelinePane::new(state.timeline()).theme(theme),
+            timeline_area,
+        );
+    }
+}
+
+/// Lines the evaluator wrote to stdout, as received
+pub(super) struct Raw;
+
+impl View for Raw {
+    fn title(&self) -> &str {
+        Tab::Raw.title()
+    }
+
+    fn render(&self, frame: &mut Frame, content: Rect, context: &mut ViewContext<'_>) {
+        let ViewContext {
+            state,
+            theme,
+            keymap,
+            ..
+        } = *context;
+        frame.render_widget(
+            RawLinesPane::new(state.raw_lines(), state.scroll_position(Tab::Raw))
+                .fold_depth(state.json_fold_depth())
+                .follow_key(keymap.key(&UiAction::Scroll(Scroll::Bottom)))
+                .theme(theme),
+            content,
+        )
+    }
+}
+
+/// Chosen metrics plotted over the run
+pub(super) struct Chart;
+
+impl View for Chart {
+    fn title(&self) -> &str {
+        Tab::Chart.title()
+    }
+
+    fn render(&self, frame: &mut Frame, content: Rect, context: &mut ViewContext<'_>) {
+        frame.render_widget(
+            MetricChart::new(context.state).theme(context.theme),
+            content,
+        )
+    }
+}
+
+/// Groups of an attribute ranked by a metric
+pub(super) struct Leaderboard;
+
+impl View for Leaderboard {
+    fn title(&self) -> &str {
+        Tab::Leaderboard.title()
+    }
+
+    fn render(&self, frame: &mut Frame, content: Rect, context: &mut ViewContext<'_>) {
+        frame.render_widget(
+            LeaderboardTable::new(context.state).theme(context.theme),
+            content,
+        )
+    }
+}
+
+/// Two metrics of each sample plotted against each other
+pub(super) struct Scatter;
+
+impl View for Scatter {
+    fn title(&self) -> &str {
+        Tab::Scatter.title()
+    }
+
+    fn render(&self, frame: &mut Frame, content: Rect, context: &mut ViewContext<'_>) {
+        frame.render_widget(
+            ScatterPlot::new(context.state)
+                .open_key(context.keymap.key(&UiAction::OpenOutlier))
+                .theme(context.theme),
+            content,
+        )
+    }
+
+    /// Enter opens the selected outlier, as there is no chart series to
+    /// plot from the scatter plot
+    fn handle_key(&self, key: &KeyEvent) -> Option<UiAction> {
+        is_plain(key, KeyCode::Enter).then_some(UiAction::OpenOutlier)
+    }
+}
@@ -0,0 +1,172 @@
+use crate::state::app::AppData;
+use crate::state::types::{ChartAxis, Tab};
+use crate::ui::theme::Theme;
+use chrono::{DateTime, Local};
+use ratatui::{
+    prelude::*,
+    symbols::Marker,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, List, ListItem, ListState},
+};
+
+/// Width of the list of metrics beside the chart
+const SERIES_LIST_WIDTH: u16 = 32;
+
+/// Chosen metrics of the samples in memory plotted against sample index or
+/// wall-clock time, beside the list of metrics to choose from
+pub(crate) struct MetricChart<'a> {
+    state: &'a AppData,
+    theme: &'a Theme,
+}
+
+impl<'a> MetricChart<'a> {
+    /// Create a chart of the metrics of `state`
+    pub(crate) fn new(state: &'a AppData) -> Self {
+        Self {
+            state,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Points of `metric` for each sample in memory that reported it
+    fn points(&self, metric: &str) -> Vec<(f64, f64)> {
+        let first = self.state.sample_store().in_memory().start;
+        self.state
+            .samples_in_memory()
+            .enumerate()
+            .filter_map(|(position, sample)| {
+                let x = match self.state.chart_axis() {
+                    ChartAxis::SampleIndex => (first + position + 1) as f64,
+                    ChartAxis::WallClock => sample.completed_at?.timestamp_millis() as f64 / 1000.0,
+                };
+                Some((x, sample.value(metric)?))
+            })
+            .collect()
+    }
+
+    /// List of metrics, marking those toggled on
+    fn render_series_list(&self, area: Rect, buf: &mut Buffer) {
+        let toggled = self.state.toggled_chart_series();
+        let items = self.state.metric_stats().iter().map(|(name, _)| {
+            let marker = if toggled.contains(name) { "[x]" } else { "[ ]" };
+            ListItem::new(format!("{} {}", marker, name))
+        });
+        let list = List::new(items)
+            .highlight_style(self.theme.highlight)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Series ([Enter] toggle)"),
+            );
+        let selected = self
+            .state
+            .chart_cursor()
+            .map(|_| self.state.scroll_position(Tab::Chart));
+        let mut list_state = ListState::default().with_selected(selected);
+        StatefulWidget::render(list, area, buf, &mut list_state);
+    }
+
+    /// Label of a position on the x axis
+    fn x_label(&self, x: f64) -> String {
+        match self.state.chart_axis() {
+            ChartAxis::SampleIndex => format!("{:.0}", x),
+            ChartAxis::WallClock => DateTime::from_timestamp_millis((x * 1000.0) as i64)
+                .map(|at| at.with_timezone(&Local).format("%H:%M:%S").to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Smallest and largest of `values`, widened when they are equal so the
+/// axis has a span
+pub(super) fn bounds(values: impl Iterator<Item = f64>) -> [f64; 2] {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    if !min.is_finite() {
+        [0.0, 1.0]
+    } else if min == max {
+        [min - 0.5, max + 0.5]
+    } else {
+        [min, max]
+    }
+}
+
+/// Labels at both ends and the middle of `bounds`
+pub(super) fn labels(bounds: [f64; 2], label: impl Fn(f64) -> String) -> Vec<Span<'static>> {
+    let middle = (bounds[0] + bounds[1]) / 2.0;
+    [bounds[0], middle, bounds[1]]
+        .into_iter()
+        .map(|value| Span::raw(label(value)))
+        .collect()
+}
+
+impl<'a> Widget for MetricChart<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [list_area, chart_area] =
+            Layout::horizontal([Constraint::Length(SERIES_LIST_WIDTH), Constraint::Min(0)])
+                .areas(area);
+        self.render_series_list(list_area, buf);
+
+        let series: Vec<(&str, Vec<(f64, f64)>)> = self
+            .state
+            .chart_series()
+            .into_iter()
+            .map(|name| (name, self.points(name)))
+            .collect();
+        let x_bounds = bounds(
+            series
+                .iter()
+                .flat_map(|(_, points)| points.iter().map(|p| p.0)),
+        );
+        let y_bounds = bounds(
+            series
+                .iter()
+                .flat_map(|(_, points)| points.iter().map(|p| p.1)),
+        );
+
+        let datasets = series
+            .iter()
+            .enumerate()
+            .map(|(index, (name, points))| {
+                Dataset::default()
+                    .name(name.to_string())
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(self.theme.series_color(index)))
+                    .data(points)
+            })
+            .collect();
+
+        let (x_title, hint) = match self.state.chart_axis() {
+            ChartAxis::SampleIndex => ("sample", "[x] plot against time"),
+            ChartAxis::WallClock => ("time", "[x] plot against sample"),
+        };
+        let chart = Chart::new(datasets)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Chart")
+                    .title(Line::from(format!(" {} ", hint)).right_aligned()),
+            )
+            .x_axis(
+                Axis::default()
+                    .title(x_title)
+                    .style(self.theme.border)
+                    .bounds(x_bounds)
+                    .labels(labels(x_bounds, |x| self.x_label(x))),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(self.theme.border)
+                    .bounds(y_bounds)
+                    .labels(labels(y_bounds, |y| format!("{:.3}", y))),
+            );
+        chart.render(chart_area, buf);
+    }
+}
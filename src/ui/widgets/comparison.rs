@@ -0,0 +1,178 @@
+use super::stats::MetricStatsTable;
+use crate::state::app::AppData;
+use crate::state::baseline::Baseline;
+use crate::state::significance::{MetricComparison, Summary, DEFAULT_ALPHA};
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+};
+
+/// The run side by side with a baseline run, with how each metric moved
+pub(crate) struct ComparisonView<'a> {
+    state: &'a AppData,
+    baseline: &'a Baseline,
+    theme: &'a Theme,
+}
+
+impl<'a> ComparisonView<'a> {
+    /// Create a comparison of the run in `state` with `baseline`
+    pub(crate) fn new(state: &'a AppData, baseline: &'a Baseline) -> Self {
+        Self {
+            state,
+            baseline,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Evaluator, progress and score of the current run
+    fn current_lines(&self) -> Vec<Line<'static>> {
+        let (completed, total, percentage) = self.state.progress();
+        let (failed, _, _) = self.state.summary_stats();
+        let evaluator = self
+            .state
+            .evaluator_name()
+            .map_or_else(|| "–".to_string(), ToString::to_string);
+        let progress = match total {
+            Some(total) => format!("{}/{} samples ({:.1}%)", completed, total, percentage),
+            None => format!("{} samples", completed),
+        };
+        self.run_lines(evaluator, progress, failed, self.state.mean_score())
+    }
+
+    /// Evaluator, samples and score of the baseline run
+    fn baseline_lines(&self) -> Vec<Line<'static>> {
+        let baseline = self.baseline;
+        let progress = match baseline.total_samples {
+            Some(total) => format!("{}/{} samples", baseline.samples_completed, total),
+            None => format!("{} samples", baseline.samples_completed),
+        };
+        self.run_lines(
+            baseline.evaluator.clone(),
+            progress,
+            baseline.samples_failed,
+            baseline.score,
+        )
+    }
+
+    /// Lines describing a run, the same way for either side
+    fn run_lines(
+        &self,
+        evaluator: String,
+        progress: String,
+        failed: usize,
+        score: Option<f64>,
+    ) -> Vec<Line<'static>> {
+        let failed_style = if failed > 0 {
+            self.theme.error
+        } else {
+            self.theme.success
+        };
+        let mut lines = vec![
+            Line::from(format!("Evaluator: {}", evaluator)),
+            Line::from(format!("Progress: {}", progress)),
+            Line::styled(format!("Failed: {}", failed), failed_style),
+        ];
+        if let Some(score) = score {
+            let name = self.state.score().map_or("Score", |score| score.name());
+            lines.push(Line::styled(
+                format!("{}: {:.3}", name, score),
+                self.theme.heading,
+            ));
+        }
+        lines
+    }
+
+    /// Table row of how one metric's mean moved from the baseline
+    fn row(&self, comparison: &MetricComparison) -> Row<'static> {
+        let mean =
+            |summary: &Summary| MetricStatsTable::cell((summary.count > 0).then_some(summary.mean));
+        let both = comparison.baseline.count > 0 && comparison.candidate.count > 0;
+        let (change, relative) = if both {
+            let difference = comparison.difference();
+            let relative = match comparison.baseline.mean {
+                0.0 => String::new(),
+                baseline => format!("{:+.1}%", difference / baseline.abs() * 100.0),
+            };
+            (format!("{:+.3}", difference), relative)
+        } else {
+            (String::new(), String::new())
+        };
+        let p_value = match comparison.test {
+            Some(test) if comparison.is_significant(DEFAULT_ALPHA) => {
+                Cell::from(format!("{:.3}", test.p_value)).style(self.theme.notice)
+            }
+            Some(test) => Cell::from(format!("{:.3}", test.p_value)).style(self.theme.muted),
+            None => Cell::from(""),
+        };
+
+        Row::new(vec![
+            Cell::from(comparison.metric.clone()),
+            Cell::from(mean(&comparison.candidate)),
+            Cell::from(mean(&comparison.baseline)),
+            Cell::from(change),
+            Cell::from(relative),
+            p_value,
+        ])
+    }
+}
+
+impl<'a> Widget for ComparisonView<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let current = self.current_lines();
+        let baseline = self.baseline_lines();
+        let runs_height = current.len().max(baseline.len()) as u16 + 2;
+        let [runs_area, deltas_area] =
+            Layout::vertical([Constraint::Length(runs_height), Constraint::Min(0)]).areas(area);
+        let [current_area, baseline_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(runs_area);
+
+        Paragraph::new(current)
+            .block(Block::default().borders(Borders::ALL).title("This run"))
+            .render(current_area, buf);
+        Paragraph::new(baseline)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Baseline: {}", self.baseline.label)),
+            )
+            .render(baseline_area, buf);
+
+        let comparisons = self.baseline.compare(self.state.metric_stats());
+        let block = Block::default().borders(Borders::ALL).title(format!(
+            "Change of the mean from the baseline ([b] back, highlighted: p < {})",
+            DEFAULT_ALPHA
+        ));
+        if comparisons.is_empty() {
+            Paragraph::new("No metrics in either run yet...")
+                .style(self.theme.muted)
+                .block(block)
+                .render(deltas_area, buf);
+            return;
+        }
+
+        let header = Row::new(["Metric", "This run", "Baseline", "Change", "Change %", "p"])
+            .style(self.theme.heading);
+        let rows = comparisons.iter().map(|comparison| self.row(comparison));
+        let widths = [
+            Constraint::Min(20),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(7),
+        ];
+        Widget::render(
+            Table::new(rows, widths).header(header).block(block),
+            deltas_area,
+            buf,
+        );
+    }
+}
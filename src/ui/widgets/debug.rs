@@ -0,0 +1,106 @@
+use super::format_bytes;
+use crate::evaluator::output_queue::OutputDiagnostics;
+use crate::state::perf::{ChannelDepth, PerfStats};
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// Width of the box in the corner of the view
+const WIDTH: u16 = 52;
+
+/// Box in the bottom right corner, clear of the toasts, showing how fast
+/// PrEval itself draws, parses and drains its channels, for diagnosing a
+/// sluggish UI
+pub(crate) struct DebugOverlay<'a> {
+    stats: &'a PerfStats,
+    output: &'a OutputDiagnostics,
+    theme: &'a Theme,
+}
+
+impl<'a> DebugOverlay<'a> {
+    /// Create an overlay showing `stats` and the evaluator's `output` buffer
+    pub(crate) fn new(stats: &'a PerfStats, output: &'a OutputDiagnostics) -> Self {
+        Self {
+            stats,
+            output,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl<'a> Widget for DebugOverlay<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let stats = self.stats;
+        let parse = match stats.parse_percentiles {
+            Some([p50, p90, p99]) => format!("p50 {:?}, p90 {:?}, p99 {:?}", p50, p90, p99),
+            None => "nothing parsed yet".to_string(),
+        };
+        let memory = match stats.memory_bytes {
+            Some(bytes) => format!(
+                "{} (peak {})",
+                format_bytes(bytes),
+                format_bytes(stats.peak_memory_bytes)
+            ),
+            None => "not sampled yet".to_string(),
+        };
+        let allocations = match stats.allocations {
+            Some(allocations) => format!(
+                "{} ({} live, {})",
+                allocations.allocations,
+                allocations.live(),
+                format_bytes(allocations.live_bytes)
+            ),
+            None => "not counted (see alloc-stats)".to_string(),
+        };
+        let queue = |name: &str, depth: usize, capacity: usize| {
+            let line = Line::from(format!("{}: {}/{}", name, depth, capacity));
+            if capacity > 0 && depth >= capacity {
+                line.style(self.theme.warning)
+            } else {
+                line
+            }
+        };
+        let channel =
+            |name: &str, channel: ChannelDepth| queue(name, channel.depth, channel.capacity);
+
+        let lines = vec![
+            Line::from(format!(
+                "Frames: {} (last {:?}, max {:?})",
+                stats.frames, stats.last_frame_time, stats.max_frame_time
+            )),
+            Line::from(format!("Lines parsed: {}", stats.lines_parsed)),
+            Line::from(format!("Parse time: {}", parse)),
+            queue("Output buffer", self.output.depth, self.output.capacity),
+            channel("Parsed queue", stats.parsed_queue),
+            channel("Action queue", stats.action_queue),
+            Line::from(format!("Memory: {}", memory)),
+            Line::from(format!("Allocations: {}", allocations)),
+        ];
+
+        let width = area.width.min(WIDTH);
+        let height = area.height.min(lines.len() as u16 + 2);
+        let [overlay] = Layout::horizontal([Constraint::Length(width)])
+            .flex(layout::Flex::End)
+            .areas(area);
+        let [overlay] = Layout::vertical([Constraint::Length(height)])
+            .flex(layout::Flex::End)
+            .areas(overlay);
+        Clear.render(overlay, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Debug")
+                    .border_style(self.theme.border),
+            )
+            .render(overlay, buf);
+    }
+}
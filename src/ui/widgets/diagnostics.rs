@@ -0,0 +1,57 @@
+use crate::evaluator::output_queue::OutputDiagnostics;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+/// Pane showing how well PrEval keeps up with the evaluator's output
+pub(crate) struct DiagnosticsPane<'a> {
+    output: &'a OutputDiagnostics,
+    theme: &'a Theme,
+}
+
+impl<'a> DiagnosticsPane<'a> {
+    /// Create a new diagnostics pane
+    pub(crate) fn new(output: &'a OutputDiagnostics) -> Self {
+        Self {
+            output,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl<'a> Widget for DiagnosticsPane<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Diagnostics")
+            .border_style(self.theme.border);
+
+        let dropped_style = if self.output.dropped > 0 {
+            self.theme.warning
+        } else {
+            Style::default()
+        };
+
+        let text = vec![
+            Line::from(format!(
+                "Output queue: {}/{} lines (peak {})",
+                self.output.depth, self.output.capacity, self.output.peak_depth
+            )),
+            Line::from(format!("Dropped lines: {}", self.output.dropped)).style(dropped_style),
+            Line::from(format!(
+                "Queue latency: {:?} (max {:?})",
+                self.output.last_latency, self.output.max_latency
+            )),
+        ];
+
+        Paragraph::new(text).block(block).render(area, buf);
+    }
+}
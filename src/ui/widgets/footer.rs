@@ -1,14 +1,38 @@
+use super::format_bytes;
+use crate::state::cost::CostTracker;
+use crate::state::types::{ConfirmAction, ResourceUsage, Scroll, UiAction};
+use crate::ui::keymap::Keymap;
+use crate::ui::theme::Theme;
 use ratatui::{prelude::*, widgets::Paragraph};
 
-/// Footer widget showing keyboard shortcuts
-pub(crate) struct Footer {
+/// Footer widget showing keyboard shortcuts, evaluator resource usage and
+/// the run's estimated cost
+pub(crate) struct Footer<'a> {
+    keymap: &'a Keymap,
     paused: bool,
+    resources: Option<&'a ResourceUsage>,
+    cost: Option<&'a CostTracker>,
+    confirmation: Option<ConfirmAction>,
+    theme: &'a Theme,
 }
 
-impl Footer {
-    /// Create a new footer widget
-    pub(crate) fn new() -> Self {
-        Self { paused: false }
+impl<'a> Footer<'a> {
+    /// Create a new footer widget showing the keys bound in `keymap`
+    pub(crate) fn new(keymap: &'a Keymap) -> Self {
+        Self {
+            keymap,
+            paused: false,
+            resources: None,
+            cost: None,
+            confirmation: None,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
     }
 
     /// Set paused state (builder pattern)
@@ -16,20 +40,110 @@ impl Footer {
         self.paused = paused;
         self
     }
+
+    /// Ask the user to confirm an action instead of listing shortcuts (builder pattern)
+    pub(crate) fn confirmation(mut self, confirmation: Option<ConfirmAction>) -> Self {
+        self.confirmation = confirmation;
+        self
+    }
+
+    /// Set the evaluator's latest resource usage (builder pattern)
+    pub(crate) fn resources(mut self, resources: Option<&'a ResourceUsage>) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    /// Set the run's estimated cost (builder pattern)
+    pub(crate) fn cost(mut self, cost: Option<&'a CostTracker>) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// `[key] label` hints for `actions`, skipping any without a key
+    fn hints(&self, actions: &[(&[UiAction], &str)]) -> String {
+        actions
+            .iter()
+            .filter_map(|(actions, label)| {
+                let keys = actions
+                    .iter()
+                    .map(|action| self.keymap.key(action).map(|key| key.to_string()))
+                    .collect::<Option<String>>()?;
+                Some(format!("[{}] {}", keys, label))
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
 }
 
-impl Widget for Footer {
+impl<'a> Widget for Footer<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let shortcuts = if self.paused {
-            "[q] Quit  [Space] Resume  [Ctrl+L] Refresh"
-        } else {
-            "[q] Quit  [Space] Pause  [Ctrl+L] Refresh"
-        };
+        if let Some(action) = self.confirmation {
+            let answers = self.hints(&[(&[UiAction::Confirm], "Yes"), (&[UiAction::Cancel], "No")]);
+            Paragraph::new(format!("{}  {}", action.prompt(), answers))
+                .style(self.theme.warning.add_modifier(Modifier::BOLD))
+                .render(area, buf);
+            return;
+        }
+
+        let shortcuts = self.hints(&[
+            (&[UiAction::Quit], "Quit"),
+            (&[UiAction::ToggleHelp], "Help"),
+            (&[UiAction::NextTab], "View"),
+            (
+                &[UiAction::Scroll(Scroll::Up), UiAction::Scroll(Scroll::Down)],
+                "Scroll",
+            ),
+            (
+                &[UiAction::TogglePause],
+                if self.paused { "Resume" } else { "Pause" },
+            ),
+            (&[UiAction::KillEvaluator], "Kill"),
+            (&[UiAction::RestartRun], "Restart"),
+            (&[UiAction::ToggleStderr], "Stderr"),
+            (&[UiAction::ToggleDiagnostics], "Diagnostics"),
+            (&[UiAction::ToggleTimeline], "Timeline"),
+            (&[UiAction::CycleGroupBy], "Group"),
+            (&[UiAction::Refresh], "Refresh"),
+        ]);
+
+        Paragraph::new(shortcuts)
+            .style(self.theme.muted)
+            .alignment(Alignment::Left)
+            .render(area, buf);
 
-        let footer = Paragraph::new(shortcuts)
-            .style(Style::default().fg(Color::DarkGray))
-            .alignment(Alignment::Left);
+        let mut status = Vec::new();
+        if let Some(usage) = self.resources {
+            let mut text = format!(
+                "CPU {:.0}%  RSS {}",
+                usage.cpu_percent,
+                format_bytes(usage.memory_bytes)
+            );
+            if let Some(open_files) = usage.open_files {
+                text.push_str(&format!("  FDs {}", open_files));
+            }
+            status.push(Span::styled(text, self.theme.muted));
+        }
+        if let Some(cost) = self.cost {
+            let mut text = format!("  Cost ${:.4}", cost.total_usd());
+            if let Some(budget) = cost.budget_usd() {
+                text.push_str(&format!(" / ${:.2}", budget));
+            }
+            // Tokens of models without a price are not in the total
+            if !cost.unpriced_models().is_empty() {
+                text.push_str(&format!(" ({} unpriced)", cost.unpriced_models().len()));
+            }
+            let style = if cost.is_over_budget() {
+                self.theme.error
+            } else {
+                self.theme.muted
+            };
+            status.push(Span::styled(text, style));
+        }
 
-        footer.render(area, buf);
+        if !status.is_empty() {
+            Paragraph::new(Line::from(status))
+                .alignment(Alignment::Right)
+                .render(area, buf);
+        }
     }
 }
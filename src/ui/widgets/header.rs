@@ -1,5 +1,6 @@
 use crate::evaluator::protocol::ValidatedHandshake;
 use crate::state::types::EvaluatorName;
+use crate::ui::theme::Theme;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
@@ -9,6 +10,8 @@ use ratatui::{
 pub(crate) struct Header<'a> {
     evaluator_name: Option<&'a EvaluatorName>,
     handshake: Option<&'a ValidatedHandshake>,
+    compact: bool,
+    theme: &'a Theme,
 }
 
 impl<'a> Header<'a> {
@@ -17,9 +20,23 @@ impl<'a> Header<'a> {
         Self {
             evaluator_name: None,
             handshake: None,
+            compact: false,
+            theme: &Theme::DARK,
         }
     }
 
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Draw a single line without the border, for small terminals (builder pattern)
+    pub(crate) fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
     /// Set the evaluator name (builder pattern)
     pub(crate) fn evaluator_name(mut self, name: &'a EvaluatorName) -> Self {
         self.evaluator_name = Some(name);
@@ -58,26 +75,29 @@ impl<'a> Widget for Header<'a> {
             }
         };
 
+        if self.compact {
+            let mut spans = vec![Span::styled(title, self.theme.title)];
+            if let Some(sub) = subtitle {
+                spans.push(Span::styled(format!("  •  {}", sub), self.theme.subtitle));
+            }
+            Paragraph::new(Line::from(spans))
+                .alignment(Alignment::Center)
+                .render(area, buf);
+            return;
+        }
+
         let block = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Cyan))
+            .border_style(self.theme.accent)
             .border_type(ratatui::widgets::BorderType::Rounded);
 
         // Create text with title and optional subtitle
         let text = match subtitle {
             Some(sub) => Text::from(vec![
-                Line::from(title).style(
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Line::from(sub).style(Style::default().fg(Color::Gray)),
+                Line::from(title).style(self.theme.title),
+                Line::from(sub).style(self.theme.subtitle),
             ]),
-            None => Text::from(title).style(
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            ),
+            None => Text::from(title).style(self.theme.title),
         };
 
         let paragraph = Paragraph::new(text)
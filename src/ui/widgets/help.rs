@@ -0,0 +1,75 @@
+use crate::ui::keymap::Keymap;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Clear, Row, Table},
+};
+
+/// Widest the help gets on large terminals
+const MAX_WIDTH: u16 = 72;
+
+/// Modal listing every keybinding, drawn over the view
+pub(crate) struct HelpOverlay<'a> {
+    keymap: &'a Keymap,
+    theme: &'a Theme,
+}
+
+impl<'a> HelpOverlay<'a> {
+    /// Create a help overlay listing the bindings of `keymap`
+    pub(crate) fn new(keymap: &'a Keymap) -> Self {
+        Self {
+            keymap,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl<'a> Widget for HelpOverlay<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let rows: Vec<Row> = self
+            .keymap
+            .bindings()
+            .iter()
+            .filter(|binding| !binding.hidden)
+            .map(|binding| {
+                let keys = binding
+                    .keys
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" / ");
+                Row::new(vec![
+                    Cell::from(keys).style(self.theme.accent),
+                    Cell::from(binding.description.as_str()),
+                ])
+            })
+            .collect();
+
+        // Centered, with room for the borders around the rows
+        let width = area.width.min(MAX_WIDTH);
+        let height = area.height.min(rows.len() as u16 + 2);
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(layout::Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(layout::Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+        Widget::render(
+            Table::new(rows, [Constraint::Length(14), Constraint::Min(0)]).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Keybindings ([?]/[Esc] close)"),
+            ),
+            area,
+            buf,
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use super::stats::MetricStatsTable;
+use crate::state::app::AppData;
+use crate::state::types::Tab;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+};
+
+/// Groups of an attribute such as the model ranked by a metric, for
+/// comparing the models or variants one evaluator runs side by side
+pub(crate) struct Leaderboard<'a> {
+    state: &'a AppData,
+    theme: &'a Theme,
+}
+
+impl<'a> Leaderboard<'a> {
+    /// Create a leaderboard of the run in `state`
+    pub(crate) fn new(state: &'a AppData) -> Self {
+        Self {
+            state,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl<'a> Widget for Leaderboard<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (Some(key), Some(metric)) = (
+            self.state.leaderboard_attribute(),
+            self.state.leaderboard_metric(),
+        ) else {
+            Paragraph::new("No samples with attributes to rank yet")
+                .style(self.theme.muted)
+                .block(Block::default().borders(Borders::ALL).title("Leaderboard"))
+                .render(area, buf);
+            return;
+        };
+
+        let ranking = self.state.leaderboard();
+        let leader = ranking.first().and_then(|(_, stats)| stats.mean());
+        let header = Row::new([
+            "#", key, "n", "Mean", "Gap", "p50", "p95", "Min", "Max", "StdDev",
+        ])
+        .style(self.theme.heading);
+        let rows = ranking.iter().enumerate().map(|(rank, (group, stats))| {
+            let gap = match (rank, leader, stats.mean()) {
+                (0, _, _) => String::new(),
+                (_, Some(leader), Some(mean)) => format!("{:+.3}", mean - leader),
+                _ => String::new(),
+            };
+            let row = Row::new(vec![
+                (rank + 1).to_string(),
+                group.to_string(),
+                stats.count().to_string(),
+                MetricStatsTable::cell(stats.mean()),
+                gap,
+                MetricStatsTable::cell(stats.median()),
+                MetricStatsTable::cell(stats.p95()),
+                MetricStatsTable::cell(stats.min()),
+                MetricStatsTable::cell(stats.max()),
+                MetricStatsTable::cell(stats.stddev()),
+            ]);
+            if rank == 0 {
+                row.style(self.theme.success.add_modifier(Modifier::BOLD))
+            } else {
+                row
+            }
+        });
+        let widths = [
+            Constraint::Length(4),
+            Constraint::Min(16),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+        ];
+
+        let order = if self.state.is_leaderboard_ascending() {
+            "lowest first"
+        } else {
+            "highest first"
+        };
+        let title = format!(
+            "Leaderboard: {} by mean {}, {} [g] attribute [m] metric [M] order",
+            key, metric, order
+        );
+        let table = Table::new(rows, widths)
+            .header(header)
+            .highlight_style(self.theme.highlight)
+            .block(Block::default().borders(Borders::ALL).title(title));
+
+        let selected = (!ranking.is_empty()).then(|| self.state.scroll_position(Tab::Leaderboard));
+        let mut table_state = TableState::default().with_selected(selected);
+        StatefulWidget::render(table, area, buf, &mut table_state);
+    }
+}
@@ -0,0 +1,265 @@
+use super::sparkline_bars;
+use crate::state::aggregation::MetricStats;
+use crate::state::histogram::{HistogramHistory, MergedHistogram};
+use crate::state::sample_store::SampleStore;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Paragraph, Sparkline},
+};
+
+/// Statistics of one metric with its values over the samples in memory
+pub(crate) struct MetricDetail<'a> {
+    name: &'a str,
+    stats: &'a MetricStats,
+    samples: &'a SampleStore,
+    histogram: Option<&'a MergedHistogram>,
+    history: Option<&'a HistogramHistory>,
+    theme: &'a Theme,
+}
+
+impl<'a> MetricDetail<'a> {
+    /// Create a detail view of metric `name`
+    pub(crate) fn new(name: &'a str, stats: &'a MetricStats, samples: &'a SampleStore) -> Self {
+        Self {
+            name,
+            stats,
+            samples,
+            histogram: None,
+            history: None,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Draw the buckets of a histogram metric instead of its values (builder pattern)
+    pub(crate) fn histogram(mut self, histogram: Option<&'a MergedHistogram>) -> Self {
+        self.histogram = histogram;
+        self
+    }
+
+    /// Draw how a histogram metric's buckets shifted over the run beside
+    /// them (builder pattern)
+    pub(crate) fn history(mut self, history: Option<&'a HistogramHistory>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Values of the metric in the samples kept in memory, oldest first
+    fn values(&self) -> Vec<f64> {
+        self.samples
+            .recent(self.samples.capacity())
+            .filter_map(|sample| sample.value(self.name))
+            .collect()
+    }
+
+    /// Number of samples in memory where the metric was anomalous
+    fn anomalies(&self) -> usize {
+        self.samples
+            .recent(self.samples.capacity())
+            .filter(|sample| sample.anomalies.iter().any(|name| name == self.name))
+            .count()
+    }
+}
+
+/// Format one statistic, or a dash when there is no value
+fn stat(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("{:.4}", v))
+}
+
+impl<'a> Widget for MetricDetail<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Metric {}", self.name));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let stats = self.stats;
+        let mut lines = vec![
+            Line::from(format!(
+                "n = {}  mean = {}  stddev = {}",
+                stats.count(),
+                stat(stats.mean()),
+                stat(stats.stddev())
+            )),
+            Line::from(format!(
+                "min = {}  p50 = {}  p90 = {}  p95 = {}  p99 = {}  max = {}",
+                stat(stats.min()),
+                stat(stats.median()),
+                stat(stats.p90()),
+                stat(stats.p95()),
+                stat(stats.p99()),
+                stat(stats.max())
+            )),
+        ];
+        let mut notes = Vec::new();
+        if stats.warmup_excluded() > 0 {
+            notes.push(format!("{} warm-up excluded", stats.warmup_excluded()));
+        }
+        if stats.trimmed() > 0 {
+            notes.push(format!("{} outliers trimmed", stats.trimmed()));
+        }
+        let anomalies = self.anomalies();
+        if anomalies > 0 {
+            notes.push(format!("{} anomalous samples in memory", anomalies));
+        }
+        if !notes.is_empty() {
+            lines.push(Line::styled(notes.join(", "), self.theme.warning));
+        }
+
+        let [text_area, chart_area] = Layout::vertical([
+            Constraint::Length(lines.len() as u16 + 1),
+            Constraint::Min(0),
+        ])
+        .areas(inner);
+        Paragraph::new(lines).render(text_area, buf);
+
+        if let Some(histogram) = self.histogram {
+            match self.history.filter(|history| history.windows().len() > 1) {
+                Some(history) => {
+                    let [buckets_area, heatmap_area] =
+                        Layout::vertical([Constraint::Percentage(50), Constraint::Min(0)])
+                            .areas(chart_area);
+                    render_buckets(histogram, self.theme, buckets_area, buf);
+                    render_heatmap(history, self.theme, heatmap_area, buf);
+                }
+                None => render_buckets(histogram, self.theme, chart_area, buf),
+            }
+            return;
+        }
+        let bars = sparkline_bars(&self.values(), chart_area.width);
+        Sparkline::default()
+            .data(&bars)
+            .max(100)
+            .style(self.theme.accent)
+            .render(chart_area, buf);
+    }
+}
+
+/// Horizontal bar per bucket, marking those holding the minimum, median and
+/// maximum
+fn render_buckets(histogram: &MergedHistogram, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let buckets = histogram.buckets();
+    let marked = |value: Option<f64>| {
+        value.and_then(|value| buckets.iter().position(|b| value <= b.upper_bound))
+    };
+    let min = marked(histogram.min());
+    let median = histogram.bucket_of_quantile(0.5);
+    let max = marked(histogram.max());
+
+    let bars: Vec<Bar> = buckets
+        .iter()
+        .enumerate()
+        .map(|(index, bucket)| {
+            let bound = bound_label(bucket.upper_bound);
+            let markers: Vec<&str> = [(min, "min"), (median, "p50"), (max, "max")]
+                .into_iter()
+                .filter(|(marked, _)| *marked == Some(index))
+                .map(|(_, marker)| marker)
+                .collect();
+            let style = if median == Some(index) {
+                theme.warning
+            } else {
+                theme.accent
+            };
+            let label = if markers.is_empty() {
+                bound
+            } else {
+                format!("{} {}", bound, markers.join("/"))
+            };
+            Bar::default()
+                .value(bucket.count)
+                .label(Line::from(label))
+                .style(style)
+                .value_style(style.reversed())
+        })
+        .collect();
+
+    let mut title = format!("Buckets ({} values", histogram.count());
+    if let Some(median) = histogram.quantile(0.5) {
+        title.push_str(&format!(", p50 ≈ {:.3}", median));
+    }
+    if histogram.mismatched() > 0 {
+        title.push_str(&format!(
+            ", {} with other bounds left out",
+            histogram.mismatched()
+        ));
+    }
+    title.push(')');
+
+    BarChart::default()
+        .block(Block::default().title(title))
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .data(BarGroup::default().bars(&bars))
+        .render(area, buf);
+}
+
+/// Label of the bucket with upper bound `bound`
+fn bound_label(bound: f64) -> String {
+    if bound.is_finite() {
+        format!("≤{}", bound)
+    } else {
+        "+Inf".to_string()
+    }
+}
+
+/// Shades of a heatmap cell, from empty to the largest count
+const HEAT_SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// Row per bucket, lowest at the bottom, and column per window of the run,
+/// newest on the right, shaded by how many values fell there
+fn render_heatmap(history: &HistogramHistory, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let block = Block::default().title(format!(
+        "Over time (columns of {}s, newest right)",
+        history.window().as_secs()
+    ));
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let labels: Vec<String> = history
+        .upper_bounds()
+        .iter()
+        .map(|bound| bound_label(*bound))
+        .collect();
+    let label_width = labels
+        .iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let columns = (inner.width as usize).saturating_sub(label_width + 1);
+    let windows = history.windows();
+    let shown = &windows[windows.len().saturating_sub(columns)..];
+    let max = history.max_count().max(1);
+    let shade = |count: u64| match count {
+        0 => HEAT_SHADES[0],
+        count => {
+            let level = (count as f64 / max as f64 * (HEAT_SHADES.len() - 1) as f64).ceil();
+            HEAT_SHADES[(level as usize).clamp(1, HEAT_SHADES.len() - 1)]
+        }
+    };
+
+    // The lowest buckets are kept when not all fit
+    let rows = labels.len().min(inner.height as usize);
+    let lines: Vec<Line> = (0..rows)
+        .rev()
+        .map(|bucket| {
+            let cells: String = shown.iter().map(|window| shade(window[bucket])).collect();
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>width$} ", labels[bucket], width = label_width),
+                    theme.muted,
+                ),
+                Span::styled(cells, theme.accent),
+            ])
+        })
+        .collect();
+    Paragraph::new(lines).render(inner, buf);
+}
@@ -1,168 +1,106 @@
-use crate::state::metrics::{AttributeKey, AttributeValue, Metric, MetricData};
-use crate::state::types::EvaluationStatus;
+use super::scroll_position_text;
+use super::stats::{stat_cell, stats_title};
+use crate::state::aggregation::{MetricAggregator, MetricStats, Trend};
+use crate::state::rules::{Aggregate, RuleSet};
+use crate::ui::theme::Theme;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem},
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
 };
-use std::fmt::Write as _;
 
-/// Metrics display widget
-#[allow(dead_code)] // Used in future stories
+/// Summary of every metric reported per sample, one row each, with the
+/// way its recent values are heading
 pub(crate) struct MetricsView<'a> {
-    metrics: &'a [MetricData],
-    status: &'a EvaluationStatus,
+    stats: &'a MetricAggregator,
+    rules: RuleSet<'a>,
+    selected: Option<usize>,
+    theme: &'a Theme,
 }
 
 impl<'a> MetricsView<'a> {
-    /// Create a new metrics view
-    #[allow(dead_code)] // Used in future stories
-    pub(crate) fn new(metrics: &'a [MetricData], status: &'a EvaluationStatus) -> Self {
-        Self { metrics, status }
+    /// Create a summary of `stats`
+    pub(crate) fn new(stats: &'a MetricAggregator) -> Self {
+        Self {
+            stats,
+            rules: RuleSet::default(),
+            selected: None,
+            theme: &Theme::DARK,
+        }
     }
 
-    /// Format a metric value for display
-    #[allow(dead_code)] // Used in future stories
-    fn format_metric_line(&self, metric: &Metric) -> Vec<String> {
-        let mut lines = Vec::new();
-
-        match metric {
-            Metric::Gauge {
-                name,
-                data_points,
-                unit,
-            } => {
-                for point in data_points {
-                    let mut line = format!("  {}: {:.2}", name, point.value.value());
-
-                    if let Some(unit) = unit {
-                        write!(&mut line, " {}", unit).ok();
-                    }
-
-                    // Add sample ID if present
-                    if let Some(sample_id) = self.get_sample_id(&point.attributes) {
-                        write!(&mut line, " (sample: {})", sample_id).ok();
-                    }
-
-                    lines.push(line);
-                }
-            }
-            Metric::Counter {
-                name,
-                data_points,
-                unit,
-            } => {
-                for point in data_points {
-                    let mut line = format!("  {}: {:.0}", name, point.value.value());
-
-                    if let Some(unit) = unit {
-                        write!(&mut line, " {}", unit).ok();
-                    }
-
-                    // Add sample ID if present
-                    if let Some(sample_id) = self.get_sample_id(&point.attributes) {
-                        write!(&mut line, " (sample: {})", sample_id).ok();
-                    }
-
-                    lines.push(line);
-                }
-            }
-            Metric::Histogram {
-                name,
-                data_points,
-                unit,
-            } => {
-                for point in data_points {
-                    let avg = if point.value.count > 0 {
-                        point.value.sum.unwrap_or(0.0) / point.value.count as f64
-                    } else {
-                        0.0
-                    };
-
-                    let mut line = format!("  {}: {:.0}", name, avg);
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
 
-                    if let Some(unit) = unit {
-                        write!(&mut line, "{}", unit).ok();
-                    }
+    /// Color the statistics by the threshold rules covering them (builder pattern)
+    pub(crate) fn rules(mut self, rules: RuleSet<'a>) -> Self {
+        self.rules = rules;
+        self
+    }
 
-                    // Add sample ID if present
-                    if let Some(sample_id) = self.get_sample_id(&point.attributes) {
-                        write!(&mut line, " (sample: {})", sample_id).ok();
-                    }
+    /// Highlight the metric at `index` (builder pattern)
+    pub(crate) fn selected(mut self, index: usize) -> Self {
+        self.selected = Some(index);
+        self
+    }
 
-                    lines.push(line);
-                }
-            }
+    /// Arrow for the trend of `stats`, blank until there is one
+    fn trend(&self, stats: &MetricStats) -> Span<'static> {
+        match stats.trend() {
+            Some(Trend::Rising) => Span::styled("↑", self.theme.accent),
+            Some(Trend::Falling) => Span::styled("↓", self.theme.accent),
+            Some(Trend::Steady) => Span::styled("→", self.theme.muted),
+            None => Span::raw(""),
         }
-
-        lines
     }
 
-    /// Extract sample ID from attributes
-    #[allow(dead_code)] // Used in future stories
-    fn get_sample_id<'b>(
-        &self,
-        attributes: &'b std::collections::HashMap<AttributeKey, AttributeValue>,
-    ) -> Option<&'b str> {
-        // Try to find sample.id attribute
-        for (key, value) in attributes {
-            if key.as_ref() == "sample.id" {
-                if let AttributeValue::StringValue(s) = value {
-                    return Some(s.as_str());
-                }
-            }
-        }
-        None
+    /// Build the table row for one metric
+    fn row(&self, name: &str, stats: &MetricStats) -> Row<'static> {
+        let cell = |aggregate, value| stat_cell(&self.rules, self.theme, name, aggregate, value);
+        Row::new(vec![
+            Cell::from(name.to_string()),
+            Cell::from(stats.count().to_string()),
+            cell(Aggregate::Mean, stats.mean()),
+            cell(Aggregate::Median, stats.median()),
+            cell(Aggregate::P95, stats.p95()),
+            cell(Aggregate::Min, stats.min()),
+            cell(Aggregate::Max, stats.max()),
+            Cell::from(Line::from(self.trend(stats)).centered()),
+        ])
     }
 }
 
 impl<'a> Widget for MetricsView<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Gray));
-
-        // Collect all metric lines
-        let mut items =
-            vec![ListItem::new("Metrics:").style(Style::default().add_modifier(Modifier::BOLD))];
-
-        if self.metrics.is_empty() {
-            items.push(
-                ListItem::new("  No metrics received yet...")
-                    .style(Style::default().fg(Color::DarkGray)),
-            );
-        } else {
-            // Show latest metrics
-            for metric_data in self.metrics.iter().rev().take(10) {
-                for metric in &metric_data.metrics {
-                    for line in self.format_metric_line(metric) {
-                        items.push(ListItem::new(line));
-                    }
-                }
-            }
+        let header = Row::new(["Metric", "n", "Mean", "p50", "p95", "Min", "Max", "Trend"])
+            .style(self.theme.heading);
+        let rows = self.stats.iter().map(|(name, stats)| self.row(name, stats));
+        let widths = [
+            Constraint::Min(20),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(5),
+        ];
+
+        let mut title = stats_title(self.stats);
+        if let Some(selected) = self.selected {
+            title.push_str(&format!(
+                " - {}",
+                scroll_position_text("metric", selected + 1, self.stats.len(), None)
+            ));
         }
+        let table = Table::new(rows, widths)
+            .header(header)
+            .highlight_style(self.theme.highlight)
+            .block(Block::default().borders(Borders::ALL).title(title));
 
-        // Add status line
-        items.push(ListItem::new(""));
-        let status_line = match self.status {
-            EvaluationStatus::Starting => "Status: Starting evaluator...".to_string(),
-            EvaluationStatus::WaitingForHandshake => "Status: Waiting for handshake...".to_string(),
-            EvaluationStatus::CollectingMetrics { received, total } => match total {
-                Some(t) => format!("Status: Collecting metrics... ({}/{})", received, t),
-                None => format!("Status: Collecting metrics... ({})", received),
-            },
-            EvaluationStatus::Completed => "Status: Evaluation completed".to_string(),
-            EvaluationStatus::Failed(err) => format!("Status: Failed - {}", err),
-        };
-
-        let status_style = match self.status {
-            EvaluationStatus::Failed(_) => Style::default().fg(Color::Red),
-            EvaluationStatus::Completed => Style::default().fg(Color::Green),
-            _ => Style::default().fg(Color::Yellow),
-        };
-
-        items.push(ListItem::new(status_line).style(status_style));
-
-        let list = List::new(items).block(block);
-        Widget::render(list, area, buf);
+        let mut state = TableState::default().with_selected(self.selected);
+        StatefulWidget::render(table, area, buf, &mut state);
     }
 }
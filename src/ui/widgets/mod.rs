@@ -1,6 +1,93 @@
 // Widget components for the TUI
 
+pub(crate) mod chart;
+pub(crate) mod comparison;
+pub(crate) mod debug;
+pub(crate) mod diagnostics;
 pub(crate) mod footer;
 pub(crate) mod header;
+pub(crate) mod help;
+pub(crate) mod leaderboard;
+pub(crate) mod metric_detail;
 pub(crate) mod metrics;
+pub(crate) mod notifications;
+pub(crate) mod pause;
 pub(crate) mod progress;
+pub(crate) mod raw_lines;
+pub(crate) mod sample_browser;
+pub(crate) mod sample_detail;
+pub(crate) mod scatter;
+pub(crate) mod stats;
+pub(crate) mod status_bar;
+pub(crate) mod stderr;
+pub(crate) mod stored_run;
+pub(crate) mod summary;
+pub(crate) mod timeline;
+pub(crate) mod trends;
+
+use crate::ui::keymap::KeyBinding;
+
+/// Where a view is in a list of `len` entries, showing the `position`th
+/// (from 1), e.g. "line 12/40"
+///
+/// Views that follow new entries also say whether they do, or else which
+/// key goes back to following them.
+pub(crate) fn scroll_position_text(
+    noun: &str,
+    position: usize,
+    len: usize,
+    follow_key: Option<KeyBinding>,
+) -> String {
+    let mut text = format!("{} {}/{}", noun, position, len);
+    match follow_key {
+        Some(key) if position < len => text.push_str(&format!(", [{}] follow", key)),
+        Some(_) => text.push_str(", following"),
+        None => {}
+    }
+    text
+}
+
+/// Format a byte count with a binary unit, e.g. "12.3 MiB"
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Scale the last `width` of `values` into sparkline bars from 1 to 100,
+/// so the lowest value still shows
+pub(crate) fn sparkline_bars(values: &[f64], width: u16) -> Vec<u64> {
+    let values = &values[values.len().saturating_sub(width as usize)..];
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        });
+    let range = (max - min).max(f64::EPSILON);
+    values
+        .iter()
+        .map(|v| 1 + ((v - min) / range * 99.0).round() as u64)
+        .collect()
+}
+
+/// Format a per-second rate compactly, e.g. "0.42/s" or "3.4k/s"
+pub(crate) fn format_rate(per_sec: f64) -> String {
+    if per_sec >= 1_000_000.0 {
+        format!("{:.1}M/s", per_sec / 1_000_000.0)
+    } else if per_sec >= 1_000.0 {
+        format!("{:.1}k/s", per_sec / 1_000.0)
+    } else if per_sec >= 10.0 {
+        format!("{:.0}/s", per_sec)
+    } else {
+        format!("{:.2}/s", per_sec)
+    }
+}
@@ -0,0 +1,112 @@
+use crate::state::types::{Notification, NotificationLevel};
+use crate::ui::keymap::KeyBinding;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// Widest a toast gets on large terminals
+const MAX_TOAST_WIDTH: u16 = 56;
+
+/// Rows of one toast, with its border
+const TOAST_HEIGHT: u16 = 3;
+
+/// Recent notifications stacked in the top right corner over the view,
+/// newest at the bottom
+pub(crate) struct Toasts<'a> {
+    notifications: Vec<&'a Notification>,
+    theme: &'a Theme,
+}
+
+impl<'a> Toasts<'a> {
+    /// Create a stack of `notifications`, oldest first
+    pub(crate) fn new(notifications: impl Iterator<Item = &'a Notification>) -> Self {
+        Self {
+            notifications: notifications.collect(),
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl<'a> Widget for Toasts<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Keep the newest when there is no room for all of them
+        let fits = (area.height / TOAST_HEIGHT) as usize;
+        let skipped = self.notifications.len().saturating_sub(fits);
+        let mut y = area.y;
+        for notification in &self.notifications[skipped..] {
+            let (icon, style) = match notification.level {
+                NotificationLevel::Info => ("ℹ", self.theme.notice),
+                NotificationLevel::Warning => ("⚠", self.theme.warning),
+            };
+            let mut text = format!("{} {}", icon, notification.message);
+            if notification.count > 1 {
+                text.push_str(&format!(" (x{})", notification.count));
+            }
+
+            let width = (Line::from(text.as_str()).width() as u16 + 2)
+                .min(MAX_TOAST_WIDTH)
+                .min(area.width);
+            let toast = Rect::new(area.right() - width, y, width, TOAST_HEIGHT);
+            Clear.render(toast, buf);
+            Paragraph::new(text)
+                .style(style.add_modifier(Modifier::BOLD))
+                .block(Block::default().borders(Borders::ALL).border_style(style))
+                .render(toast, buf);
+            y += TOAST_HEIGHT;
+        }
+    }
+}
+
+/// Line above the view reporting a fatal problem until it is dismissed
+pub(crate) struct ErrorBanner<'a> {
+    message: &'a str,
+    dismiss_key: Option<KeyBinding>,
+    theme: &'a Theme,
+}
+
+impl<'a> ErrorBanner<'a> {
+    /// Create a banner reporting `message`
+    pub(crate) fn new(message: &'a str) -> Self {
+        Self {
+            message,
+            dismiss_key: None,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Name the key that dismisses the banner (builder pattern)
+    pub(crate) fn dismiss_key(mut self, key: Option<KeyBinding>) -> Self {
+        self.dismiss_key = key;
+        self
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl<'a> Widget for ErrorBanner<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut spans = vec![Span::raw(format!("✗ {}", self.message))];
+        if let Some(key) = self.dismiss_key {
+            spans.push(Span::raw(format!("  [{}] dismiss", key)));
+        }
+        Paragraph::new(Line::from(spans))
+            .style(
+                self.theme
+                    .error
+                    .add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            )
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::ui::keymap::KeyBinding;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+
+/// Width of the box in the middle of the view
+const WIDTH: u16 = 44;
+
+/// Dims the view and says in the middle of it that the run is paused, so
+/// the frozen numbers are not mistaken for a stalled evaluator
+pub(crate) struct PauseOverlay<'a> {
+    pending: usize,
+    resume_key: Option<KeyBinding>,
+    theme: &'a Theme,
+}
+
+impl<'a> PauseOverlay<'a> {
+    /// Create an overlay noting `pending` metric lines held back until resume
+    pub(crate) fn new(pending: usize) -> Self {
+        Self {
+            pending,
+            resume_key: None,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Name the key that resumes the run (builder pattern)
+    pub(crate) fn resume_key(mut self, key: Option<KeyBinding>) -> Self {
+        self.resume_key = key;
+        self
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl<'a> Widget for PauseOverlay<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, Style::default().add_modifier(Modifier::DIM));
+
+        let mut lines = vec![
+            Line::from("Elapsed time and ETA are frozen"),
+            Line::from(format!("{} metric lines held back", self.pending)),
+        ];
+        if let Some(key) = self.resume_key {
+            lines.push(Line::styled(format!("[{}] resume", key), self.theme.accent));
+        }
+
+        let width = area.width.min(WIDTH);
+        let height = area.height.min(lines.len() as u16 + 2);
+        let [overlay] = Layout::horizontal([Constraint::Length(width)])
+            .flex(layout::Flex::Center)
+            .areas(area);
+        let [overlay] = Layout::vertical([Constraint::Length(height)])
+            .flex(layout::Flex::Center)
+            .areas(overlay);
+        Clear.render(overlay, buf);
+        Paragraph::new(lines)
+            .centered()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Thick)
+                    .border_style(self.theme.warning)
+                    .title(" ⏸ PAUSED ")
+                    .title_alignment(Alignment::Center)
+                    .title_style(self.theme.warning.add_modifier(Modifier::BOLD)),
+            )
+            .render(overlay, buf);
+    }
+}
@@ -1,117 +1,288 @@
-use crate::state::{types::SampleStatus, AppState};
+use super::sample_browser::SampleList;
+use super::stats::{GroupedStatsTable, MetricStatsTable};
+use super::trends::TrendsRow;
+use super::{format_bytes, format_rate};
+use crate::state::{
+    app::AppData,
+    types::{SampleResult, SampleStatus, StageProgress},
+};
+use crate::ui::theme::Theme;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Gauge, ListItem, Paragraph},
 };
 use std::fmt::Write as _;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Frames of the spinner shown while waiting for the handshake
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// How long each frame of the spinner is shown
+const SPINNER_FRAME: Duration = Duration::from_millis(100);
 
 /// Progress display widget showing real-time evaluation progress
 pub(crate) struct ProgressView<'a> {
-    state: &'a AppState,
+    state: &'a AppData,
+    compact: bool,
+    theme: &'a Theme,
 }
 
 impl<'a> ProgressView<'a> {
     /// Create a new progress view
-    pub(crate) fn new(state: &'a AppState) -> Self {
-        Self { state }
+    pub(crate) fn new(state: &'a AppData) -> Self {
+        Self {
+            state,
+            compact: false,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Condense progress and summary to a line each and leave out the
+    /// trends and sample list, for small terminals (builder pattern)
+    pub(crate) fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
     }
 
     /// Format duration as MM:SS
-    fn format_duration(duration: std::time::Duration) -> String {
+    pub(crate) fn format_duration(duration: Duration) -> String {
         let total_seconds = duration.as_secs();
         let minutes = total_seconds / 60;
         let seconds = total_seconds % 60;
         format!("{}:{:02}", minutes, seconds)
     }
+}
 
-    /// Format a sample result for display
-    fn format_sample_result(&self, sample: &crate::state::types::SampleResult) -> String {
-        let status_icon = match &sample.status {
-            SampleStatus::Processing => "⟳",
-            SampleStatus::Completed => "✓",
-            SampleStatus::Failed(_) => "✗",
+impl<'a> ProgressView<'a> {
+    /// Areas of the sections when drawn in `area`, from the top
+    fn sections(&self, area: Rect) -> Rc<[Rect]> {
+        let trends_height = TrendsRow::height(self.state.trends());
+        let stats_height = match self.state.group_by() {
+            Some(key) => GroupedStatsTable::height(key, self.state.grouped_stats()),
+            None => MetricStatsTable::height(self.state.metric_stats()),
         };
 
-        let mut line = format!("{} {}", status_icon, sample.sample_id);
+        let progress_height = if self.state.batch_progress().is_some() {
+            4
+        } else {
+            3
+        };
 
-        // Add key metrics (limit to 2-3 most important ones)
-        if !sample.metrics.is_empty() {
-            let mut metrics_str = String::new();
-            for (i, (name, value)) in sample.metrics.iter().take(3).enumerate() {
-                if i > 0 {
-                    metrics_str.push_str(", ");
-                }
-                write!(&mut metrics_str, "{}={:.2}", name, value).ok();
-            }
-            line.push_str(&format!(": {}", metrics_str));
+        if self.compact {
+            return Layout::vertical([
+                Constraint::Length(1), // Progress line
+                Constraint::Length(0), // Current sample, in the summary line
+                Constraint::Length(0), // No sparklines
+                Constraint::Length(0), // No sample list
+                Constraint::Min(0),    // Metric statistics
+                Constraint::Length(1), // Summary line
+            ])
+            .split(area);
         }
 
-        // Add error message for failed samples
-        if let SampleStatus::Failed(error) = &sample.status {
-            line.push_str(&format!(" ({})", error));
-        }
+        Layout::default()
+            .direction(Direction::Vertical)
+            .margin(0)
+            .constraints([
+                Constraint::Length(progress_height), // Progress bar, and the batch's
+                Constraint::Length(3),               // Current sample
+                Constraint::Length(trends_height),   // Key metric sparklines
+                Constraint::Min(5),                  // Sample list
+                Constraint::Length(stats_height),    // Metric statistics
+                Constraint::Length(3),               // Summary
+            ])
+            .split(area)
+    }
 
-        line
+    /// Part of `area` the sample list is drawn in
+    pub(crate) fn sample_list_area(&self, area: Rect) -> Rect {
+        self.sections(area)[3]
     }
 }
 
 impl<'a> Widget for ProgressView<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        let stats = self.state.metric_stats();
+        let grouped = self.state.grouped_stats();
+
+        // Too short for anything but the progress itself
+        if self.compact && area.height < 2 {
+            self.render_progress_line(area, buf);
+            return;
+        }
+
         // Split the area into sections
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(0)
-            .constraints([
-                Constraint::Length(3), // Progress bar
-                Constraint::Length(3), // Current sample
-                Constraint::Min(5),    // Recent samples
-                Constraint::Length(3), // Summary
-            ])
-            .split(area);
+        let chunks = self.sections(area);
 
-        // Render progress bar section
-        self.render_progress_bar(chunks[0], buf);
+        if self.compact {
+            self.render_progress_line(chunks[0], buf);
+            self.render_summary_line(chunks[5], buf);
+        } else {
+            // Render progress bar section
+            self.render_progress_bar(chunks[0], buf);
 
-        // Render current sample section
-        self.render_current_sample(chunks[1], buf);
+            // Render current sample section
+            self.render_current_sample(chunks[1], buf);
 
-        // Render recent samples section
-        self.render_recent_samples(chunks[2], buf);
+            // Render summary section
+            self.render_summary(chunks[5], buf);
+        }
 
-        // Render summary section
-        self.render_summary(chunks[3], buf);
+        // Render the recent trajectory of the key metrics
+        TrendsRow::new(self.state.trends())
+            .theme(self.theme)
+            .render(chunks[2], buf);
+
+        // Render the sample list, following new samples unless scrolled up
+        SampleList::new(self.state)
+            .theme(self.theme)
+            .render(chunks[3], buf);
+
+        // Render per-metric statistics, grouped by an attribute if selected
+        match self.state.group_by() {
+            Some(key) => GroupedStatsTable::new(key, grouped)
+                .theme(self.theme)
+                .render(chunks[4], buf),
+            None if !stats.is_empty() => MetricStatsTable::new(stats)
+                .rules(self.state.rule_set())
+                .theme(self.theme)
+                .render(chunks[4], buf),
+            None => {}
+        }
     }
 }
 
 impl<'a> ProgressView<'a> {
-    /// Render the progress bar with completion percentage and ETA
+    /// Render the progress bar with completion percentage and ETA, above
+    /// the progress of the current batch if the run is batched
     fn render_progress_bar(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.progress_text());
+        let inner = block.inner(area);
+        Widget::render(block, area, buf);
+
+        let batch = self.state.batch_progress();
+        let [run_area, batch_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(u16::from(batch.is_some())),
+        ])
+        .areas(inner);
+        let gauge = Gauge::default()
+            .gauge_style(self.theme.accent)
+            .ratio(self.progress_ratio());
+        Widget::render(gauge, run_area, buf);
+
+        if let Some(batch) = batch {
+            let gauge = Gauge::default()
+                .label(batch_text(&batch))
+                .gauge_style(self.theme.notice)
+                .ratio(stage_ratio(&batch));
+            Widget::render(gauge, batch_area, buf);
+        }
+    }
+
+    /// Progress bar without a border, labelled with the counts and ETA
+    fn render_progress_line(&self, area: Rect, buf: &mut Buffer) {
+        let mut label = self.progress_text();
+        if let Some(batch) = self.state.batch_progress() {
+            write!(&mut label, " - {}", batch_text(&batch)).ok();
+        }
+        let gauge = Gauge::default()
+            .label(label)
+            .gauge_style(self.theme.accent)
+            .ratio(self.progress_ratio());
+
+        Widget::render(gauge, area, buf);
+    }
+
+    /// Completed fraction of the run, 0 when the total is unknown
+    fn progress_ratio(&self) -> f64 {
+        let (_, _, percentage) = self.state.progress();
+        if percentage > 0.0 {
+            percentage / 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Sample counts, ETA and pause state of the run, or how long the
+    /// evaluator has been starting up while it has not sent its handshake
+    fn progress_text(&self) -> String {
+        if let Some(waited) = self.state.handshake_wait() {
+            let remaining = self.state.handshake_timeout().saturating_sub(waited);
+            let frame = (waited.as_millis() / SPINNER_FRAME.as_millis()) as usize % SPINNER.len();
+            return format!(
+                "{} Waiting for handshake - {} elapsed, timeout in {}",
+                SPINNER[frame],
+                Self::format_duration(waited),
+                // Round up, so the timeout is not shown as 0:00 before it hits
+                Self::format_duration(Duration::from_secs(remaining.as_secs_f64().ceil() as u64))
+            );
+        }
+
         let (completed, total, percentage) = self.state.progress();
 
+        // The phase the evaluator is in names the progress
+        let heading = match self.state.phase_progress() {
+            Some(phase) => format!(
+                "Progress ({}, phase {}/{}: {}/{})",
+                phase.name.as_deref().unwrap_or_default(),
+                phase.number,
+                phase.stages,
+                phase.completed,
+                phase.samples
+            ),
+            None => "Progress".to_string(),
+        };
         let title = match total {
-            Some(t) => format!("Progress: {}/{} samples ({:.1}%)", completed, t, percentage),
-            None => format!("Progress: {} samples", completed),
+            Some(t) => format!(
+                "{}: {}/{} samples ({:.1}%)",
+                heading, completed, t, percentage
+            ),
+            None => format!("{}: {} samples", heading, completed),
         };
 
         // Add ETA if available
-        let title_with_eta = if let Some(eta) = self.state.calculate_eta() {
-            format!("{} - ETA: {}", title, Self::format_duration(eta))
+        let mut title_with_eta = if let Some(eta) = self.state.calculate_eta() {
+            format!(
+                "{} - ETA {}–{}{}",
+                title,
+                Self::format_duration(eta.low),
+                Self::format_duration(eta.high),
+                self.frozen_mark()
+            )
         } else {
             title
         };
 
-        let progress_ratio = if percentage > 0.0 {
-            percentage / 100.0
-        } else {
-            0.0
-        };
+        // Metrics arriving while paused are held back until resume
+        if self.state.is_paused() {
+            write!(
+                &mut title_with_eta,
+                " - PAUSED ({} pending)",
+                self.state.pending_metrics()
+            )
+            .ok();
+        }
 
-        let gauge = Gauge::default()
-            .block(Block::default().borders(Borders::ALL).title(title_with_eta))
-            .gauge_style(Style::default().fg(Color::Cyan))
-            .ratio(progress_ratio);
+        title_with_eta
+    }
 
-        Widget::render(gauge, area, buf);
+    /// Mark for clocks that stand still while the run is paused
+    fn frozen_mark(&self) -> &'static str {
+        if self.state.is_paused() && !self.state.is_terminal() {
+            " ⏸"
+        } else {
+            ""
+        }
     }
 
     /// Render current sample being processed
@@ -127,48 +298,49 @@ impl<'a> ProgressView<'a> {
                     .borders(Borders::ALL)
                     .title("Current Sample"),
             )
-            .style(Style::default().fg(Color::Yellow));
+            .style(self.theme.warning);
 
         Widget::render(paragraph, area, buf);
     }
 
-    /// Render recent completed samples
-    fn render_recent_samples(&self, area: Rect, buf: &mut Buffer) {
-        let recent_samples = self.state.recent_samples();
-
-        let mut items =
-            vec![ListItem::new("Recent Samples:")
-                .style(Style::default().add_modifier(Modifier::BOLD))];
-
-        if recent_samples.is_empty() {
-            items.push(
-                ListItem::new("  No samples completed yet...")
-                    .style(Style::default().fg(Color::DarkGray)),
-            );
-        } else {
-            // Show recent samples in reverse order (most recent first)
-            for sample in recent_samples.iter().rev() {
-                let line = self.format_sample_result(sample);
-                let style = match &sample.status {
-                    SampleStatus::Completed => Style::default().fg(Color::Green),
-                    SampleStatus::Failed(_) => Style::default().fg(Color::Red),
-                    SampleStatus::Processing => Style::default().fg(Color::Yellow),
-                };
-                items.push(ListItem::new(format!("  {}", line)).style(style));
-            }
+    /// Current sample, failures, elapsed time and score on one line
+    fn render_summary_line(&self, area: Rect, buf: &mut Buffer) {
+        let (failed_count, total_completed, _) = self.state.summary_stats();
+        let current = self.state.current_sample().unwrap_or("–");
+        let mut spans = vec![
+            Span::styled(format!("Current: {}", current), self.theme.warning),
+            Span::raw(format!(
+                " | {}/{} failed | {}{}",
+                failed_count,
+                total_completed,
+                Self::format_duration(self.state.elapsed_time()),
+                self.frozen_mark()
+            )),
+        ];
+        if let (Some(score), Some(mean)) = (self.state.score(), self.state.mean_score()) {
+            spans.push(Span::raw(" | "));
+            spans.push(Span::styled(
+                format!("{}: {:.3}", score.name(), mean),
+                self.theme.heading,
+            ));
+        }
+        if !self.state.tripped_rules().is_empty() {
+            spans.push(Span::styled(" | rules tripped", self.theme.error));
         }
 
-        let list = List::new(items).block(Block::default().borders(Borders::ALL));
-
-        Widget::render(list, area, buf);
+        Widget::render(Paragraph::new(Line::from(spans)), area, buf);
     }
 
     /// Render summary statistics
     fn render_summary(&self, area: Rect, buf: &mut Buffer) {
         let (failed_count, total_completed, success_rate) = self.state.summary_stats();
-        let elapsed = Self::format_duration(self.state.elapsed_time());
+        let elapsed = format!(
+            "{}{}",
+            Self::format_duration(self.state.elapsed_time()),
+            self.frozen_mark()
+        );
 
-        let summary_text = if total_completed > 0 {
+        let mut summary_text = if total_completed > 0 {
             format!(
                 "Summary: {}/{} failed ({:.1}% success rate) | Elapsed: {}",
                 failed_count, total_completed, success_rate, elapsed
@@ -177,16 +349,163 @@ impl<'a> ProgressView<'a> {
             format!("Summary: No samples completed | Elapsed: {}", elapsed)
         };
 
-        let summary_style = if failed_count > 0 {
-            Style::default().fg(Color::Yellow)
+        // A recent rate well below the average points at throttling
+        if let Some(average) = self.state.average_throughput() {
+            let current = self.state.throughput().unwrap_or(average);
+            write!(
+                &mut summary_text,
+                " | Rate: {} samples (avg {})",
+                format_rate(current.samples_per_sec),
+                format_rate(average.samples_per_sec)
+            )
+            .ok();
+            if let (Some(tokens), Some(average_tokens)) =
+                (current.tokens_per_sec, average.tokens_per_sec)
+            {
+                write!(
+                    &mut summary_text,
+                    ", {} tokens (avg {})",
+                    format_rate(tokens),
+                    format_rate(average_tokens)
+                )
+                .ok();
+            }
+        }
+
+        let restarts = self.state.restarts().len();
+        if restarts > 0 {
+            write!(&mut summary_text, " | Restarts: {}", restarts).ok();
+        }
+
+        let peak_memory = self.state.peak_memory_bytes();
+        if peak_memory > 0 {
+            write!(
+                &mut summary_text,
+                " | Peak RSS: {}",
+                format_bytes(peak_memory)
+            )
+            .ok();
+        }
+
+        let tripped_rules = self.state.tripped_rules();
+        if !tripped_rules.is_empty() {
+            let rules: Vec<String> = tripped_rules
+                .iter()
+                .map(|(rule, violations)| format!("{} (x{})", rule, violations))
+                .collect();
+            write!(&mut summary_text, " | Rules tripped: {}", rules.join(", ")).ok();
+        }
+
+        let summary_style = if !tripped_rules.is_empty() {
+            self.theme.error
+        } else if failed_count > 0 {
+            self.theme.warning
         } else {
-            Style::default().fg(Color::Green)
+            self.theme.success
         };
 
+        // The composite score is the run's headline number
+        let title = match (self.state.score(), self.state.mean_score()) {
+            (Some(score), Some(mean)) => Line::from(vec![
+                Span::raw("Summary | "),
+                Span::styled(format!("{}: {:.3}", score.name(), mean), self.theme.heading),
+            ]),
+            _ => Line::from("Summary"),
+        };
         let paragraph = Paragraph::new(summary_text)
-            .block(Block::default().borders(Borders::ALL).title("Summary"))
+            .block(Block::default().borders(Borders::ALL).title(title))
             .style(summary_style);
 
         Widget::render(paragraph, area, buf);
     }
 }
+
+/// Which batch a batched run is in, and how far through it
+fn batch_text(batch: &StageProgress) -> String {
+    format!(
+        "Batch {}/{}: {}/{} samples",
+        batch.number, batch.stages, batch.completed, batch.samples
+    )
+}
+
+/// Completed fraction of a batch or phase
+fn stage_ratio(stage: &StageProgress) -> f64 {
+    if stage.samples > 0 {
+        (stage.completed as f64 / stage.samples as f64).min(1.0)
+    } else {
+        0.0
+    }
+}
+
+/// List entry for one sample: its status, key metrics and any error
+pub(super) fn sample_list_item(sample: &SampleResult, theme: &Theme) -> ListItem<'static> {
+    let mut line = format!("{} {}", status_icon(sample), sample.sample_id);
+    if sample.runs > 1 {
+        write!(&mut line, " (x{} runs)", sample.runs).ok();
+    }
+    if let Some(duration) = sample.duration {
+        write!(&mut line, " [{}]", format_sample_duration(duration)).ok();
+    }
+
+    // Add key metrics (limit to 2-3 most important ones)
+    if !sample.metrics.is_empty() {
+        let mut metrics_str = String::new();
+        for (i, (name, value)) in sample.metrics.iter().take(3).enumerate() {
+            if i > 0 {
+                metrics_str.push_str(", ");
+            }
+            write!(&mut metrics_str, "{}={:.2}", name, value).ok();
+        }
+        line.push_str(&format!(": {}", metrics_str));
+    }
+
+    line.push_str(&sample_notes(sample));
+    ListItem::new(format!("  {}", line)).style(sample_style(sample, theme))
+}
+
+/// Icon for the status of `sample`
+pub(super) fn status_icon(sample: &SampleResult) -> &'static str {
+    match &sample.status {
+        SampleStatus::Processing => "⟳",
+        SampleStatus::Completed => "✓",
+        SampleStatus::Failed(_) => "✗",
+    }
+}
+
+/// How long a sample took, in milliseconds below a second
+pub(super) fn format_sample_duration(duration: std::time::Duration) -> String {
+    if duration < std::time::Duration::from_secs(1) {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+/// Bookmark and note of a sample, the error of a failed one and the
+/// metrics anomalous in it
+pub(super) fn sample_notes(sample: &SampleResult) -> String {
+    let mut notes = String::new();
+    if sample.annotation.bookmarked {
+        notes.push_str(" ★");
+    }
+    if let Some(note) = &sample.annotation.note {
+        write!(&mut notes, " ✎ {}", note).ok();
+    }
+    if let SampleStatus::Failed(error) = &sample.status {
+        write!(&mut notes, " ({})", error).ok();
+    }
+    if !sample.anomalies.is_empty() {
+        write!(&mut notes, " ⚠ anomalous {}", sample.anomalies.join(", ")).ok();
+    }
+    notes
+}
+
+/// Style of a sample by its status, marking anomalies
+pub(super) fn sample_style(sample: &SampleResult, theme: &Theme) -> Style {
+    match &sample.status {
+        SampleStatus::Completed if !sample.anomalies.is_empty() => theme.notice,
+        SampleStatus::Completed => theme.success,
+        SampleStatus::Failed(_) => theme.error,
+        SampleStatus::Processing => theme.warning,
+    }
+}
@@ -0,0 +1,144 @@
+use super::scroll_position_text;
+use crate::state::types::RawLine;
+use crate::ui::json;
+use crate::ui::keymap::KeyBinding;
+use crate::ui::theme::Theme;
+use chrono::Local;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+use std::collections::VecDeque;
+
+/// Share of the pane given to the inspected line below the list
+const INSPECTOR_PERCENTAGE: u16 = 60;
+
+/// Lines the evaluator wrote to stdout, with the selected one inspected below
+pub(crate) struct RawLinesPane<'a> {
+    lines: &'a VecDeque<RawLine>,
+    scrolled_back: usize,
+    fold_depth: Option<usize>,
+    follow_key: Option<KeyBinding>,
+    theme: &'a Theme,
+}
+
+impl<'a> RawLinesPane<'a> {
+    /// Create a pane of `lines` selecting the one `scrolled_back` lines
+    /// before the newest
+    pub(crate) fn new(lines: &'a VecDeque<RawLine>, scrolled_back: usize) -> Self {
+        Self {
+            lines,
+            scrolled_back,
+            fold_depth: None,
+            follow_key: None,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Fold JSON objects and arrays nested this deep in the inspected line
+    /// (builder pattern)
+    pub(crate) fn fold_depth(mut self, fold_depth: Option<usize>) -> Self {
+        self.fold_depth = fold_depth;
+        self
+    }
+
+    /// Name the key that goes back to following new lines (builder pattern)
+    pub(crate) fn follow_key(mut self, key: Option<KeyBinding>) -> Self {
+        self.follow_key = key;
+        self
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// List entry for one line, marked by whether it was understood
+    fn item(&self, line: &RawLine) -> ListItem<'static> {
+        let (marker, style) = match line.error {
+            Some(_) => ("✗", self.theme.error),
+            None => ("✓", self.theme.success),
+        };
+        ListItem::new(Line::from(vec![
+            Span::styled(
+                line.received_at
+                    .with_timezone(&Local)
+                    .format("%H:%M:%S%.3f ")
+                    .to_string(),
+                self.theme.muted,
+            ),
+            Span::styled(format!("{} ", marker), style),
+            Span::raw(line.text.clone()),
+        ]))
+    }
+
+    /// The whole of `line`, pretty-printed and colored if it is JSON, and
+    /// why it was rejected
+    fn inspect(&self, line: &RawLine) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        if let Some(error) = &line.error {
+            lines.push(Line::styled(
+                error.clone(),
+                self.theme.error.add_modifier(Modifier::BOLD),
+            ));
+        }
+        match serde_json::from_str::<serde_json::Value>(&line.text) {
+            Ok(value) => lines.extend(json::highlight(&value, self.fold_depth, self.theme)),
+            Err(_) => lines.push(Line::from(line.text.clone())),
+        }
+        lines
+    }
+}
+
+impl<'a> Widget for RawLinesPane<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let rejected = self
+            .lines
+            .iter()
+            .filter(|line| line.error.is_some())
+            .count();
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(self.theme.border);
+
+        let Some(selected) = self.lines.len().checked_sub(self.scrolled_back + 1) else {
+            Paragraph::new("No stdout output")
+                .style(self.theme.muted)
+                .block(block.title("Stdout"))
+                .render(area, buf);
+            return;
+        };
+        let block = block.title(format!(
+            "Stdout ({}, {} rejected)",
+            scroll_position_text("line", selected + 1, self.lines.len(), self.follow_key),
+            rejected
+        ));
+
+        let [list_area, inspector_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Percentage(INSPECTOR_PERCENTAGE),
+        ])
+        .areas(area);
+
+        let list = List::new(self.lines.iter().map(|line| self.item(line)))
+            .highlight_style(self.theme.highlight)
+            .block(block);
+        let mut state = ListState::default().with_selected(Some(selected));
+        StatefulWidget::render(list, list_area, buf, &mut state);
+
+        Paragraph::new(self.inspect(&self.lines[selected]))
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(match self.fold_depth {
+                        Some(depth) => {
+                            format!("Selected line (folded from level {}, [z] fold)", depth)
+                        }
+                        None => "Selected line ([z] fold)".to_string(),
+                    }),
+            )
+            .render(inspector_area, buf);
+    }
+}
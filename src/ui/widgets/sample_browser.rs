@@ -0,0 +1,309 @@
+use super::progress::{format_sample_duration, sample_notes, sample_style, status_icon};
+use super::sample_detail::SampleDetail;
+use crate::state::app::{AppData, SAMPLE_DURATION_METRIC};
+use crate::state::rules::RuleSet;
+use crate::state::sample_store::StatusFilter;
+use crate::state::types::SampleResult;
+use crate::ui::mouse::Target;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+};
+
+/// Widest a metric column gets
+const MAX_METRIC_WIDTH: u16 = 16;
+
+/// Table of the samples of the run, with a column per metric and a cursor
+/// on the selected sample
+///
+/// Unfiltered, the table pages back through every sample that can still be
+/// read, spilled ones included; filtered or sorted, it shows the samples in
+/// memory that pass. Samples are oldest first unless sorted by a metric.
+/// The table scrolls to keep the cursor in view, so it shows the newest
+/// samples while it follows them.
+pub(crate) struct SampleList<'a> {
+    state: &'a AppData,
+    theme: &'a Theme,
+}
+
+impl<'a> SampleList<'a> {
+    /// Create a table of the samples of `state`
+    pub(crate) fn new(state: &'a AppData) -> Self {
+        Self {
+            state,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Rows of the table when drawn in `area`, and the samples on them
+    pub(crate) fn target(&self, area: Rect) -> Option<(Rect, Target)> {
+        let rows = Self::rows_area(area);
+        let first = self.first_visible(rows.height)?;
+        let samples = self
+            .state
+            .listed_page(first, usize::from(rows.height))
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect();
+        Some((rows, Target::Samples(samples)))
+    }
+
+    /// Part of `area` the samples are drawn in, below the header
+    fn rows_area(area: Rect) -> Rect {
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        Rect {
+            y: inner.y.saturating_add(1).min(inner.bottom()),
+            height: inner.height.saturating_sub(1),
+            ..inner
+        }
+    }
+
+    /// Position among the samples shown of the top row when `height` rows
+    /// are visible, scrolled just far enough to show the selection
+    fn first_visible(&self, height: u16) -> Option<usize> {
+        let selected = self.selected_position()?;
+        Some(selected.saturating_sub(usize::from(height.max(1)) - 1))
+    }
+
+    /// Position of the selected sample among the samples shown
+    fn selected_position(&self) -> Option<usize> {
+        let selected = self.state.selected_sample()?;
+        if self.state.lists_sample_history() {
+            return Some(selected - self.state.sample_store().available().start);
+        }
+        self.state
+            .listed_samples()
+            .into_iter()
+            .position(|(index, _)| index == selected)
+    }
+
+    /// The note being typed, filter and sort order below the table, with
+    /// a cursor while the note or filter is typed
+    fn query_line(&self) -> Option<Line<'static>> {
+        let filter = self.state.sample_filter();
+        let searching = self.state.is_searching_samples();
+        let mut spans = Vec::new();
+        if let Some(note) = self.state.sample_note_draft() {
+            spans.push(Span::styled(format!(" ✎ {}▏", note), self.theme.notice));
+            spans.push(Span::styled(
+                " [Enter] save [Esc] cancel ",
+                self.theme.muted,
+            ));
+        }
+        if searching || !filter.query().is_empty() {
+            spans.push(Span::styled(
+                format!(" /{}", filter.query()),
+                self.theme.accent,
+            ));
+            if searching {
+                spans.push(Span::styled("▏", self.theme.accent));
+            }
+            spans.push(Span::raw(" "));
+        }
+        if filter.status() != StatusFilter::All {
+            spans.push(Span::styled(
+                format!(" {} only ", filter.status().label()),
+                self.theme.warning,
+            ));
+        }
+        if let Some(sort) = self.state.sample_sort() {
+            spans.push(Span::styled(
+                format!(
+                    " sorted by {} {} ",
+                    column_title(&sort.metric),
+                    if sort.descending { "▼" } else { "▲" }
+                ),
+                self.theme.accent,
+            ));
+        }
+        (!spans.is_empty()).then(|| Line::from(spans))
+    }
+
+    /// Header of the table, marking the column sorted by
+    fn header(&self, metrics: &[&str]) -> Row<'static> {
+        let sort = self.state.sample_sort();
+        let mut cells = vec![String::new(), "Sample".to_string()];
+        cells.extend(metrics.iter().map(|metric| {
+            let mut title = column_title(metric).to_string();
+            match sort {
+                Some(sort) if sort.metric == *metric => {
+                    title.push_str(if sort.descending { " ▼" } else { " ▲" })
+                }
+                _ => {}
+            }
+            title
+        }));
+        cells.push("Notes".to_string());
+        Row::new(cells).style(self.theme.heading)
+    }
+
+    /// Row of the table for `sample`, coloring values by the `rules`
+    /// covering them
+    fn row(&self, sample: &SampleResult, metrics: &[&str], rules: &RuleSet) -> Row<'static> {
+        let mut id = sample.sample_id.clone();
+        if sample.runs > 1 {
+            id.push_str(&format!(" (x{})", sample.runs));
+        }
+        let mut cells = vec![Cell::from(status_icon(sample)), Cell::from(id)];
+        cells.extend(metrics.iter().map(|metric| {
+            let (text, value) = if *metric == SAMPLE_DURATION_METRIC {
+                (
+                    sample.duration.map(format_sample_duration),
+                    sample.duration.map(|d| d.as_secs_f64() * 1000.0),
+                )
+            } else {
+                let value = sample.value(metric);
+                (value.map(|value| format!("{:.3}", value)), value)
+            };
+            let cell = Cell::from(text.unwrap_or_default());
+            match value.and_then(|value| rules.judge_sample(metric, value)) {
+                Some(verdict) => cell.style(self.theme.verdict(verdict)),
+                None => cell,
+            }
+        }));
+        cells.push(Cell::from(sample_notes(sample).trim_start().to_string()));
+        Row::new(cells).style(sample_style(sample, self.theme))
+    }
+}
+
+/// Title of the column of `metric`
+fn column_title(metric: &str) -> &str {
+    if metric == SAMPLE_DURATION_METRIC {
+        "duration"
+    } else {
+        metric
+    }
+}
+
+impl<'a> Widget for SampleList<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let samples = self.state.sample_store();
+        let mut block = Block::default().borders(Borders::ALL);
+        if let Some(query) = self.query_line() {
+            block = block.title_bottom(query);
+        }
+        if samples.is_empty() {
+            Paragraph::new("No samples completed yet...")
+                .style(self.theme.muted)
+                .block(block.title("Samples"))
+                .render(area, buf);
+            return;
+        }
+        let Some(selected) = self.selected_position() else {
+            Paragraph::new("No samples match the filter")
+                .style(self.theme.muted)
+                .block(block.title(format!("Samples (0/{})", samples.len())))
+                .render(area, buf);
+            return;
+        };
+
+        let in_memory = samples.in_memory();
+        let available = samples.available();
+        let mut title = if self.state.lists_sample_history() {
+            format!(
+                "Samples ({}/{}",
+                available.start + selected + 1,
+                samples.len()
+            )
+        } else {
+            format!(
+                "Samples ({}/{} matching of {}",
+                selected + 1,
+                self.state.listed_len(),
+                samples.len()
+            )
+        };
+        if !self.state.lists_sample_history() && in_memory.start > 0 {
+            title.push_str(&format!(", {} older not searched", in_memory.start));
+        } else if available.start > 0 {
+            title.push_str(&format!(", {} oldest not kept", available.start));
+        }
+        title.push(')');
+        let hint = if self.state.is_following_samples() {
+            Span::styled(" following ", self.theme.success)
+        } else {
+            Span::styled(" [End] follow ", self.theme.warning)
+        };
+
+        let metrics = samples.metric_names();
+        let mut widths = vec![Constraint::Length(1), Constraint::Min(8)];
+        widths.extend(metrics.iter().map(|metric| {
+            let title = column_title(metric).len() as u16 + 2;
+            Constraint::Length(title.clamp(8, MAX_METRIC_WIDTH))
+        }));
+        widths.push(Constraint::Fill(1));
+
+        // Only the visible rows are read, as they may come from the spill
+        let rows = Self::rows_area(area);
+        let first = self.first_visible(rows.height).unwrap_or(0);
+        let page = self.state.listed_page(first, usize::from(rows.height));
+        let rules = self.state.rule_set();
+        let table = Table::new(
+            page.iter()
+                .map(|(_, sample)| self.row(sample, &metrics, &rules)),
+            widths,
+        )
+        .header(self.header(&metrics))
+        .highlight_style(self.theme.highlight)
+        .block(block.title(title).title(Line::from(hint).right_aligned()));
+        let mut table_state = TableState::default().with_selected(Some(selected - first));
+        StatefulWidget::render(table, area, buf, &mut table_state);
+    }
+}
+
+/// The sample list beside the detail of the selected sample
+pub(crate) struct SampleBrowser<'a> {
+    state: &'a AppData,
+    theme: &'a Theme,
+}
+
+impl<'a> SampleBrowser<'a> {
+    /// Create a browser of the samples of `state`
+    pub(crate) fn new(state: &'a AppData) -> Self {
+        Self {
+            state,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl<'a> SampleBrowser<'a> {
+    /// Areas of the sample list and the detail when drawn in `area`
+    fn areas(area: Rect) -> [Rect; 2] {
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(area)
+    }
+
+    /// Part of `area` the sample list is drawn in
+    pub(crate) fn list_area(area: Rect) -> Rect {
+        Self::areas(area)[0]
+    }
+}
+
+impl<'a> Widget for SampleBrowser<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [list_area, detail_area] = Self::areas(area);
+        SampleList::new(self.state)
+            .theme(self.theme)
+            .render(list_area, buf);
+
+        if let Some(sample) = self.state.read_selected_sample() {
+            SampleDetail::new(&sample)
+                .closable(false)
+                .theme(self.theme)
+                .render(detail_area, buf);
+        }
+    }
+}
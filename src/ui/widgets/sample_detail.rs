@@ -0,0 +1,105 @@
+use crate::state::types::{SampleResult, SampleStatus};
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+/// Everything known about one sample, including the text it was evaluated on
+pub(crate) struct SampleDetail<'a> {
+    sample: &'a SampleResult,
+    closable: bool,
+    theme: &'a Theme,
+}
+
+impl<'a> SampleDetail<'a> {
+    /// Create a detail view of `sample`
+    pub(crate) fn new(sample: &'a SampleResult) -> Self {
+        Self {
+            sample,
+            closable: true,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Set whether the title tells how to close the view (builder pattern)
+    pub(crate) fn closable(mut self, closable: bool) -> Self {
+        self.closable = closable;
+        self
+    }
+
+    /// Status line of the sample
+    fn status_line(&self) -> Line<'static> {
+        let (text, style) = match &self.sample.status {
+            SampleStatus::Processing => ("processing".to_string(), self.theme.warning),
+            SampleStatus::Completed => ("completed".to_string(), self.theme.success),
+            SampleStatus::Failed(error) => (format!("failed: {}", error), self.theme.error),
+        };
+        let mut spans = vec![Span::styled(text, style)];
+        if self.sample.runs > 1 {
+            spans.push(Span::raw(format!(
+                " | averaged over {} runs",
+                self.sample.runs
+            )));
+        }
+        if let Some(duration) = self.sample.duration {
+            spans.push(Span::raw(format!(" | took {:.3}s", duration.as_secs_f64())));
+        }
+        Line::from(spans)
+    }
+}
+
+impl<'a> Widget for SampleDetail<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let heading = self.theme.heading;
+        let mut lines = vec![self.status_line(), Line::default()];
+        if let Some(note) = &self.sample.annotation.note {
+            lines.push(Line::styled("Note", heading));
+            lines.extend(
+                note.lines()
+                    .map(|line| Line::styled(format!("  {}", line), self.theme.notice)),
+            );
+            lines.push(Line::default());
+        }
+
+        lines.push(Line::styled("Metrics", heading));
+        for (name, value) in &self.sample.metrics {
+            let mut line = format!("  {} = {:.4}", name, value);
+            if self.sample.anomalies.contains(name) {
+                line.push_str(" ⚠ anomalous");
+            }
+            lines.push(Line::from(line));
+        }
+
+        if self.sample.texts.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::styled(
+                "No text captured for this sample",
+                self.theme.muted,
+            ));
+        }
+        for (attribute, text) in &self.sample.texts {
+            lines.push(Line::default());
+            lines.push(Line::styled(attribute.clone(), heading));
+            lines.extend(text.lines().map(|line| Line::from(line.to_string())));
+        }
+
+        let mut title = format!("Sample {}", self.sample.sample_id);
+        if self.sample.annotation.bookmarked {
+            title.push_str(" ★");
+        }
+        if self.closable {
+            title.push_str(" ([Esc] back)");
+        }
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,152 @@
+use super::chart::{bounds, labels};
+use crate::state::app::AppData;
+use crate::state::scatter::ScatterPoint;
+use crate::state::types::Tab;
+use crate::ui::keymap::KeyBinding;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    symbols::Marker,
+    widgets::{
+        Axis, Block, Borders, Chart, Dataset, GraphType, List, ListItem, ListState, Paragraph,
+    },
+};
+
+/// Width of the list of outliers beside the plot
+const OUTLIER_LIST_WIDTH: u16 = 36;
+
+/// Two metrics of each sample in memory plotted against each other, beside
+/// the list of samples far off the trend between them
+pub(crate) struct ScatterPlot<'a> {
+    state: &'a AppData,
+    open_key: Option<KeyBinding>,
+    theme: &'a Theme,
+}
+
+impl<'a> ScatterPlot<'a> {
+    /// Create a scatter plot of the samples of `state`
+    pub(crate) fn new(state: &'a AppData) -> Self {
+        Self {
+            state,
+            open_key: None,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Name `key` as the one opening the selected outlier (builder pattern)
+    pub(crate) fn open_key(mut self, key: Option<KeyBinding>) -> Self {
+        self.open_key = key;
+        self
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// List of the outliers, with the selected one highlighted
+    fn render_outlier_list(&self, outliers: &[&ScatterPoint], area: Rect, buf: &mut Buffer) {
+        let items = outliers.iter().map(|point| {
+            ListItem::new(format!(
+                "{} ({:.3}, {:.3})",
+                point.sample_id, point.x, point.y
+            ))
+        });
+        let mut title = format!("Outliers: {}", outliers.len());
+        if let Some(key) = self.open_key {
+            title.push_str(&format!(" ([{}] open)", key));
+        }
+        let list = List::new(items)
+            .style(self.theme.error)
+            .highlight_style(self.theme.highlight)
+            .block(Block::default().borders(Borders::ALL).title(title));
+        let selected = (!outliers.is_empty()).then(|| self.state.scroll_position(Tab::Scatter));
+        let mut list_state = ListState::default().with_selected(selected);
+        StatefulWidget::render(list, area, buf, &mut list_state);
+    }
+}
+
+impl<'a> Widget for ScatterPlot<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (Some(x_metric), Some(y_metric)) = self.state.scatter_axes() else {
+            Paragraph::new("Needs samples reporting two metrics")
+                .style(self.theme.muted)
+                .block(Block::default().borders(Borders::ALL).title("Scatter"))
+                .render(area, buf);
+            return;
+        };
+
+        let points = self.state.scatter_points();
+        let outliers: Vec<&ScatterPoint> = points.iter().filter(|p| p.outlier).collect();
+        let [list_area, plot_area] =
+            Layout::horizontal([Constraint::Length(OUTLIER_LIST_WIDTH), Constraint::Min(0)])
+                .areas(area);
+        self.render_outlier_list(&outliers, list_area, buf);
+
+        let regular: Vec<(f64, f64)> = points
+            .iter()
+            .filter(|p| !p.outlier)
+            .map(|p| (p.x, p.y))
+            .collect();
+        let flagged: Vec<(f64, f64)> = outliers.iter().map(|p| (p.x, p.y)).collect();
+        let selected: Vec<(f64, f64)> = outliers
+            .get(self.state.scroll_position(Tab::Scatter))
+            .map(|p| vec![(p.x, p.y)])
+            .unwrap_or_default();
+
+        let x_bounds = bounds(points.iter().map(|p| p.x));
+        let y_bounds = bounds(points.iter().map(|p| p.y));
+        let dataset = |name: &str, marker, style, data| {
+            Dataset::default()
+                .name(name.to_string())
+                .marker(marker)
+                .graph_type(GraphType::Scatter)
+                .style(style)
+                .data(data)
+        };
+        let datasets = vec![
+            dataset(
+                "samples",
+                Marker::Braille,
+                Style::default().fg(self.theme.series_color(0)),
+                &regular,
+            ),
+            dataset("outliers", Marker::Dot, self.theme.error, &flagged),
+            dataset(
+                "selected",
+                Marker::Block,
+                self.theme.accent.add_modifier(Modifier::BOLD),
+                &selected,
+            ),
+        ];
+
+        let chart = Chart::new(datasets)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(
+                        "{} vs {} ({} samples)",
+                        y_metric,
+                        x_metric,
+                        points.len()
+                    ))
+                    .title(Line::from(" [h] x metric [v] y metric ").right_aligned()),
+            )
+            .x_axis(
+                Axis::default()
+                    .title(x_metric.to_string())
+                    .style(self.theme.border)
+                    .bounds(x_bounds)
+                    .labels(labels(x_bounds, |x| format!("{:.3}", x))),
+            )
+            .y_axis(
+                Axis::default()
+                    .title(y_metric.to_string())
+                    .style(self.theme.border)
+                    .bounds(y_bounds)
+                    .labels(labels(y_bounds, |y| format!("{:.3}", y))),
+            );
+        chart.render(plot_area, buf);
+    }
+}
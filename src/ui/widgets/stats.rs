@@ -0,0 +1,220 @@
+use crate::state::aggregation::{GroupedAggregator, MetricAggregator, MetricStats};
+use crate::state::rules::{Aggregate, RuleSet};
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Row, Table},
+};
+
+/// Most metrics shown before the table is cut off
+const MAX_ROWS: usize = 8;
+
+/// Most group and metric pairs shown before the grouped table is cut off
+const MAX_GROUPED_ROWS: usize = 12;
+
+/// Table of per-metric statistics across all completed samples
+pub(crate) struct MetricStatsTable<'a> {
+    stats: &'a MetricAggregator,
+    rules: RuleSet<'a>,
+    theme: &'a Theme,
+}
+
+impl<'a> MetricStatsTable<'a> {
+    /// Create a new statistics table
+    pub(crate) fn new(stats: &'a MetricAggregator) -> Self {
+        Self {
+            stats,
+            rules: RuleSet::default(),
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Color the statistics by the threshold rules covering them (builder pattern)
+    pub(crate) fn rules(mut self, rules: RuleSet<'a>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Rows needed to show every metric, including borders and header
+    pub(crate) fn height(stats: &MetricAggregator) -> u16 {
+        if stats.is_empty() {
+            0
+        } else {
+            (stats.len().min(MAX_ROWS) + 3) as u16
+        }
+    }
+
+    /// Format one statistic, leaving the cell blank when there is no value
+    pub(super) fn cell(value: Option<f64>) -> String {
+        value.map(|v| format!("{:.3}", v)).unwrap_or_default()
+    }
+
+    /// Build the table row for one metric
+    fn row(&self, name: &str, stats: &MetricStats) -> Row<'static> {
+        let cell = |aggregate, value| stat_cell(&self.rules, self.theme, name, aggregate, value);
+        Row::new(vec![
+            Cell::from(name.to_string()),
+            Cell::from(stats.count().to_string()),
+            cell(Aggregate::Mean, stats.mean()),
+            cell(Aggregate::Median, stats.median()),
+            cell(Aggregate::P95, stats.p95()),
+            cell(Aggregate::P99, stats.p99()),
+            cell(Aggregate::Min, stats.min()),
+            cell(Aggregate::Max, stats.max()),
+            cell(Aggregate::StdDev, stats.stddev()),
+        ])
+    }
+}
+
+impl<'a> Widget for MetricStatsTable<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let header = Row::new([
+            "Metric", "n", "Mean", "p50", "p95", "p99", "Min", "Max", "StdDev",
+        ])
+        .style(self.theme.heading);
+        let rows = self
+            .stats
+            .iter()
+            .take(MAX_ROWS)
+            .map(|(name, stats)| self.row(name, stats));
+        let widths = [
+            Constraint::Min(20),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+        ];
+
+        let table = Table::new(rows, widths).header(header).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(stats_title(self.stats)),
+        );
+
+        Widget::render(table, area, buf);
+    }
+}
+
+/// Cell of the `aggregate` statistic of `metric`, colored by the verdict of
+/// the rules covering it
+pub(super) fn stat_cell(
+    rules: &RuleSet,
+    theme: &Theme,
+    metric: &str,
+    aggregate: Aggregate,
+    value: Option<f64>,
+) -> Cell<'static> {
+    let cell = Cell::from(MetricStatsTable::cell(value));
+    match value.and_then(|value| rules.judge_stat(metric, aggregate, value)) {
+        Some(verdict) => cell.style(theme.verdict(verdict)),
+        None => cell,
+    }
+}
+
+/// Title of a table of `stats`, noting what the statistics leave out so
+/// they are not mistaken for statistics over every sample
+pub(super) fn stats_title(stats: &MetricAggregator) -> String {
+    let (warmup, trimmed) = stats.exclusions();
+    let mut notes = Vec::new();
+    if warmup > 0 {
+        notes.push(format!("first {} samples excluded as warm-up", warmup));
+    }
+    if trimmed > 0 {
+        notes.push(format!("{} outliers trimmed", trimmed));
+    }
+    let mut title = "Metrics".to_string();
+    if !notes.is_empty() {
+        title.push_str(&format!(" ({})", notes.join(", ")));
+    }
+    title
+}
+
+/// Table of per-metric statistics for each value of one attribute
+pub(crate) struct GroupedStatsTable<'a> {
+    key: &'a str,
+    stats: &'a GroupedAggregator,
+    theme: &'a Theme,
+}
+
+impl<'a> GroupedStatsTable<'a> {
+    /// Create a table of `stats` grouped by attribute `key`
+    pub(crate) fn new(key: &'a str, stats: &'a GroupedAggregator) -> Self {
+        Self {
+            key,
+            stats,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Rows needed to show every group, including borders and header
+    pub(crate) fn height(key: &str, stats: &GroupedAggregator) -> u16 {
+        let rows: usize = stats.groups(key).map(|(_, metrics)| metrics.len()).sum();
+        (rows.clamp(1, MAX_GROUPED_ROWS) + 3) as u16
+    }
+}
+
+impl<'a> Widget for GroupedStatsTable<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let header = Row::new(["Group", "Metric", "n", "Mean", "p50", "p95", "Min", "Max"])
+            .style(self.theme.heading);
+        let mut rows: Vec<Row> = self
+            .stats
+            .groups(self.key)
+            .flat_map(|(group, metrics)| {
+                metrics.iter().map(move |(name, stats)| {
+                    Row::new(vec![
+                        group.to_string(),
+                        name.to_string(),
+                        stats.count().to_string(),
+                        MetricStatsTable::cell(stats.mean()),
+                        MetricStatsTable::cell(stats.median()),
+                        MetricStatsTable::cell(stats.p95()),
+                        MetricStatsTable::cell(stats.min()),
+                        MetricStatsTable::cell(stats.max()),
+                    ])
+                })
+            })
+            .take(MAX_GROUPED_ROWS)
+            .collect();
+        if rows.is_empty() {
+            rows.push(
+                Row::new([format!("No samples with attribute '{}' yet", self.key)])
+                    .style(self.theme.muted),
+            );
+        }
+        let widths = [
+            Constraint::Min(12),
+            Constraint::Min(20),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+        ];
+
+        let table = Table::new(rows, widths).header(header).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Metrics by {} [g]", self.key)),
+        );
+
+        Widget::render(table, area, buf);
+    }
+}
@@ -0,0 +1,101 @@
+use super::{format_bytes, format_rate};
+use crate::state::app::AppData;
+use crate::ui::theme::Theme;
+use ratatui::{prelude::*, widgets::Paragraph};
+use std::time::Duration;
+
+/// Silence from a running evaluator long enough to point at a stall
+const STALL_AFTER: Duration = Duration::from_secs(30);
+
+/// Line of live run health above the footer: throughput, time since the
+/// evaluator's last output, output buffer usage and PrEval's own memory
+pub(crate) struct StatusBar<'a> {
+    state: &'a AppData,
+    theme: &'a Theme,
+}
+
+impl<'a> StatusBar<'a> {
+    /// Create a status bar for the run in `state`
+    pub(crate) fn new(state: &'a AppData) -> Self {
+        Self {
+            state,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Samples and tokens per second over the recent window
+    fn throughput(&self) -> Span<'static> {
+        let text = match self.state.throughput() {
+            Some(throughput) => {
+                let mut text = format!("{} samples", format_rate(throughput.samples_per_sec));
+                if let Some(tokens) = throughput.tokens_per_sec {
+                    text.push_str(&format!(", {} tok", format_rate(tokens)));
+                }
+                text
+            }
+            None => "– samples/s".to_string(),
+        };
+        Span::raw(text)
+    }
+
+    /// Time since the evaluator last wrote anything, flagged once it looks stalled
+    fn last_output(&self) -> Span<'static> {
+        let Some(age) = self.state.last_output_age() else {
+            return Span::raw("no output yet");
+        };
+        let seconds = age.as_secs();
+        let text = if seconds < 60 {
+            format!("last output {}s ago", seconds)
+        } else {
+            format!("last output {}m {:02}s ago", seconds / 60, seconds % 60)
+        };
+        let running = !self.state.is_terminal() && !self.state.is_paused();
+        if running && age >= STALL_AFTER {
+            Span::styled(text, self.theme.warning)
+        } else {
+            Span::raw(text)
+        }
+    }
+
+    /// Lines waiting in the output buffer, flagged when it is full or dropped lines
+    fn buffer(&self) -> Span<'static> {
+        let diagnostics = self.state.output_diagnostics();
+        let mut text = format!("buffer {}/{}", diagnostics.depth, diagnostics.capacity);
+        if diagnostics.dropped > 0 {
+            text.push_str(&format!(", {} dropped", diagnostics.dropped));
+        }
+        let full = diagnostics.capacity > 0 && diagnostics.depth >= diagnostics.capacity;
+        if full || diagnostics.dropped > 0 {
+            Span::styled(text, self.theme.warning)
+        } else {
+            Span::raw(text)
+        }
+    }
+}
+
+impl<'a> Widget for StatusBar<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut spans = vec![self.throughput(), self.last_output(), self.buffer()];
+        if let Some(bytes) = self.state.preval_memory_bytes() {
+            spans.push(Span::raw(format!("PrEval RSS {}", format_bytes(bytes))));
+        }
+
+        let separator = Span::styled(" │ ", self.theme.muted);
+        let mut line = Vec::with_capacity(spans.len() * 2);
+        for (i, span) in spans.into_iter().enumerate() {
+            if i > 0 {
+                line.push(separator.clone());
+            }
+            line.push(span);
+        }
+        Paragraph::new(Line::from(line))
+            .style(self.theme.subtitle)
+            .render(area, buf);
+    }
+}
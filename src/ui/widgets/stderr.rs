@@ -0,0 +1,78 @@
+use super::scroll_position_text;
+use crate::ui::keymap::KeyBinding;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+use std::collections::VecDeque;
+
+/// Pane showing the most recent lines the evaluator wrote to stderr
+pub(crate) struct StderrPane<'a> {
+    lines: &'a VecDeque<String>,
+    scrolled_back: usize,
+    follow_key: Option<KeyBinding>,
+    theme: &'a Theme,
+}
+
+impl<'a> StderrPane<'a> {
+    /// Create a new stderr pane
+    pub(crate) fn new(lines: &'a VecDeque<String>) -> Self {
+        Self {
+            lines,
+            scrolled_back: 0,
+            follow_key: None,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Name the key that goes back to following new lines (builder pattern)
+    pub(crate) fn follow_key(mut self, key: Option<KeyBinding>) -> Self {
+        self.follow_key = key;
+        self
+    }
+
+    /// Show the lines ending `lines` before the newest (builder pattern)
+    pub(crate) fn scrolled_back(mut self, lines: usize) -> Self {
+        self.scrolled_back = lines;
+        self
+    }
+}
+
+impl<'a> Widget for StderrPane<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Show the tail that fits inside the borders
+        let visible = area.height.saturating_sub(2) as usize;
+        let end = self.lines.len().saturating_sub(self.scrolled_back);
+
+        let title = match self.lines.len() {
+            0 => "Stderr".to_string(),
+            len => format!(
+                "Stderr ({})",
+                scroll_position_text("line", end, len, self.follow_key)
+            ),
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(self.theme.border);
+        let text: Vec<Line> = if self.lines.is_empty() {
+            vec![Line::from("No stderr output").style(self.theme.muted)]
+        } else {
+            self.lines
+                .iter()
+                .take(end)
+                .skip(end.saturating_sub(visible))
+                .map(|line| Line::from(line.as_str()))
+                .collect()
+        };
+
+        Paragraph::new(text).block(block).render(area, buf);
+    }
+}
@@ -0,0 +1,231 @@
+use super::progress::{sample_list_item, ProgressView};
+use super::sample_detail::SampleDetail;
+use super::stats::MetricStatsTable;
+use crate::history::database::{Outcome, RunAttempt, StoredRun};
+use crate::ui::layout::UiLayout;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+};
+
+/// Read-only view of a run from the history database
+pub(crate) struct StoredRunView<'a> {
+    run: &'a StoredRun,
+    scroll: usize,
+    /// Show the sample at the scroll position in full instead of the list
+    detail: bool,
+    theme: &'a Theme,
+}
+
+impl<'a> StoredRunView<'a> {
+    /// Create a view of `run`
+    pub(crate) fn new(run: &'a StoredRun) -> Self {
+        Self {
+            run,
+            scroll: 0,
+            detail: false,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Skip the first `scroll` samples of the list (builder pattern)
+    pub(crate) fn scroll(mut self, scroll: usize) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    /// Show the sample at the scroll position in full (builder pattern)
+    pub(crate) fn detail(mut self, detail: bool) -> Self {
+        self.detail = detail;
+        self
+    }
+
+    /// Render the run's evaluator and outcome
+    fn render_header(&self, area: Rect, buf: &mut Buffer) {
+        let record = &self.run.record;
+        let mut title = match (self.run.id, &record.run_dir) {
+            (Some(id), _) => format!("Run {}: {}", id, record.evaluator),
+            (None, Some(run_dir)) => format!("Run {} (recovered): {}", run_dir, record.evaluator),
+            (None, None) => format!("Recovered run: {}", record.evaluator),
+        };
+        if let Some(version) = &record.evaluator_version {
+            title.push_str(&format!(" v{}", version));
+        }
+
+        let duration = (record.finished_at - record.started_at)
+            .to_std()
+            .unwrap_or_default();
+        let text = format!(
+            "{} | Started {} | Took {}",
+            record.outcome,
+            record.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            ProgressView::format_duration(duration)
+        );
+        let style = match record.outcome {
+            Outcome::Completed => self.theme.success,
+            Outcome::Failed => self.theme.error,
+            Outcome::Stopped => self.theme.warning,
+        };
+
+        Paragraph::new(text)
+            .style(style)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .render(area, buf);
+    }
+
+    /// Render the summary line of the run
+    fn render_summary(&self, area: Rect, buf: &mut Buffer) {
+        let record = &self.run.record;
+        let mut text = match record.total_samples {
+            Some(total) => format!(
+                "{}/{} samples, {} failed",
+                record.samples_completed, total, record.samples_failed
+            ),
+            None => format!(
+                "{} samples, {} failed",
+                record.samples_completed, record.samples_failed
+            ),
+        };
+        if let Some(score) = record.score {
+            text.push_str(&format!(" | Score: {:.3}", score));
+        }
+        if record.restarts > 0 {
+            text.push_str(&format!(" | Restarts: {}", record.restarts));
+        }
+        if record.samples_dropped > 0 {
+            text.push_str(&format!(
+                " | {} oldest samples not kept",
+                record.samples_dropped
+            ));
+        }
+        if let Some(failure) = &record.failure {
+            text.push_str(&format!(" | {}", failure));
+        }
+
+        Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL).title("Summary"))
+            .render(area, buf);
+    }
+
+    /// Lines of the attempts table; empty unless the evaluator was restarted
+    fn attempt_lines(&self) -> Vec<Line<'static>> {
+        let attempts = &self.run.record.attempts;
+        if attempts.len() < 2 {
+            return Vec::new();
+        }
+        attempts
+            .iter()
+            .map(|attempt| self.attempt_line(attempt))
+            .collect()
+    }
+
+    /// One attempt: when it ran and how it ended
+    fn attempt_line(&self, attempt: &RunAttempt) -> Line<'static> {
+        let started = attempt.started_at.format("%H:%M:%S");
+        let Some(finished_at) = attempt.finished_at else {
+            return Line::styled(
+                format!("#{}  {}  no exit recorded", attempt.attempt, started),
+                self.theme.muted,
+            );
+        };
+        let took = (finished_at - attempt.started_at)
+            .to_std()
+            .unwrap_or_default();
+        let (ending, style) = match (&attempt.signal, attempt.exit_code) {
+            (Some(signal), _) => (format!("killed by {}", signal), self.theme.error),
+            (None, Some(0)) => ("exited with code 0".to_string(), self.theme.success),
+            (None, Some(code)) => (format!("exited with code {}", code), self.theme.error),
+            (None, None) => ("exited".to_string(), self.theme.warning),
+        };
+        Line::styled(
+            format!(
+                "#{}  {}-{} ({})  {}",
+                attempt.attempt,
+                started,
+                finished_at.format("%H:%M:%S"),
+                ProgressView::format_duration(took),
+                ending
+            ),
+            style,
+        )
+    }
+
+    /// Render the recorded samples, starting at the scroll position
+    fn render_samples(&self, area: Rect, buf: &mut Buffer) {
+        let items: Vec<ListItem> = if self.run.samples.is_empty() {
+            vec![ListItem::new("  No samples recorded").style(self.theme.muted)]
+        } else {
+            self.run
+                .samples
+                .iter()
+                .skip(self.scroll)
+                .map(|sample| sample_list_item(sample, self.theme))
+                .collect()
+        };
+
+        let title = format!("Samples ({})", self.run.samples.len());
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+        Widget::render(list, area, buf);
+    }
+}
+
+impl<'a> Widget for StoredRunView<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let layout = match UiLayout::new(area) {
+            Ok(layout) => layout,
+            Err(_) => {
+                Paragraph::new("Terminal too small!")
+                    .style(self.theme.error)
+                    .render(area, buf);
+                return;
+            }
+        };
+
+        self.render_header(layout.header, buf);
+
+        let attempts = self.attempt_lines();
+        let attempts_height = match attempts.len() {
+            0 => 0,
+            lines => lines as u16 + 2,
+        };
+        let [stats_area, attempts_area, samples_area, summary_area] = Layout::vertical([
+            Constraint::Length(MetricStatsTable::height(&self.run.metric_stats)),
+            Constraint::Length(attempts_height),
+            Constraint::Min(3),
+            Constraint::Length(3),
+        ])
+        // A stored run has no live status, so its content takes that line too
+        .areas(layout.content.union(layout.status));
+        MetricStatsTable::new(&self.run.metric_stats)
+            .theme(self.theme)
+            .render(stats_area, buf);
+        if !attempts.is_empty() {
+            Paragraph::new(attempts)
+                .block(Block::default().borders(Borders::ALL).title("Attempts"))
+                .render(attempts_area, buf);
+        }
+        match self.run.samples.get(self.scroll).filter(|_| self.detail) {
+            Some(sample) => SampleDetail::new(sample)
+                .theme(self.theme)
+                .render(samples_area, buf),
+            None => self.render_samples(samples_area, buf),
+        }
+        self.render_summary(summary_area, buf);
+
+        let shortcuts = if self.detail {
+            "[q] Quit  [Esc] Back  [Up/Down] Previous/next sample  (read-only)"
+        } else {
+            "[q] Quit  [Up/Down] Scroll  [PgUp/PgDn] Page  [Enter] Sample detail  (read-only)"
+        };
+        Paragraph::new(shortcuts)
+            .style(self.theme.muted)
+            .render(layout.footer, buf);
+    }
+}
@@ -0,0 +1,170 @@
+use super::progress::{sample_notes, ProgressView};
+use super::stats::MetricStatsTable;
+use crate::state::app::AppData;
+use crate::state::sample_store::{SampleFilter, StatusFilter};
+use crate::state::types::EvaluationStatus;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+/// Widest the summary gets on large terminals
+const MAX_WIDTH: u16 = 100;
+
+/// Most failed samples listed by name
+const MAX_FAILURES: usize = 5;
+
+/// Modal summing up a finished run, drawn over the view until dismissed
+pub(crate) struct RunSummary<'a> {
+    state: &'a AppData,
+    theme: &'a Theme,
+}
+
+impl<'a> RunSummary<'a> {
+    /// Create a summary of the run in `state`
+    pub(crate) fn new(state: &'a AppData) -> Self {
+        Self {
+            state,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Outcome, elapsed time and sample counts of the run
+    fn overview(&self) -> Vec<Line<'static>> {
+        let (status, style) = match self.state.status() {
+            EvaluationStatus::Failed(reason) => (format!("Failed: {}", reason), self.theme.error),
+            _ => ("Completed".to_string(), self.theme.success),
+        };
+        let (failed, completed, success_rate) = self.state.summary_stats();
+        let mut samples = format!("Samples: {} completed, {} failed", completed, failed);
+        if completed > 0 {
+            samples.push_str(&format!(" ({:.1}% success rate)", success_rate));
+        }
+
+        let mut lines = vec![
+            Line::styled(status, style),
+            Line::from(format!(
+                "Elapsed: {}",
+                ProgressView::format_duration(self.state.elapsed_time())
+            )),
+            Line::from(samples),
+        ];
+        if let (Some(score), Some(mean)) = (self.state.score(), self.state.mean_score()) {
+            lines.push(Line::styled(
+                format!("{}: {:.3}", score.name(), mean),
+                self.theme.heading,
+            ));
+        }
+        lines
+    }
+
+    /// Failed samples, threshold rules and saved reports
+    fn details(&self) -> Vec<Line<'static>> {
+        let heading = self.theme.heading;
+        let mut lines = Vec::new();
+
+        let failed_filter = SampleFilter::default().with_status(StatusFilter::Failed);
+        let failures: Vec<_> = self
+            .state
+            .sample_store()
+            .matching(&failed_filter)
+            .map(|(_, sample)| sample)
+            .collect();
+        if !failures.is_empty() {
+            lines.push(Line::styled("Failed samples", heading));
+            for sample in failures.iter().take(MAX_FAILURES) {
+                lines.push(Line::styled(
+                    format!("  ✗ {}{}", sample.sample_id, sample_notes(sample)),
+                    self.theme.error,
+                ));
+            }
+            if failures.len() > MAX_FAILURES {
+                lines.push(Line::styled(
+                    format!("  … and {} more", failures.len() - MAX_FAILURES),
+                    self.theme.muted,
+                ));
+            }
+            lines.push(Line::default());
+        }
+
+        let tripped = self.state.tripped_rules();
+        let rules: Vec<String> = self
+            .state
+            .threshold_rules()
+            .map(ToString::to_string)
+            .collect();
+        if !rules.is_empty() {
+            lines.push(Line::styled("Thresholds", heading));
+            for rule in rules {
+                let line = match tripped.get(&rule) {
+                    Some(violations) => Line::styled(
+                        format!("  ✗ {} (tripped x{})", rule, violations),
+                        self.theme.error,
+                    ),
+                    None => Line::styled(format!("  ✓ {}", rule), self.theme.success),
+                };
+                lines.push(line);
+            }
+            lines.push(Line::default());
+        }
+
+        if !self.state.reports().is_empty() {
+            lines.push(Line::styled("Reports", heading));
+            for (description, path) in self.state.reports() {
+                lines.push(Line::from(format!("  {}: {}", description, path.display())));
+            }
+        }
+        lines
+    }
+}
+
+impl<'a> Widget for RunSummary<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let overview = self.overview();
+        let details = self.details();
+        let stats = self.state.metric_stats();
+        let stats_height = MetricStatsTable::height(stats);
+
+        // Centered, with room for the borders and a blank line between parts
+        let height = overview.len() as u16 + 1 + stats_height + details.len() as u16 + 2;
+        let width = area.width.min(MAX_WIDTH);
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(layout::Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(height.min(area.height))])
+            .flex(layout::Flex::Center)
+            .areas(area);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(self.theme.border)
+            .title("Run summary ([q] quit, [Esc] browse results)");
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let [overview_area, stats_area, details_area] = Layout::vertical([
+            Constraint::Length(overview.len() as u16 + 1),
+            Constraint::Length(stats_height),
+            Constraint::Min(0),
+        ])
+        .areas(inner);
+        Paragraph::new(overview).render(overview_area, buf);
+        if !stats.is_empty() {
+            MetricStatsTable::new(stats)
+                .rules(self.state.rule_set())
+                .theme(self.theme)
+                .render(stats_area, buf);
+        }
+        Paragraph::new(details)
+            .wrap(Wrap { trim: false })
+            .render(details_area, buf);
+    }
+}
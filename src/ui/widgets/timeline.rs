@@ -0,0 +1,86 @@
+use crate::state::timeline::{Timeline, TimelineEventKind};
+use crate::ui::theme::Theme;
+use chrono::Local;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+/// Pane showing the most recent notable events of the run
+pub(crate) struct TimelinePane<'a> {
+    timeline: &'a Timeline,
+    theme: &'a Theme,
+}
+
+impl<'a> TimelinePane<'a> {
+    /// Create a new timeline pane
+    pub(crate) fn new(timeline: &'a Timeline) -> Self {
+        Self {
+            timeline,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Color drawing attention to events that may explain a bad run
+    fn style(&self, kind: TimelineEventKind) -> Style {
+        match kind {
+            TimelineEventKind::ParseError
+            | TimelineEventKind::ThresholdViolation
+            | TimelineEventKind::ProcessExit
+            | TimelineEventKind::Restart => self.theme.error,
+            TimelineEventKind::Anomaly | TimelineEventKind::Script => self.theme.notice,
+            TimelineEventKind::Pause | TimelineEventKind::Resume => self.theme.warning,
+            TimelineEventKind::Handshake | TimelineEventKind::Phase => Style::default(),
+        }
+    }
+}
+
+impl<'a> Widget for TimelinePane<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut title = format!("Timeline ({} events", self.timeline.len());
+        if self.timeline.dropped() > 0 {
+            title.push_str(&format!(", {} older dropped", self.timeline.dropped()));
+        }
+        title.push(')');
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(self.theme.border);
+
+        // Show the latest events that fit inside the borders
+        let visible = area.height.saturating_sub(2) as usize;
+        let text: Vec<Line> = if self.timeline.is_empty() {
+            vec![Line::from("No events yet").style(self.theme.muted)]
+        } else {
+            self.timeline
+                .events()
+                .skip(self.timeline.len().saturating_sub(visible))
+                .map(|event| {
+                    Line::from(vec![
+                        Span::styled(
+                            event
+                                .at
+                                .with_timezone(&Local)
+                                .format("%H:%M:%S ")
+                                .to_string(),
+                            self.theme.muted,
+                        ),
+                        Span::styled(
+                            format!("{:<11} ", event.kind.label()),
+                            self.style(event.kind).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(event.message.as_str()),
+                    ])
+                })
+                .collect()
+        };
+
+        Paragraph::new(text).block(block).render(area, buf);
+    }
+}
@@ -0,0 +1,85 @@
+use super::sparkline_bars;
+use crate::state::trends::Trends;
+use crate::ui::theme::Theme;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Sparkline},
+};
+
+/// Rows taken by the sparklines, including borders
+const HEIGHT: u16 = 5;
+
+/// Row of sparklines showing the recent trajectory of each key metric
+pub(crate) struct TrendsRow<'a> {
+    trends: &'a Trends,
+    theme: &'a Theme,
+}
+
+impl<'a> TrendsRow<'a> {
+    /// Create a row of sparklines for `trends`
+    pub(crate) fn new(trends: &'a Trends) -> Self {
+        Self {
+            trends,
+            theme: &Theme::DARK,
+        }
+    }
+
+    /// Set the colors to draw with (builder pattern)
+    pub(crate) fn theme(mut self, theme: &'a Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Rows needed, or none until a key metric has a value
+    pub(crate) fn height(trends: &Trends) -> u16 {
+        if trends.iter().next().is_some() {
+            HEIGHT
+        } else {
+            0
+        }
+    }
+}
+
+/// Format a metric value compactly, dropping decimals from large values
+fn format_value(value: f64) -> String {
+    if value.abs() >= 100.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+impl<'a> Widget for TrendsRow<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let trends: Vec<_> = self.trends.iter().collect();
+        let areas = Layout::horizontal(vec![Constraint::Fill(1); trends.len()]).split(area);
+
+        for ((name, values), area) in trends.into_iter().zip(areas.iter()) {
+            let values: Vec<f64> = values.iter().copied().collect();
+            let latest = values.last().copied().unwrap_or_default();
+            let (min, max) = values
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+                    (min.min(v), max.max(v))
+                });
+            let title = Line::from(vec![
+                Span::styled(
+                    format!("{} {}", name, format_value(latest)),
+                    self.theme.heading,
+                ),
+                Span::styled(
+                    format!(" ({}–{})", format_value(min), format_value(max)),
+                    self.theme.muted,
+                ),
+            ]);
+            let block = Block::default().borders(Borders::ALL).title(title);
+            let bars = sparkline_bars(&values, block.inner(*area).width);
+            Sparkline::default()
+                .block(block)
+                .data(&bars)
+                .max(100)
+                .style(self.theme.accent)
+                .render(*area, buf);
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use preval::evaluator::process::{EvaluatorMessage, EvaluatorProcess};
+use preval::evaluator::output_queue::OutputBuffer;
+use preval::evaluator::process::{EvaluatorMessage, EvaluatorProcess, ShutdownPolicy};
+use preval::evaluator::protocol::ControlMessage;
 use preval::state::types::EvaluatorCommand;
 use tokio::sync::mpsc;
 
@@ -11,13 +13,17 @@ async fn test_mock_evaluator_integration() {
     let cmd = EvaluatorCommand::try_new("cargo run --bin mock_evaluator".to_string()).unwrap();
 
     // Spawn the mock evaluator
-    let mut evaluator = EvaluatorProcess::spawn(&cmd, tx).await.unwrap();
+    let mut evaluator = EvaluatorProcess::spawn(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
 
-    // Collect first few messages
+    // Collect first few stdout messages (cargo's build output arrives on stderr)
     let mut messages = Vec::new();
-    for _ in 0..5 {
-        if let Some(msg) = rx.recv().await {
-            messages.push(msg);
+    while messages.len() < 5 {
+        match rx.recv().await {
+            Some(msg @ EvaluatorMessage::Output(_)) => messages.push(msg),
+            Some(_) => continue,
+            None => break,
         }
     }
 
@@ -44,3 +50,294 @@ async fn test_mock_evaluator_integration() {
         }
     }
 }
+
+/// Command that exits immediately with status 1
+fn failing_command() -> &'static str {
+    if cfg!(windows) {
+        "exit 1"
+    } else {
+        "false"
+    }
+}
+
+/// Command that keeps running until killed
+fn long_running_command() -> &'static str {
+    if cfg!(windows) {
+        "ping -n 30 127.0.0.1"
+    } else {
+        "sleep 30"
+    }
+}
+
+#[tokio::test]
+async fn reports_real_exit_status() {
+    let (tx, mut rx) = mpsc::channel(100);
+
+    let cmd = EvaluatorCommand::try_new(failing_command().to_string()).unwrap();
+    let _evaluator = EvaluatorProcess::spawn(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
+
+    let status = loop {
+        match rx.recv().await {
+            Some(EvaluatorMessage::Exited(status)) => break status,
+            Some(_) => continue,
+            None => panic!("Channel closed without an exit message"),
+        }
+    };
+
+    assert!(!status.success());
+    assert_eq!(status.code(), Some(1));
+}
+
+#[tokio::test]
+async fn kill_reports_exit() {
+    let (tx, mut rx) = mpsc::channel(100);
+
+    let cmd = EvaluatorCommand::try_new(long_running_command().to_string()).unwrap();
+    let mut evaluator = EvaluatorProcess::spawn(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
+    evaluator.kill().await.unwrap();
+
+    let status = loop {
+        match rx.recv().await {
+            Some(EvaluatorMessage::Exited(status)) => break status,
+            Some(_) => continue,
+            None => panic!("Channel closed without an exit message"),
+        }
+    };
+
+    assert!(!status.success());
+}
+
+#[tokio::test]
+async fn shutdown_stops_running_evaluator() {
+    let (tx, mut rx) = mpsc::channel(100);
+
+    let cmd = EvaluatorCommand::try_new(long_running_command().to_string()).unwrap();
+    let mut evaluator = EvaluatorProcess::spawn(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
+
+    let policy = ShutdownPolicy {
+        grace_period: std::time::Duration::from_secs(2),
+        ..ShutdownPolicy::default()
+    };
+    tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        evaluator.shutdown(policy),
+    )
+    .await
+    .expect("shutdown should finish within the grace period")
+    .unwrap();
+
+    let exited = loop {
+        match rx.recv().await {
+            Some(EvaluatorMessage::Exited(_)) => break true,
+            Some(_) => continue,
+            None => break false,
+        }
+    };
+    assert!(exited, "Evaluator should report its exit after shutdown");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn control_messages_reach_evaluator_stdin() {
+    let (tx, mut rx) = mpsc::channel(100);
+    // `head` echoes the first line it reads, then exits
+    let cmd = EvaluatorCommand::try_new("head -n 1".to_string()).unwrap();
+    let mut evaluator = EvaluatorProcess::spawn(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
+
+    evaluator.send_control(ControlMessage::Pause).await.unwrap();
+
+    let line = loop {
+        match rx.recv().await {
+            Some(EvaluatorMessage::Output(line)) => break line,
+            Some(_) => continue,
+            None => panic!("evaluator channel closed before output"),
+        }
+    };
+    assert_eq!(line, r#"{"type":"pause"}"#);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn cancelled_evaluator_may_exit_before_it_is_signalled() {
+    let (tx, mut rx) = mpsc::channel(100);
+    // Takes a second to wrap up after reading the cancel message; SIGTERM
+    // would cut that short
+    let script = std::env::temp_dir().join(format!("preval-cancel-{}.sh", std::process::id()));
+    std::fs::write(
+        &script,
+        "read line\nsleep 1\necho \"wrapped up after $line\"\n",
+    )
+    .unwrap();
+    let cmd = EvaluatorCommand::try_new(format!("sh {}", script.display())).unwrap();
+    let mut evaluator = EvaluatorProcess::spawn(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
+
+    evaluator
+        .send_control(ControlMessage::Cancel)
+        .await
+        .unwrap();
+    let policy = ShutdownPolicy {
+        grace_period: std::time::Duration::from_secs(5),
+        ..ShutdownPolicy::default()
+    };
+    evaluator.shutdown(policy).await.unwrap();
+
+    let mut output = Vec::new();
+    let status = loop {
+        match rx.recv().await {
+            Some(EvaluatorMessage::Output(line)) => output.push(line),
+            Some(EvaluatorMessage::Exited(status)) => break status,
+            Some(_) => continue,
+            None => panic!("Channel closed without an exit message"),
+        }
+    };
+    let _ = std::fs::remove_file(&script);
+    assert!(status.success());
+    assert_eq!(output, vec![r#"wrapped up after {"type":"cancel"}"#]);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn suspended_evaluator_can_still_be_shut_down() {
+    let (tx, mut rx) = mpsc::channel(100);
+
+    let cmd = EvaluatorCommand::try_new(long_running_command().to_string()).unwrap();
+    let mut evaluator = EvaluatorProcess::spawn(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
+    evaluator.suspend().await.unwrap();
+
+    let policy = ShutdownPolicy {
+        grace_period: std::time::Duration::from_secs(5),
+        ..ShutdownPolicy::default()
+    };
+    let started = std::time::Instant::now();
+    evaluator.shutdown(policy).await.unwrap();
+    assert!(
+        started.elapsed() < policy.grace_period,
+        "a stopped evaluator should still act on the termination request"
+    );
+
+    let exited = loop {
+        match rx.recv().await {
+            Some(EvaluatorMessage::Exited(_)) => break true,
+            Some(_) => continue,
+            None => break false,
+        }
+    };
+    assert!(exited);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn pty_mode_gives_evaluator_a_terminal() {
+    let (tx, mut rx) = mpsc::channel(100);
+    // `tty` prints the terminal device, or "not a tty" without one
+    let cmd = EvaluatorCommand::try_new("tty".to_string()).unwrap();
+
+    let _evaluator = EvaluatorProcess::spawn_pty(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
+
+    let mut output = Vec::new();
+    loop {
+        match rx.recv().await {
+            Some(EvaluatorMessage::Output(line)) => output.push(line),
+            Some(EvaluatorMessage::Exited(status)) => {
+                assert!(status.success());
+                break;
+            }
+            Some(EvaluatorMessage::Stderr(_)) => {}
+            None => panic!("evaluator channel closed before exit"),
+        }
+    }
+    assert_eq!(output.len(), 1);
+    assert!(output[0].starts_with("/dev/"), "got {:?}", output);
+}
+
+/// Next line the evaluator writes to stdout
+async fn next_output(rx: &mut mpsc::Receiver<EvaluatorMessage>) -> String {
+    loop {
+        match rx.recv().await {
+            Some(EvaluatorMessage::Output(line)) => return line,
+            Some(_) => continue,
+            None => panic!("evaluator channel closed before output"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn the_mock_evaluator_pauses_and_resumes() {
+    let (tx, mut rx) = mpsc::channel(100);
+    let command = format!(
+        "{} --samples 40 --delay-ms 20",
+        env!("CARGO_BIN_EXE_mock_evaluator")
+    );
+    let cmd = EvaluatorCommand::try_new(command).unwrap();
+    let mut evaluator = EvaluatorProcess::spawn(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
+    assert!(next_output(&mut rx)
+        .await
+        .contains("\"capabilities\":[\"pause\",\"cancel\"]"));
+    next_output(&mut rx).await;
+    evaluator.send_control(ControlMessage::Pause).await.unwrap();
+    // A sample already under way may still finish
+    let quiet = tokio::time::timeout(std::time::Duration::from_millis(500), async {
+        next_output(&mut rx).await;
+        next_output(&mut rx).await
+    })
+    .await;
+    assert!(quiet.is_err(), "the paused mock kept reporting samples");
+
+    evaluator
+        .send_control(ControlMessage::Resume)
+        .await
+        .unwrap();
+    assert!(next_output(&mut rx).await.contains("resourceMetrics"));
+    let _ = evaluator.kill().await;
+}
+
+#[tokio::test]
+async fn the_mock_evaluator_wraps_up_when_cancelled() {
+    let (tx, mut rx) = mpsc::channel(100);
+    let command = format!(
+        "{} --samples 40 --delay-ms 20",
+        env!("CARGO_BIN_EXE_mock_evaluator")
+    );
+    let cmd = EvaluatorCommand::try_new(command).unwrap();
+    let mut evaluator = EvaluatorProcess::spawn(&cmd, OutputBuffer::default(), None, tx)
+        .await
+        .unwrap();
+    next_output(&mut rx).await;
+    next_output(&mut rx).await;
+    evaluator
+        .send_control(ControlMessage::Cancel)
+        .await
+        .unwrap();
+
+    let mut output = Vec::new();
+    let status = loop {
+        match rx.recv().await {
+            Some(EvaluatorMessage::Output(line)) => output.push(line),
+            Some(EvaluatorMessage::Exited(status)) => break status,
+            Some(_) => continue,
+            None => panic!("Channel closed without an exit message"),
+        }
+    };
+    assert!(status.success());
+    assert!(
+        output.len() < 40,
+        "the cancelled mock reported every sample"
+    );
+    assert!(output.last().unwrap().contains("\"summary\""));
+}
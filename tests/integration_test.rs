@@ -1,5 +1,5 @@
 use preval::evaluator::parser::parse_metrics_line;
-use preval::state::metrics::Metric;
+use preval::state::metrics::{Metric, SampleMetric};
 
 #[test]
 fn parses_real_world_evaluator_output() {
@@ -85,15 +85,17 @@ fn parses_real_world_evaluator_output() {
         .metrics
         .iter()
         .find(|m| match m {
-            Metric::Gauge { name, .. } => name.as_ref() == "llm.eval.accuracy",
+            Metric::Sample(SampleMetric::Gauge { name, .. }) => {
+                name.as_ref() == "llm.eval.accuracy"
+            }
             _ => false,
         })
         .expect("accuracy metric not found");
 
     match accuracy_metric {
-        Metric::Gauge {
+        Metric::Sample(SampleMetric::Gauge {
             unit, data_points, ..
-        } => {
+        }) => {
             assert_eq!(unit.as_deref(), Some("ratio"));
             assert_eq!(data_points.len(), 2);
             assert_eq!(data_points[0].value.value(), 0.92);
@@ -107,15 +109,17 @@ fn parses_real_world_evaluator_output() {
         .metrics
         .iter()
         .find(|m| match m {
-            Metric::Histogram { name, .. } => name.as_ref() == "llm.eval.latency",
+            Metric::Sample(SampleMetric::Histogram { name, .. }) => {
+                name.as_ref() == "llm.eval.latency"
+            }
             _ => false,
         })
         .expect("latency metric not found");
 
     match latency_metric {
-        Metric::Histogram {
+        Metric::Sample(SampleMetric::Histogram {
             unit, data_points, ..
-        } => {
+        }) => {
             assert_eq!(unit.as_deref(), Some("ms"));
             assert_eq!(data_points.len(), 1);
             let hist = &data_points[0].value;
@@ -141,9 +145,9 @@ fn handles_multiple_json_lines() {
     assert_eq!(result2.metrics.len(), 1);
 
     match &result1.metrics[0] {
-        Metric::Gauge {
+        Metric::Sample(SampleMetric::Gauge {
             name, data_points, ..
-        } => {
+        }) => {
             assert_eq!(name.as_ref(), "metric1");
             assert_eq!(data_points[0].value.value(), 1.0);
         }
@@ -151,9 +155,9 @@ fn handles_multiple_json_lines() {
     }
 
     match &result2.metrics[0] {
-        Metric::Gauge {
+        Metric::Sample(SampleMetric::Gauge {
             name, data_points, ..
-        } => {
+        }) => {
             assert_eq!(name.as_ref(), "metric2");
             assert_eq!(data_points[0].value.value(), 2.0);
         }
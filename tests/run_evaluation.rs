@@ -0,0 +1,145 @@
+use preval::evaluator::source::FileReplay;
+use preval::state::types::{EvaluatorCommand, SampleStatus};
+use preval::{run_evaluation, RunConfig};
+use std::time::Duration;
+
+/// OTLP metrics line reporting `accuracy` of one sample
+fn accuracy_line(sample_id: &str, accuracy: f64) -> String {
+    format!(
+        r#"{{"resourceMetrics": [{{"resource": {{"attributes": []}}, "scopeMetrics": [{{"metrics": [{{"name": "accuracy", "gauge": {{"dataPoints": [{{"timeUnixNano": "1234567890000000000", "asDouble": {}, "attributes": [{{"key": "sample.id", "value": {{"stringValue": "{}"}}}}]}}]}}}}]}}]}}]}}"#,
+        accuracy, sample_id
+    )
+}
+
+#[tokio::test]
+async fn runs_an_evaluation_without_a_terminal() {
+    let path = std::env::temp_dir().join(format!("preval-runner-{}.jsonl", std::process::id()));
+    let handshake = r#"{"type": "handshake", "mode": "test_suite", "version": "1.0", "evaluator": {"name": "replayed-eval"}, "metrics_schema": []}"#;
+    std::fs::write(
+        &path,
+        [
+            handshake.to_string(),
+            accuracy_line("q-1", 0.9),
+            accuracy_line("q-2", 0.3),
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+
+    let config =
+        RunConfig::from_source(Box::new(FileReplay::new(path.clone()))).threshold_rules(vec![
+            "accuracy >= 0.5".parse().unwrap(),
+            "accuracy.mean >= 0.8".parse().unwrap(),
+        ]);
+    let result = run_evaluation(config).await.unwrap();
+    std::fs::remove_file(&path).ok();
+
+    // The mean of 0.6 breaks the aggregate rule
+    assert!(!result.success);
+    assert_eq!(result.report.status, "failed");
+    assert_eq!(result.report.samples_completed, 2);
+    assert_eq!(result.report.samples_failed, 1);
+    assert_eq!(result.report.failed_samples[0].id, "q-2");
+    let accuracy = &result.report.metrics[0];
+    assert_eq!((accuracy.name.as_str(), accuracy.count), ("accuracy", 2));
+
+    let ids: Vec<&str> = result
+        .samples
+        .iter()
+        .map(|sample| sample.sample_id.as_str())
+        .collect();
+    assert_eq!(ids, ["q-1", "q-2"]);
+    assert!(matches!(result.samples[1].status, SampleStatus::Failed(_)));
+}
+
+/// Run the mock evaluator with `args`
+fn mock_evaluator(args: &str) -> RunConfig {
+    let command = format!("{} {}", env!("CARGO_BIN_EXE_mock_evaluator"), args);
+    RunConfig::new(EvaluatorCommand::try_new(command).unwrap())
+}
+
+#[tokio::test]
+async fn the_mock_evaluator_fails_the_same_samples_for_a_seed() {
+    let failed_samples = || async {
+        let config = mock_evaluator("--samples 20 --delay-ms 0 --fail-rate 0.3 --seed 7");
+        let result = run_evaluation(config).await.unwrap();
+        assert_eq!(result.report.samples_completed, 20);
+        result
+            .report
+            .failed_samples
+            .into_iter()
+            .map(|sample| sample.id)
+            .collect::<Vec<_>>()
+    };
+
+    let failed = failed_samples().await;
+    assert!(!failed.is_empty() && failed.len() < 20);
+    assert_eq!(failed_samples().await, failed);
+}
+
+#[tokio::test]
+async fn broken_evaluators_end_the_run_as_expected() {
+    // Scenario, whether the run succeeds, start of the failure and samples completed
+    let cases = [
+        ("stderr-noise", true, None, 6),
+        ("malformed-json", true, None, 5),
+        (
+            "no-handshake",
+            false,
+            Some("Evaluator exited before sending handshake"),
+            0,
+        ),
+        ("late-handshake", false, Some("Handshake timeout"), 0),
+        ("crash-midway", false, Some("Evaluator exited with code"), 2),
+        ("hang", false, Some("timeout after"), 2),
+    ];
+
+    for (scenario, success, failure, completed) in cases {
+        let config = mock_evaluator(&format!("--samples 6 --delay-ms 0 --scenario {}", scenario))
+            .handshake_timeout(Duration::from_millis(500))
+            .timeout(Duration::from_secs(3));
+        let result = run_evaluation(config).await.unwrap();
+
+        assert_eq!(result.success, success, "{}", scenario);
+        match failure {
+            Some(failure) => {
+                let reason = result.report.failure.unwrap_or_default();
+                assert!(reason.starts_with(failure), "{}: {}", scenario, reason);
+            }
+            None => assert_eq!(result.report.failure, None, "{}", scenario),
+        }
+        assert_eq!(result.report.samples_completed, completed, "{}", scenario);
+    }
+}
+
+#[tokio::test]
+async fn the_mock_evaluator_produces_every_mode() {
+    let online = mock_evaluator("--mode online-collection --samples 6 --delay-ms 0");
+    let result = run_evaluation(online).await.unwrap();
+    assert!(result.success);
+    assert_eq!(result.report.total_samples, None);
+    assert_eq!(result.report.samples_completed, 6);
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_mock_evaluator"))
+        .args([
+            "--mode",
+            "online-collection",
+            "--samples",
+            "6",
+            "--delay-ms",
+            "0",
+        ])
+        .output()
+        .unwrap();
+    let output = String::from_utf8(output.stdout).unwrap();
+    let tagged = output
+        .lines()
+        .filter(|line| line.contains("\"session.id\""));
+    assert_eq!(tagged.count(), 6);
+
+    // A continuous run only ends when stopped
+    let continuous = mock_evaluator("--mode continuous --samples 2 --delay-ms 10")
+        .timeout(Duration::from_secs(1));
+    let result = run_evaluation(continuous).await.unwrap();
+    assert!(result.report.failure.unwrap().starts_with("timeout after"));
+    assert!(result.report.samples_completed > 2);
+}